@@ -0,0 +1,108 @@
+//! Dumps the firmware volumes of a flash image as JSON.
+//!
+//! Usage: `cargo run --example fvdump --features brotli,crc32,serde -- [--tree] [--sections] [--no-extract] <image>...`
+//!
+//! For each image, the firmware volumes found in it are listed with their files. Encapsulation sections are expanded
+//! with the Brotli and CRC32 extractors, and the firmware volumes found in FIRMWARE_VOLUME_IMAGE files are listed under
+//! the volume that contains them (with a base address of 0, as they are not mapped in the image). `--no-extract` skips
+//! the expansion, `--sections` adds the sections of the files with CRC32 hashes of their data, and `--tree` prints an
+//! indented tree of each volume instead of JSON.
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+use mu_pi::{
+    fw_fs::{
+        extractors::{BrotliSectionExtractor, CompositeSectionExtractor, Crc32SectionExtractor},
+        FfsFileType, FfsSectionType, FirmwareVolume, SectionExtractor,
+    },
+    report::{BootReport, FirmwareVolumeEntry},
+};
+use r_efi::efi;
+use serde::Serialize;
+use std::{env, error::Error, fs, process};
+
+#[derive(Serialize)]
+struct ImageDump {
+    path: String,
+    firmware_volumes: Vec<VolumeDump>,
+}
+
+#[derive(Serialize)]
+struct VolumeDump {
+    #[serde(flatten)]
+    entry: FirmwareVolumeEntry,
+    nested: Vec<VolumeDump>,
+}
+
+// Lists the files of the volume, and the volumes nested in its FIRMWARE_VOLUME_IMAGE files.
+fn dump_volume(
+    base_address: u64,
+    fv: &FirmwareVolume,
+    extractor: &dyn SectionExtractor,
+    sections: bool,
+) -> Result<VolumeDump, efi::Status> {
+    let mut report = BootReport::new();
+    if sections {
+        // the nested volumes are listed in the sections that hold them.
+        report.add_firmware_volume_with_sections(base_address, fv, extractor, true)?;
+        return Ok(VolumeDump { entry: report.firmware_volumes.remove(0), nested: Vec::new() });
+    }
+    report.add_firmware_volume(base_address, fv)?;
+    let mut nested = Vec::new();
+    for file in fv.files_of_type(FfsFileType::FirmwareVolumeImage) {
+        for section in file?.sections_of_type_with_extractor(FfsSectionType::FirmwareVolumeImage, extractor) {
+            nested.push(dump_volume(0, &section?.firmware_volume()?, extractor, false)?);
+        }
+    }
+    Ok(VolumeDump { entry: report.firmware_volumes.remove(0), nested })
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let mut tree = false;
+    let mut sections = false;
+    let mut extract = true;
+    let mut paths = Vec::new();
+    for arg in env::args().skip(1) {
+        match arg.as_str() {
+            "--tree" => tree = true,
+            "--sections" => sections = true,
+            "--no-extract" => extract = false,
+            _ if arg.starts_with("--") => {
+                eprintln!("usage: fvdump [--tree] [--sections] [--no-extract] <image>...");
+                process::exit(2);
+            }
+            _ => paths.push(arg),
+        }
+    }
+
+    let brotli = BrotliSectionExtractor {};
+    let crc32 = Crc32SectionExtractor {};
+    let extractors: Vec<&dyn SectionExtractor> = if extract { vec![&brotli, &crc32] } else { Vec::new() };
+    let extractor = CompositeSectionExtractor::new(&extractors);
+
+    let mut dumps = Vec::new();
+    for path in paths {
+        let image = fs::read(&path)?;
+        let mut firmware_volumes = Vec::new();
+        for (offset, fv) in FirmwareVolume::find_all(&image) {
+            if tree {
+                println!("{path} at {offset:#x}:");
+                print!("{}", fv.render_tree(&extractor).map_err(|status| format!("{path}: {status:?}"))?);
+                continue;
+            }
+            let dump = dump_volume(offset as u64, &fv, &extractor, sections)
+                .map_err(|status| format!("{path}: {status:?}"))?;
+            firmware_volumes.push(dump);
+        }
+        dumps.push(ImageDump { path, firmware_volumes });
+    }
+    if !tree {
+        println!("{}", serde_json::to_string_pretty(&dumps)?);
+    }
+    Ok(())
+}
@@ -0,0 +1,77 @@
+//! Dumps a HOB list as JSON.
+//!
+//! Usage: `cargo run --example hobdump --features serde -- [--capture] <file>`
+//!
+//! The file holds a raw HOB list, ending with an END_OF_HOB_LIST HOB, or with `--capture`, a capture stream (see
+//! [`mu_pi::capture`]) whose HOB lists, status codes and handle database snapshots are all dumped. The payloads of
+//! memory type information GUID HOBs are decoded.
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+use mu_pi::{
+    hob::{
+        decoders::{DecodedValue, GuidHobDecoders},
+        HobList,
+    },
+    report::BootReport,
+};
+use r_efi::efi;
+use std::{env, error::Error, fs, process};
+
+// {4C19049F-4137-4DD3-9C10-8B97A83FFDFA}
+const MEMORY_TYPE_INFORMATION_GUID: efi::Guid =
+    efi::Guid::from_fields(0x4c19049f, 0x4137, 0x4dd3, 0x9c, 0x10, &[0x8b, 0x97, 0xa8, 0x3f, 0xfd, 0xfa]);
+
+// The payload is an array of EFI_MEMORY_TYPE_INFORMATION: a u32 memory type followed by a u32 number of pages.
+fn decode_memory_type_information(payload: &[u8]) -> Option<DecodedValue> {
+    if payload.len() % 8 != 0 {
+        return None;
+    }
+    let entries = payload.chunks_exact(8).map(|entry| {
+        let field = |offset: usize| u32::from_le_bytes(entry[offset..offset + 4].try_into().unwrap());
+        DecodedValue::Map(vec![
+            ("type".into(), DecodedValue::Integer(field(0).into())),
+            ("number_of_pages".into(), DecodedValue::Integer(field(4).into())),
+        ])
+    });
+    Some(DecodedValue::List(entries.collect()))
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let mut capture = false;
+    let mut path = None;
+    for arg in env::args().skip(1) {
+        match arg.as_str() {
+            "--capture" => capture = true,
+            _ if arg.starts_with("--") || path.is_some() => {
+                eprintln!("usage: hobdump [--capture] <file>");
+                process::exit(2);
+            }
+            _ => path = Some(arg),
+        }
+    }
+    let Some(path) = path else {
+        eprintln!("usage: hobdump [--capture] <file>");
+        process::exit(2);
+    };
+
+    let mut decoders = GuidHobDecoders::new();
+    decoders.register(MEMORY_TYPE_INFORMATION_GUID, decode_memory_type_information);
+
+    let data = fs::read(&path)?;
+    let mut report = BootReport::new();
+    if capture {
+        report.add_capture_with_decoders(&data, &decoders).map_err(|err| format!("{path}: {err:?}"))?;
+    } else {
+        let mut hob_list = HobList::new();
+        hob_list.try_discover_hobs(&data).map_err(|err| format!("{path}: {err:?}"))?;
+        report.add_hob_list_with_decoders(&hob_list, &decoders);
+    }
+    println!("{}", serde_json::to_string_pretty(&report)?);
+    Ok(())
+}
@@ -14,6 +14,7 @@
 /// Returns the greatest `x` with alignment `align` so that `x <= addr`.
 ///
 /// Panics if the alignment is not a power of two.
+#[cfg(feature = "alloc")]
 #[inline]
 pub const fn align_down(addr: u64, align: u64) -> u64 {
     assert!(align.is_power_of_two(), "`align` must be a power of two");
@@ -0,0 +1,518 @@
+//! Capsules
+//!
+//! Definitions for the EFI_CAPSULE_HEADER and the Firmware Management Protocol (FMP) capsule format, and a parser that
+//! locates the payloads and firmware volumes embedded in a capsule buffer.
+//!
+//! An FMP capsule is a capsule whose GUID is [`EFI_FIRMWARE_MANAGEMENT_CAPSULE_ID_GUID`]. Its body starts with an
+//! [`FmpCapsuleHeader`] followed by a list of item offsets: first the embedded drivers, then the payloads, each of
+//! which starts with an [`FmpCapsuleImageHeader`].
+//!
+//! See <https://uefi.org/specs/UEFI/2.10/23_Firmware_Update_and_Reporting.html#delivering-capsules-containing-updates-to-firmware-management-protocol>.
+//!
+//! ## Example
+//! ```
+//! use mu_pi::capsule::Capsule;
+//!
+//! fn process(buffer: &[u8]) -> Result<(), r_efi::efi::Status> {
+//!   let capsule = Capsule::new(buffer)?;
+//!   if let Some(fmp) = capsule.fmp()? {
+//!     for payload in fmp.payloads() {
+//!       let payload = payload?;
+//!       println!("update for image type {:?} ({:#x} bytes)", payload.update_image_type_id(), payload.image().len());
+//!     }
+//!   }
+//!   for (offset, fv) in capsule.firmware_volumes() {
+//!     println!("FV at offset {:#x} of the capsule body ({:#x} bytes)", offset, fv.size());
+//!   }
+//!   Ok(())
+//! }
+//! ```
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+use core::{fmt, mem, ptr};
+
+use r_efi::efi;
+
+#[cfg(feature = "alloc")]
+use crate::fw_fs::FirmwareVolume;
+
+pub use r_efi::efi::{
+    CapsuleHeader, CAPSULE_FLAGS_INITIATE_RESET, CAPSULE_FLAGS_PERSIST_ACROSS_RESET,
+    CAPSULE_FLAGS_POPULATE_SYSTEM_TABLE,
+};
+
+/// GUID of FMP capsules.
+///
+/// # Documentation
+/// UEFI Specification, Release 2.10, Section 23.3.1
+pub const EFI_FIRMWARE_MANAGEMENT_CAPSULE_ID_GUID: efi::Guid =
+    efi::Guid::from_fields(0x6dcbd5ed, 0xe82d, 0x4c44, 0xbd, 0xa1, &[0x71, 0x94, 0x19, 0x9a, 0xd9, 0x2a]);
+
+/// Current version of the [`FmpCapsuleHeader`].
+pub const EFI_FIRMWARE_MANAGEMENT_CAPSULE_HEADER_INIT_VERSION: u32 = 0x00000001;
+
+/// Current version of the [`FmpCapsuleImageHeader`].
+pub const EFI_FIRMWARE_MANAGEMENT_CAPSULE_IMAGE_HEADER_INIT_VERSION: u32 = 0x00000003;
+
+/// The header of the body of an FMP capsule (EFI_FIRMWARE_MANAGEMENT_CAPSULE_HEADER).
+///
+/// It is followed by `embedded_driver_count + payload_item_count` 64-bit item offsets, relative to the start of this
+/// header.
+///
+/// # Documentation
+/// UEFI Specification, Release 2.10, Section 23.3.3
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct FmpCapsuleHeader {
+    pub version: u32,
+    pub embedded_driver_count: u16,
+    pub payload_item_count: u16,
+    pub item_offset_list: [u64; 0],
+}
+
+/// The header of an FMP capsule payload (EFI_FIRMWARE_MANAGEMENT_CAPSULE_IMAGE_HEADER).
+///
+/// It is followed by `update_image_size` bytes of image, then `update_vendor_code_size` bytes of vendor code. Version
+/// 1 headers end before `update_hardware_instance`, and version 2 headers before `image_capsule_support`.
+///
+/// # Documentation
+/// UEFI Specification, Release 2.10, Section 23.3.3
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct FmpCapsuleImageHeader {
+    pub version: u32,
+    pub update_image_type_id: efi::Guid,
+    pub update_image_index: u8,
+    pub reserved_bytes: [u8; 3],
+    pub update_image_size: u32,
+    pub update_vendor_code_size: u32,
+    pub update_hardware_instance: u64,
+    pub image_capsule_support: u64,
+}
+
+// Sizes of the versions of the FMP capsule image header.
+const FMP_CAPSULE_IMAGE_HEADER_V1_SIZE: usize = 32;
+const FMP_CAPSULE_IMAGE_HEADER_V2_SIZE: usize = 40;
+
+/// Errors detected while parsing a capsule.
+///
+/// They convert to `INVALID_PARAMETER` for callers that return an [`efi::Status`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CapsuleError {
+    /// The buffer is too small to contain the header or item offset list being parsed.
+    BufferTooSmall { needed: usize, available: usize },
+    /// The capsule header size is smaller than the capsule header or larger than the capsule image.
+    BadHeaderSize { header_size: u32 },
+    /// The capsule image size is larger than the buffer.
+    BadImageSize { capsule_image_size: u32 },
+    /// The FMP capsule header has a version this implementation cannot parse.
+    UnsupportedFmpVersion { version: u32 },
+    /// An FMP item offset is before the previous item or past the end of the body.
+    BadItemOffset { index: usize, offset: u64 },
+    /// The FMP payload image header has a version this implementation cannot parse.
+    UnsupportedPayloadVersion { version: u32 },
+    /// The update image and vendor code of an FMP payload do not fit in the payload.
+    BadPayloadSize { update_image_size: u32, update_vendor_code_size: u32 },
+}
+
+impl fmt::Display for CapsuleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CapsuleError::BufferTooSmall { needed, available } => {
+                write!(f, "buffer of {available:#x} bytes is too small for {needed:#x} bytes of headers")
+            }
+            CapsuleError::BadHeaderSize { header_size } => write!(f, "capsule has bad header size {header_size:#x}"),
+            CapsuleError::BadImageSize { capsule_image_size } => {
+                write!(f, "capsule has bad image size {capsule_image_size:#x}")
+            }
+            CapsuleError::UnsupportedFmpVersion { version } => {
+                write!(f, "FMP capsule header has unsupported version {version}")
+            }
+            CapsuleError::BadItemOffset { index, offset } => {
+                write!(f, "FMP capsule item {index} has bad offset {offset:#x}")
+            }
+            CapsuleError::UnsupportedPayloadVersion { version } => {
+                write!(f, "FMP payload header has unsupported version {version}")
+            }
+            CapsuleError::BadPayloadSize { update_image_size, update_vendor_code_size } => write!(
+                f,
+                "FMP payload image of {update_image_size:#x} bytes and vendor code of {update_vendor_code_size:#x} \
+                 bytes do not fit in the payload"
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for CapsuleError {}
+
+impl From<CapsuleError> for efi::Status {
+    fn from(_error: CapsuleError) -> Self {
+        efi::Status::INVALID_PARAMETER
+    }
+}
+
+/// A capsule, validated in place.
+#[derive(Clone, Copy)]
+pub struct Capsule<'a> {
+    header: CapsuleHeader,
+    body: &'a [u8],
+}
+
+impl<'a> Capsule<'a> {
+    /// Parses the capsule at the start of `buffer`.
+    ///
+    /// Returns an error if the buffer does not hold the capsule header, or the header size or capsule image size in it
+    /// are not consistent with each other and with the buffer.
+    pub fn new(buffer: &'a [u8]) -> Result<Self, CapsuleError> {
+        if buffer.len() < mem::size_of::<CapsuleHeader>() {
+            Err(CapsuleError::BufferTooSmall { needed: mem::size_of::<CapsuleHeader>(), available: buffer.len() })?;
+        }
+        // Safety: the buffer holds the header, which is read unaligned.
+        let header = unsafe { (buffer.as_ptr() as *const CapsuleHeader).read_unaligned() };
+        let header_size = header.header_size as usize;
+        let image_size = header.capsule_image_size as usize;
+        if image_size > buffer.len() {
+            Err(CapsuleError::BadImageSize { capsule_image_size: header.capsule_image_size })?;
+        }
+        if header_size < mem::size_of::<CapsuleHeader>() || header_size > image_size {
+            Err(CapsuleError::BadHeaderSize { header_size: header.header_size })?;
+        }
+        Ok(Self { header, body: &buffer[header_size..image_size] })
+    }
+
+    /// Returns the capsule header.
+    pub fn header(&self) -> &CapsuleHeader {
+        &self.header
+    }
+
+    /// Returns the capsule GUID, which identifies the format of the body.
+    pub fn capsule_guid(&self) -> efi::Guid {
+        self.header.capsule_guid
+    }
+
+    /// Returns the `CAPSULE_FLAGS_*` of the capsule.
+    pub fn flags(&self) -> u32 {
+        self.header.flags
+    }
+
+    /// Returns the body of the capsule, which follows the capsule header.
+    pub fn body(&self) -> &'a [u8] {
+        self.body
+    }
+
+    /// Returns the FMP capsule held in the body, or `None` if this is not an FMP capsule.
+    pub fn fmp(&self) -> Result<Option<FmpCapsule<'a>>, CapsuleError> {
+        if self.header.capsule_guid != EFI_FIRMWARE_MANAGEMENT_CAPSULE_ID_GUID {
+            return Ok(None);
+        }
+        FmpCapsule::new(self.body).map(Some)
+    }
+
+    /// Returns an iterator of the firmware volumes embedded in the body of the capsule, with their offsets in the body.
+    ///
+    /// The update images of the payloads of an FMP capsule, or else the whole body, are scanned with
+    /// [`FirmwareVolume::find_all`]. Payloads that cannot be parsed are skipped.
+    #[cfg(feature = "alloc")]
+    pub fn firmware_volumes(&self) -> impl Iterator<Item = (usize, FirmwareVolume<'a>)> {
+        let body = self.body;
+        let fmp = self.fmp().ok().flatten();
+        let whole_body = fmp.is_none().then_some(body);
+        fmp.into_iter()
+            .flat_map(|fmp| fmp.payloads().filter_map(Result::ok).map(|payload| payload.image()))
+            .chain(whole_body)
+            .flat_map(move |region| {
+                // payload images are slices of the body, so this is their offset in it.
+                let base = region.as_ptr() as usize - body.as_ptr() as usize;
+                FirmwareVolume::find_all(region).map(move |(offset, fv)| (base + offset, fv))
+            })
+    }
+}
+
+impl fmt::Debug for Capsule<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Capsule")
+            .field("header", &self.header)
+            .field("body_len", &self.body.len())
+            .finish_non_exhaustive()
+    }
+}
+
+/// The body of an FMP capsule, validated in place.
+#[derive(Clone, Copy)]
+pub struct FmpCapsule<'a> {
+    header: FmpCapsuleHeader,
+    data: &'a [u8],
+}
+
+impl<'a> FmpCapsule<'a> {
+    /// Parses the FMP capsule body in `data`.
+    ///
+    /// Returns an error if the header has an unknown version, or the item offset list does not fit in the body or is
+    /// not in ascending order within it.
+    pub fn new(data: &'a [u8]) -> Result<Self, CapsuleError> {
+        if data.len() < mem::size_of::<FmpCapsuleHeader>() {
+            Err(CapsuleError::BufferTooSmall { needed: mem::size_of::<FmpCapsuleHeader>(), available: data.len() })?;
+        }
+        // Safety: the data holds the header, which is read unaligned.
+        let header = unsafe { (data.as_ptr() as *const FmpCapsuleHeader).read_unaligned() };
+        if header.version != EFI_FIRMWARE_MANAGEMENT_CAPSULE_HEADER_INIT_VERSION {
+            Err(CapsuleError::UnsupportedFmpVersion { version: header.version })?;
+        }
+        let capsule = Self { header, data };
+        let item_count = header.embedded_driver_count as usize + header.payload_item_count as usize;
+        let items_start = mem::size_of::<FmpCapsuleHeader>() + item_count * mem::size_of::<u64>();
+        if items_start > data.len() {
+            Err(CapsuleError::BufferTooSmall { needed: items_start, available: data.len() })?;
+        }
+        let mut previous = items_start as u64;
+        for index in 0..item_count {
+            let offset = capsule.item_offset(index);
+            if offset < previous || offset > data.len() as u64 {
+                Err(CapsuleError::BadItemOffset { index, offset })?;
+            }
+            previous = offset;
+        }
+        Ok(capsule)
+    }
+
+    /// Returns the FMP capsule header.
+    pub fn header(&self) -> &FmpCapsuleHeader {
+        &self.header
+    }
+
+    /// Returns an iterator of the embedded drivers of the capsule.
+    pub fn embedded_drivers(&self) -> impl Iterator<Item = &'a [u8]> {
+        let capsule = *self;
+        (0..self.header.embedded_driver_count as usize).map(move |index| capsule.item(index))
+    }
+
+    /// Returns an iterator of the payloads of the capsule.
+    ///
+    /// A payload whose image header has an unknown version or whose image and vendor code do not fit in the payload is
+    /// returned as an error.
+    pub fn payloads(&self) -> impl Iterator<Item = Result<FmpPayload<'a>, CapsuleError>> {
+        let capsule = *self;
+        let first = self.header.embedded_driver_count as usize;
+        (first..first + self.header.payload_item_count as usize).map(move |index| FmpPayload::new(capsule.item(index)))
+    }
+
+    // Returns the offset of the given item. The offset list was validated to fit in the data.
+    fn item_offset(&self, index: usize) -> u64 {
+        let offset = mem::size_of::<FmpCapsuleHeader>() + index * mem::size_of::<u64>();
+        u64::from_le_bytes(self.data[offset..offset + mem::size_of::<u64>()].try_into().unwrap())
+    }
+
+    // Returns the given item, which extends to the start of the next item or to the end of the body.
+    fn item(&self, index: usize) -> &'a [u8] {
+        let item_count = self.header.embedded_driver_count as usize + self.header.payload_item_count as usize;
+        let end = if index + 1 < item_count { self.item_offset(index + 1) as usize } else { self.data.len() };
+        &self.data[self.item_offset(index) as usize..end]
+    }
+}
+
+impl fmt::Debug for FmpCapsule<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FmpCapsule")
+            .field("header", &self.header)
+            .field("data_len", &self.data.len())
+            .finish_non_exhaustive()
+    }
+}
+
+/// A payload of an FMP capsule: an image header, the update image, and the vendor code.
+#[derive(Clone, Copy)]
+pub struct FmpPayload<'a> {
+    header: FmpCapsuleImageHeader,
+    image: &'a [u8],
+    vendor_code: &'a [u8],
+}
+
+impl<'a> FmpPayload<'a> {
+    /// Parses the FMP capsule payload in `data`.
+    ///
+    /// The fields that the version of the image header does not have are zero in [`FmpPayload::header`].
+    pub fn new(data: &'a [u8]) -> Result<Self, CapsuleError> {
+        if data.len() < mem::size_of::<u32>() {
+            Err(CapsuleError::BufferTooSmall { needed: mem::size_of::<u32>(), available: data.len() })?;
+        }
+        let header_size = match u32::from_le_bytes(data[..4].try_into().unwrap()) {
+            1 => FMP_CAPSULE_IMAGE_HEADER_V1_SIZE,
+            2 => FMP_CAPSULE_IMAGE_HEADER_V2_SIZE,
+            EFI_FIRMWARE_MANAGEMENT_CAPSULE_IMAGE_HEADER_INIT_VERSION => mem::size_of::<FmpCapsuleImageHeader>(),
+            version => Err(CapsuleError::UnsupportedPayloadVersion { version })?,
+        };
+        if data.len() < header_size {
+            Err(CapsuleError::BufferTooSmall { needed: header_size, available: data.len() })?;
+        }
+        //Safety: data is large enough to contain the header of its version, which is copied into a zeroed current
+        //header with the same field offsets, leaving the fields of later versions zeroed.
+        let header = unsafe {
+            let mut header = mem::MaybeUninit::<FmpCapsuleImageHeader>::zeroed();
+            ptr::copy_nonoverlapping(data.as_ptr(), header.as_mut_ptr() as *mut u8, header_size);
+            header.assume_init()
+        };
+
+        let bad_payload_size = CapsuleError::BadPayloadSize {
+            update_image_size: header.update_image_size,
+            update_vendor_code_size: header.update_vendor_code_size,
+        };
+        let image_end = header_size.checked_add(header.update_image_size as usize).ok_or(bad_payload_size)?;
+        let vendor_code_end = image_end.checked_add(header.update_vendor_code_size as usize).ok_or(bad_payload_size)?;
+        if vendor_code_end > data.len() {
+            Err(bad_payload_size)?;
+        }
+        Ok(Self { header, image: &data[header_size..image_end], vendor_code: &data[image_end..vendor_code_end] })
+    }
+
+    /// Returns the image header of the payload.
+    pub fn header(&self) -> &FmpCapsuleImageHeader {
+        &self.header
+    }
+
+    /// Returns the GUID of the type of firmware image the payload updates.
+    pub fn update_image_type_id(&self) -> efi::Guid {
+        self.header.update_image_type_id
+    }
+
+    /// Returns the update image.
+    pub fn image(&self) -> &'a [u8] {
+        self.image
+    }
+
+    /// Returns the vendor code that follows the update image.
+    pub fn vendor_code(&self) -> &'a [u8] {
+        self.vendor_code
+    }
+}
+
+impl fmt::Debug for FmpPayload<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FmpPayload")
+            .field("header", &self.header)
+            .field("image_len", &self.image.len())
+            .field("vendor_code_len", &self.vendor_code.len())
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+    use std::{env, fs, path::Path, vec::Vec};
+
+    use super::*;
+
+    const IMAGE_TYPE_ID: efi::Guid =
+        efi::Guid::from_fields(0x0c5ca7e0, 0x1234, 0x5678, 0x9a, 0xbc, &[0xde, 0xf0, 0x01, 0x02, 0x03, 0x04]);
+
+    fn capsule_header(capsule_guid: efi::Guid, image_size: usize) -> Vec<u8> {
+        let mut bytes = capsule_guid.as_bytes().to_vec();
+        bytes.extend_from_slice(&(mem::size_of::<CapsuleHeader>() as u32).to_le_bytes());
+        bytes.extend_from_slice(&CAPSULE_FLAGS_PERSIST_ACROSS_RESET.to_le_bytes());
+        bytes.extend_from_slice(&((mem::size_of::<CapsuleHeader>() + image_size) as u32).to_le_bytes());
+        bytes
+    }
+
+    fn payload(version: u32, image: &[u8], vendor_code: &[u8]) -> Vec<u8> {
+        let mut bytes = version.to_le_bytes().to_vec();
+        bytes.extend_from_slice(IMAGE_TYPE_ID.as_bytes());
+        bytes.extend_from_slice(&[1, 0, 0, 0]);
+        bytes.extend_from_slice(&(image.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(&(vendor_code.len() as u32).to_le_bytes());
+        if version >= 2 {
+            bytes.extend_from_slice(&7u64.to_le_bytes());
+        }
+        if version >= 3 {
+            bytes.extend_from_slice(&0u64.to_le_bytes());
+        }
+        bytes.extend_from_slice(image);
+        bytes.extend_from_slice(vendor_code);
+        bytes
+    }
+
+    #[test]
+    fn fmp_capsule_should_expose_drivers_and_payloads() {
+        let root = Path::new(&env::var("CARGO_MANIFEST_DIR").unwrap()).join("test_resources");
+        let fv = fs::read(root.join("FVMAIN_COMPACT.Fv")).unwrap();
+
+        let driver = [0x4d, 0x5a, 0, 0];
+        let items = [driver.to_vec(), payload(3, &fv, b"vendor"), payload(1, b"legacy", &[])];
+        let mut body = EFI_FIRMWARE_MANAGEMENT_CAPSULE_HEADER_INIT_VERSION.to_le_bytes().to_vec();
+        body.extend_from_slice(&1u16.to_le_bytes());
+        body.extend_from_slice(&2u16.to_le_bytes());
+        let mut offset = body.len() + items.len() * mem::size_of::<u64>();
+        for item in &items {
+            body.extend_from_slice(&(offset as u64).to_le_bytes());
+            offset += item.len();
+        }
+        items.iter().for_each(|item| body.extend_from_slice(item));
+
+        let mut buffer = capsule_header(EFI_FIRMWARE_MANAGEMENT_CAPSULE_ID_GUID, body.len());
+        buffer.extend_from_slice(&body);
+        let capsule = Capsule::new(&buffer).unwrap();
+        assert_eq!(capsule.flags(), CAPSULE_FLAGS_PERSIST_ACROSS_RESET);
+        assert_eq!(capsule.body(), &body[..]);
+
+        let fmp = capsule.fmp().unwrap().unwrap();
+        assert_eq!(fmp.embedded_drivers().collect::<Vec<_>>(), [&driver[..]]);
+        let payloads = fmp.payloads().collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(payloads.len(), 2);
+        assert_eq!(payloads[0].update_image_type_id(), IMAGE_TYPE_ID);
+        assert_eq!(payloads[0].image(), &fv[..]);
+        assert_eq!(payloads[0].vendor_code(), b"vendor");
+        assert_eq!(payloads[1].header().update_hardware_instance, 0);
+        assert_eq!(payloads[1].image(), b"legacy");
+
+        let volumes = capsule.firmware_volumes().collect::<Vec<_>>();
+        assert_eq!(volumes.len(), 1);
+        assert_eq!(volumes[0].1.data(), &fv[..]);
+        assert_eq!(&body[volumes[0].0..volumes[0].0 + fv.len()], &fv[..]);
+    }
+
+    #[test]
+    fn malformed_capsules_should_be_rejected() {
+        let mut buffer = capsule_header(IMAGE_TYPE_ID, 4);
+        buffer.extend_from_slice(&[0; 4]);
+        let capsule = Capsule::new(&buffer).unwrap();
+        assert!(capsule.fmp().unwrap().is_none());
+        assert_eq!(
+            Capsule::new(&buffer[..buffer.len() - 1]).unwrap_err(),
+            CapsuleError::BadImageSize { capsule_image_size: buffer.len() as u32 }
+        );
+        assert_eq!(efi::Status::from(Capsule::new(&buffer[..4]).unwrap_err()), efi::Status::INVALID_PARAMETER);
+
+        // an FMP body whose item offset points past its end.
+        let mut body = EFI_FIRMWARE_MANAGEMENT_CAPSULE_HEADER_INIT_VERSION.to_le_bytes().to_vec();
+        body.extend_from_slice(&0u16.to_le_bytes());
+        body.extend_from_slice(&1u16.to_le_bytes());
+        body.extend_from_slice(&0x100u64.to_le_bytes());
+        assert_eq!(FmpCapsule::new(&body).unwrap_err(), CapsuleError::BadItemOffset { index: 0, offset: 0x100 });
+
+        // a payload whose image does not fit.
+        let mut item = payload(2, b"image", &[]);
+        item.pop();
+        assert_eq!(
+            FmpPayload::new(&item).unwrap_err(),
+            CapsuleError::BadPayloadSize { update_image_size: 5, update_vendor_code_size: 0 }
+        );
+        assert_eq!(
+            FmpPayload::new(&payload(4, &[], &[])).unwrap_err(),
+            CapsuleError::UnsupportedPayloadVersion { version: 4 }
+        );
+
+        // a payload whose image and vendor code sizes are at their maximum.
+        let mut item = payload(3, &[], &[]);
+        item[24..32].copy_from_slice(&[0xff; 8]);
+        assert_eq!(
+            FmpPayload::new(&item).unwrap_err(),
+            CapsuleError::BadPayloadSize { update_image_size: u32::MAX, update_vendor_code_size: u32::MAX }
+        );
+    }
+}
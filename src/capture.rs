@@ -0,0 +1,601 @@
+//! Binary Capture Format
+//!
+//! A compact framed format for streaming HOB lists, status code events and handle database snapshots out of a device
+//! over a byte-oriented link such as a UART, and for decoding the captured stream on the host.
+//!
+//! Each record is sent as a frame: a 16-byte [`FrameHeader`] (magic, version, record type, payload length and payload
+//! CRC32) followed by the payload. The encoders ([`encode_hob_list`], [`encode_status_code`],
+//! [`encode_handle_database`]) do not allocate and write the frame through a callback, so they can be used from any
+//! boot phase. The decoder ([`frames`]) scans a capture for frames, skipping any other output (e.g. debug messages)
+//! interleaved with them, and verifies each frame.
+//!
+//! ## Example
+//! ```
+//! use mu_pi::{capture, status_code};
+//!
+//! // Device side: write frames to the serial port (here, a buffer).
+//! let mut uart = Vec::new();
+//! capture::encode_status_code(
+//!   status_code::EFI_PROGRESS_CODE,
+//!   status_code::EFI_SOFTWARE_DXE_CORE | status_code::EFI_SW_DXE_CORE_PC_ENTRY_POINT,
+//!   0,
+//!   None,
+//!   &[],
+//!   &mut |bytes| uart.extend_from_slice(bytes),
+//! );
+//!
+//! // Host side: decode the captured stream.
+//! for frame in capture::frames(&uart) {
+//!   let frame = frame.expect("corrupt frame");
+//!   if frame.record_type == capture::record_type::STATUS_CODE {
+//!     println!("{:x?}", capture::StatusCodeRecord::decode(frame.payload).unwrap());
+//!   }
+//! }
+//! ```
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+use alloc::{string::String, vec::Vec};
+use core::{fmt, mem};
+use r_efi::efi;
+use zerocopy::AsBytes;
+
+use crate::{
+    crc32::{crc32, crc32_update},
+    hob::{HobError, HobList},
+    protocols::{
+        self,
+        status_code::{EfiStatusCodeType, EfiStatusCodeValue},
+    },
+};
+
+/// The magic that starts every frame.
+pub const FRAME_MAGIC: [u8; 4] = *b"PICF";
+/// The version of the capture format produced by this implementation.
+pub const FRAME_VERSION: u16 = 1;
+
+/// Record types of the frame payloads.
+pub mod record_type {
+    /// A raw HOB list, from the PHIT HOB up to and including the END_OF_HOB_LIST HOB.
+    pub const HOB_LIST: u16 = 0x0001;
+    /// A status code event, see [`StatusCodeRecord`](super::StatusCodeRecord).
+    pub const STATUS_CODE: u16 = 0x0002;
+    /// A snapshot of the handle database, see [`HandleDatabaseRecord`](super::HandleDatabaseRecord).
+    pub const HANDLE_DATABASE: u16 = 0x0003;
+}
+
+/// The header of a frame. All fields are little-endian.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FrameHeader {
+    /// [`FRAME_MAGIC`].
+    pub magic: [u8; 4],
+    /// [`FRAME_VERSION`].
+    pub version: u16,
+    /// The type of the payload, one of [`record_type`].
+    pub record_type: u16,
+    /// The length of the payload in bytes.
+    pub length: u32,
+    /// The CRC32 of the payload.
+    pub crc32: u32,
+}
+
+const FRAME_HEADER_SIZE: usize = mem::size_of::<FrameHeader>();
+
+// Status code payload: code type, value, instance and flags (u32 each), caller ID (GUID), then the extended data.
+const STATUS_CODE_FIXED_SIZE: usize = 32;
+const STATUS_CODE_FLAG_CALLER_ID: u32 = 0x01;
+
+// Handle database payload: the handle count (u32), then for each handle its value (u64), its protocol count (u32) and
+// its protocol GUIDs.
+const HANDLE_COUNT_SIZE: usize = 4;
+const HANDLE_FIXED_SIZE: usize = 12;
+
+/// Errors detected while decoding a capture.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CaptureError {
+    /// The capture ended inside the frame at `offset`.
+    Truncated { offset: usize },
+    /// The frame at `offset` has a version this implementation cannot decode.
+    UnsupportedVersion { offset: usize, version: u16 },
+    /// The payload of the frame at `offset` does not match the CRC32 in the frame header.
+    CrcMismatch { offset: usize, expected: u32, actual: u32 },
+    /// The payload is too small for its record type.
+    MalformedRecord { record_type: u16, length: usize },
+}
+
+impl fmt::Display for CaptureError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CaptureError::Truncated { offset } => write!(f, "capture ends inside the frame at offset {offset:#x}"),
+            CaptureError::UnsupportedVersion { offset, version } => {
+                write!(f, "frame at offset {offset:#x} has unsupported version {version}")
+            }
+            CaptureError::CrcMismatch { offset, expected, actual } => write!(
+                f,
+                "frame at offset {offset:#x} has payload CRC32 {actual:#010x} but the header expects {expected:#010x}"
+            ),
+            CaptureError::MalformedRecord { record_type, length } => {
+                write!(f, "record of type {record_type:#x} is malformed ({length:#x} bytes)")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for CaptureError {}
+
+/// Writes a frame holding the concatenation of `payload` to `write`, and returns the number of bytes written.
+///
+/// `write` may be called several times for a single frame.
+pub fn encode_frame(record_type: u16, payload: &[&[u8]], write: &mut dyn FnMut(&[u8])) -> usize {
+    encode_frame_with(
+        record_type,
+        &|emit| {
+            for part in payload {
+                emit(part);
+            }
+        },
+        write,
+    )
+}
+
+// Receives the parts of a frame payload.
+type Emit<'a> = dyn FnMut(&[u8]) + 'a;
+
+// Writes a frame holding the parts passed to emit by `payload`, which is called twice: once to compute the length
+// and CRC32 of the payload, and once to write it.
+fn encode_frame_with(record_type: u16, payload: &dyn Fn(&mut Emit), write: &mut dyn FnMut(&[u8])) -> usize {
+    let mut length = 0;
+    let mut crc32 = 0;
+    payload(&mut |part| {
+        length += part.len();
+        crc32 = crc32_update(crc32, part);
+    });
+
+    let mut header = [0u8; FRAME_HEADER_SIZE];
+    header[0..4].copy_from_slice(&FRAME_MAGIC);
+    header[4..6].copy_from_slice(&FRAME_VERSION.to_le_bytes());
+    header[6..8].copy_from_slice(&record_type.to_le_bytes());
+    header[8..12].copy_from_slice(&(length as u32).to_le_bytes());
+    header[12..16].copy_from_slice(&crc32.to_le_bytes());
+    write(&header);
+    payload(&mut |part| {
+        if !part.is_empty() {
+            write(part);
+        }
+    });
+    FRAME_HEADER_SIZE + length
+}
+
+/// Writes a frame holding the given raw HOB list to `write`, and returns the number of bytes written.
+pub fn encode_hob_list(hob_list: &[u8], write: &mut dyn FnMut(&[u8])) -> usize {
+    encode_frame(record_type::HOB_LIST, &[hob_list], write)
+}
+
+/// Writes a frame holding a status code event to `write`, and returns the number of bytes written.
+///
+/// The arguments are those of `ReportStatusCode()`, with `data` holding the extended data including its
+/// EFI_STATUS_CODE_DATA header (or empty if there is no extended data).
+pub fn encode_status_code(
+    code_type: EfiStatusCodeType,
+    value: EfiStatusCodeValue,
+    instance: u32,
+    caller_id: Option<&efi::Guid>,
+    data: &[u8],
+    write: &mut dyn FnMut(&[u8]),
+) -> usize {
+    let flags = if caller_id.is_some() { STATUS_CODE_FLAG_CALLER_ID } else { 0 };
+    let mut fixed = [0u8; STATUS_CODE_FIXED_SIZE];
+    fixed[0..4].copy_from_slice(&code_type.to_le_bytes());
+    fixed[4..8].copy_from_slice(&value.to_le_bytes());
+    fixed[8..12].copy_from_slice(&instance.to_le_bytes());
+    fixed[12..16].copy_from_slice(&flags.to_le_bytes());
+    if let Some(caller_id) = caller_id {
+        fixed[16..32].copy_from_slice(caller_id.as_bytes());
+    }
+    encode_frame(record_type::STATUS_CODE, &[&fixed, data], write)
+}
+
+/// Writes a frame holding a snapshot of the handle database to `write`, and returns the number of bytes written.
+///
+/// `handles` lists each handle with the GUIDs of the protocols installed on it, as returned by
+/// `ProtocolsPerHandle()`.
+pub fn encode_handle_database(handles: &[(efi::Handle, &[efi::Guid])], write: &mut dyn FnMut(&[u8])) -> usize {
+    encode_frame_with(
+        record_type::HANDLE_DATABASE,
+        &|emit| {
+            emit(&(handles.len() as u32).to_le_bytes());
+            for (handle, protocols) in handles {
+                let mut fixed = [0u8; HANDLE_FIXED_SIZE];
+                fixed[0..8].copy_from_slice(&(*handle as usize as u64).to_le_bytes());
+                fixed[8..12].copy_from_slice(&(protocols.len() as u32).to_le_bytes());
+                emit(&fixed);
+                for protocol in protocols.iter() {
+                    emit(protocol.as_bytes());
+                }
+            }
+        },
+        write,
+    )
+}
+
+/// A frame decoded from a capture.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Frame<'a> {
+    /// The offset of the frame in the capture.
+    pub offset: usize,
+    /// The type of the payload, one of [`record_type`].
+    pub record_type: u16,
+    /// The verified payload.
+    pub payload: &'a [u8],
+}
+
+/// Returns an iterator over the frames of a capture.
+///
+/// Bytes that are not part of a frame are skipped. A frame with a bad CRC32 or an unsupported version is reported as
+/// an error and skipped, and decoding continues with the next frame. A truncated frame is reported as an error and
+/// ends the iteration.
+pub fn frames(capture: &[u8]) -> FrameIterator<'_> {
+    FrameIterator { capture, next_offset: 0 }
+}
+
+/// Iterator over the frames of a capture, see [`frames`].
+pub struct FrameIterator<'a> {
+    capture: &'a [u8],
+    next_offset: usize,
+}
+
+impl<'a> Iterator for FrameIterator<'a> {
+    type Item = Result<Frame<'a>, CaptureError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let remaining = self.capture.get(self.next_offset..)?;
+        let Some(position) = remaining.windows(FRAME_MAGIC.len()).position(|x| x == FRAME_MAGIC) else {
+            self.next_offset = self.capture.len();
+            return None;
+        };
+        let offset = self.next_offset + position;
+        let frame = &self.capture[offset..];
+        if frame.len() < FRAME_HEADER_SIZE {
+            self.next_offset = self.capture.len();
+            return Some(Err(CaptureError::Truncated { offset }));
+        }
+
+        let version = u16::from_le_bytes(frame[4..6].try_into().unwrap());
+        let record_type = u16::from_le_bytes(frame[6..8].try_into().unwrap());
+        let length = u32::from_le_bytes(frame[8..12].try_into().unwrap()) as usize;
+        let expected = u32::from_le_bytes(frame[12..16].try_into().unwrap());
+
+        // On errors, resume the scan just after this magic, as it may have been noise.
+        self.next_offset = offset + FRAME_MAGIC.len();
+        if version != FRAME_VERSION {
+            return Some(Err(CaptureError::UnsupportedVersion { offset, version }));
+        }
+        let Some(payload) = frame.get(FRAME_HEADER_SIZE..FRAME_HEADER_SIZE + length) else {
+            let actual = crc32(&frame[FRAME_HEADER_SIZE..]);
+            if actual == expected || self.capture[self.next_offset..].windows(4).all(|x| x != FRAME_MAGIC) {
+                self.next_offset = self.capture.len();
+                return Some(Err(CaptureError::Truncated { offset }));
+            }
+            return Some(Err(CaptureError::CrcMismatch { offset, expected, actual }));
+        };
+        let actual = crc32(payload);
+        if actual != expected {
+            return Some(Err(CaptureError::CrcMismatch { offset, expected, actual }));
+        }
+
+        self.next_offset = offset + FRAME_HEADER_SIZE + length;
+        Some(Ok(Frame { offset, record_type, payload }))
+    }
+}
+
+/// A status code event decoded from a [`record_type::STATUS_CODE`] frame.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StatusCodeRecord {
+    pub code_type: EfiStatusCodeType,
+    pub value: EfiStatusCodeValue,
+    pub instance: u32,
+    /// The ID of the caller, serialized as a registry format GUID string.
+    #[cfg_attr(feature = "serde", serde(with = "guid_serde"))]
+    pub caller_id: Option<efi::Guid>,
+    /// The extended data, including its EFI_STATUS_CODE_DATA header.
+    pub data: Vec<u8>,
+}
+
+impl StatusCodeRecord {
+    /// Decodes the payload of a [`record_type::STATUS_CODE`] frame.
+    pub fn decode(payload: &[u8]) -> Result<Self, CaptureError> {
+        if payload.len() < STATUS_CODE_FIXED_SIZE {
+            Err(CaptureError::MalformedRecord { record_type: record_type::STATUS_CODE, length: payload.len() })?;
+        }
+        let field = |offset: usize| u32::from_le_bytes(payload[offset..offset + 4].try_into().unwrap());
+        let caller_id = (field(12) & STATUS_CODE_FLAG_CALLER_ID != 0)
+            .then(|| efi::Guid::from_bytes(payload[16..32].try_into().unwrap()));
+        Ok(Self {
+            code_type: field(0),
+            value: field(4),
+            instance: field(8),
+            caller_id,
+            data: payload[STATUS_CODE_FIXED_SIZE..].to_vec(),
+        })
+    }
+}
+
+/// A snapshot of the handle database decoded from a [`record_type::HANDLE_DATABASE`] frame.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HandleDatabaseRecord {
+    pub handles: Vec<HandleRecord>,
+}
+
+/// A handle and the protocols installed on it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HandleRecord {
+    /// The value of the handle in the firmware that took the snapshot.
+    pub handle: u64,
+    pub protocols: Vec<ProtocolRecord>,
+}
+
+/// A protocol installed on a handle.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ProtocolRecord {
+    /// The protocol GUID, serialized as a registry format GUID string.
+    #[cfg_attr(feature = "serde", serde(with = "guid_serde::required"))]
+    pub guid: efi::Guid,
+    /// The name of the protocol, if it is known to [`protocols::lookup`].
+    pub name: Option<String>,
+}
+
+impl ProtocolRecord {
+    /// Creates the record of a protocol, resolving its name with [`protocols::lookup`].
+    pub fn new(guid: efi::Guid) -> Self {
+        Self { guid, name: protocols::lookup(&guid).map(|info| info.name.into()) }
+    }
+}
+
+impl HandleDatabaseRecord {
+    /// Decodes the payload of a [`record_type::HANDLE_DATABASE`] frame.
+    pub fn decode(payload: &[u8]) -> Result<Self, CaptureError> {
+        let malformed =
+            CaptureError::MalformedRecord { record_type: record_type::HANDLE_DATABASE, length: payload.len() };
+        let mut rest = payload;
+        let mut take = |size: usize| -> Result<&[u8], CaptureError> {
+            if rest.len() < size {
+                Err(malformed)?;
+            }
+            let (taken, remainder) = rest.split_at(size);
+            rest = remainder;
+            Ok(taken)
+        };
+
+        let handle_count = u32::from_le_bytes(take(HANDLE_COUNT_SIZE)?.try_into().unwrap());
+        let mut handles = Vec::new();
+        for _ in 0..handle_count {
+            let fixed = take(HANDLE_FIXED_SIZE)?;
+            let handle = u64::from_le_bytes(fixed[0..8].try_into().unwrap());
+            let protocol_count = u32::from_le_bytes(fixed[8..12].try_into().unwrap());
+            let protocols = take(protocol_count as usize * mem::size_of::<efi::Guid>())?
+                .chunks_exact(mem::size_of::<efi::Guid>())
+                .map(|guid| ProtocolRecord::new(efi::Guid::from_bytes(guid.try_into().unwrap())))
+                .collect();
+            handles.push(HandleRecord { handle, protocols });
+        }
+        if !rest.is_empty() {
+            Err(malformed)?;
+        }
+        Ok(Self { handles })
+    }
+}
+
+/// A HOB list decoded from a [`record_type::HOB_LIST`] frame.
+///
+/// The HOB list is copied to 8-byte aligned storage, as frames in a capture are not necessarily aligned.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CapturedHobList {
+    storage: Vec<u64>,
+    length: usize,
+}
+
+impl CapturedHobList {
+    /// Copies the payload of a [`record_type::HOB_LIST`] frame.
+    pub fn new(payload: &[u8]) -> Self {
+        let mut storage = alloc::vec![0u64; (payload.len() + mem::size_of::<u64>() - 1) / mem::size_of::<u64>()];
+        storage.as_bytes_mut()[..payload.len()].copy_from_slice(payload);
+        Self { storage, length: payload.len() }
+    }
+
+    /// Returns the raw HOB list.
+    pub fn bytes(&self) -> &[u8] {
+        &self.storage.as_bytes()[..self.length]
+    }
+
+    /// Parses the HOB list.
+    pub fn hob_list(&self) -> Result<HobList<'_>, HobError> {
+        let mut hob_list = HobList::new();
+        hob_list.try_discover_hobs(self.bytes())?;
+        Ok(hob_list)
+    }
+}
+
+#[cfg(feature = "serde")]
+pub(crate) mod guid_serde {
+    extern crate alloc;
+
+    use alloc::string::{String, ToString};
+    use r_efi::efi;
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+    use uuid::Uuid;
+
+    pub fn serialize<S: Serializer>(guid: &Option<efi::Guid>, serializer: S) -> Result<S::Ok, S::Error> {
+        match guid {
+            Some(guid) => serializer.serialize_some(&Uuid::from_bytes_le(*guid.as_bytes()).to_string().to_uppercase()),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<efi::Guid>, D::Error> {
+        let Some(guid) = Option::<String>::deserialize(deserializer)? else {
+            return Ok(None);
+        };
+        let uuid = Uuid::parse_str(&guid).map_err(D::Error::custom)?;
+        Ok(Some(efi::Guid::from_bytes(&uuid.to_bytes_le())))
+    }
+
+    pub mod required {
+        use super::alloc::string::ToString;
+        use r_efi::efi;
+        use serde::{de::Error, Deserializer, Serializer};
+        use uuid::Uuid;
+
+        pub fn serialize<S: Serializer>(guid: &efi::Guid, serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.serialize_str(&Uuid::from_bytes_le(*guid.as_bytes()).to_string().to_uppercase())
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<efi::Guid, D::Error> {
+            super::deserialize(deserializer)?.ok_or_else(|| D::Error::custom("missing GUID"))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::status_code;
+
+    const CALLER_ID: efi::Guid =
+        efi::Guid::from_fields(0x1234abcd, 0x5678, 0x9abc, 0xde, 0xf0, &[0x01, 0x23, 0x45, 0x67, 0x89, 0xab]);
+
+    #[test]
+    fn frames_should_round_trip() {
+        // An END_OF_HOB_LIST HOB: type 0xffff, length 8.
+        let hob_list = [0xff, 0xff, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00];
+
+        let mut capture = Vec::new();
+        capture.extend_from_slice(b"debug output\r\n");
+        let written = encode_hob_list(&hob_list, &mut |bytes| capture.extend_from_slice(bytes));
+        assert_eq!(written, FRAME_HEADER_SIZE + hob_list.len());
+        capture.push(b'!');
+        encode_status_code(
+            status_code::EFI_ERROR_CODE | status_code::EFI_ERROR_MAJOR,
+            status_code::EFI_SOFTWARE_DXE_CORE | status_code::EFI_SW_EC_ABORTED,
+            3,
+            Some(&CALLER_ID),
+            &[0xaa, 0xbb],
+            &mut |bytes| capture.extend_from_slice(bytes),
+        );
+
+        let frames = frames(&capture).collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[0].offset, 14);
+        assert_eq!(frames[0].record_type, record_type::HOB_LIST);
+        let hobs = CapturedHobList::new(frames[0].payload);
+        assert_eq!(hobs.bytes(), hob_list);
+        assert!(hobs.hob_list().is_ok());
+
+        assert_eq!(frames[1].record_type, record_type::STATUS_CODE);
+        let record = StatusCodeRecord::decode(frames[1].payload).unwrap();
+        assert_eq!(
+            record,
+            StatusCodeRecord {
+                code_type: status_code::EFI_ERROR_CODE | status_code::EFI_ERROR_MAJOR,
+                value: status_code::EFI_SOFTWARE_DXE_CORE | status_code::EFI_SW_EC_ABORTED,
+                instance: 3,
+                caller_id: Some(CALLER_ID),
+                data: vec![0xaa, 0xbb],
+            }
+        );
+    }
+
+    #[test]
+    fn corrupt_frames_should_be_reported() {
+        let mut capture = Vec::new();
+        encode_status_code(status_code::EFI_PROGRESS_CODE, 1, 0, None, &[], &mut |x| capture.extend_from_slice(x));
+        let second = capture.len();
+        encode_status_code(status_code::EFI_PROGRESS_CODE, 2, 0, None, &[], &mut |x| capture.extend_from_slice(x));
+
+        // A corrupt payload is reported, and decoding resumes with the next frame.
+        capture[FRAME_HEADER_SIZE + 4] ^= 0xff;
+        let results = frames(&capture).collect::<Vec<_>>();
+        assert_eq!(results.len(), 2);
+        assert!(matches!(results[0], Err(CaptureError::CrcMismatch { offset: 0, .. })));
+        assert_eq!(StatusCodeRecord::decode(results[1].unwrap().payload).unwrap().value, 2);
+
+        // A truncated frame ends the capture.
+        let results = frames(&capture[..capture.len() - 1]).collect::<Vec<_>>();
+        assert_eq!(results.last(), Some(&Err(CaptureError::Truncated { offset: second })));
+
+        assert_eq!(
+            StatusCodeRecord::decode(&[0; 8]),
+            Err(CaptureError::MalformedRecord { record_type: record_type::STATUS_CODE, length: 8 })
+        );
+    }
+
+    #[test]
+    fn handle_database_should_round_trip() {
+        let first = [protocols::status_code::PROTOCOL_GUID, CALLER_ID];
+        let handles: [(efi::Handle, &[efi::Guid]); 2] = [(0x1000 as efi::Handle, &first), (0x2000 as efi::Handle, &[])];
+
+        let mut capture = Vec::new();
+        let written = encode_handle_database(&handles, &mut |bytes| capture.extend_from_slice(bytes));
+        assert_eq!(written, FRAME_HEADER_SIZE + HANDLE_COUNT_SIZE + 2 * HANDLE_FIXED_SIZE + 2 * 16);
+
+        let frame = frames(&capture).next().unwrap().unwrap();
+        assert_eq!(frame.record_type, record_type::HANDLE_DATABASE);
+        let record = HandleDatabaseRecord::decode(frame.payload).unwrap();
+        assert_eq!(
+            record,
+            HandleDatabaseRecord {
+                handles: vec![
+                    HandleRecord {
+                        handle: 0x1000,
+                        protocols: vec![
+                            ProtocolRecord {
+                                guid: protocols::status_code::PROTOCOL_GUID,
+                                name: Some("EFI_STATUS_CODE_PROTOCOL".into())
+                            },
+                            ProtocolRecord { guid: CALLER_ID, name: None },
+                        ],
+                    },
+                    HandleRecord { handle: 0x2000, protocols: Vec::new() },
+                ],
+            }
+        );
+
+        let malformed = |length| CaptureError::MalformedRecord { record_type: record_type::HANDLE_DATABASE, length };
+        let payload = frame.payload;
+        assert_eq!(HandleDatabaseRecord::decode(&payload[..payload.len() - 1]), Err(malformed(payload.len() - 1)));
+        let mut trailing = payload.to_vec();
+        trailing.push(0);
+        assert_eq!(HandleDatabaseRecord::decode(&trailing), Err(malformed(trailing.len())));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn handle_database_record_should_serialize() {
+        let record = HandleDatabaseRecord {
+            handles: vec![HandleRecord { handle: 0x1000, protocols: vec![ProtocolRecord::new(CALLER_ID)] }],
+        };
+        let yaml = serde_yaml::to_string(&record).unwrap();
+        assert!(yaml.contains("guid: 1234ABCD-5678-9ABC-DEF0-0123456789AB"));
+        assert_eq!(serde_yaml::from_str::<HandleDatabaseRecord>(&yaml).unwrap(), record);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn status_code_record_should_serialize() {
+        let record =
+            StatusCodeRecord { code_type: 1, value: 2, instance: 0, caller_id: Some(CALLER_ID), data: Vec::new() };
+        let yaml = serde_yaml::to_string(&record).unwrap();
+        assert!(yaml.contains("caller_id: 1234ABCD-5678-9ABC-DEF0-0123456789AB"));
+        assert_eq!(serde_yaml::from_str::<StatusCodeRecord>(&yaml).unwrap(), record);
+    }
+}
@@ -0,0 +1,81 @@
+//! Sum-to-Zero Checksums
+//!
+//! The 8-bit and 16-bit checksums used by the PI specification structures: a structure is valid when the sum of its
+//! bytes (or of its little-endian 16-bit words) is zero, so the checksum field holds the two's complement of the sum
+//! of the other bytes. FV headers use the 16-bit checksum, and FFS file headers and data use the 8-bit checksum.
+//!
+//! ## Example
+//! ```
+//! use mu_pi::checksum;
+//!
+//! let mut header = [0x12, 0x34, 0x56, 0x78, 0x00, 0x00];
+//! let checksum = checksum::compute16(&header);
+//! header[4..6].copy_from_slice(&checksum.to_le_bytes());
+//! assert!(checksum::verify16(&header));
+//! ```
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+/// Returns the wrapping sum of the bytes of `data`.
+pub fn sum8(data: &[u8]) -> u8 {
+    data.iter().fold(0u8, |sum, &x| sum.wrapping_add(x))
+}
+
+/// Returns the 8-bit checksum of `data`: the value that makes the bytes of `data` and the checksum sum to zero.
+pub fn compute8(data: &[u8]) -> u8 {
+    sum8(data).wrapping_neg()
+}
+
+/// Returns whether the bytes of `data`, which includes its checksum, sum to zero.
+pub fn verify8(data: &[u8]) -> bool {
+    sum8(data) == 0
+}
+
+/// Returns the wrapping sum of the little-endian 16-bit words of `data`.
+///
+/// A trailing odd byte is not part of any word, and is ignored.
+pub fn sum16(data: &[u8]) -> u16 {
+    data.chunks_exact(2).fold(0u16, |sum, x| sum.wrapping_add(u16::from_le_bytes([x[0], x[1]])))
+}
+
+/// Returns the 16-bit checksum of `data`: the value that makes the words of `data` and the checksum sum to zero.
+pub fn compute16(data: &[u8]) -> u16 {
+    sum16(data).wrapping_neg()
+}
+
+/// Returns whether the little-endian 16-bit words of `data`, which includes its checksum, sum to zero.
+///
+/// Data with an odd length does not verify.
+pub fn verify16(data: &[u8]) -> bool {
+    data.len() % 2 == 0 && sum16(data) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checksums_should_sum_to_zero() {
+        let mut data = [0x01, 0xff, 0x80, 0x7f, 0x00];
+        assert_eq!(sum8(&data[..4]), 0xff);
+        data[4] = compute8(&data[..4]);
+        assert_eq!(data[4], 0x01);
+        assert!(verify8(&data));
+        data[0] ^= 1;
+        assert!(!verify8(&data));
+
+        let mut data = [0x34, 0x12, 0xff, 0xff, 0x00, 0x00];
+        assert_eq!(sum16(&data[..4]), 0x1233);
+        let checksum = compute16(&data[..4]);
+        data[4..].copy_from_slice(&checksum.to_le_bytes());
+        assert_eq!(checksum, 0xedcd);
+        assert!(verify16(&data));
+        assert!(!verify16(&data[..5]));
+        assert!(verify8(&[]) && verify16(&[]));
+    }
+}
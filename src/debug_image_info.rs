@@ -0,0 +1,113 @@
+//! Debug Image Info Table
+//!
+//! Definitions used by debuggers to find the images loaded by the firmware:
+//! - **EFI_SYSTEM_TABLE_POINTER** - Placed on a 4MB boundary in memory so that a debugger can locate the EFI System
+//!   Table by scanning memory for its signature.
+//! - **EFI_DEBUG_IMAGE_INFO_TABLE** - Installed as a configuration table by the DXE core. Lists every loaded image,
+//!   and is updated as images are loaded and unloaded.
+//!
+//! See <https://uefi.org/specs/UEFI/2.10/18_Protocols_Debugger_Support.html#efi-debug-support-table>.
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+use r_efi::{efi, protocols::loaded_image};
+
+/// GUID of the configuration table that holds the [`DebugImageInfoTableHeader`].
+///
+/// # Documentation
+/// UEFI Specification, Release 2.10, Section 18.4.3
+pub const DEBUG_IMAGE_INFO_TABLE_GUID: efi::Guid =
+    efi::Guid::from_fields(0x49152e77, 0x1ada, 0x4764, 0xb7, 0xa2, &[0x7a, 0xfe, 0xfe, 0xd9, 0x5e, 0x8b]);
+
+/// Signature of the [`SystemTablePointer`] structure: "IBI SYST".
+pub const SYSTEM_TABLE_SIGNATURE: u64 = u64::from_le_bytes(*b"IBI SYST");
+
+/// Alignment of the [`SystemTablePointer`] structure in memory (4MB).
+pub const SYSTEM_TABLE_POINTER_ALIGNMENT: u64 = 0x400000;
+
+/// Set in [`DebugImageInfoTableHeader::update_status`] while the table is being updated.
+pub const DEBUG_IMAGE_INFO_UPDATE_IN_PROGRESS: u32 = 0x01;
+/// Set in [`DebugImageInfoTableHeader::update_status`] when the table has been modified. Cleared by the debugger.
+pub const DEBUG_IMAGE_INFO_TABLE_MODIFIED: u32 = 0x02;
+
+/// Image info type of a [`DebugImageInfoNormal`] entry.
+pub const DEBUG_IMAGE_INFO_TYPE_NORMAL: u32 = 0x01;
+
+/// Allows a debugger to locate the EFI System Table.
+///
+/// The structure is placed at the highest 4MB aligned address that can hold it in memory of type
+/// EfiRuntimeServicesData, and is updated whenever the system table moves.
+///
+/// # Documentation
+/// UEFI Specification, Release 2.10, Section 18.4.2
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SystemTablePointer {
+    /// Must be [`SYSTEM_TABLE_SIGNATURE`].
+    pub signature: u64,
+    /// The physical address of the EFI System Table.
+    pub efi_system_table_base: efi::PhysicalAddress,
+    /// The 32-bit CRC of this structure, computed with this field set to zero.
+    pub crc32: u32,
+}
+
+/// A loaded image entry in the debug image info table.
+///
+/// # Documentation
+/// UEFI Specification, Release 2.10, Section 18.4.3
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct DebugImageInfoNormal {
+    /// Must be [`DEBUG_IMAGE_INFO_TYPE_NORMAL`].
+    pub image_info_type: u32,
+    /// The loaded image protocol instance of the image.
+    pub loaded_image_protocol_instance: *mut loaded_image::Protocol,
+    /// The handle the loaded image protocol is installed on.
+    pub image_handle: efi::Handle,
+}
+
+/// An entry in the debug image info table. The entry type is identified by the first u32 of the referenced structure.
+///
+/// A null entry is a free slot.
+///
+/// # Documentation
+/// UEFI Specification, Release 2.10, Section 18.4.3
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub union DebugImageInfo {
+    pub image_info_type: *mut u32,
+    pub normal_image: *mut DebugImageInfoNormal,
+}
+
+/// The debug image info table, installed as the [`DEBUG_IMAGE_INFO_TABLE_GUID`] configuration table.
+///
+/// # Documentation
+/// UEFI Specification, Release 2.10, Section 18.4.3
+#[repr(C)]
+#[derive(Debug)]
+pub struct DebugImageInfoTableHeader {
+    /// Combination of [`DEBUG_IMAGE_INFO_UPDATE_IN_PROGRESS`] and [`DEBUG_IMAGE_INFO_TABLE_MODIFIED`]. May be written
+    /// by a debugger, so must be accessed with volatile reads and writes.
+    pub update_status: u32,
+    /// The number of entries in `efi_debug_image_info_table`.
+    pub table_size: u32,
+    /// The array of debug image info entries.
+    pub efi_debug_image_info_table: *mut DebugImageInfo,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::mem;
+
+    #[test]
+    fn system_table_pointer_layout() {
+        assert_eq!(SYSTEM_TABLE_SIGNATURE.to_le_bytes(), *b"IBI SYST");
+        assert_eq!(mem::size_of::<SystemTablePointer>(), 24);
+    }
+}
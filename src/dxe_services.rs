@@ -141,6 +141,7 @@ pub type ProcessFirmwareVolume = extern "efiapi" fn(*const c_void, usize, *mut H
 
 #[repr(C)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 /// `EFI_GCD_MEMORY_TYPE` in specification.
 pub enum GcdMemoryType {
     /// A memory region that is visible to the boot processor.
@@ -168,7 +169,8 @@ pub enum GcdMemoryType {
 }
 
 #[repr(C)]
-#[derive(Debug, Clone, Copy, Default)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 /// `EFI_GCD_ALLOCATE_TYPE` in specification.
 pub enum GcdAllocateType {
     #[default]
@@ -182,6 +184,7 @@ pub enum GcdAllocateType {
 
 #[repr(C)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 /// `EFI_GCD_MEMORY_SPACE_DESCRIPTOR` in specification.
 pub struct MemorySpaceDescriptor {
     /// The physical address of the first byte in the memory region.
@@ -197,17 +200,20 @@ pub struct MemorySpaceDescriptor {
     /// The image handle of the agent that allocated the memory resource described by PhysicalStart and NumberOfBytes.
     ///
     /// If this field is NULL, then the memory resource is not currently allocated.
+    #[cfg_attr(feature = "serde", serde(with = "handle_serde"))]
     pub image_handle: Handle,
     /// The device handle for which the memory resource has been allocated.
     ///
     /// If ImageHandle is NULL, then the memory resource is not currently allocated.
     ///
     /// If this field is NULL, then the memory resource is not associated with a device that is described by a device handle.
+    #[cfg_attr(feature = "serde", serde(with = "handle_serde"))]
     pub device_handle: Handle,
 }
 
 #[repr(C)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 /// `EFI_GCD_IO_TYPE` in specification.
 pub enum GcdIoType {
     /// An I/O region that is visible to the boot processor.
@@ -227,6 +233,7 @@ pub enum GcdIoType {
 
 #[repr(C)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 /// `EFI_GCD_IO_SPACE_DESCRIPTOR` in specification.
 pub struct IoSpaceDescriptor {
     /// Physical address of the first byte in the I/O region.
@@ -238,12 +245,14 @@ pub struct IoSpaceDescriptor {
     /// The image handle of the agent that allocated the I/O resource described by PhysicalStart and NumberOfBytes.
     ///
     /// If this field is NULL, then the I/O resource is not currently allocated.
+    #[cfg_attr(feature = "serde", serde(with = "handle_serde"))]
     pub image_handle: Handle,
     /// The device handle for which the I/O resource has been allocated.
     ///
     /// If ImageHandle is NULL , then the I/O resource is not currently allocated.
     ///
     /// If this field is NULL, then the I/O resource is not associated with a device that is described by a device handle.
+    #[cfg_attr(feature = "serde", serde(with = "handle_serde"))]
     pub device_handle: Handle,
 }
 
@@ -315,3 +324,49 @@ impl Default for IoSpaceDescriptor {
         }
     }
 }
+
+// Handles are opaque pointers, so they are represented by their address when serialized.
+#[cfg(feature = "serde")]
+mod handle_serde {
+    use r_efi::efi::Handle;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(handle: &Handle, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u64(*handle as usize as u64)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Handle, D::Error> {
+        Ok(u64::deserialize(deserializer)? as usize as Handle)
+    }
+}
+
+#[cfg(all(test, feature = "serde"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gcd_descriptors_should_round_trip_through_serde() {
+        let memory_descriptor = MemorySpaceDescriptor {
+            base_address: 0x1000,
+            length: 0x2000,
+            capabilities: 0xf,
+            attributes: 0x8,
+            memory_type: GcdMemoryType::SystemMemory,
+            image_handle: 0x1234 as Handle,
+            device_handle: 0 as Handle,
+        };
+        let yaml = serde_yaml::to_string(&memory_descriptor).unwrap();
+        assert!(yaml.contains("memory_type: SystemMemory"));
+        assert_eq!(serde_yaml::from_str::<MemorySpaceDescriptor>(&yaml).unwrap(), memory_descriptor);
+
+        let io_descriptor = IoSpaceDescriptor {
+            base_address: 0x80,
+            length: 0x10,
+            io_type: GcdIoType::Io,
+            image_handle: 0 as Handle,
+            device_handle: 0x5678 as Handle,
+        };
+        let yaml = serde_yaml::to_string(&io_descriptor).unwrap();
+        assert_eq!(serde_yaml::from_str::<IoSpaceDescriptor>(&yaml).unwrap(), io_descriptor);
+    }
+}
@@ -11,38 +11,179 @@
 //! SPDX-License-Identifier: BSD-2-Clause-Patent
 //!
 
-extern crate alloc;
+use core::{fmt, mem};
+
+// Defines a typed wrapper around raw attribute bits, with named flags, bitwise operators, conversions to and from the
+// raw bits, and a Debug implementation that lists the named flags that are set. Multi-bit fields (such as alignments)
+// are not flags; the wrapper decodes them with dedicated accessors.
+macro_rules! attribute_flags {
+    (
+        $(#[$meta:meta])*
+        pub struct $name:ident($raw:ty) {
+            $(const $flag:ident = $value:expr;)*
+        }
+    ) => {
+        $(#[$meta])*
+        #[repr(transparent)]
+        #[derive(Copy, Clone, PartialEq, Eq, Hash, Default)]
+        pub struct $name($raw);
+
+        impl $name {
+            $(pub const $flag: Self = Self($value);)*
+
+            /// Returns attributes with no bits set.
+            pub const fn empty() -> Self {
+                Self(0)
+            }
+
+            /// Returns the attributes with the given raw bits, including bits that are not named flags.
+            pub const fn from_bits_retain(bits: $raw) -> Self {
+                Self(bits)
+            }
+
+            /// Returns the raw bits of the attributes.
+            pub const fn bits(&self) -> $raw {
+                self.0
+            }
+
+            /// Returns whether all the bits of `other` are set.
+            pub const fn contains(&self, other: Self) -> bool {
+                self.0 & other.0 == other.0
+            }
+
+            /// Returns whether any of the bits of `other` are set.
+            pub const fn intersects(&self, other: Self) -> bool {
+                self.0 & other.0 != 0
+            }
+        }
 
-use core::{fmt, mem, num::Wrapping, slice};
+        impl From<$raw> for $name {
+            fn from(bits: $raw) -> Self {
+                Self(bits)
+            }
+        }
+
+        impl From<$name> for $raw {
+            fn from(attributes: $name) -> Self {
+                attributes.0
+            }
+        }
+
+        impl core::ops::BitOr for $name {
+            type Output = Self;
+            fn bitor(self, rhs: Self) -> Self {
+                Self(self.0 | rhs.0)
+            }
+        }
+
+        impl core::ops::BitOrAssign for $name {
+            fn bitor_assign(&mut self, rhs: Self) {
+                self.0 |= rhs.0;
+            }
+        }
 
+        impl core::ops::BitAnd for $name {
+            type Output = Self;
+            fn bitand(self, rhs: Self) -> Self {
+                Self(self.0 & rhs.0)
+            }
+        }
+
+        impl core::fmt::Debug for $name {
+            fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+                write!(f, "{}(", stringify!($name))?;
+                let mut remaining = self.0;
+                let mut separator = "";
+                $(
+                    if self.contains(Self::$flag) {
+                        write!(f, "{separator}{}", stringify!($flag))?;
+                        remaining &= !$value;
+                        separator = " | ";
+                    }
+                )*
+                if remaining != 0 || separator.is_empty() {
+                    write!(f, "{separator}{remaining:#x}")?;
+                }
+                write!(f, ")")
+            }
+        }
+    };
+}
+
+#[cfg(feature = "alloc")]
+pub mod audit;
+#[cfg(feature = "alloc")]
+pub mod builder;
+#[cfg(feature = "alloc")]
+pub mod diff;
+#[cfg(feature = "alloc")]
+pub mod edit;
+#[cfg(feature = "alloc")]
+pub mod extractors;
 pub mod ffs;
 pub mod fv;
 pub mod fvb;
-
-use ffs::{attributes::raw::LARGE_FILE, file, section};
+#[cfg(feature = "guid_names")]
+pub mod guid_names;
+#[cfg(feature = "alloc")]
+pub mod indexed;
+#[cfg(feature = "std")]
+pub mod mmap;
+pub mod raw;
+#[cfg(feature = "alloc")]
+pub mod rebuild;
+#[cfg(feature = "alloc")]
+pub mod reproducibility;
+#[cfg(feature = "alloc")]
+pub mod storage;
+#[cfg(feature = "alloc")]
+pub mod tree;
+
+use ffs::{
+    attributes::raw::{ffs1::TAIL_PRESENT, LARGE_FILE},
+    file,
+};
 pub use ffs::{
-    attributes::{raw as FfsRawAttribute, Attribute as FfsAttribute},
+    attributes::{raw as FfsRawAttribute, Attribute as FfsAttribute, Attributes as FfsAttributes},
     file::{
         raw::{r#type as FfsFileRawType, state as FfsFileRawState},
         State as FfsFileState, Type as FfsFileType,
     },
     section::{
-        header as FfsSectionHeader, raw_type as FfsSectionRawType,
-        raw_type::encapsulated as FfsEncapsulatedSectionRawType, EfiSectionType, Type as FfsSectionType,
+        auth_status as FfsAuthStatus, guided_attributes as FfsGuidedSectionAttribute, header as FfsSectionHeader,
+        raw_type as FfsSectionRawType, raw_type::encapsulated as FfsEncapsulatedSectionRawType, EfiSectionType,
+        GuidedAttributes as FfsGuidedSectionAttributes, Type as FfsSectionType,
     },
+    FileSystem as FfsFileSystem,
 };
 pub use fv::{
     attributes::{raw::fv2 as Fv2RawAttributes, EfiFvAttributes, Fv2 as Fv2Attributes},
-    file::{raw::attribute as FvFileRawAttribute, Attribute as FvFileAttribute, EfiFvFileAttributes},
+    ext_entry_type as FvExtEntryType,
+    file::{
+        raw::attribute as FvFileRawAttribute, Attribute as FvFileAttribute, Attributes as FvFileAttributes,
+        EfiFvFileAttributes,
+    },
     EfiFvFileType, WritePolicy,
 };
-pub use fvb::attributes::{raw::fvb2 as Fvb2RawAttributes, EfiFvbAttributes2, Fvb2 as Fvb2Attributes};
+pub use fvb::attributes::{raw::fvb2 as Fvb2RawAttributes, EfiFvbAttributes2, Fvb2 as Fvb2Attributes, FvbAttributes2};
 
-use alloc::{boxed::Box, collections::VecDeque, vec::Vec};
-use num_traits::WrappingSub;
 use r_efi::efi;
 
-use crate::address_helper::align_up;
+use crate::{checksum, parse::Deviation};
+
+#[cfg(feature = "alloc")]
+use alloc::{boxed::Box, collections::VecDeque, string::String, vec::Vec};
+#[cfg(feature = "alloc")]
+use core::{ops::Range, slice};
+#[cfg(feature = "alloc")]
+use ffs::section;
+
+#[cfg(feature = "alloc")]
+use crate::{
+    address_helper::align_up,
+    parse::{AlignmentPolicy, Diagnostic, ParseOptions, ValidationLevel},
+    pe_header::PeHeaderInfo,
+};
 
 /// Defines an interface that can be implemented to provide extraction logic for encapsulation sections.
 ///
@@ -74,6 +215,7 @@ use crate::address_helper::align_up;
 /// # Ok(())
 /// # }
 ///```
+#[cfg(feature = "alloc")]
 pub trait SectionExtractor {
     /// Extracts the given section and returns the resulting buffer.
     ///
@@ -88,25 +230,274 @@ pub trait SectionExtractor {
     /// If the section extraction implementation does not support the encapsulations type used in this section, it can
     /// return a successful extraction with a zero-size buffer - this will allow parsing the rest of the FFS while only
     /// exposing the encapsulation section as a whole (without exposing sections it contains that cannot be extracted).
+    /// GUID-defined sections with the [`PROCESSING_REQUIRED`](FfsGuidedSectionAttribute::PROCESSING_REQUIRED) attribute
+    /// are the exception: their contents are meaningless unprocessed, so the section iterators return `UNSUPPORTED`
+    /// after such a section when it is not extracted.
+    ///
+    /// The data of GUID-defined sections that require processing is available from [`Section::raw_section_data()`].
     fn extract(&self, section: &Section) -> Result<Box<[u8]>, efi::Status>;
+
+    /// Extracts the given section and returns the resulting buffer along with its authentication status, a
+    /// combination of [`FfsAuthStatus`] bits.
+    ///
+    /// For GUID-defined sections, the status is only used if the section has the
+    /// [`AUTH_STATUS_VALID`](FfsGuidedSectionAttribute::AUTH_STATUS_VALID) attribute, see [`FfsAuthStatus::nested`].
+    /// The default implementation calls [`SectionExtractor::extract`] and reports no authentication status.
+    fn extract_with_auth_status(&self, section: &Section) -> Result<(Box<[u8]>, u32), efi::Status> {
+        Ok((self.extract(section)?, 0))
+    }
+
+    /// Extracts the given section like [`SectionExtractor::extract_with_auth_status`], failing with
+    /// `OUT_OF_RESOURCES` if the extracted buffer would be larger than `max_size` bytes.
+    ///
+    /// The section iterators call this with what is left of [`ExtractionLimits::max_extracted_size`]. Extractors that
+    /// learn the extracted size from the section data (e.g. from a decompressed size field) should override it and
+    /// reject a larger size before allocating the buffer. The default implementation checks the size of the buffer
+    /// returned by [`SectionExtractor::extract_with_auth_status`].
+    fn extract_with_limit(&self, section: &Section, max_size: usize) -> Result<(Box<[u8]>, u32), efi::Status> {
+        let (buffer, authentication_status) = self.extract_with_auth_status(section)?;
+        if buffer.len() > max_size {
+            Err(efi::Status::OUT_OF_RESOURCES)?;
+        }
+        Ok((buffer, authentication_status))
+    }
+}
+
+/// Limits on the extraction of encapsulation sections.
+///
+/// They stop images whose encapsulation sections expand into more encapsulation sections (decompression bombs) from
+/// exhausting the time or memory of the consumer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ExtractionLimits {
+    /// The maximum nesting depth of extracted encapsulation sections. An encapsulation section nested deeper is returned
+    /// without being extracted, followed by a `VOLUME_CORRUPTED` error. Defaults to 16.
+    pub max_depth: usize,
+    /// The maximum total number of bytes extracted from the encapsulation sections of a file, across all nesting
+    /// levels. An encapsulation section that would exceed it is returned followed by an `OUT_OF_RESOURCES` error.
+    /// Compression sections that declare a larger uncompressed length are not extracted at all, and the remaining
+    /// budget is passed to [`SectionExtractor::extract_with_limit`]. Defaults to 256 MiB.
+    pub max_extracted_size: usize,
+}
+
+impl Default for ExtractionLimits {
+    fn default() -> Self {
+        Self { max_depth: 16, max_extracted_size: 256 * 1024 * 1024 }
+    }
 }
 
 // Null implementation of SectionExtractor used by [`FirmwareVolume::new`] and [`File::new`] when no extraction is
 // desired.
+#[cfg(feature = "alloc")]
 struct NullSectionExtractor {}
 
+#[cfg(feature = "alloc")]
 impl SectionExtractor for NullSectionExtractor {
     fn extract(&self, _section: &Section) -> Result<Box<[u8]>, efi::Status> {
         Ok(Box::new([0u8; 0]))
     }
 }
 
+/// Errors detected while parsing a firmware volume, file or section.
+///
+/// The `efi::Status` based APIs report these as `INVALID_PARAMETER` (for buffers too small to hold the structure, or
+/// volumes with an unknown file system) or `VOLUME_CORRUPTED` (for everything else); see
+/// [`FirmwareVolume::try_new`], [`FirmwareVolume::try_file_iter`], [`File::try_new`] and [`Section::try_new`] for the
+/// APIs that return them directly.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum FwFsError {
+    /// The buffer is too small to contain the header of the structure being parsed.
+    BufferTooSmall { needed: usize, available: usize },
+    /// A file or section header declares an extended header that runs past the end of the buffer.
+    TruncatedHeader { needed: usize, available: usize },
+    /// The FV header signature is not `_FVH`.
+    BadSignature { signature: u32 },
+    /// The FV header length is too small, misaligned or larger than the buffer.
+    BadHeaderLength { header_length: u16 },
+    /// The FV header checksum does not sum to zero.
+    BadHeaderChecksum,
+    /// The FV file system GUID is not a known FFS file system.
+    UnknownFileSystem { guid: efi::Guid },
+    /// The FV header revision is not supported.
+    BadRevision { revision: u8 },
+    /// The FV length is smaller than the header or larger than the storage containing the FV.
+    BadFvLength { fv_length: u64 },
+    /// The FV extended header at the given offset does not fit inside the FV.
+    BadExtHeader { offset: usize },
+    /// The FV block map is unterminated or does not match the FV length.
+    BadBlockMap,
+    /// A file declares a size that does not fit in the FV or is smaller than its header.
+    BadFileSize { name: efi::Guid, size: u64 },
+    /// A file is in a state that does not describe a valid file.
+    BadFileState { name: efi::Guid, state: u8 },
+    /// A file header checksum does not sum to zero.
+    BadFileHeaderChecksum { name: efi::Guid },
+    /// A file data checksum does not sum to zero.
+    BadFileDataChecksum { name: efi::Guid },
+    /// An FFS1 file tail is not the complement of the file integrity check.
+    BadFileTail { name: efi::Guid, tail: u16 },
+    /// A section declares a size that does not fit in the buffer or is smaller than its header.
+    BadSectionSize { section_type: u8, size: usize },
+    /// A GUID-defined section declares a data offset outside of the section.
+    BadSectionDataOffset { data_offset: u16 },
+    /// A spec deviation that is rejected under [`Strictness::Strict`](crate::parse::Strictness::Strict).
+    Deviation(Deviation),
+}
+
+impl fmt::Display for FwFsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FwFsError::BufferTooSmall { needed, available } => {
+                write!(f, "buffer of {available:#x} bytes is too small for a {needed:#x} byte header")
+            }
+            FwFsError::TruncatedHeader { needed, available } => {
+                write!(f, "extended header needs {needed:#x} bytes but only {available:#x} are available")
+            }
+            FwFsError::BadSignature { signature } => write!(f, "FV header has bad signature {signature:#x}"),
+            FwFsError::BadHeaderLength { header_length } => {
+                write!(f, "FV header has bad header length {header_length:#x}")
+            }
+            FwFsError::BadHeaderChecksum => write!(f, "FV header has bad checksum"),
+            FwFsError::UnknownFileSystem { guid } => write!(f, "FV has unknown file system {guid:?}"),
+            FwFsError::BadRevision { revision } => write!(f, "FV header has unsupported revision {revision:#x}"),
+            FwFsError::BadFvLength { fv_length } => write!(f, "FV has bad length {fv_length:#x}"),
+            FwFsError::BadExtHeader { offset } => write!(f, "FV extended header at offset {offset:#x} is malformed"),
+            FwFsError::BadBlockMap => write!(f, "FV block map is malformed"),
+            FwFsError::BadFileSize { name, size } => write!(f, "file {name:?} has bad size {size:#x}"),
+            FwFsError::BadFileState { name, state } => write!(f, "file {name:?} has bad state {state:#x}"),
+            FwFsError::BadFileHeaderChecksum { name } => write!(f, "file {name:?} has bad header checksum"),
+            FwFsError::BadFileDataChecksum { name } => write!(f, "file {name:?} has bad data checksum"),
+            FwFsError::BadFileTail { name, tail } => write!(f, "file {name:?} has bad tail {tail:#x}"),
+            FwFsError::BadSectionSize { section_type, size } => {
+                write!(f, "section of type {section_type:#x} has bad size {size:#x}")
+            }
+            FwFsError::BadSectionDataOffset { data_offset } => {
+                write!(f, "GUID-defined section has bad data offset {data_offset:#x}")
+            }
+            FwFsError::Deviation(deviation) => write!(f, "{deviation}"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for FwFsError {}
+
+impl From<FwFsError> for efi::Status {
+    fn from(error: FwFsError) -> Self {
+        match error {
+            FwFsError::BufferTooSmall { .. } | FwFsError::UnknownFileSystem { .. } => efi::Status::INVALID_PARAMETER,
+            _ => efi::Status::VOLUME_CORRUPTED,
+        }
+    }
+}
+
+/// Firmware Volume extended header access support
+///
+/// Provides access to the FV name and to the entries that follow the extended header.
 #[derive(Clone)]
 pub struct FirmwareVolumeExtHeader<'a> {
     header: fv::ExtHeader,
     data: &'a [u8],
 }
 
+impl<'a> FirmwareVolumeExtHeader<'a> {
+    /// Returns the FV name.
+    pub fn fv_name(&self) -> efi::Guid {
+        self.header.fv_name
+    }
+
+    /// Returns an iterator of the entries that follow the extended header.
+    ///
+    /// Iteration stops after the first entry that does not fit in the extended header, which is returned as
+    /// `VOLUME_CORRUPTED`.
+    #[cfg(feature = "alloc")]
+    pub fn entries(&self) -> impl Iterator<Item = Result<FvExtEntry<'a>, efi::Status>> {
+        let mut buffer = &self.data[mem::size_of::<fv::ExtHeader>()..];
+        core::iter::from_fn(move || {
+            if buffer.is_empty() {
+                return None;
+            }
+            let entry = FvExtEntry::parse(buffer);
+            match entry {
+                Ok((_, size)) => buffer = &buffer[size..],
+                Err(_) => buffer = &[],
+            }
+            Some(entry.map(|(entry, _)| entry))
+        })
+    }
+
+    /// Returns the used size of the FV from the USED_SIZE_TYPE entry, if present. Space in the FV beyond the used size
+    /// is free.
+    #[cfg(feature = "alloc")]
+    pub fn used_size(&self) -> Option<u32> {
+        self.entries().map_while(Result::ok).find_map(|entry| match entry {
+            FvExtEntry::UsedSize(used_size) => Some(used_size),
+            _ => None,
+        })
+    }
+}
+
+/// An entry that follows the FV extended header (EFI_FIRMWARE_VOLUME_EXT_ENTRY).
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Volume 3, Section 3.2.1.2
+#[cfg(feature = "alloc")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FvExtEntry<'a> {
+    /// EFI_FIRMWARE_VOLUME_EXT_ENTRY_OEM_TYPE: the OEM file types used in the FV.
+    Oem { type_mask: u32, types: Vec<efi::Guid> },
+    /// EFI_FIRMWARE_VOLUME_EXT_ENTRY_GUID_TYPE: data in the format identified by `format_type`.
+    Guid { format_type: efi::Guid, data: &'a [u8] },
+    /// EFI_FIRMWARE_VOLUME_EXT_ENTRY_USED_SIZE_TYPE: the number of bytes in the FV that are in use.
+    UsedSize(u32),
+    /// An entry of an unknown type. `data` is the entry contents after the entry header.
+    Unknown { entry_type: u16, data: &'a [u8] },
+}
+
+#[cfg(feature = "alloc")]
+impl<'a> FvExtEntry<'a> {
+    // Parses the entry at the start of buffer, returning the entry and its size.
+    fn parse(buffer: &'a [u8]) -> Result<(Self, usize), efi::Status> {
+        let header_size = mem::size_of::<fv::ExtEntryHeader>();
+        if buffer.len() < header_size {
+            Err(efi::Status::VOLUME_CORRUPTED)?;
+        }
+        let entry_size = u16::from_le_bytes(buffer[..2].try_into().unwrap()) as usize;
+        let entry_type = u16::from_le_bytes(buffer[2..4].try_into().unwrap());
+        if entry_size < header_size || entry_size > buffer.len() {
+            Err(efi::Status::VOLUME_CORRUPTED)?;
+        }
+        let data = &buffer[header_size..entry_size];
+
+        let guid_size = mem::size_of::<efi::Guid>();
+        let entry = match entry_type {
+            fv::ext_entry_type::OEM_TYPE => {
+                if data.len() < mem::size_of::<u32>() || (data.len() - mem::size_of::<u32>()) % guid_size != 0 {
+                    Err(efi::Status::VOLUME_CORRUPTED)?;
+                }
+                let type_mask = u32::from_le_bytes(data[..4].try_into().unwrap());
+                let types =
+                    data[4..].chunks_exact(guid_size).map(|x| efi::Guid::from_bytes(x.try_into().unwrap())).collect();
+                FvExtEntry::Oem { type_mask, types }
+            }
+            fv::ext_entry_type::GUID_TYPE => {
+                if data.len() < guid_size {
+                    Err(efi::Status::VOLUME_CORRUPTED)?;
+                }
+                let format_type = efi::Guid::from_bytes(data[..guid_size].try_into().unwrap());
+                FvExtEntry::Guid { format_type, data: &data[guid_size..] }
+            }
+            fv::ext_entry_type::USED_SIZE_TYPE => {
+                if data.len() < mem::size_of::<u32>() {
+                    Err(efi::Status::VOLUME_CORRUPTED)?;
+                }
+                FvExtEntry::UsedSize(u32::from_le_bytes(data[..4].try_into().unwrap()))
+            }
+            entry_type => FvExtEntry::Unknown { entry_type, data },
+        };
+        Ok((entry, entry_size))
+    }
+}
+
 impl<'a> fmt::Debug for FirmwareVolumeExtHeader<'a> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("FirmwareVolumeExtHeader")
@@ -120,6 +511,10 @@ impl<'a> fmt::Debug for FirmwareVolumeExtHeader<'a> {
 ///
 /// Provides access to firmware volume contents.
 ///
+/// A FirmwareVolume, and the [`File`]s and [`Section`]s obtained from it, only borrow or own plain data, so they are
+/// `Send` and `Sync` and can be used from several threads at once. Use [`FirmwareVolumeOwned`] to share a volume
+/// between threads without borrowing its buffer.
+///
 /// ## Example
 ///```
 /// # use std::{env, fs, path::Path, error::Error};
@@ -132,175 +527,294 @@ impl<'a> fmt::Debug for FirmwareVolumeExtHeader<'a> {
 /// # Ok(())
 /// # }
 ///```
+#[cfg(feature = "alloc")]
 #[derive(Clone)]
 pub struct FirmwareVolume<'a> {
     data: &'a [u8],
     attributes: EfiFvbAttributes2,
-    block_map: Vec<fv::BlockMapEntry>,
+    // The block map entries, without the terminating entry.
+    block_map: &'a [u8],
     ext_header: Option<FirmwareVolumeExtHeader<'a>>,
     data_offset: usize,
     erase_byte: u8,
+    file_system: FfsFileSystem,
+    options: ParseOptions,
 }
 
+#[cfg(feature = "alloc")]
 impl<'a> FirmwareVolume<'a> {
     /// Instantiate a new FirmwareVolume.
     ///
     /// Contents of the FirmwareVolume will be cached in this instance.
     pub fn new(buffer: &'a [u8]) -> Result<Self, efi::Status> {
-        //buffer must be large enough to hold the header structure.
-        if buffer.len() < mem::size_of::<fv::Header>() {
-            Err(efi::Status::INVALID_PARAMETER)?;
-        }
-
-        //Safety: buffer is large enough to contain the header, so can cast to a ref.
-        let fv_header = unsafe { &*(buffer.as_ptr() as *const fv::Header) };
-
-        // signature: must be ASCII '_FVH'
-        if fv_header.signature != u32::from_le_bytes(*b"_FVH") {
-            Err(efi::Status::VOLUME_CORRUPTED)?;
-        }
-
-        // header_length: must be large enough to hold the header.
-        if (fv_header.header_length as usize) < mem::size_of::<fv::Header>() {
-            Err(efi::Status::VOLUME_CORRUPTED)?;
-        }
-
-        // header_length: buffer must be large enough to hold the header.
-        if (fv_header.header_length as usize) > buffer.len() {
-            Err(efi::Status::VOLUME_CORRUPTED)?;
-        }
-
-        // checksum: fv header must sum to zero (and must be multiple of 2 bytes)
-        if fv_header.header_length & 0x01 != 0 {
-            Err(efi::Status::VOLUME_CORRUPTED)?;
-        }
-
-        let header_slice = &buffer[..fv_header.header_length as usize];
-        let sum: Wrapping<u16> =
-            header_slice.chunks_exact(2).map(|x| Wrapping(u16::from_le_bytes(x.try_into().unwrap()))).sum();
-
-        if sum != Wrapping(0u16) {
-            Err(efi::Status::VOLUME_CORRUPTED)?;
-        }
+        Self::new_with_options(buffer, &ParseOptions::default())
+    }
 
-        // revision: must be at least 2. Assumes that if later specs bump the rev they will maintain
-        // backwards compat with existing header definition.
-        if fv_header.revision < 2 {
-            Err(efi::Status::VOLUME_CORRUPTED)?;
-        }
+    /// Instantiate a new FirmwareVolume using the given parse options.
+    ///
+    /// The options are also used to parse the files returned by [`FirmwareVolume::file_iter()`].
+    pub fn new_with_options(buffer: &'a [u8], options: &ParseOptions) -> Result<Self, efi::Status> {
+        Self::new_with_diagnostics(buffer, options, &mut |_| ())
+    }
 
-        // file_system_guid: must be EFI_FIRMWARE_FILE_SYSTEM2_GUID or EFI_FIRMWARE_FILE_SYSTEM3_GUID.
-        if fv_header.file_system_guid != ffs::guid::EFI_FIRMWARE_FILE_SYSTEM2_GUID
-            && fv_header.file_system_guid != ffs::guid::EFI_FIRMWARE_FILE_SYSTEM3_GUID
-        {
-            Err(efi::Status::INVALID_PARAMETER)?;
-        }
+    /// Instantiate a new FirmwareVolume using the given parse options.
+    ///
+    /// Behaves like [`FirmwareVolume::new_with_options`], but returns an error that describes why the firmware volume
+    /// is malformed.
+    ///
+    /// ## Example
+    ///```
+    /// use mu_pi::{fw_fs::FirmwareVolume, parse::ParseOptions};
+    ///
+    /// if let Err(err) = FirmwareVolume::try_new(&[0u8; 0x100], &ParseOptions::default()) {
+    ///   println!("malformed firmware volume: {err}");
+    /// }
+    ///```
+    pub fn try_new(buffer: &'a [u8], options: &ParseOptions) -> Result<Self, FwFsError> {
+        Self::parse(buffer, buffer.len() as u64, options, &mut |_| ())
+    }
 
-        // fv_length: must be large enough to hold the header.
-        if fv_header.fv_length < fv_header.header_length as u64 {
-            Err(efi::Status::VOLUME_CORRUPTED)?;
-        }
+    /// Instantiate a new FirmwareVolume using the given parse options, reporting non-fatal oddities in the firmware
+    /// volume header to `diagnostics`.
+    ///
+    /// Use [`FirmwareVolume::file_iter_with_diagnostics()`] to also receive diagnostics for the files.
+    pub fn new_with_diagnostics(
+        buffer: &'a [u8],
+        options: &ParseOptions,
+        diagnostics: &mut dyn FnMut(Diagnostic),
+    ) -> Result<Self, efi::Status> {
+        Ok(Self::parse(buffer, buffer.len() as u64, options, diagnostics)?)
+    }
 
-        // fv_length: must be less than or equal to fv_data buffer length
-        if fv_header.fv_length > buffer.len() as u64 {
-            Err(efi::Status::VOLUME_CORRUPTED)?;
-        }
+    /// Instantiate a new FirmwareVolume, validating its header at the given level.
+    ///
+    /// [`ValidationLevel::Structural`] skips the header checksum and block map entry checks, which is intended for
+    /// trusted firmware volumes that are instantiated repeatedly, such as in a dispatcher loop. The structure of the
+    /// header is always validated, so the returned FirmwareVolume never reads outside of `buffer`; the skipped checks
+    /// can be made later with [`FirmwareVolume::verify_header`]. Use [`FirmwareVolume::new_with_options`] to also set
+    /// the other parse options.
+    ///
+    /// ## Example
+    ///```
+    /// # use std::{env, fs, path::Path, error::Error};
+    /// use mu_pi::{fw_fs::FirmwareVolume, parse::ValidationLevel};
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// # let root = Path::new(&env::var("CARGO_MANIFEST_DIR")?).join("test_resources");
+    /// # let fv_bytes = fs::read(root.join("DXEFV.Fv"))?;
+    /// let fv = FirmwareVolume::new_with_validation(&fv_bytes, ValidationLevel::Structural)
+    ///     .expect("Firmware Volume Corrupt");
+    /// assert_eq!(fv.verify_header(), Ok(()));
+    /// # Ok(())
+    /// # }
+    ///```
+    pub fn new_with_validation(buffer: &'a [u8], validation: ValidationLevel) -> Result<Self, efi::Status> {
+        Self::new_with_options(buffer, &ParseOptions { validation, ..Default::default() })
+    }
 
-        //ext_header_offset: must be inside the fv
-        if fv_header.ext_header_offset as u64 > fv_header.fv_length {
-            Err(efi::Status::VOLUME_CORRUPTED)?;
-        }
+    /// Returns an iterator of the firmware volumes found in `buffer`, such as a flash device image, along with their
+    /// offsets in the buffer.
+    ///
+    /// Each 8-byte aligned offset followed by the `_FVH` signature is a candidate, and is returned if it holds a valid
+    /// firmware volume; other candidates are ignored. The scan resumes after the end of each firmware volume found, so
+    /// firmware volumes nested in the files of another one are not returned (see
+    /// [`FirmwareVolume::all_files_recursive`]).
+    ///
+    /// ## Example
+    ///```
+    /// # use std::{env, fs, path::Path, error::Error};
+    /// use mu_pi::fw_fs::FirmwareVolume;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// # let root = Path::new(&env::var("CARGO_MANIFEST_DIR")?).join("test_resources");
+    /// # let flash_image = fs::read(root.join("FVMAIN_COMPACT.Fv"))?;
+    /// for (offset, fv) in FirmwareVolume::find_all(&flash_image) {
+    ///   println!("{:#x}: FV of {:#x} bytes", offset, fv.size());
+    /// }
+    /// # Ok(())
+    /// # }
+    ///```
+    pub fn find_all(buffer: &'a [u8]) -> impl Iterator<Item = (usize, FirmwareVolume<'a>)> {
+        Self::find_all_with_options(buffer, &ParseOptions::default())
+    }
 
-        //if ext_header is present, its size must fit inside the FV.
-        let ext_header = {
-            if fv_header.ext_header_offset != 0 {
-                let ext_header_offset = fv_header.ext_header_offset as usize;
-                if ext_header_offset + mem::size_of::<fv::ExtHeader>() > buffer.len() {
-                    Err(efi::Status::VOLUME_CORRUPTED)?;
+    /// Returns an iterator of the firmware volumes found in `buffer`, instantiated with the given parse options.
+    ///
+    /// See [`FirmwareVolume::find_all`].
+    pub fn find_all_with_options(
+        buffer: &'a [u8],
+        options: &ParseOptions,
+    ) -> impl Iterator<Item = (usize, FirmwareVolume<'a>)> {
+        use fv::header_offset::{FV_LENGTH, SIGNATURE};
+
+        let options = *options;
+        let mut offset = 0;
+        core::iter::from_fn(move || {
+            while offset + mem::size_of::<fv::Header>() <= buffer.len() {
+                let candidate = offset;
+                offset += 8;
+                if buffer[candidate + SIGNATURE..candidate + SIGNATURE + 4] != *b"_FVH" {
+                    continue;
                 }
-
-                //Safety: previous check ensures that fv_data is large enough to contain the ext_header
-                let ext_header = unsafe { &*(buffer[ext_header_offset..].as_ptr() as *const fv::ExtHeader) };
-                let ext_header_end = ext_header_offset + ext_header.ext_header_size as usize;
-                if ext_header_end > buffer.len() {
-                    Err(efi::Status::VOLUME_CORRUPTED)?;
+                let fv_length = &buffer[candidate + FV_LENGTH..candidate + FV_LENGTH + 8];
+                let fv_length = u64::from_le_bytes(fv_length.try_into().unwrap());
+                let Some(fv_buffer) = usize::try_from(fv_length)
+                    .ok()
+                    .and_then(|fv_length| buffer.get(candidate..candidate.checked_add(fv_length)?))
+                else {
+                    continue;
+                };
+                if let Ok(fv) = Self::new_with_options(fv_buffer, &options) {
+                    offset = align_up((candidate + fv_buffer.len()) as u64, 8) as usize;
+                    return Some((candidate, fv));
                 }
-                Some(FirmwareVolumeExtHeader { header: *ext_header, data: &buffer[ext_header_offset..ext_header_end] })
-            } else {
-                None
             }
-        };
+            None
+        })
+    }
 
-        //block map must fit within the fv header (which is checked above to guarantee it is within the fv_data buffer).
-        let block_map = &buffer[mem::size_of::<fv::Header>()..fv_header.header_length as usize];
+    /// Recomputes the header checksum of the firmware volume at the start of `buffer`, such as after its header was
+    /// edited.
+    ///
+    /// Returns `VOLUME_CORRUPTED` if `buffer` does not hold a header of the length given by its header_length field,
+    /// or if that length is odd.
+    pub fn recompute_checksum(buffer: &mut [u8]) -> Result<(), efi::Status> {
+        use fv::header_offset::{CHECKSUM, HEADER_LENGTH};
 
-        //block map should be a multiple of 8 in size
-        if block_map.len() & 0x7 != 0 {
+        if buffer.len() < mem::size_of::<fv::Header>() {
             Err(efi::Status::VOLUME_CORRUPTED)?;
         }
-
-        let mut block_map = block_map
-            .chunks_exact(8)
-            .map(|x| fv::BlockMapEntry {
-                num_blocks: u32::from_le_bytes(x[..4].try_into().unwrap()),
-                length: u32::from_le_bytes(x[4..].try_into().unwrap()),
-            })
-            .collect::<Vec<_>>();
-
-        //block map should terminate with zero entry
-        if block_map.last() != Some(&fv::BlockMapEntry { num_blocks: 0, length: 0 }) {
+        let header_length = u16::from_le_bytes([buffer[HEADER_LENGTH], buffer[HEADER_LENGTH + 1]]) as usize;
+        if header_length < mem::size_of::<fv::Header>() || header_length > buffer.len() || header_length % 2 != 0 {
             Err(efi::Status::VOLUME_CORRUPTED)?;
         }
+        buffer[CHECKSUM..CHECKSUM + 2].fill(0);
+        let checksum = checksum::compute16(&buffer[..header_length]);
+        buffer[CHECKSUM..CHECKSUM + 2].copy_from_slice(&checksum.to_le_bytes());
+        Ok(())
+    }
 
-        //remove the terminator.
-        block_map.pop();
-
-        //thre must be at least one valid entry in the block map.
-        if block_map.is_empty() {
+    /// Repairs the header of the firmware volume at the start of `buffer` after external edits, and parses the result.
+    ///
+    /// The last entry of the block map, as given by the header_length field, is made the terminator, the fv_length
+    /// field is set to the size described by the other entries, and the header checksum is recomputed. The repaired
+    /// firmware volume must then pass the checks of [`FirmwareVolume::new`].
+    ///
+    /// Returns `VOLUME_CORRUPTED` if `buffer` does not start with a firmware volume header (with the `_FVH` signature
+    /// and a header_length that leaves room for a block map of at least one entry), or if an entry of the block map is
+    /// zero, as well as any error of [`FirmwareVolume::new`].
+    ///
+    /// ## Example
+    ///```
+    /// use mu_pi::fw_fs::{builder::blank_volume, fv::BlockMapEntry, FfsFileSystem, FirmwareVolume, FvbAttributes2};
+    ///
+    /// let block_map = [BlockMapEntry { num_blocks: 2, length: 0x1000 }];
+    /// let mut buffer = blank_volume(FfsFileSystem::Ffs2, &block_map, FvbAttributes2::ERASE_POLARITY).unwrap();
+    /// // grow the volume by a block, without updating the rest of the header.
+    /// buffer[56] = 3;
+    /// buffer.resize(0x3000, 0xff);
+    /// assert!(FirmwareVolume::new(&buffer).is_err());
+    ///
+    /// let fv = FirmwareVolume::repair(&mut buffer).unwrap();
+    /// assert_eq!(fv.size(), 0x3000);
+    ///```
+    pub fn repair(buffer: &mut [u8]) -> Result<FirmwareVolume<'_>, efi::Status> {
+        use fv::header_offset::{FV_LENGTH, HEADER_LENGTH, SIGNATURE};
+
+        let entry_size = mem::size_of::<fv::BlockMapEntry>();
+        if buffer.len() < mem::size_of::<fv::Header>() || buffer[SIGNATURE..SIGNATURE + 4] != *b"_FVH" {
             Err(efi::Status::VOLUME_CORRUPTED)?;
         }
-
-        //other entries in block map must be non-zero.
-        if block_map.iter().any(|x| x == &fv::BlockMapEntry { num_blocks: 0, length: 0 }) {
+        let header_length = u16::from_le_bytes([buffer[HEADER_LENGTH], buffer[HEADER_LENGTH + 1]]) as usize;
+        let block_map_length = header_length.wrapping_sub(mem::size_of::<fv::Header>());
+        if header_length > buffer.len() || block_map_length < 2 * entry_size || block_map_length % entry_size != 0 {
             Err(efi::Status::VOLUME_CORRUPTED)?;
         }
 
-        let data_offset = {
-            if let Some(ext_header) = &ext_header {
-                // if ext header exists, then data starts after ext header
-                fv_header.ext_header_offset as usize + ext_header.header.ext_header_size as usize
-            } else {
-                // otherwise data starts after the fv_header.
-                fv_header.header_length as usize
+        // the block map is validated before anything is written, so buffer is left unchanged on error.
+        let terminator_offset = header_length - entry_size;
+        let mut fv_length = 0u64;
+        for entry in buffer[mem::size_of::<fv::Header>()..terminator_offset].chunks_exact(entry_size) {
+            let num_blocks = u32::from_le_bytes(entry[..4].try_into().unwrap());
+            let length = u32::from_le_bytes(entry[4..].try_into().unwrap());
+            if num_blocks == 0 || length == 0 {
+                Err(efi::Status::VOLUME_CORRUPTED)?;
             }
-        };
+            fv_length =
+                fv_length.checked_add(num_blocks as u64 * length as u64).ok_or(efi::Status::VOLUME_CORRUPTED)?;
+        }
 
-        let data_offset = align_up(data_offset as u64, 8) as usize;
-        let erase_byte = if fv_header.attributes & Fvb2RawAttributes::ERASE_POLARITY != 0 { 0xff } else { 0 };
+        buffer[terminator_offset..header_length].fill(0);
+        buffer[FV_LENGTH..FV_LENGTH + 8].copy_from_slice(&fv_length.to_le_bytes());
+        Self::recompute_checksum(buffer)?;
+        FirmwareVolume::new(buffer)
+    }
 
-        Ok(Self { data: buffer, attributes: fv_header.attributes, block_map, ext_header, data_offset, erase_byte })
+    // Parses the FV headers in buffer. storage_size is the size of the storage holding the FV, which may be larger
+    // than buffer when buffer only holds the headers (see storage::StorageFirmwareVolume).
+    fn parse(
+        buffer: &'a [u8],
+        storage_size: u64,
+        options: &ParseOptions,
+        diagnostics: &mut dyn FnMut(Diagnostic),
+    ) -> Result<Self, FwFsError> {
+        let raw = raw::RawFirmwareVolume::parse(buffer, storage_size, options, diagnostics)?;
+        Ok(Self {
+            data: raw.data,
+            attributes: raw.attributes,
+            block_map: raw.block_map,
+            ext_header: raw.ext_header,
+            data_offset: raw.data_offset,
+            erase_byte: raw.erase_byte,
+            file_system: raw.file_system,
+            options: raw.options,
+        })
     }
 
     /// Instantiate a new FirmwareVolume from a base address.
     ///
+    /// The length of the firmware volume is read from its header, and the firmware volume is then validated in the same
+    /// way as by [`FirmwareVolume::new`].
+    ///
     /// ## Safety
-    /// Caller must ensure that base_address is the address of the start of a firmware volume.
+    /// Caller must ensure that base_address is the address of the start of a firmware volume, and that the memory
+    /// described by its header is readable and is not modified for the lifetime of the returned FirmwareVolume.
     ///
     /// Contents of the FirmwareVolume will be cached in this instance.
-    pub unsafe fn new_from_address(base_address: u64) -> Result<Self, efi::Status> {
+    pub unsafe fn new_from_address(base_address: efi::PhysicalAddress) -> Result<Self, efi::Status> {
+        Self::new_from_address_with_options(base_address, &ParseOptions::default())
+    }
+
+    /// Instantiate a new FirmwareVolume from a base address using the given parse options.
+    ///
+    /// ## Safety
+    /// See [`FirmwareVolume::new_from_address`].
+    pub unsafe fn new_from_address_with_options(
+        base_address: efi::PhysicalAddress,
+        options: &ParseOptions,
+    ) -> Result<Self, efi::Status> {
+        if base_address == 0 {
+            Err(efi::Status::INVALID_PARAMETER)?;
+        }
         let fv_header = &*(base_address as *const fv::Header);
         if fv_header.signature != u32::from_le_bytes(*b"_FVH") {
             // base_address is not the start of a firmware volume.
             return Err(efi::Status::VOLUME_CORRUPTED);
         }
 
-        let fv_buffer = slice::from_raw_parts(base_address as *const u8, fv_header.fv_length as usize);
-        Self::new(fv_buffer)
+        let fv_length = usize::try_from(fv_header.fv_length).map_err(|_| efi::Status::VOLUME_CORRUPTED)?;
+        let fv_buffer = slice::from_raw_parts(base_address as *const u8, fv_length);
+        Self::new_with_options(fv_buffer, options)
     }
 
-    /// Returns the block map for the FV
-    pub fn block_map(&self) -> &Vec<fv::BlockMapEntry> {
-        &self.block_map
+    /// Verifies the header checksum and the block map entries of the FV.
+    ///
+    /// [`FirmwareVolume::new`] already verifies them unless the FV was parsed with [`ValidationLevel::Structural`].
+    pub fn verify_header(&self) -> Result<(), efi::Status> {
+        Ok(raw::verify_fv_header(self.data, self.block_map)?)
+    }
+
+    /// Returns an iterator of the block map entries of the FV, without the terminating entry.
+    pub fn block_map(&self) -> impl Iterator<Item = fv::BlockMapEntry> + 'a {
+        raw::block_map_entries(self.block_map)
     }
 
     /// Returns the GUID name of the FV, if any.
@@ -308,39 +822,108 @@ impl<'a> FirmwareVolume<'a> {
         self.ext_header.as_ref().map(|ext_header| ext_header.header.fv_name)
     }
 
+    /// Returns the Firmware File System version of the FV, as given by its file system GUID.
+    pub fn file_system(&self) -> FfsFileSystem {
+        self.file_system
+    }
+
+    /// Returns the extended header of this FV, if present.
+    pub fn ext_header(&self) -> Option<&FirmwareVolumeExtHeader<'a>> {
+        self.ext_header.as_ref()
+    }
+
     /// Returns an iterator of the files in this FV.
+    ///
+    /// A file that fails to parse is returned as an error (typically `VOLUME_CORRUPTED`), and ends the iteration since
+    /// the location of the next file cannot be trusted. The iteration ends without error only at the free space of the
+    /// FV or at its end.
     pub fn file_iter(&self) -> impl Iterator<Item = Result<File<'a>, efi::Status>> {
-        FvFileIterator::new(&self.data[self.data_offset..], self.erase_byte)
+        self.try_file_iter().map(|file| Ok(file?))
     }
 
-    /// returns the (linear block offset from FV base, block_size, remaining_blocks) given an LBA.
-    pub fn lba_info(&self, lba: u32) -> Result<(u32, u32, u32), efi::Status> {
-        let block_map = self.block_map();
+    /// Returns an iterator of the files in this FV.
+    ///
+    /// Behaves like [`FirmwareVolume::file_iter`], but returns errors that describe why a file is malformed.
+    pub fn try_file_iter(&self) -> impl Iterator<Item = Result<File<'a>, FwFsError>> {
+        FvFileIterator::new(&self.data[self.data_offset..], self.erase_byte, self.file_system, self.options, None)
+    }
 
-        let mut total_blocks = 0;
-        let mut offset = 0;
-        let mut block_size = 0;
+    /// Returns an iterator of the files in this FV that reports non-fatal oddities in the files to `diagnostics`.
+    pub fn file_iter_with_diagnostics<'s>(
+        &self,
+        diagnostics: &'s mut dyn FnMut(Diagnostic),
+    ) -> impl Iterator<Item = Result<File<'a>, efi::Status>> + 's
+    where
+        'a: 's,
+    {
+        FvFileIterator::new(
+            &self.data[self.data_offset..],
+            self.erase_byte,
+            self.file_system,
+            self.options,
+            Some(diagnostics),
+        )
+        .map(|file| Ok(file?))
+    }
+
+    /// Returns an iterator of the files of the given type in this FV. [`FfsFileType::All`] matches any file type.
+    ///
+    /// Errors encountered while parsing the files are returned as-is.
+    pub fn files_of_type(&self, file_type: FfsFileType) -> impl Iterator<Item = Result<File<'a>, efi::Status>> {
+        self.file_iter().filter(move |file| match file {
+            Ok(file) => file_type == FfsFileType::All || file.file_type() == Some(file_type),
+            Err(_) => true,
+        })
+    }
 
-        for entry in block_map {
-            total_blocks += entry.num_blocks;
-            block_size = entry.length;
-            if lba < total_blocks {
-                break;
+    /// returns the (linear block offset from FV base, block_size, remaining_blocks) given an LBA.
+    ///
+    /// `remaining_blocks` counts the blocks of the same size starting at `lba`, as returned by the FVB protocol
+    /// GetBlockSize() service.
+    pub fn lba_info(&self, lba: u32) -> Result<(u32, u32, u32), efi::Status> {
+        let lba = lba as u64;
+        for (first_lba, offset, entry) in self.block_map_runs() {
+            let end_lba = first_lba + entry.num_blocks as u64;
+            if lba < end_lba {
+                let offset = offset + (lba - first_lba) * entry.length as u64;
+                let offset = u32::try_from(offset).map_err(|_| efi::Status::INVALID_PARAMETER)?;
+                return Ok((offset, entry.length, (end_lba - lba) as u32));
             }
-            offset += entry.num_blocks * entry.length;
         }
+        Err(efi::Status::INVALID_PARAMETER) //lba out of range.
+    }
 
-        if lba >= total_blocks {
-            return Err(efi::Status::INVALID_PARAMETER); //lba out of range.
-        }
+    /// Returns the number of logical blocks in the FV, from its block map.
+    pub fn lba_count(&self) -> u64 {
+        self.block_map().map(|entry| entry.num_blocks as u64).sum()
+    }
 
-        let remaining_blocks = total_blocks - lba;
-        Ok((offset + lba * block_size, block_size, remaining_blocks))
+    /// Returns the total size in bytes of the logical blocks in the block map of the FV.
+    pub fn total_block_size(&self) -> u64 {
+        self.block_map().map(|entry| entry.num_blocks as u64 * entry.length as u64).sum()
+    }
+
+    /// Returns an iterator of the logical blocks of the FV, as `(lba, offset from FV base, block size)` tuples.
+    pub fn lba_iter(&self) -> impl Iterator<Item = (u64, u64, u32)> + 'a {
+        self.block_map_runs().flat_map(|(first_lba, offset, entry)| {
+            (0..entry.num_blocks as u64)
+                .map(move |index| (first_lba + index, offset + index * entry.length as u64, entry.length))
+        })
+    }
+
+    // Returns the block map entries with the LBA and offset of their first block.
+    fn block_map_runs(&self) -> impl Iterator<Item = (u64, u64, fv::BlockMapEntry)> + 'a {
+        self.block_map().scan((0u64, 0u64), |(lba, offset), entry| {
+            let run = (*lba, *offset, entry);
+            *lba += entry.num_blocks as u64;
+            *offset += entry.num_blocks as u64 * entry.length as u64;
+            Some(run)
+        })
     }
 
     /// Returns the attributes for the FirmwareVolume
-    pub fn attributes(&self) -> EfiFvbAttributes2 {
-        self.attributes
+    pub fn attributes(&self) -> FvbAttributes2 {
+        FvbAttributes2::from_bits_retain(self.attributes)
     }
 
     /// Returns the size in bytes of the FV data + header.
@@ -352,16 +935,248 @@ impl<'a> FirmwareVolume<'a> {
     pub fn data(&self) -> &[u8] {
         self.data
     }
+
+    /// Returns the number of bytes of the FV that are in use: the offset of the end of the last file, or the length of
+    /// the FV header if the FV holds no files.
+    ///
+    /// Unlike [`FirmwareVolumeExtHeader::used_size`], which reports what the tool that built the FV recorded, this is
+    /// computed from the files of the FV.
+    #[cfg(feature = "alloc")]
+    pub fn used_size(&self) -> Result<u64, efi::Status> {
+        Ok(self.walk_free_blocks(&mut |_| ())? as u64)
+    }
+
+    /// Returns the total number of bytes of the FV that are not covered by a file.
+    ///
+    /// This includes the space after the last file, the alignment padding between files, and files skipped by
+    /// [`ParseOptions::skip_invalid_files`] (such as deleted files). Pad files are files, so their space is not free.
+    pub fn free_space(&self) -> Result<u64, efi::Status> {
+        let mut free_space = 0;
+        self.walk_free_blocks(&mut |block| free_space += block.len() as u64)?;
+        Ok(free_space)
+    }
+
+    /// Returns the length of the largest contiguous region of the FV that is not covered by a file, which bounds the
+    /// size of a file that can be added to the FV without moving the existing files.
+    pub fn largest_free_block(&self) -> Result<u64, efi::Status> {
+        let mut largest_free_block = 0;
+        self.walk_free_blocks(&mut |block| largest_free_block = largest_free_block.max(block.len() as u64))?;
+        Ok(largest_free_block)
+    }
+
+    // Passes each region of the FV that is not covered by a file (the gaps before and between files, and the space
+    // after the last file) to free_block, and returns the offset of the end of the last file.
+    fn walk_free_blocks(&self, free_block: &mut dyn FnMut(Range<usize>)) -> Result<usize, FwFsError> {
+        let base = self.data.as_ptr() as usize;
+        let mut free_start = self.data_offset;
+        for file in self.try_file_iter() {
+            let file = file?;
+            let offset = file.data().as_ptr() as usize - base;
+            if offset > free_start {
+                free_block(free_start..offset);
+            }
+            free_start = offset + file.size() as usize;
+        }
+        if self.data.len() > free_start {
+            free_block(free_start..self.data.len());
+        }
+        Ok(free_start)
+    }
+
+    /// Returns the names of the files listed in the PEI apriori file (PEI_APRIORI_FILE_NAME_GUID), in dispatch order,
+    /// or `None` if the FV has no PEI apriori file.
+    pub fn pei_apriori_file_names(&self) -> Result<Option<Vec<efi::Guid>>, efi::Status> {
+        self.apriori_file_names(ffs::guid::PEI_APRIORI_FILE_NAME_GUID)
+    }
+
+    /// Returns the names of the files listed in the DXE apriori file (EFI_APRIORI_GUID), in dispatch order, or `None`
+    /// if the FV has no DXE apriori file.
+    pub fn dxe_apriori_file_names(&self) -> Result<Option<Vec<efi::Guid>>, efi::Status> {
+        self.apriori_file_names(ffs::guid::EFI_APRIORI_GUID)
+    }
+
+    /// Returns the file with the given name, or `None` if the FV has no such file.
+    ///
+    /// Files that follow a file that fails to parse are not searched.
+    pub fn file_by_name(&self, name: efi::Guid) -> Option<File<'a>> {
+        self.file_by_name_and_type_raw(name, FfsFileRawType::ALL)
+    }
+
+    /// Returns the file with the given name and raw file type, or `None` if the FV has no such file.
+    ///
+    /// [`FfsFileRawType::ALL`] matches any file type.
+    ///
+    /// Files that follow a file that fails to parse are not searched.
+    pub fn file_by_name_and_type_raw(&self, name: efi::Guid, file_type: EfiFvFileType) -> Option<File<'a>> {
+        self.file_iter()
+            .map_while(Result::ok)
+            .find(|file| file.name() == name && (file_type == FfsFileRawType::ALL || file.file_type_raw() == file_type))
+    }
+
+    /// Visits every file in this FV and in the FVs nested in it, depth-first.
+    ///
+    /// `visitor` is called with each file and its nesting depth (0 for the files of this FV). After a
+    /// FIRMWARE_VOLUME_IMAGE file is visited, each FIRMWARE_VOLUME_IMAGE section in it, including those in encapsulation
+    /// sections expanded by `extractor`, is instantiated as an FV with the parse options of this FV and traversed in
+    /// turn. The first error returned by `visitor` or encountered while parsing stops the traversal and is returned.
+    ///
+    /// Files in nested FVs borrow the buffers of the sections that contain them, so they are only valid for the duration
+    /// of the call to `visitor`.
+    pub fn all_files_recursive(
+        &self,
+        extractor: &dyn SectionExtractor,
+        visitor: &mut dyn FnMut(&File, usize) -> Result<(), efi::Status>,
+    ) -> Result<(), efi::Status> {
+        self.all_files_recursive_with_limits(extractor, ExtractionLimits::default(), visitor)
+    }
+
+    /// Visits every file in this FV and in the FVs nested in it, depth-first, as [`Self::all_files_recursive`] does,
+    /// but within the given limits.
+    ///
+    /// FVs nested deeper than [`ExtractionLimits::max_depth`] fail the traversal with `VOLUME_CORRUPTED`, and the
+    /// sections of each file are extracted within `limits`.
+    pub fn all_files_recursive_with_limits(
+        &self,
+        extractor: &dyn SectionExtractor,
+        limits: ExtractionLimits,
+        visitor: &mut dyn FnMut(&File, usize) -> Result<(), efi::Status>,
+    ) -> Result<(), efi::Status> {
+        self.visit_files_recursive(extractor, limits, visitor, 0)
+    }
+
+    fn visit_files_recursive(
+        &self,
+        extractor: &dyn SectionExtractor,
+        limits: ExtractionLimits,
+        visitor: &mut dyn FnMut(&File, usize) -> Result<(), efi::Status>,
+        depth: usize,
+    ) -> Result<(), efi::Status> {
+        // extracted FVs can nest without bound in a malicious image.
+        if depth > limits.max_depth {
+            Err(efi::Status::VOLUME_CORRUPTED)?;
+        }
+        for file in self.file_iter() {
+            let file = file?;
+            visitor(&file, depth)?;
+            if file.file_type() != Some(FfsFileType::FirmwareVolumeImage) {
+                continue;
+            }
+            let sections = file
+                .section_iter_with_limits(extractor, limits)
+                .filter(|section| is_section_of_type(section, FfsSectionType::FirmwareVolumeImage));
+            for section in sections {
+                let section = section?;
+                section.firmware_volume_with_options(&self.options)?.visit_files_recursive(
+                    extractor,
+                    limits,
+                    visitor,
+                    depth + 1,
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    fn apriori_file_names(&self, apriori_file_name: efi::Guid) -> Result<Option<Vec<efi::Guid>>, efi::Status> {
+        for file in self.file_iter() {
+            let file = file?;
+            if file.name() == apriori_file_name {
+                return file.apriori_file_names().map(Some);
+            }
+        }
+        Ok(None)
+    }
+}
+
+// Keep the parsed types shareable between threads; adding a field that is not Send or Sync fails the build.
+#[cfg(feature = "alloc")]
+const _: () = {
+    const fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<FirmwareVolume<'static>>();
+    assert_send_sync::<FirmwareVolumeOwned>();
+    assert_send_sync::<File<'static>>();
+    assert_send_sync::<Section>();
+};
+
+/// A firmware volume that owns its buffer.
+///
+/// The buffer is validated when the FirmwareVolumeOwned is created, and [`FirmwareVolumeOwned::volume()`] gives
+/// access to its contents. As it holds no borrows, it can be wrapped in an `Arc` and shared between threads.
+///
+/// ## Example
+///```
+/// # use std::{env, fs, path::Path, error::Error};
+/// use mu_pi::fw_fs::FirmwareVolumeOwned;
+/// use std::{sync::Arc, thread};
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// # let root = Path::new(&env::var("CARGO_MANIFEST_DIR")?).join("test_resources");
+/// let fv = Arc::new(FirmwareVolumeOwned::new(fs::read(root.join("DXEFV.Fv"))?).expect("Firmware Volume Corrupt"));
+/// let worker = {
+///   let fv = fv.clone();
+///   thread::spawn(move || fv.volume().file_iter().count())
+/// };
+/// println!("{} files", worker.join().unwrap());
+/// # Ok(())
+/// # }
+///```
+#[cfg(feature = "alloc")]
+#[derive(Clone)]
+pub struct FirmwareVolumeOwned {
+    data: Box<[u8]>,
+    options: ParseOptions,
+}
+
+#[cfg(feature = "alloc")]
+impl FirmwareVolumeOwned {
+    /// Takes ownership of the given buffer and validates it as a firmware volume.
+    pub fn new(data: impl Into<Box<[u8]>>) -> Result<Self, efi::Status> {
+        Self::new_with_options(data, &ParseOptions::default())
+    }
+
+    /// Takes ownership of the given buffer and validates it as a firmware volume using the given parse options.
+    pub fn new_with_options(data: impl Into<Box<[u8]>>, options: &ParseOptions) -> Result<Self, efi::Status> {
+        let data = data.into();
+        FirmwareVolume::new_with_options(&data, options)?;
+        Ok(Self { data, options: *options })
+    }
+
+    /// Returns the firmware volume.
+    pub fn volume(&self) -> FirmwareVolume<'_> {
+        FirmwareVolume::new_with_options(&self.data, &self.options).expect("buffer was validated on creation")
+    }
+
+    /// Returns the buffer holding the firmware volume.
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Returns the buffer holding the firmware volume, releasing ownership of it.
+    pub fn into_data(self) -> Box<[u8]> {
+        self.data
+    }
 }
 
+#[cfg(feature = "alloc")]
+impl fmt::Debug for FirmwareVolumeOwned {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FirmwareVolumeOwned")
+            .field("options", &self.options)
+            .field("data.len()", &self.data.len())
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(feature = "alloc")]
 impl<'a> fmt::Debug for FirmwareVolume<'a> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("FirmwareVolume")
             .field("attributes", &self.attributes)
-            .field("block_map", &self.block_map)
+            .field("block_map", &self.block_map().collect::<Vec<_>>())
             .field("ext_header", &self.ext_header)
             .field("data_offset", &self.data_offset)
             .field("erase_byte", &self.erase_byte)
+            .field("file_system", &self.file_system)
+            .field("options", &self.options)
             .field("data.len()", &self.data.len())
             .finish_non_exhaustive()
     }
@@ -385,6 +1200,7 @@ impl<'a> fmt::Debug for FirmwareVolume<'a> {
 /// # Ok(())
 /// # }
 ///```
+#[cfg(feature = "alloc")]
 #[derive(Clone)]
 pub struct File<'a> {
     data: &'a [u8],
@@ -392,123 +1208,107 @@ pub struct File<'a> {
     file_type: u8,
     attributes: u8,
     header_size: usize,
+    tail_size: usize,
     size: u64,
+    state: u8,
+    erase_polarity: bool,
+    file_system: FfsFileSystem,
+    alignment: AlignmentPolicy,
+    deviations: Vec<Deviation>,
 }
 
+#[cfg(feature = "alloc")]
 impl<'a> File<'a> {
     /// Instantiates a new File by parsing the given buffer.
     ///
     /// The normal way to obtain a File instance would be through the [`FirmwareVolume::files()`] method, but
-    /// a constructor is provided here to enable independent instantiation of a file.
+    /// a constructor is provided here to enable independent instantiation of a file. Independently instantiated files
+    /// are parsed as FFS2/FFS3 files.
     pub fn new(buffer: &'a [u8]) -> Result<Self, efi::Status> {
-        // verify that buffer has enough storage for a file header.
-        if buffer.len() < mem::size_of::<file::Header>() {
-            Err(efi::Status::INVALID_PARAMETER)?;
-        }
+        Self::new_with_options(buffer, &ParseOptions::default())
+    }
 
-        //Safety: buffer is large enough to contain the header, so can cast to a ref.
-        let file_header = unsafe { &*(buffer.as_ptr() as *const file::Header) };
-
-        // determine size and data offset
-        let (header_size, size) = {
-            let header_size = mem::size_of::<file::Header>();
-            if (file_header.attributes & LARGE_FILE) == 0 {
-                //standard header with 24-bit size
-                let mut size_vec = file_header.size.to_vec();
-                size_vec.push(0);
-                let size = u32::from_le_bytes(size_vec.try_into().unwrap());
-                (header_size, size as u64)
-            } else {
-                //extended header with 64-bit size
-                let extended_size_length = mem::size_of::<u64>();
-                if buffer[header_size..].len() < extended_size_length {
-                    Err(efi::Status::VOLUME_CORRUPTED)?;
-                }
-                let size =
-                    u64::from_le_bytes(buffer[header_size..header_size + extended_size_length].try_into().unwrap());
-                (header_size + extended_size_length, size as u64)
-            }
-        };
+    /// Instantiates a new File by parsing the given buffer using the given parse options.
+    ///
+    /// Spec deviations tolerated under [`Strictness::Compatible`](crate::parse::Strictness::Compatible) are available
+    /// from [`File::deviations()`].
+    pub fn new_with_options(buffer: &'a [u8], options: &ParseOptions) -> Result<Self, efi::Status> {
+        Self::new_with_diagnostics(buffer, options, &mut |_| ())
+    }
 
-        // Verify that the total size of the file fits within the buffer.
-        if size as usize > buffer.len() {
-            Err(efi::Status::VOLUME_CORRUPTED)?;
-        }
+    /// Instantiates a new File by parsing the given buffer using the given parse options, reporting non-fatal
+    /// oddities in the file to `diagnostics`.
+    ///
+    /// Tolerated spec deviations are reported as [`Diagnostic::Deviation`] in addition to being available from
+    /// [`File::deviations()`].
+    pub fn new_with_diagnostics(
+        buffer: &'a [u8],
+        options: &ParseOptions,
+        diagnostics: &mut dyn FnMut(Diagnostic),
+    ) -> Result<Self, efi::Status> {
+        Ok(Self::parse(buffer, None, FfsFileSystem::Ffs3, options, diagnostics)?)
+    }
 
-        // Interpreting the state field requires knowledge of the EFI_FVB_ERASE_POLARITY from the FV header, which is not
-        // available here unless the constructor API is modified to specify it. So it is inferred based on the state of
-        // the reserved bits in the EFI_FFS_FILE_STATE which spec requires to be set to EFI_FVB_ERASE_POLARITY.
-        // This implementation does not support FV modification, so the only valid state is EFI_FILE_DATA_VALID.
-        if (file_header.state & 0x80) == 0 {
-            //erase polarity = 0. Verify DATA_VALID is set, and no higher-order bits are set.
-            if file_header.state & 0xFC != ffs::file::raw::state::DATA_VALID {
-                //file is not in EFI_FILE_DATA_VALID state.
-                Err(efi::Status::VOLUME_CORRUPTED)?;
-            }
-        } else {
-            //erase polarity = 1. Verify DATA_VALID is clear, and no higher-order bits are clear.
-            if (!file_header.state) & 0xFC != ffs::file::raw::state::DATA_VALID {
-                //file is not in EFI_FILE_DATA_VALID state.
-                Err(efi::Status::VOLUME_CORRUPTED)?;
-            }
-        }
+    /// Instantiates a new File by parsing the given buffer using the given parse options.
+    ///
+    /// Behaves like [`File::new_with_options`], but returns an error that describes why the file is malformed.
+    pub fn try_new(buffer: &'a [u8], options: &ParseOptions) -> Result<Self, FwFsError> {
+        Self::parse(buffer, None, FfsFileSystem::Ffs3, options, &mut |_| ())
+    }
 
-        //Verify the header checksum.
-        let header_sum: Wrapping<u8> = buffer[..header_size].iter().map(|&x| Wrapping(x)).sum();
-        // integrity_check_file and state are assumed to be zero for checksum, so subtract them here.
-        let header_sum = header_sum.wrapping_sub(&Wrapping(file_header.integrity_check_file));
-        let header_sum = header_sum.wrapping_sub(&Wrapping(file_header.state));
-        if header_sum != Wrapping(0u8) {
-            Err(efi::Status::VOLUME_CORRUPTED)?;
-        }
+    // Parses the file in buffer as a file of the given file system. When the erase polarity of the containing FV is
+    // not known, it is inferred from the file state.
+    fn parse(
+        buffer: &'a [u8],
+        erase_polarity: Option<bool>,
+        file_system: FfsFileSystem,
+        options: &ParseOptions,
+        diagnostics: &mut dyn FnMut(Diagnostic),
+    ) -> Result<Self, FwFsError> {
+        Ok(Self::from_raw(raw::RawFile::parse(buffer, erase_polarity, file_system, options, diagnostics)?))
+    }
 
-        //Verify the file data checksum.
-        if file_header.attributes & ffs::attributes::raw::CHECKSUM != 0 {
-            let data_sum: Wrapping<u8> = buffer[header_size..size as usize].iter().map(|&x| Wrapping(x)).sum();
-            if data_sum != Wrapping(0u8) {
-                Err(efi::Status::VOLUME_CORRUPTED)?;
-            }
-        } else {
-            // Verify that the checksum is initialized to 0xAA per spec requirements when CHECKSUM attribute is cleared.
-            if file_header.integrity_check_file != 0xAA {
-                Err(efi::Status::VOLUME_CORRUPTED)?;
-            }
+    fn from_raw(raw: raw::RawFile<'a>) -> Self {
+        Self {
+            data: raw.data,
+            name: raw.name,
+            file_type: raw.file_type,
+            attributes: raw.attributes,
+            header_size: raw.header_size,
+            tail_size: raw.tail_size,
+            size: raw.size,
+            state: raw.state,
+            erase_polarity: raw.erase_polarity,
+            file_system: raw.file_system,
+            alignment: raw.alignment,
+            deviations: raw.deviations().collect(),
         }
+    }
 
-        Ok(Self {
-            data: &buffer[..size as usize],
-            name: file_header.name,
-            file_type: file_header.file_type,
-            attributes: file_header.attributes,
-            header_size,
-            size,
-        })
+    /// Verifies the file header checksum and, when the CHECKSUM attribute is set, the file data checksum. For FFS1
+    /// files with a tail, the tail is verified as well.
+    ///
+    /// [`File::new`] already verifies the checksums unless the file was parsed with
+    /// [`ParseOptions::verify_checksums`] cleared.
+    pub fn verify_checksums(&self) -> Result<(), efi::Status> {
+        //Safety: data is at least header_size long, which was checked to hold a file header in File::new.
+        let file_header = unsafe { &*(self.data.as_ptr() as *const file::Header) };
+        Ok(verify_file_checksums(file_header, self.data, self.header_size, self.file_system)?)
     }
 
     /// Returns the file type.
     pub fn file_type(&self) -> Option<FfsFileType> {
-        match self.file_type {
-            FfsFileRawType::RAW => Some(FfsFileType::Raw),
-            FfsFileRawType::FREEFORM => Some(FfsFileType::FreeForm),
-            FfsFileRawType::SECURITY_CORE => Some(FfsFileType::SecurityCore),
-            FfsFileRawType::PEI_CORE => Some(FfsFileType::PeiCore),
-            FfsFileRawType::DXE_CORE => Some(FfsFileType::DxeCore),
-            FfsFileRawType::PEIM => Some(FfsFileType::Peim),
-            FfsFileRawType::DRIVER => Some(FfsFileType::Driver),
-            FfsFileRawType::COMBINED_PEIM_DRIVER => Some(FfsFileType::CombinedPeimDriver),
-            FfsFileRawType::APPLICATION => Some(FfsFileType::Application),
-            FfsFileRawType::MM => Some(FfsFileType::Mm),
-            FfsFileRawType::FIRMWARE_VOLUME_IMAGE => Some(FfsFileType::FirmwareVolumeImage),
-            FfsFileRawType::COMBINED_MM_DXE => Some(FfsFileType::CombinedMmDxe),
-            FfsFileRawType::MM_CORE => Some(FfsFileType::MmCore),
-            FfsFileRawType::MM_STANDALONE => Some(FfsFileType::MmStandalone),
-            FfsFileRawType::MM_CORE_STANDALONE => Some(FfsFileType::MmCoreStandalone),
-            FfsFileRawType::OEM_MIN..=FfsFileRawType::OEM_MAX => Some(FfsFileType::OemMin),
-            FfsFileRawType::DEBUG_MIN..=FfsFileRawType::DEBUG_MAX => Some(FfsFileType::DebugMin),
-            FfsFileRawType::FFS_PAD => Some(FfsFileType::FfsPad),
-            FfsFileRawType::FFS_MIN..=FfsFileRawType::FFS_MAX => Some(FfsFileType::FfsUnknown),
-            _ => None,
-        }
+        file_type_from_raw(self.file_type)
+    }
+
+    /// Returns the file state, decoded using the erase polarity of the containing FV.
+    pub fn state(&self) -> Option<FfsFileState> {
+        FfsFileState::from_raw(self.state, self.erase_polarity)
+    }
+
+    /// Returns the file state as a raw u8, as stored in the FV.
+    pub fn state_raw(&self) -> u8 {
+        self.state
     }
 
     /// Returns the file type as a raw u8.
@@ -517,29 +1317,51 @@ impl<'a> File<'a> {
     }
 
     /// Returns the FV attributes for the file.
-    pub fn fv_attributes(&self) -> EfiFvFileAttributes {
-        let attributes = self.attributes;
-        let data_alignment = (attributes & FfsRawAttribute::DATA_ALIGNMENT) >> 3;
-        // decode alignment per Table 3.3 in PI spec 1.8 Part III.
-        let mut file_attributes: u32 = match (
-            data_alignment,
-            (attributes & FfsRawAttribute::DATA_ALIGNMENT_2) == FfsRawAttribute::DATA_ALIGNMENT_2,
-        ) {
-            (0, false) => 0,
-            (1, false) => 4,
-            (2, false) => 7,
-            (3, false) => 9,
-            (4, false) => 10,
-            (5, false) => 12,
-            (6, false) => 15,
-            (7, false) => 16,
-            (x @ 0..=7, true) => (17 + x) as u32,
-            (_, _) => panic!("Invalid data_alignment!"),
-        };
-        if attributes & FfsRawAttribute::FIXED != 0 {
-            file_attributes |= FvFileRawAttribute::FIXED;
+    pub fn fv_attributes(&self) -> FvFileAttributes {
+        let attributes = self.attributes();
+        let mut file_attributes = FvFileAttributes::from_bits_retain(attributes.alignment_exponent());
+        if attributes.contains(FfsAttributes::FIXED) {
+            file_attributes |= FvFileAttributes::FIXED;
+        }
+        file_attributes
+    }
+
+    /// Returns the file attributes.
+    ///
+    /// For FFS1 files, the bits that hold the FFS1-only attributes (RECOVERY and HEADER_EXTENSION) are cleared, since
+    /// FFS1 has no DATA_ALIGNMENT_2 or FIXED attributes; use [`File::attributes_raw`] to read them.
+    pub fn attributes(&self) -> FfsAttributes {
+        match self.file_system {
+            FfsFileSystem::Ffs1 => FfsAttributes::from_bits_retain(
+                self.attributes & !(FfsRawAttribute::DATA_ALIGNMENT_2 | FfsRawAttribute::FIXED),
+            ),
+            FfsFileSystem::Ffs2 | FfsFileSystem::Ffs3 => FfsAttributes::from_bits_retain(self.attributes),
+        }
+    }
+
+    /// Returns the alignment in bytes required for the file data, decoded from the file attributes.
+    pub fn required_alignment(&self) -> usize {
+        self.fv_attributes().alignment()
+    }
+
+    /// Returns whether the file data meets its [`File::required_alignment`] in `fv`, the firmware volume holding it.
+    ///
+    /// The file data must be aligned relative to the start of the FV. Unless the FV has the WEAK_ALIGNMENT attribute,
+    /// the FV alignment must also be at least the required alignment, since the file data is otherwise not aligned
+    /// in memory once the FV is loaded at an address that only meets the FV alignment. Returns `false` if the file is
+    /// not in `fv`.
+    pub fn is_properly_aligned(&self, fv: &FirmwareVolume) -> bool {
+        let fv_data = fv.data().as_ptr_range();
+        let content = self.content().as_ptr();
+        if !fv_data.contains(&content) {
+            return false;
         }
-        file_attributes as EfiFvFileAttributes
+        let required_alignment = self.required_alignment();
+        let offset = content as usize - fv_data.start as usize;
+        let fv_attributes = fv.attributes();
+        offset % required_alignment == 0
+            && (fv_attributes.contains(FvbAttributes2::WEAK_ALIGNMENT)
+                || fv_attributes.alignment() >= required_alignment)
     }
 
     /// Returns the file attributes as a raw u8
@@ -557,9 +1379,22 @@ impl<'a> File<'a> {
         self.size
     }
 
-    /// Returns the raw data from the file (without extracting any sections), not including the header.
+    /// Returns the Firmware File System version the file was parsed as.
+    pub fn file_system(&self) -> FfsFileSystem {
+        self.file_system
+    }
+
+    /// Returns the spec deviations that were tolerated while parsing this file.
+    ///
+    /// Only populated when the file was parsed with [`Strictness::Compatible`](crate::parse::Strictness::Compatible).
+    pub fn deviations(&self) -> &[Deviation] {
+        &self.deviations
+    }
+
+    /// Returns the raw data from the file (without extracting any sections), not including the header or the FFS1
+    /// file tail.
     pub fn content(&self) -> &[u8] {
-        &self.data[self.header_size..self.size as usize]
+        &self.data[self.header_size..self.size as usize - self.tail_size]
     }
 
     /// Returns the raw data for the file, including the header.
@@ -567,36 +1402,309 @@ impl<'a> File<'a> {
         self.data
     }
 
+    /// Parses this file as an apriori file, returning the file names it lists in dispatch order.
+    ///
+    /// The file names are held in the first RAW section of the file as an array of GUIDs.
+    pub fn apriori_file_names(&self) -> Result<Vec<efi::Guid>, efi::Status> {
+        let section = self.first_section_of_type(FfsSectionType::Raw)?.ok_or(efi::Status::NOT_FOUND)?;
+        let file_names = section.section_data();
+        if file_names.len() % mem::size_of::<efi::Guid>() != 0 {
+            Err(efi::Status::VOLUME_CORRUPTED)?;
+        }
+        Ok(file_names
+            .chunks_exact(mem::size_of::<efi::Guid>())
+            .map(|x| efi::Guid::from_bytes(x.try_into().unwrap()))
+            .collect())
+    }
+
     // Returns an iterator over the sections of this file (without extracting encapsulation sections).
     pub fn section_iter(&self) -> impl Iterator<Item = Result<Section, efi::Status>> + '_ {
-        self.section_iter_with_extractor(&NullSectionExtractor {})
+        FileSectionIterator::new(self.content(), &NullSectionExtractor {}, ExtractionLimits::default())
+            .with_alignment(self.alignment)
+            .without_extraction()
     }
 
     // Returns an iterator over the sections of this file, extracting encapsulation sections with the given extractor.
+    //
+    // A GUID-defined section with the PROCESSING_REQUIRED attribute that the extractor does not extract is followed by
+    // an `UNSUPPORTED` error.
     pub fn section_iter_with_extractor<'b>(
         &'b self,
         extractor: &'b dyn SectionExtractor,
     ) -> impl Iterator<Item = Result<Section, efi::Status>> + 'b {
-        FileSectionIterator::new(&self.data[self.header_size..self.size as usize], extractor)
+        self.section_iter_with_limits(extractor, ExtractionLimits::default())
+    }
+
+    /// Returns an iterator over the sections of this file, extracting encapsulation sections with the given extractor
+    /// within the given limits.
+    pub fn section_iter_with_limits<'b>(
+        &'b self,
+        extractor: &'b dyn SectionExtractor,
+        limits: ExtractionLimits,
+    ) -> impl Iterator<Item = Result<Section, efi::Status>> + 'b {
+        FileSectionIterator::new(self.content(), extractor, limits).with_alignment(self.alignment)
+    }
+
+    /// Returns an iterator over the sections of the given type in this file (without extracting encapsulation
+    /// sections). [`FfsSectionType::All`] matches any section type.
+    ///
+    /// Errors encountered while parsing the sections are returned as-is.
+    pub fn sections_of_type(
+        &self,
+        section_type: FfsSectionType,
+    ) -> impl Iterator<Item = Result<Section, efi::Status>> + '_ {
+        self.section_iter().filter(move |section| is_section_of_type(section, section_type))
+    }
+
+    /// Returns an iterator over the sections of the given type in this file, extracting encapsulation sections with the
+    /// given extractor. [`FfsSectionType::All`] matches any section type.
+    ///
+    /// Errors encountered while parsing or extracting the sections are returned as-is.
+    pub fn sections_of_type_with_extractor<'b>(
+        &'b self,
+        section_type: FfsSectionType,
+        extractor: &'b dyn SectionExtractor,
+    ) -> impl Iterator<Item = Result<Section, efi::Status>> + 'b {
+        self.section_iter_with_extractor(extractor).filter(move |section| is_section_of_type(section, section_type))
+    }
+
+    /// Returns the first section of the given type in this file (without extracting encapsulation sections), or `None`
+    /// if the file has no such section.
+    ///
+    /// ## Example
+    ///```
+    /// # use std::{env, fs, path::Path, error::Error};
+    /// use mu_pi::fw_fs::{FfsFileType, FfsSectionType, FirmwareVolume};
+    ///
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// # let root = Path::new(&env::var("CARGO_MANIFEST_DIR")?).join("test_resources");
+    /// # let fv_bytes = fs::read(root.join("DXEFV.Fv"))?;
+    /// let fv = FirmwareVolume::new(&fv_bytes).expect("Firmware Volume Corrupt");
+    /// let dxe_core = fv.files_of_type(FfsFileType::DxeCore).next().expect("no DXE core").expect("parse error");
+    /// let pe32 = dxe_core.first_section_of_type(FfsSectionType::Pe32).expect("parse error").expect("no PE32 section");
+    /// println!("DXE core image size: {:#x}", pe32.section_data().len());
+    /// # Ok(())
+    /// # }
+    ///```
+    pub fn first_section_of_type(&self, section_type: FfsSectionType) -> Result<Option<Section>, efi::Status> {
+        self.sections_of_type(section_type).next().transpose()
+    }
+
+    /// Returns the zero-based `instance` of the sections of the given type in this file (without extracting
+    /// encapsulation sections), or `None` if the file has fewer matching sections. Mirrors the PEI
+    /// `FfsFindSectionData3` service.
+    pub fn section(&self, section_type: FfsSectionType, instance: usize) -> Result<Option<Section>, efi::Status> {
+        self.sections_of_type(section_type).nth(instance).transpose()
+    }
+
+    /// Returns the zero-based `instance` of the sections of the given type in this file, extracting encapsulation
+    /// sections with the given extractor so that nested sections are counted as well.
+    ///
+    /// Sections are counted in the order returned by [`File::section_iter_with_extractor`].
+    pub fn section_with_extractor(
+        &self,
+        section_type: FfsSectionType,
+        instance: usize,
+        extractor: &dyn SectionExtractor,
+    ) -> Result<Option<Section>, efi::Status> {
+        self.sections_of_type_with_extractor(section_type, extractor).nth(instance).transpose()
+    }
+
+    /// Returns the first GUID_DEFINED section in this file whose section definition GUID matches `guid` (without
+    /// extracting encapsulation sections), or `None` if the file has no such section.
+    pub fn guid_defined_section(&self, guid: efi::Guid) -> Result<Option<Section>, efi::Status> {
+        find_guid_defined_section(self.sections_of_type(FfsSectionType::GuidDefined), guid)
+    }
+
+    /// Returns the first GUID_DEFINED section in this file whose section definition GUID matches `guid`, extracting
+    /// encapsulation sections with the given extractor so that nested GUID_DEFINED sections are found as well.
+    pub fn guid_defined_section_with_extractor(
+        &self,
+        guid: efi::Guid,
+        extractor: &dyn SectionExtractor,
+    ) -> Result<Option<Section>, efi::Status> {
+        find_guid_defined_section(self.sections_of_type_with_extractor(FfsSectionType::GuidDefined, extractor), guid)
+    }
+
+    /// Returns the user interface name of the file, from its USER_INTERFACE section.
+    ///
+    /// Returns `None` if the file has no USER_INTERFACE section. Encapsulation sections are not extracted.
+    pub fn ui_name(&self) -> Result<Option<String>, efi::Status> {
+        self.first_section_of_type(FfsSectionType::UserInterface)?.map(|section| section.ui_name()).transpose()
     }
 }
 
+#[cfg(feature = "alloc")]
 impl<'a> fmt::Debug for File<'a> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("File")
-            .field("name", &self.name)
+            .field("name", &DebugGuid(&self.name))
             .field("file_type", &self.file_type)
             .field("attributes", &self.attributes)
             .field("header_size", &self.header_size)
+            .field("tail_size", &self.tail_size)
             .field("size", &self.size)
+            .field("state", &self.state)
+            .field("file_system", &self.file_system)
+            .field("deviations", &self.deviations)
             .field("data.len()", &self.data.len())
             .finish_non_exhaustive()
     }
 }
 
+// Returns the (header size, file size) of the file in buffer, accounting for the extended size of large files. FFS1
+// has no large files; the attribute bit signals a file tail instead.
+fn file_header_size_and_length(buffer: &[u8], file_system: FfsFileSystem) -> Result<(usize, u64), FwFsError> {
+    // verify that buffer has enough storage for a file header.
+    if buffer.len() < mem::size_of::<file::Header>() {
+        Err(FwFsError::BufferTooSmall { needed: mem::size_of::<file::Header>(), available: buffer.len() })?;
+    }
+
+    //Safety: buffer is large enough to contain the header, so can cast to a ref.
+    let file_header = unsafe { &*(buffer.as_ptr() as *const file::Header) };
+
+    let header_size = mem::size_of::<file::Header>();
+    if file_system == FfsFileSystem::Ffs1 || (file_header.attributes & LARGE_FILE) == 0 {
+        //standard header with 24-bit size
+        let [size_0, size_1, size_2] = file_header.size;
+        let size = u32::from_le_bytes([size_0, size_1, size_2, 0]);
+        Ok((header_size, size as u64))
+    } else {
+        //extended header with 64-bit size
+        let extended_size_length = mem::size_of::<u64>();
+        if buffer[header_size..].len() < extended_size_length {
+            Err(FwFsError::TruncatedHeader { needed: header_size + extended_size_length, available: buffer.len() })?;
+        }
+        let size = u64::from_le_bytes(buffer[header_size..header_size + extended_size_length].try_into().unwrap());
+        Ok((header_size + extended_size_length, size))
+    }
+}
+
+// Indicates whether an item of a section iterator is a section of the given type, or an error (which is kept).
+#[cfg(feature = "alloc")]
+fn is_section_of_type(section: &Result<Section, efi::Status>, section_type: FfsSectionType) -> bool {
+    match section {
+        Ok(section) => section_type == FfsSectionType::All || section.section_type() == Some(section_type),
+        Err(_) => true,
+    }
+}
+
+// Returns the first GUID-defined section with the given section definition GUID.
+#[cfg(feature = "alloc")]
+fn find_guid_defined_section(
+    sections: impl Iterator<Item = Result<Section, efi::Status>>,
+    guid: efi::Guid,
+) -> Result<Option<Section>, efi::Status> {
+    for section in sections {
+        let section = section?;
+        if let SectionMetaData::GuidDefined(header, _) = section.meta_data() {
+            if header.section_definition_guid == guid {
+                return Ok(Some(section));
+            }
+        }
+    }
+    Ok(None)
+}
+
+// Returns the size of the file tail: FFS1 files with the TAIL_PRESENT attribute end with a 16-bit tail.
+fn file_tail_size(file_header: &file::Header, file_system: FfsFileSystem) -> usize {
+    if file_system == FfsFileSystem::Ffs1 && file_header.attributes & TAIL_PRESENT != 0 {
+        mem::size_of::<u16>()
+    } else {
+        0
+    }
+}
+
+// Decodes a raw file type.
+fn file_type_from_raw(file_type: u8) -> Option<FfsFileType> {
+    match file_type {
+        FfsFileRawType::RAW => Some(FfsFileType::Raw),
+        FfsFileRawType::FREEFORM => Some(FfsFileType::FreeForm),
+        FfsFileRawType::SECURITY_CORE => Some(FfsFileType::SecurityCore),
+        FfsFileRawType::PEI_CORE => Some(FfsFileType::PeiCore),
+        FfsFileRawType::DXE_CORE => Some(FfsFileType::DxeCore),
+        FfsFileRawType::PEIM => Some(FfsFileType::Peim),
+        FfsFileRawType::DRIVER => Some(FfsFileType::Driver),
+        FfsFileRawType::COMBINED_PEIM_DRIVER => Some(FfsFileType::CombinedPeimDriver),
+        FfsFileRawType::APPLICATION => Some(FfsFileType::Application),
+        FfsFileRawType::MM => Some(FfsFileType::Mm),
+        FfsFileRawType::FIRMWARE_VOLUME_IMAGE => Some(FfsFileType::FirmwareVolumeImage),
+        FfsFileRawType::COMBINED_MM_DXE => Some(FfsFileType::CombinedMmDxe),
+        FfsFileRawType::MM_CORE => Some(FfsFileType::MmCore),
+        FfsFileRawType::MM_STANDALONE => Some(FfsFileType::MmStandalone),
+        FfsFileRawType::MM_CORE_STANDALONE => Some(FfsFileType::MmCoreStandalone),
+        FfsFileRawType::OEM_MIN..=FfsFileRawType::OEM_MAX => Some(FfsFileType::OemMin),
+        FfsFileRawType::DEBUG_MIN..=FfsFileRawType::DEBUG_MAX => Some(FfsFileType::DebugMin),
+        FfsFileRawType::FFS_PAD => Some(FfsFileType::FfsPad),
+        FfsFileRawType::FFS_MIN..=FfsFileRawType::FFS_MAX => Some(FfsFileType::FfsUnknown),
+        _ => None,
+    }
+}
+
+// Decodes a raw section type.
+fn section_type_from_raw(section_type: u8) -> Option<FfsSectionType> {
+    match section_type {
+        FfsSectionRawType::encapsulated::COMPRESSION => Some(FfsSectionType::Compression),
+        FfsSectionRawType::encapsulated::GUID_DEFINED => Some(FfsSectionType::GuidDefined),
+        FfsSectionRawType::encapsulated::DISPOSABLE => Some(FfsSectionType::Disposable),
+        FfsSectionRawType::PE32 => Some(FfsSectionType::Pe32),
+        FfsSectionRawType::PIC => Some(FfsSectionType::Pic),
+        FfsSectionRawType::TE => Some(FfsSectionType::Te),
+        FfsSectionRawType::DXE_DEPEX => Some(FfsSectionType::DxeDepex),
+        FfsSectionRawType::VERSION => Some(FfsSectionType::Version),
+        FfsSectionRawType::USER_INTERFACE => Some(FfsSectionType::UserInterface),
+        FfsSectionRawType::COMPATIBILITY16 => Some(FfsSectionType::Compatibility16),
+        FfsSectionRawType::FIRMWARE_VOLUME_IMAGE => Some(FfsSectionType::FirmwareVolumeImage),
+        FfsSectionRawType::FREEFORM_SUBTYPE_GUID => Some(FfsSectionType::FreeformSubtypeGuid),
+        FfsSectionRawType::RAW => Some(FfsSectionType::Raw),
+        FfsSectionRawType::PEI_DEPEX => Some(FfsSectionType::PeiDepex),
+        FfsSectionRawType::MM_DEPEX => Some(FfsSectionType::MmDepex),
+        _ => None,
+    }
+}
+
+// Verifies the header checksum, the data checksum (if enabled by the CHECKSUM attribute) and the FFS1 file tail (if
+// present) of the file in `data`.
+fn verify_file_checksums(
+    file_header: &file::Header,
+    data: &[u8],
+    header_size: usize,
+    file_system: FfsFileSystem,
+) -> Result<(), FwFsError> {
+    let tail_offset = data.len() - file_tail_size(file_header, file_system);
+
+    //Verify the header checksum.
+    // integrity_check_file and state are assumed to be zero for checksum, so subtract them here.
+    let header_sum = checksum::sum8(&data[..header_size])
+        .wrapping_sub(file_header.integrity_check_file)
+        .wrapping_sub(file_header.state);
+    if header_sum != 0 {
+        Err(FwFsError::BadFileHeaderChecksum { name: file_header.name })?;
+    }
+
+    //Verify the file data checksum: the data and integrity_check_file must sum to zero.
+    if file_header.attributes & ffs::attributes::raw::CHECKSUM != 0
+        && checksum::sum8(&data[header_size..tail_offset]).wrapping_add(file_header.integrity_check_file) != 0
+    {
+        Err(FwFsError::BadFileDataChecksum { name: file_header.name })?;
+    }
+
+    //Verify the FFS1 file tail: the complement of the integrity check (header and file checksums as a u16).
+    if tail_offset != data.len() {
+        let integrity_check =
+            u16::from_le_bytes([file_header.integrity_check_header, file_header.integrity_check_file]);
+        let tail = u16::from_le_bytes(data[tail_offset..].try_into().unwrap());
+        if tail != !integrity_check {
+            Err(FwFsError::BadFileTail { name: file_header.name, tail })?;
+        }
+    }
+    Ok(())
+}
+
 /// Section Metadata
 ///
 /// Describes the meta data in the section header (if any - most section types do not have metadata).
+#[cfg(feature = "alloc")]
 #[derive(Debug, Clone)]
 pub enum SectionMetaData {
     None,
@@ -606,6 +1714,20 @@ pub enum SectionMetaData {
     FreeformSubtypeGuid(FfsSectionHeader::FreeformSubtypeGuid),
 }
 
+/// Describes an encapsulation section that a section was extracted from.
+///
+/// See [`Section::extracted_from`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SectionOrigin {
+    /// The raw type of the encapsulation section.
+    pub section_type: u8,
+    /// The section definition GUID of the encapsulation section, if it is a GUID-defined section.
+    pub section_definition: Option<efi::Guid>,
+    /// The offset of the encapsulation section in the data that holds it: the file content for the outermost
+    /// encapsulation section, and the extracted data of its parent otherwise.
+    pub offset: usize,
+}
+
 /// Section access support
 ///
 /// Provides access to section contents.
@@ -627,141 +1749,52 @@ pub enum SectionMetaData {
 /// # Ok(())
 /// # }
 ///```
+#[cfg(feature = "alloc")]
 #[derive(Clone)]
 pub struct Section {
     section_type: u8,
     meta_data: SectionMetaData,
     data: Box<[u8]>,
     section_size: usize,
+    authentication_status: u32,
+    extracted_from: Vec<SectionOrigin>,
 }
 
+#[cfg(feature = "alloc")]
 impl Section {
     /// Instantiates a new Section by parsing the given buffer.
     ///
     /// The normal way to obtain a Section instance would be through the [`File::sections()`] method, but
     /// a constructor is provided here to enable independent instantiation of a section.
     pub fn new(buffer: &[u8]) -> Result<Self, efi::Status> {
-        // verify that buffer has enough storage for a section header.
-        if buffer.len() < mem::size_of::<section::Header>() {
-            Err(efi::Status::INVALID_PARAMETER)?;
-        }
-
-        //Safety: buffer is large enough to contain the header, so can cast to a ref.
-        let section_header = unsafe { &*(buffer.as_ptr() as *const section::Header) };
-
-        //determine section_size and start of section content based on whether extended size field is present.
-        let header_end = mem::size_of::<section::Header>();
-        let (section_size, content_offset) = {
-            if section_header.size.iter().all(|&x| x == 0xff) {
-                //extended header - confirm there is space for extended size
-                if buffer.len() < header_end + mem::size_of::<u32>() {
-                    Err(efi::Status::VOLUME_CORRUPTED)?;
-                }
-                let size =
-                    u32::from_le_bytes(buffer[header_end..header_end + mem::size_of::<u32>()].try_into().unwrap());
-                (size as usize, header_end + mem::size_of::<u32>())
-            } else {
-                //standard header
-                let mut size_vec = section_header.size.to_vec();
-                size_vec.push(0);
-                let size = u32::from_le_bytes(size_vec.try_into().unwrap());
-                (size as usize, header_end)
-            }
-        };
-
-        let (meta_data, data) = match section_header.section_type {
-            FfsSectionRawType::encapsulated::COMPRESSION => {
-                let compression_header_size = mem::size_of::<section::header::Compression>();
-                //verify that buffer has enough storage for a compression header.
-                if buffer.len() < content_offset + compression_header_size {
-                    Err(efi::Status::VOLUME_CORRUPTED)?;
-                }
-                //Safety: buffer is large enough to hold compression header
-                let compression_header =
-                    unsafe { &*(buffer[content_offset..].as_ptr() as *const section::header::Compression) };
-                let data: Box<[u8]> = Box::from(&buffer[content_offset + compression_header_size..section_size]);
-                (SectionMetaData::Compression(*compression_header), data)
-            }
-            FfsSectionRawType::encapsulated::GUID_DEFINED => {
-                let guid_defined_header_size = mem::size_of::<section::header::GuidDefined>();
-                //verify that buffer has enough storage for a guid_defined header.
-                if buffer.len() < content_offset + guid_defined_header_size {
-                    Err(efi::Status::VOLUME_CORRUPTED)?;
-                }
-                //Safety: buffer is large enough to hold guid_defined header
-                let guid_defined =
-                    unsafe { &*(buffer[content_offset..].as_ptr() as *const section::header::GuidDefined) };
-
-                //verify that buffer has enough storage for guid-specific fields.
-                let data_offset = guid_defined.data_offset as usize;
-                if buffer.len() < data_offset {
-                    Err(efi::Status::VOLUME_CORRUPTED)?;
-                }
-
-                let guid_specific_header_fields: Box<[u8]> =
-                    Box::from(&buffer[content_offset + guid_defined_header_size..data_offset]);
-                let data: Box<[u8]> = Box::from(&buffer[data_offset..section_size]);
+        Ok(Self::try_new(buffer)?)
+    }
 
-                (SectionMetaData::GuidDefined(*guid_defined, guid_specific_header_fields), data)
-            }
-            FfsSectionRawType::VERSION => {
-                let version_header_size = mem::size_of::<section::header::Version>();
-                //verify that buffer has enough storage for a version header.
-                if buffer.len() < content_offset + version_header_size {
-                    Err(efi::Status::VOLUME_CORRUPTED)?;
-                }
-                //Safety: buffer is large enough to hold version header
-                let version_header =
-                    unsafe { &*(buffer[content_offset..].as_ptr() as *const section::header::Version) };
-                let data: Box<[u8]> = Box::from(&buffer[content_offset + version_header_size..section_size]);
-                (SectionMetaData::Version(*version_header), data)
-            }
-            FfsSectionRawType::FREEFORM_SUBTYPE_GUID => {
-                let freeform_header_size = mem::size_of::<section::header::FreeformSubtypeGuid>();
-                //verify that buffer has enough storage for a freeform header.
-                if buffer.len() < content_offset + freeform_header_size {
-                    Err(efi::Status::VOLUME_CORRUPTED)?;
-                }
-                //Safety: buffer is large enough to hold freeform header
-                let freeform_header =
-                    unsafe { &*(buffer[content_offset..].as_ptr() as *const section::header::FreeformSubtypeGuid) };
-                let data: Box<[u8]> = Box::from(&buffer[content_offset + freeform_header_size..section_size]);
-                (SectionMetaData::FreeformSubtypeGuid(*freeform_header), data)
-            }
-            FfsSectionRawType::OEM_MIN..=FfsSectionRawType::FFS_MAX => {
-                //these section types do not have a defined header. So set metadata to none, and set data to the entire section buffer.
-                let data: Box<[u8]> = Box::from(buffer);
-                (SectionMetaData::None, data)
-            }
-            _ => {
-                let data: Box<[u8]> = Box::from(&buffer[content_offset..section_size]);
-                (SectionMetaData::None, data)
-            }
+    /// Instantiates a new Section by parsing the given buffer.
+    ///
+    /// Behaves like [`Section::new`], but returns an error that describes why the section is malformed.
+    pub fn try_new(buffer: &[u8]) -> Result<Self, FwFsError> {
+        let raw = raw::RawSection::new(buffer)?;
+        let meta_data = match raw.meta_data {
+            raw::RawSectionMetaData::None => SectionMetaData::None,
+            raw::RawSectionMetaData::Compression(header) => SectionMetaData::Compression(header),
+            raw::RawSectionMetaData::GuidDefined(header, fields) => SectionMetaData::GuidDefined(header, fields.into()),
+            raw::RawSectionMetaData::Version(header) => SectionMetaData::Version(header),
+            raw::RawSectionMetaData::FreeformSubtypeGuid(header) => SectionMetaData::FreeformSubtypeGuid(header),
         };
-
-        Ok(Self { section_type: section_header.section_type, meta_data, data, section_size })
+        Ok(Self {
+            section_type: raw.section_type,
+            meta_data,
+            data: raw.data.into(),
+            section_size: raw.section_size,
+            authentication_status: 0,
+            extracted_from: Vec::new(),
+        })
     }
 
     /// Returns the section type.
     pub fn section_type(&self) -> Option<FfsSectionType> {
-        match self.section_type {
-            FfsSectionRawType::encapsulated::COMPRESSION => Some(FfsSectionType::Compression),
-            FfsSectionRawType::encapsulated::GUID_DEFINED => Some(FfsSectionType::GuidDefined),
-            FfsSectionRawType::encapsulated::DISPOSABLE => Some(FfsSectionType::Disposable),
-            FfsSectionRawType::PE32 => Some(FfsSectionType::Pe32),
-            FfsSectionRawType::PIC => Some(FfsSectionType::Pic),
-            FfsSectionRawType::TE => Some(FfsSectionType::Te),
-            FfsSectionRawType::DXE_DEPEX => Some(FfsSectionType::DxeDepex),
-            FfsSectionRawType::VERSION => Some(FfsSectionType::Version),
-            FfsSectionRawType::USER_INTERFACE => Some(FfsSectionType::UserInterface),
-            FfsSectionRawType::COMPATIBILITY16 => Some(FfsSectionType::Compatibility16),
-            FfsSectionRawType::FIRMWARE_VOLUME_IMAGE => Some(FfsSectionType::FirmwareVolumeImage),
-            FfsSectionRawType::FREEFORM_SUBTYPE_GUID => Some(FfsSectionType::FreeformSubtypeGuid),
-            FfsSectionRawType::RAW => Some(FfsSectionType::Raw),
-            FfsSectionRawType::PEI_DEPEX => Some(FfsSectionType::PeiDepex),
-            FfsSectionRawType::MM_DEPEX => Some(FfsSectionType::MmDepex),
-            _ => None,
-        }
+        section_type_from_raw(self.section_type)
     }
 
     /// Returns the section type as a raw u8.
@@ -781,89 +1814,309 @@ impl Section {
     }
 
     /// Returns the section data.
+    ///
+    /// The data of a GUID-defined section with the
+    /// [`PROCESSING_REQUIRED`](FfsGuidedSectionAttribute::PROCESSING_REQUIRED) attribute is encoded and only meaningful
+    /// once extracted, so an empty slice is returned for such sections. Use [`Section::raw_section_data()`] to access
+    /// the encoded data, e.g. in a [`SectionExtractor`].
     pub fn section_data(&self) -> &[u8] {
+        if self.requires_processing() {
+            &[]
+        } else {
+            &self.data
+        }
+    }
+
+    /// Returns the section data as stored in the section, without regard to the GUID-defined section attributes.
+    pub fn raw_section_data(&self) -> &[u8] {
         &self.data
     }
+
+    /// Indicates whether this is a GUID-defined section with the
+    /// [`PROCESSING_REQUIRED`](FfsGuidedSectionAttribute::PROCESSING_REQUIRED) attribute.
+    pub fn requires_processing(&self) -> bool {
+        self.guided_attributes()
+            .map_or(false, |attributes| attributes.contains(FfsGuidedSectionAttributes::PROCESSING_REQUIRED))
+    }
+
+    /// Returns the attributes of this GUID-defined section, or `None` if this is not a GUID-defined section.
+    pub fn guided_attributes(&self) -> Option<FfsGuidedSectionAttributes> {
+        match &self.meta_data {
+            SectionMetaData::GuidDefined(header, _) => {
+                Some(FfsGuidedSectionAttributes::from_bits_retain(header.attributes))
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the authentication status of the section, a combination of [`FfsAuthStatus`] bits.
+    ///
+    /// Sections extracted from GUID-defined sections with the
+    /// [`AUTH_STATUS_VALID`](FfsGuidedSectionAttribute::AUTH_STATUS_VALID) attribute accumulate the status reported by
+    /// [`SectionExtractor::extract_with_auth_status`] for each enclosing section, as the EDK2 section extraction does.
+    /// Sections that are not in such a section have a status of 0.
+    pub fn authentication_status(&self) -> u32 {
+        self.authentication_status
+    }
+
+    /// Returns the encapsulation sections this section was extracted from, outermost first.
+    ///
+    /// The slice is empty for sections stored directly in the file, and its length is the nesting depth of the section
+    /// otherwise. Sections instantiated with [`Section::new`] have no provenance.
+    pub fn extracted_from(&self) -> &[SectionOrigin] {
+        &self.extracted_from
+    }
+
+    /// Decodes the null-terminated UCS-2 string held in this USER_INTERFACE section.
+    ///
+    /// Returns `INVALID_PARAMETER` if this is not a USER_INTERFACE section. Invalid characters are replaced with
+    /// U+FFFD.
+    pub fn ui_name(&self) -> Result<String, efi::Status> {
+        if self.section_type() != Some(FfsSectionType::UserInterface) {
+            Err(efi::Status::INVALID_PARAMETER)?;
+        }
+        Ok(ucs2_string(&self.data))
+    }
+
+    /// Returns the build number and the decoded null-terminated UCS-2 version string of this VERSION section.
+    ///
+    /// Returns `INVALID_PARAMETER` if this is not a VERSION section. Invalid characters are replaced with U+FFFD.
+    pub fn version(&self) -> Result<(u16, String), efi::Status> {
+        let SectionMetaData::Version(header) = self.meta_data else {
+            return Err(efi::Status::INVALID_PARAMETER);
+        };
+        Ok((header.build_number, ucs2_string(&self.data)))
+    }
+
+    /// Reads the PE/COFF header of the image in this PE32 section.
+    ///
+    /// Returns `INVALID_PARAMETER` if this is not a PE32 section. The errors of [`PeHeaderInfo::parse`] are returned as
+    /// the status they convert to.
+    pub fn pe_header_info(&self) -> Result<PeHeaderInfo, efi::Status> {
+        if self.section_type() != Some(FfsSectionType::Pe32) {
+            Err(efi::Status::INVALID_PARAMETER)?;
+        }
+        Ok(PeHeaderInfo::parse(&self.data)?)
+    }
+
+    /// Instantiates the FV contained in this FIRMWARE_VOLUME_IMAGE section.
+    ///
+    /// Returns `INVALID_PARAMETER` if this is not a FIRMWARE_VOLUME_IMAGE section.
+    pub fn firmware_volume(&self) -> Result<FirmwareVolume<'_>, efi::Status> {
+        self.firmware_volume_with_options(&ParseOptions::default())
+    }
+
+    /// Instantiates the FV contained in this FIRMWARE_VOLUME_IMAGE section using the given parse options.
+    ///
+    /// Returns `INVALID_PARAMETER` if this is not a FIRMWARE_VOLUME_IMAGE section.
+    pub fn firmware_volume_with_options(&self, options: &ParseOptions) -> Result<FirmwareVolume<'_>, efi::Status> {
+        if self.section_type() != Some(FfsSectionType::FirmwareVolumeImage) {
+            Err(efi::Status::INVALID_PARAMETER)?;
+        }
+        FirmwareVolume::new_with_options(&self.data, options)
+    }
     pub fn section_size(&self) -> usize {
         self.section_size
     }
 }
 
+// Decodes a null-terminated little-endian UCS-2 string.
+#[cfg(feature = "alloc")]
+fn ucs2_string(data: &[u8]) -> String {
+    let chars = data
+        .chunks_exact(2)
+        .map(|x| u16::from_le_bytes(x.try_into().unwrap()))
+        .take_while(|&x| x != 0)
+        .collect::<Vec<_>>();
+    String::from_utf16_lossy(&chars)
+}
+
+#[cfg(feature = "alloc")]
 impl fmt::Debug for Section {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        f.debug_struct("Section")
-            .field("section_type", &self.section_type)
-            .field("meta_data", &self.meta_data)
+        let mut debug = f.debug_struct("Section");
+        debug.field("section_type", &self.section_type).field("meta_data", &self.meta_data);
+        #[cfg(feature = "guid_names")]
+        if let SectionMetaData::GuidDefined(header, _) = &self.meta_data {
+            if let Some(name) = guid_names::lookup(&header.section_definition_guid) {
+                debug.field("section_definition", &name);
+            }
+        }
+        debug
             .field("data.len()", &self.data.len())
+            .field("authentication_status", &self.authentication_status)
+            .field("extracted_from", &self.extracted_from)
             .finish_non_exhaustive()
     }
 }
 
-struct FvFileIterator<'a> {
-    buffer: &'a [u8],
-    erase_byte: u8,
-    next_offset: usize,
-    error: bool,
-}
+// Formats a GUID for Debug output, followed by its name if it is a well-known GUID (with the `guid_names` feature).
+#[cfg(feature = "alloc")]
+struct DebugGuid<'a>(&'a efi::Guid);
 
-impl<'a> FvFileIterator<'a> {
-    pub fn new(buffer: &'a [u8], erase_byte: u8) -> Self {
-        FvFileIterator { buffer, erase_byte, next_offset: 0, error: false }
+#[cfg(feature = "alloc")]
+impl fmt::Debug for DebugGuid<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.0, f)?;
+        #[cfg(feature = "guid_names")]
+        if let Some(name) = guid_names::lookup(self.0) {
+            write!(f, " ({})", name)?;
+        }
+        Ok(())
     }
 }
 
-impl<'a> Iterator for FvFileIterator<'a> {
-    type Item = Result<File<'a>, efi::Status>;
+#[cfg(feature = "alloc")]
+struct FvFileIterator<'a, 's> {
+    files: raw::RawFileIterator<'a>,
+    diagnostics: Option<&'s mut dyn FnMut(Diagnostic)>,
+}
 
-    fn next(&mut self) -> Option<Self::Item> {
-        if self.error {
-            return None;
-        }
-        if self.next_offset > self.buffer.len() {
-            return None;
-        }
-        if self.buffer[self.next_offset..].len() < mem::size_of::<file::Header>() {
-            return None;
-        }
-        if self.buffer[self.next_offset..self.next_offset + mem::size_of::<file::Header>()]
-            .iter()
-            .all(|&x| x == self.erase_byte)
-        {
-            return None;
+#[cfg(feature = "alloc")]
+impl<'a, 's> FvFileIterator<'a, 's> {
+    pub fn new(
+        buffer: &'a [u8],
+        erase_byte: u8,
+        file_system: FfsFileSystem,
+        options: ParseOptions,
+        diagnostics: Option<&'s mut dyn FnMut(Diagnostic)>,
+    ) -> Self {
+        FvFileIterator { files: raw::RawFileIterator::new(buffer, erase_byte, file_system, options), diagnostics }
+    }
+}
+
+// Returns the number of bytes to skip if the file whose header is in buffer is not valid. remaining is the number of
+// bytes from the start of the file to the end of the FV. Like the EDK2 FFS driver, files that are being constructed,
+// deleted, or have an invalid header are skipped.
+fn invalid_file_length(
+    buffer: &[u8],
+    remaining: usize,
+    file_system: FfsFileSystem,
+    erase_polarity: bool,
+) -> Result<Option<u64>, FwFsError> {
+    let (header_size, size) = file_header_size_and_length(buffer, file_system)?;
+    //Safety: file_header_size_and_length verified the buffer is large enough to contain the header.
+    let file_header = unsafe { &*(buffer.as_ptr() as *const file::Header) };
+    match FfsFileState::from_raw(file_header.state, erase_polarity) {
+        Some(FfsFileState::DataValid) | Some(FfsFileState::MarkedForUpdate) => Ok(None),
+        // headers that never became valid are skipped by header size, as the file size cannot be trusted.
+        Some(FfsFileState::HeaderConstruction) | Some(FfsFileState::HeaderInvalid) | None => {
+            Ok(Some(header_size as u64))
         }
-        let result = File::new(&self.buffer[self.next_offset..]);
-        if let Ok(ref file) = result {
-            // per the PI spec, "Given a file F, the next file FvHeader is located at the next 8-byte aligned firmware volume
-            // offset following the last byte the file F"
-            self.next_offset = align_up(self.next_offset as u64 + file.size(), 8) as usize;
-        } else {
-            self.error = true;
+        Some(FfsFileState::HeaderValid) | Some(FfsFileState::Deleted) => {
+            if size > remaining as u64 || (size as usize) < header_size {
+                Err(FwFsError::BadFileSize { name: file_header.name, size })?;
+            }
+            Ok(Some(size))
         }
+    }
+}
 
-        Some(result)
+#[cfg(feature = "alloc")]
+impl<'a, 's> Iterator for FvFileIterator<'a, 's> {
+    type Item = Result<File<'a>, FwFsError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let raw = match self.diagnostics.as_mut() {
+            Some(diagnostics) => self.files.next_with_diagnostics(*diagnostics)?,
+            None => self.files.next_with_diagnostics(&mut |_| ())?,
+        };
+        Some(raw.map(File::from_raw))
     }
 }
 
+#[cfg(feature = "alloc")]
 struct FileSectionIterator<'a> {
     buffer: &'a [u8],
     extractor: &'a dyn SectionExtractor,
     next_offset: usize,
     error: bool,
     pending_extracted_sections: VecDeque<Result<Section, efi::Status>>,
+    authentication_status: u32,
+    // the encapsulation sections the sections of this iterator are extracted from.
+    extracted_from: Vec<SectionOrigin>,
+    // whether encapsulation sections are extracted at all.
+    extraction: bool,
+    limits: ExtractionLimits,
+    alignment: AlignmentPolicy,
+    depth: usize,
+    // bytes extracted so far from the file, including by the iterators of enclosing encapsulation sections.
+    extracted_size: usize,
 }
 
+#[cfg(feature = "alloc")]
 impl<'a> FileSectionIterator<'a> {
-    pub fn new(buffer: &'a [u8], extractor: &'a dyn SectionExtractor) -> Self {
+    pub fn new(buffer: &'a [u8], extractor: &'a dyn SectionExtractor, limits: ExtractionLimits) -> Self {
         FileSectionIterator {
             buffer,
             extractor,
             next_offset: 0,
             error: false,
             pending_extracted_sections: VecDeque::new(),
+            authentication_status: 0,
+            extracted_from: Vec::new(),
+            extraction: true,
+            limits,
+            alignment: AlignmentPolicy::default(),
+            depth: 0,
+            extracted_size: 0,
+        }
+    }
+
+    // Returns the encapsulation sections without extracting them, e.g. for callers that render or extract them on their
+    // own.
+    fn without_extraction(mut self) -> Self {
+        self.extraction = false;
+        self
+    }
+
+    // Aligns the sections, including the extracted ones, with the given policy.
+    fn with_alignment(mut self, alignment: AlignmentPolicy) -> Self {
+        self.alignment = alignment;
+        self
+    }
+
+    // Creates an iterator over the sections extracted from an encapsulation section with the given status and origin.
+    fn new_nested(&self, buffer: &'a [u8], authentication_status: u32, origin: SectionOrigin) -> Self {
+        let mut extracted_from = self.extracted_from.clone();
+        extracted_from.push(origin);
+        FileSectionIterator {
+            buffer,
+            extractor: self.extractor,
+            next_offset: 0,
+            error: false,
+            pending_extracted_sections: VecDeque::new(),
+            authentication_status,
+            extracted_from,
+            extraction: self.extraction,
+            limits: self.limits,
+            alignment: self.alignment,
+            depth: self.depth + 1,
+            extracted_size: self.extracted_size,
         }
     }
+
+    // Extracts an encapsulation section within the limits, returning the extracted buffer and its status.
+    fn extract(&mut self, section: &Section) -> Result<(Box<[u8]>, u32), efi::Status> {
+        if self.depth >= self.limits.max_depth {
+            Err(efi::Status::VOLUME_CORRUPTED)?;
+        }
+        let budget = self.limits.max_extracted_size - self.extracted_size;
+        if let SectionMetaData::Compression(header) = section.meta_data() {
+            if header.uncompressed_length as usize > budget {
+                Err(efi::Status::OUT_OF_RESOURCES)?;
+            }
+        }
+        let (extracted_buffer, extraction_status) = self.extractor.extract_with_limit(section, budget)?;
+        if extracted_buffer.len() > budget {
+            Err(efi::Status::OUT_OF_RESOURCES)?;
+        }
+        self.extracted_size += extracted_buffer.len();
+        Ok((extracted_buffer, extraction_status))
+    }
 }
 
+#[cfg(feature = "alloc")]
 impl<'a> Iterator for FileSectionIterator<'a> {
     type Item = Result<Section, efi::Status>;
 
@@ -886,15 +2139,38 @@ impl<'a> Iterator for FileSectionIterator<'a> {
         if self.buffer[self.next_offset..].len() < mem::size_of::<ffs::section::Header>() {
             return None;
         }
-        let result = Section::new(&self.buffer[self.next_offset..]);
-        if let Ok(ref section) = result {
-            if section.is_encapsulation() {
+        let mut result = Section::new(&self.buffer[self.next_offset..]);
+        if let Ok(ref mut section) = result {
+            section.authentication_status = self.authentication_status;
+            section.extracted_from.clone_from(&self.extracted_from);
+            if section.is_encapsulation() && self.extraction {
                 // attempt to extract the encapsulated section.
-                match self.extractor.extract(section) {
-                    Ok(extracted_buffer) => {
-                        for section in FileSectionIterator::new(&extracted_buffer, self.extractor) {
+                match self.extract(section) {
+                    Ok((extracted_buffer, _)) if extracted_buffer.is_empty() && section.requires_processing() => {
+                        // the data of this section is meaningless unprocessed, so it must not be skipped silently.
+                        self.pending_extracted_sections.push_back(Err(efi::Status::UNSUPPORTED));
+                    }
+                    Ok((extracted_buffer, extraction_status)) => {
+                        let authentication_status = match section.meta_data() {
+                            SectionMetaData::GuidDefined(header, _) => {
+                                FfsAuthStatus::nested(self.authentication_status, header.attributes, extraction_status)
+                            }
+                            _ => FfsAuthStatus::combine(self.authentication_status, extraction_status),
+                        };
+                        let section_definition = match section.meta_data() {
+                            SectionMetaData::GuidDefined(header, _) => Some(header.section_definition_guid),
+                            _ => None,
+                        };
+                        let origin = SectionOrigin {
+                            section_type: section.section_type_raw(),
+                            section_definition,
+                            offset: self.next_offset,
+                        };
+                        let mut nested = self.new_nested(&extracted_buffer, authentication_status, origin);
+                        for section in nested.by_ref() {
                             self.pending_extracted_sections.push_back(section);
                         }
+                        self.extracted_size = nested.extracted_size;
                     }
                     Err(err) => {
                         // on error, push the error on pending sections. This encapsulation section will be returned, and on the
@@ -903,7 +2179,7 @@ impl<'a> Iterator for FileSectionIterator<'a> {
                     }
                 }
             }
-            self.next_offset += align_up(section.section_size() as u64, 4) as usize;
+            self.next_offset = self.alignment.align(self.buffer, self.next_offset + section.section_size(), 4);
         } else {
             self.error = true;
         }
@@ -919,6 +2195,8 @@ mod unit_tests {
         error::Error,
         fs::{self, File},
         path::Path,
+        sync::Arc,
+        thread,
     };
 
     use core::{mem, sync::atomic::AtomicBool};
@@ -926,9 +2204,18 @@ mod unit_tests {
     use serde::Deserialize;
     use uuid::Uuid;
 
-    use crate::fw_fs::SectionMetaData;
+    use crate::{
+        checksum,
+        fw_fs::SectionMetaData,
+        parse::{AlignmentPolicy, Deviation, Diagnostic, ParseOptions, Strictness, ValidationLevel},
+    };
 
-    use super::{fv, FfsSectionType, FirmwareVolume, NullSectionExtractor, Section, SectionExtractor};
+    use super::{
+        builder, ffs, fv, raw, EfiFvFileAttributes, FfsAttributes, FfsFileRawType, FfsFileState, FfsFileSystem,
+        FfsFileType, FfsRawAttribute, FfsSectionType, FirmwareVolume, FirmwareVolumeOwned, FvExtEntry,
+        FvFileAttributes, FvFileRawAttribute, Fvb2RawAttributes, FvbAttributes2, FwFsError, NullSectionExtractor,
+        Section, SectionExtractor,
+    };
 
     #[derive(Debug, Deserialize)]
     struct TargetValues {
@@ -959,7 +2246,6 @@ mod unit_tests {
     fn test_firmware_volume_worker(
         fv: FirmwareVolume,
         mut expected_values: TargetValues,
-        extractor: &dyn SectionExtractor,
     ) -> Result<(), Box<dyn Error>> {
         let mut count = 0;
         for ffs_file in fv.file_iter() {
@@ -974,8 +2260,7 @@ mod unit_tests {
                     "[{file_name}] Error with the file attributes."
                 );
                 assert_eq!(target.size, ffs_file.size(), "[{file_name}] Error with the file size (Full size).");
-                let sections: Result<Vec<Section>, efi::Status> =
-                    ffs_file.section_iter_with_extractor(extractor).collect();
+                let sections: Result<Vec<Section>, efi::Status> = ffs_file.section_iter().collect();
                 let sections = sections.map_err(stringify)?;
                 for section in sections.iter().enumerate() {
                     println!("{:x?}", section);
@@ -995,49 +2280,165 @@ mod unit_tests {
                         );
                         assert_eq!(
                             target.size,
-                            section.section_data().len() as u64,
+                            section.raw_section_data().len() as u64,
                             "[{file_name}, section: {idx}] Error with the section Size"
                         );
                         assert_eq!(
                             target.text,
-                            extract_text_from_section(section),
+                            section.ui_name().ok(),
                             "[{file_name}, section: {idx}] Error with the section Text"
                         );
                     }
                 }
 
-                assert!(target.sections.is_empty(), "Some section use case has not been run.");
-            }
+                assert!(target.sections.is_empty(), "Some section use case has not been run.");
+            }
+        }
+        assert_eq!(
+            expected_values.total_number_of_files, count,
+            "The number of file found does not match the expected one."
+        );
+        assert!(expected_values.files_to_test.is_empty(), "Some file use case has not been run.");
+        Ok(())
+    }
+
+    #[test]
+    fn test_firmware_volume() -> Result<(), Box<dyn Error>> {
+        let root = Path::new(&env::var("CARGO_MANIFEST_DIR")?).join("test_resources");
+
+        let fv_bytes = fs::read(root.join("DXEFV.Fv"))?;
+        let fv = FirmwareVolume::new(&fv_bytes).unwrap();
+
+        let expected_values =
+            serde_yaml::from_reader::<File, TargetValues>(File::open(root.join("DXEFV_expected_values.yml"))?)?;
+
+        test_firmware_volume_worker(fv, expected_values)
+    }
+
+    #[test]
+    fn apriori_file_names_should_be_parsed() -> Result<(), Box<dyn Error>> {
+        let root = Path::new(&env::var("CARGO_MANIFEST_DIR")?).join("test_resources");
+
+        let fv_bytes = fs::read(root.join("DXEFV.Fv"))?;
+        let fv = FirmwareVolume::new(&fv_bytes).unwrap();
+        assert_eq!(fv.pei_apriori_file_names(), Ok(None));
+        let file_names = fv.dxe_apriori_file_names().unwrap().unwrap();
+        let file_names = file_names.iter().map(|x| Uuid::from_bytes_le(*x.as_bytes()).to_string()).collect::<Vec<_>>();
+        assert_eq!(file_names, ["9b680fce-ad6b-4f3a-b60b-f59899003443", "80cf7257-87ab-47f9-a3fe-d50b76d89541"]);
+
+        let fv_bytes = fs::read(root.join("GIGANTOR.Fv"))?;
+        let fv = FirmwareVolume::new(&fv_bytes).unwrap();
+        assert_eq!(fv.dxe_apriori_file_names(), Ok(None));
+        Ok(())
+    }
+
+    #[test]
+    fn type_filters_should_match_manual_filters() -> Result<(), Box<dyn Error>> {
+        let root = Path::new(&env::var("CARGO_MANIFEST_DIR")?).join("test_resources");
+
+        let fv_bytes = fs::read(root.join("DXEFV.Fv"))?;
+        let fv = FirmwareVolume::new(&fv_bytes).unwrap();
+
+        let drivers = fv.files_of_type(FfsFileType::Driver).collect::<Result<Vec<_>, _>>().unwrap();
+        let expected = fv.file_iter().filter(|f| f.as_ref().unwrap().file_type() == Some(FfsFileType::Driver)).count();
+        assert!(!drivers.is_empty());
+        assert_eq!(drivers.len(), expected);
+        assert_eq!(fv.files_of_type(FfsFileType::All).count(), fv.file_iter().count());
+
+        for driver in drivers {
+            let pe32 = driver.first_section_of_type(FfsSectionType::Pe32).unwrap().unwrap();
+            assert_eq!(pe32.section_type(), Some(FfsSectionType::Pe32));
+            assert_eq!(driver.sections_of_type(FfsSectionType::Pe32).count(), 1);
+            assert!(driver.first_section_of_type(FfsSectionType::PeiDepex).unwrap().is_none());
         }
-        assert_eq!(
-            expected_values.total_number_of_files, count,
-            "The number of file found does not match the expected one."
-        );
-        assert!(expected_values.files_to_test.is_empty(), "Some file use case has not been run.");
         Ok(())
     }
 
-    fn extract_text_from_section(section: &Section) -> Option<String> {
-        if section.section_type() == Some(FfsSectionType::UserInterface) {
-            let display_name_chars: Vec<u16> =
-                section.section_data().chunks(2).map(|x| u16::from_le_bytes(x.try_into().unwrap())).collect();
-            Some(String::from_utf16_lossy(&display_name_chars).trim_end_matches(char::from(0)).to_string())
-        } else {
-            None
+    #[test]
+    fn file_by_name_should_find_file() -> Result<(), Box<dyn Error>> {
+        let root = Path::new(&env::var("CARGO_MANIFEST_DIR")?).join("test_resources");
+
+        let fv_bytes = fs::read(root.join("DXEFV.Fv"))?;
+        let fv = FirmwareVolume::new(&fv_bytes).unwrap();
+
+        let file = fv.file_by_name(ffs::guid::EFI_APRIORI_GUID).unwrap();
+        assert_eq!(file.name(), ffs::guid::EFI_APRIORI_GUID);
+        assert_eq!(file.file_type(), Some(FfsFileType::FreeForm));
+
+        let file = fv.file_by_name_and_type_raw(ffs::guid::EFI_APRIORI_GUID, FfsFileRawType::FREEFORM);
+        assert!(file.is_some());
+        assert!(fv.file_by_name_and_type_raw(ffs::guid::EFI_APRIORI_GUID, FfsFileRawType::DRIVER).is_none());
+        assert!(fv.file_by_name(ffs::guid::PEI_APRIORI_FILE_NAME_GUID).is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn owned_volume_should_be_shared_between_threads() -> Result<(), Box<dyn Error>> {
+        let root = Path::new(&env::var("CARGO_MANIFEST_DIR")?).join("test_resources");
+
+        let fv_bytes = fs::read(root.join("DXEFV.Fv"))?;
+        let file_count = FirmwareVolume::new(&fv_bytes).unwrap().file_iter().count();
+
+        let fv = Arc::new(FirmwareVolumeOwned::new(fv_bytes.clone()).unwrap());
+        let workers = (0..4)
+            .map(|_| {
+                let fv = fv.clone();
+                thread::spawn(move || fv.volume().file_iter().filter(|file| file.is_ok()).count())
+            })
+            .collect::<Vec<_>>();
+        for worker in workers {
+            assert_eq!(worker.join().unwrap(), file_count);
         }
+
+        // Borrowed volumes can be shared with scoped threads.
+        let borrowed = FirmwareVolume::new(&fv_bytes).unwrap();
+        thread::scope(|scope| {
+            scope.spawn(|| assert_eq!(borrowed.file_iter().count(), file_count));
+        });
+
+        assert_eq!(FirmwareVolumeOwned::new(vec![0u8; 0x100]).unwrap_err(), efi::Status::VOLUME_CORRUPTED);
+        assert_eq!(Arc::try_unwrap(fv).unwrap().into_data()[..], fv_bytes[..]);
+        Ok(())
     }
 
     #[test]
-    fn test_firmware_volume() -> Result<(), Box<dyn Error>> {
+    fn volume_should_be_created_from_address() -> Result<(), Box<dyn Error>> {
         let root = Path::new(&env::var("CARGO_MANIFEST_DIR")?).join("test_resources");
 
         let fv_bytes = fs::read(root.join("DXEFV.Fv"))?;
         let fv = FirmwareVolume::new(&fv_bytes).unwrap();
 
-        let expected_values =
-            serde_yaml::from_reader::<File, TargetValues>(File::open(root.join("DXEFV_expected_values.yml"))?)?;
+        let from_address =
+            unsafe { FirmwareVolume::new_from_address(fv_bytes.as_ptr() as efi::PhysicalAddress) }.unwrap();
+        assert_eq!(from_address.size(), fv.size());
+        assert_eq!(from_address.data().as_ptr(), fv_bytes.as_ptr());
+        assert_eq!(from_address.file_iter().count(), fv.file_iter().count());
+
+        let not_a_volume = vec![0u64; 0x20];
+        assert_eq!(
+            unsafe { FirmwareVolume::new_from_address(not_a_volume.as_ptr() as efi::PhysicalAddress) }.unwrap_err(),
+            efi::Status::VOLUME_CORRUPTED
+        );
+        assert_eq!(unsafe { FirmwareVolume::new_from_address(0) }.unwrap_err(), efi::Status::INVALID_PARAMETER);
+        Ok(())
+    }
 
-        test_firmware_volume_worker(fv, expected_values, &NullSectionExtractor {})
+    #[test]
+    fn ui_name_should_be_decoded() -> Result<(), Box<dyn Error>> {
+        let root = Path::new(&env::var("CARGO_MANIFEST_DIR")?).join("test_resources");
+
+        let fv_bytes = fs::read(root.join("DXEFV.Fv"))?;
+        let fv = FirmwareVolume::new(&fv_bytes).unwrap();
+
+        let dxe_core = fv.files_of_type(FfsFileType::DxeCore).next().unwrap().unwrap();
+        assert_eq!(dxe_core.ui_name().unwrap().as_deref(), Some("DxeRust"));
+
+        // The apriori file has no USER_INTERFACE section, and other sections have no UI name.
+        let apriori = fv.file_by_name(ffs::guid::EFI_APRIORI_GUID).unwrap();
+        assert_eq!(apriori.ui_name().unwrap(), None);
+        let pe32 = dxe_core.first_section_of_type(FfsSectionType::Pe32).unwrap().unwrap();
+        assert_eq!(pe32.ui_name(), Err(efi::Status::INVALID_PARAMETER));
+        Ok(())
     }
 
     #[test]
@@ -1050,7 +2451,7 @@ mod unit_tests {
         let expected_values =
             serde_yaml::from_reader::<File, TargetValues>(File::open(root.join("GIGANTOR_expected_values.yml"))?)?;
 
-        test_firmware_volume_worker(fv, expected_values, &NullSectionExtractor {})
+        test_firmware_volume_worker(fv, expected_values)
     }
 
     #[test]
@@ -1090,8 +2491,20 @@ mod unit_tests {
 
         let fv = FirmwareVolume::new(&fv_bytes).unwrap();
 
-        test_firmware_volume_worker(fv, expected_values, &test_extractor)?;
+        // without extraction, the brotli section is returned as a whole.
+        test_firmware_volume_worker(fv.clone(), expected_values)?;
 
+        // an extractor that does not extract the brotli section, which requires processing, surfaces an error.
+        let mut unprocessed = 0;
+        for file in fv.file_iter() {
+            let sections = file.unwrap().section_iter_with_extractor(&test_extractor).collect::<Vec<_>>();
+            if let Some(Err(err)) = sections.last() {
+                assert_eq!(err, &efi::Status::UNSUPPORTED);
+                assert!(sections[sections.len() - 2].as_ref().unwrap().requires_processing());
+                unprocessed += 1;
+            }
+        }
+        assert_eq!(unprocessed, 1);
         assert!(test_extractor.invoked.load(core::sync::atomic::Ordering::SeqCst));
 
         Ok(())
@@ -1185,7 +2598,7 @@ mod unit_tests {
         let a_ptr = &a as *const A;
 
         unsafe {
-            assert_eq!((&(*a_ptr).block_map).as_ptr(), a_ptr.offset(1) as *const fv::BlockMapEntry);
+            assert_eq!((*a_ptr).block_map.as_ptr(), a_ptr.offset(1) as *const fv::BlockMapEntry);
         }
     }
 
@@ -1265,8 +2678,10 @@ mod unit_tests {
             }
             otherwise_bad => panic!("invalid section: {:x?}", otherwise_bad),
         }
+        assert_eq!(section.version().unwrap(), (0, String::from("1.0")));
+        assert_eq!(Section::new(&empty_pe32).unwrap().version(), Err(efi::Status::INVALID_PARAMETER));
 
-        let empty_freeform_subtype: [u8; 024] = [
+        let empty_freeform_subtype: [u8; 24] = [
             0x18, 0x00, 0x00, 0x18, //Header
             0x01, 0x23, 0x45, 0x67, 0x89, 0xAB, 0xCD, 0xEF, 0x01, 0x23, 0x45, 0x67, 0x89, 0xAB, 0xCD, 0xEF, //GUID
             0x04, 0x15, 0x19, 0x80, //Data
@@ -1287,4 +2702,873 @@ mod unit_tests {
 
         Ok(())
     }
+
+    #[test]
+    fn file_should_tolerate_deviations_per_strictness() {
+        // empty RAW file with a zero name, the legacy fixed checksum (0x5a) and the MARKED_FOR_UPDATE state.
+        let mut file_bytes: [u8; 24] = [0; 24];
+        file_bytes[16..24].copy_from_slice(&[0xe7, 0x5a, 0x01, 0x00, 0x18, 0x00, 0x00, 0x0f]);
+        let name = efi::Guid::from_bytes(&[0; 16]);
+
+        assert_eq!(super::File::new(&file_bytes).unwrap_err(), efi::Status::VOLUME_CORRUPTED);
+
+        let file = super::File::new_with_options(&file_bytes, &ParseOptions::COMPATIBLE).unwrap();
+        assert_eq!(
+            file.deviations(),
+            [Deviation::FileMarkedForUpdate { name }, Deviation::FileFixedChecksum { name, checksum: 0x5a }]
+        );
+
+        let file = super::File::new_with_options(&file_bytes, &ParseOptions::PERMISSIVE).unwrap();
+        assert!(file.deviations().is_empty());
+
+        // only the fixed checksum deviates once the file is back in the DATA_VALID state.
+        file_bytes[23] = 0x07;
+        assert_eq!(super::File::new(&file_bytes).unwrap_err(), efi::Status::VOLUME_CORRUPTED);
+        let file = super::File::new_with_options(&file_bytes, &ParseOptions::COMPATIBLE).unwrap();
+        assert_eq!(file.deviations(), [Deviation::FileFixedChecksum { name, checksum: 0x5a }]);
+    }
+
+    #[test]
+    fn diagnostics_should_report_oddities() -> Result<(), Box<dyn Error>> {
+        let root = Path::new(&env::var("CARGO_MANIFEST_DIR")?).join("test_resources");
+
+        // the FV data after the ext header is not 8-byte aligned, and the revision is bumped past EFI_FVH_REVISION.
+        let mut fv_bytes = fs::read(root.join("DXEFV.Fv"))?;
+        let fv_header = fv_bytes.as_mut_ptr() as *mut fv::Header;
+        unsafe {
+            (*fv_header).revision = 3;
+            (*fv_header).checksum = (*fv_header).checksum.wrapping_sub(0x100);
+        };
+        let mut diagnostics = Vec::new();
+        let fv = FirmwareVolume::new_with_diagnostics(&fv_bytes, &ParseOptions::default(), &mut |diagnostic| {
+            diagnostics.push(diagnostic)
+        })
+        .unwrap();
+        assert_eq!(diagnostics, [Diagnostic::FvRevision { revision: 3 }, Diagnostic::UnalignedFvData { offset: 0x74 }]);
+
+        let mut diagnostics = Vec::new();
+        assert!(fv.file_iter_with_diagnostics(&mut |diagnostic| diagnostics.push(diagnostic)).all(|x| x.is_ok()));
+        assert!(diagnostics.is_empty());
+
+        // empty RAW file with the legacy fixed checksum (0x5a).
+        let mut file_bytes: [u8; 24] = [0; 24];
+        file_bytes[16..24].copy_from_slice(&[0xe7, 0x5a, 0x01, 0x00, 0x18, 0x00, 0x00, 0x07]);
+        let name = efi::Guid::from_bytes(&[0; 16]);
+        let mut diagnostics = Vec::new();
+        super::File::new_with_diagnostics(&file_bytes, &ParseOptions::COMPATIBLE, &mut |diagnostic| {
+            diagnostics.push(diagnostic)
+        })
+        .unwrap();
+        assert_eq!(
+            diagnostics,
+            [
+                Diagnostic::EmptyFile { name },
+                Diagnostic::Deviation(Deviation::FileFixedChecksum { name, checksum: 0x5a })
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn typed_attributes_should_decode_alignments() {
+        let attributes = FfsAttributes::FIXED | FfsAttributes::from_bits_retain(0x08);
+        assert_eq!(attributes.alignment(), 16);
+        assert_eq!(format!("{attributes:?}"), "Attributes(FIXED | 0x8)");
+        let attributes =
+            FfsAttributes::from_bits_retain(FfsRawAttribute::DATA_ALIGNMENT) | FfsAttributes::DATA_ALIGNMENT_2;
+        assert_eq!(attributes.alignment(), 0x1000000);
+        assert_eq!(format!("{:?}", FfsAttributes::empty()), "Attributes(0x0)");
+
+        let attributes = FvFileAttributes::from_bits_retain(12) | FvFileAttributes::MEMORY_MAPPED;
+        assert_eq!(attributes.alignment(), 0x1000);
+        assert!(!attributes.contains(FvFileAttributes::FIXED | FvFileAttributes::MEMORY_MAPPED));
+        assert!(attributes.intersects(FvFileAttributes::FIXED | FvFileAttributes::MEMORY_MAPPED));
+        assert_eq!(EfiFvFileAttributes::from(attributes), 12 | FvFileRawAttribute::MEMORY_MAPPED);
+
+        let fv_bytes = build_ffs1_volume(b"data");
+        let fv = FirmwareVolume::new(&fv_bytes).unwrap();
+        assert_eq!(
+            fv.attributes().alignment(),
+            1 << ((fv.attributes().bits() & Fvb2RawAttributes::ALIGNMENT_2G) >> 16)
+        );
+        let file = fv.file_iter().next().unwrap().unwrap();
+        // TAIL_PRESENT shares its bit with LARGE_FILE, which the attributes keep.
+        assert_eq!(file.attributes(), FfsAttributes::CHECKSUM | FfsAttributes::LARGE_FILE);
+        assert_eq!(file.fv_attributes(), FvFileAttributes::empty());
+    }
+
+    #[test]
+    fn file_checksums_should_be_verified() {
+        // RAW file with the CHECKSUM attribute and four bytes of data.
+        let mut file_bytes: [u8; 28] = [0; 28];
+        file_bytes[16..24].copy_from_slice(&[0xa3, 0xf6, 0x01, 0x40, 0x1c, 0x00, 0x00, 0x07]);
+        file_bytes[24..].copy_from_slice(&[1, 2, 3, 4]);
+
+        let file = super::File::new(&file_bytes).unwrap();
+        assert_eq!(file.verify_checksums(), Ok(()));
+
+        // bad data checksum.
+        file_bytes[27] = 5;
+        assert_eq!(super::File::new(&file_bytes).unwrap_err(), efi::Status::VOLUME_CORRUPTED);
+        let options = ParseOptions { verify_checksums: false, ..Default::default() };
+        let file = super::File::new_with_options(&file_bytes, &options).unwrap();
+        assert_eq!(file.verify_checksums(), Err(efi::Status::VOLUME_CORRUPTED));
+
+        // bad header checksum.
+        file_bytes[27] = 4;
+        file_bytes[16] = 0;
+        assert_eq!(super::File::new(&file_bytes).unwrap_err(), efi::Status::VOLUME_CORRUPTED);
+        let file = super::File::new_with_options(&file_bytes, &options).unwrap();
+        assert_eq!(file.verify_checksums(), Err(efi::Status::VOLUME_CORRUPTED));
+    }
+
+    #[test]
+    fn file_state_should_follow_erase_polarity() {
+        assert_eq!(FfsFileState::from_raw(0x00, false), None);
+        assert_eq!(FfsFileState::from_raw(0xff, true), None);
+        assert_eq!(FfsFileState::from_raw(0x01, false), Some(FfsFileState::HeaderConstruction));
+        assert_eq!(FfsFileState::from_raw(0x03, false), Some(FfsFileState::HeaderValid));
+        assert_eq!(FfsFileState::from_raw(0x07, false), Some(FfsFileState::DataValid));
+        assert_eq!(FfsFileState::from_raw(0xf8, true), Some(FfsFileState::DataValid));
+        assert_eq!(FfsFileState::from_raw(0x0f, false), Some(FfsFileState::MarkedForUpdate));
+        assert_eq!(FfsFileState::from_raw(0xe8, true), Some(FfsFileState::Deleted));
+        assert_eq!(FfsFileState::from_raw(0xd8, true), Some(FfsFileState::HeaderInvalid));
+    }
+
+    #[test]
+    fn file_iter_should_optionally_skip_deleted_files() -> Result<(), Box<dyn Error>> {
+        let root = Path::new(&env::var("CARGO_MANIFEST_DIR")?).join("test_resources");
+        let mut fv_bytes = fs::read(root.join("DXEFV.Fv"))?;
+        let expected_values =
+            serde_yaml::from_reader::<File, TargetValues>(File::open(root.join("DXEFV_expected_values.yml"))?)?;
+
+        let (first_file_name, state_offset, erase_polarity) = {
+            let fv = FirmwareVolume::new(&fv_bytes).unwrap();
+            let first_file = fv.file_iter().next().unwrap().unwrap();
+            assert_eq!(first_file.state(), Some(FfsFileState::DataValid));
+            (first_file.name(), fv.data_offset + 23, fv.erase_byte != 0)
+        };
+
+        // mark the first file deleted. The state is not covered by the header checksum.
+        if erase_polarity {
+            fv_bytes[state_offset] &= !super::FfsFileRawState::DELETED;
+        } else {
+            fv_bytes[state_offset] |= super::FfsFileRawState::DELETED;
+        }
+
+        let fv = FirmwareVolume::new(&fv_bytes).unwrap();
+        assert_eq!(fv.file_iter().next().unwrap().unwrap_err(), efi::Status::VOLUME_CORRUPTED);
+
+        let options = ParseOptions { skip_invalid_files: true, ..Default::default() };
+        let fv = FirmwareVolume::new_with_options(&fv_bytes, &options).unwrap();
+        let files = fv.file_iter().collect::<Result<Vec<_>, efi::Status>>().unwrap();
+        assert_eq!(files.len() as u32, expected_values.total_number_of_files - 1);
+        assert!(files.iter().all(|file| file.name() != first_file_name));
+        Ok(())
+    }
+
+    #[test]
+    fn find_all_should_locate_volumes_in_flash_image() -> Result<(), Box<dyn Error>> {
+        let root = Path::new(&env::var("CARGO_MANIFEST_DIR")?).join("test_resources");
+        let compact = fs::read(root.join("FVMAIN_COMPACT.Fv"))?;
+        let dxe = fs::read(root.join("DXEFV.Fv"))?;
+
+        // erased space, an FV, a header with a bad checksum, then another FV.
+        let mut image = vec![0xffu8; 0x1000];
+        image.extend_from_slice(&compact);
+        let corrupt_offset = image.len();
+        image.extend_from_slice(&dxe[..0x1000]);
+        image[corrupt_offset + 0x30] ^= 0xff;
+        let dxe_offset = image.len();
+        image.extend_from_slice(&dxe);
+
+        let volumes = FirmwareVolume::find_all(&image).collect::<Vec<_>>();
+        assert_eq!(volumes.iter().map(|(offset, _)| *offset).collect::<Vec<_>>(), [0x1000, dxe_offset]);
+        assert_eq!(volumes[0].1.size(), compact.len() as u64);
+        assert_eq!(volumes[1].1.data(), &dxe[..]);
+
+        // a truncated FV is not returned.
+        assert_eq!(FirmwareVolume::find_all(&image[..image.len() - 1]).count(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn free_space_should_follow_file_layout() -> Result<(), Box<dyn Error>> {
+        let root = Path::new(&env::var("CARGO_MANIFEST_DIR")?).join("test_resources");
+        let mut fv_bytes = fs::read(root.join("DXEFV.Fv"))?;
+
+        let (first_file_size, state_offset, erase_polarity, free_space) = {
+            let fv = FirmwareVolume::new(&fv_bytes).unwrap();
+            let last_file = fv.file_iter().last().unwrap().unwrap();
+            let used_size = (last_file.data().as_ptr() as usize - fv_bytes.as_ptr() as usize) as u64 + last_file.size();
+            assert_eq!(fv.used_size().unwrap(), used_size);
+
+            // only alignment padding lies between the files.
+            let free_space = fv.free_space().unwrap();
+            let file_count = fv.file_iter().count() as u64;
+            assert!(free_space >= fv.size() - used_size);
+            assert!(free_space < fv.size() - used_size + 8 * file_count);
+            assert!(fv.largest_free_block().unwrap() >= fv.size() - used_size);
+            assert!(fv.largest_free_block().unwrap() <= free_space);
+
+            let first_file = fv.file_iter().next().unwrap().unwrap();
+            (first_file.size(), fv.data_offset + 23, fv.erase_byte != 0, free_space)
+        };
+
+        // a deleted file that is skipped is free space.
+        if erase_polarity {
+            fv_bytes[state_offset] &= !super::FfsFileRawState::DELETED;
+        } else {
+            fv_bytes[state_offset] |= super::FfsFileRawState::DELETED;
+        }
+        let options = ParseOptions { skip_invalid_files: true, ..Default::default() };
+        let fv = FirmwareVolume::new_with_options(&fv_bytes, &options).unwrap();
+        assert!(fv.free_space().unwrap() >= free_space + first_file_size);
+        assert!(fv.largest_free_block().unwrap() >= first_file_size);
+
+        let fv = FirmwareVolume::new(&fv_bytes).unwrap();
+        assert_eq!(fv.free_space(), Err(efi::Status::VOLUME_CORRUPTED));
+        Ok(())
+    }
+
+    #[test]
+    fn file_iter_should_report_corrupt_file_mid_volume() -> Result<(), Box<dyn Error>> {
+        let root = Path::new(&env::var("CARGO_MANIFEST_DIR")?).join("test_resources");
+        let mut fv_bytes = fs::read(root.join("DXEFV.Fv"))?;
+
+        let third_file_offset = {
+            let fv = FirmwareVolume::new(&fv_bytes).unwrap();
+            let third_file = fv.file_iter().nth(2).unwrap().unwrap();
+            third_file.data().as_ptr() as usize - fv_bytes.as_ptr() as usize
+        };
+
+        // corrupt the header checksum of the third file.
+        fv_bytes[third_file_offset + 16] ^= 0xff;
+
+        let fv = FirmwareVolume::new(&fv_bytes).unwrap();
+        let results = fv.file_iter().collect::<Vec<_>>();
+        assert_eq!(results.len(), 3);
+        assert!(results[..2].iter().all(|file| file.is_ok()));
+        assert_eq!(results[2].as_ref().unwrap_err(), &efi::Status::VOLUME_CORRUPTED);
+
+        let storage_fv = super::storage::StorageFirmwareVolume::new(fv_bytes.as_slice()).unwrap();
+        let results = storage_fv.file_iter().collect::<Vec<_>>();
+        assert_eq!(results.len(), 3);
+        assert!(matches!(
+            results[2].as_ref().unwrap_err(),
+            super::storage::StorageError::Malformed(FwFsError::BadFileHeaderChecksum { .. })
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn try_apis_should_describe_errors() -> Result<(), Box<dyn Error>> {
+        let root = Path::new(&env::var("CARGO_MANIFEST_DIR")?).join("test_resources");
+        let mut fv_bytes = fs::read(root.join("DXEFV.Fv"))?;
+
+        let error = FirmwareVolume::try_new(&fv_bytes[..0x10], &ParseOptions::default()).unwrap_err();
+        assert_eq!(error, FwFsError::BufferTooSmall { needed: mem::size_of::<fv::Header>(), available: 0x10 });
+        assert_eq!(efi::Status::from(error), efi::Status::INVALID_PARAMETER);
+
+        let signature = fv_bytes[0x28..0x2c].to_vec();
+        fv_bytes[0x28] ^= 0xff;
+        let error = FirmwareVolume::try_new(&fv_bytes, &ParseOptions::default()).unwrap_err();
+        assert!(matches!(error, FwFsError::BadSignature { .. }));
+        assert_eq!(efi::Status::from(error), efi::Status::VOLUME_CORRUPTED);
+        fv_bytes[0x28..0x2c].copy_from_slice(&signature);
+
+        let third_file_offset = {
+            let fv = FirmwareVolume::new(&fv_bytes).unwrap();
+            let third_file = fv.file_iter().nth(2).unwrap().unwrap();
+            third_file.data().as_ptr() as usize - fv_bytes.as_ptr() as usize
+        };
+        fv_bytes[third_file_offset + 16] ^= 0xff;
+
+        let fv = FirmwareVolume::try_new(&fv_bytes, &ParseOptions::default()).unwrap();
+        let error = fv.try_file_iter().nth(2).unwrap().unwrap_err();
+        let FwFsError::BadFileHeaderChecksum { name } = error else { panic!("unexpected error {error:?}") };
+        assert_eq!(error.to_string(), format!("file {name:?} has bad header checksum"));
+
+        let error = Section::try_new(&[0x10, 0x00, 0x00, ffs::section::raw_type::RAW]).unwrap_err();
+        assert_eq!(error, FwFsError::BadSectionSize { section_type: ffs::section::raw_type::RAW, size: 0x10 });
+        assert_eq!(
+            Section::new(&[0x10, 0x00, 0x00, ffs::section::raw_type::RAW]).unwrap_err(),
+            efi::Status::VOLUME_CORRUPTED
+        );
+        Ok(())
+    }
+
+    // Builds a revision 1 FFS1 volume holding a single RAW file with the CHECKSUM and TAIL_PRESENT attributes.
+    pub(super) fn build_ffs1_volume(content: &[u8]) -> Vec<u8> {
+        let header_length = mem::size_of::<fv::Header>() + 2 * mem::size_of::<fv::BlockMapEntry>();
+        let file_size = mem::size_of::<super::file::Header>() + content.len() + 2;
+        let fv_length = header_length + super::align_up(file_size as u64, 8) as usize;
+
+        let mut fv_bytes = vec![0u8; fv_length];
+        fv_bytes[16..32].copy_from_slice(super::ffs::guid::EFI_FIRMWARE_FILE_SYSTEM_GUID.as_bytes());
+        fv_bytes[32..40].copy_from_slice(&(fv_length as u64).to_le_bytes());
+        fv_bytes[40..44].copy_from_slice(b"_FVH");
+        fv_bytes[48..50].copy_from_slice(&(header_length as u16).to_le_bytes());
+        fv_bytes[55] = 1;
+        fv_bytes[56..60].copy_from_slice(&1u32.to_le_bytes());
+        fv_bytes[60..64].copy_from_slice(&(fv_length as u32).to_le_bytes());
+        let checksum = checksum::compute16(&fv_bytes[..header_length]);
+        fv_bytes[50..52].copy_from_slice(&checksum.to_le_bytes());
+
+        let file = &mut fv_bytes[header_length..header_length + file_size];
+        file[..16].copy_from_slice(&[0x5a; 16]);
+        file[18] = super::FfsFileRawType::RAW;
+        file[19] = super::FfsRawAttribute::CHECKSUM | super::FfsRawAttribute::ffs1::TAIL_PRESENT;
+        file[20..23].copy_from_slice(&(file_size as u32).to_le_bytes()[..3]);
+        file[24..24 + content.len()].copy_from_slice(content);
+        file[16] = checksum::compute8(&file[..24]);
+        file[17] = checksum::compute8(content);
+        file[23] = super::FfsFileRawState::HEADER_CONSTRUCTION
+            | super::FfsFileRawState::HEADER_VALID
+            | super::FfsFileRawState::DATA_VALID;
+        let tail = !u16::from_le_bytes([file[16], file[17]]);
+        file[file_size - 2..].copy_from_slice(&tail.to_le_bytes());
+        fv_bytes
+    }
+
+    #[test]
+    fn ffs1_volume_should_parse() {
+        let fv_bytes = build_ffs1_volume(&[1, 2, 3, 4]);
+        let fv = FirmwareVolume::new(&fv_bytes).unwrap();
+        assert_eq!(fv.file_system(), super::FfsFileSystem::Ffs1);
+        assert_eq!(fv.fv_name(), None);
+
+        let files = fv.file_iter().collect::<Result<Vec<_>, efi::Status>>().unwrap();
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].file_system(), super::FfsFileSystem::Ffs1);
+        assert_eq!(files[0].size(), 30);
+        assert_eq!(files[0].content(), &[1, 2, 3, 4]);
+        assert_eq!(files[0].verify_checksums(), Ok(()));
+
+        // a bad tail fails verification.
+        let mut fv_bytes = fv_bytes;
+        let tail_offset = fv_bytes.len() - 4;
+        fv_bytes[tail_offset] ^= 0xff;
+        let fv = FirmwareVolume::new(&fv_bytes).unwrap();
+        assert_eq!(fv.file_iter().next().unwrap().unwrap_err(), efi::Status::VOLUME_CORRUPTED);
+    }
+
+    // Builds an FFS2 file without the CHECKSUM attribute holding the given content.
+    pub(super) fn build_ffs2_file(name: [u8; 16], file_type: u8, content: &[u8]) -> Vec<u8> {
+        let file_size = mem::size_of::<super::file::Header>() + content.len();
+        let mut file = vec![0u8; file_size];
+        file[..16].copy_from_slice(&name);
+        file[18] = file_type;
+        file[20..23].copy_from_slice(&(file_size as u32).to_le_bytes()[..3]);
+        file[16] = checksum::compute8(&file[..24]);
+        file[17] = 0xaa;
+        file[23] = super::FfsFileRawState::HEADER_CONSTRUCTION
+            | super::FfsFileRawState::HEADER_VALID
+            | super::FfsFileRawState::DATA_VALID;
+        file[24..].copy_from_slice(content);
+        file
+    }
+
+    // Builds an FFS2 volume holding the given file.
+    pub(super) fn build_ffs2_volume(file: &[u8]) -> Vec<u8> {
+        let header_length = mem::size_of::<fv::Header>() + 2 * mem::size_of::<fv::BlockMapEntry>();
+        let fv_length = header_length + super::align_up(file.len() as u64, 8) as usize;
+
+        let mut fv_bytes = vec![0u8; fv_length];
+        fv_bytes[16..32].copy_from_slice(super::ffs::guid::EFI_FIRMWARE_FILE_SYSTEM2_GUID.as_bytes());
+        fv_bytes[32..40].copy_from_slice(&(fv_length as u64).to_le_bytes());
+        fv_bytes[40..44].copy_from_slice(b"_FVH");
+        fv_bytes[48..50].copy_from_slice(&(header_length as u16).to_le_bytes());
+        fv_bytes[55] = 2;
+        fv_bytes[56..60].copy_from_slice(&1u32.to_le_bytes());
+        fv_bytes[60..64].copy_from_slice(&(fv_length as u32).to_le_bytes());
+        let checksum = checksum::compute16(&fv_bytes[..header_length]);
+        fv_bytes[50..52].copy_from_slice(&checksum.to_le_bytes());
+        fv_bytes[header_length..header_length + file.len()].copy_from_slice(file);
+        fv_bytes
+    }
+
+    #[test]
+    fn file_alignment_should_honor_weak_alignment() {
+        // the file data is at offset 0x60 of the FV.
+        let mut file = build_ffs2_file([0x11; 16], FfsFileRawType::RAW, &[0; 8]);
+        let aligned_fv = |file: &[u8], fv_attributes: u32| {
+            let mut fv_bytes = build_ffs2_volume(file);
+            fv_bytes[44..48].copy_from_slice(&fv_attributes.to_le_bytes());
+            fv_bytes[50..52].fill(0);
+            let checksum = checksum::compute16(&fv_bytes[..0x48]);
+            fv_bytes[50..52].copy_from_slice(&checksum.to_le_bytes());
+            fv_bytes
+        };
+        let is_properly_aligned = |fv_bytes: &[u8]| {
+            let fv = FirmwareVolume::new(fv_bytes).unwrap();
+            let file = fv.file_iter().next().unwrap().unwrap();
+            (file.required_alignment(), file.is_properly_aligned(&fv))
+        };
+
+        let fv_bytes = aligned_fv(&file, 0);
+        assert_eq!(is_properly_aligned(&fv_bytes), (1, true));
+
+        // keeps the header checksum, which covers the attributes.
+        let set_attributes = |file: &mut [u8], attributes: u8| {
+            file[16] = file[16].wrapping_add(file[19]).wrapping_sub(attributes);
+            file[19] = attributes;
+        };
+
+        // 16-byte data alignment: aligned in an FV that is 16-byte aligned, or weakly aligned.
+        set_attributes(&mut file, 1 << 3);
+        assert_eq!(is_properly_aligned(&aligned_fv(&file, 0)), (16, false));
+        assert_eq!(is_properly_aligned(&aligned_fv(&file, Fvb2RawAttributes::ALIGNMENT_16)), (16, true));
+        assert_eq!(is_properly_aligned(&aligned_fv(&file, Fvb2RawAttributes::WEAK_ALIGNMENT)), (16, true));
+
+        // 512-byte data alignment: the file data is not aligned in the FV, whatever the FV alignment.
+        set_attributes(&mut file, 3 << 3);
+        let fv_bytes = aligned_fv(&file, Fvb2RawAttributes::ALIGNMENT_4K | Fvb2RawAttributes::WEAK_ALIGNMENT);
+        assert_eq!(is_properly_aligned(&fv_bytes), (512, false));
+
+        // a file is not aligned in another FV.
+        let other_fv = aligned_fv(&file, 0);
+        let fv = FirmwareVolume::new(&fv_bytes).unwrap();
+        let file = fv.file_iter().next().unwrap().unwrap();
+        assert!(!file.is_properly_aligned(&FirmwareVolume::new(&other_fv).unwrap()));
+    }
+
+    #[test]
+    fn lbas_should_follow_block_map() {
+        // two 0x100-byte blocks, then three 0x200-byte blocks.
+        let block_map =
+            [fv::BlockMapEntry { num_blocks: 2, length: 0x100 }, fv::BlockMapEntry { num_blocks: 3, length: 0x200 }];
+        let fv_bytes = builder::blank_volume(FfsFileSystem::Ffs2, &block_map, FvbAttributes2::ERASE_POLARITY).unwrap();
+        let fv = FirmwareVolume::new(&fv_bytes).unwrap();
+
+        assert_eq!(fv.lba_count(), 5);
+        assert_eq!(fv.total_block_size(), 0x800);
+        assert_eq!(
+            fv.lba_iter().collect::<Vec<_>>(),
+            [(0, 0, 0x100), (1, 0x100, 0x100), (2, 0x200, 0x200), (3, 0x400, 0x200), (4, 0x600, 0x200)]
+        );
+        assert_eq!(fv.lba_info(1), Ok((0x100, 0x100, 1)));
+        assert_eq!(fv.lba_info(3), Ok((0x400, 0x200, 2)));
+        assert_eq!(fv.lba_info(5), Err(efi::Status::INVALID_PARAMETER));
+    }
+
+    #[test]
+    fn ext_header_entries_should_parse() {
+        let mut entries = Vec::new();
+        entries.extend_from_slice(&[8, 0, 3, 0, 0x00, 0x10, 0, 0]);
+        entries.extend_from_slice(&[23, 0, 2, 0]);
+        entries.extend_from_slice(&[0x22; 16]);
+        entries.extend_from_slice(&[1, 2, 3]);
+        entries.extend_from_slice(&[24, 0, 1, 0, 0x04, 0, 0, 0]);
+        entries.extend_from_slice(&[0x33; 16]);
+        entries.extend_from_slice(&[4, 0, 0x99, 0]);
+
+        // the extended header, holding the entries, directly follows the volume header.
+        let block_map = [fv::BlockMapEntry { num_blocks: 1, length: 0x100 }];
+        let mut fv_bytes =
+            builder::blank_volume(FfsFileSystem::Ffs2, &block_map, FvbAttributes2::ERASE_POLARITY).unwrap();
+        let header_length = mem::size_of::<fv::Header>() + 2 * mem::size_of::<fv::BlockMapEntry>();
+        let ext_header_size = mem::size_of::<fv::ExtHeader>() + entries.len();
+        let ext_header = &mut fv_bytes[header_length..header_length + ext_header_size];
+        ext_header[..16].copy_from_slice(&[0x11; 16]);
+        ext_header[16..20].copy_from_slice(&(ext_header_size as u32).to_le_bytes());
+        ext_header[20..].copy_from_slice(&entries);
+        let ext_header_offset = fv::header_offset::EXT_HEADER_OFFSET;
+        fv_bytes[ext_header_offset..ext_header_offset + 2].copy_from_slice(&(header_length as u16).to_le_bytes());
+        FirmwareVolume::recompute_checksum(&mut fv_bytes).unwrap();
+
+        let fv = FirmwareVolume::new(&fv_bytes).unwrap();
+        let ext_header = fv.ext_header().unwrap();
+        assert_eq!(ext_header.fv_name(), efi::Guid::from_bytes(&[0x11; 16]));
+        assert_eq!(ext_header.used_size(), Some(0x1000));
+        let entries = ext_header.entries().collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(
+            entries,
+            [
+                FvExtEntry::UsedSize(0x1000),
+                FvExtEntry::Guid { format_type: efi::Guid::from_bytes(&[0x22; 16]), data: &[1, 2, 3] },
+                FvExtEntry::Oem { type_mask: 0x04, types: vec![efi::Guid::from_bytes(&[0x33; 16])] },
+                FvExtEntry::Unknown { entry_type: 0x99, data: &[] },
+            ]
+        );
+
+        // An entry that overruns the extended header is corrupt.
+        fv_bytes[header_length + 20] = 0xff;
+        let fv = FirmwareVolume::new(&fv_bytes).unwrap();
+        let entries = fv.ext_header().unwrap().entries().collect::<Vec<_>>();
+        assert_eq!(entries, [Err(efi::Status::VOLUME_CORRUPTED)]);
+        assert_eq!(fv.ext_header().unwrap().used_size(), None);
+    }
+
+    #[test]
+    fn repair_should_fix_edited_headers() {
+        let file = build_ffs2_file([0x11; 16], super::FfsFileRawType::RAW, &[1, 2, 3]);
+        let fv_bytes = build_ffs2_volume(&file);
+        let fv_length = fv_bytes.len() as u64;
+
+        // a corrupted fv_length and block map terminator, and a stale checksum.
+        let mut edited = fv_bytes.clone();
+        edited[32..40].copy_from_slice(&(fv_length * 2).to_le_bytes());
+        edited[64..72].fill(0x5a);
+        edited[50] ^= 0xff;
+        assert!(FirmwareVolume::new(&edited).is_err());
+        let fv = FirmwareVolume::repair(&mut edited).unwrap();
+        assert_eq!(fv.size(), fv_length);
+        assert_eq!(fv.file_iter().next().unwrap().unwrap().name(), efi::Guid::from_bytes(&[0x11; 16]));
+        assert_eq!(edited, fv_bytes);
+
+        // a header that is still invalid after repair is rejected, and left unchanged.
+        let mut edited = fv_bytes.clone();
+        edited[56..60].fill(0);
+        edited[64..72].fill(0x5a);
+        let invalid = edited.clone();
+        assert_eq!(FirmwareVolume::repair(&mut edited).err(), Some(efi::Status::VOLUME_CORRUPTED));
+        assert_eq!(edited, invalid);
+        let mut edited = fv_bytes.clone();
+        edited[40] = b'X';
+        assert_eq!(FirmwareVolume::repair(&mut edited).err(), Some(efi::Status::VOLUME_CORRUPTED));
+        let mut edited = fv_bytes;
+        edited[48..50].copy_from_slice(&0x41u16.to_le_bytes());
+        assert_eq!(FirmwareVolume::recompute_checksum(&mut edited), Err(efi::Status::VOLUME_CORRUPTED));
+    }
+
+    #[test]
+    fn structural_validation_should_defer_header_checks() {
+        let file = build_ffs2_file([0x11; 16], super::FfsFileRawType::RAW, &[1, 2, 3]);
+        let mut fv_bytes = build_ffs2_volume(&file);
+        fv_bytes[50] ^= 0xff;
+
+        assert_eq!(FirmwareVolume::new(&fv_bytes).err(), Some(efi::Status::VOLUME_CORRUPTED));
+        let fv = FirmwareVolume::new_with_validation(&fv_bytes, ValidationLevel::Structural).unwrap();
+        assert_eq!(fv.file_iter().next().unwrap().unwrap().name(), efi::Guid::from_bytes(&[0x11; 16]));
+        assert_eq!(fv.verify_header(), Err(efi::Status::VOLUME_CORRUPTED));
+
+        let options = ParseOptions { validation: ValidationLevel::Structural, ..Default::default() };
+        let raw = raw::RawFirmwareVolume::new_with_options(&fv_bytes, &options).unwrap();
+        assert_eq!(raw.verify_header().err(), Some(FwFsError::BadHeaderChecksum));
+
+        // structural checks are still made.
+        fv_bytes[40] = b'X';
+        assert!(FirmwareVolume::new_with_validation(&fv_bytes, ValidationLevel::Structural).is_err());
+    }
+
+    #[test]
+    fn alignment_policy_should_select_file_offsets() {
+        // the file is at offset 0x4c, the 8-byte aligned address that EDK2 finds after the 0x48 byte FV header when
+        // the FV is loaded 4 bytes past an 8-byte aligned address.
+        let file = build_ffs2_file([0x11; 16], super::FfsFileRawType::RAW, &[1, 2, 3]);
+        let fv_bytes = build_ffs2_volume(&[&[0u8; 4][..], &file].concat());
+        let mut storage = vec![0u8; fv_bytes.len() + 8];
+        let start = (0..8).find(|offset| (storage.as_ptr() as usize + offset) % 8 == 4).unwrap();
+        storage[start..start + fv_bytes.len()].copy_from_slice(&fv_bytes);
+        let unaligned = &storage[start..start + fv_bytes.len()];
+
+        assert_eq!(ParseOptions::new(Strictness::Strict).alignment, AlignmentPolicy::RelativeToBase);
+        let options = ParseOptions::new(Strictness::Compatible);
+        assert_eq!(options.alignment, AlignmentPolicy::Absolute);
+
+        // relative to the FV, the first file is at offset 0x48, which does not hold a valid file header.
+        assert!(FirmwareVolume::new(unaligned).unwrap().file_iter().next().unwrap().is_err());
+        let fv = FirmwareVolume::new_with_options(unaligned, &options).unwrap();
+        let files = fv.file_iter().collect::<Result<Vec<_>, efi::Status>>().unwrap();
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].data().as_ptr() as usize - unaligned.as_ptr() as usize, 0x4c);
+    }
+
+    #[test]
+    fn nested_volumes_should_be_traversed() {
+        let inner_fv = build_ffs1_volume(&[1, 2, 3, 4]);
+
+        let mut section = ((4 + inner_fv.len()) as u32).to_le_bytes();
+        section[3] = super::FfsSectionRawType::FIRMWARE_VOLUME_IMAGE;
+        let content = [&section[..], &inner_fv].concat();
+        let outer_file = build_ffs2_file([0x11; 16], super::FfsFileRawType::FIRMWARE_VOLUME_IMAGE, &content);
+        let outer_fv = build_ffs2_volume(&outer_file);
+
+        let fv = FirmwareVolume::new(&outer_fv).unwrap();
+        let file = fv.file_iter().next().unwrap().unwrap();
+        let section = file.first_section_of_type(FfsSectionType::FirmwareVolumeImage).unwrap().unwrap();
+        assert_eq!(section.firmware_volume().unwrap().file_system(), super::FfsFileSystem::Ffs1);
+
+        let mut visited = Vec::new();
+        fv.all_files_recursive(&NullSectionExtractor {}, &mut |file, depth| {
+            visited.push((file.name(), depth));
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(visited, [(efi::Guid::from_bytes(&[0x11; 16]), 0), (efi::Guid::from_bytes(&[0x5a; 16]), 1)]);
+
+        // Visitor errors stop the traversal.
+        let result = fv.all_files_recursive(&NullSectionExtractor {}, &mut |_, depth| match depth {
+            0 => Ok(()),
+            _ => Err(efi::Status::ABORTED),
+        });
+        assert_eq!(result, Err(efi::Status::ABORTED));
+
+        // FVs nested deeper than the limits allow stop the traversal.
+        let limits = super::ExtractionLimits { max_depth: 0, ..Default::default() };
+        let mut visited = Vec::new();
+        let result = fv.all_files_recursive_with_limits(&NullSectionExtractor {}, limits, &mut |file, depth| {
+            visited.push((file.name(), depth));
+            Ok(())
+        });
+        assert_eq!(result, Err(efi::Status::VOLUME_CORRUPTED));
+        assert_eq!(visited, [(efi::Guid::from_bytes(&[0x11; 16]), 0)]);
+
+        let raw = build_ffs2_volume(&build_ffs2_file([0x22; 16], super::FfsFileRawType::RAW, &[0x04, 0, 0, 0x19]));
+        let fv = FirmwareVolume::new(&raw).unwrap();
+        let file = fv.file_iter().next().unwrap().unwrap();
+        let section = file.section_iter().next().unwrap().unwrap();
+        assert_eq!(section.firmware_volume().unwrap_err(), efi::Status::INVALID_PARAMETER);
+    }
+
+    #[test]
+    fn auth_status_should_propagate_to_extracted_sections() {
+        use super::{
+            rebuild::build_section, FfsAuthStatus, FfsGuidedSectionAttribute, FfsGuidedSectionAttributes,
+            FfsSectionRawType,
+        };
+
+        const SIGNED_GUID: efi::Guid =
+            efi::Guid::from_fields(0x51a7ed00, 0x1234, 0x5678, 0x9a, 0xbc, &[0xde, 0xf0, 0x12, 0x34, 0x56, 0x78]);
+
+        // Reports a signed section if the section has AUTH_STATUS_VALID, and a failure otherwise.
+        struct SignedExtractor {}
+        impl SectionExtractor for SignedExtractor {
+            fn extract(&self, section: &Section) -> Result<Box<[u8]>, efi::Status> {
+                self.extract_with_auth_status(section).map(|(buffer, _)| buffer)
+            }
+            fn extract_with_auth_status(&self, section: &Section) -> Result<(Box<[u8]>, u32), efi::Status> {
+                match section.meta_data() {
+                    SectionMetaData::GuidDefined(header, _) if header.section_definition_guid == SIGNED_GUID => {
+                        let status = if header.attributes & FfsGuidedSectionAttribute::AUTH_STATUS_VALID != 0 {
+                            FfsAuthStatus::IMAGE_SIGNED
+                        } else {
+                            FfsAuthStatus::TEST_FAILED
+                        };
+                        Ok((Box::from(section.raw_section_data()), status))
+                    }
+                    _ => Ok((Box::new([0u8; 0]), 0)),
+                }
+            }
+        }
+
+        let guided = |attributes: u16, content: &[u8]| {
+            let mut header_fields = SIGNED_GUID.as_bytes().to_vec();
+            header_fields.extend_from_slice(&24u16.to_le_bytes());
+            header_fields.extend_from_slice(&attributes.to_le_bytes());
+            build_section(FfsSectionRawType::encapsulated::GUID_DEFINED, &header_fields, content)
+        };
+        let leaf = build_section(FfsSectionRawType::RAW, &[], b"leaf");
+        let inner = guided(FfsGuidedSectionAttribute::PROCESSING_REQUIRED, &leaf);
+        let outer = guided(
+            FfsGuidedSectionAttribute::PROCESSING_REQUIRED | FfsGuidedSectionAttribute::AUTH_STATUS_VALID,
+            &inner,
+        );
+
+        let file_bytes = build_ffs2_file([0x5a; 16], FfsFileRawType::FREEFORM, &outer);
+        let file = super::File::new(&file_bytes).unwrap();
+        let sections = file.section_iter_with_extractor(&SignedExtractor {}).collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(sections.len(), 3);
+
+        // Data that requires processing is not handed out as section data.
+        assert!(sections[0].requires_processing());
+        assert_eq!(
+            sections[0].guided_attributes(),
+            Some(FfsGuidedSectionAttributes::PROCESSING_REQUIRED | FfsGuidedSectionAttributes::AUTH_STATUS_VALID)
+        );
+        assert!(sections[0].section_data().is_empty());
+        assert_eq!(sections[0].raw_section_data(), &inner[..]);
+        assert_eq!(sections[0].authentication_status(), 0);
+
+        // The inner section does not have AUTH_STATUS_VALID, so the leaf inherits the status of the outer section.
+        assert_eq!(sections[1].authentication_status(), FfsAuthStatus::IMAGE_SIGNED);
+        assert_eq!(sections[2].section_data(), b"leaf");
+        assert_eq!(sections[2].authentication_status(), FfsAuthStatus::IMAGE_SIGNED);
+    }
+
+    #[test]
+    fn sections_should_be_found_by_instance_and_guid() {
+        use super::{rebuild::build_section, FfsSectionRawType};
+
+        const WRAPPER_GUID: efi::Guid =
+            efi::Guid::from_fields(0x7a9c0d11, 0x4e2b, 0x4f1a, 0x8b, 0x3c, &[0x01, 0x23, 0x45, 0x67, 0x89, 0xab]);
+        const OTHER_GUID: efi::Guid =
+            efi::Guid::from_fields(0x7a9c0d12, 0x4e2b, 0x4f1a, 0x8b, 0x3c, &[0x01, 0x23, 0x45, 0x67, 0x89, 0xab]);
+
+        struct WrapperExtractor {}
+        impl SectionExtractor for WrapperExtractor {
+            fn extract(&self, section: &Section) -> Result<Box<[u8]>, efi::Status> {
+                Ok(Box::from(section.raw_section_data()))
+            }
+        }
+
+        let mut header_fields = WRAPPER_GUID.as_bytes().to_vec();
+        header_fields.extend_from_slice(&24u16.to_le_bytes());
+        header_fields.extend_from_slice(&0u16.to_le_bytes());
+        let mut content = build_section(FfsSectionRawType::RAW, &[], b"aaaa");
+        content.extend(build_section(
+            FfsSectionRawType::encapsulated::GUID_DEFINED,
+            &header_fields,
+            &build_section(FfsSectionRawType::RAW, &[], b"bbbb"),
+        ));
+        content.extend(build_section(FfsSectionRawType::RAW, &[], b"cccc"));
+
+        let file_bytes = build_ffs2_file([0x5b; 16], FfsFileRawType::FREEFORM, &content);
+        let file = super::File::new(&file_bytes).unwrap();
+
+        // Without an extractor, the encapsulated section is not counted.
+        assert_eq!(file.section(FfsSectionType::Raw, 0).unwrap().unwrap().section_data(), b"aaaa");
+        assert_eq!(file.section(FfsSectionType::Raw, 1).unwrap().unwrap().section_data(), b"cccc");
+        assert!(file.section(FfsSectionType::Raw, 2).unwrap().is_none());
+
+        let extractor = WrapperExtractor {};
+        let section = |instance| file.section_with_extractor(FfsSectionType::Raw, instance, &extractor).unwrap();
+        assert_eq!(section(1).unwrap().section_data(), b"bbbb");
+        assert_eq!(section(2).unwrap().section_data(), b"cccc");
+        assert!(section(3).is_none());
+
+        let wrapper = file.guid_defined_section(WRAPPER_GUID).unwrap().unwrap();
+        assert_eq!(wrapper.section_type(), Some(FfsSectionType::GuidDefined));
+        assert!(file.guid_defined_section(OTHER_GUID).unwrap().is_none());
+        assert!(file.guid_defined_section_with_extractor(WRAPPER_GUID, &extractor).unwrap().is_some());
+    }
+
+    #[test]
+    fn extracted_sections_should_record_their_provenance() {
+        use super::{builder::SectionBuilder, ffs::section::header::NOT_COMPRESSED, FfsSectionRawType, SectionOrigin};
+
+        const WRAPPER_GUID: efi::Guid =
+            efi::Guid::from_fields(0x7a9c0d13, 0x4e2b, 0x4f1a, 0x8b, 0x3c, &[0x01, 0x23, 0x45, 0x67, 0x89, 0xab]);
+
+        struct WrapperExtractor {}
+        impl SectionExtractor for WrapperExtractor {
+            fn extract(&self, section: &Section) -> Result<Box<[u8]>, efi::Status> {
+                Ok(Box::from(section.raw_section_data()))
+            }
+        }
+
+        let builder = SectionBuilder::new();
+        let leaf = builder.raw(b"leaf");
+        let compression = builder.compression(NOT_COMPRESSED, &[&leaf]).unwrap();
+        let mut header_fields = WRAPPER_GUID.as_bytes().to_vec();
+        header_fields.extend_from_slice(&24u16.to_le_bytes());
+        header_fields.extend_from_slice(&0u16.to_le_bytes());
+        let mut content = builder.raw(b"aaaa");
+        content.extend(builder.leaf(
+            FfsSectionRawType::encapsulated::GUID_DEFINED,
+            &[&header_fields[..], &builder.pe32(b"MZ"), &[0, 0], &compression].concat(),
+        ));
+
+        let file_bytes = build_ffs2_file([0x5c; 16], FfsFileRawType::FREEFORM, &content);
+        let file = super::File::new(&file_bytes).unwrap();
+        let sections = file.section_iter_with_extractor(&WrapperExtractor {}).collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(sections.len(), 5);
+        assert!(sections[..2].iter().all(|section| section.extracted_from().is_empty()));
+
+        let guid_defined = SectionOrigin {
+            section_type: FfsSectionRawType::encapsulated::GUID_DEFINED,
+            section_definition: Some(WRAPPER_GUID),
+            offset: 8,
+        };
+        assert_eq!(sections[2].section_type(), Some(FfsSectionType::Pe32));
+        assert_eq!(sections[2].extracted_from(), &[guid_defined]);
+        assert_eq!(sections[3].extracted_from(), &[guid_defined]);
+        let compression = SectionOrigin {
+            section_type: FfsSectionRawType::encapsulated::COMPRESSION,
+            section_definition: None,
+            offset: 8,
+        };
+        assert_eq!(sections[4].section_data(), b"leaf");
+        assert_eq!(sections[4].extracted_from(), &[guid_defined, compression]);
+    }
+
+    #[test]
+    #[cfg(feature = "guid_names")]
+    fn debug_output_should_name_known_guids() {
+        use super::{ffs::guid::LZMA_CUSTOM_DECOMPRESS_GUID, rebuild::build_section, FfsSectionRawType};
+
+        let mut header_fields = LZMA_CUSTOM_DECOMPRESS_GUID.as_bytes().to_vec();
+        header_fields.extend_from_slice(&24u16.to_le_bytes());
+        header_fields.extend_from_slice(&0u16.to_le_bytes());
+        let content = build_section(FfsSectionRawType::encapsulated::GUID_DEFINED, &header_fields, b"data");
+        let dxe_core =
+            efi::Guid::from_fields(0xd6a2cb7f, 0x6a18, 0x4e2f, 0xb4, 0x3b, &[0x99, 0x20, 0xa7, 0x33, 0x70, 0x0a]);
+
+        let file_bytes = build_ffs2_file(*dxe_core.as_bytes(), FfsFileRawType::DXE_CORE, &content);
+        let file = super::File::new(&file_bytes).unwrap();
+        assert!(format!("{:?}", file).contains(" (DxeCore)"));
+        let section = file.section_iter().next().unwrap().unwrap();
+        assert!(format!("{:?}", section).contains("section_definition: \"LzmaCustomDecompress\""));
+    }
+
+    #[test]
+    fn section_extraction_should_be_limited() {
+        use super::{rebuild::build_section, ExtractionLimits, FfsSectionRawType};
+
+        let compressed = |uncompressed_length: u32| {
+            let mut header_fields = uncompressed_length.to_le_bytes().to_vec();
+            header_fields.push(super::ffs::section::header::NOT_COMPRESSED);
+            build_section(FfsSectionRawType::encapsulated::COMPRESSION, &header_fields, &[])
+        };
+
+        // Expands every compression section into another compression section, without end.
+        struct BombExtractor(Vec<u8>);
+        impl SectionExtractor for BombExtractor {
+            fn extract(&self, _section: &Section) -> Result<Box<[u8]>, efi::Status> {
+                Ok(self.0.clone().into_boxed_slice())
+            }
+        }
+        let extractor = BombExtractor(compressed(9));
+
+        let file_bytes = build_ffs2_file([0x5b; 16], FfsFileRawType::FREEFORM, &compressed(9));
+        let file = super::File::new(&file_bytes).unwrap();
+        let results = file.section_iter_with_extractor(&extractor).collect::<Vec<_>>();
+        assert_eq!(results.len(), ExtractionLimits::default().max_depth + 2);
+        assert!(results[..results.len() - 1].iter().all(|section| section.is_ok()));
+        assert_eq!(results.last().unwrap().as_ref().unwrap_err(), &efi::Status::VOLUME_CORRUPTED);
+
+        let limits = ExtractionLimits { max_extracted_size: 20, ..Default::default() };
+        let results = file.section_iter_with_limits(&extractor, limits).collect::<Vec<_>>();
+        assert_eq!(results.len(), 4);
+        assert_eq!(results[3].as_ref().unwrap_err(), &efi::Status::OUT_OF_RESOURCES);
+
+        // compression sections that declare more than the budget are not extracted.
+        let file_bytes = build_ffs2_file([0x5b; 16], FfsFileRawType::FREEFORM, &compressed(u32::MAX));
+        let file = super::File::new(&file_bytes).unwrap();
+        let results = file.section_iter_with_limits(&extractor, limits).collect::<Vec<_>>();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[1].as_ref().unwrap_err(), &efi::Status::OUT_OF_RESOURCES);
+    }
+
+    #[test]
+    fn large_file_in_ffs2_volume_should_be_a_deviation() {
+        // a RAW file with the LARGE_FILE extended header, which only FFS3 volumes should hold.
+        let file_size = mem::size_of::<super::file::Header>() + mem::size_of::<u64>() + 8;
+        let mut file_bytes = vec![0u8; file_size];
+        file_bytes[..16].copy_from_slice(&[0x3c; 16]);
+        file_bytes[18] = super::FfsFileRawType::RAW;
+        file_bytes[19] = super::FfsRawAttribute::LARGE_FILE;
+        file_bytes[24..32].copy_from_slice(&(file_size as u64).to_le_bytes());
+        file_bytes[16] = checksum::compute8(&file_bytes[..32]);
+        file_bytes[17] = 0xaa;
+        file_bytes[23] = super::FfsFileRawState::HEADER_CONSTRUCTION
+            | super::FfsFileRawState::HEADER_VALID
+            | super::FfsFileRawState::DATA_VALID;
+        let name = efi::Guid::from_bytes(&[0x3c; 16]);
+        let fv_bytes = build_ffs2_volume(&file_bytes);
+
+        let fv = FirmwareVolume::new_with_options(&fv_bytes, &ParseOptions::STRICT).unwrap();
+        assert_eq!(fv.file_system(), super::FfsFileSystem::Ffs2);
+        assert_eq!(fv.file_iter().next().unwrap().unwrap_err(), efi::Status::VOLUME_CORRUPTED);
+
+        let fv = FirmwareVolume::new_with_options(&fv_bytes, &ParseOptions::COMPATIBLE).unwrap();
+        let file = fv.file_iter().next().unwrap().unwrap();
+        assert_eq!(file.size(), file_size as u64);
+        assert_eq!(file.deviations(), [Deviation::LargeFileInFfs2 { name }]);
+
+        // Independently instantiated files accept large files.
+        let file = super::File::new_with_options(&file_bytes, &ParseOptions::STRICT).unwrap();
+        assert!(file.deviations().is_empty());
+    }
 }
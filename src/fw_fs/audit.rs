@@ -0,0 +1,171 @@
+//! Firmware Volume Audits
+//!
+//! Checks of firmware volumes against rules of the PI specification that parsing does not enforce:
+//! - [`duplicate_files`] finds the file names used more than once in a firmware volume or in the firmware volumes
+//!   nested in it. The PI specification requires file names to be unique, and a dispatcher that finds two files with
+//!   the same name dispatches whichever it finds first, which depends on the order it discovers the volumes in.
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+use alloc::vec::Vec;
+use core::fmt;
+use r_efi::efi;
+
+use super::{ExtractionLimits, FfsFileRawType, FfsFileType, FfsSectionType, FirmwareVolume, SectionExtractor};
+
+/// The location of a file in a firmware volume or in a firmware volume nested in it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FileLocation {
+    /// The nesting depth of the firmware volume holding the file, 0 for the audited firmware volume.
+    pub depth: usize,
+    /// The name of the firmware volume holding the file, from its extended header.
+    pub volume_name: Option<efi::Guid>,
+    /// The offset of the file in the firmware volume holding it.
+    pub offset: usize,
+}
+
+/// A file name used by more than one file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DuplicateFile {
+    pub name: efi::Guid,
+    /// The locations of the files with that name, in the order they were found.
+    pub locations: Vec<FileLocation>,
+}
+
+impl fmt::Display for DuplicateFile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "file {:?} is duplicated at", self.name)?;
+        for (index, location) in self.locations.iter().enumerate() {
+            let separator = if index == 0 { "" } else { "," };
+            write!(f, "{separator} offset {:#x}", location.offset)?;
+            if location.depth > 0 {
+                write!(f, " of nested volume {:?}", location.volume_name)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Returns the file names used more than once in `fv` and in the firmware volumes nested in it, in the order they are
+/// first found.
+///
+/// Firmware volumes are traversed depth-first, as by [`FirmwareVolume::all_files_recursive`]: the FIRMWARE_VOLUME_IMAGE
+/// sections of FIRMWARE_VOLUME_IMAGE files, including those in encapsulation sections expanded by `extractor`, are
+/// audited in turn. Pad files are exempt from the rule and ignored. Errors encountered while parsing stop the audit and
+/// are returned.
+pub fn duplicate_files(
+    fv: &FirmwareVolume,
+    extractor: &dyn SectionExtractor,
+) -> Result<Vec<DuplicateFile>, efi::Status> {
+    let mut files = Vec::new();
+    collect_files(fv, extractor, 0, &mut files)?;
+
+    let mut duplicates: Vec<DuplicateFile> = Vec::new();
+    for (index, (name, _)) in files.iter().enumerate() {
+        // each name is reported at its first occurrence.
+        if files[..index].iter().any(|(other, _)| other == name) {
+            continue;
+        }
+        let locations: Vec<_> =
+            files[index..].iter().filter(|(other, _)| other == name).map(|(_, location)| *location).collect();
+        if locations.len() > 1 {
+            duplicates.push(DuplicateFile { name: *name, locations });
+        }
+    }
+    Ok(duplicates)
+}
+
+fn collect_files(
+    fv: &FirmwareVolume,
+    extractor: &dyn SectionExtractor,
+    depth: usize,
+    files: &mut Vec<(efi::Guid, FileLocation)>,
+) -> Result<(), efi::Status> {
+    // extracted FVs can nest without bound in a malicious image.
+    if depth > ExtractionLimits::default().max_depth {
+        Err(efi::Status::VOLUME_CORRUPTED)?;
+    }
+    let base = fv.data().as_ptr() as usize;
+    for file in fv.file_iter() {
+        let file = file?;
+        if file.file_type_raw() != FfsFileRawType::FFS_PAD {
+            let offset = file.data().as_ptr() as usize - base;
+            files.push((file.name(), FileLocation { depth, volume_name: fv.fv_name(), offset }));
+        }
+        if file.file_type() != Some(FfsFileType::FirmwareVolumeImage) {
+            continue;
+        }
+        for section in file.sections_of_type_with_extractor(FfsSectionType::FirmwareVolumeImage, extractor) {
+            let section = section?;
+            collect_files(&section.firmware_volume_with_options(&fv.options)?, extractor, depth + 1, files)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs, path::Path};
+
+    use super::*;
+    use crate::{
+        address_helper::align_up,
+        fw_fs::{
+            unit_tests::{build_ffs1_volume, build_ffs2_file, build_ffs2_volume},
+            FfsSectionRawType, NullSectionExtractor,
+        },
+    };
+
+    #[test]
+    fn volumes_without_duplicates_should_pass() {
+        let root = Path::new(&env::var("CARGO_MANIFEST_DIR").unwrap()).join("test_resources");
+        let fv_bytes = fs::read(root.join("DXEFV.Fv")).unwrap();
+        let fv = FirmwareVolume::new(&fv_bytes).unwrap();
+        assert_eq!(duplicate_files(&fv, &NullSectionExtractor {}).unwrap(), []);
+    }
+
+    #[test]
+    fn duplicates_should_be_found_within_and_across_volumes() {
+        // the inner volume holds a file named [0x5a; 16].
+        let inner_fv = build_ffs1_volume(&[1, 2, 3, 4]);
+        let mut section = ((4 + inner_fv.len()) as u32).to_le_bytes();
+        section[3] = FfsSectionRawType::FIRMWARE_VOLUME_IMAGE;
+        let fv_image =
+            build_ffs2_file([0x11; 16], FfsFileRawType::FIRMWARE_VOLUME_IMAGE, &[&section[..], &inner_fv].concat());
+
+        // the outer volume holds the FV image, two pad files and two more files named [0x5a; 16].
+        let mut files = Vec::new();
+        for file in [
+            fv_image,
+            build_ffs2_file([0xff; 16], FfsFileRawType::FFS_PAD, &[]),
+            build_ffs2_file([0x5a; 16], FfsFileRawType::RAW, &[]),
+            build_ffs2_file([0xff; 16], FfsFileRawType::FFS_PAD, &[]),
+            build_ffs2_file([0x5a; 16], FfsFileRawType::RAW, &[]),
+        ] {
+            files.resize(align_up(files.len() as u64, 8) as usize, 0);
+            files.extend_from_slice(&file);
+        }
+        let outer_fv = build_ffs2_volume(&files);
+        let fv = FirmwareVolume::new(&outer_fv).unwrap();
+        let offsets: Vec<_> =
+            fv.file_iter().map(|file| file.unwrap().data().as_ptr() as usize - outer_fv.as_ptr() as usize).collect();
+
+        let duplicates = duplicate_files(&fv, &NullSectionExtractor {}).unwrap();
+        assert_eq!(
+            duplicates,
+            [DuplicateFile {
+                name: efi::Guid::from_bytes(&[0x5a; 16]),
+                locations: vec![
+                    FileLocation { depth: 1, volume_name: None, offset: offsets[0] },
+                    FileLocation { depth: 0, volume_name: None, offset: offsets[2] },
+                    FileLocation { depth: 0, volume_name: None, offset: offsets[4] },
+                ],
+            }]
+        );
+    }
+}
@@ -0,0 +1,997 @@
+//! Firmware Volume Generation
+//!
+//! Support for generating firmware volume images:
+//! - [`blank_volume`] generates an empty firmware volume of a given block geometry, such as an NVRAM region or the
+//!   starting point of an image that files are written to.
+//! - [`FirmwareVolumeBuilder`] assembles a firmware volume from a set of FFS files, inserting pad files to meet the
+//!   alignment the files require and to hold the extended header, as EDK2's GenFv does. In deterministic mode, the
+//!   image only depends on the set of files, for reproducible builds.
+//! - [`FfsFileBuilder`] constructs an FFS file from a set of sections.
+//! - [`SectionBuilder`] constructs the leaf and encapsulation sections of FFS files.
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+use alloc::{boxed::Box, vec, vec::Vec};
+use core::{fmt, mem};
+use r_efi::efi;
+
+use super::{
+    ffs::{file, section},
+    fv::{
+        self,
+        header_offset::{
+            ATTRIBUTES, BLOCK_MAP, CHECKSUM, EXT_HEADER_OFFSET, FILE_SYSTEM_GUID, FV_LENGTH, HEADER_LENGTH, REVISION,
+            SIGNATURE,
+        },
+        BlockMapEntry,
+    },
+    rebuild::{
+        build_compression_section, build_guid_defined_section, build_section, EncodedSection, SectionCompressor,
+    },
+    reproducibility::normalize_volume,
+    FfsAttributes, FfsFileRawState, FfsFileRawType, FfsFileSystem, FfsSectionRawType, File, FvbAttributes2, FwFsError,
+};
+use crate::{address_helper::align_up, checksum, parse::ParseOptions};
+
+// EFI_FVH_REVISION, the revision of volumes holding FFS2 or FFS3 files. FFS1 volumes predate it and have revision 1.
+const FVH_REVISION: u8 = 2;
+
+// The name EDK2 gives to pad files.
+const PAD_FILE_NAME: [u8; 16] = [0xff; 16];
+
+// The largest size of a file with a standard header, whose size field is 24 bits.
+pub(super) const MAX_FILE_SIZE: usize = 0xff_ffff;
+
+// The state of a complete file in a volume whose erase polarity is 0.
+pub(super) const FILE_STATE_VALID: u8 =
+    FfsFileRawState::HEADER_CONSTRUCTION | FfsFileRawState::HEADER_VALID | FfsFileRawState::DATA_VALID;
+
+/// Errors returned while building firmware volumes, files and sections.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum BuildError {
+    /// FFS1 volumes cannot be built, only blank FFS1 volumes can be generated.
+    UnsupportedFileSystem,
+    /// The block size is zero, or the block map is empty, holds an empty entry or describes a volume too small for its
+    /// header.
+    BadBlockMap,
+    /// The volume does not fit in memory.
+    VolumeTooLarge { fv_length: u64 },
+    /// The file added at the given index does not parse as an FFS file.
+    InvalidFile { index: usize, error: FwFsError },
+    /// The file needs the extended header of large files, which only FFS3 volumes can hold.
+    LargeFile { name: efi::Guid },
+    /// The alignment is larger than the attributes can encode.
+    BadAlignment { alignment: usize },
+    /// Aligning the file needs a pad file larger than 16MB, which only FFS3 volumes can hold.
+    LargePadFile { name: efi::Guid },
+    /// The files do not fit in the requested volume size, or it is not a multiple of the block size.
+    BadVolumeSize { size: u64 },
+    /// The volume that was built does not parse, so it cannot be put in deterministic form.
+    Malformed(FwFsError),
+    /// The compressor failed to encode a Compression or GUID-defined section, e.g. with `UNSUPPORTED` for an encoding
+    /// it does not support.
+    Encoding(efi::Status),
+}
+
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BuildError::UnsupportedFileSystem => write!(f, "FFS1 volumes cannot be built"),
+            BuildError::BadBlockMap => write!(f, "block map is empty or malformed"),
+            BuildError::VolumeTooLarge { fv_length } => {
+                write!(f, "volume of {fv_length:#x} bytes does not fit in memory")
+            }
+            BuildError::InvalidFile { index, error } => write!(f, "file {index} is invalid: {error}"),
+            BuildError::LargeFile { name } => write!(f, "large file {name:?} can only be stored in an FFS3 volume"),
+            BuildError::BadAlignment { alignment } => write!(f, "alignment {alignment:#x} cannot be encoded"),
+            BuildError::LargePadFile { name } => {
+                write!(f, "aligning file {name:?} needs a pad file larger than 16MB, which needs an FFS3 volume")
+            }
+            BuildError::BadVolumeSize { size } => write!(f, "files do not fit in a volume of {size:#x} bytes"),
+            BuildError::Malformed(error) => write!(f, "built volume is malformed: {error}"),
+            BuildError::Encoding(status) => write!(f, "section encoding failed with {status:?}"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for BuildError {}
+
+impl From<FwFsError> for BuildError {
+    fn from(error: FwFsError) -> Self {
+        BuildError::Malformed(error)
+    }
+}
+
+impl From<BuildError> for efi::Status {
+    fn from(error: BuildError) -> Self {
+        match error {
+            BuildError::UnsupportedFileSystem | BuildError::LargePadFile { .. } => efi::Status::UNSUPPORTED,
+            BuildError::BadBlockMap
+            | BuildError::InvalidFile { .. }
+            | BuildError::LargeFile { .. }
+            | BuildError::BadAlignment { .. } => efi::Status::INVALID_PARAMETER,
+            BuildError::VolumeTooLarge { .. } => efi::Status::OUT_OF_RESOURCES,
+            BuildError::BadVolumeSize { .. } => efi::Status::BAD_BUFFER_SIZE,
+            BuildError::Malformed(error) => error.into(),
+            BuildError::Encoding(status) => status,
+        }
+    }
+}
+
+/// The order in which [`FirmwareVolumeBuilder`] places files in the volume.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FileOrder {
+    /// The order in which the files were added.
+    #[default]
+    Added,
+    /// The byte order of the file names, then of the file bytes for files with the same name, so that the image does
+    /// not depend on the order in which the files were added.
+    Name,
+}
+
+/// Assembles a firmware volume image from FFS files.
+///
+/// Files are placed in the [`FileOrder`] of the builder (by default, the order they were added), each at the next
+/// 8-byte aligned offset. When the data of a file requires a larger alignment, a pad file is inserted before it; in
+/// FFS3 volumes, pad files larger than 16MB use the extended header of large files. Unless the attributes have the
+/// WEAK_ALIGNMENT attribute, the ALIGNMENT attribute of the volume is raised to the largest alignment the files
+/// require, as EDK2's GenFv does. The state of the files is rewritten to match the erase polarity of the volume, and
+/// the rest of the volume holds the erase byte.
+///
+/// When the volume has a name, its extended header is stored as the data of a pad file placed first in the volume, as
+/// GenFv does, so that cores walking the volume from its header find a valid file there.
+///
+/// In [deterministic](FirmwareVolumeBuilder::deterministic) mode, files are placed in [`FileOrder::Name`] order and the
+/// image is put in the form of [`normalize_volume`]: the reserved fields are zeroed and the free space and the content
+/// of pad files hold the erase byte. Builds from the same files then produce byte-for-byte identical images,
+/// whichever order the files were added in.
+///
+/// ## Example
+///```
+/// use mu_pi::fw_fs::{builder::FirmwareVolumeBuilder, FfsFileSystem, FirmwareVolume, FvbAttributes2};
+/// use r_efi::efi;
+///
+/// fn assemble(files: &[&[u8]]) -> Result<Vec<u8>, efi::Status> {
+///   let name = efi::Guid::from_fields(0x6938079b, 0xb503, 0x4e3d, 0x9d, 0x24, &[0xb2, 0x83, 0x37, 0xa2, 0x58, 0x06]);
+///   let mut builder = FirmwareVolumeBuilder::new(FfsFileSystem::Ffs2)
+///     .attributes(FvbAttributes2::ERASE_POLARITY | FvbAttributes2::MEMORY_MAPPED)
+///     .fv_name(name);
+///   for file in files {
+///     builder = builder.add_file(*file);
+///   }
+///   Ok(builder.build()?)
+/// }
+///
+/// let image = assemble(&[]).unwrap();
+/// assert_eq!(FirmwareVolume::new(&image).unwrap().size(), 0x1000);
+///```
+#[derive(Debug, Clone)]
+pub struct FirmwareVolumeBuilder {
+    file_system: FfsFileSystem,
+    attributes: FvbAttributes2,
+    block_size: u32,
+    size: Option<u64>,
+    fv_name: Option<efi::Guid>,
+    files: Vec<Box<[u8]>>,
+    file_order: FileOrder,
+    deterministic: bool,
+}
+
+impl FirmwareVolumeBuilder {
+    /// Creates a builder for an empty volume with the given file system, no attributes and 4KB blocks.
+    pub fn new(file_system: FfsFileSystem) -> Self {
+        Self {
+            file_system,
+            attributes: FvbAttributes2::empty(),
+            block_size: 0x1000,
+            size: None,
+            fv_name: None,
+            files: Vec::new(),
+            file_order: FileOrder::Added,
+            deterministic: false,
+        }
+    }
+
+    /// Sets the attributes of the volume, which determine its erase polarity.
+    pub fn attributes(mut self, attributes: FvbAttributes2) -> Self {
+        self.attributes = attributes;
+        self
+    }
+
+    /// Sets the size of the blocks of the volume.
+    pub fn block_size(mut self, block_size: u32) -> Self {
+        self.block_size = block_size;
+        self
+    }
+
+    /// Sets the size of the volume, which must be a multiple of the block size. By default, the volume is the smallest
+    /// number of blocks that holds the files.
+    pub fn size(mut self, size: u64) -> Self {
+        self.size = Some(size);
+        self
+    }
+
+    /// Sets the name of the volume, which is stored in an extended header.
+    pub fn fv_name(mut self, fv_name: efi::Guid) -> Self {
+        self.fv_name = Some(fv_name);
+        self
+    }
+
+    /// Sets the order in which the files are placed in the volume.
+    pub fn file_order(mut self, file_order: FileOrder) -> Self {
+        self.file_order = file_order;
+        self
+    }
+
+    /// Builds the volume in deterministic mode, which places the files in [`FileOrder::Name`] order and normalizes the
+    /// image, so that the image only depends on the set of files added.
+    pub fn deterministic(mut self) -> Self {
+        self.file_order = FileOrder::Name;
+        self.deterministic = true;
+        self
+    }
+
+    /// Adds a file to the volume, as the complete bytes of an FFS file including its header.
+    pub fn add_file(mut self, file: impl Into<Box<[u8]>>) -> Self {
+        self.files.push(file.into());
+        self
+    }
+
+    /// Builds the volume image.
+    ///
+    /// Returns [`BuildError::InvalidFile`] if a file does not parse as a valid FFS2 file, [`BuildError::LargeFile`] for
+    /// FFS3 files outside of FFS3 volumes, [`BuildError::UnsupportedFileSystem`] for FFS1 volumes,
+    /// [`BuildError::LargePadFile`] for alignments that need pad files larger than 16MB in FFS2 volumes, and
+    /// [`BuildError::BadVolumeSize`] if the files do not fit in the requested size or it is not a multiple of the block
+    /// size.
+    pub fn build(&self) -> Result<Vec<u8>, BuildError> {
+        if self.file_system == FfsFileSystem::Ffs1 {
+            Err(BuildError::UnsupportedFileSystem)?;
+        }
+        if self.block_size == 0 {
+            Err(BuildError::BadBlockMap)?;
+        }
+        let mut files = self
+            .files
+            .iter()
+            .enumerate()
+            .map(|(index, bytes)| {
+                let file = File::try_new(bytes, &ParseOptions::default())
+                    .map_err(|error| BuildError::InvalidFile { index, error })?;
+                // files that need an extended header can only be stored in FFS3 volumes.
+                if file.header_size > mem::size_of::<file::Header>() && self.file_system != FfsFileSystem::Ffs3 {
+                    Err(BuildError::LargeFile { name: file.name() })?;
+                }
+                Ok(file)
+            })
+            .collect::<Result<Vec<_>, BuildError>>()?;
+        if self.file_order == FileOrder::Name {
+            files.sort_by(|a, b| a.name().as_bytes().cmp(b.name().as_bytes()).then_with(|| a.data().cmp(b.data())));
+        }
+
+        let mut attributes = self.attributes;
+        let max_alignment = files.iter().map(File::required_alignment).max().unwrap_or(1);
+        if !attributes.contains(FvbAttributes2::WEAK_ALIGNMENT) && attributes.alignment() < max_alignment {
+            attributes = attributes
+                .with_alignment(max_alignment)
+                .ok_or(BuildError::BadAlignment { alignment: max_alignment })?;
+        }
+        let erase_byte = attributes.erase_byte();
+
+        // lay out the files, with pad files where needed. The extended header is the data of the first pad file.
+        let header_length = mem::size_of::<fv::Header>() + 2 * mem::size_of::<BlockMapEntry>();
+        let ext_header_offset = header_length + mem::size_of::<file::Header>();
+        let mut offset = header_length;
+        let mut placements = Vec::new();
+        if self.fv_name.is_some() {
+            let pad_size = mem::size_of::<file::Header>() + mem::size_of::<fv::ExtHeader>();
+            placements.push((offset, None, pad_size));
+            offset += pad_size;
+        }
+        for file in &files {
+            offset = align_up(offset as u64, 8) as usize;
+            let alignment = file.required_alignment();
+            if (offset + file.header_size) % alignment != 0 {
+                let mut pad_header_size = mem::size_of::<file::Header>();
+                let mut aligned =
+                    align_up((offset + pad_header_size + file.header_size) as u64, alignment as u64) as usize;
+                // pad files larger than 16MB need the extended header, which only FFS3 volumes can hold.
+                if aligned - file.header_size - offset > MAX_FILE_SIZE {
+                    if self.file_system != FfsFileSystem::Ffs3 {
+                        Err(BuildError::LargePadFile { name: file.name() })?;
+                    }
+                    pad_header_size = mem::size_of::<file::Header2>();
+                    aligned = align_up((offset + pad_header_size + file.header_size) as u64, alignment as u64) as usize;
+                }
+                placements.push((offset, None, aligned - file.header_size - offset));
+                offset = aligned - file.header_size;
+            }
+            placements.push((offset, Some(file), file.size() as usize));
+            offset += file.size() as usize;
+        }
+
+        let block_size = self.block_size as u64;
+        let size = self.size.unwrap_or_else(|| align_up(offset as u64, block_size));
+        if size % block_size != 0 || size < offset as u64 || size / block_size > u32::MAX as u64 {
+            Err(BuildError::BadVolumeSize { size })?;
+        }
+        let block_map = [BlockMapEntry { num_blocks: (size / block_size) as u32, length: self.block_size }];
+        let mut buffer = blank_volume(self.file_system, &block_map, attributes)?;
+
+        let state = if erase_byte == 0 { FILE_STATE_VALID } else { !FILE_STATE_VALID };
+        for (offset, file, size) in placements {
+            let target = &mut buffer[offset..offset + size];
+            match file {
+                Some(file) => target.copy_from_slice(file.data()),
+                None => write_pad_file(target, erase_byte),
+            }
+            target[23] = state;
+        }
+
+        if let Some(fv_name) = self.fv_name {
+            let ext_header = &mut buffer[ext_header_offset..ext_header_offset + mem::size_of::<fv::ExtHeader>()];
+            ext_header[..16].copy_from_slice(fv_name.as_bytes());
+            ext_header[16..].copy_from_slice(&(mem::size_of::<fv::ExtHeader>() as u32).to_le_bytes());
+            buffer[EXT_HEADER_OFFSET..EXT_HEADER_OFFSET + 2].copy_from_slice(&(ext_header_offset as u16).to_le_bytes());
+            buffer[CHECKSUM..CHECKSUM + 2].fill(0);
+            let checksum = checksum::compute16(&buffer[..header_length]);
+            buffer[CHECKSUM..CHECKSUM + 2].copy_from_slice(&checksum.to_le_bytes());
+        }
+        if self.deterministic {
+            normalize_volume(&mut buffer)?;
+        }
+        Ok(buffer)
+    }
+}
+
+// Writes a pad file filling `target`, whose content holds the erase byte. Pad files larger than 16MB are written with
+// the extended header of large files.
+pub(super) fn write_pad_file(target: &mut [u8], erase_byte: u8) {
+    let size = target.len();
+    let header_size =
+        if size > MAX_FILE_SIZE { mem::size_of::<file::Header2>() } else { mem::size_of::<file::Header>() };
+    target.fill(erase_byte);
+    target[..16].copy_from_slice(&PAD_FILE_NAME);
+    target[16..header_size].fill(0);
+    target[18] = FfsFileRawType::FFS_PAD;
+    if header_size == mem::size_of::<file::Header>() {
+        target[20..23].copy_from_slice(&(size as u32).to_le_bytes()[..3]);
+    } else {
+        target[19] = FfsAttributes::LARGE_FILE.bits();
+        target[mem::size_of::<file::Header>()..header_size].copy_from_slice(&(size as u64).to_le_bytes());
+    }
+    // the header checksum excludes the file checksum and state.
+    target[16] = checksum::compute8(&target[..header_size]);
+    target[17] = FfsFileSystem::Ffs2.fixed_file_checksum();
+}
+
+/// Builds an FFS file from its sections.
+///
+/// Sections are placed in the order they were added, each at the next 4-byte aligned offset of the file data. The
+/// extended header of the LARGE_FILE attribute is used when the file is too large for the 24-bit size field, which
+/// limits the file to FFS3 volumes. The header checksum is computed, as is the data checksum when the CHECKSUM attribute
+/// is set. The state of the file is that of a complete file in a volume whose erase polarity is 0;
+/// [`FirmwareVolumeBuilder`] rewrites it to match the volume the file is placed in.
+///
+/// ## Example
+///```
+/// use mu_pi::fw_fs::{
+///   builder::FfsFileBuilder, rebuild::build_section, FfsAttributes, FfsFileRawType, FfsSectionRawType, File,
+/// };
+/// use r_efi::efi;
+///
+/// let name = efi::Guid::from_fields(0x1ba0062e, 0xc779, 0x4582, 0x85, 0x66, &[0x33, 0x6a, 0xe8, 0xf7, 0x8f, 0x09]);
+/// let ui: Vec<u8> = "Driver\0".encode_utf16().flat_map(u16::to_le_bytes).collect();
+/// let bytes = FfsFileBuilder::new(name, FfsFileRawType::DRIVER)
+///   .attributes(FfsAttributes::CHECKSUM)
+///   .alignment(0x1000)
+///   .add_section(build_section(FfsSectionRawType::PE32, &[], &[0x4d, 0x5a]))
+///   .add_section(build_section(FfsSectionRawType::USER_INTERFACE, &[], &ui))
+///   .build()
+///   .unwrap();
+///
+/// let file = File::new(&bytes).unwrap();
+/// assert_eq!(file.name(), name);
+/// assert_eq!(file.required_alignment(), 0x1000);
+/// assert_eq!(file.verify_checksums(), Ok(()));
+/// assert_eq!(file.section_iter().count(), 2);
+///```
+#[derive(Debug, Clone)]
+pub struct FfsFileBuilder {
+    name: efi::Guid,
+    file_type: u8,
+    attributes: FfsAttributes,
+    alignment: usize,
+    sections: Vec<Box<[u8]>>,
+}
+
+impl FfsFileBuilder {
+    /// Creates a builder for a file of the given name and raw type, with no attributes and no sections.
+    pub fn new(name: efi::Guid, file_type: u8) -> Self {
+        Self { name, file_type, attributes: FfsAttributes::empty(), alignment: 1, sections: Vec::new() }
+    }
+
+    /// Sets the FIXED and CHECKSUM attributes of the file. The other attributes are derived from the size of the file
+    /// and its alignment.
+    pub fn attributes(mut self, attributes: FfsAttributes) -> Self {
+        self.attributes = attributes;
+        self
+    }
+
+    /// Sets the alignment the file data requires, which is rounded up to the next alignment the DATA_ALIGNMENT
+    /// attributes can encode.
+    pub fn alignment(mut self, alignment: usize) -> Self {
+        self.alignment = alignment;
+        self
+    }
+
+    /// Adds a section to the file, as the complete bytes of the section including its header. Files that do not hold
+    /// sections, such as RAW files, take their content as a single section.
+    pub fn add_section(mut self, section: impl Into<Box<[u8]>>) -> Self {
+        self.sections.push(section.into());
+        self
+    }
+
+    /// Builds the file.
+    ///
+    /// Returns [`BuildError::BadAlignment`] if the alignment is larger than 16MB, the largest the attributes can
+    /// encode.
+    pub fn build(&self) -> Result<Vec<u8>, BuildError> {
+        let attributes = self.attributes.bits() & (FfsAttributes::FIXED | FfsAttributes::CHECKSUM).bits();
+        let mut attributes = FfsAttributes::from_bits_retain(attributes)
+            .with_alignment(self.alignment)
+            .ok_or(BuildError::BadAlignment { alignment: self.alignment })?;
+
+        let content = concat_sections(self.sections.iter().map(|section| &section[..]));
+
+        let mut header_size = mem::size_of::<file::Header>();
+        if header_size + content.len() > MAX_FILE_SIZE {
+            attributes |= FfsAttributes::LARGE_FILE;
+            header_size = mem::size_of::<file::Header2>();
+        }
+        let size = header_size + content.len();
+        let mut buffer = vec![0; header_size];
+        buffer[..16].copy_from_slice(self.name.as_bytes());
+        buffer[18] = self.file_type;
+        buffer[19] = attributes.bits();
+        if attributes.contains(FfsAttributes::LARGE_FILE) {
+            buffer[mem::size_of::<file::Header>()..].copy_from_slice(&(size as u64).to_le_bytes());
+        } else {
+            buffer[20..23].copy_from_slice(&(size as u32).to_le_bytes()[..3]);
+        }
+        // the header checksum excludes the file checksum and state.
+        buffer[16] = checksum::compute8(&buffer);
+        buffer[17] = if attributes.contains(FfsAttributes::CHECKSUM) {
+            checksum::compute8(&content)
+        } else {
+            FfsFileSystem::Ffs2.fixed_file_checksum()
+        };
+        buffer[23] = FILE_STATE_VALID;
+        buffer.extend_from_slice(&content);
+        Ok(buffer)
+    }
+}
+
+// Concatenates sections, each at the next 4-byte aligned offset.
+fn concat_sections<'a>(sections: impl IntoIterator<Item = &'a [u8]>) -> Vec<u8> {
+    let mut content = Vec::new();
+    for section in sections {
+        content.resize(align_up(content.len() as u64, 4) as usize, 0);
+        content.extend_from_slice(section);
+    }
+    content
+}
+
+// Encodes the NUL-terminated UCS-2 strings of UI and Version sections.
+fn encode_ucs2(string: &str) -> Vec<u8> {
+    string.encode_utf16().chain([0]).flat_map(u16::to_le_bytes).collect()
+}
+
+// The compressor of builders that were not given one.
+struct NoCompression;
+
+impl SectionCompressor for NoCompression {
+    fn compress(&self, _header: &section::header::GuidDefined, _content: &[u8]) -> Result<EncodedSection, efi::Status> {
+        Err(efi::Status::UNSUPPORTED)
+    }
+}
+
+/// Builds the sections of FFS files.
+///
+/// Leaf sections are built from their data, and encapsulation sections from the sections they hold, which are placed
+/// at 4-byte aligned offsets. The extended section header is used for sections too large for the 24-bit size field.
+/// Compression and GUID-defined sections are encoded with a pluggable [`SectionCompressor`], the reverse of the
+/// [`SectionExtractor`](super::SectionExtractor) that decodes them; without one, only NOT_COMPRESSED Compression
+/// sections can be built.
+///
+/// ## Example
+///```
+/// use mu_pi::fw_fs::{builder::SectionBuilder, ffs::section::header::NOT_COMPRESSED, FfsSectionType, Section};
+///
+/// let builder = SectionBuilder::new();
+/// let ui = builder.user_interface("Driver");
+/// let version = builder.version(1, "1.0");
+/// let compression = builder.compression(NOT_COMPRESSED, &[&ui, &version]).unwrap();
+///
+/// let section = Section::new(&compression).unwrap();
+/// assert_eq!(section.section_type(), Some(FfsSectionType::Compression));
+/// assert_eq!(section.section_data()[..ui.len()], ui[..]);
+///```
+#[derive(Clone, Copy)]
+pub struct SectionBuilder<'a> {
+    compressor: &'a dyn SectionCompressor,
+}
+
+impl Default for SectionBuilder<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a> SectionBuilder<'a> {
+    /// Creates a builder without a compressor.
+    pub fn new() -> Self {
+        Self { compressor: &NoCompression }
+    }
+
+    /// Sets the compressor that encodes Compression and GUID-defined sections.
+    pub fn compressor(mut self, compressor: &'a dyn SectionCompressor) -> Self {
+        self.compressor = compressor;
+        self
+    }
+
+    /// Builds a leaf section of the given raw type holding `data`, e.g. an OEM-defined section.
+    pub fn leaf(&self, section_type: u8, data: &[u8]) -> Vec<u8> {
+        build_section(section_type, &[], data)
+    }
+
+    /// Builds a PE32 section holding a PE32+ image.
+    pub fn pe32(&self, image: &[u8]) -> Vec<u8> {
+        self.leaf(FfsSectionRawType::PE32, image)
+    }
+
+    /// Builds a PIC section holding a position-independent image.
+    pub fn pic(&self, image: &[u8]) -> Vec<u8> {
+        self.leaf(FfsSectionRawType::PIC, image)
+    }
+
+    /// Builds a TE section holding a Terse Executable image.
+    pub fn te(&self, image: &[u8]) -> Vec<u8> {
+        self.leaf(FfsSectionRawType::TE, image)
+    }
+
+    /// Builds a RAW section.
+    pub fn raw(&self, data: &[u8]) -> Vec<u8> {
+        self.leaf(FfsSectionRawType::RAW, data)
+    }
+
+    /// Builds a COMPATIBILITY16 section holding a 16-bit image.
+    pub fn compatibility16(&self, image: &[u8]) -> Vec<u8> {
+        self.leaf(FfsSectionRawType::COMPATIBILITY16, image)
+    }
+
+    /// Builds a FIRMWARE_VOLUME_IMAGE section holding a firmware volume.
+    pub fn firmware_volume_image(&self, fv: &[u8]) -> Vec<u8> {
+        self.leaf(FfsSectionRawType::FIRMWARE_VOLUME_IMAGE, fv)
+    }
+
+    /// Builds a USER_INTERFACE section holding the NUL-terminated UCS-2 encoding of `name`.
+    pub fn user_interface(&self, name: &str) -> Vec<u8> {
+        self.leaf(FfsSectionRawType::USER_INTERFACE, &encode_ucs2(name))
+    }
+
+    /// Builds a VERSION section holding a build number and the NUL-terminated UCS-2 encoding of `version`.
+    pub fn version(&self, build_number: u16, version: &str) -> Vec<u8> {
+        build_section(FfsSectionRawType::VERSION, &build_number.to_le_bytes(), &encode_ucs2(version))
+    }
+
+    /// Builds a DXE_DEPEX section holding a dependency expression.
+    pub fn dxe_depex(&self, expression: &[u8]) -> Vec<u8> {
+        self.leaf(FfsSectionRawType::DXE_DEPEX, expression)
+    }
+
+    /// Builds a PEI_DEPEX section holding a dependency expression.
+    pub fn pei_depex(&self, expression: &[u8]) -> Vec<u8> {
+        self.leaf(FfsSectionRawType::PEI_DEPEX, expression)
+    }
+
+    /// Builds an MM_DEPEX section holding a dependency expression.
+    pub fn mm_depex(&self, expression: &[u8]) -> Vec<u8> {
+        self.leaf(FfsSectionRawType::MM_DEPEX, expression)
+    }
+
+    /// Builds a FREEFORM_SUBTYPE_GUID section holding data of the given sub-type.
+    pub fn freeform_subtype_guid(&self, sub_type_guid: efi::Guid, data: &[u8]) -> Vec<u8> {
+        build_section(FfsSectionRawType::FREEFORM_SUBTYPE_GUID, sub_type_guid.as_bytes(), data)
+    }
+
+    /// Builds a Compression section holding `sections`.
+    ///
+    /// Sections are stored as-is with the [`NOT_COMPRESSED`](section::header::NOT_COMPRESSED) type, and compressed with
+    /// the compressor otherwise. Returns [`BuildError::Encoding`] with `UNSUPPORTED` if the compressor does not support
+    /// the compression type, and with `BAD_BUFFER_SIZE` if the sections are too large for the uncompressed length field.
+    pub fn compression(&self, compression_type: u8, sections: &[&[u8]]) -> Result<Vec<u8>, BuildError> {
+        build_compression_section(compression_type, &concat_sections(sections.iter().copied()), self.compressor)
+            .map_err(BuildError::Encoding)
+    }
+
+    /// Builds a GUID-defined section holding `sections`, encoded with the compressor.
+    ///
+    /// Returns [`BuildError::Encoding`] with `UNSUPPORTED` if the compressor does not support the section definition
+    /// GUID.
+    pub fn guid_defined(
+        &self,
+        section_definition_guid: efi::Guid,
+        attributes: u16,
+        sections: &[&[u8]],
+    ) -> Result<Vec<u8>, BuildError> {
+        let content = concat_sections(sections.iter().copied());
+        build_guid_defined_section(section_definition_guid, attributes, &content, self.compressor)
+            .map_err(BuildError::Encoding)
+    }
+}
+
+/// Generates an empty firmware volume with the given file system, block map and attributes.
+///
+/// The size of the volume is the sum of the sizes of the blocks in `block_map`, which must not hold empty entries (the
+/// terminating entry of the block map is added). The header holds a zero vector, no extended header and a valid
+/// checksum, and the rest of the volume holds the erase byte of the ERASE_POLARITY attribute.
+///
+/// Returns [`BuildError::BadBlockMap`] if the block map is empty, holds an empty entry, or describes a volume too small
+/// for its header, and [`BuildError::VolumeTooLarge`] if the volume does not fit in memory.
+///
+/// ## Example
+///```
+/// use mu_pi::fw_fs::{builder::blank_volume, fv::BlockMapEntry, FfsFileSystem, FirmwareVolume, FvbAttributes2};
+///
+/// // a 64KB NVRAM region of 16 4KB blocks.
+/// let attributes = FvbAttributes2::ERASE_POLARITY | FvbAttributes2::MEMORY_MAPPED;
+/// let block_map = [BlockMapEntry { num_blocks: 16, length: 0x1000 }];
+/// let image = blank_volume(FfsFileSystem::Ffs2, &block_map, attributes).unwrap();
+///
+/// let fv = FirmwareVolume::new(&image).unwrap();
+/// assert_eq!(fv.size(), 0x10000);
+/// assert_eq!(fv.file_iter().count(), 0);
+///```
+pub fn blank_volume(
+    file_system: FfsFileSystem,
+    block_map: &[BlockMapEntry],
+    attributes: FvbAttributes2,
+) -> Result<Vec<u8>, BuildError> {
+    let header_length = mem::size_of::<fv::Header>() + (block_map.len() + 1) * mem::size_of::<BlockMapEntry>();
+    let fv_length = block_map
+        .iter()
+        .try_fold(0u64, |fv_length, entry| match (entry.num_blocks, entry.length) {
+            (0, _) | (_, 0) => None,
+            (num_blocks, length) => fv_length.checked_add(num_blocks as u64 * length as u64),
+        })
+        .ok_or(BuildError::BadBlockMap)?;
+    if block_map.is_empty() || header_length > u16::MAX as usize || fv_length < header_length as u64 {
+        Err(BuildError::BadBlockMap)?;
+    }
+    let fv_length_bytes = usize::try_from(fv_length).map_err(|_| BuildError::VolumeTooLarge { fv_length })?;
+
+    let mut buffer = vec![attributes.erase_byte(); fv_length_bytes];
+    let header = &mut buffer[..header_length];
+    header[..16].fill(0);
+    header[FILE_SYSTEM_GUID..FV_LENGTH].copy_from_slice(file_system.guid().as_bytes());
+    header[FV_LENGTH..FV_LENGTH + 8].copy_from_slice(&fv_length.to_le_bytes());
+    header[SIGNATURE..SIGNATURE + 4].copy_from_slice(b"_FVH");
+    header[ATTRIBUTES..ATTRIBUTES + 4].copy_from_slice(&attributes.bits().to_le_bytes());
+    header[HEADER_LENGTH..HEADER_LENGTH + 2].copy_from_slice(&(header_length as u16).to_le_bytes());
+    header[CHECKSUM..BLOCK_MAP].fill(0);
+    header[REVISION] = if file_system == FfsFileSystem::Ffs1 { 1 } else { FVH_REVISION };
+    let entries = header[BLOCK_MAP..].chunks_exact_mut(mem::size_of::<BlockMapEntry>());
+    for (bytes, entry) in entries.zip(block_map.iter().chain([&BlockMapEntry { num_blocks: 0, length: 0 }])) {
+        bytes[..4].copy_from_slice(&entry.num_blocks.to_le_bytes());
+        bytes[4..].copy_from_slice(&entry.length.to_le_bytes());
+    }
+    let checksum = checksum::compute16(header);
+    header[CHECKSUM..CHECKSUM + 2].copy_from_slice(&checksum.to_le_bytes());
+    Ok(buffer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fw_fs::{unit_tests::build_ffs2_file, FfsFileType, FirmwareVolume};
+
+    // Builds an FFS2 RAW file whose data requires the given DATA_ALIGNMENT field.
+    fn aligned_file(name: u8, data_alignment: u8, content: &[u8]) -> Vec<u8> {
+        let mut file = build_ffs2_file([name; 16], FfsFileRawType::RAW, content);
+        file[19] = data_alignment << 3;
+        file[16] = file[16].wrapping_sub(file[19]);
+        file
+    }
+
+    #[test]
+    fn files_should_be_placed_in_order_and_aligned() {
+        for erase_polarity in [FvbAttributes2::empty(), FvbAttributes2::ERASE_POLARITY] {
+            let name = efi::Guid::from_bytes(&[0x33; 16]);
+            // DATA_ALIGNMENT 5 is 4KB.
+            let image = FirmwareVolumeBuilder::new(FfsFileSystem::Ffs2)
+                .attributes(erase_polarity | FvbAttributes2::MEMORY_MAPPED)
+                .fv_name(name)
+                .add_file(aligned_file(1, 0, &[1; 5]))
+                .add_file(aligned_file(2, 5, &[2; 0x10]))
+                .add_file(aligned_file(3, 0, &[3; 3]))
+                .build()
+                .unwrap();
+            assert_eq!(image.len(), 0x2000);
+
+            let fv = FirmwareVolume::new(&image).unwrap();
+            assert_eq!(fv.fv_name(), Some(name));
+            assert_eq!(
+                fv.attributes(),
+                (erase_polarity | FvbAttributes2::MEMORY_MAPPED).with_alignment(0x1000).unwrap()
+            );
+            let files = fv.file_iter().collect::<Result<Vec<_>, efi::Status>>().unwrap();
+            let names: Vec<_> = files.iter().map(|file| file.name().as_bytes()[0]).collect();
+            assert_eq!(names, [1, 0xff, 2, 3]);
+            assert_eq!(files[1].file_type(), Some(FfsFileType::FfsPad));
+            for file in &files {
+                assert!(file.is_properly_aligned(&fv));
+                assert_eq!(file.verify_checksums(), Ok(()));
+                assert_eq!(file.state(), Some(crate::fw_fs::FfsFileState::DataValid));
+            }
+            assert_eq!(files[2].content().as_ptr() as usize - image.as_ptr() as usize, 0x1000);
+            assert!(image[fv.used_size().unwrap() as usize..].iter().all(|&byte| byte == fv.attributes().erase_byte()));
+        }
+    }
+
+    #[test]
+    fn deterministic_builds_should_not_depend_on_file_order() {
+        use crate::fw_fs::reproducibility::{check_volume, compare_volumes, Difference};
+
+        let files = [aligned_file(3, 0, &[3; 7]), aligned_file(1, 5, &[1; 0x10]), aligned_file(2, 0, &[2; 5])];
+        let build = |order: [usize; 3], deterministic: bool| {
+            let mut builder = FirmwareVolumeBuilder::new(FfsFileSystem::Ffs2)
+                .attributes(FvbAttributes2::ERASE_POLARITY)
+                .fv_name(efi::Guid::from_bytes(&[0x55; 16]));
+            for index in order {
+                builder = builder.add_file(files[index].clone());
+            }
+            if deterministic {
+                builder = builder.deterministic();
+            }
+            builder.build().unwrap()
+        };
+
+        let image = build([0, 1, 2], true);
+        for order in [[0, 2, 1], [1, 0, 2], [1, 2, 0], [2, 0, 1], [2, 1, 0]] {
+            assert_eq!(build(order, true), image);
+        }
+        assert_eq!(check_volume(&image).unwrap(), []);
+        let fv = FirmwareVolume::new(&image).unwrap();
+        let names: Vec<_> = fv.file_iter().map(|file| file.unwrap().name().as_bytes()[0]).collect();
+        assert_eq!(names, [0xff, 1, 2, 3]);
+
+        // without deterministic mode, the files are placed in the order they were added.
+        let differences = compare_volumes(&build([0, 1, 2], false), &build([2, 1, 0], false)).unwrap();
+        assert!(differences.iter().any(|difference| matches!(difference, Difference::FileOrder { index: 0, .. })));
+    }
+
+    #[test]
+    fn ext_header_should_be_held_by_a_pad_file() {
+        let name = efi::Guid::from_bytes(&[0x44; 16]);
+        let image = FirmwareVolumeBuilder::new(FfsFileSystem::Ffs2)
+            .attributes(FvbAttributes2::ERASE_POLARITY)
+            .fv_name(name)
+            .add_file(aligned_file(1, 0, &[1; 5]))
+            .build()
+            .unwrap();
+
+        let fv = FirmwareVolume::new(&image).unwrap();
+        let header_length = mem::size_of::<fv::Header>() + 2 * mem::size_of::<BlockMapEntry>();
+        let pad_file = File::new(&image[header_length..]).unwrap();
+        assert_eq!(pad_file.file_type(), Some(FfsFileType::FfsPad));
+        assert_eq!(pad_file.verify_checksums(), Ok(()));
+        assert_eq!(pad_file.state(), Some(crate::fw_fs::FfsFileState::DataValid));
+        assert_eq!(&pad_file.content()[..16], name.as_bytes());
+        assert_eq!(fv.ext_header().unwrap().fv_name(), name);
+
+        // the pad file is not reported, as the files of the volume start after the extended header.
+        let files = fv.file_iter().collect::<Result<Vec<_>, efi::Status>>().unwrap();
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].name(), efi::Guid::from_bytes(&[1; 16]));
+        assert_eq!(files[0].data().as_ptr() as usize - image.as_ptr() as usize, header_length + 48);
+    }
+
+    #[test]
+    fn large_pad_files_should_use_extended_header() {
+        let mut buffer = vec![0; MAX_FILE_SIZE + 9];
+        write_pad_file(&mut buffer, 0xff);
+        buffer[23] = FILE_STATE_VALID;
+        let pad_file = File::new(&buffer).unwrap();
+        assert_eq!(pad_file.size(), buffer.len() as u64);
+        assert_eq!(pad_file.file_type(), Some(FfsFileType::FfsPad));
+        assert!(pad_file.content().iter().all(|&byte| byte == 0xff));
+    }
+
+    #[test]
+    fn volume_size_should_be_checked() {
+        let builder =
+            FirmwareVolumeBuilder::new(FfsFileSystem::Ffs3).block_size(0x200).add_file(aligned_file(1, 0, &[0; 0x300]));
+        assert_eq!(builder.clone().size(0x200).build(), Err(BuildError::BadVolumeSize { size: 0x200 }));
+        assert_eq!(builder.clone().size(0x500).build(), Err(BuildError::BadVolumeSize { size: 0x500 }));
+        assert_eq!(builder.clone().size(0x800).build().unwrap().len(), 0x800);
+        assert_eq!(builder.build().unwrap().len(), 0x400);
+
+        assert_eq!(FirmwareVolumeBuilder::new(FfsFileSystem::Ffs1).build(), Err(BuildError::UnsupportedFileSystem));
+        assert_eq!(efi::Status::from(BuildError::UnsupportedFileSystem), efi::Status::UNSUPPORTED);
+        let corrupt = FirmwareVolumeBuilder::new(FfsFileSystem::Ffs2).add_file(&[0u8; 8][..]);
+        assert!(matches!(corrupt.build(), Err(BuildError::InvalidFile { index: 0, .. })));
+        assert_eq!(efi::Status::from(corrupt.build().unwrap_err()), efi::Status::INVALID_PARAMETER);
+    }
+
+    #[test]
+    fn built_files_should_parse_with_their_sections() {
+        use crate::fw_fs::{rebuild::build_section, FfsSectionRawType, FfsSectionType};
+
+        let name = efi::Guid::from_bytes(&[0x42; 16]);
+        let file = FfsFileBuilder::new(name, FfsFileRawType::FREEFORM)
+            .attributes(FfsAttributes::CHECKSUM | FfsAttributes::FIXED | FfsAttributes::LARGE_FILE)
+            .alignment(0x20)
+            .add_section(build_section(FfsSectionRawType::RAW, &[], &[1; 5]))
+            .add_section(build_section(FfsSectionRawType::RAW, &[], &[2; 3]))
+            .build()
+            .unwrap();
+        assert_eq!(file.len(), 24 + 12 + 7);
+
+        let file = File::new(&file).unwrap();
+        assert_eq!(file.name(), name);
+        assert_eq!(file.file_type_raw(), FfsFileRawType::FREEFORM);
+        // 32 bytes is rounded up to the next alignment the attributes encode, 128 bytes.
+        assert_eq!(file.required_alignment(), 0x80);
+        assert!(file.attributes().contains(FfsAttributes::CHECKSUM | FfsAttributes::FIXED));
+        assert!(!file.attributes().contains(FfsAttributes::LARGE_FILE));
+        assert_eq!(file.verify_checksums(), Ok(()));
+        let sections = file.section_iter().collect::<Result<Vec<_>, efi::Status>>().unwrap();
+        assert_eq!(sections.len(), 2);
+        assert!(sections.iter().all(|section| section.section_type() == Some(FfsSectionType::Raw)));
+        assert_eq!(sections[1].section_data(), &[2; 3]);
+
+        let image = FirmwareVolumeBuilder::new(FfsFileSystem::Ffs2).add_file(file.data()).build().unwrap();
+        let fv = FirmwareVolume::new(&image).unwrap();
+        // the file follows the pad file that aligns it.
+        let placed = fv.file_iter().last().unwrap().unwrap();
+        assert_eq!(placed.name(), name);
+        assert!(placed.is_properly_aligned(&fv));
+
+        assert_eq!(
+            FfsFileBuilder::new(name, FfsFileRawType::RAW).alignment(0x200_0000).build(),
+            Err(BuildError::BadAlignment { alignment: 0x200_0000 })
+        );
+    }
+
+    #[test]
+    fn large_files_should_use_extended_header() {
+        let name = efi::Guid::from_bytes(&[0x24; 16]);
+        let content = vec![0x5a; MAX_FILE_SIZE];
+        for checksum in [FfsAttributes::empty(), FfsAttributes::CHECKSUM] {
+            let bytes = FfsFileBuilder::new(name, FfsFileRawType::RAW)
+                .attributes(checksum)
+                .add_section(&content[..])
+                .build()
+                .unwrap();
+            assert_eq!(bytes.len(), 32 + MAX_FILE_SIZE);
+
+            let file = File::new(&bytes).unwrap();
+            assert!(file.attributes().contains(FfsAttributes::LARGE_FILE));
+            assert_eq!(file.size(), bytes.len() as u64);
+            assert_eq!(file.content().len(), MAX_FILE_SIZE);
+            assert_eq!(file.verify_checksums(), Ok(()));
+        }
+    }
+
+    #[test]
+    fn sections_should_parse_with_their_types_and_data() {
+        use crate::fw_fs::{FfsSectionType, Section, SectionMetaData};
+
+        let builder = SectionBuilder::new();
+        let sub_type = efi::Guid::from_bytes(&[0x17; 16]);
+        let leaves = [
+            (builder.pe32(&[1; 3]), FfsSectionType::Pe32),
+            (builder.pic(&[1; 3]), FfsSectionType::Pic),
+            (builder.te(&[1; 3]), FfsSectionType::Te),
+            (builder.raw(&[1; 3]), FfsSectionType::Raw),
+            (builder.compatibility16(&[1; 3]), FfsSectionType::Compatibility16),
+            (builder.firmware_volume_image(&[1; 3]), FfsSectionType::FirmwareVolumeImage),
+            (builder.dxe_depex(&[1; 3]), FfsSectionType::DxeDepex),
+            (builder.pei_depex(&[1; 3]), FfsSectionType::PeiDepex),
+            (builder.mm_depex(&[1; 3]), FfsSectionType::MmDepex),
+            (builder.freeform_subtype_guid(sub_type, &[1; 3]), FfsSectionType::FreeformSubtypeGuid),
+        ];
+        for (bytes, section_type) in &leaves {
+            let section = Section::new(bytes).unwrap();
+            assert_eq!(section.section_type(), Some(*section_type));
+            assert_eq!(section.section_data(), &[1; 3]);
+        }
+        let freeform = Section::new(&leaves[9].0).unwrap();
+        assert!(
+            matches!(freeform.meta_data(), SectionMetaData::FreeformSubtypeGuid(header) if header.sub_type_guid == sub_type)
+        );
+
+        let ui = builder.user_interface("Ui");
+        assert_eq!(Section::new(&ui).unwrap().section_data(), &[b'U', 0, b'i', 0, 0, 0]);
+        let version = builder.version(7, "1");
+        let version = Section::new(&version).unwrap();
+        assert!(matches!(version.meta_data(), SectionMetaData::Version(header) if header.build_number == 7));
+        assert_eq!(version.section_data(), &[b'1', 0, 0, 0]);
+    }
+
+    #[test]
+    fn encapsulation_sections_should_hold_aligned_sections() {
+        use crate::fw_fs::{Section, SectionMetaData};
+
+        // stores the content as-is, without GUID-specific header fields.
+        struct Identity;
+        impl SectionCompressor for Identity {
+            fn compress(
+                &self,
+                _header: &section::header::GuidDefined,
+                content: &[u8],
+            ) -> Result<EncodedSection, efi::Status> {
+                Ok(EncodedSection { guid_specific: Box::new([]), data: content.into() })
+            }
+        }
+
+        let guid = efi::Guid::from_bytes(&[0x71; 16]);
+        let builder = SectionBuilder::new();
+        let (first, second) = (builder.raw(&[1; 3]), builder.raw(&[2; 2]));
+        assert_eq!(
+            builder.guid_defined(guid, 0, &[&first, &second]),
+            Err(BuildError::Encoding(efi::Status::UNSUPPORTED))
+        );
+        assert_eq!(builder.compression(2, &[&first]), Err(BuildError::Encoding(efi::Status::UNSUPPORTED)));
+
+        let bytes = builder.compressor(&Identity).guid_defined(guid, 0, &[&first, &second]).unwrap();
+        let section = Section::new(&bytes).unwrap();
+        assert!(
+            matches!(section.meta_data(), SectionMetaData::GuidDefined(header, _) if header.section_definition_guid == guid)
+        );
+        // the second section starts at the next 4-byte aligned offset.
+        assert_eq!(section.section_data(), [&first[..], &[0], &second[..]].concat());
+    }
+
+    #[test]
+    fn blank_volumes_should_parse_and_be_erased() {
+        let block_map =
+            [BlockMapEntry { num_blocks: 2, length: 0x1000 }, BlockMapEntry { num_blocks: 4, length: 0x200 }];
+        for file_system in [FfsFileSystem::Ffs1, FfsFileSystem::Ffs2, FfsFileSystem::Ffs3] {
+            for attributes in [FvbAttributes2::empty(), FvbAttributes2::ERASE_POLARITY | FvbAttributes2::WEAK_ALIGNMENT]
+            {
+                let image = blank_volume(file_system, &block_map, attributes).unwrap();
+                assert_eq!(image.len(), 0x2800);
+                assert_eq!(image[..16], [0; 16]);
+
+                let fv = FirmwareVolume::new(&image).unwrap();
+                assert_eq!(fv.file_system(), file_system);
+                assert_eq!(fv.attributes(), attributes);
+                assert_eq!(fv.block_map().collect::<Vec<_>>(), block_map);
+                assert_eq!(fv.file_iter().count(), 0);
+                assert_eq!(fv.free_space().unwrap(), 0x2800 - 0x50);
+                assert!(image[0x50..].iter().all(|&byte| byte == attributes.erase_byte()));
+            }
+        }
+    }
+
+    #[test]
+    fn invalid_block_maps_should_be_rejected() {
+        let attributes = FvbAttributes2::ERASE_POLARITY;
+        for block_map in [
+            &[][..],
+            &[BlockMapEntry { num_blocks: 0, length: 0x1000 }],
+            &[BlockMapEntry { num_blocks: 1, length: 0 }],
+            &[BlockMapEntry { num_blocks: 1, length: 0x40 }],
+        ] {
+            assert_eq!(blank_volume(FfsFileSystem::Ffs2, block_map, attributes), Err(BuildError::BadBlockMap));
+        }
+    }
+}
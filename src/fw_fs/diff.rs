@@ -0,0 +1,325 @@
+//! Firmware Volume Diffing
+//!
+//! Support for comparing the contents of two firmware volumes, typically an installed firmware image and an update
+//! for it. [`diff_volumes`] matches the files of the volumes by name and reports the files that were added, removed or
+//! changed, with the sections that changed in each changed file. Unlike
+//! [`compare_volumes`](super::reproducibility::compare_volumes), the layout of the volumes is not compared: files that
+//! only moved are not reported.
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+use alloc::vec::Vec;
+use core::fmt;
+use r_efi::efi;
+
+use super::{FfsFileRawType, File, FirmwareVolume, Section, SectionExtractor};
+use crate::crc32::crc32;
+
+/// A difference between a file of two firmware volumes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FileDiff {
+    /// The file is only in the right volume.
+    Added { name: efi::Guid, file_type: u8, crc32: u32 },
+    /// The file is only in the left volume.
+    Removed { name: efi::Guid, file_type: u8, crc32: u32 },
+    /// The file type or content differs between the volumes.
+    Changed {
+        name: efi::Guid,
+        left_type: u8,
+        right_type: u8,
+        /// The CRC32 of the content of the file in the left volume.
+        left_crc32: u32,
+        /// The CRC32 of the content of the file in the right volume.
+        right_crc32: u32,
+        sections: Vec<SectionDiff>,
+    },
+}
+
+/// A difference between the sections of a changed file.
+///
+/// Sections are matched by position, in the order they are returned by
+/// [`File::section_iter_with_extractor`], so the sections extracted from an encapsulation section follow it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SectionDiff {
+    /// The right file holds more sections; this one is only in the right file.
+    Added { index: usize, section_type: u8 },
+    /// The left file holds more sections; this one is only in the left file.
+    Removed { index: usize, section_type: u8 },
+    /// The type or data of the section differs between the files.
+    Changed { index: usize, left_type: u8, right_type: u8 },
+}
+
+impl fmt::Display for FileDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FileDiff::Added { name, file_type, crc32 } => {
+                write!(f, "file {name:?} of type {file_type:#04x} was added (crc32 {crc32:#010x})")
+            }
+            FileDiff::Removed { name, file_type, crc32 } => {
+                write!(f, "file {name:?} of type {file_type:#04x} was removed (crc32 {crc32:#010x})")
+            }
+            FileDiff::Changed { name, left_crc32, right_crc32, sections, .. } => {
+                write!(f, "file {name:?} changed (crc32 {left_crc32:#010x} -> {right_crc32:#010x})")?;
+                for section in sections {
+                    write!(f, "; {section}")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl fmt::Display for SectionDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SectionDiff::Added { index, section_type } => {
+                write!(f, "section {index} of type {section_type:#04x} was added")
+            }
+            SectionDiff::Removed { index, section_type } => {
+                write!(f, "section {index} of type {section_type:#04x} was removed")
+            }
+            SectionDiff::Changed { index, left_type, right_type } if left_type != right_type => {
+                write!(f, "section {index} changed from type {left_type:#04x} to {right_type:#04x}")
+            }
+            SectionDiff::Changed { index, left_type, .. } => {
+                write!(f, "section {index} of type {left_type:#04x} changed")
+            }
+        }
+    }
+}
+
+/// Compares the files of two firmware volumes.
+///
+/// Files are matched by name; pad files are ignored, and files that share a name (which the PI specification forbids)
+/// are matched in the order they appear in the volumes. The differences are returned in the order of the left volume,
+/// followed by the files added in the right volume in their order. An empty result means both volumes hold the same
+/// files with the same types and contents.
+///
+/// The sections of changed files are compared with encapsulation sections expanded by `extractor`. Errors encountered
+/// while parsing or extracting stop the comparison and are returned.
+///
+/// ## Example
+///```
+/// # use std::{env, fs, path::Path, error::Error};
+/// use mu_pi::fw_fs::{diff::diff_volumes, FirmwareVolume, Section, SectionExtractor};
+/// use r_efi::efi;
+///
+/// struct NoExtraction;
+/// impl SectionExtractor for NoExtraction {
+///   fn extract(&self, _section: &Section) -> Result<Box<[u8]>, efi::Status> {
+///     Ok(Box::new([]))
+///   }
+/// }
+///
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// # let root = Path::new(&env::var("CARGO_MANIFEST_DIR")?).join("test_resources");
+/// # let installed = fs::read(root.join("DXEFV.Fv"))?;
+/// # let update = installed.clone();
+/// let installed = FirmwareVolume::new(&installed).expect("Firmware Volume Corrupt");
+/// let update = FirmwareVolume::new(&update).expect("Firmware Volume Corrupt");
+/// for diff in diff_volumes(&installed, &update, &NoExtraction).expect("parse error") {
+///   println!("{diff}");
+/// }
+/// # Ok(())
+/// # }
+///```
+pub fn diff_volumes(
+    left: &FirmwareVolume,
+    right: &FirmwareVolume,
+    extractor: &dyn SectionExtractor,
+) -> Result<Vec<FileDiff>, efi::Status> {
+    let left_files = named_files(left)?;
+    let right_files = named_files(right)?;
+    let mut matched = Vec::new();
+    let mut diffs = Vec::new();
+
+    for (index, left_file) in left_files.iter().enumerate() {
+        let name = left_file.name();
+        let occurrence = left_files[..index].iter().filter(|file| file.name() == name).count();
+        let right_index = right_files
+            .iter()
+            .enumerate()
+            .filter(|(_, file)| file.name() == name)
+            .nth(occurrence)
+            .map(|(index, _)| index);
+        let Some(right_index) = right_index else {
+            diffs.push(FileDiff::Removed {
+                name,
+                file_type: left_file.file_type_raw(),
+                crc32: crc32(left_file.content()),
+            });
+            continue;
+        };
+        matched.push(right_index);
+
+        let right_file = &right_files[right_index];
+        if left_file.file_type_raw() != right_file.file_type_raw() || left_file.content() != right_file.content() {
+            diffs.push(FileDiff::Changed {
+                name,
+                left_type: left_file.file_type_raw(),
+                right_type: right_file.file_type_raw(),
+                left_crc32: crc32(left_file.content()),
+                right_crc32: crc32(right_file.content()),
+                sections: diff_sections(&sections(left_file, extractor)?, &sections(right_file, extractor)?),
+            });
+        }
+    }
+
+    for (index, right_file) in right_files.iter().enumerate() {
+        if !matched.contains(&index) {
+            diffs.push(FileDiff::Added {
+                name: right_file.name(),
+                file_type: right_file.file_type_raw(),
+                crc32: crc32(right_file.content()),
+            });
+        }
+    }
+    Ok(diffs)
+}
+
+// Returns the files of the volume, without its pad files.
+fn named_files<'a>(fv: &FirmwareVolume<'a>) -> Result<Vec<File<'a>>, efi::Status> {
+    let mut files = Vec::new();
+    for file in fv.file_iter() {
+        let file = file?;
+        if file.file_type_raw() != FfsFileRawType::FFS_PAD {
+            files.push(file);
+        }
+    }
+    Ok(files)
+}
+
+// Returns the sections of the file, or none if its type does not hold sections.
+fn sections(file: &File, extractor: &dyn SectionExtractor) -> Result<Vec<Section>, efi::Status> {
+    if (FfsFileRawType::FREEFORM..=FfsFileRawType::MM_CORE_STANDALONE).contains(&file.file_type_raw()) {
+        file.section_iter_with_extractor(extractor).collect()
+    } else {
+        Ok(Vec::new())
+    }
+}
+
+fn diff_sections(left: &[Section], right: &[Section]) -> Vec<SectionDiff> {
+    let mut diffs = Vec::new();
+    for (index, (left, right)) in left.iter().zip(right).enumerate() {
+        if left.section_type_raw() != right.section_type_raw() || left.raw_section_data() != right.raw_section_data() {
+            diffs.push(SectionDiff::Changed {
+                index,
+                left_type: left.section_type_raw(),
+                right_type: right.section_type_raw(),
+            });
+        }
+    }
+    for (index, section) in left.iter().enumerate().skip(right.len()) {
+        diffs.push(SectionDiff::Removed { index, section_type: section.section_type_raw() });
+    }
+    for (index, section) in right.iter().enumerate().skip(left.len()) {
+        diffs.push(SectionDiff::Added { index, section_type: section.section_type_raw() });
+    }
+    diffs
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs, path::Path};
+
+    use super::*;
+    use crate::{
+        address_helper::align_up,
+        fw_fs::{
+            rebuild::build_section,
+            unit_tests::{build_ffs2_file, build_ffs2_volume},
+            FfsSectionRawType, NullSectionExtractor,
+        },
+    };
+
+    // Builds an FFS2 volume holding the given files.
+    fn build_volume(files: &[Vec<u8>]) -> Vec<u8> {
+        let mut content = Vec::new();
+        for file in files {
+            content.resize(align_up(content.len() as u64, 8) as usize, 0);
+            content.extend_from_slice(file);
+        }
+        build_ffs2_volume(&content)
+    }
+
+    fn freeform_file(name: u8, sections: &[Vec<u8>]) -> Vec<u8> {
+        let mut content = Vec::new();
+        for section in sections {
+            content.resize(align_up(content.len() as u64, 4) as usize, 0);
+            content.extend_from_slice(section);
+        }
+        build_ffs2_file([name; 16], FfsFileRawType::FREEFORM, &content)
+    }
+
+    #[test]
+    fn identical_volumes_should_have_no_diffs() {
+        let root = Path::new(&env::var("CARGO_MANIFEST_DIR").unwrap()).join("test_resources");
+        let fv_bytes = fs::read(root.join("DXEFV.Fv")).unwrap();
+        let fv = FirmwareVolume::new(&fv_bytes).unwrap();
+        assert_eq!(diff_volumes(&fv, &fv, &NullSectionExtractor {}).unwrap(), []);
+    }
+
+    #[test]
+    fn added_removed_and_changed_files_should_be_reported() {
+        let raw = build_section(FfsSectionRawType::RAW, &[], &[1, 2, 3]);
+        let changed_raw = build_section(FfsSectionRawType::RAW, &[], &[1, 2, 4]);
+        let ui = build_section(FfsSectionRawType::USER_INTERFACE, &[], &[b'A', 0, 0, 0]);
+
+        let left_bytes = build_volume(&[
+            freeform_file(1, &[raw.clone()]),
+            freeform_file(2, &[raw.clone(), ui.clone()]),
+            build_ffs2_file([0xff; 16], FfsFileRawType::FFS_PAD, &[]),
+            freeform_file(3, &[raw.clone()]),
+        ]);
+        // file 3 is removed, file 2 loses its UI section and has its RAW section changed, and file 4 is added. The order
+        // of files 1 and 2 is swapped, which is not a difference.
+        let right_bytes = build_volume(&[
+            freeform_file(2, &[changed_raw.clone()]),
+            freeform_file(1, &[raw.clone()]),
+            freeform_file(4, &[raw.clone()]),
+        ]);
+        let left = FirmwareVolume::new(&left_bytes).unwrap();
+        let right = FirmwareVolume::new(&right_bytes).unwrap();
+        let content = |fv: &FirmwareVolume, name: u8| {
+            crc32(fv.file_by_name(efi::Guid::from_bytes(&[name; 16])).unwrap().content())
+        };
+
+        let diffs = diff_volumes(&left, &right, &NullSectionExtractor {}).unwrap();
+        assert_eq!(
+            diffs,
+            [
+                FileDiff::Changed {
+                    name: efi::Guid::from_bytes(&[2; 16]),
+                    left_type: FfsFileRawType::FREEFORM,
+                    right_type: FfsFileRawType::FREEFORM,
+                    left_crc32: content(&left, 2),
+                    right_crc32: content(&right, 2),
+                    sections: vec![
+                        SectionDiff::Changed {
+                            index: 0,
+                            left_type: FfsSectionRawType::RAW,
+                            right_type: FfsSectionRawType::RAW
+                        },
+                        SectionDiff::Removed { index: 1, section_type: FfsSectionRawType::USER_INTERFACE },
+                    ],
+                },
+                FileDiff::Removed {
+                    name: efi::Guid::from_bytes(&[3; 16]),
+                    file_type: FfsFileRawType::FREEFORM,
+                    crc32: content(&left, 3),
+                },
+                FileDiff::Added {
+                    name: efi::Guid::from_bytes(&[4; 16]),
+                    file_type: FfsFileRawType::FREEFORM,
+                    crc32: content(&right, 4),
+                },
+            ]
+        );
+        assert!(diffs[0].to_string().ends_with("section 0 of type 0x19 changed; section 1 of type 0x15 was removed"));
+    }
+}
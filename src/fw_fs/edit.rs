@@ -0,0 +1,320 @@
+//! Firmware Volume Editing
+//!
+//! [`FirmwareVolumeMut`] edits the files of a firmware volume in place, without moving the other files of the volume:
+//! the content of a file can be replaced with content of the same size or smaller, and files can be marked deleted.
+//! This supports patching the files of a firmware image.
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+use core::{fmt, mem};
+use r_efi::efi;
+
+use super::{
+    builder::{write_pad_file, FILE_STATE_VALID, MAX_FILE_SIZE},
+    ffs::file,
+    FfsFileRawState, FfsFileSystem, FfsRawAttribute, FirmwareVolume, FwFsError,
+};
+use crate::{checksum, parse::ParseOptions};
+
+/// Errors returned while editing a firmware volume.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum EditError {
+    /// The firmware volume, or a file in it, is malformed.
+    Malformed(FwFsError),
+    /// The firmware volume is an FFS1 volume, whose files have a different layout.
+    UnsupportedFileSystem,
+    /// The firmware volume holds no file with the given name.
+    FileNotFound { name: efi::Guid },
+    /// The new content is larger than the current content of the file.
+    ContentTooLarge { name: efi::Guid, size: usize, available: usize },
+    /// The space left before the next file is too small or too large to be covered by a pad file.
+    BadPadSize { size: usize },
+}
+
+impl fmt::Display for EditError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EditError::Malformed(error) => write!(f, "{error}"),
+            EditError::UnsupportedFileSystem => write!(f, "FFS1 volumes cannot be edited"),
+            EditError::FileNotFound { name } => write!(f, "file {name:?} not found"),
+            EditError::ContentTooLarge { name, size, available } => {
+                write!(f, "content of {size:#x} bytes does not fit the {available:#x} bytes of file {name:?}")
+            }
+            EditError::BadPadSize { size } => write!(f, "space of {size:#x} bytes cannot be covered by a pad file"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for EditError {}
+
+impl From<FwFsError> for EditError {
+    fn from(error: FwFsError) -> Self {
+        EditError::Malformed(error)
+    }
+}
+
+impl From<EditError> for efi::Status {
+    fn from(error: EditError) -> Self {
+        match error {
+            EditError::Malformed(error) => error.into(),
+            EditError::UnsupportedFileSystem => efi::Status::UNSUPPORTED,
+            EditError::FileNotFound { .. } => efi::Status::NOT_FOUND,
+            EditError::ContentTooLarge { .. } | EditError::BadPadSize { .. } => efi::Status::BAD_BUFFER_SIZE,
+        }
+    }
+}
+
+// The location of a file in the volume.
+struct FileLocation {
+    offset: usize,
+    header_size: usize,
+    size: usize,
+    erase_byte: u8,
+    // whether the file is the last file of the volume.
+    last: bool,
+}
+
+/// A firmware volume whose files are edited in place.
+///
+/// Files are identified by name; when several files have the same name, the first one is edited. The volume keeps its
+/// layout: the files that follow an edited file are not moved. Deleted files are skipped when the volume is parsed.
+///
+/// ## Example
+///```
+/// use mu_pi::fw_fs::{edit::FirmwareVolumeMut, FirmwareVolume};
+/// use r_efi::efi;
+///
+/// fn patch(image: &mut [u8], name: efi::Guid, content: &[u8]) -> Result<(), efi::Status> {
+///   let mut fv = FirmwareVolumeMut::new(image)?;
+///   fv.replace_file_content(name, content)?;
+///   assert_eq!(fv.firmware_volume()?.file_by_name(name).ok_or(efi::Status::NOT_FOUND)?.content(), content);
+///   Ok(())
+/// }
+///
+/// fn try_patch(image: &mut [u8], name: efi::Guid, content: &[u8]) {
+///   if let Err(err) = FirmwareVolumeMut::new(image).and_then(|mut fv| fv.replace_file_content(name, content)) {
+///     println!("cannot patch file {name:?}: {err}");
+///   }
+/// }
+///```
+#[derive(Debug)]
+pub struct FirmwareVolumeMut<'a> {
+    buffer: &'a mut [u8],
+    options: ParseOptions,
+}
+
+impl<'a> FirmwareVolumeMut<'a> {
+    /// Instantiates an editable firmware volume over the given buffer, which must hold a valid firmware volume.
+    ///
+    /// The volume is parsed with the default options, except that invalid files such as deleted files are skipped.
+    /// Returns [`EditError::UnsupportedFileSystem`] for FFS1 volumes, and the errors of [`FirmwareVolume::try_new`]
+    /// otherwise.
+    pub fn new(buffer: &'a mut [u8]) -> Result<Self, EditError> {
+        Self::new_with_options(buffer, &ParseOptions { skip_invalid_files: true, ..ParseOptions::default() })
+    }
+
+    /// Instantiates an editable firmware volume over the given buffer, parsed with the given options.
+    ///
+    /// See [`FirmwareVolumeMut::new`].
+    pub fn new_with_options(buffer: &'a mut [u8], options: &ParseOptions) -> Result<Self, EditError> {
+        if FirmwareVolume::try_new(buffer, options)?.file_system() == FfsFileSystem::Ffs1 {
+            Err(EditError::UnsupportedFileSystem)?;
+        }
+        Ok(Self { buffer, options: *options })
+    }
+
+    /// Parses the firmware volume as it currently is.
+    pub fn firmware_volume(&self) -> Result<FirmwareVolume<'_>, FwFsError> {
+        FirmwareVolume::try_new(self.buffer, &self.options)
+    }
+
+    /// Replaces the content of the file with the given name, keeping its header.
+    ///
+    /// The size of the file shrinks to fit `content`, and its header and data checksums are recomputed. The space the
+    /// file no longer covers holds the erase byte; when other files follow, it is covered by a pad file so that they
+    /// remain reachable.
+    ///
+    /// Returns [`EditError::FileNotFound`] if the volume holds no such file, [`EditError::ContentTooLarge`] if
+    /// `content` is larger than the current content of the file, and [`EditError::BadPadSize`] if it leaves a space
+    /// before the next file that cannot hold a pad file.
+    pub fn replace_file_content(&mut self, name: efi::Guid, content: &[u8]) -> Result<(), EditError> {
+        let location = self.locate(name)?;
+        let new_size = location.header_size + content.len();
+        if new_size > location.size {
+            Err(EditError::ContentTooLarge {
+                name,
+                size: content.len(),
+                available: location.size - location.header_size,
+            })?;
+        }
+        // the files that follow start at the next 8-byte aligned offset.
+        let pad_start = self.options.alignment.align(self.buffer, location.offset + new_size, 8);
+        let pad_end =
+            self.options.alignment.align(self.buffer, location.offset + location.size, 8).min(self.buffer.len());
+        let pad_size = pad_end.saturating_sub(pad_start);
+        if !location.last && pad_size != 0 {
+            // pad files are written with a standard header, which limits them to 16MB.
+            if pad_size < mem::size_of::<file::Header>() || pad_size > MAX_FILE_SIZE {
+                Err(EditError::BadPadSize { size: pad_size })?;
+            }
+        }
+
+        let file = &mut self.buffer[location.offset..location.offset + location.size];
+        file[location.header_size..new_size].copy_from_slice(content);
+        file[new_size..].fill(location.erase_byte);
+        if location.header_size > mem::size_of::<file::Header>() {
+            file[mem::size_of::<file::Header>()..location.header_size]
+                .copy_from_slice(&(new_size as u64).to_le_bytes());
+        } else {
+            file[20..23].copy_from_slice(&(new_size as u32).to_le_bytes()[..3]);
+        }
+        write_file_checksums(&mut file[..new_size], location.header_size);
+
+        if !location.last && pad_size != 0 {
+            let pad = &mut self.buffer[pad_start..pad_end];
+            write_pad_file(pad, location.erase_byte);
+            pad[23] = if location.erase_byte == 0 { FILE_STATE_VALID } else { !FILE_STATE_VALID };
+        }
+        Ok(())
+    }
+
+    /// Marks the file with the given name deleted, by setting the DELETED bit of its state.
+    ///
+    /// The file keeps its space in the volume, which is skipped when the files are iterated. Returns
+    /// [`EditError::FileNotFound`] if the volume holds no such file.
+    pub fn delete_file(&mut self, name: efi::Guid) -> Result<(), EditError> {
+        let location = self.locate(name)?;
+        // the state is not covered by the header checksum, and bits are only ever moved away from the erase polarity.
+        let state = &mut self.buffer[location.offset + 23];
+        if location.erase_byte == 0 {
+            *state |= FfsFileRawState::DELETED;
+        } else {
+            *state &= !FfsFileRawState::DELETED;
+        }
+        Ok(())
+    }
+
+    // Finds the first file with the given name.
+    fn locate(&self, name: efi::Guid) -> Result<FileLocation, EditError> {
+        let fv = self.firmware_volume()?;
+        let used_size = fv.walk_free_blocks(&mut |_| ())?;
+        for file in fv.try_file_iter() {
+            let file = file?;
+            if file.name() == name {
+                let offset = file.data().as_ptr() as usize - self.buffer.as_ptr() as usize;
+                let size = file.data().len();
+                return Ok(FileLocation {
+                    offset,
+                    header_size: file.header_size,
+                    size,
+                    erase_byte: fv.attributes().erase_byte(),
+                    last: offset + size >= used_size,
+                });
+            }
+        }
+        Err(EditError::FileNotFound { name })
+    }
+}
+
+// Recomputes the header and data checksums of a file of a volume whose file system is FFS2 or FFS3.
+fn write_file_checksums(file: &mut [u8], header_size: usize) {
+    // the header checksum excludes the file checksum and state.
+    let state = file[23];
+    file[16] = 0;
+    file[17] = 0;
+    file[23] = 0;
+    file[16] = checksum::compute8(&file[..header_size]);
+    file[17] = if file[19] & FfsRawAttribute::CHECKSUM != 0 {
+        checksum::compute8(&file[header_size..])
+    } else {
+        FfsFileSystem::Ffs2.fixed_file_checksum()
+    };
+    file[23] = state;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fw_fs::{
+        builder::{FfsFileBuilder, FirmwareVolumeBuilder},
+        FfsAttributes, FfsFileRawType, FfsFileType, FvbAttributes2,
+    };
+
+    fn name(value: u8) -> efi::Guid {
+        efi::Guid::from_bytes(&[value; 16])
+    }
+
+    fn build_volume(attributes: FvbAttributes2, sizes: &[usize]) -> Vec<u8> {
+        let mut builder = FirmwareVolumeBuilder::new(FfsFileSystem::Ffs2).attributes(attributes);
+        for (index, &size) in sizes.iter().enumerate() {
+            let value = index as u8 + 1;
+            let file = FfsFileBuilder::new(name(value), FfsFileRawType::RAW)
+                .attributes(FfsAttributes::CHECKSUM)
+                .add_section(vec![value; size])
+                .build()
+                .unwrap();
+            builder = builder.add_file(file);
+        }
+        builder.build().unwrap()
+    }
+
+    #[test]
+    fn replaced_content_should_be_padded() {
+        for attributes in [FvbAttributes2::empty(), FvbAttributes2::ERASE_POLARITY] {
+            let mut image = build_volume(attributes, &[0x40, 0x100, 0x20]);
+            let mut fv = FirmwareVolumeMut::new(&mut image).unwrap();
+            fv.replace_file_content(name(2), &[0x22; 0x30]).unwrap();
+            // the last file does not need a pad file.
+            fv.replace_file_content(name(3), &[0x33; 4]).unwrap();
+
+            let fv = fv.firmware_volume().unwrap();
+            let files = fv.file_iter().collect::<Result<Vec<_>, efi::Status>>().unwrap();
+            let types: Vec<_> = files.iter().map(|file| file.file_type()).collect();
+            assert_eq!(
+                types,
+                [Some(FfsFileType::Raw), Some(FfsFileType::Raw), Some(FfsFileType::FfsPad), Some(FfsFileType::Raw)]
+            );
+            assert!(files.iter().all(|file| file.verify_checksums().is_ok()));
+            assert_eq!(files[1].content(), &[0x22; 0x30]);
+            assert_eq!(files[3].content(), &[0x33; 4]);
+            let used_size = fv.used_size().unwrap() as usize;
+            assert!(image[used_size..].iter().all(|&byte| byte == attributes.erase_byte()));
+        }
+    }
+
+    #[test]
+    fn replacement_should_fit_the_file() {
+        let mut image = build_volume(FvbAttributes2::ERASE_POLARITY, &[0x40, 0x20]);
+        let mut fv = FirmwareVolumeMut::new(&mut image).unwrap();
+        assert_eq!(
+            fv.replace_file_content(name(1), &[0; 0x41]),
+            Err(EditError::ContentTooLarge { name: name(1), size: 0x41, available: 0x40 })
+        );
+        // an 8-byte space cannot hold a pad file.
+        assert_eq!(fv.replace_file_content(name(1), &[0; 0x38]), Err(EditError::BadPadSize { size: 8 }));
+        assert_eq!(fv.replace_file_content(name(9), &[]), Err(EditError::FileNotFound { name: name(9) }));
+        assert_eq!(efi::Status::from(EditError::FileNotFound { name: name(9) }), efi::Status::NOT_FOUND);
+        fv.replace_file_content(name(1), &[1; 0x40]).unwrap();
+        assert_eq!(image, build_volume(FvbAttributes2::ERASE_POLARITY, &[0x40, 0x20]));
+    }
+
+    #[test]
+    fn deleted_files_should_be_skipped() {
+        for attributes in [FvbAttributes2::empty(), FvbAttributes2::ERASE_POLARITY] {
+            let mut image = build_volume(attributes, &[0x10, 0x10, 0x10]);
+            let mut fv = FirmwareVolumeMut::new(&mut image).unwrap();
+            fv.delete_file(name(2)).unwrap();
+            assert_eq!(fv.delete_file(name(2)), Err(EditError::FileNotFound { name: name(2) }));
+
+            let fv = fv.firmware_volume().unwrap();
+            let names: Vec<_> = fv.file_iter().map(|file| file.unwrap().name()).collect();
+            assert_eq!(names, [name(1), name(3)]);
+        }
+    }
+}
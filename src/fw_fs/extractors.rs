@@ -0,0 +1,586 @@
+//! Section Extractors
+//!
+//! Ready-made [`SectionExtractor`] implementations for common GUID-defined encapsulation sections. Each extractor is
+//! enabled by a cargo feature:
+//! - **brotli** - `BrotliSectionExtractor` for sections compressed with Brotli.
+//! - **crc32** - `Crc32SectionExtractor` for CRC32 guided sections.
+//! - **lzma** - `LzmaSectionExtractor` for sections compressed with LZMA.
+//! - **tiano** - `TianoSectionExtractor` for sections compressed with the EFI 1.1 or Tiano algorithms.
+//!
+//! [`CompositeSectionExtractor`] is always available and can be used to combine several extractors.
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+use alloc::boxed::Box;
+use r_efi::efi;
+
+use super::{Section, SectionExtractor};
+
+#[cfg(any(feature = "brotli", feature = "crc32", feature = "lzma", feature = "tiano"))]
+use super::{ffs::guid, SectionMetaData};
+
+/// A [`SectionExtractor`] that delegates to a list of extractors.
+///
+/// Each extractor is invoked in turn, and the first one that returns an error or a non-empty buffer determines the
+/// result. If no extractor supports the section, an empty buffer is returned.
+///
+/// ## Example
+///```
+/// use mu_pi::fw_fs::{extractors::CompositeSectionExtractor, SectionExtractor};
+///
+/// let extractors: [&dyn SectionExtractor; 0] = [];
+/// let extractor = CompositeSectionExtractor::new(&extractors);
+///```
+pub struct CompositeSectionExtractor<'a> {
+    extractors: &'a [&'a dyn SectionExtractor],
+}
+
+impl<'a> CompositeSectionExtractor<'a> {
+    /// Creates a new composite extractor over the given extractors.
+    pub fn new(extractors: &'a [&'a dyn SectionExtractor]) -> Self {
+        Self { extractors }
+    }
+}
+
+impl SectionExtractor for CompositeSectionExtractor<'_> {
+    fn extract(&self, section: &Section) -> Result<Box<[u8]>, efi::Status> {
+        self.extract_with_auth_status(section).map(|(buffer, _)| buffer)
+    }
+
+    fn extract_with_auth_status(&self, section: &Section) -> Result<(Box<[u8]>, u32), efi::Status> {
+        for extractor in self.extractors {
+            let (buffer, authentication_status) = extractor.extract_with_auth_status(section)?;
+            if !buffer.is_empty() {
+                return Ok((buffer, authentication_status));
+            }
+        }
+        Ok((Box::new([0u8; 0]), 0))
+    }
+
+    fn extract_with_limit(&self, section: &Section, max_size: usize) -> Result<(Box<[u8]>, u32), efi::Status> {
+        for extractor in self.extractors {
+            let (buffer, authentication_status) = extractor.extract_with_limit(section, max_size)?;
+            if !buffer.is_empty() {
+                return Ok((buffer, authentication_status));
+            }
+        }
+        Ok((Box::new([0u8; 0]), 0))
+    }
+}
+
+// Returns the attributes, GUID-specific header and data of the section if it is defined by the given GUID.
+#[cfg(any(feature = "brotli", feature = "crc32", feature = "lzma", feature = "tiano"))]
+fn guid_defined_data<'a>(section: &'a Section, definition_guid: &efi::Guid) -> Option<(u16, &'a [u8], &'a [u8])> {
+    match section.meta_data() {
+        SectionMetaData::GuidDefined(header, guid_specific) if header.section_definition_guid == *definition_guid => {
+            Some((header.attributes, guid_specific, section.raw_section_data()))
+        }
+        _ => None,
+    }
+}
+
+#[cfg(feature = "brotli")]
+mod brotli {
+    use alloc::{boxed::Box, vec, vec::Vec};
+    use core::ops::{Index, IndexMut};
+
+    use brotli_decompressor::{
+        Allocator, BrotliDecompressStream, BrotliResult, BrotliState, HuffmanCode, SliceWrapper, SliceWrapperMut,
+    };
+    use r_efi::efi;
+
+    use super::{guid, guid_defined_data, Section, SectionExtractor};
+    use crate::fw_fs::ExtractionLimits;
+
+    // Size of the header that precedes the compressed stream: the decompressed size and the scratch buffer size.
+    const BROTLI_HEADER_SIZE: usize = 16;
+
+    // Rebox and HeapAllocator satisfy the BrotliDecompress custom allocation requirement.
+    struct Rebox<T>(Box<[T]>);
+
+    impl<T> Default for Rebox<T> {
+        fn default() -> Self {
+            Rebox(Vec::new().into_boxed_slice())
+        }
+    }
+
+    impl<T> SliceWrapper<T> for Rebox<T> {
+        fn slice(&self) -> &[T] {
+            &self.0
+        }
+    }
+
+    impl<T> SliceWrapperMut<T> for Rebox<T> {
+        fn slice_mut(&mut self) -> &mut [T] {
+            &mut self.0
+        }
+    }
+
+    impl<T> Index<usize> for Rebox<T> {
+        type Output = T;
+        fn index(&self, index: usize) -> &T {
+            &self.0[index]
+        }
+    }
+
+    impl<T> IndexMut<usize> for Rebox<T> {
+        fn index_mut(&mut self, index: usize) -> &mut T {
+            &mut self.0[index]
+        }
+    }
+
+    struct HeapAllocator<T: Clone> {
+        default_value: T,
+    }
+
+    impl<T: Clone> Allocator<T> for HeapAllocator<T> {
+        type AllocatedMemory = Rebox<T>;
+        fn alloc_cell(&mut self, len: usize) -> Rebox<T> {
+            Rebox(vec![self.default_value.clone(); len].into_boxed_slice())
+        }
+        fn free_cell(&mut self, _data: Rebox<T>) {}
+    }
+
+    /// Extracts GUID-defined sections compressed with Brotli.
+    ///
+    /// The section data starts with the 64-bit decompressed size and the 64-bit scratch buffer size, followed by the
+    /// Brotli stream. Sections defined by any other GUID are not extracted. Sections that declare a decompressed size
+    /// larger than the limit are rejected before any buffer is allocated; [`SectionExtractor::extract`] uses the
+    /// default [`ExtractionLimits::max_extracted_size`].
+    #[derive(Debug, Default, Clone, Copy)]
+    pub struct BrotliSectionExtractor {}
+
+    impl SectionExtractor for BrotliSectionExtractor {
+        fn extract(&self, section: &Section) -> Result<Box<[u8]>, efi::Status> {
+            self.extract_with_limit(section, ExtractionLimits::default().max_extracted_size).map(|(buffer, _)| buffer)
+        }
+
+        fn extract_with_limit(&self, section: &Section, max_size: usize) -> Result<(Box<[u8]>, u32), efi::Status> {
+            let Some((_, _, data)) = guid_defined_data(section, &guid::BROTLI_CUSTOM_DECOMPRESS_GUID) else {
+                return Ok((Box::new([0u8; 0]), 0));
+            };
+            if data.len() < BROTLI_HEADER_SIZE {
+                Err(efi::Status::VOLUME_CORRUPTED)?;
+            }
+
+            let out_size = u64::from_le_bytes(data[0..8].try_into().unwrap());
+            let out_size = usize::try_from(out_size).map_err(|_| efi::Status::VOLUME_CORRUPTED)?;
+            if out_size > max_size {
+                Err(efi::Status::OUT_OF_RESOURCES)?;
+            }
+            let in_data = &data[BROTLI_HEADER_SIZE..];
+
+            let mut brotli_state = BrotliState::new(
+                HeapAllocator::<u8> { default_value: 0 },
+                HeapAllocator::<u32> { default_value: 0 },
+                HeapAllocator::<HuffmanCode> { default_value: Default::default() },
+            );
+            let mut out_data: Vec<u8> = vec![0u8; out_size];
+            let mut out_data_size = 0;
+            let result = BrotliDecompressStream(
+                &mut in_data.len(),
+                &mut 0,
+                in_data,
+                &mut out_data.len(),
+                &mut 0,
+                out_data.as_mut_slice(),
+                &mut out_data_size,
+                &mut brotli_state,
+            );
+
+            match result {
+                BrotliResult::ResultSuccess if out_data_size == out_size => Ok((out_data.into_boxed_slice(), 0)),
+                _ => Err(efi::Status::VOLUME_CORRUPTED),
+            }
+        }
+    }
+}
+
+#[cfg(feature = "brotli")]
+pub use brotli::BrotliSectionExtractor;
+
+#[cfg(feature = "crc32")]
+mod crc32 {
+    use alloc::boxed::Box;
+    use r_efi::efi;
+
+    use super::{guid, guid_defined_data, Section, SectionExtractor};
+    use crate::{
+        crc32::crc32,
+        fw_fs::{
+            rebuild::{EncodedSection, SectionCompressor},
+            FfsAuthStatus, FfsGuidedSectionAttribute, FfsSectionHeader,
+        },
+    };
+
+    /// Extracts CRC32 guided sections.
+    ///
+    /// The section data is always returned. If the section has the
+    /// [`AUTH_STATUS_VALID`](FfsGuidedSectionAttribute::AUTH_STATUS_VALID) attribute, the CRC32 in the GUID-specific
+    /// header is verified against the section data and a mismatch is reported with a
+    /// [`TEST_FAILED`](FfsAuthStatus::TEST_FAILED) authentication status, as the EDK2 CRC32 guided section extraction
+    /// libraries do. Sections defined by any other GUID are not extracted.
+    ///
+    /// # Documentation
+    /// UEFI Platform Initialization Specification, Release 1.8, Volume 3, Section 2.1.2.1
+    #[derive(Debug, Default, Clone, Copy)]
+    pub struct Crc32SectionExtractor {}
+
+    impl SectionExtractor for Crc32SectionExtractor {
+        fn extract(&self, section: &Section) -> Result<Box<[u8]>, efi::Status> {
+            self.extract_with_auth_status(section).map(|(buffer, _)| buffer)
+        }
+
+        fn extract_with_auth_status(&self, section: &Section) -> Result<(Box<[u8]>, u32), efi::Status> {
+            let Some((attributes, guid_specific, data)) =
+                guid_defined_data(section, &guid::EFI_CRC32_GUIDED_SECTION_EXTRACTION_GUID)
+            else {
+                return Ok((Box::new([0u8; 0]), 0));
+            };
+            let Some(crc) = guid_specific.get(0..4) else {
+                return Err(efi::Status::VOLUME_CORRUPTED);
+            };
+            let mut authentication_status = 0;
+            if attributes & FfsGuidedSectionAttribute::AUTH_STATUS_VALID != 0
+                && u32::from_le_bytes(crc.try_into().unwrap()) != crc32(data)
+            {
+                authentication_status |= FfsAuthStatus::TEST_FAILED;
+            }
+            Ok((Box::from(data), authentication_status))
+        }
+    }
+
+    impl SectionCompressor for Crc32SectionExtractor {
+        fn compress(
+            &self,
+            header: &FfsSectionHeader::GuidDefined,
+            content: &[u8],
+        ) -> Result<EncodedSection, efi::Status> {
+            if header.section_definition_guid != guid::EFI_CRC32_GUIDED_SECTION_EXTRACTION_GUID {
+                Err(efi::Status::UNSUPPORTED)?;
+            }
+            Ok(EncodedSection { guid_specific: Box::from(crc32(content).to_le_bytes()), data: Box::from(content) })
+        }
+    }
+}
+
+#[cfg(feature = "crc32")]
+pub use crc32::Crc32SectionExtractor;
+
+#[cfg(feature = "lzma")]
+mod lzma {
+    use alloc::{boxed::Box, vec};
+    use lzma_rs::{io::Cursor, lzma_decompress};
+    use r_efi::efi;
+
+    use super::{guid, guid_defined_data, Section, SectionExtractor};
+    use crate::fw_fs::ExtractionLimits;
+
+    // Size of the header that precedes the compressed stream: the 5 bytes of LZMA properties and the decompressed size.
+    const LZMA_HEADER_SIZE: usize = 13;
+
+    /// Extracts GUID-defined sections compressed with LZMA.
+    ///
+    /// The section data is an LZMA stream whose header holds the 64-bit decompressed size, as produced by the EDK2
+    /// LzmaCompress tool. Sections defined by any other GUID are not extracted. Sections that declare a decompressed
+    /// size larger than the limit are rejected before any buffer is allocated; [`SectionExtractor::extract`] uses the
+    /// default [`ExtractionLimits::max_extracted_size`].
+    #[derive(Debug, Default, Clone, Copy)]
+    pub struct LzmaSectionExtractor {}
+
+    impl SectionExtractor for LzmaSectionExtractor {
+        fn extract(&self, section: &Section) -> Result<Box<[u8]>, efi::Status> {
+            self.extract_with_limit(section, ExtractionLimits::default().max_extracted_size).map(|(buffer, _)| buffer)
+        }
+
+        fn extract_with_limit(&self, section: &Section, max_size: usize) -> Result<(Box<[u8]>, u32), efi::Status> {
+            let Some((_, _, data)) = guid_defined_data(section, &guid::LZMA_CUSTOM_DECOMPRESS_GUID) else {
+                return Ok((Box::new([0u8; 0]), 0));
+            };
+            if data.len() < LZMA_HEADER_SIZE {
+                Err(efi::Status::VOLUME_CORRUPTED)?;
+            }
+
+            let out_size = u64::from_le_bytes(data[5..LZMA_HEADER_SIZE].try_into().unwrap());
+            let out_size = usize::try_from(out_size).map_err(|_| efi::Status::VOLUME_CORRUPTED)?;
+            if out_size > max_size {
+                Err(efi::Status::OUT_OF_RESOURCES)?;
+            }
+
+            let mut out_data = vec![0u8; out_size];
+            let mut output = Cursor::new(&mut out_data[..]);
+            lzma_decompress(&mut Cursor::new(data), &mut output).map_err(|_| efi::Status::VOLUME_CORRUPTED)?;
+            if output.position() != out_size as u64 {
+                Err(efi::Status::VOLUME_CORRUPTED)?;
+            }
+            Ok((out_data.into_boxed_slice(), 0))
+        }
+    }
+}
+
+#[cfg(feature = "lzma")]
+pub use lzma::LzmaSectionExtractor;
+
+#[cfg(feature = "tiano")]
+mod tiano {
+    use alloc::{boxed::Box, vec};
+    use r_efi::efi;
+    use uefi_decompress::{decompress_into_with_algo, DecompressionAlgorithm};
+
+    use super::{guid, guid_defined_data, Section, SectionExtractor, SectionMetaData};
+    use crate::fw_fs::{ExtractionLimits, FfsSectionHeader};
+
+    // Size of the header that precedes the compressed stream: the compressed size and the decompressed size.
+    const TIANO_HEADER_SIZE: usize = 8;
+
+    /// Extracts sections compressed with the EFI 1.1 and Tiano compression algorithms.
+    ///
+    /// Compression sections of the [`STANDARD_COMPRESSION`](FfsSectionHeader::STANDARD_COMPRESSION) type are
+    /// decompressed with the EFI 1.1 (UEFI) algorithm, and GUID-defined sections defined by
+    /// [`TIANO_CUSTOM_DECOMPRESS_GUID`](guid::TIANO_CUSTOM_DECOMPRESS_GUID) with the Tiano algorithm. Other sections are
+    /// not extracted. Sections that declare a decompressed size larger than the limit are rejected before any buffer is
+    /// allocated; [`SectionExtractor::extract`] uses the default [`ExtractionLimits::max_extracted_size`].
+    #[derive(Debug, Default, Clone, Copy)]
+    pub struct TianoSectionExtractor {}
+
+    impl SectionExtractor for TianoSectionExtractor {
+        fn extract(&self, section: &Section) -> Result<Box<[u8]>, efi::Status> {
+            self.extract_with_limit(section, ExtractionLimits::default().max_extracted_size).map(|(buffer, _)| buffer)
+        }
+
+        fn extract_with_limit(&self, section: &Section, max_size: usize) -> Result<(Box<[u8]>, u32), efi::Status> {
+            let (data, algorithm) = match section.meta_data() {
+                SectionMetaData::Compression(header)
+                    if header.compression_type == FfsSectionHeader::STANDARD_COMPRESSION =>
+                {
+                    (section.raw_section_data(), DecompressionAlgorithm::UefiDecompress)
+                }
+                _ => match guid_defined_data(section, &guid::TIANO_CUSTOM_DECOMPRESS_GUID) {
+                    Some((_, _, data)) => (data, DecompressionAlgorithm::TianoDecompress),
+                    None => return Ok((Box::new([0u8; 0]), 0)),
+                },
+            };
+            if data.len() < TIANO_HEADER_SIZE {
+                Err(efi::Status::VOLUME_CORRUPTED)?;
+            }
+
+            let out_size = u32::from_le_bytes(data[4..TIANO_HEADER_SIZE].try_into().unwrap()) as usize;
+            if out_size > max_size {
+                Err(efi::Status::OUT_OF_RESOURCES)?;
+            }
+
+            let mut out_data = vec![0u8; out_size];
+            decompress_into_with_algo(data, &mut out_data, algorithm).map_err(|_| efi::Status::VOLUME_CORRUPTED)?;
+            Ok((out_data.into_boxed_slice(), 0))
+        }
+    }
+}
+
+#[cfg(feature = "tiano")]
+pub use tiano::TianoSectionExtractor;
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "brotli")]
+    #[test]
+    fn brotli_extractor_should_expose_compressed_volume() {
+        use super::BrotliSectionExtractor;
+        use crate::fw_fs::{FfsSectionType, FirmwareVolume};
+        use std::{env, fs, path::Path};
+
+        let root = Path::new(&env::var("CARGO_MANIFEST_DIR").unwrap()).join("test_resources");
+        let fv_bytes = fs::read(root.join("FVMAIN_COMPACT.Fv")).unwrap();
+        let fv = FirmwareVolume::new(&fv_bytes).unwrap();
+
+        let mut volume_sections = 0;
+        for file in fv.file_iter() {
+            let file = file.unwrap();
+            for section in file.section_iter_with_extractor(&BrotliSectionExtractor {}) {
+                if section.unwrap().section_type() == Some(FfsSectionType::FirmwareVolumeImage) {
+                    volume_sections += 1;
+                }
+            }
+        }
+        assert!(volume_sections > 0);
+
+        let mut nested_files = 0;
+        fv.all_files_recursive(&BrotliSectionExtractor {}, &mut |_, depth| {
+            if depth > 0 {
+                nested_files += 1;
+            }
+            Ok(())
+        })
+        .unwrap();
+        assert!(nested_files > 0);
+    }
+
+    #[cfg(feature = "brotli")]
+    #[test]
+    fn brotli_extractor_should_reject_sizes_above_the_limit() {
+        use super::BrotliSectionExtractor;
+        use crate::fw_fs::{
+            ffs::guid, rebuild::build_section, ExtractionLimits, FfsFileRawType, FfsGuidedSectionAttribute,
+            FfsSectionRawType, File, Section, SectionExtractor,
+        };
+        use r_efi::efi;
+
+        let mut header_fields = guid::BROTLI_CUSTOM_DECOMPRESS_GUID.as_bytes().to_vec();
+        header_fields.extend_from_slice(&24u16.to_le_bytes());
+        header_fields.extend_from_slice(&FfsGuidedSectionAttribute::PROCESSING_REQUIRED.to_le_bytes());
+        let mut data = (u64::MAX >> 1).to_le_bytes().to_vec();
+        data.extend_from_slice(&[0u8; 8]);
+        let section_bytes = build_section(FfsSectionRawType::encapsulated::GUID_DEFINED, &header_fields, &data);
+
+        let section = Section::new(&section_bytes).unwrap();
+        assert_eq!(BrotliSectionExtractor {}.extract(&section), Err(efi::Status::OUT_OF_RESOURCES));
+        assert_eq!(
+            BrotliSectionExtractor {}.extract_with_limit(&section, 16).unwrap_err(),
+            efi::Status::OUT_OF_RESOURCES
+        );
+
+        let file_bytes =
+            crate::fw_fs::unit_tests::build_ffs2_file([0x5c; 16], FfsFileRawType::FREEFORM, &section_bytes);
+        let file = File::new(&file_bytes).unwrap();
+        let limits = ExtractionLimits { max_extracted_size: 64, ..Default::default() };
+        let results = file.section_iter_with_limits(&BrotliSectionExtractor {}, limits).collect::<Vec<_>>();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[1].as_ref().unwrap_err(), &efi::Status::OUT_OF_RESOURCES);
+    }
+
+    #[cfg(feature = "crc32")]
+    #[test]
+    fn crc32_extractor_should_verify_section() {
+        use super::{CompositeSectionExtractor, Crc32SectionExtractor};
+        use crate::crc32::crc32;
+        use crate::fw_fs::{
+            ffs::guid, rebuild::rebuild_guid_defined_section, FfsAuthStatus, FfsGuidedSectionAttribute, Section,
+            SectionExtractor,
+        };
+
+        assert_eq!(crc32(b"123456789"), 0xcbf43926);
+
+        let payload = b"guided section payload";
+        // Common header (4) + GUID-defined header (20) + CRC32 (4).
+        let data_offset = 28usize;
+        let size = data_offset + payload.len();
+        let mut buffer = Vec::new();
+        buffer.extend_from_slice(&(size as u32).to_le_bytes()[..3]);
+        buffer.push(0x02); // EFI_SECTION_GUID_DEFINED
+        buffer.extend_from_slice(guid::EFI_CRC32_GUIDED_SECTION_EXTRACTION_GUID.as_bytes());
+        buffer.extend_from_slice(&(data_offset as u16).to_le_bytes());
+        buffer.extend_from_slice(&FfsGuidedSectionAttribute::AUTH_STATUS_VALID.to_le_bytes());
+        buffer.extend_from_slice(&crc32(payload).to_le_bytes());
+        buffer.extend_from_slice(payload);
+
+        let section = Section::new(&buffer).unwrap();
+        let extractors: [&dyn SectionExtractor; 1] = [&Crc32SectionExtractor {}];
+        let (extracted, status) =
+            CompositeSectionExtractor::new(&extractors).extract_with_auth_status(&section).unwrap();
+        assert_eq!(&extracted[..], payload);
+        assert_eq!(status, 0);
+
+        // Rebuilding the section with the same content produces the same section.
+        let rebuilt =
+            rebuild_guid_defined_section(&section, &Crc32SectionExtractor {}, &Crc32SectionExtractor {}, &mut |_| {
+                Ok(())
+            });
+        assert_eq!(rebuilt.unwrap(), buffer);
+
+        // a CRC32 mismatch is reported through the authentication status, along with the data.
+        let last = buffer.len() - 1;
+        buffer[last] ^= 0xff;
+        let section = Section::new(&buffer).unwrap();
+        let (extracted, status) = Crc32SectionExtractor {}.extract_with_auth_status(&section).unwrap();
+        assert_eq!(&extracted[..], &buffer[data_offset..]);
+        assert_eq!(status, FfsAuthStatus::TEST_FAILED);
+
+        // the CRC32 is only checked for sections with the AUTH_STATUS_VALID attribute.
+        buffer[22..24].copy_from_slice(&0u16.to_le_bytes());
+        let section = Section::new(&buffer).unwrap();
+        let (extracted, status) = Crc32SectionExtractor {}.extract_with_auth_status(&section).unwrap();
+        assert_eq!(&extracted[..], &buffer[data_offset..]);
+        assert_eq!(status, 0);
+    }
+
+    #[cfg(feature = "lzma")]
+    #[test]
+    fn lzma_extractor_should_decompress_section() {
+        use super::LzmaSectionExtractor;
+        use crate::fw_fs::{
+            ffs::guid, rebuild::build_section, FfsGuidedSectionAttribute, FfsSectionRawType, Section, SectionExtractor,
+        };
+        use lzma_rs::{
+            compress::{Options, UnpackedSize},
+            io::Cursor,
+            lzma_compress_with_options,
+        };
+        use r_efi::efi;
+
+        let payload = b"lzma compressed section payload, lzma compressed section payload".repeat(16);
+        let options = Options { unpacked_size: UnpackedSize::WriteToHeader(Some(payload.len() as u64)) };
+        let mut data = Vec::new();
+        lzma_compress_with_options(&mut Cursor::new(&payload[..]), &mut data, &options).unwrap();
+
+        let mut header_fields = guid::LZMA_CUSTOM_DECOMPRESS_GUID.as_bytes().to_vec();
+        header_fields.extend_from_slice(&24u16.to_le_bytes());
+        header_fields.extend_from_slice(&FfsGuidedSectionAttribute::PROCESSING_REQUIRED.to_le_bytes());
+        let section_bytes = build_section(FfsSectionRawType::encapsulated::GUID_DEFINED, &header_fields, &data);
+        let section = Section::new(&section_bytes).unwrap();
+
+        assert_eq!(&LzmaSectionExtractor {}.extract(&section).unwrap()[..], &payload[..]);
+        assert_eq!(
+            LzmaSectionExtractor {}.extract_with_limit(&section, payload.len() - 1).unwrap_err(),
+            efi::Status::OUT_OF_RESOURCES
+        );
+
+        let truncated = build_section(FfsSectionRawType::encapsulated::GUID_DEFINED, &header_fields, &data[..20]);
+        let section = Section::new(&truncated).unwrap();
+        assert_eq!(LzmaSectionExtractor {}.extract(&section), Err(efi::Status::VOLUME_CORRUPTED));
+    }
+
+    #[cfg(feature = "tiano")]
+    #[test]
+    fn tiano_extractor_should_decompress_sections() {
+        use super::TianoSectionExtractor;
+        use crate::fw_fs::{
+            ffs::guid, rebuild::build_section, FfsGuidedSectionAttribute, FfsSectionHeader, FfsSectionRawType, Section,
+            SectionExtractor,
+        };
+        use r_efi::efi;
+        use std::{env, fs, path::Path};
+
+        let root = Path::new(&env::var("CARGO_MANIFEST_DIR").unwrap()).join("test_resources");
+        let compressed = fs::read(root.join("tiano_compressed.bin")).unwrap();
+        let uncompressed = fs::read(root.join("tiano_uncompressed.bin")).unwrap();
+
+        let mut header_fields = guid::TIANO_CUSTOM_DECOMPRESS_GUID.as_bytes().to_vec();
+        header_fields.extend_from_slice(&24u16.to_le_bytes());
+        header_fields.extend_from_slice(&FfsGuidedSectionAttribute::PROCESSING_REQUIRED.to_le_bytes());
+        let section_bytes = build_section(FfsSectionRawType::encapsulated::GUID_DEFINED, &header_fields, &compressed);
+        let section = Section::new(&section_bytes).unwrap();
+
+        assert_eq!(TianoSectionExtractor {}.extract(&section).unwrap(), uncompressed.into_boxed_slice());
+        assert_eq!(
+            TianoSectionExtractor {}.extract_with_limit(&section, 16).unwrap_err(),
+            efi::Status::OUT_OF_RESOURCES
+        );
+
+        // compression sections declaring the EFI 1.1 algorithm are decompressed as well.
+        let mut header_fields = (0x10000u32).to_le_bytes().to_vec();
+        header_fields.push(FfsSectionHeader::STANDARD_COMPRESSION);
+        let mut data = 0u32.to_le_bytes().to_vec();
+        data.extend_from_slice(&0x10000u32.to_le_bytes());
+        let section_bytes = build_section(FfsSectionRawType::encapsulated::COMPRESSION, &header_fields, &data);
+        let section = Section::new(&section_bytes).unwrap();
+        assert_eq!(
+            TianoSectionExtractor {}.extract_with_limit(&section, 16).unwrap_err(),
+            efi::Status::OUT_OF_RESOURCES
+        );
+        assert_eq!(TianoSectionExtractor {}.extract(&section), Err(efi::Status::VOLUME_CORRUPTED));
+    }
+}
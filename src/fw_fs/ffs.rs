@@ -13,3 +13,46 @@ pub mod attributes;
 pub mod file;
 pub mod guid;
 pub mod section;
+
+use r_efi::efi;
+
+/// The version of the Firmware File System used by a firmware volume, as identified by its file system GUID.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FileSystem {
+    /// The original (pre-PI) Firmware File System (EFI_FIRMWARE_FILE_SYSTEM_GUID). Files may carry a 16-bit tail and
+    /// use a fixed checksum of 0x5A.
+    Ffs1,
+    /// EFI_FIRMWARE_FILE_SYSTEM2_GUID.
+    Ffs2,
+    /// EFI_FIRMWARE_FILE_SYSTEM3_GUID. Adds support for files larger than 16MB.
+    Ffs3,
+}
+
+impl FileSystem {
+    /// Returns the file system for the given file system GUID, if it is a supported FFS version.
+    pub fn from_guid(guid: &efi::Guid) -> Option<Self> {
+        match *guid {
+            guid::EFI_FIRMWARE_FILE_SYSTEM_GUID => Some(FileSystem::Ffs1),
+            guid::EFI_FIRMWARE_FILE_SYSTEM2_GUID => Some(FileSystem::Ffs2),
+            guid::EFI_FIRMWARE_FILE_SYSTEM3_GUID => Some(FileSystem::Ffs3),
+            _ => None,
+        }
+    }
+
+    /// Returns the file system GUID for this FFS version.
+    pub fn guid(&self) -> efi::Guid {
+        match self {
+            FileSystem::Ffs1 => guid::EFI_FIRMWARE_FILE_SYSTEM_GUID,
+            FileSystem::Ffs2 => guid::EFI_FIRMWARE_FILE_SYSTEM2_GUID,
+            FileSystem::Ffs3 => guid::EFI_FIRMWARE_FILE_SYSTEM3_GUID,
+        }
+    }
+
+    /// Returns the value integrity_check_file must hold when the CHECKSUM attribute is clear.
+    pub const fn fixed_file_checksum(&self) -> u8 {
+        match self {
+            FileSystem::Ffs1 => 0x5A,
+            FileSystem::Ffs2 | FileSystem::Ffs3 => 0xAA,
+        }
+    }
+}
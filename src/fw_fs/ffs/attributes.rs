@@ -16,6 +16,58 @@ pub mod raw {
     pub const FIXED: u8 = 0x04;
     pub const DATA_ALIGNMENT: u8 = 0x38;
     pub const CHECKSUM: u8 = 0x40;
+
+    /// Attributes of the original (pre-PI) Firmware File System, which differ from FFS2/FFS3 in the low bits.
+    pub mod ffs1 {
+        pub const TAIL_PRESENT: u8 = 0x01;
+        pub const RECOVERY: u8 = 0x02;
+        pub const HEADER_EXTENSION: u8 = 0x04;
+    }
+}
+
+// The log2 of the file data alignments encoded by the DATA_ALIGNMENT field, per Table 3.3 in PI spec 1.8 Part III,
+// without and with DATA_ALIGNMENT_2.
+const DATA_ALIGNMENT_EXPONENTS: [[u32; 8]; 2] = [[0, 4, 7, 9, 10, 12, 15, 16], [17, 18, 19, 20, 21, 22, 23, 24]];
+
+attribute_flags! {
+    /// Typed EFI_FFS_FILE_ATTRIBUTES of an FFS2 or FFS3 file. The raw `u8` value is kept for FFI.
+    ///
+    /// FFS1 files use the low bits for other attributes (see [`raw::ffs1`]).
+    pub struct Attributes(u8) {
+        const LARGE_FILE = raw::LARGE_FILE;
+        const DATA_ALIGNMENT_2 = raw::DATA_ALIGNMENT_2;
+        const FIXED = raw::FIXED;
+        const CHECKSUM = raw::CHECKSUM;
+    }
+}
+
+impl Attributes {
+    /// Returns the log2 of the alignment required for the file data, decoded from the DATA_ALIGNMENT and
+    /// DATA_ALIGNMENT_2 attributes.
+    pub const fn alignment_exponent(&self) -> u32 {
+        let data_alignment = ((self.0 & raw::DATA_ALIGNMENT) >> 3) as usize;
+        DATA_ALIGNMENT_EXPONENTS[self.contains(Self::DATA_ALIGNMENT_2) as usize][data_alignment]
+    }
+
+    /// Returns the alignment in bytes required for the file data.
+    pub const fn alignment(&self) -> usize {
+        1 << self.alignment_exponent()
+    }
+
+    /// Returns these attributes with the DATA_ALIGNMENT and DATA_ALIGNMENT_2 fields set to the smallest alignment of at
+    /// least `alignment` bytes they can encode, or `None` if `alignment` is larger than 16MB.
+    pub const fn with_alignment(&self, alignment: usize) -> Option<Self> {
+        let mut index = 0;
+        while index < 16 {
+            if 1usize << DATA_ALIGNMENT_EXPONENTS[index / 8][index % 8] >= alignment {
+                let data_alignment_2 = if index < 8 { 0 } else { raw::DATA_ALIGNMENT_2 };
+                let cleared = self.0 & !(raw::DATA_ALIGNMENT | raw::DATA_ALIGNMENT_2);
+                return Some(Self(cleared | data_alignment_2 | ((index % 8) as u8) << 3));
+            }
+            index += 1;
+        }
+        None
+    }
 }
 
 #[repr(u8)]
@@ -90,6 +90,26 @@ pub enum State {
     HeaderInvalid = raw::state::HEADER_INVALID,
 }
 
+impl State {
+    /// Decodes a raw EFI_FFS_FILE_STATE value written to a firmware volume with the given EFI_FVB_ERASE_POLARITY.
+    ///
+    /// State bits are set (or cleared, for an erase polarity of 1) one at a time as a file progresses through its
+    /// lifecycle, so the state of the file is given by the highest-order state bit that has been written, matching
+    /// EDK2. Returns `None` if no state bit has been written (e.g. erased flash).
+    pub fn from_raw(state: u8, erase_polarity: bool) -> Option<Self> {
+        let state = if erase_polarity { !state } else { state };
+        match state & 0x3F {
+            0 => None,
+            x if x >= raw::state::HEADER_INVALID => Some(Self::HeaderInvalid),
+            x if x >= raw::state::DELETED => Some(Self::Deleted),
+            x if x >= raw::state::MARKED_FOR_UPDATE => Some(Self::MarkedForUpdate),
+            x if x >= raw::state::DATA_VALID => Some(Self::DataValid),
+            x if x >= raw::state::HEADER_VALID => Some(Self::HeaderValid),
+            _ => Some(Self::HeaderConstruction),
+        }
+    }
+}
+
 // EFI_FFS_FILE_HEADER
 #[repr(C)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
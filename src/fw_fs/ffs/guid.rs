@@ -11,6 +11,10 @@
 
 use r_efi::efi;
 
+// {7A9354D9-0468-444A-81CE-0BF617D890DF}
+pub const EFI_FIRMWARE_FILE_SYSTEM_GUID: efi::Guid =
+    efi::Guid::from_fields(0x7a9354d9, 0x0468, 0x444a, 0x81, 0xce, &[0x0b, 0xf6, 0x17, 0xd8, 0x90, 0xdf]);
+
 // {8C8CE578-8A3D-4F1C-9935-896185C32DD3}
 pub const EFI_FIRMWARE_FILE_SYSTEM2_GUID: efi::Guid =
     efi::Guid::from_fields(0x8c8ce578, 0x8a3d, 0x4f1c, 0x99, 0x35, &[0x89, 0x61, 0x85, 0xc3, 0x2d, 0xd3]);
@@ -19,6 +23,30 @@ pub const EFI_FIRMWARE_FILE_SYSTEM2_GUID: efi::Guid =
 pub const EFI_FIRMWARE_FILE_SYSTEM3_GUID: efi::Guid =
     efi::Guid::from_fields(0x5473c07a, 0x3dcb, 0x4dca, 0xbd, 0x6f, &[0x1e, 0x96, 0x89, 0xe7, 0x34, 0x9a]);
 
+// {1B45CC0A-156A-428A-AF62-49864DA0E6E6}
+pub const PEI_APRIORI_FILE_NAME_GUID: efi::Guid =
+    efi::Guid::from_fields(0x1b45cc0a, 0x156a, 0x428a, 0xaf, 0x62, &[0x49, 0x86, 0x4d, 0xa0, 0xe6, 0xe6]);
+
+// {FC510EE7-FFDC-11D4-BD41-0080C73C8881}
+pub const EFI_APRIORI_GUID: efi::Guid =
+    efi::Guid::from_fields(0xfc510ee7, 0xffdc, 0x11d4, 0xbd, 0x41, &[0x00, 0x80, 0xc7, 0x3c, 0x88, 0x81]);
+
 // {1BA0062E-C779-4582-8566-336AE8F78F09}
 pub const EFI_FFS_VOLUME_TOP_FILE_GUID: efi::Guid =
     efi::Guid::from_fields(0x1ba0062e, 0xc779, 0x4582, 0x85, 0x66, &[0x33, 0x6a, 0xe8, 0xf7, 0x8f, 0x9]);
+
+// {3D532050-5CDA-4FD0-879E-0F7F630D5AFB}
+pub const BROTLI_CUSTOM_DECOMPRESS_GUID: efi::Guid =
+    efi::Guid::from_fields(0x3d532050, 0x5cda, 0x4fd0, 0x87, 0x9e, &[0x0f, 0x7f, 0x63, 0x0d, 0x5a, 0xfb]);
+
+// {EE4E5898-3914-4259-9D6E-DC7BD79403CF}
+pub const LZMA_CUSTOM_DECOMPRESS_GUID: efi::Guid =
+    efi::Guid::from_fields(0xee4e5898, 0x3914, 0x4259, 0x9d, 0x6e, &[0xdc, 0x7b, 0xd7, 0x94, 0x03, 0xcf]);
+
+// {A31280AD-481E-41B6-95E8-127F4C984779}
+pub const TIANO_CUSTOM_DECOMPRESS_GUID: efi::Guid =
+    efi::Guid::from_fields(0xa31280ad, 0x481e, 0x41b6, 0x95, 0xe8, &[0x12, 0x7f, 0x4c, 0x98, 0x47, 0x79]);
+
+// {FC1BCDB0-7D31-49AA-936A-A4600D9DD083}
+pub const EFI_CRC32_GUIDED_SECTION_EXTRACTION_GUID: efi::Guid =
+    efi::Guid::from_fields(0xfc1bcdb0, 0x7d31, 0x49aa, 0x93, 0x6a, &[0xa4, 0x60, 0x0d, 0x9d, 0xd0, 0x83]);
@@ -128,3 +128,95 @@ pub mod header {
         pub sub_type_guid: Guid,
     }
 }
+
+/// Attributes of a GUID-defined section (`EFI_GUIDED_SECTION_*` in EDK II code).
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Volume 3, Section 3.2.5.7
+pub mod guided_attributes {
+    /// The section requires processing to obtain meaningful data from the section contents.
+    pub const PROCESSING_REQUIRED: u16 = 0x01;
+    /// The section contains authentication data that is reported through the authentication status.
+    pub const AUTH_STATUS_VALID: u16 = 0x02;
+}
+
+attribute_flags! {
+    /// Typed attributes of a GUID-defined section. The raw `u16` value is kept in the section header for FFI.
+    pub struct GuidedAttributes(u16) {
+        const PROCESSING_REQUIRED = guided_attributes::PROCESSING_REQUIRED;
+        const AUTH_STATUS_VALID = guided_attributes::AUTH_STATUS_VALID;
+    }
+}
+
+/// Authentication status of a section (`EFI_AUTH_STATUS_*` in EDK II code).
+///
+/// The authentication status of a section nested in GUID-defined sections is the combination of the status reported
+/// by each enclosing section, per the rules described for
+/// `EFI_SECTION_EXTRACTION_PROTOCOL.GetSection()`.
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Volume 3, Section 4.2.1
+pub mod auth_status {
+    use super::guided_attributes;
+
+    /// The platform security policy overrode the authentication of the section.
+    pub const PLATFORM_OVERRIDE: u32 = 0x01;
+    /// The section is signed.
+    pub const IMAGE_SIGNED: u32 = 0x02;
+    /// The signature of the section was not checked.
+    pub const NOT_TESTED: u32 = 0x04;
+    /// The signature of the section was checked and failed.
+    pub const TEST_FAILED: u32 = 0x08;
+    /// Mask of all the authentication status bits.
+    pub const ALL: u32 = 0x0f;
+
+    /// Combines the authentication status of a parent section with the status reported for a child section.
+    ///
+    /// The bits of both are accumulated, so a failure or override anywhere in the nesting is visible on the child.
+    pub const fn combine(parent: u32, child: u32) -> u32 {
+        (parent & ALL) | (child & ALL)
+    }
+
+    /// Returns the authentication status of the data extracted from a GUID-defined section.
+    ///
+    /// `section_attributes` are the attributes from the GUID-defined section header, and `extraction_status` is the
+    /// status reported by the extraction of the section. If the section has
+    /// [`AUTH_STATUS_VALID`](guided_attributes::AUTH_STATUS_VALID) set, the extraction status is combined with the parent
+    /// status. Otherwise, the section contributes no authentication data and the parent status is inherited.
+    pub const fn nested(parent: u32, section_attributes: u16, extraction_status: u32) -> u32 {
+        if section_attributes & guided_attributes::AUTH_STATUS_VALID != 0 {
+            combine(parent, extraction_status)
+        } else {
+            parent & ALL
+        }
+    }
+
+    /// Indicates whether the given authentication status reports a signed section that passed authentication.
+    pub const fn is_authenticated(status: u32) -> bool {
+        status & (IMAGE_SIGNED | NOT_TESTED | TEST_FAILED) == IMAGE_SIGNED
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{auth_status, guided_attributes};
+
+    #[test]
+    fn auth_status_should_follow_nesting_rules() {
+        let signed = auth_status::IMAGE_SIGNED;
+        assert!(auth_status::is_authenticated(signed));
+
+        // A section without AUTH_STATUS_VALID inherits the parent status.
+        assert_eq!(
+            auth_status::nested(signed, guided_attributes::PROCESSING_REQUIRED, auth_status::TEST_FAILED),
+            signed
+        );
+
+        // A failure in a nested section is accumulated with the parent status.
+        let status =
+            auth_status::nested(signed, guided_attributes::AUTH_STATUS_VALID, signed | auth_status::TEST_FAILED);
+        assert_eq!(status, auth_status::IMAGE_SIGNED | auth_status::TEST_FAILED);
+        assert!(!auth_status::is_authenticated(status));
+        assert!(!auth_status::is_authenticated(auth_status::NOT_TESTED));
+    }
+}
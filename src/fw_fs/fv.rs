@@ -49,6 +49,43 @@ pub struct Header {
     pub(crate) block_map: [BlockMapEntry; 0],
 }
 
+/// Offsets of the [`Header`] fields that are read or patched in place in raw firmware volume buffers.
+#[cfg_attr(not(feature = "alloc"), allow(dead_code))]
+pub(crate) mod header_offset {
+    pub(crate) const FILE_SYSTEM_GUID: usize = 16;
+    pub(crate) const FV_LENGTH: usize = 32;
+    pub(crate) const SIGNATURE: usize = 40;
+    pub(crate) const ATTRIBUTES: usize = 44;
+    pub(crate) const HEADER_LENGTH: usize = 48;
+    pub(crate) const CHECKSUM: usize = 50;
+    pub(crate) const EXT_HEADER_OFFSET: usize = 52;
+    pub(crate) const RESERVED: usize = 54;
+    pub(crate) const REVISION: usize = 55;
+    pub(crate) const BLOCK_MAP: usize = 56;
+
+    // The offsets must match the layout of the Header struct.
+    const _: () = {
+        let value = core::mem::MaybeUninit::<super::Header>::uninit();
+        let base = value.as_ptr();
+        macro_rules! offset_of {
+            ($field:ident) => {
+                // SAFETY: no reference to the (uninitialized) field is created.
+                unsafe { core::ptr::addr_of!((*base).$field).cast::<u8>().offset_from(base.cast::<u8>()) as usize }
+            };
+        }
+        assert!(offset_of!(file_system_guid) == FILE_SYSTEM_GUID);
+        assert!(offset_of!(fv_length) == FV_LENGTH);
+        assert!(offset_of!(signature) == SIGNATURE);
+        assert!(offset_of!(attributes) == ATTRIBUTES);
+        assert!(offset_of!(header_length) == HEADER_LENGTH);
+        assert!(offset_of!(checksum) == CHECKSUM);
+        assert!(offset_of!(ext_header_offset) == EXT_HEADER_OFFSET);
+        assert!(offset_of!(reserved) == RESERVED);
+        assert!(offset_of!(revision) == REVISION);
+        assert!(offset_of!(block_map) == BLOCK_MAP);
+    };
+}
+
 #[repr(C)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct BlockMapEntry {
@@ -63,3 +100,19 @@ pub(crate) struct ExtHeader {
     pub(crate) fv_name: efi::Guid,
     pub(crate) ext_header_size: u32,
 }
+
+/// Types of the entries that follow the FV extended header (`EFI_FV_EXT_TYPE_*` in EDK II code).
+pub mod ext_entry_type {
+    pub const OEM_TYPE: u16 = 0x01;
+    pub const GUID_TYPE: u16 = 0x02;
+    pub const USED_SIZE_TYPE: u16 = 0x03;
+}
+
+/// EFI_FIRMWARE_VOLUME_EXT_ENTRY
+#[repr(C)]
+#[cfg_attr(not(feature = "alloc"), allow(dead_code))]
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct ExtEntryHeader {
+    pub(crate) ext_entry_size: u16,
+    pub(crate) ext_entry_type: u16,
+}
@@ -22,6 +22,21 @@ pub mod raw {
     }
 }
 
+attribute_flags! {
+    /// Typed EFI_FV_FILE_ATTRIBUTES of a file. The [`EfiFvFileAttributes`] raw value is kept for FFI.
+    pub struct Attributes(EfiFvFileAttributes) {
+        const FIXED = raw::attribute::FIXED;
+        const MEMORY_MAPPED = raw::attribute::MEMORY_MAPPED;
+    }
+}
+
+impl Attributes {
+    /// Returns the alignment in bytes required for the file, decoded from the ALIGNMENT field.
+    pub const fn alignment(&self) -> usize {
+        1 << (self.0 & raw::attribute::ALIGNMENT)
+    }
+}
+
 #[repr(u32)]
 #[derive(Debug, Copy, Clone, PartialEq)]
 pub enum Attribute {
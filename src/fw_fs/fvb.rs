@@ -3,6 +3,8 @@
 //! Based on the values defined in the UEFI Platform Initialization (PI) Specification V1.8A Section 3.4.2
 //! Firmware Volume Block2 Protocol.
 //!
+//! [`backend::FvbBackend`] abstracts the device operations of the protocol for FVB drivers.
+//!
 //! ## License
 //!
 //! Copyright (C) Microsoft Corporation. All rights reserved.
@@ -11,3 +13,5 @@
 //!
 
 pub mod attributes;
+#[cfg(feature = "alloc")]
+pub mod backend;
@@ -69,6 +69,68 @@ pub mod raw {
     }
 }
 
+// The ALIGNMENT field of the attributes: the log2 of the required alignment of the firmware volume.
+const ALIGNMENT_MASK: u32 = raw::fvb2::ALIGNMENT_2G;
+const ALIGNMENT_SHIFT: u32 = 16;
+
+attribute_flags! {
+    /// Typed EFI_FVB_ATTRIBUTES_2 of a firmware volume. The [`EfiFvbAttributes2`] raw value is kept for FFI.
+    ///
+    /// ## Example
+    /// ```
+    /// use mu_pi::fw_fs::{Fvb2RawAttributes, FvbAttributes2};
+    ///
+    /// let attributes = FvbAttributes2::from(Fvb2RawAttributes::ERASE_POLARITY | Fvb2RawAttributes::ALIGNMENT_4K);
+    /// assert!(attributes.contains(FvbAttributes2::ERASE_POLARITY));
+    /// assert_eq!(attributes.alignment(), 0x1000);
+    /// assert_eq!(attributes.with_alignment(0x10000).unwrap().alignment(), 0x10000);
+    /// assert_eq!(attributes.with_alignment(0x3000), None);
+    /// ```
+    pub struct FvbAttributes2(EfiFvbAttributes2) {
+        const READ_DISABLED_CAP = raw::fvb2::READ_DISABLED_CAP;
+        const READ_ENABLED_CAP = raw::fvb2::READ_ENABLED_CAP;
+        const READ_STATUS = raw::fvb2::READ_STATUS;
+        const WRITE_DISABLED_CAP = raw::fvb2::WRITE_DISABLED_CAP;
+        const WRITE_ENABLED_CAP = raw::fvb2::WRITE_ENABLED_CAP;
+        const WRITE_STATUS = raw::fvb2::WRITE_STATUS;
+        const LOCK_CAP = raw::fvb2::LOCK_CAP;
+        const LOCK_STATUS = raw::fvb2::LOCK_STATUS;
+        const STICKY_WRITE = raw::fvb2::STICKY_WRITE;
+        const MEMORY_MAPPED = raw::fvb2::MEMORY_MAPPED;
+        const ERASE_POLARITY = raw::fvb2::ERASE_POLARITY;
+        const READ_LOCK_CAP = raw::fvb2::READ_LOCK_CAP;
+        const READ_LOCK_STATUS = raw::fvb2::READ_LOCK_STATUS;
+        const WRITE_LOCK_CAP = raw::fvb2::WRITE_LOCK_CAP;
+        const WRITE_LOCK_STATUS = raw::fvb2::WRITE_LOCK_STATUS;
+        const WEAK_ALIGNMENT = raw::fvb2::WEAK_ALIGNMENT;
+    }
+}
+
+impl FvbAttributes2 {
+    /// Returns the alignment in bytes required for the firmware volume, decoded from the ALIGNMENT field.
+    pub const fn alignment(&self) -> usize {
+        1 << ((self.0 & ALIGNMENT_MASK) >> ALIGNMENT_SHIFT)
+    }
+
+    /// Returns these attributes with the ALIGNMENT field set to `alignment`, or `None` if `alignment` is not a power of
+    /// two or is larger than 2GB.
+    pub const fn with_alignment(&self, alignment: usize) -> Option<Self> {
+        if !alignment.is_power_of_two() || alignment > 1 << 31 {
+            return None;
+        }
+        Some(Self((self.0 & !ALIGNMENT_MASK) | (alignment.trailing_zeros() << ALIGNMENT_SHIFT)))
+    }
+
+    /// Returns the value of erased bytes of the firmware volume, which follows the ERASE_POLARITY attribute.
+    pub const fn erase_byte(&self) -> u8 {
+        if self.contains(Self::ERASE_POLARITY) {
+            0xff
+        } else {
+            0
+        }
+    }
+}
+
 #[repr(u32)]
 #[derive(Debug, Copy, Clone, PartialEq)]
 pub enum Fvb2 {
@@ -0,0 +1,317 @@
+//! Firmware Volume Block Backends
+//!
+//! [`FvbBackend`] is the Rust counterpart of the operations of the
+//! [Firmware Volume Block2 Protocol](crate::protocols::firmware_volume_block), so FVB drivers can implement the
+//! protocol over a common abstraction of the device. [`MemoryFvb`] is a reference implementation over a buffer in
+//! memory, e.g. for emulated flash or for testing the consumers of a driver.
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+use alloc::vec::Vec;
+use r_efi::efi;
+
+use super::attributes::FvbAttributes2;
+use crate::fw_fs::{fv::BlockMapEntry, FirmwareVolume};
+
+// The status bits of the attributes, the only ones that SetAttributes() changes.
+const STATUS_ATTRIBUTES: FvbAttributes2 = FvbAttributes2::from_bits_retain(
+    FvbAttributes2::READ_STATUS.bits()
+        | FvbAttributes2::WRITE_STATUS.bits()
+        | FvbAttributes2::LOCK_STATUS.bits()
+        | FvbAttributes2::READ_LOCK_STATUS.bits()
+        | FvbAttributes2::WRITE_LOCK_STATUS.bits(),
+);
+
+/// The device operations of a firmware volume block service, as defined by the Firmware Volume Block2 Protocol.
+///
+/// Blocks are addressed by their logical block address (LBA), the index of the block from the start of the firmware
+/// volume. Reads and writes do not cross block boundaries: like the protocol, they stop at the end of the block and
+/// return the number of bytes transferred, which the protocol reports along with `BAD_BUFFER_SIZE`.
+pub trait FvbBackend {
+    /// Returns the attributes of the firmware volume.
+    fn attributes(&self) -> Result<FvbAttributes2, efi::Status>;
+
+    /// Sets the status attributes of the firmware volume and returns the resulting attributes.
+    ///
+    /// Returns `INVALID_PARAMETER` if `attributes` changes attributes that are not status attributes, or a status that
+    /// the capabilities of the firmware volume do not allow changing.
+    fn set_attributes(&mut self, attributes: FvbAttributes2) -> Result<FvbAttributes2, efi::Status>;
+
+    /// Returns the physical address of a memory-mapped firmware volume.
+    ///
+    /// The default implementation returns `UNSUPPORTED`, as for firmware volumes that are not memory mapped.
+    fn physical_address(&self) -> Result<u64, efi::Status> {
+        Err(efi::Status::UNSUPPORTED)
+    }
+
+    /// Returns the size of the block at `lba` and the number of consecutive blocks of that size starting at `lba`.
+    ///
+    /// Returns `INVALID_PARAMETER` if `lba` is beyond the last block.
+    fn block_size(&self, lba: efi::Lba) -> Result<(usize, usize), efi::Status>;
+
+    /// Reads from the block at `lba`, starting at `offset` in the block, into `buffer`.
+    ///
+    /// Returns the number of bytes read, which is less than the length of `buffer` if the read reached the end of the
+    /// block. Returns `ACCESS_DENIED` if the firmware volume is read-disabled, and `INVALID_PARAMETER` if `lba` or
+    /// `offset` is beyond the end of the firmware volume.
+    fn read(&self, lba: efi::Lba, offset: usize, buffer: &mut [u8]) -> Result<usize, efi::Status>;
+
+    /// Writes `data` to the block at `lba`, starting at `offset` in the block.
+    ///
+    /// Returns the number of bytes written, which is less than the length of `data` if the write reached the end of
+    /// the block. Returns `ACCESS_DENIED` if the firmware volume is write-disabled, and `INVALID_PARAMETER` if `lba`
+    /// or `offset` is beyond the end of the firmware volume.
+    fn write(&mut self, lba: efi::Lba, offset: usize, data: &[u8]) -> Result<usize, efi::Status>;
+
+    /// Erases the blocks of each range, given as a starting LBA and a number of blocks, to the erase byte.
+    ///
+    /// No block is erased if a range is beyond the end of the firmware volume, which returns `INVALID_PARAMETER`.
+    /// Returns `ACCESS_DENIED` if the firmware volume is write-disabled.
+    fn erase_blocks(&mut self, ranges: &[(efi::Lba, u64)]) -> Result<(), efi::Status>;
+}
+
+/// A firmware volume block service over a firmware volume in memory.
+///
+/// The block map and the initial attributes are those of the firmware volume header. Writes are applied as-is: like
+/// the emulated flash of EDK2, they are not restricted to bits that flash could program.
+///
+/// ## Example
+///```
+/// use mu_pi::fw_fs::{
+///   builder::blank_volume, fv::BlockMapEntry, fvb::backend::{FvbBackend, MemoryFvb}, FfsFileSystem, FvbAttributes2,
+/// };
+///
+/// let attributes = FvbAttributes2::ERASE_POLARITY | FvbAttributes2::READ_STATUS | FvbAttributes2::WRITE_STATUS;
+/// let block_map = [BlockMapEntry { num_blocks: 4, length: 0x1000 }];
+/// let mut image = blank_volume(FfsFileSystem::Ffs2, &block_map, attributes).unwrap();
+///
+/// let mut fvb = MemoryFvb::new(&mut image).unwrap();
+/// assert_eq!(fvb.block_size(1).unwrap(), (0x1000, 3));
+/// assert_eq!(fvb.write(1, 0xffe, b"data").unwrap(), 2);
+/// fvb.erase_blocks(&[(1, 1)]).unwrap();
+/// assert_eq!(image[0x1ffe], 0xff);
+///```
+#[derive(Debug)]
+pub struct MemoryFvb<'a> {
+    buffer: &'a mut [u8],
+    block_map: Vec<BlockMapEntry>,
+    attributes: FvbAttributes2,
+    base_address: Option<u64>,
+}
+
+impl<'a> MemoryFvb<'a> {
+    /// Creates a service over the firmware volume in `buffer`, whose header provides the block map and attributes.
+    ///
+    /// Returns the errors of [`FirmwareVolume::new`].
+    pub fn new(buffer: &'a mut [u8]) -> Result<Self, efi::Status> {
+        let (block_map, attributes) = {
+            let fv = FirmwareVolume::new(buffer)?;
+            (fv.block_map().collect(), fv.attributes())
+        };
+        Ok(Self { buffer, block_map, attributes, base_address: None })
+    }
+
+    /// Sets the physical address that is reported for a memory-mapped firmware volume.
+    pub fn with_base_address(mut self, base_address: u64) -> Self {
+        self.base_address = Some(base_address);
+        self
+    }
+
+    // Returns the offset of the block at `lba` in the buffer, along with its size and the number of blocks of that
+    // size that follow it.
+    fn locate(&self, lba: efi::Lba) -> Result<(usize, usize, usize), efi::Status> {
+        let mut first_lba = 0;
+        let mut offset = 0;
+        for entry in &self.block_map {
+            let num_blocks = entry.num_blocks as u64;
+            if lba < first_lba + num_blocks {
+                let index = (lba - first_lba) as usize;
+                let length = entry.length as usize;
+                return Ok((offset + index * length, length, entry.num_blocks as usize - index));
+            }
+            first_lba += num_blocks;
+            offset += entry.num_blocks as usize * entry.length as usize;
+        }
+        Err(efi::Status::INVALID_PARAMETER)
+    }
+
+    // Returns the range of the buffer that a transfer of `length` bytes at `offset` in the block at `lba` covers,
+    // stopping at the end of the block.
+    fn transfer_range(&self, lba: efi::Lba, offset: usize, length: usize) -> Result<(usize, usize), efi::Status> {
+        let (block_offset, block_size, _) = self.locate(lba)?;
+        if offset >= block_size {
+            Err(efi::Status::INVALID_PARAMETER)?;
+        }
+        let start = block_offset + offset;
+        Ok((start, start + length.min(block_size - offset)))
+    }
+}
+
+impl FvbBackend for MemoryFvb<'_> {
+    fn attributes(&self) -> Result<FvbAttributes2, efi::Status> {
+        Ok(self.attributes)
+    }
+
+    fn set_attributes(&mut self, attributes: FvbAttributes2) -> Result<FvbAttributes2, efi::Status> {
+        let current = self.attributes;
+        let changed = FvbAttributes2::from_bits_retain(attributes.bits() ^ current.bits());
+        if !STATUS_ATTRIBUTES.contains(changed) {
+            Err(efi::Status::INVALID_PARAMETER)?;
+        }
+        // each status can only be changed in the directions its capabilities allow, and not at all once locked.
+        let rules = [
+            (FvbAttributes2::READ_STATUS, FvbAttributes2::READ_ENABLED_CAP, FvbAttributes2::READ_DISABLED_CAP),
+            (FvbAttributes2::WRITE_STATUS, FvbAttributes2::WRITE_ENABLED_CAP, FvbAttributes2::WRITE_DISABLED_CAP),
+            (FvbAttributes2::LOCK_STATUS, FvbAttributes2::LOCK_CAP, FvbAttributes2::empty()),
+            (FvbAttributes2::READ_LOCK_STATUS, FvbAttributes2::READ_LOCK_CAP, FvbAttributes2::empty()),
+            (FvbAttributes2::WRITE_LOCK_STATUS, FvbAttributes2::WRITE_LOCK_CAP, FvbAttributes2::empty()),
+        ];
+        for (status, enable_cap, disable_cap) in rules {
+            if !changed.contains(status) {
+                continue;
+            }
+            let cap = if attributes.contains(status) { enable_cap } else { disable_cap };
+            if cap.bits() == 0 || !current.contains(cap) || current.contains(FvbAttributes2::LOCK_STATUS) {
+                Err(efi::Status::INVALID_PARAMETER)?;
+            }
+        }
+        self.attributes = attributes;
+        Ok(attributes)
+    }
+
+    fn physical_address(&self) -> Result<u64, efi::Status> {
+        match self.base_address {
+            Some(base_address) if self.attributes.contains(FvbAttributes2::MEMORY_MAPPED) => Ok(base_address),
+            _ => Err(efi::Status::UNSUPPORTED),
+        }
+    }
+
+    fn block_size(&self, lba: efi::Lba) -> Result<(usize, usize), efi::Status> {
+        let (_, block_size, num_blocks) = self.locate(lba)?;
+        Ok((block_size, num_blocks))
+    }
+
+    fn read(&self, lba: efi::Lba, offset: usize, buffer: &mut [u8]) -> Result<usize, efi::Status> {
+        if !self.attributes.contains(FvbAttributes2::READ_STATUS) {
+            Err(efi::Status::ACCESS_DENIED)?;
+        }
+        let (start, end) = self.transfer_range(lba, offset, buffer.len())?;
+        buffer[..end - start].copy_from_slice(&self.buffer[start..end]);
+        Ok(end - start)
+    }
+
+    fn write(&mut self, lba: efi::Lba, offset: usize, data: &[u8]) -> Result<usize, efi::Status> {
+        if !self.attributes.contains(FvbAttributes2::WRITE_STATUS) {
+            Err(efi::Status::ACCESS_DENIED)?;
+        }
+        let (start, end) = self.transfer_range(lba, offset, data.len())?;
+        self.buffer[start..end].copy_from_slice(&data[..end - start]);
+        Ok(end - start)
+    }
+
+    fn erase_blocks(&mut self, ranges: &[(efi::Lba, u64)]) -> Result<(), efi::Status> {
+        if !self.attributes.contains(FvbAttributes2::WRITE_STATUS) {
+            Err(efi::Status::ACCESS_DENIED)?;
+        }
+        // check every range before erasing any block.
+        let mut regions = Vec::with_capacity(ranges.len());
+        for &(lba, count) in ranges {
+            if count == 0 {
+                Err(efi::Status::INVALID_PARAMETER)?;
+            }
+            let last = lba.checked_add(count - 1).ok_or(efi::Status::INVALID_PARAMETER)?;
+            let (start, _, _) = self.locate(lba)?;
+            let (last_offset, last_size, _) = self.locate(last)?;
+            regions.push(start..last_offset + last_size);
+        }
+        let erase_byte = self.attributes.erase_byte();
+        for region in regions {
+            self.buffer[region].fill(erase_byte);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fw_fs::{builder::blank_volume, FfsFileSystem};
+
+    const READ_WRITE: FvbAttributes2 = FvbAttributes2::from_bits_retain(
+        FvbAttributes2::READ_STATUS.bits()
+            | FvbAttributes2::WRITE_STATUS.bits()
+            | FvbAttributes2::WRITE_ENABLED_CAP.bits()
+            | FvbAttributes2::WRITE_DISABLED_CAP.bits()
+            | FvbAttributes2::LOCK_CAP.bits(),
+    );
+
+    fn volume(attributes: FvbAttributes2) -> Vec<u8> {
+        let block_map =
+            [BlockMapEntry { num_blocks: 2, length: 0x1000 }, BlockMapEntry { num_blocks: 4, length: 0x200 }];
+        blank_volume(FfsFileSystem::Ffs2, &block_map, attributes).unwrap()
+    }
+
+    #[test]
+    fn transfers_should_stop_at_block_boundaries() {
+        let mut image = volume(READ_WRITE);
+        let mut fvb = MemoryFvb::new(&mut image).unwrap();
+        assert_eq!(fvb.block_size(0), Ok((0x1000, 2)));
+        assert_eq!(fvb.block_size(3), Ok((0x200, 3)));
+        assert_eq!(fvb.block_size(6), Err(efi::Status::INVALID_PARAMETER));
+
+        assert_eq!(fvb.write(1, 0xffc, &[0x5a; 8]), Ok(4));
+        assert_eq!(fvb.write(2, 0, &[0xa5; 8]), Ok(8));
+        assert_eq!(fvb.write(2, 0x200, &[0; 8]), Err(efi::Status::INVALID_PARAMETER));
+        let mut buffer = [0u8; 8];
+        assert_eq!(fvb.read(1, 0xffc, &mut buffer), Ok(4));
+        assert_eq!(buffer, [0x5a, 0x5a, 0x5a, 0x5a, 0, 0, 0, 0]);
+        assert_eq!(fvb.read(5, 0x1f8, &mut buffer), Ok(8));
+        assert_eq!(image[0x1ffc..0x2008], [[0x5a; 4], [0xa5; 4], [0xa5; 4]].concat());
+    }
+
+    #[test]
+    fn erase_should_check_all_ranges_first() {
+        let mut image = volume(READ_WRITE | FvbAttributes2::ERASE_POLARITY);
+        let mut fvb = MemoryFvb::new(&mut image).unwrap();
+        fvb.write(0, 0x100, &[0; 4]).unwrap();
+        fvb.write(3, 0, &[0; 4]).unwrap();
+        assert_eq!(fvb.erase_blocks(&[(3, 1), (5, 2)]), Err(efi::Status::INVALID_PARAMETER));
+        assert_eq!(fvb.erase_blocks(&[(3, 0)]), Err(efi::Status::INVALID_PARAMETER));
+        fvb.erase_blocks(&[(1, 3)]).unwrap();
+        assert_eq!(image[0x100..0x104], [0; 4]);
+        assert!(image[0x1000..].iter().all(|&byte| byte == 0xff));
+    }
+
+    #[test]
+    fn attributes_should_follow_capabilities() {
+        let mut image = volume(READ_WRITE);
+        let mut fvb = MemoryFvb::new(&mut image).unwrap();
+        assert_eq!(fvb.physical_address(), Err(efi::Status::UNSUPPORTED));
+
+        // the erase polarity is not a status attribute, and reads cannot be disabled without the capability.
+        assert_eq!(
+            fvb.set_attributes(READ_WRITE | FvbAttributes2::ERASE_POLARITY),
+            Err(efi::Status::INVALID_PARAMETER)
+        );
+        let read_only = FvbAttributes2::from_bits_retain(READ_WRITE.bits() & !FvbAttributes2::WRITE_STATUS.bits());
+        let write_only = FvbAttributes2::from_bits_retain(READ_WRITE.bits() & !FvbAttributes2::READ_STATUS.bits());
+        assert_eq!(fvb.set_attributes(write_only), Err(efi::Status::INVALID_PARAMETER));
+
+        assert_eq!(fvb.set_attributes(read_only), Ok(read_only));
+        assert_eq!(fvb.write(0, 0, &[0]), Err(efi::Status::ACCESS_DENIED));
+        assert_eq!(fvb.erase_blocks(&[(0, 1)]), Err(efi::Status::ACCESS_DENIED));
+
+        // once locked, the attributes cannot change.
+        assert_eq!(
+            fvb.set_attributes(read_only | FvbAttributes2::LOCK_STATUS),
+            Ok(read_only | FvbAttributes2::LOCK_STATUS)
+        );
+        assert_eq!(fvb.set_attributes(READ_WRITE | FvbAttributes2::LOCK_STATUS), Err(efi::Status::INVALID_PARAMETER));
+        assert_eq!(fvb.attributes(), Ok(read_only | FvbAttributes2::LOCK_STATUS));
+    }
+}
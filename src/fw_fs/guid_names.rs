@@ -0,0 +1,124 @@
+//! Names of Well-Known Firmware File System GUIDs
+//!
+//! [`KNOWN_GUIDS`] maps the GUIDs of well-known section encapsulations, special files, common core files and memory
+//! allocation HOB names to human-readable names, and [`lookup`] resolves a GUID to its name. The `Debug`
+//! implementations of [`File`](super::File) and [`Section`](super::Section) use it to annotate file names and
+//! GUID_DEFINED sections, and the HOB dumps use it to label memory allocations.
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+use r_efi::efi;
+
+use super::ffs::guid;
+use crate::hob;
+
+/// A well-known GUID and its name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KnownGuid {
+    /// The GUID.
+    pub guid: efi::Guid,
+    /// The human-readable name of the GUID (e.g. `LzmaCustomDecompress`).
+    pub name: &'static str,
+}
+
+const fn known(guid: efi::Guid, name: &'static str) -> KnownGuid {
+    KnownGuid { guid, name }
+}
+
+/// All well-known GUIDs.
+pub const KNOWN_GUIDS: &[KnownGuid] = &[
+    // File systems.
+    known(guid::EFI_FIRMWARE_FILE_SYSTEM_GUID, "FirmwareFileSystem"),
+    known(guid::EFI_FIRMWARE_FILE_SYSTEM2_GUID, "FirmwareFileSystem2"),
+    known(guid::EFI_FIRMWARE_FILE_SYSTEM3_GUID, "FirmwareFileSystem3"),
+    // Section encapsulations.
+    known(guid::BROTLI_CUSTOM_DECOMPRESS_GUID, "BrotliCustomDecompress"),
+    known(guid::LZMA_CUSTOM_DECOMPRESS_GUID, "LzmaCustomDecompress"),
+    known(
+        // {D42AE6BD-1352-4BFB-909A-CA72A6EAE889}
+        efi::Guid::from_fields(0xd42ae6bd, 0x1352, 0x4bfb, 0x90, 0x9a, &[0xca, 0x72, 0xa6, 0xea, 0xe8, 0x89]),
+        "LzmaF86CustomDecompress",
+    ),
+    known(guid::TIANO_CUSTOM_DECOMPRESS_GUID, "TianoCustomDecompress"),
+    known(guid::EFI_CRC32_GUIDED_SECTION_EXTRACTION_GUID, "Crc32GuidedSectionExtraction"),
+    // Special files.
+    known(guid::PEI_APRIORI_FILE_NAME_GUID, "PeiAprioriFile"),
+    known(guid::EFI_APRIORI_GUID, "DxeAprioriFile"),
+    known(guid::EFI_FFS_VOLUME_TOP_FILE_GUID, "FfsVolumeTopFile"),
+    // Common core files.
+    known(
+        // {52C05B14-0B98-496C-BC3B-04B50211D680}
+        efi::Guid::from_fields(0x52c05b14, 0x0b98, 0x496c, 0xbc, 0x3b, &[0x04, 0xb5, 0x02, 0x11, 0xd6, 0x80]),
+        "PeiCore",
+    ),
+    known(
+        // {9B3ADA4F-AE56-4C24-8DEA-F03B7558AE50}
+        efi::Guid::from_fields(0x9b3ada4f, 0xae56, 0x4c24, 0x8d, 0xea, &[0xf0, 0x3b, 0x75, 0x58, 0xae, 0x50]),
+        "PcdPeim",
+    ),
+    known(
+        // {86D70125-BAA3-4296-A62F-602BEBBB9081}
+        efi::Guid::from_fields(0x86d70125, 0xbaa3, 0x4296, 0xa6, 0x2f, &[0x60, 0x2b, 0xeb, 0xbb, 0x90, 0x81]),
+        "DxeIpl",
+    ),
+    known(
+        // {D6A2CB7F-6A18-4E2F-B43B-9920A733700A}
+        efi::Guid::from_fields(0xd6a2cb7f, 0x6a18, 0x4e2f, 0xb4, 0x3b, &[0x99, 0x20, 0xa7, 0x33, 0x70, 0x0a]),
+        "DxeCore",
+    ),
+    known(
+        // {80CF7257-87AB-47F9-A3FE-D50B76D89541}
+        efi::Guid::from_fields(0x80cf7257, 0x87ab, 0x47f9, 0xa3, 0xfe, &[0xd5, 0x0b, 0x76, 0xd8, 0x95, 0x41]),
+        "PcdDxe",
+    ),
+    known(
+        // {B601F8C4-43B7-4784-95B1-F4226CB40CEE}
+        efi::Guid::from_fields(0xb601f8c4, 0x43b7, 0x4784, 0x95, 0xb1, &[0xf4, 0x22, 0x6c, 0xb4, 0x0c, 0xee]),
+        "RuntimeDxe",
+    ),
+    known(
+        // {F80697E9-7FD6-4665-8646-88E33EF71DFC}
+        efi::Guid::from_fields(0xf80697e9, 0x7fd6, 0x4665, 0x86, 0x46, &[0x88, 0xe3, 0x3e, 0xf7, 0x1d, 0xfc]),
+        "SecurityStubDxe",
+    ),
+    known(
+        // {6D33944A-EC75-4855-A54D-809C75241F6C}
+        efi::Guid::from_fields(0x6d33944a, 0xec75, 0x4855, 0xa5, 0x4d, &[0x80, 0x9c, 0x75, 0x24, 0x1f, 0x6c]),
+        "BdsDxe",
+    ),
+    // Memory allocation HOB names.
+    known(hob::HOB_MEMORY_ALLOC_STACK_GUID, "MemoryAllocStack"),
+    known(hob::HOB_MEMORY_ALLOC_BSP_STORE_GUID, "MemoryAllocBspStore"),
+    known(hob::HOB_MEMORY_ALLOC_MODULE_GUID, "MemoryAllocModule"),
+];
+
+/// Returns the name of the given GUID, if it is a well-known GUID.
+///
+/// ## Example
+/// ```
+/// use mu_pi::fw_fs::{ffs::guid::LZMA_CUSTOM_DECOMPRESS_GUID, guid_names};
+///
+/// assert_eq!(guid_names::lookup(&LZMA_CUSTOM_DECOMPRESS_GUID), Some("LzmaCustomDecompress"));
+/// ```
+pub fn lookup(guid: &efi::Guid) -> Option<&'static str> {
+    KNOWN_GUIDS.iter().find(|known| &known.guid == guid).map(|known| known.name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn known_guids_should_be_unique() {
+        for (index, known) in KNOWN_GUIDS.iter().enumerate() {
+            assert_eq!(lookup(&known.guid), Some(known.name), "duplicate GUID for {}", known.name);
+            assert!(KNOWN_GUIDS[index + 1..].iter().all(|other| other.name != known.name));
+        }
+        assert_eq!(lookup(&efi::Guid::from_fields(0, 0, 0, 0, 0, &[0; 6])), None);
+    }
+}
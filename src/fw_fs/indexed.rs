@@ -0,0 +1,107 @@
+//! Indexed Firmware Volume
+//!
+//! [`IndexedFirmwareVolume`] walks the files of a firmware volume once and indexes them by name, for callers that look
+//! up files in the same FV repeatedly (e.g. a dispatcher locating drivers by GUID) and should not re-walk the FFS chain
+//! on every query. Lookups by name take constant time.
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+use alloc::vec::Vec;
+use hashbrown::HashMap;
+use r_efi::efi;
+
+use super::{File, FirmwareVolume};
+
+/// A firmware volume with an index of its files by name.
+///
+/// The files are parsed when the index is built, so lookups neither re-parse nor re-verify them. If several files have
+/// the same name, lookups return the first one, as [`FirmwareVolume::file_by_name()`] does.
+///
+/// ## Example
+///```
+/// # use std::{env, fs, path::Path, error::Error};
+/// use mu_pi::fw_fs::{ffs, indexed::IndexedFirmwareVolume, FirmwareVolume};
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// # let root = Path::new(&env::var("CARGO_MANIFEST_DIR")?).join("test_resources");
+/// # let fv_bytes = fs::read(root.join("DXEFV.Fv"))?;
+/// let fv = FirmwareVolume::new(&fv_bytes).expect("Firmware Volume Corrupt");
+/// let indexed = IndexedFirmwareVolume::new(fv).expect("File Corrupt");
+/// if let Some(file) = indexed.file_by_name(ffs::guid::EFI_APRIORI_GUID) {
+///   println!("apriori file at offset {:#x?}", indexed.file_offset(file.name()));
+/// }
+/// # Ok(())
+/// # }
+///```
+#[derive(Debug, Clone)]
+pub struct IndexedFirmwareVolume<'a> {
+    fv: FirmwareVolume<'a>,
+    files: Vec<File<'a>>,
+    index: HashMap<efi::Guid, usize>,
+}
+
+impl<'a> IndexedFirmwareVolume<'a> {
+    /// Builds the file index of the given FV.
+    ///
+    /// Returns the error of the first file that fails to parse.
+    pub fn new(fv: FirmwareVolume<'a>) -> Result<Self, efi::Status> {
+        let files = fv.file_iter().collect::<Result<Vec<_>, _>>()?;
+        let mut index = HashMap::with_capacity(files.len());
+        for (position, file) in files.iter().enumerate() {
+            index.entry(file.name()).or_insert(position);
+        }
+        Ok(Self { fv, files, index })
+    }
+
+    /// Returns the indexed FV.
+    pub fn firmware_volume(&self) -> &FirmwareVolume<'a> {
+        &self.fv
+    }
+
+    /// Returns the files of the FV, in FV order.
+    pub fn files(&self) -> &[File<'a>] {
+        &self.files
+    }
+
+    /// Returns the file with the given name, or `None` if the FV has no such file.
+    pub fn file_by_name(&self, name: efi::Guid) -> Option<&File<'a>> {
+        self.index.get(&name).map(|&position| &self.files[position])
+    }
+
+    /// Returns the offset in the FV of the file with the given name, or `None` if the FV has no such file.
+    pub fn file_offset(&self, name: efi::Guid) -> Option<usize> {
+        let file = self.file_by_name(name)?;
+        Some(file.data().as_ptr() as usize - self.fv.data().as_ptr() as usize)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fw_fs::ffs;
+    use std::{env, fs, path::Path};
+
+    #[test]
+    fn indexed_lookups_should_match_file_walk() {
+        let root = Path::new(&env::var("CARGO_MANIFEST_DIR").unwrap()).join("test_resources");
+        let fv_bytes = fs::read(root.join("DXEFV.Fv")).unwrap();
+        let fv = FirmwareVolume::new(&fv_bytes).unwrap();
+        let indexed = IndexedFirmwareVolume::new(fv.clone()).unwrap();
+        assert_eq!(indexed.files().len(), fv.file_iter().count());
+
+        for file in fv.file_iter() {
+            let file = file.unwrap();
+            let found = indexed.file_by_name(file.name()).unwrap();
+            assert_eq!(found.data().as_ptr(), fv.file_by_name(file.name()).unwrap().data().as_ptr());
+            let offset = indexed.file_offset(file.name()).unwrap();
+            assert_eq!(&fv_bytes[offset..offset + found.data().len()], found.data());
+        }
+
+        assert!(indexed.file_by_name(ffs::guid::PEI_APRIORI_FILE_NAME_GUID).is_none());
+        assert_eq!(indexed.file_offset(ffs::guid::PEI_APRIORI_FILE_NAME_GUID), None);
+    }
+}
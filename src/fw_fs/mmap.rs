@@ -0,0 +1,111 @@
+//! Memory-Mapped Firmware Volumes
+//!
+//! Host tools that parse multi-gigabyte flash dumps can map the file read-only instead of reading it into memory.
+//! [`MappedFirmwareVolume`] owns the mapping, and the [`FirmwareVolume`] parsed from it borrows the mapping, so it
+//! cannot outlive it. Pages of the file are only read as the parser touches them.
+//!
+//! ## Example
+//! ```no_run
+//! use mu_pi::fw_fs::FirmwareVolume;
+//!
+//! let mapped = FirmwareVolume::open_mmap("flash.bin").expect("cannot map file");
+//! for (offset, fv) in mapped.firmware_volumes() {
+//!   println!("FV at {:#x}: {} files", offset, fv.file_iter().count());
+//! }
+//! ```
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+use std::{fmt, fs, io, path::Path};
+
+use memmap2::Mmap;
+
+use super::{FirmwareVolume, FwFsError};
+use crate::parse::ParseOptions;
+
+/// A file mapped read-only in memory, holding a firmware volume or a flash image with firmware volumes.
+pub struct MappedFirmwareVolume {
+    mmap: Mmap,
+}
+
+impl MappedFirmwareVolume {
+    /// Maps the file at `path` read-only.
+    ///
+    /// The file must not be modified while it is mapped: the parsers assume the buffer they parse does not change.
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let file = fs::File::open(path)?;
+        // Safety: the mapping is read-only, and the caller is documented not to modify the file while it is mapped.
+        let mmap = unsafe { Mmap::map(&file)? };
+        Ok(Self { mmap })
+    }
+
+    /// Returns the contents of the mapped file.
+    pub fn data(&self) -> &[u8] {
+        &self.mmap
+    }
+
+    /// Parses the firmware volume at the start of the mapped file.
+    pub fn volume(&self) -> Result<FirmwareVolume<'_>, FwFsError> {
+        FirmwareVolume::try_new(self.data(), &ParseOptions::default())
+    }
+
+    /// Parses the firmware volume at the start of the mapped file with the given options.
+    pub fn volume_with_options(&self, options: &ParseOptions) -> Result<FirmwareVolume<'_>, FwFsError> {
+        FirmwareVolume::try_new(self.data(), options)
+    }
+
+    /// Returns an iterator of the firmware volumes in the mapped file, with their offsets (see
+    /// [`FirmwareVolume::find_all`]).
+    pub fn firmware_volumes(&self) -> impl Iterator<Item = (usize, FirmwareVolume<'_>)> {
+        FirmwareVolume::find_all(self.data())
+    }
+}
+
+impl fmt::Debug for MappedFirmwareVolume {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MappedFirmwareVolume").field("len", &self.mmap.len()).finish()
+    }
+}
+
+impl FirmwareVolume<'_> {
+    /// Maps the file at `path` read-only, to parse the firmware volumes in it without reading it into memory.
+    ///
+    /// The returned mapping owns the file contents; parse them with [`MappedFirmwareVolume::volume`] or
+    /// [`MappedFirmwareVolume::firmware_volumes`].
+    pub fn open_mmap(path: impl AsRef<Path>) -> io::Result<MappedFirmwareVolume> {
+        MappedFirmwareVolume::open(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs, path::Path};
+
+    use super::*;
+
+    #[test]
+    fn mapped_volume_should_match_buffered_volume() {
+        let path = Path::new(&env::var("CARGO_MANIFEST_DIR").unwrap()).join("test_resources").join("DXEFV.Fv");
+        let buffer = fs::read(&path).unwrap();
+        let buffered = FirmwareVolume::new(&buffer).unwrap();
+
+        let mapped = FirmwareVolume::open_mmap(&path).unwrap();
+        assert_eq!(mapped.data(), &buffer[..]);
+        let fv = mapped.volume().unwrap();
+        assert_eq!(fv.fv_name(), buffered.fv_name());
+        assert!(fv
+            .file_iter()
+            .map(|file| file.unwrap().name())
+            .eq(buffered.file_iter().map(|file| file.unwrap().name())));
+        assert_eq!(mapped.firmware_volumes().count(), 1);
+
+        let not_a_volume =
+            FirmwareVolume::open_mmap(Path::new(&env::var("CARGO_MANIFEST_DIR").unwrap()).join("Cargo.toml"));
+        assert!(matches!(not_a_volume.unwrap().volume(), Err(FwFsError::BadSignature { .. })));
+    }
+}
@@ -0,0 +1,942 @@
+//! Allocation-Free Firmware Volume Access
+//!
+//! [`FirmwareVolume`](super::FirmwareVolume), [`File`](super::File) and [`Section`](super::Section) allocate: the
+//! block map and the tolerated spec deviations are collected into vectors, and section data is copied into boxed
+//! buffers. The types in this module validate and walk a firmware volume in place without allocating, so they can be
+//! used before memory is available (SEC and early PEI). The allocating types are built on them, so a firmware volume
+//! is accepted or rejected in the same way by both.
+//!
+//! Encapsulation sections are not extracted. [`RawSection::section_iter`] walks the sections held in place in an
+//! encapsulation section whose data is not encoded, and the volume held in a FIRMWARE_VOLUME_IMAGE section can be
+//! opened with [`RawFirmwareVolume::new`].
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+use core::{fmt, mem};
+
+use r_efi::efi;
+
+use super::{
+    ffs::{attributes::raw::LARGE_FILE, file, section},
+    file_header_size_and_length, file_tail_size, file_type_from_raw, fv, invalid_file_length, section_type_from_raw,
+    verify_file_checksums, EfiFvbAttributes2, FfsFileState, FfsFileSystem, FfsFileType, FfsGuidedSectionAttribute,
+    FfsSectionHeader, FfsSectionRawType, FfsSectionType, FirmwareVolumeExtHeader, FvbAttributes2, FwFsError,
+};
+use crate::{
+    checksum,
+    parse::{AlignmentPolicy, Deviation, Diagnostic, ParseOptions, ValidationLevel},
+};
+
+// The most spec deviations a single file can have: marked for update, large file in FFS2 and fixed checksum.
+const MAX_FILE_DEVIATIONS: usize = 3;
+
+/// A firmware volume that is validated and walked in place, without allocating.
+///
+/// ## Example
+///```
+/// # use std::{env, fs, path::Path, error::Error};
+/// use mu_pi::fw_fs::{raw::RawFirmwareVolume, FfsFileType, FfsSectionType};
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// # let root = Path::new(&env::var("CARGO_MANIFEST_DIR")?).join("test_resources");
+/// # let fv_bytes = fs::read(root.join("DXEFV.Fv"))?;
+/// let fv = RawFirmwareVolume::new(&fv_bytes).expect("Firmware Volume Corrupt");
+/// for file in fv.file_iter() {
+///   let file = file.expect("File Corrupt");
+///   if file.file_type() != Some(FfsFileType::Driver) {
+///     continue;
+///   }
+///   for section in file.section_iter() {
+///     let section = section.expect("Section Corrupt");
+///     if section.section_type() == Some(FfsSectionType::Pe32) {
+///       println!("driver {:?} has a {:#x} byte image", file.name(), section.section_data().len());
+///     }
+///   }
+/// }
+/// # Ok(())
+/// # }
+///```
+#[derive(Clone)]
+pub struct RawFirmwareVolume<'a> {
+    pub(super) data: &'a [u8],
+    pub(super) attributes: EfiFvbAttributes2,
+    // The block map entries, without the terminating entry.
+    pub(super) block_map: &'a [u8],
+    pub(super) ext_header: Option<FirmwareVolumeExtHeader<'a>>,
+    pub(super) data_offset: usize,
+    pub(super) erase_byte: u8,
+    pub(super) file_system: FfsFileSystem,
+    pub(super) options: ParseOptions,
+}
+
+impl<'a> RawFirmwareVolume<'a> {
+    /// Validates the firmware volume in buffer.
+    pub fn new(buffer: &'a [u8]) -> Result<Self, FwFsError> {
+        Self::new_with_options(buffer, &ParseOptions::default())
+    }
+
+    /// Validates the firmware volume in buffer using the given parse options.
+    ///
+    /// The options are also used to parse the files returned by [`RawFirmwareVolume::file_iter()`].
+    pub fn new_with_options(buffer: &'a [u8], options: &ParseOptions) -> Result<Self, FwFsError> {
+        Self::parse(buffer, buffer.len() as u64, options, &mut |_| ())
+    }
+
+    /// Validates the firmware volume in buffer using the given parse options, reporting non-fatal oddities in the
+    /// firmware volume header to `diagnostics`.
+    pub fn new_with_diagnostics(
+        buffer: &'a [u8],
+        options: &ParseOptions,
+        diagnostics: &mut dyn FnMut(Diagnostic),
+    ) -> Result<Self, FwFsError> {
+        Self::parse(buffer, buffer.len() as u64, options, diagnostics)
+    }
+
+    // Parses the FV headers in buffer. storage_size is the size of the storage holding the FV, which may be larger
+    // than buffer when buffer only holds the headers (see storage::StorageFirmwareVolume).
+    pub(super) fn parse(
+        buffer: &'a [u8],
+        storage_size: u64,
+        options: &ParseOptions,
+        diagnostics: &mut dyn FnMut(Diagnostic),
+    ) -> Result<Self, FwFsError> {
+        //buffer must be large enough to hold the header structure.
+        if buffer.len() < mem::size_of::<fv::Header>() {
+            Err(FwFsError::BufferTooSmall { needed: mem::size_of::<fv::Header>(), available: buffer.len() })?;
+        }
+
+        //Safety: buffer is large enough to contain the header, so can cast to a ref.
+        let fv_header = unsafe { &*(buffer.as_ptr() as *const fv::Header) };
+
+        // signature: must be ASCII '_FVH'
+        if fv_header.signature != u32::from_le_bytes(*b"_FVH") {
+            Err(FwFsError::BadSignature { signature: fv_header.signature })?;
+        }
+
+        // header_length: must be large enough to hold the header.
+        if (fv_header.header_length as usize) < mem::size_of::<fv::Header>() {
+            Err(FwFsError::BadHeaderLength { header_length: fv_header.header_length })?;
+        }
+
+        // header_length: buffer must be large enough to hold the header.
+        if (fv_header.header_length as usize) > buffer.len() {
+            Err(FwFsError::BadHeaderLength { header_length: fv_header.header_length })?;
+        }
+
+        // checksum: fv header must sum to zero (and must be multiple of 2 bytes)
+        if fv_header.header_length & 0x01 != 0 {
+            Err(FwFsError::BadHeaderLength { header_length: fv_header.header_length })?;
+        }
+
+        let full_validation = options.validation == ValidationLevel::Full;
+        if full_validation {
+            verify_header_checksum(&buffer[..fv_header.header_length as usize])?;
+        }
+
+        // file_system_guid: must be EFI_FIRMWARE_FILE_SYSTEM_GUID, EFI_FIRMWARE_FILE_SYSTEM2_GUID or
+        // EFI_FIRMWARE_FILE_SYSTEM3_GUID.
+        let file_system = FfsFileSystem::from_guid(&fv_header.file_system_guid)
+            .ok_or(FwFsError::UnknownFileSystem { guid: fv_header.file_system_guid })?;
+
+        // revision: must be at least 2, or 1 for FFS1 volumes which predate EFI_FVH_REVISION 2. Assumes that if later
+        // specs bump the rev they will maintain backwards compat with existing header definition.
+        let min_revision = if file_system == FfsFileSystem::Ffs1 { 1 } else { 2 };
+        if fv_header.revision < min_revision {
+            Err(FwFsError::BadRevision { revision: fv_header.revision })?;
+        }
+        if fv_header.revision > 2 {
+            diagnostics(Diagnostic::FvRevision { revision: fv_header.revision });
+        }
+
+        // fv_length: must be large enough to hold the header.
+        if fv_header.fv_length < fv_header.header_length as u64 {
+            Err(FwFsError::BadFvLength { fv_length: fv_header.fv_length })?;
+        }
+
+        // fv_length: must be less than or equal to the storage length
+        if fv_header.fv_length > storage_size {
+            Err(FwFsError::BadFvLength { fv_length: fv_header.fv_length })?;
+        }
+
+        //ext_header_offset: must be inside the fv. Revision 1 headers have no ext header; the field is reserved.
+        let ext_header_offset = if fv_header.revision < 2 { 0 } else { fv_header.ext_header_offset };
+        if ext_header_offset as u64 > fv_header.fv_length {
+            Err(FwFsError::BadExtHeader { offset: ext_header_offset as usize })?;
+        }
+
+        //if ext_header is present, its size must fit inside the FV.
+        let ext_header = {
+            if ext_header_offset != 0 {
+                let ext_header_offset = ext_header_offset as usize;
+                if ext_header_offset + mem::size_of::<fv::ExtHeader>() > buffer.len() {
+                    Err(FwFsError::BadExtHeader { offset: ext_header_offset })?;
+                }
+
+                //Safety: previous check ensures that fv_data is large enough to contain the ext_header
+                let ext_header = unsafe { &*(buffer[ext_header_offset..].as_ptr() as *const fv::ExtHeader) };
+                let ext_header_end = ext_header_offset + ext_header.ext_header_size as usize;
+                if ext_header_end > buffer.len()
+                    || (ext_header.ext_header_size as usize) < mem::size_of::<fv::ExtHeader>()
+                {
+                    Err(FwFsError::BadExtHeader { offset: ext_header_offset })?;
+                }
+                Some(FirmwareVolumeExtHeader { header: *ext_header, data: &buffer[ext_header_offset..ext_header_end] })
+            } else {
+                None
+            }
+        };
+
+        //block map must fit within the fv header (which is checked above to guarantee it is within the fv_data buffer).
+        let block_map = &buffer[mem::size_of::<fv::Header>()..fv_header.header_length as usize];
+
+        //block map should be a multiple of 8 in size
+        if block_map.len() & 0x7 != 0 {
+            Err(FwFsError::BadBlockMap)?;
+        }
+
+        //block map should terminate with zero entry
+        let terminator = mem::size_of::<fv::BlockMapEntry>();
+        if block_map.len() < terminator || block_map[block_map.len() - terminator..].iter().any(|&x| x != 0) {
+            Err(FwFsError::BadBlockMap)?;
+        }
+
+        //remove the terminator.
+        let block_map = &block_map[..block_map.len() - terminator];
+
+        //thre must be at least one valid entry in the block map.
+        if block_map.is_empty() {
+            Err(FwFsError::BadBlockMap)?;
+        }
+
+        if full_validation {
+            verify_block_map_entries(block_map)?;
+        }
+
+        let data_offset = {
+            if let Some(ext_header) = &ext_header {
+                // if ext header exists, then data starts after ext header
+                ext_header_offset as usize + ext_header.header.ext_header_size as usize
+            } else {
+                // otherwise data starts after the fv_header.
+                fv_header.header_length as usize
+            }
+        };
+
+        if data_offset & 0x7 != 0 {
+            diagnostics(Diagnostic::UnalignedFvData { offset: data_offset });
+        }
+        let data_offset = options.alignment.align(buffer, data_offset, 8);
+        let erase_byte = FvbAttributes2::from_bits_retain(fv_header.attributes).erase_byte();
+
+        Ok(Self {
+            data: buffer,
+            attributes: fv_header.attributes,
+            block_map,
+            ext_header,
+            data_offset,
+            erase_byte,
+            file_system,
+            options: *options,
+        })
+    }
+
+    /// Verifies the header checksum and the block map entries of the FV, which are not checked when it was
+    /// instantiated with [`ValidationLevel::Structural`].
+    pub fn verify_header(&self) -> Result<(), FwFsError> {
+        verify_fv_header(self.data, self.block_map)
+    }
+
+    /// Returns an iterator of the block map entries of the FV, without the terminating entry.
+    pub fn block_map(&self) -> impl Iterator<Item = fv::BlockMapEntry> + 'a {
+        block_map_entries(self.block_map)
+    }
+
+    /// Returns the GUID name of the FV, if any.
+    pub fn fv_name(&self) -> Option<efi::Guid> {
+        self.ext_header.as_ref().map(|ext_header| ext_header.header.fv_name)
+    }
+
+    /// Returns the Firmware File System version of the FV, as given by its file system GUID.
+    pub fn file_system(&self) -> FfsFileSystem {
+        self.file_system
+    }
+
+    /// Returns the extended header of this FV, if present.
+    pub fn ext_header(&self) -> Option<&FirmwareVolumeExtHeader<'a>> {
+        self.ext_header.as_ref()
+    }
+
+    /// Returns the attributes for the FirmwareVolume
+    pub fn attributes(&self) -> FvbAttributes2 {
+        FvbAttributes2::from_bits_retain(self.attributes)
+    }
+
+    /// Returns the size in bytes of the FV data + header.
+    pub fn size(&self) -> u64 {
+        self.data.len() as u64
+    }
+
+    /// Returns the FV data.
+    pub fn data(&self) -> &'a [u8] {
+        self.data
+    }
+
+    /// Returns an iterator of the files in this FV.
+    ///
+    /// Iteration behaves like [`FirmwareVolume::try_file_iter`](super::FirmwareVolume::try_file_iter).
+    pub fn file_iter(&self) -> RawFileIterator<'a> {
+        RawFileIterator::new(&self.data[self.data_offset..], self.erase_byte, self.file_system, self.options)
+    }
+
+    /// Returns an iterator of the files in this FV that reports non-fatal oddities in the files, including the
+    /// tolerated spec deviations, to `diagnostics`.
+    pub fn file_iter_with_diagnostics<'s>(
+        &self,
+        diagnostics: &'s mut dyn FnMut(Diagnostic),
+    ) -> impl Iterator<Item = Result<RawFile<'a>, FwFsError>> + 's
+    where
+        'a: 's,
+    {
+        let mut files = self.file_iter();
+        core::iter::from_fn(move || files.next_with_diagnostics(diagnostics))
+    }
+}
+
+impl<'a> fmt::Debug for RawFirmwareVolume<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RawFirmwareVolume")
+            .field("attributes", &self.attributes)
+            .field("block_map.len()", &(self.block_map.len() / mem::size_of::<fv::BlockMapEntry>()))
+            .field("ext_header", &self.ext_header)
+            .field("data_offset", &self.data_offset)
+            .field("erase_byte", &self.erase_byte)
+            .field("file_system", &self.file_system)
+            .field("options", &self.options)
+            .field("data.len()", &self.data.len())
+            .finish_non_exhaustive()
+    }
+}
+
+// Decodes the block map entries held in block_map.
+pub(super) fn block_map_entries(block_map: &[u8]) -> impl Iterator<Item = fv::BlockMapEntry> + '_ {
+    block_map.chunks_exact(mem::size_of::<fv::BlockMapEntry>()).map(|x| fv::BlockMapEntry {
+        num_blocks: u32::from_le_bytes(x[..4].try_into().unwrap()),
+        length: u32::from_le_bytes(x[4..].try_into().unwrap()),
+    })
+}
+
+// Makes the checks of the FV header in data that are skipped under ValidationLevel::Structural. block_map holds the
+// block map entries without the terminating entry.
+pub(super) fn verify_fv_header(data: &[u8], block_map: &[u8]) -> Result<(), FwFsError> {
+    let header_length = mem::size_of::<fv::Header>() + block_map.len() + mem::size_of::<fv::BlockMapEntry>();
+    verify_header_checksum(&data[..header_length])?;
+    verify_block_map_entries(block_map)
+}
+
+// Verifies that the FV header (including its block map) sums to zero.
+fn verify_header_checksum(header: &[u8]) -> Result<(), FwFsError> {
+    if !checksum::verify16(header) {
+        Err(FwFsError::BadHeaderChecksum)?;
+    }
+    Ok(())
+}
+
+// Verifies that the block map entries before the terminator are non-zero.
+fn verify_block_map_entries(block_map: &[u8]) -> Result<(), FwFsError> {
+    if block_map_entries(block_map).any(|x| x == fv::BlockMapEntry { num_blocks: 0, length: 0 }) {
+        Err(FwFsError::BadBlockMap)?;
+    }
+    Ok(())
+}
+
+/// A file that is validated in place, without allocating.
+#[derive(Clone, Copy)]
+pub struct RawFile<'a> {
+    pub(super) data: &'a [u8],
+    pub(super) name: efi::Guid,
+    pub(super) file_type: u8,
+    pub(super) attributes: u8,
+    pub(super) header_size: usize,
+    pub(super) tail_size: usize,
+    pub(super) size: u64,
+    pub(super) state: u8,
+    pub(super) erase_polarity: bool,
+    pub(super) file_system: FfsFileSystem,
+    pub(super) alignment: AlignmentPolicy,
+    deviations: [Option<Deviation>; MAX_FILE_DEVIATIONS],
+}
+
+impl<'a> RawFile<'a> {
+    /// Validates the file in buffer using the given parse options. The file is parsed as an FFS2/FFS3 file.
+    pub fn new(buffer: &'a [u8], options: &ParseOptions) -> Result<Self, FwFsError> {
+        Self::parse(buffer, None, FfsFileSystem::Ffs3, options, &mut |_| ())
+    }
+
+    // Parses the file in buffer as a file of the given file system. When the erase polarity of the containing FV is
+    // not known, it is inferred from the file state.
+    pub(super) fn parse(
+        buffer: &'a [u8],
+        erase_polarity: Option<bool>,
+        file_system: FfsFileSystem,
+        options: &ParseOptions,
+        diagnostics: &mut dyn FnMut(Diagnostic),
+    ) -> Result<Self, FwFsError> {
+        let (header_size, size) = file_header_size_and_length(buffer, file_system)?;
+
+        //Safety: file_header_size_and_length verified the buffer is large enough to contain the header.
+        let file_header = unsafe { &*(buffer.as_ptr() as *const file::Header) };
+        let tail_size = file_tail_size(file_header, file_system);
+
+        // Verify that the total size of the file fits within the buffer, and is large enough to hold the header and
+        // the tail (if any).
+        if size as usize > buffer.len() || (size as usize) < header_size + tail_size {
+            Err(FwFsError::BadFileSize { name: file_header.name, size })?;
+        }
+
+        let mut deviations = [None; MAX_FILE_DEVIATIONS];
+        let mut deviation_count = 0;
+        let mut report = |deviation| {
+            deviations[deviation_count] = Some(deviation);
+            deviation_count += 1;
+        };
+
+        // Interpreting the state field requires knowledge of the EFI_FVB_ERASE_POLARITY from the FV header. When it is
+        // not specified, it is inferred based on the state of the reserved bits in the EFI_FFS_FILE_STATE which spec
+        // requires to be set to EFI_FVB_ERASE_POLARITY.
+        // This implementation does not support FV modification, so the only valid state is EFI_FILE_DATA_VALID. EDK2
+        // also accepts files in the EFI_FILE_MARKED_FOR_UPDATE state, which is tolerated per the parse options.
+        let erase_polarity = erase_polarity.unwrap_or(file_header.state & 0x80 != 0);
+        let state = if !erase_polarity {
+            //erase polarity = 0. Bits are set as the state advances.
+            file_header.state & 0xFC
+        } else {
+            //erase polarity = 1. Bits are cleared as the state advances.
+            (!file_header.state) & 0xFC
+        };
+        if state == file::raw::state::DATA_VALID | file::raw::state::MARKED_FOR_UPDATE {
+            options
+                .tolerate(Deviation::FileMarkedForUpdate { name: file_header.name }, &mut report)
+                .map_err(FwFsError::Deviation)?;
+        } else if state != file::raw::state::DATA_VALID {
+            //file is not in EFI_FILE_DATA_VALID state.
+            Err(FwFsError::BadFileState { name: file_header.name, state: file_header.state })?;
+        }
+
+        // Large files (with an extended header) may only be present in FFS3 volumes.
+        if file_system == FfsFileSystem::Ffs2 && file_header.attributes & LARGE_FILE != 0 {
+            options
+                .tolerate(Deviation::LargeFileInFfs2 { name: file_header.name }, &mut report)
+                .map_err(FwFsError::Deviation)?;
+        }
+
+        if options.verify_checksums {
+            verify_file_checksums(file_header, &buffer[..size as usize], header_size, file_system)?;
+
+            // Verify that the checksum is initialized to the fixed value (0xAA, or 0x5A for FFS1) per spec requirements
+            // when CHECKSUM attribute is cleared.
+            if file_header.attributes & super::ffs::attributes::raw::CHECKSUM == 0
+                && file_header.integrity_check_file != file_system.fixed_file_checksum()
+            {
+                let deviation =
+                    Deviation::FileFixedChecksum { name: file_header.name, checksum: file_header.integrity_check_file };
+                options.tolerate(deviation, &mut report).map_err(FwFsError::Deviation)?;
+            }
+        }
+
+        if size as usize == header_size + tail_size {
+            diagnostics(Diagnostic::EmptyFile { name: file_header.name });
+        }
+        deviations.iter().flatten().for_each(|&deviation| diagnostics(Diagnostic::Deviation(deviation)));
+
+        Ok(Self {
+            data: &buffer[..size as usize],
+            name: file_header.name,
+            file_type: file_header.file_type,
+            attributes: file_header.attributes,
+            header_size,
+            tail_size,
+            size,
+            state: file_header.state,
+            erase_polarity,
+            file_system,
+            alignment: options.alignment,
+            deviations,
+        })
+    }
+
+    /// Returns the spec deviations that were tolerated while parsing this file.
+    ///
+    /// Only populated when the file was parsed with [`Strictness::Compatible`](crate::parse::Strictness::Compatible).
+    pub fn deviations(&self) -> impl Iterator<Item = Deviation> + '_ {
+        self.deviations.iter().flatten().copied()
+    }
+
+    /// Returns the file type.
+    pub fn file_type(&self) -> Option<FfsFileType> {
+        file_type_from_raw(self.file_type)
+    }
+
+    /// Returns the file type as a raw u8.
+    pub fn file_type_raw(&self) -> u8 {
+        self.file_type
+    }
+
+    /// Returns the file state, decoded using the erase polarity of the containing FV.
+    pub fn state(&self) -> Option<FfsFileState> {
+        FfsFileState::from_raw(self.state, self.erase_polarity)
+    }
+
+    /// Returns the file attributes as a raw u8
+    pub fn attributes_raw(&self) -> u8 {
+        self.attributes
+    }
+
+    /// Returns the file name GUID.
+    pub fn name(&self) -> efi::Guid {
+        self.name
+    }
+
+    /// Returns the size in bytes of the whole file, including the header.
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    /// Returns the Firmware File System version the file was parsed as.
+    pub fn file_system(&self) -> FfsFileSystem {
+        self.file_system
+    }
+
+    /// Returns the raw data from the file (without extracting any sections), not including the header or the FFS1
+    /// file tail.
+    pub fn content(&self) -> &'a [u8] {
+        &self.data[self.header_size..self.size as usize - self.tail_size]
+    }
+
+    /// Returns the raw data for the file, including the header.
+    pub fn data(&self) -> &'a [u8] {
+        self.data
+    }
+
+    /// Returns an iterator over the sections of this file, without extracting encapsulation sections.
+    pub fn section_iter(&self) -> RawSectionIterator<'a> {
+        RawSectionIterator::new(self.content(), self.alignment)
+    }
+}
+
+impl<'a> fmt::Debug for RawFile<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RawFile")
+            .field("name", &self.name)
+            .field("file_type", &self.file_type)
+            .field("attributes", &self.attributes)
+            .field("header_size", &self.header_size)
+            .field("tail_size", &self.tail_size)
+            .field("size", &self.size)
+            .field("state", &self.state)
+            .field("file_system", &self.file_system)
+            .field("data.len()", &self.data.len())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Iterator of the files in a [`RawFirmwareVolume`].
+///
+/// A file that fails to parse is returned as an error, and ends the iteration since the location of the next file
+/// cannot be trusted.
+#[derive(Clone)]
+pub struct RawFileIterator<'a> {
+    buffer: &'a [u8],
+    erase_byte: u8,
+    file_system: FfsFileSystem,
+    options: ParseOptions,
+    next_offset: usize,
+    error: bool,
+}
+
+impl<'a> RawFileIterator<'a> {
+    pub(super) fn new(buffer: &'a [u8], erase_byte: u8, file_system: FfsFileSystem, options: ParseOptions) -> Self {
+        RawFileIterator { buffer, erase_byte, file_system, options, next_offset: 0, error: false }
+    }
+
+    // Returns the next file, reporting non-fatal oddities in it to diagnostics.
+    pub(super) fn next_with_diagnostics(
+        &mut self,
+        diagnostics: &mut dyn FnMut(Diagnostic),
+    ) -> Option<Result<RawFile<'a>, FwFsError>> {
+        if self.error {
+            return None;
+        }
+        let erase_polarity = self.erase_byte != 0;
+        loop {
+            if self.next_offset > self.buffer.len() {
+                return None;
+            }
+            if self.buffer[self.next_offset..].len() < mem::size_of::<file::Header>() {
+                return None;
+            }
+            if self.buffer[self.next_offset..self.next_offset + mem::size_of::<file::Header>()]
+                .iter()
+                .all(|&x| x == self.erase_byte)
+            {
+                return None;
+            }
+            if !self.options.skip_invalid_files {
+                break;
+            }
+            let buffer = &self.buffer[self.next_offset..];
+            match invalid_file_length(buffer, buffer.len(), self.file_system, erase_polarity) {
+                Ok(Some(length)) => {
+                    self.next_offset = self.options.alignment.align(self.buffer, self.next_offset + length as usize, 8)
+                }
+                Ok(None) => break,
+                Err(err) => {
+                    self.error = true;
+                    return Some(Err(err));
+                }
+            }
+        }
+        let buffer = &self.buffer[self.next_offset..];
+        let result = RawFile::parse(buffer, Some(erase_polarity), self.file_system, &self.options, diagnostics);
+        if let Ok(ref file) = result {
+            // per the PI spec, "Given a file F, the next file FvHeader is located at the next 8-byte aligned firmware volume
+            // offset following the last byte the file F". EDK2 aligns the address of the next file instead.
+            self.next_offset = self.options.alignment.align(self.buffer, self.next_offset + file.size() as usize, 8);
+        } else {
+            self.error = true;
+        }
+
+        Some(result)
+    }
+}
+
+impl<'a> Iterator for RawFileIterator<'a> {
+    type Item = Result<RawFile<'a>, FwFsError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_with_diagnostics(&mut |_| ())
+    }
+}
+
+/// Section Metadata
+///
+/// Describes the meta data in the section header (if any - most section types do not have metadata), borrowing the
+/// GUID-specific header fields of GUID-defined sections.
+#[derive(Debug, Clone, Copy)]
+pub enum RawSectionMetaData<'a> {
+    None,
+    Compression(FfsSectionHeader::Compression),
+    GuidDefined(FfsSectionHeader::GuidDefined, &'a [u8]),
+    Version(FfsSectionHeader::Version),
+    FreeformSubtypeGuid(FfsSectionHeader::FreeformSubtypeGuid),
+}
+
+/// A section that is validated in place, without allocating.
+#[derive(Debug, Clone, Copy)]
+pub struct RawSection<'a> {
+    pub(super) section_type: u8,
+    pub(super) meta_data: RawSectionMetaData<'a>,
+    pub(super) data: &'a [u8],
+    pub(super) section_size: usize,
+    // the alignment of the sections encapsulated in this section.
+    alignment: AlignmentPolicy,
+}
+
+impl<'a> RawSection<'a> {
+    /// Validates the section at the start of buffer.
+    pub fn new(buffer: &'a [u8]) -> Result<Self, FwFsError> {
+        // verify that buffer has enough storage for a section header.
+        if buffer.len() < mem::size_of::<section::Header>() {
+            Err(FwFsError::BufferTooSmall { needed: mem::size_of::<section::Header>(), available: buffer.len() })?;
+        }
+
+        //Safety: buffer is large enough to contain the header, so can cast to a ref.
+        let section_header = unsafe { &*(buffer.as_ptr() as *const section::Header) };
+
+        //determine section_size and start of section content based on whether extended size field is present.
+        let header_end = mem::size_of::<section::Header>();
+        let (section_size, content_offset) = {
+            if section_header.size.iter().all(|&x| x == 0xff) {
+                //extended header - confirm there is space for extended size
+                if buffer.len() < header_end + mem::size_of::<u32>() {
+                    Err(FwFsError::TruncatedHeader {
+                        needed: header_end + mem::size_of::<u32>(),
+                        available: buffer.len(),
+                    })?;
+                }
+                let size =
+                    u32::from_le_bytes(buffer[header_end..header_end + mem::size_of::<u32>()].try_into().unwrap());
+                (size as usize, header_end + mem::size_of::<u32>())
+            } else {
+                //standard header
+                let [b0, b1, b2] = section_header.size;
+                let size = u32::from_le_bytes([b0, b1, b2, 0]);
+                (size as usize, header_end)
+            }
+        };
+
+        //verify that the section fits in the buffer and is large enough to hold its header.
+        if section_size > buffer.len() || section_size < content_offset {
+            Err(FwFsError::BadSectionSize { section_type: section_header.section_type, size: section_size })?;
+        }
+
+        let (meta_data, data) = match section_header.section_type {
+            FfsSectionRawType::encapsulated::COMPRESSION => {
+                let compression_header_size = mem::size_of::<section::header::Compression>();
+                //verify that buffer has enough storage for a compression header.
+                if buffer.len() < content_offset + compression_header_size {
+                    Err(FwFsError::TruncatedHeader {
+                        needed: content_offset + compression_header_size,
+                        available: buffer.len(),
+                    })?;
+                }
+                //Safety: buffer is large enough to hold compression header
+                let compression_header =
+                    unsafe { &*(buffer[content_offset..].as_ptr() as *const section::header::Compression) };
+                let data = &buffer[content_offset + compression_header_size..section_size];
+                (RawSectionMetaData::Compression(*compression_header), data)
+            }
+            FfsSectionRawType::encapsulated::GUID_DEFINED => {
+                let guid_defined_header_size = mem::size_of::<section::header::GuidDefined>();
+                //verify that buffer has enough storage for a guid_defined header.
+                if buffer.len() < content_offset + guid_defined_header_size {
+                    Err(FwFsError::TruncatedHeader {
+                        needed: content_offset + guid_defined_header_size,
+                        available: buffer.len(),
+                    })?;
+                }
+                //Safety: buffer is large enough to hold guid_defined header
+                let guid_defined =
+                    unsafe { &*(buffer[content_offset..].as_ptr() as *const section::header::GuidDefined) };
+
+                //verify that buffer has enough storage for guid-specific fields.
+                let data_offset = guid_defined.data_offset as usize;
+                if section_size < data_offset || data_offset < content_offset + guid_defined_header_size {
+                    Err(FwFsError::BadSectionDataOffset { data_offset: guid_defined.data_offset })?;
+                }
+
+                let guid_specific_header_fields = &buffer[content_offset + guid_defined_header_size..data_offset];
+                let data = &buffer[data_offset..section_size];
+
+                (RawSectionMetaData::GuidDefined(*guid_defined, guid_specific_header_fields), data)
+            }
+            FfsSectionRawType::VERSION => {
+                let version_header_size = mem::size_of::<section::header::Version>();
+                //verify that buffer has enough storage for a version header.
+                if buffer.len() < content_offset + version_header_size {
+                    Err(FwFsError::TruncatedHeader {
+                        needed: content_offset + version_header_size,
+                        available: buffer.len(),
+                    })?;
+                }
+                //Safety: buffer is large enough to hold version header
+                let version_header =
+                    unsafe { &*(buffer[content_offset..].as_ptr() as *const section::header::Version) };
+                let data = &buffer[content_offset + version_header_size..section_size];
+                (RawSectionMetaData::Version(*version_header), data)
+            }
+            FfsSectionRawType::FREEFORM_SUBTYPE_GUID => {
+                let freeform_header_size = mem::size_of::<section::header::FreeformSubtypeGuid>();
+                //verify that buffer has enough storage for a freeform header.
+                if buffer.len() < content_offset + freeform_header_size {
+                    Err(FwFsError::TruncatedHeader {
+                        needed: content_offset + freeform_header_size,
+                        available: buffer.len(),
+                    })?;
+                }
+                //Safety: buffer is large enough to hold freeform header
+                let freeform_header =
+                    unsafe { &*(buffer[content_offset..].as_ptr() as *const section::header::FreeformSubtypeGuid) };
+                let data = &buffer[content_offset + freeform_header_size..section_size];
+                (RawSectionMetaData::FreeformSubtypeGuid(*freeform_header), data)
+            }
+            FfsSectionRawType::OEM_MIN..=FfsSectionRawType::FFS_MAX => {
+                //these section types do not have a defined header. So set metadata to none, and set data to the entire section buffer.
+                (RawSectionMetaData::None, buffer)
+            }
+            _ => (RawSectionMetaData::None, &buffer[content_offset..section_size]),
+        };
+
+        Ok(Self {
+            section_type: section_header.section_type,
+            meta_data,
+            data,
+            section_size,
+            alignment: AlignmentPolicy::default(),
+        })
+    }
+
+    /// Returns the section type.
+    pub fn section_type(&self) -> Option<FfsSectionType> {
+        section_type_from_raw(self.section_type)
+    }
+
+    /// Returns the section type as a raw u8.
+    pub fn section_type_raw(&self) -> u8 {
+        self.section_type
+    }
+
+    /// Returns the section metadata.
+    pub fn meta_data(&self) -> &RawSectionMetaData<'a> {
+        &self.meta_data
+    }
+
+    /// Returns the section data.
+    ///
+    /// The data of a GUID-defined section with the
+    /// [`PROCESSING_REQUIRED`](FfsGuidedSectionAttribute::PROCESSING_REQUIRED) attribute is encoded, so an empty slice
+    /// is returned for such sections. Use [`RawSection::raw_section_data()`] to access the encoded data.
+    pub fn section_data(&self) -> &'a [u8] {
+        if self.requires_processing() {
+            &[]
+        } else {
+            self.data
+        }
+    }
+
+    /// Returns the section data as stored in the section, without regard to the GUID-defined section attributes.
+    pub fn raw_section_data(&self) -> &'a [u8] {
+        self.data
+    }
+
+    /// Indicates whether this is a GUID-defined section with the
+    /// [`PROCESSING_REQUIRED`](FfsGuidedSectionAttribute::PROCESSING_REQUIRED) attribute.
+    pub fn requires_processing(&self) -> bool {
+        matches!(&self.meta_data, RawSectionMetaData::GuidDefined(header, _)
+            if header.attributes & FfsGuidedSectionAttribute::PROCESSING_REQUIRED != 0)
+    }
+
+    /// Returns the size of the section, including the header.
+    pub fn section_size(&self) -> usize {
+        self.section_size
+    }
+
+    /// Returns an iterator over the sections held in the data of this encapsulation section.
+    ///
+    /// The data is only walked in place: compression sections must use
+    /// [`NOT_COMPRESSED`](crate::fw_fs::ffs::section::header::NOT_COMPRESSED) and GUID-defined sections must not
+    /// require processing. Returns `None` for other sections.
+    pub fn section_iter(&self) -> Option<RawSectionIterator<'a>> {
+        match &self.meta_data {
+            RawSectionMetaData::Compression(header) if header.compression_type == section::header::NOT_COMPRESSED => {
+                Some(RawSectionIterator::new(self.data, self.alignment))
+            }
+            RawSectionMetaData::GuidDefined(..) if !self.requires_processing() => {
+                Some(RawSectionIterator::new(self.data, self.alignment))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Iterator of the sections in a [`RawFile`] or encapsulation [`RawSection`].
+///
+/// A section that fails to parse is returned as an error and ends the iteration.
+#[derive(Debug, Clone)]
+pub struct RawSectionIterator<'a> {
+    buffer: &'a [u8],
+    alignment: AlignmentPolicy,
+    next_offset: usize,
+    error: bool,
+}
+
+impl<'a> RawSectionIterator<'a> {
+    fn new(buffer: &'a [u8], alignment: AlignmentPolicy) -> Self {
+        RawSectionIterator { buffer, alignment, next_offset: 0, error: false }
+    }
+}
+
+impl<'a> Iterator for RawSectionIterator<'a> {
+    type Item = Result<RawSection<'a>, FwFsError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.error || self.next_offset > self.buffer.len() {
+            return None;
+        }
+        if self.buffer[self.next_offset..].len() < mem::size_of::<section::Header>() {
+            return None;
+        }
+        let mut result = RawSection::new(&self.buffer[self.next_offset..]);
+        match result {
+            Ok(ref mut section) => {
+                section.alignment = self.alignment;
+                self.next_offset = self.alignment.align(self.buffer, self.next_offset + section.section_size(), 4)
+            }
+            Err(_) => self.error = true,
+        }
+        Some(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs, path::Path};
+
+    use super::*;
+    use crate::fw_fs::FirmwareVolume;
+
+    #[test]
+    fn raw_volumes_should_match_firmware_volumes() {
+        let root = Path::new(&env::var("CARGO_MANIFEST_DIR").unwrap()).join("test_resources");
+        for fv_name in ["DXEFV.Fv", "GIGANTOR.Fv", "FVMAIN_COMPACT.Fv"] {
+            let fv_bytes = fs::read(root.join(fv_name)).unwrap();
+            let fv = FirmwareVolume::new(&fv_bytes).unwrap();
+            let raw_fv = RawFirmwareVolume::new(&fv_bytes).unwrap();
+            assert!(raw_fv.block_map().eq(fv.block_map()));
+            assert_eq!(raw_fv.fv_name(), fv.fv_name());
+            assert_eq!(raw_fv.file_system(), fv.file_system());
+
+            let files = fv.file_iter().collect::<Result<Vec<_>, _>>().unwrap();
+            let raw_files = raw_fv.file_iter().collect::<Result<Vec<_>, _>>().unwrap();
+            assert_eq!(raw_files.len(), files.len());
+            for (raw_file, file) in raw_files.iter().zip(&files) {
+                assert_eq!(raw_file.name(), file.name());
+                assert_eq!(raw_file.file_type(), file.file_type());
+                assert_eq!(raw_file.data(), file.data());
+
+                // Files that do not hold sections, such as pad files, fail to parse in the same way.
+                let sections = file.section_iter().collect::<Vec<_>>();
+                let raw_sections = raw_file.section_iter().collect::<Vec<_>>();
+                assert_eq!(raw_sections.len(), sections.len());
+                for (raw_section, section) in raw_sections.into_iter().zip(sections) {
+                    match (raw_section, section) {
+                        (Ok(raw_section), Ok(section)) => {
+                            assert_eq!(raw_section.section_type_raw(), section.section_type_raw());
+                            assert_eq!(raw_section.section_size(), section.section_size());
+                            assert_eq!(raw_section.section_data(), section.section_data());
+                        }
+                        (raw_section, section) => {
+                            assert_eq!(raw_section.map(|_| ()).map_err(efi::Status::from), section.map(|_| ()))
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn uncompressed_sections_should_be_walked_in_place() {
+        // A compression section with no compression, holding a RAW section.
+        let mut buffer = vec![0x11, 0x00, 0x00, FfsSectionRawType::encapsulated::COMPRESSION];
+        buffer.extend_from_slice(&8u32.to_le_bytes());
+        buffer.push(section::header::NOT_COMPRESSED);
+        buffer.extend_from_slice(&[0x08, 0x00, 0x00, FfsSectionRawType::RAW, 1, 2, 3, 4]);
+
+        let section = RawSection::new(&buffer).unwrap();
+        let inner = section.section_iter().unwrap().collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(inner.len(), 1);
+        assert_eq!(inner[0].section_type(), Some(FfsSectionType::Raw));
+        assert_eq!(inner[0].section_data(), [1, 2, 3, 4]);
+
+        buffer[8] = section::header::STANDARD_COMPRESSION;
+        assert!(RawSection::new(&buffer).unwrap().section_iter().is_none());
+
+        assert_eq!(
+            RawSection::new(&buffer[..0x10]).unwrap_err(),
+            FwFsError::BadSectionSize { section_type: FfsSectionRawType::encapsulated::COMPRESSION, size: 0x11 }
+        );
+    }
+}
@@ -0,0 +1,357 @@
+//! Section Rebuilding
+//!
+//! Support for editing the contents of encapsulation sections: a GUID-defined section is expanded with a
+//! [`SectionExtractor`], the expanded sections are modified, and the section is rebuilt around the new contents with a
+//! [`SectionCompressor`]. This is typically used to modify a compressed FV nested in a FIRMWARE_VOLUME_IMAGE section.
+//!
+//! Compressors are pluggable in the same way as extractors: [`CompositeSectionCompressor`] combines several of them, so
+//! the builders do not depend on any single compression algorithm.
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+use alloc::{boxed::Box, vec::Vec};
+use core::mem;
+use r_efi::efi;
+
+use super::{section, EfiSectionType, FfsSectionRawType, Section, SectionExtractor, SectionMetaData};
+
+/// The GUID-specific header fields and data of a GUID-defined section, as produced by a [`SectionCompressor`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EncodedSection {
+    /// The GUID-specific header fields that follow the GUID-defined section header.
+    pub guid_specific: Box<[u8]>,
+    /// The section data.
+    pub data: Box<[u8]>,
+}
+
+/// Defines an interface that can be implemented to provide the encoding of GUID-defined sections, the reverse of a
+/// [`SectionExtractor`].
+pub trait SectionCompressor {
+    /// Encodes `content` for a GUID-defined section with the given header.
+    ///
+    /// Returns the encoded GUID-specific header fields and section data. Only the `section_definition_guid` and
+    /// `attributes` of `header` are meaningful; `data_offset` is recomputed when the section is built.
+    ///
+    /// If the compressor does not support the section definition GUID, it should return `UNSUPPORTED`.
+    fn compress(&self, header: &section::header::GuidDefined, content: &[u8]) -> Result<EncodedSection, efi::Status>;
+
+    /// Compresses `content` for a Compression section with the given compression type.
+    ///
+    /// If the compressor does not support the compression type, it should return `UNSUPPORTED`, which is the default.
+    fn compress_standard(&self, compression_type: u8, content: &[u8]) -> Result<Box<[u8]>, efi::Status> {
+        let _ = (compression_type, content);
+        Err(efi::Status::UNSUPPORTED)
+    }
+}
+
+/// A [`SectionCompressor`] that delegates to a list of compressors.
+///
+/// Each compressor is invoked in turn, and the first one that does not return `UNSUPPORTED` determines the result. If
+/// no compressor supports the section, `UNSUPPORTED` is returned.
+///
+/// ## Example
+///```
+/// use mu_pi::fw_fs::rebuild::{CompositeSectionCompressor, SectionCompressor};
+///
+/// let compressors: [&dyn SectionCompressor; 0] = [];
+/// let compressor = CompositeSectionCompressor::new(&compressors);
+///```
+pub struct CompositeSectionCompressor<'a> {
+    compressors: &'a [&'a dyn SectionCompressor],
+}
+
+impl<'a> CompositeSectionCompressor<'a> {
+    /// Creates a new composite compressor over the given compressors.
+    pub fn new(compressors: &'a [&'a dyn SectionCompressor]) -> Self {
+        Self { compressors }
+    }
+}
+
+impl SectionCompressor for CompositeSectionCompressor<'_> {
+    fn compress(&self, header: &section::header::GuidDefined, content: &[u8]) -> Result<EncodedSection, efi::Status> {
+        for compressor in self.compressors {
+            match compressor.compress(header, content) {
+                Err(efi::Status::UNSUPPORTED) => continue,
+                result => return result,
+            }
+        }
+        Err(efi::Status::UNSUPPORTED)
+    }
+
+    fn compress_standard(&self, compression_type: u8, content: &[u8]) -> Result<Box<[u8]>, efi::Status> {
+        for compressor in self.compressors {
+            match compressor.compress_standard(compression_type, content) {
+                Err(efi::Status::UNSUPPORTED) => continue,
+                result => return result,
+            }
+        }
+        Err(efi::Status::UNSUPPORTED)
+    }
+}
+
+/// Builds a section of the given type from its type-specific header fields (if any) and data.
+///
+/// The extended section header is used when the section is too large for the 24-bit size field.
+pub fn build_section(section_type: EfiSectionType, header_fields: &[u8], data: &[u8]) -> Vec<u8> {
+    let standard_size = mem::size_of::<section::Header>() + header_fields.len() + data.len();
+    let mut buffer = Vec::with_capacity(standard_size + mem::size_of::<u32>());
+    if standard_size < 0xffffff {
+        buffer.extend_from_slice(&(standard_size as u32).to_le_bytes()[..3]);
+        buffer.push(section_type);
+    } else {
+        buffer.extend_from_slice(&[0xff, 0xff, 0xff, section_type]);
+        buffer.extend_from_slice(&((standard_size + mem::size_of::<u32>()) as u32).to_le_bytes());
+    }
+    buffer.extend_from_slice(header_fields);
+    buffer.extend_from_slice(data);
+    buffer
+}
+
+/// Builds a Compression section around `content`.
+///
+/// Content with [`NOT_COMPRESSED`](section::header::NOT_COMPRESSED) type is stored as-is, other compression types are
+/// compressed with `compressor`. Returns `BAD_BUFFER_SIZE` if `content` is too large for the uncompressed length field.
+pub fn build_compression_section(
+    compression_type: u8,
+    content: &[u8],
+    compressor: &dyn SectionCompressor,
+) -> Result<Vec<u8>, efi::Status> {
+    let uncompressed_length = u32::try_from(content.len()).map_err(|_| efi::Status::BAD_BUFFER_SIZE)?;
+    let compressed = match compression_type {
+        section::header::NOT_COMPRESSED => Box::from(content),
+        _ => compressor.compress_standard(compression_type, content)?,
+    };
+
+    let mut header_fields = Vec::with_capacity(mem::size_of::<section::header::Compression>());
+    header_fields.extend_from_slice(&uncompressed_length.to_le_bytes());
+    header_fields.push(compression_type);
+    Ok(build_section(FfsSectionRawType::encapsulated::COMPRESSION, &header_fields, &compressed))
+}
+
+/// Builds a GUID-defined section around `content`, which is encoded with `compressor`.
+pub fn build_guid_defined_section(
+    section_definition_guid: efi::Guid,
+    attributes: u16,
+    content: &[u8],
+    compressor: &dyn SectionCompressor,
+) -> Result<Vec<u8>, efi::Status> {
+    let mut header = section::header::GuidDefined { section_definition_guid, data_offset: 0, attributes };
+    let EncodedSection { guid_specific, data } = compressor.compress(&header, content)?;
+
+    let header_size = mem::size_of::<section::header::GuidDefined>() + guid_specific.len();
+    let mut common_header_size = mem::size_of::<section::Header>();
+    if common_header_size + header_size + data.len() >= 0xffffff {
+        common_header_size += mem::size_of::<u32>();
+    }
+    header.data_offset = u16::try_from(common_header_size + header_size).map_err(|_| efi::Status::BAD_BUFFER_SIZE)?;
+
+    let mut header_fields = Vec::with_capacity(header_size);
+    header_fields.extend_from_slice(header.section_definition_guid.as_bytes());
+    header_fields.extend_from_slice(&header.data_offset.to_le_bytes());
+    header_fields.extend_from_slice(&header.attributes.to_le_bytes());
+    header_fields.extend_from_slice(&guid_specific);
+    Ok(build_section(FfsSectionRawType::encapsulated::GUID_DEFINED, &header_fields, &data))
+}
+
+/// Expands a GUID-defined section with `extractor`, passes the expanded sections to `modify`, and rebuilds the section
+/// around the modified contents with `compressor`, keeping the section definition GUID and attributes.
+///
+/// The expanded contents are the sections held in the encapsulation section, e.g. a FIRMWARE_VOLUME_IMAGE section
+/// whose FV can be modified in place. Returns `INVALID_PARAMETER` if `section` is not a GUID-defined section, and
+/// `UNSUPPORTED` if `extractor` does not support it.
+///
+/// ## Example
+///```
+/// use mu_pi::fw_fs::{rebuild::{rebuild_guid_defined_section, SectionCompressor}, Section, SectionExtractor};
+/// use r_efi::efi;
+///
+/// fn patch_byte(
+///   section: &Section,
+///   extractor: &dyn SectionExtractor,
+///   compressor: &dyn SectionCompressor,
+/// ) -> Result<Vec<u8>, efi::Status> {
+///   rebuild_guid_defined_section(section, extractor, compressor, &mut |content| {
+///     // content holds the encapsulated sections, e.g. a FIRMWARE_VOLUME_IMAGE section.
+///     *content.last_mut().ok_or(efi::Status::BAD_BUFFER_SIZE)? ^= 0xff;
+///     Ok(())
+///   })
+/// }
+///```
+pub fn rebuild_guid_defined_section(
+    section: &Section,
+    extractor: &dyn SectionExtractor,
+    compressor: &dyn SectionCompressor,
+    modify: &mut dyn FnMut(&mut Vec<u8>) -> Result<(), efi::Status>,
+) -> Result<Vec<u8>, efi::Status> {
+    let SectionMetaData::GuidDefined(header, _) = section.meta_data() else {
+        return Err(efi::Status::INVALID_PARAMETER);
+    };
+    let extracted = extractor.extract(section)?;
+    if extracted.is_empty() {
+        Err(efi::Status::UNSUPPORTED)?;
+    }
+
+    let mut content = extracted.into_vec();
+    modify(&mut content)?;
+    build_guid_defined_section(header.section_definition_guid, header.attributes, &content, compressor)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fw_fs::{FfsSectionType, FirmwareVolume};
+    use std::{env, fs, path::Path};
+
+    // Stores the content as-is, with the content length as GUID-specific header field.
+    struct IdentityCodec {}
+
+    const IDENTITY_GUID: efi::Guid =
+        efi::Guid::from_fields(0x5a1e5a1e, 0x1234, 0x5678, 0x9a, 0xbc, &[0xde, 0xf0, 0x12, 0x34, 0x56, 0x78]);
+
+    impl SectionExtractor for IdentityCodec {
+        fn extract(&self, section: &Section) -> Result<Box<[u8]>, efi::Status> {
+            match section.meta_data() {
+                SectionMetaData::GuidDefined(header, guid_specific)
+                    if header.section_definition_guid == IDENTITY_GUID =>
+                {
+                    assert_eq!(guid_specific[..], (section.raw_section_data().len() as u32).to_le_bytes());
+                    Ok(Box::from(section.raw_section_data()))
+                }
+                _ => Ok(Box::new([0u8; 0])),
+            }
+        }
+    }
+
+    impl SectionCompressor for IdentityCodec {
+        fn compress(
+            &self,
+            header: &section::header::GuidDefined,
+            content: &[u8],
+        ) -> Result<EncodedSection, efi::Status> {
+            if header.section_definition_guid != IDENTITY_GUID {
+                Err(efi::Status::UNSUPPORTED)?;
+            }
+            Ok(EncodedSection {
+                guid_specific: Box::from((content.len() as u32).to_le_bytes()),
+                data: Box::from(content),
+            })
+        }
+    }
+
+    #[test]
+    fn nested_volume_should_round_trip() {
+        let root = Path::new(&env::var("CARGO_MANIFEST_DIR").unwrap()).join("test_resources");
+        let fv_bytes = fs::read(root.join("DXEFV.Fv")).unwrap();
+
+        let fv_section = build_section(FfsSectionRawType::FIRMWARE_VOLUME_IMAGE, &[], &fv_bytes);
+        let buffer = build_guid_defined_section(IDENTITY_GUID, 0x01, &fv_section, &IdentityCodec {}).unwrap();
+        let section = Section::new(&buffer).unwrap();
+        assert_eq!(section.section_size(), buffer.len());
+
+        // An unmodified rebuild is identical.
+        let rebuilt =
+            rebuild_guid_defined_section(&section, &IdentityCodec {}, &IdentityCodec {}, &mut |_| Ok(())).unwrap();
+        assert_eq!(rebuilt, buffer);
+
+        // Replace the nested FV with a different one, and check the sizes are updated.
+        let replacement = fs::read(root.join("FVMAIN_COMPACT.Fv")).unwrap();
+        let file_count = FirmwareVolume::new(&replacement).unwrap().file_iter().count();
+        let rebuilt = rebuild_guid_defined_section(&section, &IdentityCodec {}, &IdentityCodec {}, &mut |content| {
+            *content = build_section(FfsSectionRawType::FIRMWARE_VOLUME_IMAGE, &[], &replacement);
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(rebuilt.len(), buffer.len() - fv_bytes.len() + replacement.len());
+
+        let section = Section::new(&rebuilt).unwrap();
+        assert_eq!(section.section_size(), rebuilt.len());
+        let content = IdentityCodec {}.extract(&section).unwrap();
+        let nested = Section::new(&content).unwrap();
+        assert_eq!(nested.section_type(), Some(FfsSectionType::FirmwareVolumeImage));
+        assert_eq!(nested.firmware_volume().unwrap().file_iter().count(), file_count);
+
+        // Sections that are not GUID-defined, or that the extractor does not support, cannot be rebuilt.
+        assert_eq!(
+            rebuild_guid_defined_section(&nested, &IdentityCodec {}, &IdentityCodec {}, &mut |_| Ok(())),
+            Err(efi::Status::INVALID_PARAMETER)
+        );
+    }
+
+    // Supports the identity "compression" type only.
+    struct IdentityStandardCompressor {}
+
+    const IDENTITY_COMPRESSION: u8 = 0x7f;
+
+    impl SectionCompressor for IdentityStandardCompressor {
+        fn compress(
+            &self,
+            _header: &section::header::GuidDefined,
+            _content: &[u8],
+        ) -> Result<EncodedSection, efi::Status> {
+            Err(efi::Status::UNSUPPORTED)
+        }
+
+        fn compress_standard(&self, compression_type: u8, content: &[u8]) -> Result<Box<[u8]>, efi::Status> {
+            if compression_type != IDENTITY_COMPRESSION {
+                Err(efi::Status::UNSUPPORTED)?;
+            }
+            Ok(Box::from(content))
+        }
+    }
+
+    #[test]
+    fn composite_compressor_should_dispatch() {
+        let compressors: [&dyn SectionCompressor; 2] = [&IdentityStandardCompressor {}, &IdentityCodec {}];
+        let compressor = CompositeSectionCompressor::new(&compressors);
+        let content = [0xa5u8; 0x20];
+
+        let buffer = build_guid_defined_section(IDENTITY_GUID, 0, &content, &compressor).unwrap();
+        let section = Section::new(&buffer).unwrap();
+        assert_eq!(IdentityCodec {}.extract(&section).unwrap()[..], content);
+
+        let buffer = build_compression_section(IDENTITY_COMPRESSION, &content, &compressor).unwrap();
+        let section = Section::new(&buffer).unwrap();
+        match section.meta_data() {
+            SectionMetaData::Compression(header) => {
+                let length = header.uncompressed_length;
+                assert_eq!(length as usize, content.len());
+                assert_eq!(header.compression_type, IDENTITY_COMPRESSION);
+            }
+            otherwise_bad => panic!("invalid section: {:x?}", otherwise_bad),
+        }
+        assert_eq!(section.section_data(), content);
+
+        // Uncompressed content does not need a compressor.
+        let buffer = build_compression_section(section::header::NOT_COMPRESSED, &content, &IdentityCodec {}).unwrap();
+        assert_eq!(Section::new(&buffer).unwrap().section_data(), content);
+
+        // Unsupported GUIDs and compression types are reported.
+        assert_eq!(
+            build_guid_defined_section(efi::Guid::from_bytes(&[0; 16]), 0, &content, &compressor),
+            Err(efi::Status::UNSUPPORTED)
+        );
+        assert_eq!(
+            build_compression_section(section::header::STANDARD_COMPRESSION, &content, &compressor),
+            Err(efi::Status::UNSUPPORTED)
+        );
+    }
+
+    #[test]
+    fn large_sections_should_use_extended_header() {
+        let data = vec![0x5au8; 0x1000000];
+        let buffer = build_section(FfsSectionRawType::RAW, &[], &data);
+        assert_eq!(buffer.len(), data.len() + 8);
+        let section = Section::new(&buffer).unwrap();
+        assert_eq!(section.section_size(), buffer.len());
+        assert_eq!(section.section_data().len(), data.len());
+
+        let buffer = build_guid_defined_section(IDENTITY_GUID, 0, &data, &IdentityCodec {}).unwrap();
+        let section = Section::new(&buffer).unwrap();
+        assert_eq!(IdentityCodec {}.extract(&section).unwrap().len(), data.len());
+    }
+}
@@ -0,0 +1,357 @@
+//! Firmware Volume Reproducibility
+//!
+//! Support for checking that firmware volume images are built reproducibly:
+//! - [`compare_volumes`] compares two images of the same volume and explains each difference at the level of the
+//!   header field, file or free space region that differs.
+//! - [`check_volume`] reports the parts of a single image that commonly make builds non-reproducible: non-zero
+//!   reserved fields and free space that does not hold the erase byte.
+//! - [`normalize_volume`] puts an image in a deterministic form by zeroing the reserved fields and filling free space
+//!   with the erase byte, so images that only differ in those parts become byte-for-byte identical.
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+use alloc::vec::Vec;
+use core::{fmt, ops::Range};
+use r_efi::efi;
+
+use super::{fv::header_offset, File, FirmwareVolume, FwFsError};
+use crate::{checksum, parse::ParseOptions};
+
+// Name, offset and size of the fields of the firmware volume header that precede the block map.
+const FV_HEADER_FIELDS: [(&str, usize, usize); 10] = [
+    ("zero_vector", 0, 16),
+    ("file_system_guid", 16, 16),
+    ("fv_length", 32, 8),
+    ("signature", 40, 4),
+    ("attributes", 44, 4),
+    ("header_length", 48, 2),
+    ("checksum", 50, 2),
+    ("ext_header_offset", 52, 2),
+    ("reserved", 54, 1),
+    ("revision", 55, 1),
+];
+
+// Name, offset and size of the fields of the FFS file header.
+const FILE_HEADER_FIELDS: [(&str, usize, usize); 7] = [
+    ("name", 0, 16),
+    ("header_checksum", 16, 1),
+    ("file_checksum", 17, 1),
+    ("type", 18, 1),
+    ("attributes", 19, 1),
+    ("size", 20, 3),
+    ("state", 23, 1),
+];
+const FILE_EXTENDED_SIZE_FIELD: (&str, usize, usize) = ("extended_size", 24, 8);
+
+/// A reproducibility difference between two firmware volume images, or a reproducibility hazard in a single image.
+///
+/// Offsets are relative to the start of the (first) image.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Difference {
+    /// A field of the firmware volume header differs. The block map and extended header are reported as the
+    /// `block_map` and `ext_header` fields.
+    VolumeHeader { field: &'static str, offset: usize },
+    /// The volumes hold a different number of files.
+    FileCount { left: usize, right: usize },
+    /// The files at the same position in the volumes have different names, i.e. the file order differs.
+    FileOrder { index: usize, left: efi::Guid, right: efi::Guid },
+    /// The files with the same name are at different offsets in the volumes.
+    FileOffset { name: efi::Guid, left: usize, right: usize },
+    /// A field of a file header differs.
+    FileHeader { name: efi::Guid, field: &'static str, offset: usize },
+    /// The content of a file differs, starting at `offset`.
+    FileContent { name: efi::Guid, offset: usize },
+    /// The free space before, between or after the files differs, starting at `offset`.
+    FreeSpace { offset: usize },
+    /// A reserved field is not zero.
+    NonZeroReserved { field: &'static str, offset: usize },
+    /// The free space starting at `offset` does not hold the erase byte of the volume.
+    UnerasedFreeSpace { offset: usize },
+}
+
+impl fmt::Display for Difference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Difference::VolumeHeader { field, offset } => {
+                write!(f, "firmware volume header field {field} at offset {offset:#x} differs")
+            }
+            Difference::FileCount { left, right } => write!(f, "volumes hold {left} and {right} files"),
+            Difference::FileOrder { index, left, right } => {
+                write!(f, "file {index} is {left:?} in one volume and {right:?} in the other")
+            }
+            Difference::FileOffset { name, left, right } => {
+                write!(f, "file {name:?} is at offset {left:#x} in one volume and {right:#x} in the other")
+            }
+            Difference::FileHeader { name, field, offset } => {
+                write!(f, "file {name:?} header field {field} at offset {offset:#x} differs")
+            }
+            Difference::FileContent { name, offset } => {
+                write!(f, "file {name:?} content differs at offset {offset:#x}")
+            }
+            Difference::FreeSpace { offset } => write!(f, "free space differs at offset {offset:#x}"),
+            Difference::NonZeroReserved { field, offset } => {
+                write!(f, "reserved field {field} at offset {offset:#x} is not zero")
+            }
+            Difference::UnerasedFreeSpace { offset } => {
+                write!(f, "free space at offset {offset:#x} does not hold the erase byte")
+            }
+        }
+    }
+}
+
+// The files of a volume with their offsets, and the free space regions around them.
+struct Layout<'a> {
+    files: Vec<(usize, File<'a>)>,
+    free_space: Vec<Range<usize>>,
+}
+
+impl<'a> Layout<'a> {
+    fn new(fv: &FirmwareVolume<'a>) -> Result<Self, FwFsError> {
+        let base = fv.data().as_ptr() as usize;
+        let mut files = Vec::new();
+        let mut free_space = Vec::new();
+        let mut free_start = fv.data_offset;
+        for file in fv.try_file_iter() {
+            let file = file?;
+            let offset = file.data().as_ptr() as usize - base;
+            if offset > free_start {
+                free_space.push(free_start..offset);
+            }
+            free_start = offset + file.size() as usize;
+            files.push((offset, file));
+        }
+        let end = fv.size() as usize;
+        if end > free_start {
+            free_space.push(free_start..end);
+        }
+        Ok(Self { files, free_space })
+    }
+}
+
+// Returns the offset of the first byte that differs between the slices, if any.
+fn first_difference(left: &[u8], right: &[u8]) -> Option<usize> {
+    match left.iter().zip(right).position(|(l, r)| l != r) {
+        Some(position) => Some(position),
+        None if left.len() != right.len() => Some(left.len().min(right.len())),
+        None => None,
+    }
+}
+
+/// Compares two images of a firmware volume and returns their differences.
+///
+/// An empty result means the images are byte-for-byte identical. Files are matched by position, so a different file
+/// order is reported as [`Difference::FileOrder`] rather than as content differences. Returns the error of the first
+/// image that is not a valid firmware volume.
+///
+/// ## Example
+///```
+/// # use std::{env, fs, path::Path, error::Error};
+/// use mu_pi::fw_fs::reproducibility::compare_volumes;
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// # let root = Path::new(&env::var("CARGO_MANIFEST_DIR")?).join("test_resources");
+/// # let first_build = fs::read(root.join("DXEFV.Fv"))?;
+/// # let second_build = first_build.clone();
+/// for difference in compare_volumes(&first_build, &second_build).expect("Firmware Volume Corrupt") {
+///   println!("{difference}");
+/// }
+/// # Ok(())
+/// # }
+///```
+pub fn compare_volumes(left: &[u8], right: &[u8]) -> Result<Vec<Difference>, FwFsError> {
+    let left_fv = FirmwareVolume::try_new(left, &ParseOptions::default())?;
+    let right_fv = FirmwareVolume::try_new(right, &ParseOptions::default())?;
+    let mut differences = Vec::new();
+
+    for (field, offset, size) in FV_HEADER_FIELDS {
+        if left[offset..offset + size] != right[offset..offset + size] {
+            differences.push(Difference::VolumeHeader { field, offset });
+        }
+    }
+    let left_header_length = u16::from_le_bytes([left[48], left[49]]) as usize;
+    let right_header_length = u16::from_le_bytes([right[48], right[49]]) as usize;
+    if left[header_offset::BLOCK_MAP..left_header_length] != right[header_offset::BLOCK_MAP..right_header_length] {
+        differences.push(Difference::VolumeHeader { field: "block_map", offset: header_offset::BLOCK_MAP });
+    }
+    if left[left_header_length..left_fv.data_offset] != right[right_header_length..right_fv.data_offset] {
+        differences.push(Difference::VolumeHeader { field: "ext_header", offset: left_header_length });
+    }
+
+    let left_layout = Layout::new(&left_fv)?;
+    let right_layout = Layout::new(&right_fv)?;
+    if left_layout.files.len() != right_layout.files.len() {
+        differences.push(Difference::FileCount { left: left_layout.files.len(), right: right_layout.files.len() });
+    }
+    for (index, ((left_offset, left_file), (right_offset, right_file))) in
+        left_layout.files.iter().zip(&right_layout.files).enumerate()
+    {
+        let name = left_file.name();
+        if name != right_file.name() {
+            differences.push(Difference::FileOrder { index, left: name, right: right_file.name() });
+            continue;
+        }
+        if left_offset != right_offset {
+            differences.push(Difference::FileOffset { name, left: *left_offset, right: *right_offset });
+        }
+
+        let extended_size =
+            (left_file.header_size > FILE_EXTENDED_SIZE_FIELD.1).then_some(FILE_EXTENDED_SIZE_FIELD).into_iter();
+        for (field, offset, size) in FILE_HEADER_FIELDS.into_iter().chain(extended_size) {
+            if left_file.data().get(offset..offset + size) != right_file.data().get(offset..offset + size) {
+                differences.push(Difference::FileHeader { name, field, offset: left_offset + offset });
+            }
+        }
+        if let Some(position) = first_difference(left_file.content(), right_file.content()) {
+            differences.push(Difference::FileContent { name, offset: left_offset + left_file.header_size + position });
+        }
+    }
+
+    for (left_range, right_range) in left_layout.free_space.iter().zip(&right_layout.free_space) {
+        if let Some(position) = first_difference(&left[left_range.clone()], &right[right_range.clone()]) {
+            differences.push(Difference::FreeSpace { offset: left_range.start + position });
+        }
+    }
+    Ok(differences)
+}
+
+/// Checks a firmware volume image for parts that commonly make builds non-reproducible.
+///
+/// Reports non-zero reserved fields as [`Difference::NonZeroReserved`] and free space that does not hold the erase
+/// byte as [`Difference::UnerasedFreeSpace`]. Such images can be fixed with [`normalize_volume`].
+pub fn check_volume(buffer: &[u8]) -> Result<Vec<Difference>, FwFsError> {
+    let fv = FirmwareVolume::try_new(buffer, &ParseOptions::default())?;
+    let mut differences = Vec::new();
+
+    if buffer[header_offset::RESERVED] != 0 {
+        differences.push(Difference::NonZeroReserved { field: "reserved", offset: header_offset::RESERVED });
+    }
+    for range in Layout::new(&fv)?.free_space {
+        if let Some(position) = buffer[range.clone()].iter().position(|&x| x != fv.erase_byte) {
+            differences.push(Difference::UnerasedFreeSpace { offset: range.start + position });
+        }
+    }
+    Ok(differences)
+}
+
+/// Puts a firmware volume image in a deterministic form.
+///
+/// Zeroes the reserved field of the volume header (updating the header checksum) and fills the free space before,
+/// between and after the files with the erase byte of the volume. After normalization, [`check_volume`] reports no
+/// differences.
+///
+/// The deterministic mode of [`FirmwareVolumeBuilder`](super::builder::FirmwareVolumeBuilder::deterministic) builds
+/// images in this form.
+pub fn normalize_volume(buffer: &mut [u8]) -> Result<(), FwFsError> {
+    let (free_space, erase_byte, header_length) = {
+        let fv = FirmwareVolume::try_new(buffer, &ParseOptions::default())?;
+        let header_length = u16::from_le_bytes([buffer[48], buffer[49]]) as usize;
+        (Layout::new(&fv)?.free_space, fv.erase_byte, header_length)
+    };
+
+    for range in free_space {
+        buffer[range].fill(erase_byte);
+    }
+
+    if buffer[header_offset::RESERVED] != 0 {
+        buffer[header_offset::RESERVED] = 0;
+        buffer[header_offset::CHECKSUM..header_offset::CHECKSUM + 2].fill(0);
+        let checksum = checksum::compute16(&buffer[..header_length]);
+        buffer[header_offset::CHECKSUM..header_offset::CHECKSUM + 2].copy_from_slice(&checksum.to_le_bytes());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{env, fs, path::Path};
+
+    #[test]
+    fn identical_volumes_should_have_no_differences() {
+        let root = Path::new(&env::var("CARGO_MANIFEST_DIR").unwrap()).join("test_resources");
+        let fv_bytes = fs::read(root.join("DXEFV.Fv")).unwrap();
+        assert_eq!(compare_volumes(&fv_bytes, &fv_bytes.clone()).unwrap(), []);
+        assert_eq!(check_volume(&fv_bytes).unwrap(), []);
+    }
+
+    #[test]
+    fn invalid_volumes_should_be_rejected() {
+        let root = Path::new(&env::var("CARGO_MANIFEST_DIR").unwrap()).join("test_resources");
+        let fv_bytes = fs::read(root.join("DXEFV.Fv")).unwrap();
+        let mut corrupted = fv_bytes.clone();
+        corrupted[header_offset::CHECKSUM] ^= 1;
+
+        assert_eq!(compare_volumes(&fv_bytes, &corrupted), Err(FwFsError::BadHeaderChecksum));
+        assert_eq!(check_volume(&corrupted), Err(FwFsError::BadHeaderChecksum));
+        assert_eq!(normalize_volume(&mut corrupted), Err(FwFsError::BadHeaderChecksum));
+    }
+
+    #[test]
+    fn file_content_difference_should_be_located() {
+        let root = Path::new(&env::var("CARGO_MANIFEST_DIR").unwrap()).join("test_resources");
+        let fv_bytes = fs::read(root.join("DXEFV.Fv")).unwrap();
+        let fv = FirmwareVolume::new(&fv_bytes).unwrap();
+        let layout = Layout::new(&fv).unwrap();
+        let (offset, file) = &layout.files[1];
+
+        // Modify two content bytes so that the file checksum is unchanged.
+        let content_offset = offset + file.header_size + 4;
+        let mut modified = fv_bytes.clone();
+        modified[content_offset] = modified[content_offset].wrapping_add(1);
+        modified[content_offset + 1] = modified[content_offset + 1].wrapping_sub(1);
+        assert_eq!(
+            compare_volumes(&fv_bytes, &modified).unwrap(),
+            [Difference::FileContent { name: file.name(), offset: content_offset }]
+        );
+
+        // A file with a different name (keeping the header checksum valid) is reported as a file order difference.
+        let mut renamed = fv_bytes.clone();
+        renamed[*offset] = renamed[*offset].wrapping_add(1);
+        renamed[offset + 1] = renamed[offset + 1].wrapping_sub(1);
+        let right = FirmwareVolume::new(&renamed).unwrap().file_iter().nth(1).unwrap().unwrap().name();
+        assert_eq!(
+            compare_volumes(&fv_bytes, &renamed).unwrap(),
+            [Difference::FileOrder { index: 1, left: file.name(), right }]
+        );
+    }
+
+    #[test]
+    fn normalize_should_make_volume_deterministic() {
+        let root = Path::new(&env::var("CARGO_MANIFEST_DIR").unwrap()).join("test_resources");
+        let fv_bytes = fs::read(root.join("DXEFV.Fv")).unwrap();
+        let fv = FirmwareVolume::new(&fv_bytes).unwrap();
+        let free_space = Layout::new(&fv).unwrap().free_space;
+        let free_offset = free_space.last().unwrap().start;
+
+        // Set the reserved field (keeping the header checksum valid) and write to the free space.
+        let mut modified = fv_bytes.clone();
+        modified[header_offset::RESERVED] = 1;
+        let checksum = u16::from_le_bytes([modified[50], modified[51]]).wrapping_sub(1);
+        modified[50..52].copy_from_slice(&checksum.to_le_bytes());
+        modified[free_offset] ^= 0x5a;
+
+        assert_eq!(
+            check_volume(&modified).unwrap(),
+            [
+                Difference::NonZeroReserved { field: "reserved", offset: header_offset::RESERVED },
+                Difference::UnerasedFreeSpace { offset: free_offset }
+            ]
+        );
+        assert_eq!(
+            compare_volumes(&fv_bytes, &modified).unwrap(),
+            [
+                Difference::VolumeHeader { field: "checksum", offset: 50 },
+                Difference::VolumeHeader { field: "reserved", offset: header_offset::RESERVED },
+                Difference::FreeSpace { offset: free_offset }
+            ]
+        );
+
+        normalize_volume(&mut modified).unwrap();
+        assert_eq!(check_volume(&modified).unwrap(), []);
+        assert_eq!(compare_volumes(&fv_bytes, &modified).unwrap(), []);
+    }
+}
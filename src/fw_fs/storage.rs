@@ -0,0 +1,429 @@
+//! Firmware Volume Storage
+//!
+//! [`FirmwareVolume`] parses a firmware volume that is mapped in memory as one contiguous buffer. [`FvStorage`]
+//! abstracts storage that can only be read at an offset, such as a SPI flash accessor or an FVB protocol instance, and
+//! [`StorageFirmwareVolume`] walks the files of a firmware volume on such storage. Only the FV headers, the file
+//! headers and the files that are returned are read, using the same parsing code as [`FirmwareVolume`].
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+use alloc::{boxed::Box, vec, vec::Vec};
+use core::{fmt, mem, ptr};
+use r_efi::efi;
+
+use super::{
+    ffs::file, file_header_size_and_length, fv, invalid_file_length, EfiFvbAttributes2, FfsFileSystem, File,
+    FirmwareVolume, FirmwareVolumeOwned, FvbAttributes2, FwFsError,
+};
+use crate::{address_helper::align_up, parse::ParseOptions};
+
+/// Storage holding a firmware volume that can be read at an offset.
+///
+/// The firmware volume starts at offset 0 of the storage.
+pub trait FvStorage {
+    /// Returns the size of the storage in bytes.
+    fn size(&self) -> usize;
+
+    /// Fills `buffer` with the bytes of the storage starting at `offset`.
+    ///
+    /// Returns `INVALID_PARAMETER` if the range extends beyond the end of the storage, or the error of the underlying
+    /// device.
+    fn read_at(&self, offset: usize, buffer: &mut [u8]) -> Result<(), efi::Status>;
+}
+
+impl FvStorage for [u8] {
+    fn size(&self) -> usize {
+        self.len()
+    }
+
+    fn read_at(&self, offset: usize, buffer: &mut [u8]) -> Result<(), efi::Status> {
+        let end = offset.checked_add(buffer.len()).ok_or(efi::Status::INVALID_PARAMETER)?;
+        buffer.copy_from_slice(self.get(offset..end).ok_or(efi::Status::INVALID_PARAMETER)?);
+        Ok(())
+    }
+}
+
+fn read_vec<S: FvStorage + ?Sized>(storage: &S, offset: usize, length: usize) -> Result<Vec<u8>, StorageError> {
+    let mut buffer = vec![0u8; length];
+    storage.read_at(offset, &mut buffer).map_err(|status| StorageError::Read { offset, length, status })?;
+    Ok(buffer)
+}
+
+/// Errors returned while reading a firmware volume from [`FvStorage`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum StorageError {
+    /// Reading `length` bytes at `offset` of the storage failed with the given status.
+    Read { offset: usize, length: usize, status: efi::Status },
+    /// The firmware volume, or a file in it, is malformed.
+    Malformed(FwFsError),
+}
+
+impl fmt::Display for StorageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StorageError::Read { offset, length, status } => {
+                write!(f, "reading {length:#x} bytes at offset {offset:#x} failed with {status:?}")
+            }
+            StorageError::Malformed(error) => write!(f, "{error}"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for StorageError {}
+
+impl From<FwFsError> for StorageError {
+    fn from(error: FwFsError) -> Self {
+        StorageError::Malformed(error)
+    }
+}
+
+impl From<StorageError> for efi::Status {
+    fn from(error: StorageError) -> Self {
+        match error {
+            StorageError::Read { status, .. } => status,
+            StorageError::Malformed(error) => error.into(),
+        }
+    }
+}
+
+/// A firmware volume on [`FvStorage`].
+///
+/// The FV headers are read and validated on creation, in the same way as by [`FirmwareVolume::new`]. Files are read
+/// as they are iterated.
+///
+/// ## Example
+///```
+/// # use std::{env, fs, path::Path, error::Error};
+/// use mu_pi::fw_fs::storage::StorageFirmwareVolume;
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// # let root = Path::new(&env::var("CARGO_MANIFEST_DIR")?).join("test_resources");
+/// # let fv_bytes = fs::read(root.join("DXEFV.Fv"))?;
+/// // Any FvStorage implementation can be used, such as a flash accessor. Byte slices implement FvStorage.
+/// let fv = StorageFirmwareVolume::new(fv_bytes.as_slice()).expect("Firmware Volume Corrupt");
+/// for file in fv.file_iter() {
+///   let file = file.expect("File Corrupt");
+///   println!("{:#x}: {:?}", file.offset(), file.file().name());
+/// }
+/// # Ok(())
+/// # }
+///```
+pub struct StorageFirmwareVolume<'s, S: FvStorage + ?Sized> {
+    storage: &'s S,
+    fv_length: usize,
+    attributes: EfiFvbAttributes2,
+    block_map: Vec<fv::BlockMapEntry>,
+    fv_name: Option<efi::Guid>,
+    data_offset: usize,
+    erase_byte: u8,
+    file_system: FfsFileSystem,
+    options: ParseOptions,
+}
+
+impl<'s, S: FvStorage + ?Sized> StorageFirmwareVolume<'s, S> {
+    /// Reads and validates the headers of the firmware volume on the given storage.
+    pub fn new(storage: &'s S) -> Result<Self, StorageError> {
+        Self::new_with_options(storage, &ParseOptions::default())
+    }
+
+    /// Reads and validates the headers of the firmware volume on the given storage using the given parse options.
+    ///
+    /// The options are also used to parse the files returned by [`StorageFirmwareVolume::file_iter()`].
+    pub fn new_with_options(storage: &'s S, options: &ParseOptions) -> Result<Self, StorageError> {
+        //storage must be large enough to hold the header structure.
+        if storage.size() < mem::size_of::<fv::Header>() {
+            Err(FwFsError::BufferTooSmall { needed: mem::size_of::<fv::Header>(), available: storage.size() })?;
+        }
+        let header = read_vec(storage, 0, mem::size_of::<fv::Header>())?;
+        //Safety: header is large enough to contain the header structure.
+        let fv_header = unsafe { ptr::read_unaligned(header.as_ptr() as *const fv::Header) };
+
+        // Read the FV header including the block map, and the ext header if there is one, so that FirmwareVolume can
+        // validate them. Anything that does not fit in the storage is left for the validation to reject.
+        let mut headers_size = fv_header.header_length as usize;
+        let ext_header_offset = fv_header.ext_header_offset as usize;
+        if fv_header.revision >= 2
+            && ext_header_offset != 0
+            && ext_header_offset + mem::size_of::<fv::ExtHeader>() <= storage.size()
+        {
+            let ext_header = read_vec(storage, ext_header_offset, mem::size_of::<fv::ExtHeader>())?;
+            //Safety: ext_header is large enough to contain the ext header structure.
+            let ext_header = unsafe { ptr::read_unaligned(ext_header.as_ptr() as *const fv::ExtHeader) };
+            headers_size = headers_size.max(ext_header_offset.saturating_add(ext_header.ext_header_size as usize));
+        }
+        let headers = read_vec(storage, 0, headers_size.min(storage.size()))?;
+        let fv = FirmwareVolume::parse(&headers, storage.size() as u64, options, &mut |_| ())?;
+
+        Ok(Self {
+            storage,
+            fv_length: fv_header.fv_length as usize,
+            attributes: fv.attributes,
+            block_map: fv.block_map().collect(),
+            fv_name: fv.fv_name(),
+            data_offset: fv.data_offset,
+            erase_byte: fv.erase_byte,
+            file_system: fv.file_system,
+            options: *options,
+        })
+    }
+
+    /// Returns the block map for the FV
+    pub fn block_map(&self) -> &Vec<fv::BlockMapEntry> {
+        &self.block_map
+    }
+
+    /// Returns the GUID name of the FV, if any.
+    pub fn fv_name(&self) -> Option<efi::Guid> {
+        self.fv_name
+    }
+
+    /// Returns the file system of the FV (FFS1, FFS2 or FFS3).
+    pub fn file_system(&self) -> FfsFileSystem {
+        self.file_system
+    }
+
+    /// Returns the FV attributes.
+    pub fn attributes(&self) -> FvbAttributes2 {
+        FvbAttributes2::from_bits_retain(self.attributes)
+    }
+
+    /// Returns the size of the FV, as given by its header.
+    pub fn size(&self) -> u64 {
+        self.fv_length as u64
+    }
+
+    /// Returns an iterator over the files of the FV, reading each file from the storage.
+    pub fn file_iter(&self) -> impl Iterator<Item = Result<StorageFile, StorageError>> + '_ {
+        StorageFileIterator { fv: self, next_offset: self.data_offset, error: false }
+    }
+
+    /// Reads the whole FV from the storage.
+    pub fn read_volume(&self) -> Result<FirmwareVolumeOwned, StorageError> {
+        let data = read_vec(self.storage, 0, self.fv_length)?;
+        FirmwareVolume::try_new(&data, &self.options)?;
+        Ok(FirmwareVolumeOwned { data: data.into_boxed_slice(), options: self.options })
+    }
+}
+
+impl<S: FvStorage + ?Sized> fmt::Debug for StorageFirmwareVolume<'_, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StorageFirmwareVolume")
+            .field("fv_name", &self.fv_name)
+            .field("file_system", &self.file_system)
+            .field("attributes", &format_args!("{:#x}", self.attributes))
+            .field("fv_length", &self.fv_length)
+            .field("block_map", &self.block_map)
+            .finish_non_exhaustive()
+    }
+}
+
+/// A file read from a [`StorageFirmwareVolume`].
+#[derive(Clone)]
+pub struct StorageFile {
+    offset: usize,
+    data: Box<[u8]>,
+    erase_polarity: bool,
+    file_system: FfsFileSystem,
+    options: ParseOptions,
+}
+
+impl StorageFile {
+    /// Returns the offset of the file in the FV.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Returns the file.
+    pub fn file(&self) -> File<'_> {
+        File::parse(&self.data, Some(self.erase_polarity), self.file_system, &self.options, &mut |_| ())
+            .expect("file was validated when read")
+    }
+
+    /// Returns the buffer holding the file.
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+}
+
+impl fmt::Debug for StorageFile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StorageFile")
+            .field("offset", &self.offset)
+            .field("file_system", &self.file_system)
+            .field("data.len()", &self.data.len())
+            .finish_non_exhaustive()
+    }
+}
+
+struct StorageFileIterator<'f, 's, S: FvStorage + ?Sized> {
+    fv: &'f StorageFirmwareVolume<'s, S>,
+    next_offset: usize,
+    error: bool,
+}
+
+impl<S: FvStorage + ?Sized> StorageFileIterator<'_, '_, S> {
+    // Reads the header of the file at next_offset, including the extended size of large files when it fits in the FV.
+    // Returns None at the end of the files.
+    fn read_file_header(&mut self) -> Result<Option<Vec<u8>>, StorageError> {
+        let fv = self.fv;
+        let erase_polarity = fv.erase_byte != 0;
+        loop {
+            let remaining = fv.fv_length.saturating_sub(self.next_offset);
+            if remaining < mem::size_of::<file::Header>() {
+                return Ok(None);
+            }
+            let header_length = remaining.min(mem::size_of::<file::Header>() + mem::size_of::<u64>());
+            let header = read_vec(fv.storage, self.next_offset, header_length)?;
+            if header[..mem::size_of::<file::Header>()].iter().all(|&x| x == fv.erase_byte) {
+                return Ok(None);
+            }
+            if !fv.options.skip_invalid_files {
+                return Ok(Some(header));
+            }
+            match invalid_file_length(&header, remaining, fv.file_system, erase_polarity)? {
+                Some(length) => self.next_offset = align_up(self.next_offset as u64 + length, 8) as usize,
+                None => return Ok(Some(header)),
+            }
+        }
+    }
+
+    fn read_file(&mut self) -> Result<Option<StorageFile>, StorageError> {
+        let fv = self.fv;
+        let Some(header) = self.read_file_header()? else {
+            return Ok(None);
+        };
+        let (_, size) = file_header_size_and_length(&header, fv.file_system)?;
+
+        // A size beyond the end of the FV is rejected when the file is parsed.
+        let offset = self.next_offset;
+        let length = (fv.fv_length - offset).min(size.try_into().unwrap_or(usize::MAX));
+        let data = read_vec(fv.storage, offset, length)?;
+        let erase_polarity = fv.erase_byte != 0;
+        File::parse(&data, Some(erase_polarity), fv.file_system, &fv.options, &mut |_| ())?;
+
+        // per the PI spec, "Given a file F, the next file FvHeader is located at the next 8-byte aligned firmware volume
+        // offset following the last byte the file F"
+        self.next_offset = align_up(offset as u64 + size, 8) as usize;
+        Ok(Some(StorageFile {
+            offset,
+            data: data.into_boxed_slice(),
+            erase_polarity,
+            file_system: fv.file_system,
+            options: fv.options,
+        }))
+    }
+}
+
+impl<S: FvStorage + ?Sized> Iterator for StorageFileIterator<'_, '_, S> {
+    type Item = Result<StorageFile, StorageError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.error {
+            return None;
+        }
+        let result = self.read_file();
+        self.error = result.is_err();
+        result.transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::cell::Cell;
+    use std::{env, fs, path::Path};
+
+    // Storage that counts the bytes read from it.
+    struct CountingStorage<'a> {
+        data: &'a [u8],
+        bytes_read: Cell<usize>,
+    }
+
+    impl FvStorage for CountingStorage<'_> {
+        fn size(&self) -> usize {
+            self.data.len()
+        }
+
+        fn read_at(&self, offset: usize, buffer: &mut [u8]) -> Result<(), efi::Status> {
+            self.bytes_read.set(self.bytes_read.get() + buffer.len());
+            self.data.read_at(offset, buffer)
+        }
+    }
+
+    #[test]
+    fn storage_fv_should_match_slice_fv() {
+        let root = Path::new(&env::var("CARGO_MANIFEST_DIR").unwrap()).join("test_resources");
+        let fv_bytes = fs::read(root.join("DXEFV.Fv")).unwrap();
+        let fv = FirmwareVolume::new(&fv_bytes).unwrap();
+
+        let storage = CountingStorage { data: &fv_bytes, bytes_read: Cell::new(0) };
+        let storage_fv = StorageFirmwareVolume::new(&storage).unwrap();
+        assert_eq!(storage_fv.fv_name(), fv.fv_name());
+        assert_eq!(storage_fv.file_system(), fv.file_system());
+        assert_eq!(storage_fv.attributes(), fv.attributes());
+        assert_eq!(*storage_fv.block_map(), fv.block_map().collect::<Vec<_>>());
+        assert_eq!(storage_fv.size(), fv.size());
+        assert!(storage.bytes_read.get() < 0x100);
+
+        let files = storage_fv.file_iter().collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(files.len(), fv.file_iter().count());
+        for (storage_file, file) in files.iter().zip(fv.file_iter()) {
+            let file = file.unwrap();
+            assert_eq!(storage_file.file().name(), file.name());
+            assert_eq!(storage_file.data(), file.data());
+            assert_eq!(&fv_bytes[storage_file.offset()..][..file.data().len()], file.data());
+        }
+
+        // Only the first file is read when the iteration stops there.
+        storage.bytes_read.set(0);
+        let first = storage_fv.file_iter().next().unwrap().unwrap();
+        assert!(storage.bytes_read.get() < first.data().len() + 0x40);
+
+        assert_eq!(storage_fv.read_volume().unwrap().data(), fv_bytes.as_slice());
+    }
+
+    #[test]
+    fn storage_fv_should_reject_truncated_storage() {
+        let root = Path::new(&env::var("CARGO_MANIFEST_DIR").unwrap()).join("test_resources");
+        let fv_bytes = fs::read(root.join("DXEFV.Fv")).unwrap();
+
+        assert_eq!(
+            StorageFirmwareVolume::new(&fv_bytes[..0x20]).unwrap_err(),
+            StorageError::Malformed(FwFsError::BufferTooSmall {
+                needed: mem::size_of::<fv::Header>(),
+                available: 0x20
+            })
+        );
+        let error = StorageFirmwareVolume::new(&fv_bytes[..fv_bytes.len() - 1]).unwrap_err();
+        assert!(matches!(error, StorageError::Malformed(FwFsError::BadFvLength { .. })));
+        assert_eq!(efi::Status::from(error), efi::Status::VOLUME_CORRUPTED);
+        assert_eq!([0u8; 4].read_at(2, &mut [0; 4]), Err(efi::Status::INVALID_PARAMETER));
+    }
+
+    #[test]
+    fn storage_fv_should_report_read_errors() {
+        struct FailingStorage;
+
+        impl FvStorage for FailingStorage {
+            fn size(&self) -> usize {
+                0x1000
+            }
+
+            fn read_at(&self, _offset: usize, _buffer: &mut [u8]) -> Result<(), efi::Status> {
+                Err(efi::Status::DEVICE_ERROR)
+            }
+        }
+
+        let error = StorageFirmwareVolume::new(&FailingStorage).unwrap_err();
+        assert_eq!(
+            error,
+            StorageError::Read { offset: 0, length: mem::size_of::<fv::Header>(), status: efi::Status::DEVICE_ERROR }
+        );
+        assert_eq!(efi::Status::from(error), efi::Status::DEVICE_ERROR);
+    }
+}
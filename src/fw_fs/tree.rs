@@ -0,0 +1,228 @@
+//! Firmware Volume Tree Dumps
+//!
+//! [`FirmwareVolume::render_tree`] renders a firmware volume as an indented textual tree of its files, their sections
+//! and the firmware volumes nested in them, in the spirit of UEFITool, for CLI inspection tools:
+//!
+//! ```text
+//! FirmwareVolume Ffs2 48DB5E17-707C-472D-91CD-1613E7EF51B0 size 0x348000
+//!   File 9E21FD93-9C72-4C15-8C4B-E77F1DB2D792 FirmwareVolumeImage size 0x3185b1
+//!     Section GuidDefined 3D532050-5CDA-4FD0-879E-0F7F630D5AFB size 0x318599
+//!       Section Raw size 0x7c
+//!       Section FirmwareVolumeImage size 0xe0004
+//!         FirmwareVolume Ffs2 6938079B-B503-4E3D-9D24-B28337A25806 size 0xe0000
+//!           File 1B45CC0A-156A-428A-AF62-49864DA0E6E6 FreeForm size 0x2c
+//!             Section Raw size 0x14
+//!           File 52C05B14-0B98-496C-BC3B-04B50211D680 PeiCore "PeiCore" size 0xd27a
+//!             Section Raw size 0x3c
+//!             Section Pe32 size 0xd204
+//!             ...
+//! ```
+//!
+//! Each line is indented by two spaces per nesting level. File lines hold the file name, type, user interface name
+//! and size; section lines hold the section type, the section definition GUID of GUID_DEFINED sections and the section
+//! size. With the `guid_names` feature, well-known GUIDs are followed by their name.
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+use alloc::string::String;
+use core::fmt::{self, Write};
+
+use r_efi::efi;
+use uuid::Uuid;
+
+use super::{
+    ExtractionLimits, File, FileSectionIterator, FirmwareVolume, NullSectionExtractor, Section, SectionExtractor,
+    SectionMetaData,
+};
+use crate::fw_fs::{FfsFileRawType, FfsSectionType};
+
+impl FirmwareVolume<'_> {
+    /// Renders this FV as an indented tree of files, sections and nested FVs, see the [module documentation](self).
+    ///
+    /// Encapsulation sections are expanded with `extractor`; sections it returns no data for are rendered without
+    /// children. Errors encountered while parsing or extracting stop the rendering and are returned.
+    ///
+    /// ## Example
+    ///```
+    /// # use std::{env, fs, path::Path, error::Error};
+    /// use mu_pi::fw_fs::{FirmwareVolume, Section, SectionExtractor};
+    /// use r_efi::efi;
+    ///
+    /// struct NoExtraction;
+    /// impl SectionExtractor for NoExtraction {
+    ///   fn extract(&self, _section: &Section) -> Result<Box<[u8]>, efi::Status> {
+    ///     Ok(Box::new([]))
+    ///   }
+    /// }
+    ///
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// # let root = Path::new(&env::var("CARGO_MANIFEST_DIR")?).join("test_resources");
+    /// # let fv_bytes = fs::read(root.join("DXEFV.Fv"))?;
+    /// let fv = FirmwareVolume::new(&fv_bytes).expect("Firmware Volume Corrupt");
+    /// let tree = fv.render_tree(&NoExtraction).expect("parse error");
+    /// println!("{tree}");
+    /// # assert!(tree.contains("DxeCore \"DxeRust\""));
+    /// # Ok(())
+    /// # }
+    ///```
+    pub fn render_tree(&self, extractor: &dyn SectionExtractor) -> Result<String, efi::Status> {
+        let mut tree = String::new();
+        render_volume(&mut tree, self, extractor, 0)?;
+        Ok(tree)
+    }
+}
+
+// Formats a GUID in registry format, followed by its name if it is a well-known GUID (with the `guid_names` feature).
+struct DisplayGuid<'a>(&'a efi::Guid);
+
+impl fmt::Display for DisplayGuid<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:X}", Uuid::from_bytes_le(*self.0.as_bytes()))?;
+        #[cfg(feature = "guid_names")]
+        if let Some(name) = super::guid_names::lookup(self.0) {
+            write!(f, " ({})", name)?;
+        }
+        Ok(())
+    }
+}
+
+// Appends a line holding `args` at the given nesting depth.
+fn push_line(tree: &mut String, depth: usize, args: fmt::Arguments) {
+    // writing to a String cannot fail.
+    let _ = writeln!(tree, "{:indent$}{}", "", args, indent = depth * 2);
+}
+
+fn render_volume(
+    tree: &mut String,
+    fv: &FirmwareVolume,
+    extractor: &dyn SectionExtractor,
+    depth: usize,
+) -> Result<(), efi::Status> {
+    // extracted FVs and sections can nest without bound in a malicious image.
+    if depth > ExtractionLimits::default().max_depth * 2 {
+        Err(efi::Status::VOLUME_CORRUPTED)?;
+    }
+    match fv.fv_name() {
+        Some(name) => push_line(
+            tree,
+            depth,
+            format_args!("FirmwareVolume {:?} {} size {:#x}", fv.file_system(), DisplayGuid(&name), fv.size()),
+        ),
+        None => push_line(tree, depth, format_args!("FirmwareVolume {:?} size {:#x}", fv.file_system(), fv.size())),
+    }
+    for file in fv.file_iter() {
+        render_file(tree, fv, &file?, extractor, depth + 1)?;
+    }
+    Ok(())
+}
+
+fn render_file(
+    tree: &mut String,
+    fv: &FirmwareVolume,
+    file: &File,
+    extractor: &dyn SectionExtractor,
+    depth: usize,
+) -> Result<(), efi::Status> {
+    // writing to a String cannot fail.
+    let mut line = String::new();
+    let _ = write!(line, "File {}", DisplayGuid(&file.name()));
+    let _ = match file.file_type() {
+        Some(file_type) => write!(line, " {:?}", file_type),
+        None => write!(line, " {:#04x}", file.file_type_raw()),
+    };
+    if let Ok(Some(ui_name)) = file.ui_name() {
+        let _ = write!(line, " {:?}", ui_name);
+    }
+    push_line(tree, depth, format_args!("{} size {:#x}", line, file.size()));
+
+    // only these file types hold sections.
+    if (FfsFileRawType::FREEFORM..=FfsFileRawType::MM_CORE_STANDALONE).contains(&file.file_type_raw()) {
+        for section in file.section_iter() {
+            render_section(tree, fv, &section?, extractor, depth + 1)?;
+        }
+    }
+    Ok(())
+}
+
+fn render_section(
+    tree: &mut String,
+    fv: &FirmwareVolume,
+    section: &Section,
+    extractor: &dyn SectionExtractor,
+    depth: usize,
+) -> Result<(), efi::Status> {
+    if depth > ExtractionLimits::default().max_depth * 2 {
+        Err(efi::Status::VOLUME_CORRUPTED)?;
+    }
+    let size = section.section_size();
+    match (section.section_type(), section.meta_data()) {
+        (_, SectionMetaData::GuidDefined(header, _)) => push_line(
+            tree,
+            depth,
+            format_args!("Section GuidDefined {} size {:#x}", DisplayGuid(&header.section_definition_guid), size),
+        ),
+        (Some(section_type), _) => push_line(tree, depth, format_args!("Section {:?} size {:#x}", section_type, size)),
+        (None, _) => {
+            push_line(tree, depth, format_args!("Section {:#04x} size {:#x}", section.section_type_raw(), size))
+        }
+    }
+
+    if section.section_type() == Some(FfsSectionType::FirmwareVolumeImage) {
+        render_volume(tree, &section.firmware_volume_with_options(&fv.options)?, extractor, depth + 1)?;
+    } else if section.is_encapsulation() {
+        let extracted = extractor.extract(section)?;
+        let nested_sections =
+            FileSectionIterator::new(&extracted, &NullSectionExtractor {}, ExtractionLimits::default())
+                .without_extraction();
+        for nested in nested_sections {
+            render_section(tree, fv, &nested?, extractor, depth + 1)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs, path::Path};
+
+    use super::*;
+
+    fn read_resource(name: &str) -> Vec<u8> {
+        let root = Path::new(&env::var("CARGO_MANIFEST_DIR").unwrap()).join("test_resources");
+        fs::read(root.join(name)).unwrap()
+    }
+
+    #[test]
+    fn tree_should_list_files_and_sections() {
+        let fv_bytes = read_resource("DXEFV.Fv");
+        let fv = FirmwareVolume::new(&fv_bytes).unwrap();
+        let tree = fv.render_tree(&NullSectionExtractor {}).unwrap();
+
+        let lines = tree.lines().collect::<Vec<_>>();
+        assert!(lines[0].starts_with("FirmwareVolume Ffs2 "));
+        assert_eq!(lines.iter().filter(|line| line.starts_with("  File ")).count(), fv.file_iter().count());
+        let dxe_core = lines.iter().position(|line| line.contains(" DxeCore \"DxeRust\" size ")).unwrap();
+        assert!(lines[dxe_core + 1].starts_with("    Section "));
+    }
+
+    #[test]
+    #[cfg(feature = "brotli")]
+    fn tree_should_expand_nested_volumes() {
+        use crate::fw_fs::extractors::BrotliSectionExtractor;
+
+        let fv_bytes = read_resource("FVMAIN_COMPACT.Fv");
+        let fv = FirmwareVolume::new(&fv_bytes).unwrap();
+        let compact = fv.render_tree(&NullSectionExtractor {}).unwrap();
+        assert_eq!(compact.lines().filter(|line| line.trim_start().starts_with("FirmwareVolume ")).count(), 1);
+
+        let expanded = fv.render_tree(&BrotliSectionExtractor {}).unwrap();
+        assert!(expanded.lines().filter(|line| line.trim_start().starts_with("FirmwareVolume ")).count() > 1);
+        assert!(expanded.lines().any(|line| line.starts_with("          File D6A2CB7F-6A18-4E2F-B43B-9920A733700A ")
+            && line.contains(" DxeCore \"DxeCore\" size ")));
+    }
+}
@@ -0,0 +1,565 @@
+//! GCD Initialization Plan
+//!
+//! Converts a HOB list into the ordered list of Global Coherency Domain (GCD) operations that initialize the GCD
+//! memory and I/O space maps, mirroring what the EDK2 DXE core does with the HOB list at initialization
+//! (CoreInitializeGcdServicesPhase2). Producing the plan separately from applying it lets a DXE core implementation
+//! test its HOB handling without a GCD.
+//!
+//! See <https://uefi.org/specs/PI/1.8A/V2_Services_DXE_Services.html#global-coherency-domain-services>.
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+use alloc::vec::Vec;
+use core::fmt;
+
+use r_efi::efi;
+
+use crate::{
+    address_helper::align_down,
+    dxe_services::{GcdIoType, GcdMemoryType},
+    hob::{self, Hob, HobList},
+};
+
+const UEFI_PAGE_SIZE: u64 = 0x1000;
+
+const MEMORY_ATTRIBUTE_MASK: u32 = hob::EFI_RESOURCE_ATTRIBUTE_PRESENT
+    | hob::EFI_RESOURCE_ATTRIBUTE_INITIALIZED
+    | hob::EFI_RESOURCE_ATTRIBUTE_TESTED
+    | hob::EFI_RESOURCE_ATTRIBUTE_READ_PROTECTED
+    | hob::EFI_RESOURCE_ATTRIBUTE_WRITE_PROTECTED
+    | hob::EFI_RESOURCE_ATTRIBUTE_EXECUTION_PROTECTED
+    | hob::EFI_RESOURCE_ATTRIBUTE_READ_ONLY_PROTECTED
+    | hob::EFI_RESOURCE_ATTRIBUTE_16_BIT_IO
+    | hob::EFI_RESOURCE_ATTRIBUTE_32_BIT_IO
+    | hob::EFI_RESOURCE_ATTRIBUTE_64_BIT_IO
+    | hob::EFI_RESOURCE_ATTRIBUTE_PERSISTENT;
+
+const TESTED_MEMORY_ATTRIBUTES: u32 =
+    hob::EFI_RESOURCE_ATTRIBUTE_PRESENT | hob::EFI_RESOURCE_ATTRIBUTE_INITIALIZED | hob::EFI_RESOURCE_ATTRIBUTE_TESTED;
+const INITIALIZED_MEMORY_ATTRIBUTES: u32 =
+    hob::EFI_RESOURCE_ATTRIBUTE_PRESENT | hob::EFI_RESOURCE_ATTRIBUTE_INITIALIZED;
+const PRESENT_MEMORY_ATTRIBUTES: u32 = hob::EFI_RESOURCE_ATTRIBUTE_PRESENT;
+
+// (resource attribute, GCD capability, applies to memory resources only)
+const RESOURCE_ATTRIBUTE_CAPABILITIES: [(u32, u64, bool); 14] = [
+    (hob::EFI_RESOURCE_ATTRIBUTE_UNCACHEABLE, efi::MEMORY_UC, true),
+    (hob::EFI_RESOURCE_ATTRIBUTE_UNCACHED_EXPORTED, efi::MEMORY_UCE, true),
+    (hob::EFI_RESOURCE_ATTRIBUTE_WRITE_COMBINEABLE, efi::MEMORY_WC, true),
+    (hob::EFI_RESOURCE_ATTRIBUTE_WRITE_THROUGH_CACHEABLE, efi::MEMORY_WT, true),
+    (hob::EFI_RESOURCE_ATTRIBUTE_WRITE_BACK_CACHEABLE, efi::MEMORY_WB, true),
+    (hob::EFI_RESOURCE_ATTRIBUTE_READ_PROTECTABLE, efi::MEMORY_RP, true),
+    (hob::EFI_RESOURCE_ATTRIBUTE_WRITE_PROTECTABLE, efi::MEMORY_WP, true),
+    (hob::EFI_RESOURCE_ATTRIBUTE_EXECUTION_PROTECTABLE, efi::MEMORY_XP, true),
+    (hob::EFI_RESOURCE_ATTRIBUTE_READ_ONLY_PROTECTABLE, efi::MEMORY_RO, true),
+    (hob::EFI_RESOURCE_ATTRIBUTE_PRESENT, hob::EFI_MEMORY_PRESENT, false),
+    (hob::EFI_RESOURCE_ATTRIBUTE_INITIALIZED, hob::EFI_MEMORY_INITIALIZED, false),
+    (hob::EFI_RESOURCE_ATTRIBUTE_TESTED, hob::EFI_MEMORY_TESTED, false),
+    (hob::EFI_RESOURCE_ATTRIBUTE_PERSISTABLE, hob::EFI_MEMORY_NV, true),
+    (hob::EFI_RESOURCE_ATTRIBUTE_MORE_RELIABLE, hob::EFI_MEMORY_MORE_RELIABLE, true),
+];
+
+// (resource attribute, GCD attribute) for the protections currently applied to a memory resource.
+const RESOURCE_ATTRIBUTE_PROTECTIONS: [(u32, u64); 4] = [
+    (hob::EFI_RESOURCE_ATTRIBUTE_READ_PROTECTED, efi::MEMORY_RP),
+    (hob::EFI_RESOURCE_ATTRIBUTE_WRITE_PROTECTED, efi::MEMORY_WP),
+    (hob::EFI_RESOURCE_ATTRIBUTE_EXECUTION_PROTECTED, efi::MEMORY_XP),
+    (hob::EFI_RESOURCE_ATTRIBUTE_READ_ONLY_PROTECTED, efi::MEMORY_RO),
+];
+
+/// A GCD operation in a [`plan`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GcdOperation {
+    /// AddMemorySpace() for a memory resource descriptor HOB.
+    AddMemorySpace { memory_type: GcdMemoryType, base_address: efi::PhysicalAddress, length: u64, capabilities: u64 },
+    /// SetMemorySpaceAttributes() for a memory resource descriptor HOB that describes memory that is currently
+    /// protected.
+    SetMemorySpaceAttributes { base_address: efi::PhysicalAddress, length: u64, attributes: u64 },
+    /// AddIoSpace() for an I/O resource descriptor HOB.
+    AddIoSpace { io_type: GcdIoType, base_address: efi::PhysicalAddress, length: u64 },
+    /// AllocateMemorySpace() with EfiGcdAllocateAddress for a memory allocation HOB or a firmware volume HOB, owned by
+    /// the DXE core image.
+    ///
+    /// For memory allocation HOBs in system memory (including persistent and more reliable memory), `memory_type`
+    /// holds the allocation's EFI_MEMORY_TYPE, which should be recorded in the memory map.
+    AllocateMemorySpace {
+        gcd_memory_type: GcdMemoryType,
+        base_address: efi::PhysicalAddress,
+        length: u64,
+        memory_type: Option<efi::MemoryType>,
+    },
+}
+
+/// A HOB skipped by [`plan_with_errors`] because the range it describes does not fit in the 64-bit address space.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum GcdPlanError {
+    /// A resource descriptor HOB whose range ends past the top of the address space.
+    ResourceOverflow { base_address: efi::PhysicalAddress, length: u64 },
+    /// A memory allocation HOB whose page-aligned range ends past the top of the address space.
+    MemoryAllocationOverflow { base_address: efi::PhysicalAddress, length: u64 },
+    /// A firmware volume HOB whose range ends past the top of the address space.
+    FirmwareVolumeOverflow { base_address: efi::PhysicalAddress, length: u64 },
+}
+
+impl fmt::Display for GcdPlanError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (kind, base_address, length) = match self {
+            GcdPlanError::ResourceOverflow { base_address, length } => ("resource descriptor", base_address, length),
+            GcdPlanError::MemoryAllocationOverflow { base_address, length } => {
+                ("memory allocation", base_address, length)
+            }
+            GcdPlanError::FirmwareVolumeOverflow { base_address, length } => ("firmware volume", base_address, length),
+        };
+        write!(f, "{kind} HOB at {base_address:#x} with length {length:#x} overflows the address space")
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for GcdPlanError {}
+
+/// Converts the resource attributes of a resource descriptor HOB to GCD capabilities.
+///
+/// Cacheability and protection capabilities are only reported for memory resources.
+pub fn resource_attributes_to_capabilities(resource_type: u32, resource_attribute: u32) -> u64 {
+    let is_memory = resource_type != hob::EFI_RESOURCE_IO && resource_type != hob::EFI_RESOURCE_IO_RESERVED;
+    RESOURCE_ATTRIBUTE_CAPABILITIES
+        .iter()
+        .filter(|(attribute, _, memory_only)| resource_attribute & attribute != 0 && (is_memory || !memory_only))
+        .fold(0, |capabilities, (_, capability, _)| capabilities | capability)
+}
+
+/// Returns the GCD memory type of a memory resource descriptor HOB, or `None` if the HOB does not describe memory.
+///
+/// System memory that is neither persistent nor exactly tested, initialized or present (e.g. memory that is not
+/// present or is protected) is [`GcdMemoryType::NonExistent`], and is not added to the GCD.
+pub fn resource_memory_type(resource_type: u32, resource_attribute: u32) -> Option<GcdMemoryType> {
+    match resource_type {
+        hob::EFI_RESOURCE_SYSTEM_MEMORY => {
+            if resource_attribute & hob::EFI_RESOURCE_ATTRIBUTE_PERSISTENT != 0 {
+                return Some(GcdMemoryType::Persistent);
+            }
+            match resource_attribute & MEMORY_ATTRIBUTE_MASK {
+                TESTED_MEMORY_ATTRIBUTES if resource_attribute & hob::EFI_RESOURCE_ATTRIBUTE_MORE_RELIABLE != 0 => {
+                    Some(GcdMemoryType::MoreReliable)
+                }
+                TESTED_MEMORY_ATTRIBUTES => Some(GcdMemoryType::SystemMemory),
+                INITIALIZED_MEMORY_ATTRIBUTES | PRESENT_MEMORY_ATTRIBUTES => Some(GcdMemoryType::Reserved),
+                _ => Some(GcdMemoryType::NonExistent),
+            }
+        }
+        hob::EFI_RESOURCE_MEMORY_MAPPED_IO | hob::EFI_RESOURCE_FIRMWARE_DEVICE => Some(GcdMemoryType::MemoryMappedIo),
+        hob::EFI_RESOURCE_MEMORY_MAPPED_IO_PORT | hob::EFI_RESOURCE_MEMORY_RESERVED => Some(GcdMemoryType::Reserved),
+        _ => None,
+    }
+}
+
+/// Converts a HOB list into the ordered GCD operations that initialize the GCD memory and I/O space maps.
+///
+/// Resource descriptor HOBs are added first, in HOB list order, except for the system memory resources that
+/// [`resource_memory_type`] reports as non-existent. Memory allocation HOBs and firmware volume HOBs are then allocated
+/// (in HOB list order) from the memory space added for the resource that contains them; allocations outside any memory
+/// resource are dropped, as EDK2 ignores the failure to allocate them.
+///
+/// ## Example
+/// ```no_run
+/// use mu_pi::{gcd_plan::{self, GcdOperation}, hob::HobList};
+///
+/// # let hob_list_ptr = core::ptr::null();
+/// let mut hob_list = HobList::default();
+/// hob_list.discover_hobs(hob_list_ptr);
+/// for operation in gcd_plan::plan(&hob_list) {
+///     match operation {
+///         GcdOperation::AddMemorySpace { .. } => (), // gcd.add_memory_space(..)
+///         _ => (),
+///     }
+/// }
+/// ```
+pub fn plan(hob_list: &HobList) -> Vec<GcdOperation> {
+    plan_with_errors(hob_list, &mut |_| ())
+}
+
+/// Converts a HOB list into GCD operations like [`plan`], passing each HOB skipped because its range overflows the
+/// address space to `errors`.
+pub fn plan_with_errors(hob_list: &HobList, errors: &mut dyn FnMut(GcdPlanError)) -> Vec<GcdOperation> {
+    let mut operations = Vec::new();
+
+    for hob in hob_list.iter() {
+        let Hob::ResourceDescriptor(resource) = hob else {
+            continue;
+        };
+        let (base_address, length) = (resource.physical_start, resource.resource_length);
+        if base_address.checked_add(length).is_none() {
+            errors(GcdPlanError::ResourceOverflow { base_address, length });
+            continue;
+        }
+        let capabilities = resource_attributes_to_capabilities(resource.resource_type, resource.resource_attribute);
+        if let Some(memory_type) = resource_memory_type(resource.resource_type, resource.resource_attribute) {
+            if memory_type == GcdMemoryType::NonExistent {
+                continue;
+            }
+            operations.push(GcdOperation::AddMemorySpace { memory_type, base_address, length, capabilities });
+            let attributes = RESOURCE_ATTRIBUTE_PROTECTIONS
+                .iter()
+                .filter(|(attribute, _)| resource.resource_attribute & attribute != 0)
+                .fold(0, |attributes, (_, protection)| attributes | protection);
+            if attributes != 0 {
+                operations.push(GcdOperation::SetMemorySpaceAttributes { base_address, length, attributes });
+            }
+        } else {
+            let io_type = match resource.resource_type {
+                hob::EFI_RESOURCE_IO => GcdIoType::Io,
+                hob::EFI_RESOURCE_IO_RESERVED => GcdIoType::Reserved,
+                _ => continue,
+            };
+            operations.push(GcdOperation::AddIoSpace { io_type, base_address, length });
+        }
+    }
+
+    // the GCD memory type of the memory space added for the resource containing the given range. Both ranges are
+    // known not to overflow.
+    let added_memory_type = |operations: &[GcdOperation], base_address: u64, length: u64| {
+        operations.iter().rev().find_map(|operation| match *operation {
+            GcdOperation::AddMemorySpace { memory_type, base_address: start, length: resource_length, .. }
+                if base_address >= start && base_address + length <= start + resource_length =>
+            {
+                Some(memory_type)
+            }
+            _ => None,
+        })
+    };
+
+    for hob in hob_list.iter() {
+        let (base_address, length, memory_type) = match hob {
+            Hob::MemoryAllocation(hob::MemoryAllocation { alloc_descriptor, .. })
+            | Hob::MemoryAllocationModule(hob::MemoryAllocationModule { alloc_descriptor, .. }) => {
+                let (base_address, length) = (alloc_descriptor.memory_base_address, alloc_descriptor.memory_length);
+                let Some(end) = base_address
+                    .checked_add(length)
+                    .and_then(|end| end.checked_add(UEFI_PAGE_SIZE - 1))
+                    .map(|end| align_down(end, UEFI_PAGE_SIZE))
+                else {
+                    errors(GcdPlanError::MemoryAllocationOverflow { base_address, length });
+                    continue;
+                };
+                let base_address = align_down(base_address, UEFI_PAGE_SIZE);
+                (base_address, end - base_address, Some(alloc_descriptor.memory_type))
+            }
+            Hob::FirmwareVolume(fv) => {
+                if fv.base_address.checked_add(fv.length).is_none() {
+                    errors(GcdPlanError::FirmwareVolumeOverflow { base_address: fv.base_address, length: fv.length });
+                    continue;
+                }
+                (fv.base_address, fv.length, None)
+            }
+            _ => continue,
+        };
+        let Some(gcd_memory_type) = added_memory_type(&operations, base_address, length) else {
+            continue;
+        };
+        let memory_type = match memory_type {
+            Some(memory_type) => matches!(
+                gcd_memory_type,
+                GcdMemoryType::SystemMemory | GcdMemoryType::Persistent | GcdMemoryType::MoreReliable
+            )
+            .then_some(memory_type),
+            // firmware volumes are allocated as memory mapped I/O, so must be in a memory mapped I/O resource.
+            None if gcd_memory_type != GcdMemoryType::MemoryMappedIo => continue,
+            None => None,
+        };
+        operations.push(GcdOperation::AllocateMemorySpace { gcd_memory_type, base_address, length, memory_type });
+    }
+
+    operations
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::mem::size_of;
+
+    fn resource(
+        resource_type: u32,
+        resource_attribute: u32,
+        physical_start: u64,
+        length: u64,
+    ) -> hob::ResourceDescriptor {
+        hob::ResourceDescriptor {
+            header: hob::header::Hob {
+                r#type: hob::RESOURCE_DESCRIPTOR,
+                length: size_of::<hob::ResourceDescriptor>() as u16,
+                reserved: 0,
+            },
+            owner: efi::Guid::from_bytes(&[0; 16]),
+            resource_type,
+            resource_attribute,
+            physical_start,
+            resource_length: length,
+        }
+    }
+
+    fn memory_allocation(memory_base_address: u64, memory_length: u64) -> hob::MemoryAllocation {
+        hob::MemoryAllocation {
+            header: hob::header::Hob {
+                r#type: hob::MEMORY_ALLOCATION,
+                length: size_of::<hob::MemoryAllocation>() as u16,
+                reserved: 0,
+            },
+            alloc_descriptor: hob::header::MemoryAllocation {
+                name: efi::Guid::from_bytes(&[0; 16]),
+                memory_base_address,
+                memory_length,
+                memory_type: efi::RUNTIME_SERVICES_DATA,
+                reserved: [0; 4],
+            },
+        }
+    }
+
+    #[test]
+    fn plan_should_follow_edk2_gcd_initialization() {
+        let system_memory = resource(
+            hob::EFI_RESOURCE_SYSTEM_MEMORY,
+            TESTED_MEMORY_ATTRIBUTES
+                | hob::EFI_RESOURCE_ATTRIBUTE_WRITE_BACK_CACHEABLE
+                | hob::EFI_RESOURCE_ATTRIBUTE_EXECUTION_PROTECTABLE,
+            0x100000,
+            0x100000,
+        );
+        let untested_memory = resource(
+            hob::EFI_RESOURCE_SYSTEM_MEMORY,
+            hob::EFI_RESOURCE_ATTRIBUTE_PRESENT
+                | hob::EFI_RESOURCE_ATTRIBUTE_INITIALIZED
+                | hob::EFI_RESOURCE_ATTRIBUTE_READ_ONLY_PROTECTABLE,
+            0x200000,
+            0x100000,
+        );
+        let flash =
+            resource(hob::EFI_RESOURCE_FIRMWARE_DEVICE, hob::EFI_RESOURCE_ATTRIBUTE_UNCACHEABLE, 0xff000000, 0x1000000);
+        let io = resource(hob::EFI_RESOURCE_IO, hob::EFI_RESOURCE_ATTRIBUTE_UNCACHEABLE, 0, 0x10000);
+        let allocation = hob::MemoryAllocation {
+            header: hob::header::Hob {
+                r#type: hob::MEMORY_ALLOCATION,
+                length: size_of::<hob::MemoryAllocation>() as u16,
+                reserved: 0,
+            },
+            alloc_descriptor: hob::header::MemoryAllocation {
+                name: efi::Guid::from_bytes(&[0; 16]),
+                memory_base_address: 0x180800,
+                memory_length: 0x1000,
+                memory_type: efi::BOOT_SERVICES_DATA,
+                reserved: [0; 4],
+            },
+        };
+        let fv = hob::FirmwareVolume {
+            header: hob::header::Hob { r#type: hob::FV, length: size_of::<hob::FirmwareVolume>() as u16, reserved: 0 },
+            base_address: 0xff800000,
+            length: 0x800000,
+        };
+
+        let mut hob_list = HobList::new();
+        hob_list.push(Hob::MemoryAllocation(&allocation));
+        hob_list.push(Hob::FirmwareVolume(&fv));
+        hob_list.push(Hob::ResourceDescriptor(&system_memory));
+        hob_list.push(Hob::ResourceDescriptor(&untested_memory));
+        hob_list.push(Hob::ResourceDescriptor(&flash));
+        hob_list.push(Hob::ResourceDescriptor(&io));
+
+        assert_eq!(
+            plan(&hob_list),
+            [
+                GcdOperation::AddMemorySpace {
+                    memory_type: GcdMemoryType::SystemMemory,
+                    base_address: 0x100000,
+                    length: 0x100000,
+                    capabilities: efi::MEMORY_WB
+                        | efi::MEMORY_XP
+                        | hob::EFI_MEMORY_PRESENT
+                        | hob::EFI_MEMORY_INITIALIZED
+                        | hob::EFI_MEMORY_TESTED,
+                },
+                GcdOperation::AddMemorySpace {
+                    memory_type: GcdMemoryType::Reserved,
+                    base_address: 0x200000,
+                    length: 0x100000,
+                    capabilities: efi::MEMORY_RO | hob::EFI_MEMORY_PRESENT | hob::EFI_MEMORY_INITIALIZED,
+                },
+                GcdOperation::AddMemorySpace {
+                    memory_type: GcdMemoryType::MemoryMappedIo,
+                    base_address: 0xff000000,
+                    length: 0x1000000,
+                    capabilities: efi::MEMORY_UC,
+                },
+                GcdOperation::AddIoSpace { io_type: GcdIoType::Io, base_address: 0, length: 0x10000 },
+                GcdOperation::AllocateMemorySpace {
+                    gcd_memory_type: GcdMemoryType::SystemMemory,
+                    base_address: 0x180000,
+                    length: 0x2000,
+                    memory_type: Some(efi::BOOT_SERVICES_DATA),
+                },
+                GcdOperation::AllocateMemorySpace {
+                    gcd_memory_type: GcdMemoryType::MemoryMappedIo,
+                    base_address: 0xff800000,
+                    length: 0x800000,
+                    memory_type: None,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn plan_should_skip_and_report_overflowing_hobs() {
+        let tested_memory = TESTED_MEMORY_ATTRIBUTES | hob::EFI_RESOURCE_ATTRIBUTE_WRITE_BACK_CACHEABLE;
+        let system_memory = resource(hob::EFI_RESOURCE_SYSTEM_MEMORY, tested_memory, 0x100000, 0x100000);
+        let overflowing_memory = resource(hob::EFI_RESOURCE_SYSTEM_MEMORY, tested_memory, 0x200000, u64::MAX);
+        let allocation = hob::MemoryAllocation {
+            header: hob::header::Hob {
+                r#type: hob::MEMORY_ALLOCATION,
+                length: size_of::<hob::MemoryAllocation>() as u16,
+                reserved: 0,
+            },
+            alloc_descriptor: hob::header::MemoryAllocation {
+                name: efi::Guid::from_bytes(&[0; 16]),
+                memory_base_address: 0x180000,
+                memory_length: u64::MAX - 0x180000,
+                memory_type: efi::BOOT_SERVICES_DATA,
+                reserved: [0; 4],
+            },
+        };
+        let fv = hob::FirmwareVolume {
+            header: hob::header::Hob { r#type: hob::FV, length: size_of::<hob::FirmwareVolume>() as u16, reserved: 0 },
+            base_address: 0x180000,
+            length: u64::MAX,
+        };
+
+        let mut hob_list = HobList::new();
+        hob_list.push(Hob::ResourceDescriptor(&system_memory));
+        hob_list.push(Hob::ResourceDescriptor(&overflowing_memory));
+        hob_list.push(Hob::MemoryAllocation(&allocation));
+        hob_list.push(Hob::FirmwareVolume(&fv));
+
+        let mut errors = Vec::new();
+        let operations = plan_with_errors(&hob_list, &mut |error| errors.push(error));
+
+        assert_eq!(
+            operations,
+            [GcdOperation::AddMemorySpace {
+                memory_type: GcdMemoryType::SystemMemory,
+                base_address: 0x100000,
+                length: 0x100000,
+                capabilities: efi::MEMORY_WB
+                    | hob::EFI_MEMORY_PRESENT
+                    | hob::EFI_MEMORY_INITIALIZED
+                    | hob::EFI_MEMORY_TESTED,
+            }]
+        );
+        assert_eq!(
+            errors,
+            [
+                GcdPlanError::ResourceOverflow { base_address: 0x200000, length: u64::MAX },
+                GcdPlanError::MemoryAllocationOverflow { base_address: 0x180000, length: u64::MAX - 0x180000 },
+                GcdPlanError::FirmwareVolumeOverflow { base_address: 0x180000, length: u64::MAX },
+            ]
+        );
+        assert_eq!(plan(&hob_list), operations);
+    }
+
+    #[test]
+    fn plan_should_add_tested_more_reliable_memory_as_more_reliable() {
+        let more_reliable_memory = resource(
+            hob::EFI_RESOURCE_SYSTEM_MEMORY,
+            TESTED_MEMORY_ATTRIBUTES
+                | hob::EFI_RESOURCE_ATTRIBUTE_WRITE_BACK_CACHEABLE
+                | hob::EFI_RESOURCE_ATTRIBUTE_MORE_RELIABLE,
+            0x100000,
+            0x100000,
+        );
+        let allocation = memory_allocation(0x180000, 0x1000);
+
+        let mut hob_list = HobList::new();
+        hob_list.push(Hob::ResourceDescriptor(&more_reliable_memory));
+        hob_list.push(Hob::MemoryAllocation(&allocation));
+
+        assert_eq!(
+            plan(&hob_list),
+            [
+                GcdOperation::AddMemorySpace {
+                    memory_type: GcdMemoryType::MoreReliable,
+                    base_address: 0x100000,
+                    length: 0x100000,
+                    capabilities: efi::MEMORY_WB
+                        | hob::EFI_MEMORY_MORE_RELIABLE
+                        | hob::EFI_MEMORY_PRESENT
+                        | hob::EFI_MEMORY_INITIALIZED
+                        | hob::EFI_MEMORY_TESTED,
+                },
+                GcdOperation::AllocateMemorySpace {
+                    gcd_memory_type: GcdMemoryType::MoreReliable,
+                    base_address: 0x180000,
+                    length: 0x1000,
+                    memory_type: Some(efi::RUNTIME_SERVICES_DATA),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn plan_should_skip_system_memory_that_is_not_present_or_protected() {
+        let not_present_memory = resource(
+            hob::EFI_RESOURCE_SYSTEM_MEMORY,
+            hob::EFI_RESOURCE_ATTRIBUTE_WRITE_BACK_CACHEABLE,
+            0x100000,
+            0x100000,
+        );
+        let protected_memory = resource(
+            hob::EFI_RESOURCE_SYSTEM_MEMORY,
+            TESTED_MEMORY_ATTRIBUTES
+                | hob::EFI_RESOURCE_ATTRIBUTE_READ_PROTECTED
+                | hob::EFI_RESOURCE_ATTRIBUTE_READ_PROTECTABLE,
+            0x200000,
+            0x100000,
+        );
+        let protected_persistent_memory = resource(
+            hob::EFI_RESOURCE_SYSTEM_MEMORY,
+            TESTED_MEMORY_ATTRIBUTES
+                | hob::EFI_RESOURCE_ATTRIBUTE_PERSISTENT
+                | hob::EFI_RESOURCE_ATTRIBUTE_WRITE_PROTECTED
+                | hob::EFI_RESOURCE_ATTRIBUTE_WRITE_PROTECTABLE,
+            0x300000,
+            0x100000,
+        );
+        let allocation = memory_allocation(0x280000, 0x1000);
+
+        let mut hob_list = HobList::new();
+        hob_list.push(Hob::ResourceDescriptor(&not_present_memory));
+        hob_list.push(Hob::ResourceDescriptor(&protected_memory));
+        hob_list.push(Hob::ResourceDescriptor(&protected_persistent_memory));
+        hob_list.push(Hob::MemoryAllocation(&allocation));
+
+        assert_eq!(
+            resource_memory_type(hob::EFI_RESOURCE_SYSTEM_MEMORY, not_present_memory.resource_attribute),
+            Some(GcdMemoryType::NonExistent)
+        );
+        assert_eq!(
+            plan(&hob_list),
+            [
+                GcdOperation::AddMemorySpace {
+                    memory_type: GcdMemoryType::Persistent,
+                    base_address: 0x300000,
+                    length: 0x100000,
+                    capabilities: efi::MEMORY_WP
+                        | hob::EFI_MEMORY_PRESENT
+                        | hob::EFI_MEMORY_INITIALIZED
+                        | hob::EFI_MEMORY_TESTED,
+                },
+                GcdOperation::SetMemorySpaceAttributes {
+                    base_address: 0x300000,
+                    length: 0x100000,
+                    attributes: efi::MEMORY_WP
+                },
+            ]
+        );
+    }
+}
@@ -68,7 +68,8 @@
 //!
 
 use crate::{
-    address_helper::{align_down, align_up},
+    image::LoadedImage,
+    parse::{Deviation, ParseOptions},
     BootMode,
 };
 use core::{
@@ -78,12 +79,25 @@ use core::{
     mem::{self, size_of},
     slice,
 };
-use indoc::indoc;
+use r_efi::efi;
 
 // Expectation is someone will provide alloc
-extern crate alloc;
-use alloc::boxed::Box;
-use alloc::vec::Vec;
+#[cfg(feature = "alloc")]
+use crate::{
+    address_helper::{align_down, align_up},
+    parse::Diagnostic,
+};
+#[cfg(feature = "alloc")]
+use alloc::{boxed::Box, vec, vec::Vec};
+#[cfg(feature = "alloc")]
+use indoc::indoc;
+#[cfg(feature = "alloc")]
+use zerocopy::AsBytes;
+
+#[cfg(feature = "alloc")]
+pub mod decoders;
+#[cfg(all(feature = "alloc", feature = "pei"))]
+pub mod migration;
 
 // If the target is x86_64, then EfiPhysicalAddress is u64
 #[cfg(target_arch = "x86_64")]
@@ -116,6 +130,10 @@ pub const FV3: u16 = 0x000C;
 pub const UNUSED: u16 = 0xFFFE;
 pub const END_OF_HOB_LIST: u16 = 0xFFFF;
 
+// HOBs are required to start on 8-byte boundaries, so GUID HOB payloads cannot rely on stricter alignment.
+#[cfg(feature = "alloc")]
+const GUID_HOB_PAYLOAD_ALIGNMENT: usize = 8;
+
 pub mod header {
     use crate::hob::EfiPhysicalAddress;
     use r_efi::system::MemoryType;
@@ -288,6 +306,110 @@ pub struct MemoryAllocationModule {
     pub entry_point: u64, // EFI_PHYSICAL_ADDRESS
 }
 
+/// The name of the memory allocation HOB (EFI_HOB_MEMORY_ALLOCATION_HEADER.Name) for a memory allocation module HOB.
+pub const HOB_MEMORY_ALLOC_MODULE_GUID: r_efi::efi::Guid =
+    r_efi::efi::Guid::from_fields(0xf8e21975, 0x0899, 0x4f58, 0xa4, 0xbe, &[0x55, 0x25, 0xa9, 0xc6, 0xd7, 0x7a]);
+
+/// The name of the memory allocation HOB for the stack of the HOB producer phase ([`MemoryAllocationStack`]).
+pub const HOB_MEMORY_ALLOC_STACK_GUID: r_efi::efi::Guid =
+    r_efi::efi::Guid::from_fields(0x4ed4bf27, 0x4092, 0x42e9, 0x80, 0x7d, &[0x52, 0x7b, 0x1d, 0x00, 0xc9, 0xbd]);
+
+/// The name of the memory allocation HOB for the BSP store of the HOB producer phase ([`MemoryAllocationBspStore`]).
+pub const HOB_MEMORY_ALLOC_BSP_STORE_GUID: r_efi::efi::Guid =
+    r_efi::efi::Guid::from_fields(0x564b33cd, 0xc92a, 0x4593, 0x90, 0xbf, &[0x24, 0x73, 0xe4, 0x3c, 0x63, 0x22]);
+
+/// The purpose of a memory allocation, from the name of its memory allocation HOB.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MemoryAllocationKind {
+    /// An allocation without a name, such as those made through the PEI AllocatePages() service.
+    Unnamed,
+    /// The stack of the HOB producer phase.
+    Stack,
+    /// The BSP store of the HOB producer phase.
+    BspStore,
+    /// A module loaded by the HOB producer phase, such as the DXE core.
+    Module,
+    /// An allocation with another name.
+    Other(efi::Guid),
+}
+
+impl MemoryAllocationKind {
+    /// Returns the kind of memory allocation named by `name`.
+    pub fn from_name(name: &efi::Guid) -> Self {
+        match *name {
+            HOB_MEMORY_ALLOC_STACK_GUID => Self::Stack,
+            HOB_MEMORY_ALLOC_BSP_STORE_GUID => Self::BspStore,
+            HOB_MEMORY_ALLOC_MODULE_GUID => Self::Module,
+            name if name.as_bytes() == &[0; 16] => Self::Unnamed,
+            name => Self::Other(name),
+        }
+    }
+
+    /// Returns a readable label for the kind of allocation, if it is known.
+    ///
+    /// The names of other allocations are resolved from the well-known GUID names of `fw_fs::guid_names` when the
+    /// `guid_names` feature is enabled.
+    pub fn label(&self) -> Option<&'static str> {
+        match self {
+            Self::Unnamed => None,
+            Self::Stack => Some("Stack"),
+            Self::BspStore => Some("BspStore"),
+            Self::Module => Some("Module"),
+            Self::Other(name) => known_guid_label(name),
+        }
+    }
+}
+
+#[cfg(feature = "guid_names")]
+fn known_guid_label(guid: &efi::Guid) -> Option<&'static str> {
+    crate::fw_fs::guid_names::lookup(guid)
+}
+
+#[cfg(not(feature = "guid_names"))]
+fn known_guid_label(_guid: &efi::Guid) -> Option<&'static str> {
+    None
+}
+
+impl MemoryAllocation {
+    /// Returns the kind of the allocation, from its name.
+    pub fn kind(&self) -> MemoryAllocationKind {
+        MemoryAllocationKind::from_name(&self.alloc_descriptor.name)
+    }
+}
+
+impl MemoryAllocationModule {
+    /// Creates a memory allocation module HOB describing the memory occupied by a loaded image.
+    pub fn new(image: &LoadedImage, memory_type: r_efi::efi::MemoryType) -> Self {
+        MemoryAllocationModule {
+            header: header::Hob {
+                r#type: MEMORY_ALLOCATION,
+                length: size_of::<MemoryAllocationModule>() as u16,
+                reserved: 0,
+            },
+            alloc_descriptor: header::MemoryAllocation {
+                name: HOB_MEMORY_ALLOC_MODULE_GUID,
+                memory_base_address: image.image_base,
+                memory_length: image.image_size,
+                memory_type,
+                reserved: [0; 4],
+            },
+            module_name: image.file_name,
+            entry_point: image.entry_point,
+        }
+    }
+
+    /// Returns the kind of the allocation, from its name, which is [`MemoryAllocationKind::Module`] for well-formed
+    /// HOBs.
+    pub fn kind(&self) -> MemoryAllocationKind {
+        MemoryAllocationKind::from_name(&self.alloc_descriptor.name)
+    }
+
+    /// Returns the name of the module GUID if it is well-known, e.g. `DxeCore` (with the `guid_names` feature).
+    pub fn module_label(&self) -> Option<&'static str> {
+        known_guid_label(&self.module_name)
+    }
+}
+
 //
 // Value of ResourceType in EFI_HOB_RESOURCE_DESCRIPTOR.
 //
@@ -472,6 +594,50 @@ pub struct GuidHob {
     //
 }
 
+impl GuidHob {
+    /// Serializes a GUID extension HOB carrying the given payload.
+    ///
+    /// The returned buffer holds the HOB header, the GUID name and the payload, zero-padded so that the HOB length is
+    /// a multiple of 8 bytes and the next HOB in the list stays 8-byte aligned.
+    ///
+    /// Returns `BAD_BUFFER_SIZE` if the padded HOB would not fit the 16-bit HOB length field, and `INVALID_PARAMETER`
+    /// if the payload requires an alignment greater than the 8-byte alignment guaranteed by the HOB list.
+    ///
+    /// ## Example
+    /// ```
+    /// use mu_pi::hob::{GuidHob, GUID_EXTENSION};
+    /// use r_efi::efi;
+    ///
+    /// let name = efi::Guid::from_fields(1, 2, 3, 4, 5, &[6, 7, 8, 9, 10, 11]);
+    /// let hob = GuidHob::new_with_payload(name, &[0xa5u8; 5]).unwrap();
+    /// assert_eq!(hob.len(), 32);
+    /// assert_eq!(u16::from_le_bytes([hob[0], hob[1]]), GUID_EXTENSION);
+    /// assert_eq!(u16::from_le_bytes([hob[2], hob[3]]), 32);
+    /// ```
+    #[cfg(feature = "alloc")]
+    pub fn new_with_payload<T: AsBytes + ?Sized>(
+        name: r_efi::base::Guid,
+        payload: &T,
+    ) -> Result<Box<[u8]>, efi::Status> {
+        if mem::align_of_val(payload) > GUID_HOB_PAYLOAD_ALIGNMENT {
+            return Err(efi::Status::INVALID_PARAMETER);
+        }
+
+        let payload = payload.as_bytes();
+        let length = align_up((size_of::<GuidHob>() + payload.len()) as u64, GUID_HOB_PAYLOAD_ALIGNMENT as u64);
+        let length: u16 = length.try_into().map_err(|_| efi::Status::BAD_BUFFER_SIZE)?;
+
+        let hob = GuidHob { header: header::Hob { r#type: GUID_EXTENSION, length, reserved: 0 }, name };
+
+        let mut buffer = vec![0u8; length as usize];
+        // Safety: GuidHob is repr(C) with no padding, so all of its bytes are initialized.
+        let hob_bytes = unsafe { slice::from_raw_parts(&hob as *const GuidHob as *const u8, size_of::<GuidHob>()) };
+        buffer[..size_of::<GuidHob>()].copy_from_slice(hob_bytes);
+        buffer[size_of::<GuidHob>()..size_of::<GuidHob>() + payload.len()].copy_from_slice(payload);
+        Ok(buffer.into_boxed_slice())
+    }
+}
+
 /// Details the location of firmware volumes that contain firmware files.
 ///
 #[repr(C)]
@@ -587,6 +753,9 @@ pub struct Cpu {
 /// sometime after the UEFI UpdateCapsule service invocation with the
 /// CAPSULE_FLAGS_POPULATE_SYSTEM_TABLE flag set in the EFI_CAPSULE_HEADER.
 ///
+/// The base address is an EFI_PHYSICAL_ADDRESS and the length a UINT64, as in EFI_HOB_UEFI_CAPSULE, which makes
+/// the HOB 24 bytes long and keeps the HOB that follows it 8-byte aligned.
+///
 #[repr(C)]
 #[derive(Copy, Clone, Debug)]
 pub struct Capsule {
@@ -597,16 +766,20 @@ pub struct Capsule {
 
     /// The physical memory-mapped base address of an UEFI capsule. This value is set to
     /// point to the base of the contiguous memory of the UEFI capsule.
+    ///
+    pub base_address: EfiPhysicalAddress,
+
     /// The length of the contiguous memory in bytes.
     ///
-    pub base_address: u8,
-    pub length: u8,
+    pub length: u64,
 }
 
 /// Represents a HOB list.
 ///
+#[cfg(feature = "alloc")]
 pub struct HobList<'a>(Vec<Hob<'a>>);
 
+#[cfg(feature = "alloc")]
 impl Default for HobList<'_> {
     fn default() -> Self {
         HobList::new()
@@ -627,7 +800,9 @@ pub enum Hob<'a> {
     FirmwareVolume2(&'a FirmwareVolume2),
     FirmwareVolume3(&'a FirmwareVolume3),
     Cpu(&'a Cpu),
-    Misc(u16),
+    /// An UNUSED HOB or a HOB with a type not defined by the PI specification, along with its raw contents (the bytes
+    /// following the generic HOB header). These HOBs are platform-specific, so their contents are preserved as-is.
+    Vendor(&'a header::Hob, &'a [u8]),
 }
 
 pub trait HobTrait {
@@ -650,7 +825,7 @@ impl HobTrait for Hob<'_> {
             Hob::FirmwareVolume2(_) => size_of::<FirmwareVolume2>(),
             Hob::FirmwareVolume3(_) => size_of::<FirmwareVolume3>(),
             Hob::Cpu(_) => size_of::<Cpu>(),
-            Hob::Misc(_) => size_of::<u16>(),
+            Hob::Vendor(hob, _) => hob.length as usize,
         }
     }
 
@@ -667,9 +842,98 @@ impl HobTrait for Hob<'_> {
             Hob::FirmwareVolume2(hob) => *hob as *const FirmwareVolume2 as *const _,
             Hob::FirmwareVolume3(hob) => *hob as *const FirmwareVolume3 as *const _,
             Hob::Cpu(hob) => *hob as *const Cpu as *const _,
-            Hob::Misc(hob) => *hob as *const u16 as *const _,
+            Hob::Vendor(hob, _) => *hob as *const header::Hob as *const _,
+        }
+    }
+}
+
+/// Errors detected while walking a HOB list.
+///
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum HobError {
+    /// The HOB list does not start on an 8-byte boundary.
+    MisalignedList,
+    /// The buffer ended before an END_OF_HOB_LIST HOB was found.
+    UnexpectedEndOfBuffer { offset: usize },
+    /// A HOB header declared a length of zero, which would never advance to the next HOB.
+    ZeroLength { offset: usize },
+    /// A HOB header declared a length that is not a multiple of 8 bytes.
+    MisalignedLength { offset: usize, length: u16 },
+    /// A HOB header declared a length that runs past the end of the buffer.
+    LengthExceedsBuffer { offset: usize, length: u16, remaining: usize },
+    /// A HOB header declared a length that does not match the structure defined for its type.
+    InvalidLength { offset: usize, hob_type: u16, length: u16 },
+}
+
+impl fmt::Display for HobError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HobError::MisalignedList => write!(f, "HOB list is not 8-byte aligned"),
+            HobError::UnexpectedEndOfBuffer { offset } => {
+                write!(f, "HOB list ended at offset {offset:#x} without an END_OF_HOB_LIST HOB")
+            }
+            HobError::ZeroLength { offset } => write!(f, "HOB at offset {offset:#x} has zero length"),
+            HobError::MisalignedLength { offset, length } => {
+                write!(f, "HOB at offset {offset:#x} has length {length:#x} which is not a multiple of 8")
+            }
+            HobError::LengthExceedsBuffer { offset, length, remaining } => write!(
+                f,
+                "HOB at offset {offset:#x} has length {length:#x} but only {remaining:#x} bytes remain in the HOB list"
+            ),
+            HobError::InvalidLength { offset, hob_type, length } => {
+                write!(f, "HOB at offset {offset:#x} of type {hob_type:#x} has invalid length {length:#x}")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for HobError {}
+
+// Validates the length of the HOB at `offset`, given the number of bytes `remaining` in the HOB list from `offset`.
+fn validate_hob_length(
+    hob: &header::Hob,
+    offset: usize,
+    remaining: usize,
+    options: &ParseOptions,
+    deviations: &mut dyn FnMut(Deviation),
+) -> Result<(), HobError> {
+    let length = hob.length as usize;
+    if length == 0 {
+        Err(HobError::ZeroLength { offset })?;
+    }
+    if length % 8 != 0 {
+        Err(HobError::MisalignedLength { offset, length: hob.length })?;
+    }
+    if length > remaining {
+        Err(HobError::LengthExceedsBuffer { offset, length: hob.length, remaining })?;
+    }
+
+    // (minimum length, whether any longer length is a spec deviation)
+    let (min_length, fixed_size) = match hob.r#type {
+        HANDOFF => (size_of::<PhaseHandoffInformationTable>(), true),
+        MEMORY_ALLOCATION if length >= size_of::<MemoryAllocationModule>() => {
+            (size_of::<MemoryAllocationModule>(), true)
         }
+        MEMORY_ALLOCATION => (size_of::<MemoryAllocation>(), true),
+        RESOURCE_DESCRIPTOR => (size_of::<ResourceDescriptor>(), true),
+        GUID_EXTENSION => (size_of::<GuidHob>(), false),
+        FV => (size_of::<FirmwareVolume>(), true),
+        FV2 => (size_of::<FirmwareVolume2>(), true),
+        FV3 => (size_of::<FirmwareVolume3>(), true),
+        CPU => (size_of::<Cpu>(), true),
+        UEFI_CAPSULE => (size_of::<Capsule>(), true),
+        _ => (size_of::<header::Hob>(), false),
+    };
+    let invalid_length = HobError::InvalidLength { offset, hob_type: hob.r#type, length: hob.length };
+    if length < min_length {
+        Err(invalid_length)?;
+    }
+    if fixed_size && length > min_length {
+        let deviation = Deviation::HobOversized { offset, hob_type: hob.r#type, length: hob.length };
+        options.tolerate(deviation, deviations).map_err(|_| invalid_length)?;
     }
+    Ok(())
 }
 
 /// Calculates the total size of a HOB list in bytes.
@@ -696,8 +960,9 @@ impl HobTrait for Hob<'_> {
 /// use core::ffi::c_void;
 ///
 /// // Assuming `hob_list` is a valid pointer to a HOB list
-/// # let some_val = 0;
-/// # let hob_list = &some_val as *const _ as *const c_void;
+/// # use mu_pi::hob::{header, END_OF_HOB_LIST};
+/// # let end = header::Hob { r#type: END_OF_HOB_LIST, length: core::mem::size_of::<header::Hob>() as u16, reserved: 0 };
+/// # let hob_list = &end as *const _ as *const c_void;
 /// let hob_list_ptr: *const c_void = hob_list;
 /// let size = unsafe { get_c_hob_list_size(hob_list_ptr) };
 /// println!("HOB list size: {}", size);
@@ -708,6 +973,13 @@ pub unsafe fn get_c_hob_list_size(hob_list: *const c_void) -> usize {
 
     loop {
         let current_header = unsafe { hob_header.cast::<header::Hob>().as_ref().expect("Could not get hob list len") };
+        // Only the lengths matter when sizing the list, so oversized HOBs are not an error here.
+        let options = ParseOptions::PERMISSIVE;
+        if let Err(err) =
+            validate_hob_length(current_header, hob_list_len, usize::MAX - hob_list_len, &options, &mut |_| ())
+        {
+            panic!("Could not get hob list len: {err}");
+        }
         hob_list_len += current_header.length as usize;
         if current_header.r#type == END_OF_HOB_LIST {
             break;
@@ -719,6 +991,7 @@ pub unsafe fn get_c_hob_list_size(hob_list: *const c_void) -> usize {
     hob_list_len
 }
 
+#[cfg(feature = "alloc")]
 impl<'a> HobList<'a> {
     /// Instantiates a Hoblist.
     pub const fn new() -> Self {
@@ -888,17 +1161,46 @@ impl<'a> HobList<'a> {
     /// }
     /// ```
     pub fn discover_hobs(&mut self, hob_list: *const c_void) {
+        self.discover_hobs_with_options(hob_list, &ParseOptions::default());
+    }
+
+    /// Discovers hobs from a C style void* using the given parse options and adds them to a rust structure.
+    ///
+    /// Returns the spec deviations that were tolerated while discovering the HOBs. HOBs that are longer than the
+    /// structure for their type are only accepted if the options tolerate it; any trailing bytes are ignored.
+    ///
+    /// # Example(s)
+    ///
+    /// ```no_run
+    /// use core::ffi::c_void;
+    /// use mu_pi::{hob::HobList, parse::ParseOptions};
+    ///
+    /// fn example(hob_list: *const c_void) {
+    ///     let mut the_hob_list = HobList::default();
+    ///     for deviation in the_hob_list.discover_hobs_with_options(hob_list, &ParseOptions::COMPATIBLE) {
+    ///         println!("{deviation}");
+    ///     }
+    /// }
+    /// ```
+    pub fn discover_hobs_with_options(&mut self, hob_list: *const c_void, options: &ParseOptions) -> Vec<Deviation> {
         const NOT_NULL: &str = "Ptr should not be NULL";
         fn assert_hob_size<T>(hob: &header::Hob) {
             let hob_len = hob.length as usize;
             let hob_size = mem::size_of::<T>();
-            assert_eq!(hob_len, hob_size, "Trying to cast hob of length {hob_len} into a pointer of size {hob_size}");
+            assert!(hob_len >= hob_size, "Trying to cast hob of length {hob_len} into a pointer of size {hob_size}");
         }
 
         let mut hob_header: *const header::Hob = hob_list as *const header::Hob;
+        let mut offset = 0;
+        let mut deviations = Vec::new();
 
         loop {
             let current_header = unsafe { hob_header.cast::<header::Hob>().as_ref().expect(NOT_NULL) };
+            if let Err(err) =
+                validate_hob_length(current_header, offset, usize::MAX - offset, options, &mut |d| deviations.push(d))
+            {
+                panic!("Malformed HOB list: {err}");
+            }
             match current_header.r#type {
                 HANDOFF => {
                     assert_hob_size::<PhaseHandoffInformationTable>(current_header);
@@ -907,7 +1209,7 @@ impl<'a> HobList<'a> {
                     self.0.push(Hob::Handoff(phit_hob));
                 }
                 MEMORY_ALLOCATION => {
-                    if current_header.length == mem::size_of::<MemoryAllocationModule>() as u16 {
+                    if current_header.length as usize >= mem::size_of::<MemoryAllocationModule>() {
                         let mem_alloc_hob =
                             unsafe { hob_header.cast::<MemoryAllocationModule>().as_ref().expect(NOT_NULL) };
                         self.0.push(Hob::MemoryAllocationModule(mem_alloc_hob));
@@ -926,7 +1228,7 @@ impl<'a> HobList<'a> {
                 GUID_EXTENSION => {
                     let (guid_hob, data) = unsafe {
                         let hob = hob_header.cast::<GuidHob>().as_ref().expect(NOT_NULL);
-                        let data_ptr = hob_header.byte_add(mem::size_of::<GuidHob>()) as *mut u8;
+                        let data_ptr = (hob_header as *const u8).add(mem::size_of::<GuidHob>());
                         let data_len = hob.header.length as usize - mem::size_of::<GuidHob>();
                         (hob, slice::from_raw_parts(data_ptr, data_len))
                     };
@@ -961,12 +1263,117 @@ impl<'a> HobList<'a> {
                     break;
                 }
                 _ => {
-                    self.0.push(Hob::Misc(current_header.r#type));
+                    let data = unsafe {
+                        let data_ptr = (hob_header as *const u8).add(mem::size_of::<header::Hob>());
+                        let data_len = current_header.length as usize - mem::size_of::<header::Hob>();
+                        slice::from_raw_parts(data_ptr, data_len)
+                    };
+                    self.0.push(Hob::Vendor(current_header, data));
                 }
             }
+            offset += current_header.length as usize;
             let next_hob = hob_header as usize + current_header.length as usize;
             hob_header = next_hob as *const header::Hob;
         }
+        deviations
+    }
+
+    /// Discovers hobs from a buffer containing a HOB list and adds them to a rust structure.
+    ///
+    /// Unlike [`HobList::discover_hobs`], every HOB header is validated against the bounds of `buffer` before it is
+    /// used, so a malformed HOB list (for example, a HOB with a zero or misaligned length) is reported as an error
+    /// instead of causing a panic or an endless walk. No HOBs are added to the list if an error is returned.
+    ///
+    /// # Example(s)
+    ///
+    /// ```no_run
+    /// use mu_pi::hob::HobList;
+    ///
+    /// fn example(hob_list: &[u8]) {
+    ///     let mut the_hob_list = HobList::default();
+    ///     if let Err(err) = the_hob_list.try_discover_hobs(hob_list) {
+    ///         println!("malformed hob list: {err}");
+    ///     }
+    /// }
+    /// ```
+    pub fn try_discover_hobs(&mut self, buffer: &'a [u8]) -> Result<(), HobError> {
+        self.try_discover_hobs_with_options(buffer, &ParseOptions::default()).map(|_| ())
+    }
+
+    /// Discovers hobs from a buffer containing a HOB list using the given parse options.
+    ///
+    /// Behaves like [`HobList::try_discover_hobs`], and returns the spec deviations that were tolerated while
+    /// discovering the HOBs.
+    ///
+    /// # Example(s)
+    ///
+    /// ```no_run
+    /// use mu_pi::{hob::HobList, parse::ParseOptions};
+    ///
+    /// fn example(hob_list: &[u8]) {
+    ///     let mut the_hob_list = HobList::default();
+    ///     match the_hob_list.try_discover_hobs_with_options(hob_list, &ParseOptions::COMPATIBLE) {
+    ///         Ok(deviations) => deviations.iter().for_each(|deviation| println!("{deviation}")),
+    ///         Err(err) => println!("malformed hob list: {err}"),
+    ///     }
+    /// }
+    /// ```
+    pub fn try_discover_hobs_with_options(
+        &mut self,
+        buffer: &'a [u8],
+        options: &ParseOptions,
+    ) -> Result<Vec<Deviation>, HobError> {
+        if buffer.as_ptr() as usize % 8 != 0 {
+            Err(HobError::MisalignedList)?;
+        }
+
+        let mut offset = 0;
+        let mut deviations = Vec::new();
+        loop {
+            let remaining = buffer.len() - offset;
+            if remaining < size_of::<header::Hob>() {
+                Err(HobError::UnexpectedEndOfBuffer { offset })?;
+            }
+            // Safety: buffer is 8-byte aligned, HOB lengths are multiples of 8, and there is room for a header.
+            let current_header = unsafe { &*(buffer[offset..].as_ptr() as *const header::Hob) };
+            validate_hob_length(current_header, offset, remaining, options, &mut |d| deviations.push(d))?;
+            if current_header.r#type == END_OF_HOB_LIST {
+                break;
+            }
+            offset += current_header.length as usize;
+        }
+
+        // deviations were already collected by the validation pass above.
+        self.discover_hobs_with_options(buffer.as_ptr() as *const c_void, options);
+        Ok(deviations)
+    }
+
+    /// Discovers hobs from a buffer containing a HOB list using the given parse options, reporting the tolerated spec
+    /// deviations to `diagnostics`.
+    ///
+    /// # Example(s)
+    ///
+    /// ```no_run
+    /// use mu_pi::{hob::HobList, parse::ParseOptions};
+    ///
+    /// fn example(hob_list: &[u8]) {
+    ///     let mut the_hob_list = HobList::default();
+    ///     let mut report = |diagnostic| println!("{diagnostic}");
+    ///     let result = the_hob_list.try_discover_hobs_with_diagnostics(hob_list, &ParseOptions::COMPATIBLE, &mut report);
+    ///     if let Err(err) = result {
+    ///         println!("malformed hob list: {err}");
+    ///     }
+    /// }
+    /// ```
+    pub fn try_discover_hobs_with_diagnostics(
+        &mut self,
+        buffer: &'a [u8],
+        options: &ParseOptions,
+        diagnostics: &mut dyn FnMut(Diagnostic),
+    ) -> Result<(), HobError> {
+        let deviations = self.try_discover_hobs_with_options(buffer, options)?;
+        deviations.into_iter().for_each(|deviation| diagnostics(Diagnostic::Deviation(deviation)));
+        Ok(())
     }
 
     /// Relocates all HOBs in the list to new memory locations.
@@ -1078,7 +1485,7 @@ impl<'a> HobList<'a> {
                     });
                     Hob::Cpu(Box::leak(new_hob))
                 }
-                Hob::Misc(hob_type) => Hob::Misc(*hob_type),
+                Hob::Vendor(hob, data) => Hob::Vendor(Box::leak(Box::new(**hob)), data),
             };
             new_hobs.push(new_hob);
         }
@@ -1089,6 +1496,7 @@ impl<'a> HobList<'a> {
 /// Implements IntoIterator for HobList.
 ///
 /// Defines how it will be converted to an iterator.
+#[cfg(feature = "alloc")]
 impl<'a> IntoIterator for HobList<'a> {
     type Item = Hob<'a>;
     type IntoIter = <Vec<Hob<'a>> as IntoIterator>::IntoIter;
@@ -1098,10 +1506,26 @@ impl<'a> IntoIterator for HobList<'a> {
     }
 }
 
+// Formats a GUID followed by its label, if it has one.
+#[cfg(feature = "alloc")]
+struct LabeledGuid<'a>(&'a efi::Guid, Option<&'static str>);
+
+#[cfg(feature = "alloc")]
+impl fmt::Display for LabeledGuid<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self.0)?;
+        if let Some(label) = self.1 {
+            write!(f, " ({label})")?;
+        }
+        Ok(())
+    }
+}
+
 /// Implements Debug for Hoblist.
 ///
 /// Writes Hoblist debug information to stdio
 ///
+#[cfg(feature = "alloc")]
 impl fmt::Debug for HobList<'_> {
     #[cfg_attr(feature = "nightly", feature(no_coverage))]
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -1136,15 +1560,36 @@ impl fmt::Debug for HobList<'_> {
                         indoc! {"
                         MEMORY ALLOCATION HOB
                           HOB Length: 0x{:x}
+                          Name: {}
                           Memory Base Address: 0x{:x}
                           Memory Length: 0x{:x}
                           Memory Type: {:?}\n"},
                         hob.header.length,
+                        LabeledGuid(&hob.alloc_descriptor.name, hob.kind().label()),
                         hob.alloc_descriptor.memory_base_address,
                         hob.alloc_descriptor.memory_length,
                         hob.alloc_descriptor.memory_type
                     )?;
                 }
+                Hob::MemoryAllocationModule(hob) => {
+                    write!(
+                        f,
+                        indoc! {"
+                        MEMORY ALLOCATION MODULE HOB
+                          HOB Length: 0x{:x}
+                          Memory Base Address: 0x{:x}
+                          Memory Length: 0x{:x}
+                          Memory Type: {:?}
+                          Module Name: {}
+                          Entry Point: 0x{:x}\n"},
+                        hob.header.length,
+                        hob.alloc_descriptor.memory_base_address,
+                        hob.alloc_descriptor.memory_length,
+                        hob.alloc_descriptor.memory_type,
+                        LabeledGuid(&hob.module_name, hob.module_label()),
+                        hob.entry_point
+                    )?;
+                }
                 Hob::ResourceDescriptor(hob) => {
                     write!(
                         f,
@@ -1222,7 +1667,16 @@ impl fmt::Debug for HobList<'_> {
                         hob.base_address, hob.length
                     )?;
                 }
-                _ => (),
+                Hob::Vendor(hob, _data) => {
+                    write!(
+                        f,
+                        indoc! {"
+                        VENDOR HOB
+                          HOB Type: 0x{:x}
+                          HOB Length: 0x{:x}\n"},
+                        hob.r#type, hob.length
+                    )?;
+                }
             }
         }
         write!(f, "Parsed HOBs")
@@ -1242,9 +1696,7 @@ impl Hob<'_> {
             Hob::FirmwareVolume2(hob) => hob.header,
             Hob::FirmwareVolume3(hob) => hob.header,
             Hob::Cpu(hob) => hob.header,
-            Hob::Misc(hob_type) => {
-                header::Hob { r#type: *hob_type, length: mem::size_of::<header::Hob>() as u16, reserved: 0 }
-            }
+            Hob::Vendor(hob, _) => **hob,
         }
     }
 }
@@ -1272,12 +1724,17 @@ impl<'a> Iterator for HobIter<'a> {
     fn next(&mut self) -> Option<Self::Item> {
         const NOT_NULL: &str = "Ptr should not be NULL";
         let hob_header = unsafe { *(self.hob_ptr) };
+        // A malformed length would make the walk wander or never advance, so end the iteration instead. Oversized HOBs
+        // were already subject to the parse options when the list was discovered.
+        if validate_hob_length(&hob_header, 0, usize::MAX, &ParseOptions::PERMISSIVE, &mut |_| ()).is_err() {
+            return None;
+        }
         let hob = unsafe {
             match hob_header.r#type {
                 HANDOFF => {
                     Hob::Handoff((self.hob_ptr as *const PhaseHandoffInformationTable).as_ref().expect(NOT_NULL))
                 }
-                MEMORY_ALLOCATION if hob_header.length as usize == mem::size_of::<MemoryAllocationModule>() => {
+                MEMORY_ALLOCATION if hob_header.length as usize >= mem::size_of::<MemoryAllocationModule>() => {
                     Hob::MemoryAllocationModule(
                         (self.hob_ptr as *const MemoryAllocationModule).as_ref().expect(NOT_NULL),
                     )
@@ -1290,7 +1747,7 @@ impl<'a> Iterator for HobIter<'a> {
                 }
                 GUID_EXTENSION => {
                     let hob = (self.hob_ptr as *const GuidHob).as_ref().expect(NOT_NULL);
-                    let data_ptr = self.hob_ptr.byte_add(mem::size_of::<GuidHob>()) as *const u8;
+                    let data_ptr = (self.hob_ptr as *const u8).add(mem::size_of::<GuidHob>());
                     let data_len = hob.header.length as usize - mem::size_of::<GuidHob>();
                     Hob::GuidHob(hob, slice::from_raw_parts(data_ptr, data_len))
                 }
@@ -1300,7 +1757,12 @@ impl<'a> Iterator for HobIter<'a> {
                 CPU => Hob::Cpu((self.hob_ptr as *const Cpu).as_ref().expect(NOT_NULL)),
                 UEFI_CAPSULE => Hob::Capsule((self.hob_ptr as *const Capsule).as_ref().expect(NOT_NULL)),
                 END_OF_HOB_LIST => return None,
-                hob_type => Hob::Misc(hob_type),
+                _ => {
+                    let hob = self.hob_ptr.as_ref().expect(NOT_NULL);
+                    let data_ptr = (self.hob_ptr as *const u8).add(mem::size_of::<header::Hob>());
+                    let data_len = hob.length as usize - mem::size_of::<header::Hob>();
+                    Hob::Vendor(hob, slice::from_raw_parts(data_ptr, data_len))
+                }
             }
         };
         self.hob_ptr = (self.hob_ptr as usize + hob_header.length as usize) as *const header::Hob;
@@ -1327,6 +1789,8 @@ mod tests {
     use crate::{
         hob,
         hob::{Hob, HobList, HobTrait},
+        image::LoadedImage,
+        parse::{Deviation, Diagnostic, ParseOptions},
         BootMode,
     };
 
@@ -1804,4 +2268,301 @@ mod tests {
 
         manually_free_c_array(c_array_hoblist, length);
     }
+
+    #[test]
+    fn test_guid_hob_payload() {
+        let name = r_efi::efi::Guid::from_fields(1, 2, 3, 4, 5, &[6, 7, 8, 9, 10, 11]);
+
+        // payload is padded so the hob length stays 8-byte aligned.
+        let hob_bytes = hob::GuidHob::new_with_payload(name, &[0xa5u8; 5]).unwrap();
+        assert_eq!(hob_bytes.len(), size_of::<hob::GuidHob>() + 8);
+        let guid_hob = unsafe { (hob_bytes.as_ptr() as *const hob::GuidHob).read_unaligned() };
+        assert_eq!(guid_hob.header.r#type, hob::GUID_EXTENSION);
+        assert_eq!(guid_hob.header.length as usize, hob_bytes.len());
+        assert_eq!(guid_hob.name, name);
+        assert_eq!(&hob_bytes[size_of::<hob::GuidHob>()..], &[0xa5, 0xa5, 0xa5, 0xa5, 0xa5, 0, 0, 0]);
+
+        // empty and already-aligned payloads need no padding.
+        assert_eq!(hob::GuidHob::new_with_payload(name, &[0u8; 0]).unwrap().len(), size_of::<hob::GuidHob>());
+        assert_eq!(hob::GuidHob::new_with_payload(name, &0x1234u64).unwrap().len(), size_of::<hob::GuidHob>() + 8);
+
+        // largest payload that still fits in a 16-bit, 8-byte aligned length.
+        let max_payload = vec![0u8; 0xFFF8 - size_of::<hob::GuidHob>()];
+        assert_eq!(hob::GuidHob::new_with_payload(name, max_payload.as_slice()).unwrap().len(), 0xFFF8);
+
+        // one more byte would need padding past u16::MAX.
+        let too_big = vec![0u8; 0xFFF8 - size_of::<hob::GuidHob>() + 1];
+        assert_eq!(
+            hob::GuidHob::new_with_payload(name, too_big.as_slice()).unwrap_err(),
+            r_efi::efi::Status::BAD_BUFFER_SIZE
+        );
+
+        // payloads requiring more than 8-byte alignment are rejected.
+        assert_eq!(
+            hob::GuidHob::new_with_payload(name, &[0u128; 1]).unwrap_err(),
+            r_efi::efi::Status::INVALID_PARAMETER
+        );
+    }
+
+    // Builds an 8-byte aligned HOB list buffer from (type, length) headers, each followed by zeroed HOB contents.
+    fn gen_raw_hob_list(headers: &[(u16, u16)]) -> Vec<u64> {
+        let mut buffer = Vec::new();
+        for &(r#type, length) in headers {
+            let header = hob::header::Hob { r#type, length, reserved: 0 };
+            let header = unsafe { core::mem::transmute::<hob::header::Hob, u64>(header) };
+            buffer.push(header);
+            buffer.extend(core::iter::repeat(0).take((length as usize).saturating_sub(8) / 8));
+        }
+        buffer
+    }
+
+    fn as_bytes(buffer: &[u64]) -> &[u8] {
+        unsafe { from_raw_parts(buffer.as_ptr() as *const u8, core::mem::size_of_val(buffer)) }
+    }
+
+    #[test]
+    fn test_try_discover_hobs() {
+        let cpu_length = size_of::<hob::Cpu>() as u16;
+        let guid_length = size_of::<hob::GuidHob>() as u16 + 8;
+        let buffer = gen_raw_hob_list(&[
+            (hob::CPU, cpu_length),
+            (hob::GUID_EXTENSION, guid_length),
+            (0x8000, 8),
+            (hob::END_OF_HOB_LIST, 8),
+        ]);
+
+        let mut hoblist = HobList::new();
+        hoblist.try_discover_hobs(as_bytes(&buffer)).unwrap();
+        assert_eq!(hoblist.len(), 3);
+        assert!(matches!(hoblist.iter().nth(1), Some(Hob::GuidHob(_, data)) if data.len() == 8));
+        assert!(
+            matches!(hoblist.iter().nth(2), Some(Hob::Vendor(hob, data)) if hob.r#type == 0x8000 && data.is_empty())
+        );
+    }
+
+    #[test]
+    fn test_try_discover_hobs_malformed() {
+        let cpu_length = size_of::<hob::Cpu>() as u16;
+
+        let cases: &[(&[(u16, u16)], hob::HobError)] = &[
+            (
+                &[(hob::CPU, cpu_length), (0x8000, 0), (hob::END_OF_HOB_LIST, 8)],
+                hob::HobError::ZeroLength { offset: 16 },
+            ),
+            (&[(hob::CPU, cpu_length), (0x8000, 12)], hob::HobError::MisalignedLength { offset: 16, length: 12 }),
+            (&[(hob::CPU, cpu_length)], hob::HobError::UnexpectedEndOfBuffer { offset: 16 }),
+            (
+                &[(hob::CPU, 8), (hob::END_OF_HOB_LIST, 8)],
+                hob::HobError::InvalidLength { offset: 0, hob_type: hob::CPU, length: 8 },
+            ),
+            (
+                &[(hob::GUID_EXTENSION, 16), (hob::END_OF_HOB_LIST, 8)],
+                hob::HobError::InvalidLength { offset: 0, hob_type: hob::GUID_EXTENSION, length: 16 },
+            ),
+        ];
+
+        for (headers, expected) in cases {
+            let buffer = gen_raw_hob_list(headers);
+            let mut hoblist = HobList::new();
+            assert_eq!(hoblist.try_discover_hobs(as_bytes(&buffer)).unwrap_err(), *expected);
+            assert!(hoblist.is_empty());
+        }
+
+        // a HOB that claims to extend past the end of the buffer.
+        let mut buffer = gen_raw_hob_list(&[(hob::CPU, cpu_length), (hob::END_OF_HOB_LIST, 8)]);
+        buffer[2] = unsafe {
+            core::mem::transmute::<hob::header::Hob, u64>(hob::header::Hob {
+                r#type: hob::END_OF_HOB_LIST,
+                length: 0x100,
+                reserved: 0,
+            })
+        };
+        assert_eq!(
+            HobList::new().try_discover_hobs(as_bytes(&buffer)).unwrap_err(),
+            hob::HobError::LengthExceedsBuffer { offset: 16, length: 0x100, remaining: 8 }
+        );
+
+        // a HOB list that does not start on an 8-byte boundary.
+        let buffer = gen_raw_hob_list(&[(hob::END_OF_HOB_LIST, 8), (hob::END_OF_HOB_LIST, 8)]);
+        let misaligned = &as_bytes(&buffer)[4..];
+        assert_eq!(HobList::new().try_discover_hobs(misaligned).unwrap_err(), hob::HobError::MisalignedList);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_hob_error_is_std_error() {
+        fn discover(buffer: &[u8]) -> Result<usize, Box<dyn std::error::Error>> {
+            let mut hoblist = HobList::new();
+            hoblist.try_discover_hobs(buffer)?;
+            Ok(hoblist.len())
+        }
+
+        let buffer = gen_raw_hob_list(&[(hob::CPU, size_of::<hob::Cpu>() as u16), (0x8000, 0)]);
+        let error = discover(as_bytes(&buffer)).unwrap_err();
+        assert_eq!(error.to_string(), "HOB at offset 0x10 has zero length");
+    }
+
+    #[test]
+    #[should_panic(expected = "zero length")]
+    fn test_discover_hobs_zero_length_should_panic() {
+        let buffer = gen_raw_hob_list(&[(hob::CPU, size_of::<hob::Cpu>() as u16), (0x8000, 0)]);
+        HobList::new().discover_hobs(buffer.as_ptr() as *const c_void);
+    }
+
+    #[test]
+    fn test_hob_iterator_stops_on_malformed_length() {
+        let buffer = gen_raw_hob_list(&[(hob::CPU, size_of::<hob::Cpu>() as u16), (0x8000, 0), (0x8001, 8)]);
+        let cpu = Hob::Cpu(unsafe { (buffer.as_ptr() as *const hob::Cpu).as_ref().unwrap() });
+        assert_eq!((&cpu).into_iter().count(), 1);
+    }
+
+    #[test]
+    fn test_try_discover_hobs_with_options() {
+        let cpu_length = size_of::<hob::Cpu>() as u16;
+        let buffer = gen_raw_hob_list(&[(hob::CPU, cpu_length + 8), (hob::END_OF_HOB_LIST, 8)]);
+        let invalid_length = hob::HobError::InvalidLength { offset: 0, hob_type: hob::CPU, length: cpu_length + 8 };
+
+        let mut hoblist = HobList::new();
+        assert_eq!(hoblist.try_discover_hobs(as_bytes(&buffer)).unwrap_err(), invalid_length);
+        assert_eq!(
+            hoblist.try_discover_hobs_with_options(as_bytes(&buffer), &ParseOptions::STRICT).unwrap_err(),
+            invalid_length
+        );
+        assert!(hoblist.is_empty());
+
+        let deviations = hoblist.try_discover_hobs_with_options(as_bytes(&buffer), &ParseOptions::COMPATIBLE).unwrap();
+        assert_eq!(deviations, [Deviation::HobOversized { offset: 0, hob_type: hob::CPU, length: cpu_length + 8 }]);
+        assert_eq!(hoblist.len(), 1);
+        assert!(matches!(hoblist.iter().next(), Some(Hob::Cpu(_))));
+
+        let mut hoblist = HobList::new();
+        let deviations = hoblist.try_discover_hobs_with_options(as_bytes(&buffer), &ParseOptions::PERMISSIVE).unwrap();
+        assert!(deviations.is_empty());
+        assert_eq!(hoblist.len(), 1);
+
+        let mut diagnostics = Vec::new();
+        HobList::new()
+            .try_discover_hobs_with_diagnostics(as_bytes(&buffer), &ParseOptions::COMPATIBLE, &mut |diagnostic| {
+                diagnostics.push(diagnostic)
+            })
+            .unwrap();
+        assert_eq!(
+            diagnostics,
+            [Diagnostic::Deviation(Deviation::HobOversized { offset: 0, hob_type: hob::CPU, length: cpu_length + 8 })]
+        );
+    }
+
+    #[test]
+    fn test_memory_allocation_module_from_loaded_image() {
+        let image = LoadedImage {
+            file_name: r_efi::efi::Guid::from_bytes(&[0x5a; 16]),
+            image_base: 0x7f000000,
+            image_size: 0x40000,
+            entry_point: 0x7f001000,
+            authentication_state: 0,
+        };
+        let module_hob = hob::MemoryAllocationModule::new(&image, r_efi::efi::BOOT_SERVICES_CODE);
+        assert_eq!(module_hob.alloc_descriptor.name, hob::HOB_MEMORY_ALLOC_MODULE_GUID);
+        assert_eq!(module_hob.alloc_descriptor.memory_base_address, image.image_base);
+        assert_eq!(module_hob.alloc_descriptor.memory_length, image.image_size);
+        assert_eq!(module_hob.module_name, image.file_name);
+
+        let mut hoblist = HobList::new();
+        let end_of_hob_list = gen_end_of_hoblist();
+        hoblist.push(Hob::MemoryAllocationModule(&module_hob));
+        hoblist.push(Hob::Handoff(&end_of_hob_list));
+        let (c_array_hoblist, length) = to_c_array(&hoblist);
+        let mut discovered = HobList::new();
+        discovered.discover_hobs(c_array_hoblist);
+        match discovered.iter().next() {
+            Some(Hob::MemoryAllocationModule(hob)) => assert_eq!(hob.entry_point, image.entry_point),
+            _ => panic!("expected a memory allocation module HOB"),
+        }
+        manually_free_c_array(c_array_hoblist, length);
+    }
+
+    #[test]
+    fn capsule_hobs_should_use_pi_field_widths() {
+        assert_eq!(size_of::<hob::Capsule>(), 24);
+
+        // a capsule above 4 GiB, followed by a HOB that must stay 8-byte aligned.
+        let mut buffer = gen_raw_hob_list(&[(hob::UEFI_CAPSULE, 24), (0x8000, 16), (hob::END_OF_HOB_LIST, 8)]);
+        buffer[1] = 0x1_2345_6000;
+        buffer[2] = 0x1_0000_0000;
+
+        let mut hoblist = HobList::new();
+        hoblist.try_discover_hobs(as_bytes(&buffer)).unwrap();
+        let mut hobs = hoblist.iter();
+        match hobs.next() {
+            Some(Hob::Capsule(capsule)) => {
+                assert_eq!(capsule.base_address, 0x1_2345_6000);
+                assert_eq!(capsule.length, 0x1_0000_0000);
+            }
+            otherwise_bad => panic!("unexpected hob: {:x?}", otherwise_bad),
+        }
+        assert!(matches!(hobs.next(), Some(Hob::Vendor(hob, _)) if hob.r#type == 0x8000));
+    }
+
+    #[test]
+    fn test_vendor_hobs_preserve_payload() {
+        let mut buffer = gen_raw_hob_list(&[(0x8000, 24), (hob::UNUSED, 16), (hob::END_OF_HOB_LIST, 8)]);
+        buffer[1] = 0x0123456789abcdef;
+        buffer[2] = 0xfedcba9876543210;
+        buffer[4] = 0xa5a5a5a5a5a5a5a5;
+
+        let mut hoblist = HobList::new();
+        hoblist.try_discover_hobs(as_bytes(&buffer)).unwrap();
+        assert_eq!(hoblist.len(), 2);
+
+        {
+            let mut hobs = hoblist.iter();
+            match hobs.next() {
+                Some(Hob::Vendor(hob, data)) => {
+                    assert_eq!(hob.r#type, 0x8000);
+                    assert_eq!(*data, as_bytes(&buffer[1..3]));
+                }
+                otherwise_bad => panic!("unexpected hob: {:x?}", otherwise_bad),
+            }
+            match hobs.next() {
+                Some(Hob::Vendor(hob, data)) => {
+                    assert_eq!(hob.r#type, hob::UNUSED);
+                    assert_eq!(*data, as_bytes(&buffer[4..5]));
+                }
+                otherwise_bad => panic!("unexpected hob: {:x?}", otherwise_bad),
+            }
+        }
+
+        // serializing the list reproduces the raw vendor HOBs.
+        let (c_array_hoblist, length) = to_c_array(&hoblist);
+        assert_eq!(unsafe { from_raw_parts(c_array_hoblist as *const u8, length) }, as_bytes(&buffer[..5]));
+        manually_free_c_array(c_array_hoblist, length);
+
+        hoblist.relocate_hobs();
+        assert!(
+            matches!(hoblist.iter().next(), Some(Hob::Vendor(hob, data)) if hob.r#type == 0x8000 && data.len() == 16)
+        );
+    }
+
+    #[test]
+    fn memory_allocations_should_be_labeled_by_kind() {
+        let mut stack = gen_memory_allocation();
+        assert_eq!(
+            stack.kind(),
+            hob::MemoryAllocationKind::Other(r_efi::efi::Guid::from_fields(1, 2, 3, 4, 5, &[6, 7, 8, 9, 10, 11]))
+        );
+        stack.alloc_descriptor.name = hob::HOB_MEMORY_ALLOC_STACK_GUID;
+        assert_eq!(stack.kind(), hob::MemoryAllocationKind::Stack);
+        let mut unnamed = gen_memory_allocation();
+        unnamed.alloc_descriptor.name = r_efi::efi::Guid::from_bytes(&[0; 16]);
+        assert_eq!(unnamed.kind(), hob::MemoryAllocationKind::Unnamed);
+        assert_eq!(unnamed.kind().label(), None);
+        let module = gen_memory_allocation_module();
+        assert_eq!(module.kind(), hob::MemoryAllocationKind::Other(module.alloc_descriptor.name));
+
+        let mut hoblist = HobList::new();
+        hoblist.push(Hob::MemoryAllocation(&stack));
+        let dump = format!("{:?}", hoblist);
+        let name = format!("Name: {:?} (Stack)\n", hob::HOB_MEMORY_ALLOC_STACK_GUID);
+        assert!(dump.contains(&name), "{dump}");
+    }
 }
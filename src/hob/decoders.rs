@@ -0,0 +1,136 @@
+//! GUID HOB Decoders
+//!
+//! GUID HOBs carry payloads whose format is only known to the platform that produced them. A [`GuidHobDecoders`]
+//! registry maps the name of a GUID HOB to a decoder that turns its payload into a [`DecodedValue`], which tools render
+//! in dumps and diffs (see [`report::HobEntry`](crate::report::HobEntry)). Platform crates register decoders for their
+//! own GUID HOBs without changes to this crate.
+//!
+//! ## Example
+//! ```
+//! use mu_pi::hob::decoders::{DecodedValue, GuidHobDecoders};
+//! use r_efi::efi;
+//!
+//! const PLATFORM_INFO_GUID: efi::Guid =
+//!   efi::Guid::from_fields(0x5f2a3c41, 0x9e1d, 0x4b6a, 0x8c, 0x07, &[0x1d, 0x2e, 0x3f, 0x40, 0x51, 0x62]);
+//!
+//! // The payload of the platform info HOB is a u32 board id followed by a u32 revision.
+//! fn decode_platform_info(payload: &[u8]) -> Option<DecodedValue> {
+//!   let field = |offset: usize| payload.get(offset..offset + 4).map(|x| u32::from_le_bytes(x.try_into().unwrap()));
+//!   Some(DecodedValue::Map(vec![
+//!     ("board_id".into(), DecodedValue::Integer(field(0)?.into())),
+//!     ("revision".into(), DecodedValue::Integer(field(4)?.into())),
+//!   ]))
+//! }
+//!
+//! let mut decoders = GuidHobDecoders::new();
+//! decoders.register(PLATFORM_INFO_GUID, decode_platform_info);
+//! let decoded = decoders.decode_payload(&PLATFORM_INFO_GUID, &[7, 0, 0, 0, 2, 0, 0, 0]).unwrap();
+//! assert_eq!(
+//!   decoded,
+//!   DecodedValue::Map(vec![
+//!     ("board_id".into(), DecodedValue::Integer(7)),
+//!     ("revision".into(), DecodedValue::Integer(2)),
+//!   ])
+//! );
+//! ```
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+use alloc::{string::String, vec::Vec};
+use core::fmt;
+use r_efi::efi;
+
+use super::Hob;
+
+/// A decoded GUID HOB payload, or a part of one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DecodedValue {
+    Bool(bool),
+    Integer(u64),
+    Text(String),
+    /// A GUID, serialized as a registry format GUID string.
+    Guid(#[cfg_attr(feature = "serde", serde(with = "crate::capture::guid_serde::required"))] efi::Guid),
+    Bytes(Vec<u8>),
+    List(Vec<DecodedValue>),
+    /// Named fields, in the order they are rendered.
+    Map(Vec<(String, DecodedValue)>),
+}
+
+/// Decodes the payload of a GUID HOB, the bytes that follow its name. Returns `None` if the payload is malformed.
+pub type GuidHobDecoder = fn(&[u8]) -> Option<DecodedValue>;
+
+/// A registry of decoders for GUID HOB payloads, by the name of the GUID HOB.
+#[derive(Clone, Default)]
+pub struct GuidHobDecoders {
+    decoders: Vec<(efi::Guid, GuidHobDecoder)>,
+}
+
+impl GuidHobDecoders {
+    /// Creates an empty registry.
+    pub fn new() -> Self {
+        Self { decoders: Vec::new() }
+    }
+
+    /// Registers the decoder for the GUID HOBs named `guid`, returning the decoder it replaces, if any.
+    pub fn register(&mut self, guid: efi::Guid, decoder: GuidHobDecoder) -> Option<GuidHobDecoder> {
+        match self.decoders.iter_mut().find(|(name, _)| *name == guid) {
+            Some((_, registered)) => Some(core::mem::replace(registered, decoder)),
+            None => {
+                self.decoders.push((guid, decoder));
+                None
+            }
+        }
+    }
+
+    /// Returns the decoder registered for the GUID HOBs named `guid`.
+    pub fn get(&self, guid: &efi::Guid) -> Option<GuidHobDecoder> {
+        self.decoders.iter().find(|(name, _)| name == guid).map(|(_, decoder)| *decoder)
+    }
+
+    /// Decodes the payload of a GUID HOB named `guid`. Returns `None` if no decoder is registered for it, or the
+    /// decoder rejects the payload.
+    pub fn decode_payload(&self, guid: &efi::Guid, payload: &[u8]) -> Option<DecodedValue> {
+        self.get(guid)?(payload)
+    }
+
+    /// Decodes a HOB. Returns `None` if it is not a GUID HOB, no decoder is registered for it, or the decoder rejects
+    /// its payload.
+    pub fn decode(&self, hob: &Hob) -> Option<DecodedValue> {
+        match hob {
+            Hob::GuidHob(hob, payload) => self.decode_payload(&hob.name, payload),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Debug for GuidHobDecoders {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.decoders.iter().map(|(guid, _)| guid)).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NAME: efi::Guid =
+        efi::Guid::from_fields(0x1234abcd, 0x5678, 0x9abc, 0xde, 0xf0, &[0x01, 0x23, 0x45, 0x67, 0x89, 0xab]);
+
+    #[test]
+    fn registering_a_decoder_should_replace_the_previous_one() {
+        let mut decoders = GuidHobDecoders::new();
+        assert!(decoders.register(NAME, |payload| Some(DecodedValue::Bytes(payload.to_vec()))).is_none());
+        assert_eq!(decoders.decode_payload(&NAME, &[1, 2]), Some(DecodedValue::Bytes(alloc::vec![1, 2])));
+
+        let previous = decoders.register(NAME, |payload| Some(DecodedValue::Integer(payload.len() as u64)));
+        assert_eq!(previous.unwrap()(&[3]), Some(DecodedValue::Bytes(alloc::vec![3])));
+        assert_eq!(decoders.decode_payload(&NAME, &[1, 2]), Some(DecodedValue::Integer(2)));
+        assert_eq!(decoders.decode_payload(&efi::Guid::from_bytes(&[0; 16]), &[1, 2]), None);
+    }
+}
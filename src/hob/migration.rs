@@ -0,0 +1,406 @@
+//! HOB List Migration to Permanent Memory
+//!
+//! Until permanent memory is installed, the PEI Foundation runs from temporary RAM (e.g. cache-as-RAM), which holds the
+//! HOB list along with the stack and heap of the PEIMs. When permanent memory is installed with InstallPeiMemory(), the
+//! contents of temporary RAM are copied to permanent memory, the HOB list is rebuilt there with every address that
+//! refers to temporary RAM rewritten, and the
+//! [Permanent Memory Installed PPI](crate::ppis::permanent_memory_installed) is installed. An address that is missed
+//! keeps pointing at temporary RAM, which stops existing once it is torn down, so the step is notoriously bug-prone.
+//!
+//! [`MemoryMigration`] models the switch: [`MemoryMigration::migrate`] produces the migrated HOB list and its updated
+//! PHIT, and [`MemoryMigration::verify`] checks that a HOB list no longer refers to temporary RAM.
+//!
+//! ## Example
+//! ```
+//! use mu_pi::{
+//!     hob::{self, migration::MemoryMigration, header, Hob, HobList, PhaseHandoffInformationTable},
+//!     BootMode,
+//! };
+//!
+//! let temporary_ram = 0xfef0_0000..0xff00_0000;
+//! let phit = PhaseHandoffInformationTable {
+//!     header: header::Hob { r#type: hob::HANDOFF, length: 56, reserved: 0 },
+//!     version: 0x9,
+//!     boot_mode: BootMode::BootWithFullConfiguration,
+//!     memory_top: temporary_ram.end,
+//!     memory_bottom: temporary_ram.start,
+//!     free_memory_top: 0xfef8_0000,
+//!     free_memory_bottom: 0xfef0_1000,
+//!     end_of_hob_list: 0xfef0_0038,
+//! };
+//! let mut hob_list = HobList::new();
+//! hob_list.push(Hob::Handoff(&phit));
+//! assert!(MemoryMigration::new(temporary_ram.clone(), 0..0x100_0000).unwrap().verify(&hob_list).is_err());
+//!
+//! let migration = MemoryMigration::new(temporary_ram, 0x8000_0000..0x8400_0000).unwrap();
+//! let (migrated, phit) = migration.migrate(&hob_list).unwrap();
+//! assert_eq!(phit.memory_bottom, 0x8000_0000);
+//! assert_eq!(phit.end_of_hob_list, 0x8000_0038);
+//! assert_eq!(migration.verify(&migrated), Ok(()));
+//! ```
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+use alloc::boxed::Box;
+use core::{fmt, mem::size_of, ops::Range};
+
+use r_efi::efi;
+
+use super::{
+    header, Capsule, EfiPhysicalAddress, FirmwareVolume, FirmwareVolume2, FirmwareVolume3, GuidHob, Hob, HobList,
+    HobTrait, MemoryAllocation, MemoryAllocationModule, PhaseHandoffInformationTable,
+};
+
+/// The switch of a HOB producer from temporary RAM to permanent memory.
+///
+/// The contents of temporary RAM are copied to the top of permanent memory, so an address in temporary RAM is
+/// translated to the same offset from the start of the copy. The migrated HOB list is placed at the bottom of permanent
+/// memory, and the memory in between is free.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MemoryMigration {
+    temporary_ram: Range<EfiPhysicalAddress>,
+    permanent_memory: Range<EfiPhysicalAddress>,
+}
+
+/// A reference to temporary RAM found by [`MemoryMigration::verify`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StaleReference {
+    /// The index of the HOB in the HOB list.
+    pub index: usize,
+    /// The address in temporary RAM that the HOB is stored at or refers to.
+    pub address: EfiPhysicalAddress,
+}
+
+impl fmt::Display for StaleReference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "HOB {} refers to temporary RAM at {:#x}", self.index, self.address)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for StaleReference {}
+
+impl MemoryMigration {
+    /// Describes the migration from the given temporary RAM to the given permanent memory.
+    ///
+    /// Returns `INVALID_PARAMETER` if either range is empty or if they overlap, and `BUFFER_TOO_SMALL` if permanent
+    /// memory cannot hold a copy of temporary RAM.
+    pub fn new(
+        temporary_ram: Range<EfiPhysicalAddress>,
+        permanent_memory: Range<EfiPhysicalAddress>,
+    ) -> Result<Self, efi::Status> {
+        if temporary_ram.is_empty()
+            || permanent_memory.is_empty()
+            || (temporary_ram.start < permanent_memory.end && permanent_memory.start < temporary_ram.end)
+        {
+            Err(efi::Status::INVALID_PARAMETER)?;
+        }
+        if permanent_memory.end - permanent_memory.start < temporary_ram.end - temporary_ram.start {
+            Err(efi::Status::BUFFER_TOO_SMALL)?;
+        }
+        Ok(Self { temporary_ram, permanent_memory })
+    }
+
+    /// Returns the temporary RAM range.
+    pub fn temporary_ram(&self) -> Range<EfiPhysicalAddress> {
+        self.temporary_ram.clone()
+    }
+
+    /// Returns the permanent memory range.
+    pub fn permanent_memory(&self) -> Range<EfiPhysicalAddress> {
+        self.permanent_memory.clone()
+    }
+
+    /// Returns the address in permanent memory that the contents of temporary RAM are copied to.
+    pub fn destination(&self) -> EfiPhysicalAddress {
+        self.permanent_memory.end - (self.temporary_ram.end - self.temporary_ram.start)
+    }
+
+    /// Translates an address in temporary RAM to the address of its copy in permanent memory. Other addresses are
+    /// returned unchanged.
+    pub fn translate(&self, address: EfiPhysicalAddress) -> EfiPhysicalAddress {
+        if self.temporary_ram.contains(&address) {
+            self.destination() + (address - self.temporary_ram.start)
+        } else {
+            address
+        }
+    }
+
+    // Translates the exclusive end of a range, which is in temporary RAM when the range ends at its end.
+    fn translate_end(&self, end: EfiPhysicalAddress) -> EfiPhysicalAddress {
+        if end > self.temporary_ram.start && end <= self.temporary_ram.end {
+            self.translate(end - 1) + 1
+        } else {
+            end
+        }
+    }
+
+    /// Migrates a HOB list to permanent memory, and returns the migrated list with its updated PHIT.
+    ///
+    /// The HOBs are copied out of the given list, as [`HobList::relocate_hobs`] does, along with the data of GUID HOBs,
+    /// and the base addresses of memory allocations, module entry points, firmware volumes and capsules that are in
+    /// temporary RAM are translated. Resource descriptors describe physical memory rather than its contents and are
+    /// left as they are, as are the contents of GUID HOBs, whose format is unknown.
+    ///
+    /// The PHIT of the migrated list describes permanent memory: the list ends at the bottom of permanent memory, and
+    /// free memory extends from the end-of-list HOB to the copy of temporary RAM.
+    ///
+    /// Returns `INVALID_PARAMETER` if the list does not start with a PHIT, and `OUT_OF_RESOURCES` if the list does not
+    /// fit below the copy of temporary RAM.
+    pub fn migrate(
+        &self,
+        hob_list: &HobList<'_>,
+    ) -> Result<(HobList<'static>, PhaseHandoffInformationTable), efi::Status> {
+        let Some(Hob::Handoff(phit)) = hob_list.iter().next() else {
+            return Err(efi::Status::INVALID_PARAMETER);
+        };
+
+        let end_of_hob_list = self.permanent_memory.start + hob_list.size() as u64;
+        let free_memory_bottom = end_of_hob_list + size_of::<header::Hob>() as u64;
+        if free_memory_bottom > self.destination() {
+            Err(efi::Status::OUT_OF_RESOURCES)?;
+        }
+        let phit = PhaseHandoffInformationTable {
+            memory_top: self.permanent_memory.end,
+            memory_bottom: self.permanent_memory.start,
+            free_memory_top: self.destination(),
+            free_memory_bottom,
+            end_of_hob_list,
+            ..**phit
+        };
+
+        let mut migrated = HobList::new();
+        migrated.push(Hob::Handoff(Box::leak(Box::new(phit))));
+        for hob in hob_list.iter().skip(1) {
+            let hob = match hob {
+                Hob::Handoff(hob) => Hob::Handoff(Box::leak(Box::new(**hob))),
+                Hob::MemoryAllocation(hob) => {
+                    let mut hob = MemoryAllocation { header: hob.header, alloc_descriptor: hob.alloc_descriptor };
+                    hob.alloc_descriptor.memory_base_address = self.translate(hob.alloc_descriptor.memory_base_address);
+                    Hob::MemoryAllocation(Box::leak(Box::new(hob)))
+                }
+                Hob::MemoryAllocationModule(hob) => {
+                    let mut hob = MemoryAllocationModule {
+                        header: hob.header,
+                        alloc_descriptor: hob.alloc_descriptor,
+                        module_name: hob.module_name,
+                        entry_point: self.translate(hob.entry_point),
+                    };
+                    hob.alloc_descriptor.memory_base_address = self.translate(hob.alloc_descriptor.memory_base_address);
+                    Hob::MemoryAllocationModule(Box::leak(Box::new(hob)))
+                }
+                Hob::Capsule(hob) => Hob::Capsule(Box::leak(Box::new(Capsule {
+                    header: hob.header,
+                    base_address: self.translate(hob.base_address),
+                    length: hob.length,
+                }))),
+                Hob::ResourceDescriptor(hob) => Hob::ResourceDescriptor(Box::leak(Box::new(**hob))),
+                Hob::GuidHob(hob, data) => Hob::GuidHob(
+                    Box::leak(Box::new(GuidHob { header: hob.header, name: hob.name })),
+                    Box::leak(data.to_vec().into_boxed_slice()),
+                ),
+                Hob::FirmwareVolume(hob) => Hob::FirmwareVolume(Box::leak(Box::new(FirmwareVolume {
+                    header: hob.header,
+                    base_address: self.translate(hob.base_address),
+                    length: hob.length,
+                }))),
+                Hob::FirmwareVolume2(hob) => Hob::FirmwareVolume2(Box::leak(Box::new(FirmwareVolume2 {
+                    base_address: self.translate(hob.base_address),
+                    ..**hob
+                }))),
+                Hob::FirmwareVolume3(hob) => Hob::FirmwareVolume3(Box::leak(Box::new(FirmwareVolume3 {
+                    base_address: self.translate(hob.base_address),
+                    ..**hob
+                }))),
+                Hob::Cpu(hob) => Hob::Cpu(Box::leak(Box::new(**hob))),
+                Hob::Vendor(hob, data) => {
+                    Hob::Vendor(Box::leak(Box::new(**hob)), Box::leak(data.to_vec().into_boxed_slice()))
+                }
+            };
+            migrated.push(hob);
+        }
+        Ok((migrated, phit))
+    }
+
+    /// Verifies that no HOB of a migrated HOB list is stored in temporary RAM or refers to it.
+    ///
+    /// The addresses checked are those [`MemoryMigration::migrate`] translates, the addresses of the PHIT, and the
+    /// locations of the HOBs and of the data of GUID HOBs. Returns the first reference to temporary RAM found.
+    pub fn verify(&self, hob_list: &HobList<'_>) -> Result<(), StaleReference> {
+        for (index, hob) in hob_list.iter().enumerate() {
+            let stale = |address: EfiPhysicalAddress| self.temporary_ram.contains(&address);
+            let stale_end = |end: EfiPhysicalAddress| self.translate_end(end) != end;
+            let mut addresses = [None; 5];
+            addresses[0] = Some(hob.as_ptr::<u8>() as EfiPhysicalAddress);
+            match hob {
+                Hob::Handoff(hob) => {
+                    addresses[1] = Some(hob.memory_bottom);
+                    addresses[2] = Some(hob.free_memory_bottom);
+                    addresses[3] = Some(hob.end_of_hob_list);
+                    for end in [hob.memory_top, hob.free_memory_top] {
+                        if stale_end(end) {
+                            return Err(StaleReference { index, address: end });
+                        }
+                    }
+                }
+                Hob::MemoryAllocation(hob) => addresses[1] = Some(hob.alloc_descriptor.memory_base_address),
+                Hob::MemoryAllocationModule(hob) => {
+                    addresses[1] = Some(hob.alloc_descriptor.memory_base_address);
+                    addresses[2] = Some(hob.entry_point);
+                }
+                Hob::Capsule(hob) => addresses[1] = Some(hob.base_address),
+                Hob::FirmwareVolume(hob) => addresses[1] = Some(hob.base_address),
+                Hob::FirmwareVolume2(hob) => addresses[1] = Some(hob.base_address),
+                Hob::FirmwareVolume3(hob) => addresses[1] = Some(hob.base_address),
+                Hob::GuidHob(_, data) | Hob::Vendor(_, data) if !data.is_empty() => {
+                    addresses[1] = Some(data.as_ptr() as EfiPhysicalAddress)
+                }
+                _ => (),
+            }
+            if let Some(address) = addresses.into_iter().flatten().find(|&address| stale(address)) {
+                return Err(StaleReference { index, address });
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{hob, BootMode};
+
+    const TEMPORARY_RAM: Range<u64> = 0xfef0_0000..0xff00_0000;
+    const PERMANENT_MEMORY: Range<u64> = 0x8000_0000..0x8400_0000;
+
+    fn header<T>(r#type: u16) -> header::Hob {
+        header::Hob { r#type, length: size_of::<T>() as u16, reserved: 0 }
+    }
+
+    fn allocation(name: efi::Guid, base: u64, length: u64) -> header::MemoryAllocation {
+        header::MemoryAllocation {
+            name,
+            memory_base_address: base,
+            memory_length: length,
+            memory_type: efi::BOOT_SERVICES_DATA,
+            reserved: [0; 4],
+        }
+    }
+
+    #[test]
+    fn migrations_should_rewrite_temporary_ram_addresses() {
+        let phit = PhaseHandoffInformationTable {
+            header: header::<PhaseHandoffInformationTable>(hob::HANDOFF),
+            version: 0x9,
+            boot_mode: BootMode::BootWithFullConfiguration,
+            memory_top: TEMPORARY_RAM.end,
+            memory_bottom: TEMPORARY_RAM.start,
+            free_memory_top: 0xfef8_0000,
+            free_memory_bottom: 0xfef0_1000,
+            end_of_hob_list: 0xfef0_0ff8,
+        };
+        let stack = MemoryAllocation {
+            header: header::<MemoryAllocation>(hob::MEMORY_ALLOCATION),
+            alloc_descriptor: allocation(hob::HOB_MEMORY_ALLOC_STACK_GUID, 0xfef8_0000, 0x8_0000),
+        };
+        let module = MemoryAllocationModule {
+            header: header::<MemoryAllocationModule>(hob::MEMORY_ALLOCATION),
+            alloc_descriptor: allocation(hob::HOB_MEMORY_ALLOC_MODULE_GUID, 0xfef0_2000, 0x1000),
+            module_name: efi::Guid::from_bytes(&[1; 16]),
+            entry_point: 0xfef0_2400,
+        };
+        let flash_fv =
+            FirmwareVolume { header: header::<FirmwareVolume>(hob::FV), base_address: 0xff80_0000, length: 0x10_0000 };
+        let guid_hob =
+            GuidHob { header: header::<GuidHob>(hob::GUID_EXTENSION), name: efi::Guid::from_bytes(&[2; 16]) };
+        let data = [1u8, 2, 3, 4, 5, 6, 7, 8];
+
+        let mut hob_list = HobList::new();
+        hob_list.push(Hob::Handoff(&phit));
+        hob_list.push(Hob::MemoryAllocation(&stack));
+        hob_list.push(Hob::MemoryAllocationModule(&module));
+        hob_list.push(Hob::FirmwareVolume(&flash_fv));
+        hob_list.push(Hob::GuidHob(&guid_hob, &data));
+
+        let migration = MemoryMigration::new(TEMPORARY_RAM, PERMANENT_MEMORY).unwrap();
+        assert_eq!(migration.destination(), 0x83f0_0000);
+        assert_eq!(migration.verify(&hob_list), Err(StaleReference { index: 0, address: TEMPORARY_RAM.end }));
+
+        let (migrated, new_phit) = migration.migrate(&hob_list).unwrap();
+        assert_eq!(migration.verify(&migrated), Ok(()));
+        assert_eq!(new_phit.memory_bottom, PERMANENT_MEMORY.start);
+        assert_eq!(new_phit.memory_top, PERMANENT_MEMORY.end);
+        assert_eq!(new_phit.end_of_hob_list, PERMANENT_MEMORY.start + hob_list.size() as u64);
+        assert_eq!(new_phit.free_memory_bottom, new_phit.end_of_hob_list + 8);
+        assert_eq!(new_phit.free_memory_top, 0x83f0_0000);
+        assert_eq!(new_phit.version, 0x9);
+
+        let hobs = migrated.iter().collect::<Vec<_>>();
+        assert_eq!(hobs.len(), 5);
+        match hobs[0] {
+            Hob::Handoff(hob) => assert_eq!(hob.end_of_hob_list, new_phit.end_of_hob_list),
+            hob => panic!("unexpected HOB {hob:?}"),
+        }
+        match hobs[1] {
+            Hob::MemoryAllocation(hob) => {
+                assert_eq!(hob.alloc_descriptor.memory_base_address, 0x83f8_0000);
+                assert_eq!(hob.alloc_descriptor.memory_length, 0x8_0000);
+            }
+            hob => panic!("unexpected HOB {hob:?}"),
+        }
+        match hobs[2] {
+            Hob::MemoryAllocationModule(hob) => {
+                assert_eq!(hob.alloc_descriptor.memory_base_address, 0x83f0_2000);
+                assert_eq!(hob.entry_point, 0x83f0_2400);
+            }
+            hob => panic!("unexpected HOB {hob:?}"),
+        }
+        match hobs[3] {
+            Hob::FirmwareVolume(hob) => assert_eq!(hob.base_address, 0xff80_0000),
+            hob => panic!("unexpected HOB {hob:?}"),
+        }
+        match hobs[4] {
+            Hob::GuidHob(hob, migrated_data) => {
+                assert_eq!(hob.name, guid_hob.name);
+                assert_eq!(*migrated_data, data);
+                assert_ne!(migrated_data.as_ptr(), data.as_ptr());
+            }
+            hob => panic!("unexpected HOB {hob:?}"),
+        }
+
+        // a missed address is reported.
+        let mut partial = HobList::new();
+        partial.push(hobs[0].clone());
+        partial.push(Hob::MemoryAllocation(&stack));
+        assert_eq!(migration.verify(&partial), Err(StaleReference { index: 1, address: 0xfef8_0000 }));
+    }
+
+    #[test]
+    fn migrations_should_validate_their_ranges() {
+        assert_eq!(MemoryMigration::new(0..0, PERMANENT_MEMORY), Err(efi::Status::INVALID_PARAMETER));
+        assert_eq!(MemoryMigration::new(TEMPORARY_RAM, 0xfe00_0000..0xfef8_0000), Err(efi::Status::INVALID_PARAMETER));
+        assert_eq!(MemoryMigration::new(TEMPORARY_RAM, 0x8000_0000..0x8000_1000), Err(efi::Status::BUFFER_TOO_SMALL));
+
+        // permanent memory only large enough for the copy of temporary RAM leaves no room for the HOB list.
+        let migration = MemoryMigration::new(TEMPORARY_RAM, 0x8000_0000..0x8010_0000).unwrap();
+        let mut hob_list = HobList::new();
+        assert_eq!(migration.migrate(&hob_list).err(), Some(efi::Status::INVALID_PARAMETER));
+        let phit = PhaseHandoffInformationTable {
+            header: header::<PhaseHandoffInformationTable>(hob::HANDOFF),
+            version: 0x9,
+            boot_mode: BootMode::BootWithFullConfiguration,
+            memory_top: TEMPORARY_RAM.end,
+            memory_bottom: TEMPORARY_RAM.start,
+            free_memory_top: TEMPORARY_RAM.end,
+            free_memory_bottom: TEMPORARY_RAM.start + 0x40,
+            end_of_hob_list: TEMPORARY_RAM.start + 0x38,
+        };
+        hob_list.push(Hob::Handoff(&phit));
+        assert_eq!(migration.migrate(&hob_list).err(), Some(efi::Status::OUT_OF_RESOURCES));
+    }
+}
@@ -0,0 +1,90 @@
+//! I2C Definitions
+//!
+//! Request packet structures and operation flags shared by the I2C master PPI and the I2C master protocol.
+//!
+//! See <https://uefi.org/specs/PI/1.8A/V5_I2C_Protocol_Stack.html>.
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+/// Define an I2C read operation. The operation is a write when this flag is clear.
+pub const I2C_FLAG_READ: u32 = 0x00000001;
+
+/// Define an SMBus operation. Only the write and read of the first operation are combined (I2C restart).
+pub const I2C_FLAG_SMBUS_OPERATION: u32 = 0x00010000;
+
+/// Define an SMBus block transfer. The first byte of the read or write data is the byte count.
+pub const I2C_FLAG_SMBUS_BLOCK: u32 = 0x00020000;
+
+/// Define an SMBus process call. The write is immediately followed by the read with an I2C restart.
+pub const I2C_FLAG_SMBUS_PROCESS_CALL: u32 = 0x00040000;
+
+/// Define an SMBus operation with packet error checking (PEC).
+pub const I2C_FLAG_SMBUS_PEC: u32 = 0x00080000;
+
+/// Set in the slave address to select 10-bit addressing, clear for 7-bit addressing.
+pub const I2C_ADDRESSING_10_BIT: u32 = 0x80000000;
+
+/// Describes a single I2C operation (a read or a write) within a request packet.
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Volume 5, EFI_I2C_OPERATION
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct Operation {
+    /// Flags qualifying the I2C operation (`I2C_FLAG_*`).
+    pub flags: u32,
+    /// Number of bytes to send to or receive from the I2C device.
+    pub length_in_bytes: u32,
+    /// Buffer containing the data to send, or receiving the data read from the device.
+    pub buffer: *mut u8,
+}
+
+/// A list of I2C operations that are performed as a single transaction.
+///
+/// The structure is followed in memory by `operation_count` [`Operation`] entries.
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Volume 5, EFI_I2C_REQUEST_PACKET
+#[repr(C)]
+#[derive(Debug)]
+pub struct RequestPacket {
+    /// Number of elements in the operation array.
+    pub operation_count: usize,
+    /// Description of the I2C operations.
+    pub operation: [Operation; 0],
+}
+
+/// Describes the limits of an I2C controller.
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Volume 5, EFI_I2C_CONTROLLER_CAPABILITIES
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ControllerCapabilities {
+    /// Length of this data structure in bytes.
+    pub structure_size_in_bytes: u32,
+    /// The maximum number of bytes the I2C host controller is able to receive from the I2C bus.
+    pub maximum_receive_bytes: u32,
+    /// The maximum number of bytes the I2C host controller is able to send on the I2C bus.
+    pub maximum_transmit_bytes: u32,
+    /// The maximum number of bytes in the I2C bus transaction.
+    pub maximum_total_bytes: u32,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::mem::size_of;
+
+    #[test]
+    fn request_packet_layout_should_match_c() {
+        assert_eq!(size_of::<Operation>(), 8 + size_of::<usize>());
+        assert_eq!(size_of::<RequestPacket>(), size_of::<usize>());
+        assert_eq!(size_of::<ControllerCapabilities>(), 16);
+    }
+}
@@ -0,0 +1,45 @@
+//! Loaded Image Hand-off
+//!
+//! Describes an image loaded by the PEI Foundation or the DXE IPL (e.g. through the EFI_PEI_LOAD_FILE_PPI), so that
+//! the load can be recorded in a memory allocation module HOB and published for debug support.
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+use r_efi::efi;
+
+/// The result of loading an image from a firmware file.
+///
+/// ## Example
+/// ```
+/// use mu_pi::{hob::MemoryAllocationModule, image::LoadedImage};
+/// use r_efi::efi;
+///
+/// # let dxe_core_file_name = efi::Guid::from_bytes(&[0x5a; 16]);
+/// let image = LoadedImage {
+///     file_name: dxe_core_file_name,
+///     image_base: 0x7f000000,
+///     image_size: 0x40000,
+///     entry_point: 0x7f001000,
+///     authentication_state: 0,
+/// };
+/// let hob = MemoryAllocationModule::new(&image, efi::BOOT_SERVICES_CODE);
+/// assert_eq!(hob.entry_point, 0x7f001000);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LoadedImage {
+    /// The name of the firmware file the image was loaded from.
+    pub file_name: efi::Guid,
+    /// The address at which the image was loaded.
+    pub image_base: efi::PhysicalAddress,
+    /// The size in bytes of the loaded image.
+    pub image_size: u64,
+    /// The address of the image entry point.
+    pub entry_point: efi::PhysicalAddress,
+    /// The authentication status of the image, as returned by EFI_PEI_LOAD_FILE_PPI.LoadFile().
+    pub authentication_state: u32,
+}
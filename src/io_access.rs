@@ -0,0 +1,108 @@
+//! I/O Access Definitions
+//!
+//! The access width used by the memory and I/O access services of the CPU I/O protocols (and the PCI root bridge I/O
+//! protocol, which uses the same encoding), along with helpers describing how `Count` elements are transferred for
+//! each width.
+//!
+//! See <https://uefi.org/specs/PI/1.8A/V5_CPU_IO_Protocol.html>.
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+/// The width of a memory or I/O access.
+///
+/// The `Uint*` widths transfer `Count` elements from incrementing addresses to an incrementing buffer. The `Fifo*`
+/// widths access the same address `Count` times, and the `Fill*` widths write the same buffer element `Count` times.
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Volume 5, EFI_CPU_IO_PROTOCOL_WIDTH
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Width {
+    Uint8,
+    Uint16,
+    Uint32,
+    Uint64,
+    FifoUint8,
+    FifoUint16,
+    FifoUint32,
+    FifoUint64,
+    FillUint8,
+    FillUint16,
+    FillUint32,
+    FillUint64,
+    Maximum,
+}
+
+impl Width {
+    /// Returns the size in bytes of a single element, or `None` for [`Width::Maximum`].
+    pub const fn element_size(self) -> Option<usize> {
+        match self {
+            Width::Uint8 | Width::FifoUint8 | Width::FillUint8 => Some(1),
+            Width::Uint16 | Width::FifoUint16 | Width::FillUint16 => Some(2),
+            Width::Uint32 | Width::FifoUint32 | Width::FillUint32 => Some(4),
+            Width::Uint64 | Width::FifoUint64 | Width::FillUint64 => Some(8),
+            Width::Maximum => None,
+        }
+    }
+
+    /// Returns the number of bytes the device address advances by after each element (zero for the `Fifo*` widths),
+    /// or `None` for [`Width::Maximum`].
+    pub const fn address_stride(self) -> Option<usize> {
+        match self {
+            Width::FifoUint8 | Width::FifoUint16 | Width::FifoUint32 | Width::FifoUint64 => Some(0),
+            _ => self.element_size(),
+        }
+    }
+
+    /// Returns the number of bytes the buffer advances by after each element (zero for the `Fill*` widths), or `None`
+    /// for [`Width::Maximum`].
+    pub const fn buffer_stride(self) -> Option<usize> {
+        match self {
+            Width::FillUint8 | Width::FillUint16 | Width::FillUint32 | Width::FillUint64 => Some(0),
+            _ => self.element_size(),
+        }
+    }
+
+    /// Returns the size in bytes of the buffer required to transfer `count` elements, or `None` for
+    /// [`Width::Maximum`] or if the size overflows.
+    pub const fn buffer_length(self, count: usize) -> Option<usize> {
+        match (self.element_size(), self.buffer_stride()) {
+            (Some(_), Some(_)) if count == 0 => Some(0),
+            (Some(size), Some(0)) => Some(size),
+            (Some(_), Some(stride)) => count.checked_mul(stride),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn width_strides() {
+        assert_eq!(Width::Uint32.element_size(), Some(4));
+        assert_eq!(Width::Uint32.address_stride(), Some(4));
+        assert_eq!(Width::Uint32.buffer_stride(), Some(4));
+        assert_eq!(Width::FifoUint16.address_stride(), Some(0));
+        assert_eq!(Width::FifoUint16.buffer_stride(), Some(2));
+        assert_eq!(Width::FillUint64.address_stride(), Some(8));
+        assert_eq!(Width::FillUint64.buffer_stride(), Some(0));
+        assert_eq!(Width::Maximum.element_size(), None);
+    }
+
+    #[test]
+    fn width_buffer_length() {
+        assert_eq!(Width::Uint16.buffer_length(8), Some(16));
+        assert_eq!(Width::FifoUint8.buffer_length(8), Some(8));
+        assert_eq!(Width::FillUint32.buffer_length(8), Some(4));
+        assert_eq!(Width::FillUint32.buffer_length(0), Some(0));
+        assert_eq!(Width::Uint64.buffer_length(usize::MAX), None);
+        assert_eq!(Width::Maximum.buffer_length(1), None);
+    }
+}
@@ -0,0 +1,226 @@
+//! Layout Verification
+//!
+//! Verifies the size and field offsets of the `#[repr(C)]` definitions of this crate against the layouts mandated by
+//! the PI and UEFI specifications (and EDK II for definitions the specifications do not cover), so that a change to a
+//! definition cannot silently break the ABI. New `#[repr(C)]` definitions should be added here.
+//!
+//! The expected values are those of 64-bit targets (x86_64 and aarch64 share the same layout rules), so the checks are
+//! only run on 64-bit targets.
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+#![cfg(target_pointer_width = "64")]
+
+use core::{mem, ptr};
+
+// Asserts the size of a type and the offsets of the given fields. Works with packed structs and with types that have
+// no valid all-zero value, as no reference to the fields is created.
+macro_rules! assert_layout {
+    ($type:ty, size: $size:expr $(, $field:ident: $offset:expr)* $(,)?) => {{
+        assert_eq!(mem::size_of::<$type>(), $size, concat!("size of ", stringify!($type)));
+        #[allow(unused_variables)]
+        let value = mem::MaybeUninit::<$type>::uninit();
+        $(
+            let offset = unsafe { ptr::addr_of!((*value.as_ptr()).$field) } as usize - value.as_ptr() as usize;
+            assert_eq!(offset, $offset, concat!("offset of ", stringify!($type), "::", stringify!($field)));
+        )*
+    }};
+}
+
+#[test]
+fn hob_layouts_should_match_spec() {
+    use crate::hob::{self, header};
+
+    assert_layout!(header::Hob, size: 8, r#type: 0, length: 2, reserved: 4);
+    assert_layout!(header::MemoryAllocation, size: 40,
+        name: 0, memory_base_address: 16, memory_length: 24, memory_type: 32, reserved: 36);
+    assert_layout!(hob::PhaseHandoffInformationTable, size: 56,
+        header: 0, version: 8, boot_mode: 12, memory_top: 16, memory_bottom: 24, free_memory_top: 32,
+        free_memory_bottom: 40, end_of_hob_list: 48);
+    assert_layout!(hob::MemoryAllocation, size: 48, header: 0, alloc_descriptor: 8);
+    assert_layout!(hob::MemoryAllocationModule, size: 72,
+        header: 0, alloc_descriptor: 8, module_name: 48, entry_point: 64);
+    assert_layout!(hob::ResourceDescriptor, size: 48,
+        header: 0, owner: 8, resource_type: 24, resource_attribute: 28, physical_start: 32, resource_length: 40);
+    assert_layout!(hob::GuidHob, size: 24, header: 0, name: 8);
+    assert_layout!(hob::FirmwareVolume, size: 24, header: 0, base_address: 8, length: 16);
+    assert_layout!(hob::FirmwareVolume2, size: 56,
+        header: 0, base_address: 8, length: 16, fv_name: 24, file_name: 40);
+    assert_layout!(hob::FirmwareVolume3, size: 64,
+        header: 0, base_address: 8, length: 16, authentication_status: 24, extracted_fv: 28, fv_name: 32,
+        file_name: 48);
+    assert_layout!(hob::Cpu, size: 16, header: 0, size_of_memory_space: 8, size_of_io_space: 9, reserved: 10);
+    assert_layout!(hob::Capsule, size: 24, header: 0, base_address: 8, length: 16);
+    assert_layout!(hob::EFiMemoryTypeInformation, size: 8, memory_type: 0, number_of_pages: 4);
+}
+
+#[test]
+#[cfg(feature = "fw_fs")]
+fn fv_and_ffs_layouts_should_match_spec() {
+    use crate::fw_fs::{
+        ffs::{file, section},
+        fv,
+    };
+
+    assert_layout!(fv::Header, size: 56,
+        zero_vector: 0, file_system_guid: 16, fv_length: 32, signature: 40, attributes: 44, header_length: 48,
+        checksum: 50, ext_header_offset: 52, reserved: 54, revision: 55, block_map: 56);
+    assert_layout!(fv::BlockMapEntry, size: 8, num_blocks: 0, length: 4);
+    assert_layout!(fv::ExtHeader, size: 20, fv_name: 0, ext_header_size: 16);
+    assert_layout!(fv::ExtEntryHeader, size: 4, ext_entry_size: 0, ext_entry_type: 2);
+
+    assert_layout!(file::Header, size: 24,
+        name: 0, integrity_check_header: 16, integrity_check_file: 17, file_type: 18, attributes: 19, size: 20,
+        state: 23);
+    assert_layout!(file::Header2, size: 32, header: 0, extended_size: 24);
+
+    assert_layout!(section::Header, size: 4, size: 0, section_type: 3);
+    assert_layout!(section::header::CommonSectionHeaderStandard, size: 4, size: 0, section_type: 3);
+    assert_layout!(section::header::CommonSectionHeaderExtended, size: 8, size: 0, section_type: 3, extended_size: 4);
+    assert_layout!(section::header::Compression, size: 5, uncompressed_length: 0, compression_type: 4);
+    assert_layout!(section::header::GuidDefined, size: 20, section_definition_guid: 0, data_offset: 16, attributes: 18);
+    assert_layout!(section::header::Version, size: 2, build_number: 0);
+    assert_layout!(section::header::FreeformSubtypeGuid, size: 16, sub_type_guid: 0);
+}
+
+#[test]
+fn table_layouts_should_match_spec() {
+    use crate::{i2c, list_entry, smbus, win_certificate};
+
+    #[cfg(feature = "pei")]
+    {
+        use crate::pei_core;
+
+        assert_layout!(pei_core::SecPeiHandOff, size: 72,
+            data_size: 0, boot_firmware_volume_base: 8, boot_firmware_volume_size: 16, temporary_ram_base: 24,
+            temporary_ram_size: 32, pei_temporary_ram_base: 40, pei_temporary_ram_size: 48, stack_base: 56,
+            stack_size: 64);
+        assert_layout!(pei_core::PpiDescriptor, size: 24, flags: 0, guid: 8, ppi: 16);
+    }
+    assert_layout!(list_entry::Entry, size: 16, forward_link: 0, back_link: 8);
+
+    #[cfg(feature = "fw_fs")]
+    {
+        use crate::variable_store;
+
+        assert_layout!(variable_store::StoreHeader, size: 28,
+            signature: 0, size: 16, format: 20, state: 21, reserved: 22, reserved1: 24);
+        assert_layout!(variable_store::Header, size: 32,
+            start_id: 0, state: 2, reserved: 3, attributes: 4, name_size: 8, data_size: 12, vendor_guid: 16);
+        assert_layout!(variable_store::AuthenticatedHeader, size: 64,
+            start_id: 0, state: 2, reserved: 3, attributes: 4, monotonic_count: 8, time_stamp: 16, pub_key_index: 32,
+            name_size: 36, data_size: 40, vendor_guid: 44);
+    }
+
+    assert_layout!(win_certificate::WinCertificate, size: 8, length: 0, revision: 4, certificate_type: 6);
+    assert_layout!(win_certificate::WinCertificateUefiGuid, size: 24, hdr: 0, cert_type: 8);
+    assert_layout!(win_certificate::WinCertificateEfiPkcs1_15, size: 24, hdr: 0, hash_algorithm: 8);
+    assert_layout!(win_certificate::FirmwareImageAuthentication, size: 32, monotonic_count: 0, auth_info: 8);
+
+    #[cfg(feature = "fw_fs")]
+    {
+        use crate::capsule;
+
+        assert_layout!(capsule::FmpCapsuleHeader, size: 8,
+            version: 0, embedded_driver_count: 4, payload_item_count: 6, item_offset_list: 8);
+        assert_layout!(capsule::FmpCapsuleImageHeader, size: 48,
+            version: 0, update_image_type_id: 4, update_image_index: 20, reserved_bytes: 21, update_image_size: 24,
+            update_vendor_code_size: 28, update_hardware_instance: 32, image_capsule_support: 40);
+    }
+
+    #[cfg(feature = "dxe")]
+    {
+        use crate::{debug_image_info, memory_attributes_table};
+
+        assert_layout!(memory_attributes_table::MemoryAttributesTableHeader, size: 16,
+            version: 0, number_of_entries: 4, descriptor_size: 8, flags: 12);
+        assert_layout!(memory_attributes_table::PropertiesTable, size: 16,
+            version: 0, length: 4, memory_protection_attribute: 8);
+
+        assert_layout!(debug_image_info::SystemTablePointer, size: 24,
+            signature: 0, efi_system_table_base: 8, crc32: 16);
+        assert_layout!(debug_image_info::DebugImageInfoNormal, size: 24,
+            image_info_type: 0, loaded_image_protocol_instance: 8, image_handle: 16);
+        assert_layout!(debug_image_info::DebugImageInfo, size: 8, image_info_type: 0, normal_image: 0);
+        assert_layout!(debug_image_info::DebugImageInfoTableHeader, size: 16,
+            update_status: 0, table_size: 4, efi_debug_image_info_table: 8);
+    }
+
+    assert_layout!(smbus::Udid, size: 16,
+        vendor_specific_id: 0, subsystem_device_id: 4, subsystem_vendor_id: 6, interface: 8, device_id: 10,
+        vendor_id: 12, vendor_revision: 14, device_capabilities: 15);
+    assert_layout!(smbus::DeviceMap, size: 24, smbus_device_address: 0, smbus_device_udid: 8);
+    assert_layout!(i2c::Operation, size: 16, flags: 0, length_in_bytes: 4, buffer: 8);
+    assert_layout!(i2c::RequestPacket, size: 8, operation_count: 0, operation: 8);
+    assert_layout!(i2c::ControllerCapabilities, size: 16,
+        structure_size_in_bytes: 0, maximum_receive_bytes: 4, maximum_transmit_bytes: 8, maximum_total_bytes: 12);
+}
+
+#[test]
+fn protocol_layouts_should_match_spec() {
+    use crate::protocols::*;
+
+    assert_layout!(status_code::EfiStatusCodeData, size: 20, header_size: 0, size: 2, r#type: 4);
+    assert_layout!(status_code::Protocol, size: 8, report_status_code: 0);
+
+    #[cfg(feature = "dxe")]
+    {
+        assert_layout!(bds::Protocol, size: 8, entry: 0);
+        assert_layout!(cpu_arch::Protocol, size: 72,
+            flush_data_cache: 0, enable_interrupt: 8, disable_interrupt: 16, get_interrupt_state: 24, init: 32,
+            register_interrupt_handler: 40, get_timer_value: 48, set_memory_attributes: 56, number_of_timers: 64,
+            dma_buffer_alignment: 68);
+        assert_layout!(cpu_io2::Access, size: 16, read: 0, write: 8);
+        assert_layout!(cpu_io2::Protocol, size: 32, mem: 0, io: 16);
+        #[cfg(feature = "fw_fs")]
+        {
+            assert_layout!(firmware_volume::EfiFvWriteFileData, size: 32);
+            assert_layout!(firmware_volume::Protocol, size: 80,
+                get_volume_attributes: 0, set_volume_attributes: 8, read_file: 16, read_section: 24, write_file: 32,
+                get_next_file: 40, key_size: 48, parent_handle: 56, get_info: 64, set_info: 72);
+            assert_layout!(firmware_volume_block::Protocol, size: 64,
+                get_attributes: 0, set_attributes: 8, get_physical_address: 16, get_block_size: 24, read: 32,
+                write: 40, erase_blocks: 48, parent_handle: 56);
+        }
+        assert_layout!(i2c_master::Protocol, size: 32,
+            set_bus_frequency: 0, reset: 8, start_request: 16, i2c_controller_capabilities: 24);
+        assert_layout!(metronome::Protocol, size: 16, wait_for_tick: 0, tick_period: 8);
+        assert_layout!(runtime::Protocol, size: 80,
+            image_head: 0, event_head: 16, memory_descriptor_size: 32, memory_descriptor_version: 40,
+            memory_map_size: 48, memory_map_physical: 56, memory_map_virtual: 64, virtual_mode: 72, at_runtime: 73);
+        assert_layout!(security::Protocol, size: 8, file_authentication_state: 0);
+        assert_layout!(security2::Protocol, size: 8, file_authentication: 0);
+        assert_layout!(smbus_hc::Protocol, size: 32, execute: 0, arp_device: 8, get_arp_map: 16, notify: 24);
+        assert_layout!(timer::Protocol, size: 32,
+            register_handler: 0, set_timer_period: 8, get_timer_period: 16, generate_soft_interrupt: 24);
+        assert_layout!(watchdog::Protocol, size: 24, register_handler: 0, set_timer_period: 8, get_timer_period: 16);
+    }
+
+    #[cfg(feature = "mm")]
+    {
+        assert_layout!(mm_communication::CommunicateHeader, size: 24, header_guid: 0, message_length: 16);
+        assert_layout!(mm_communication::Protocol, size: 8, communicate: 0);
+        assert_layout!(mm_communication2::Protocol, size: 8, communicate: 0);
+        assert_layout!(mm_communication3::CommunicateHeader, size: 56,
+            header_guid: 0, buffer_size: 16, reserved: 24, message_guid: 32, message_size: 48);
+        assert_layout!(mm_communication3::Protocol, size: 8, communicate: 0);
+    }
+}
+
+#[test]
+#[cfg(feature = "pei")]
+fn ppi_layouts_should_match_spec() {
+    use crate::ppis::*;
+
+    assert_layout!(i2c_master::Ppi, size: 48,
+        set_bus_frequency: 0, reset: 8, start_request: 16, i2c_controller_capabilities: 24, identifier: 32);
+    assert_layout!(load_file::Ppi, size: 8, load_file: 0);
+    assert_layout!(smbus2::Ppi, size: 48, execute: 0, arp_device: 8, get_arp_map: 16, notify: 24, identifier: 32);
+    assert_layout!(vector_handoff_info::Ppi, size: 8, info: 0);
+    assert_layout!(vector_handoff_info::VectorHandoffInfo, size: 24, vector_number: 0, attribute: 4, owner: 8);
+}
@@ -26,18 +26,74 @@
 //! content. These details will be documented and managed using appropriately versioned releases of the crate to
 //! reflect the degree of change.
 //!
+//! # Features
+//!
+//! The definitions specific to a boot phase can be left out of firmware built for other phases. Shared elements (HOBs,
+//! status codes and the Status Code Protocol, images, I2C and SMBus definitions) are always available.
+//!
+//! - `alloc` (default): everything that allocates, such as the HOB list, the parsed firmware volume types, captures
+//!   and reports. Without it, the HOB structures and the on-disk firmware volume, file and section types are still
+//!   available, along with [`fw_fs::raw`], which validates and walks a firmware volume in place.
+//! - `pei` (default): PPIs and the PEI Services.
+//! - `dxe` (default): DXE protocols, the DXE Services and GCD, and the DXE configuration tables.
+//! - `mm` (default): MM protocols.
+//! - `fw_fs` (default): firmware volumes and files, capsules, variable stores and boot reports. The Firmware Volume
+//!   and Firmware Volume Block protocols also require `dxe`.
+//! - `serde`: serialization of captures, reports and decoded HOBs.
+//! - `log`: mapping of status code severities onto `log` levels, and a status code reporter that logs them.
+//! - `std-tools`: everything host-side tools need: `std` (memory-mapped files), `serde`, `fw_fs` and `guid_names`.
+//! - `brotli`, `crc32`, `lzma`, `tiano` and `guid_names` add section extractors and GUID names to `fw_fs`, which they
+//!   enable.
+//!
 
-#![cfg_attr(not(test), no_std)]
+#![cfg_attr(not(any(test, feature = "std")), no_std)]
 #![cfg_attr(feature = "nightly", feature(coverage_attribute))]
 
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
 mod address_helper;
 mod boot_mode;
+#[cfg(feature = "alloc")]
+mod crc32;
+#[cfg(test)]
+mod layout_tests;
 
+#[cfg(feature = "fw_fs")]
+pub mod capsule;
+#[cfg(feature = "alloc")]
+pub mod capture;
+pub mod checksum;
+#[cfg(feature = "dxe")]
+pub mod debug_image_info;
+#[cfg(feature = "dxe")]
 pub mod dxe_services;
+#[cfg(feature = "fw_fs")]
 pub mod fw_fs;
+#[cfg(all(feature = "alloc", feature = "dxe"))]
+pub mod gcd_plan;
 pub mod hob;
+pub mod i2c;
+pub mod image;
+pub mod io_access;
 pub mod list_entry;
+#[cfg(all(feature = "alloc", feature = "dxe"))]
+pub mod memory_attributes_table;
+pub mod parse;
+pub mod pe_header;
+#[cfg(feature = "pei")]
+pub mod pei_core;
+#[cfg(feature = "pei")]
+pub mod ppis;
 pub mod protocols;
+#[cfg(all(feature = "alloc", feature = "fw_fs"))]
+pub mod report;
+pub mod smbus;
 pub mod status_code;
+#[cfg(all(feature = "alloc", feature = "fw_fs"))]
+pub mod variable_store;
+pub mod win_certificate;
 
 pub use boot_mode::Mode as BootMode;
+#[doc(hidden)]
+pub use r_efi;
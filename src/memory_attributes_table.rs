@@ -0,0 +1,374 @@
+//! Memory Attributes Table
+//!
+//! Definitions for the EFI_MEMORY_ATTRIBUTES_TABLE and the (deprecated) EFI_PROPERTIES_TABLE configuration tables,
+//! which describe the memory protections an OS should apply to UEFI runtime memory, and support code to derive the
+//! memory attributes table from the memory map and the runtime image records.
+//!
+//! See <https://uefi.org/specs/UEFI/2.10/04_EFI_System_Table.html#efi-memory-attributes-table>.
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+use alloc::vec::Vec;
+use core::{fmt, mem, ops::Range};
+
+use r_efi::efi;
+
+use crate::address_helper::align_up;
+
+/// GUID of the memory attributes table configuration table.
+///
+/// # Documentation
+/// UEFI Specification, Release 2.10, Section 4.6.4
+pub const EFI_MEMORY_ATTRIBUTES_TABLE_GUID: efi::Guid =
+    efi::Guid::from_fields(0xdcfa911d, 0x26eb, 0x469f, 0xa2, 0x20, &[0x38, 0xb7, 0xdc, 0x46, 0x12, 0x20]);
+
+/// Current version of the memory attributes table.
+pub const EFI_MEMORY_ATTRIBUTES_TABLE_VERSION: u32 = 0x00000002;
+
+/// Set in [`MemoryAttributesTableHeader::flags`] when all runtime code is compiled with forward control flow guard
+/// instructions.
+pub const EFI_MEMORY_ATTRIBUTES_FLAGS_RT_FORWARD_CONTROL_FLOW_GUARD: u32 = 0x1;
+
+/// GUID of the properties table configuration table. Deprecated in favor of the memory attributes table.
+///
+/// # Documentation
+/// UEFI Specification, Release 2.5, Section 4.6
+pub const EFI_PROPERTIES_TABLE_GUID: efi::Guid =
+    efi::Guid::from_fields(0x880aaca3, 0x4adc, 0x4a04, 0x90, 0x79, &[0xb7, 0x47, 0x34, 0x08, 0x25, 0xe5]);
+
+/// Current version of the properties table.
+pub const EFI_PROPERTIES_TABLE_VERSION: u32 = 0x00010000;
+
+/// Set in [`PropertiesTable::memory_protection_attribute`] when the runtime PE image code and data sections are
+/// separated into distinct pages, and data pages are not executable.
+pub const EFI_PROPERTIES_RUNTIME_MEMORY_PROTECTION_NON_EXECUTABLE_PE_DATA: u64 = 0x1;
+
+const UEFI_PAGE_SIZE: u64 = 0x1000;
+
+/// Header of the memory attributes table. Followed by `number_of_entries` memory descriptors of `descriptor_size`
+/// bytes each.
+///
+/// # Documentation
+/// UEFI Specification, Release 2.10, Section 4.6.4
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemoryAttributesTableHeader {
+    pub version: u32,
+    pub number_of_entries: u32,
+    pub descriptor_size: u32,
+    pub flags: u32,
+}
+
+/// The properties table.
+///
+/// # Documentation
+/// UEFI Specification, Release 2.5, Section 4.6
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PropertiesTable {
+    pub version: u32,
+    /// Size of this structure in bytes.
+    pub length: u32,
+    pub memory_protection_attribute: u64,
+}
+
+/// Describes a PE image loaded into runtime services code memory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImageRecord {
+    /// The page aligned base address of the image.
+    pub image_base: efi::PhysicalAddress,
+    /// The size in bytes of the image. Rounded up to a page multiple.
+    pub image_size: u64,
+    /// The page aligned address ranges of the code sections of the image. The rest of the image (headers and data
+    /// sections) is treated as data.
+    pub code_sections: Vec<Range<efi::PhysicalAddress>>,
+}
+
+/// Errors detected while deriving a [`MemoryAttributesTable`].
+///
+/// They convert to `INVALID_PARAMETER` for callers that return an [`efi::Status`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MemoryAttributesTableError {
+    /// An image in runtime services code memory is not page aligned.
+    UnalignedImage { image_base: efi::PhysicalAddress },
+    /// An image in runtime services code memory overlaps the previous image.
+    OverlappingImage { image_base: efi::PhysicalAddress },
+    /// A code section of an image is not page aligned.
+    UnalignedCodeSection { image_base: efi::PhysicalAddress, section: Range<efi::PhysicalAddress> },
+    /// A code section of an image is not within the image or overlaps the previous code section.
+    BadCodeSection { image_base: efi::PhysicalAddress, section: Range<efi::PhysicalAddress> },
+}
+
+impl fmt::Display for MemoryAttributesTableError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MemoryAttributesTableError::UnalignedImage { image_base } => {
+                write!(f, "runtime image at {image_base:#x} is not page aligned")
+            }
+            MemoryAttributesTableError::OverlappingImage { image_base } => {
+                write!(f, "runtime image at {image_base:#x} overlaps the previous image")
+            }
+            MemoryAttributesTableError::UnalignedCodeSection { image_base, section } => {
+                write!(f, "code section {section:#x?} of the runtime image at {image_base:#x} is not page aligned")
+            }
+            MemoryAttributesTableError::BadCodeSection { image_base, section } => write!(
+                f,
+                "code section {section:#x?} of the runtime image at {image_base:#x} is outside the image or overlaps \
+                 another code section"
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for MemoryAttributesTableError {}
+
+impl From<MemoryAttributesTableError> for efi::Status {
+    fn from(_error: MemoryAttributesTableError) -> Self {
+        efi::Status::INVALID_PARAMETER
+    }
+}
+
+/// A memory attributes table, as derived from the memory map and the runtime image records.
+///
+/// ## Example
+/// ```
+/// use mu_pi::memory_attributes_table::{ImageRecord, MemoryAttributesTable};
+/// use r_efi::efi;
+///
+/// let memory_map = [efi::MemoryDescriptor {
+///     r#type: efi::RUNTIME_SERVICES_CODE,
+///     physical_start: 0x100000,
+///     virtual_start: 0,
+///     number_of_pages: 0x10,
+///     attribute: efi::MEMORY_WB | efi::MEMORY_RUNTIME,
+/// }];
+/// let image = ImageRecord { image_base: 0x100000, image_size: 0x4000, code_sections: vec![0x101000..0x103000] };
+///
+/// let table = MemoryAttributesTable::new(&memory_map, &[image]).unwrap();
+/// assert_eq!(table.entries().len(), 4);
+/// let bytes = table.to_bytes(); // install as the EFI_MEMORY_ATTRIBUTES_TABLE_GUID configuration table.
+/// ```
+#[derive(Debug, Clone)]
+pub struct MemoryAttributesTable {
+    flags: u32,
+    entries: Vec<efi::MemoryDescriptor>,
+}
+
+impl MemoryAttributesTable {
+    /// Derives the memory attributes table from a memory map and the records of the images loaded into runtime
+    /// services code memory.
+    ///
+    /// As in EDK2, runtime services data is marked non-executable, and runtime services code regions holding an image
+    /// are split so that the image code sections are read-only and the rest of the image is non-executable. Images
+    /// that are not contained in a runtime services code region are ignored.
+    ///
+    /// Returns an error if an image or code section is not page aligned, or a code section is not within its image or
+    /// overlaps another code section.
+    pub fn new(
+        memory_map: &[efi::MemoryDescriptor],
+        image_records: &[ImageRecord],
+    ) -> Result<Self, MemoryAttributesTableError> {
+        let mut entries = Vec::new();
+        for descriptor in memory_map {
+            match descriptor.r#type {
+                efi::RUNTIME_SERVICES_DATA => entries.push(efi::MemoryDescriptor {
+                    attribute: efi::MEMORY_RUNTIME | efi::MEMORY_XP,
+                    virtual_start: 0,
+                    ..*descriptor
+                }),
+                efi::RUNTIME_SERVICES_CODE => split_code_descriptor(descriptor, image_records, &mut entries)?,
+                _ => (),
+            }
+        }
+        entries.sort_by_key(|descriptor| descriptor.physical_start);
+        Ok(Self { flags: 0, entries })
+    }
+
+    /// Sets [`EFI_MEMORY_ATTRIBUTES_FLAGS_RT_FORWARD_CONTROL_FLOW_GUARD`] in the table flags.
+    pub fn set_forward_control_flow_guard(&mut self, enabled: bool) {
+        if enabled {
+            self.flags |= EFI_MEMORY_ATTRIBUTES_FLAGS_RT_FORWARD_CONTROL_FLOW_GUARD;
+        } else {
+            self.flags &= !EFI_MEMORY_ATTRIBUTES_FLAGS_RT_FORWARD_CONTROL_FLOW_GUARD;
+        }
+    }
+
+    /// Returns the table header.
+    pub fn header(&self) -> MemoryAttributesTableHeader {
+        MemoryAttributesTableHeader {
+            version: EFI_MEMORY_ATTRIBUTES_TABLE_VERSION,
+            number_of_entries: self.entries.len() as u32,
+            descriptor_size: mem::size_of::<efi::MemoryDescriptor>() as u32,
+            flags: self.flags,
+        }
+    }
+
+    /// Returns the table entries, in ascending address order.
+    pub fn entries(&self) -> &[efi::MemoryDescriptor] {
+        &self.entries
+    }
+
+    /// Returns the table as it is laid out in memory: the header followed by the entries.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let header = self.header();
+        let mut bytes = Vec::with_capacity(
+            mem::size_of::<MemoryAttributesTableHeader>() + self.entries.len() * header.descriptor_size as usize,
+        );
+        for field in [header.version, header.number_of_entries, header.descriptor_size, header.flags] {
+            bytes.extend_from_slice(&field.to_ne_bytes());
+        }
+        for entry in &self.entries {
+            let entry_start = bytes.len();
+            bytes.extend_from_slice(&entry.r#type.to_ne_bytes());
+            // the descriptor is 8-byte aligned after the 32-bit type.
+            bytes.resize(entry_start + mem::size_of::<u64>(), 0);
+            for field in [entry.physical_start, entry.virtual_start, entry.number_of_pages, entry.attribute] {
+                bytes.extend_from_slice(&field.to_ne_bytes());
+            }
+        }
+        bytes
+    }
+}
+
+// Splits a runtime services code descriptor into the entries for the images it contains and the code around them.
+fn split_code_descriptor(
+    descriptor: &efi::MemoryDescriptor,
+    image_records: &[ImageRecord],
+    entries: &mut Vec<efi::MemoryDescriptor>,
+) -> Result<(), MemoryAttributesTableError> {
+    let start = descriptor.physical_start;
+    let end = start + descriptor.number_of_pages * UEFI_PAGE_SIZE;
+    let mut push = |range: Range<u64>, attribute: u64| {
+        if !range.is_empty() {
+            entries.push(efi::MemoryDescriptor {
+                r#type: efi::RUNTIME_SERVICES_CODE,
+                physical_start: range.start,
+                virtual_start: 0,
+                number_of_pages: (range.end - range.start) / UEFI_PAGE_SIZE,
+                attribute: efi::MEMORY_RUNTIME | attribute,
+            });
+        }
+    };
+
+    let mut images = image_records
+        .iter()
+        .filter(|image| image.image_base >= start && image.image_base.saturating_add(image.image_size) <= end)
+        .collect::<Vec<_>>();
+    images.sort_by_key(|image| image.image_base);
+
+    let mut cursor = start;
+    for image in images {
+        let image_base = image.image_base;
+        if image_base % UEFI_PAGE_SIZE != 0 {
+            Err(MemoryAttributesTableError::UnalignedImage { image_base })?;
+        }
+        if image_base < cursor {
+            Err(MemoryAttributesTableError::OverlappingImage { image_base })?;
+        }
+        let image_end = align_up(image.image_base + image.image_size, UEFI_PAGE_SIZE);
+        push(cursor..image.image_base, 0);
+
+        let mut code_sections = image.code_sections.clone();
+        code_sections.sort_by_key(|section| section.start);
+        let mut position = image.image_base;
+        for section in code_sections {
+            if section.start % UEFI_PAGE_SIZE != 0 || section.end % UEFI_PAGE_SIZE != 0 {
+                return Err(MemoryAttributesTableError::UnalignedCodeSection { image_base, section });
+            }
+            if section.start < position || section.end > image_end {
+                return Err(MemoryAttributesTableError::BadCodeSection { image_base, section });
+            }
+            push(position..section.start, efi::MEMORY_XP);
+            push(section.clone(), efi::MEMORY_RO);
+            position = section.end;
+        }
+        push(position..image_end, efi::MEMORY_XP);
+        cursor = image_end;
+    }
+    push(cursor..end, 0);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn descriptor(r#type: u32, physical_start: u64, number_of_pages: u64) -> efi::MemoryDescriptor {
+        efi::MemoryDescriptor {
+            r#type,
+            physical_start,
+            virtual_start: 0,
+            number_of_pages,
+            attribute: efi::MEMORY_WB | efi::MEMORY_RUNTIME,
+        }
+    }
+
+    #[test]
+    fn table_should_split_runtime_images() {
+        let memory_map = [
+            descriptor(efi::CONVENTIONAL_MEMORY, 0, 0x100),
+            descriptor(efi::RUNTIME_SERVICES_DATA, 0x200000, 0x4),
+            descriptor(efi::RUNTIME_SERVICES_CODE, 0x100000, 0x10),
+        ];
+        let images = [
+            ImageRecord {
+                image_base: 0x101000,
+                image_size: 0x3800,
+                code_sections: vec![Range { start: 0x102000, end: 0x103000 }],
+            },
+            // not in runtime services code memory.
+            ImageRecord { image_base: 0x300000, image_size: 0x1000, code_sections: vec![] },
+        ];
+
+        let table = MemoryAttributesTable::new(&memory_map, &images).unwrap();
+        let entries =
+            table.entries().iter().map(|x| (x.physical_start, x.number_of_pages, x.attribute)).collect::<Vec<_>>();
+        assert_eq!(
+            entries,
+            [
+                (0x100000, 1, efi::MEMORY_RUNTIME),
+                (0x101000, 1, efi::MEMORY_RUNTIME | efi::MEMORY_XP),
+                (0x102000, 1, efi::MEMORY_RUNTIME | efi::MEMORY_RO),
+                (0x103000, 2, efi::MEMORY_RUNTIME | efi::MEMORY_XP),
+                (0x105000, 0xb, efi::MEMORY_RUNTIME),
+                (0x200000, 4, efi::MEMORY_RUNTIME | efi::MEMORY_XP),
+            ]
+        );
+
+        let bytes = table.to_bytes();
+        assert_eq!(bytes.len(), 16 + 6 * 40);
+        assert_eq!(&bytes[..16], &[2, 0, 0, 0, 6, 0, 0, 0, 40, 0, 0, 0, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn table_should_reject_unaligned_code_sections() {
+        let memory_map = [descriptor(efi::RUNTIME_SERVICES_CODE, 0x100000, 0x10)];
+        let images = [ImageRecord {
+            image_base: 0x101000,
+            image_size: 0x3000,
+            code_sections: vec![Range { start: 0x101200, end: 0x102000 }],
+        }];
+        assert_eq!(
+            MemoryAttributesTable::new(&memory_map, &images).unwrap_err(),
+            MemoryAttributesTableError::UnalignedCodeSection { image_base: 0x101000, section: 0x101200..0x102000 }
+        );
+
+        let images = [ImageRecord {
+            image_base: 0x101000,
+            image_size: 0x3000,
+            code_sections: vec![Range { start: 0x102000, end: 0x105000 }],
+        }];
+        let error = MemoryAttributesTable::new(&memory_map, &images).unwrap_err();
+        assert_eq!(
+            error,
+            MemoryAttributesTableError::BadCodeSection { image_base: 0x101000, section: 0x102000..0x105000 }
+        );
+        assert_eq!(efi::Status::from(error), efi::Status::INVALID_PARAMETER);
+    }
+}
@@ -0,0 +1,275 @@
+//! Parser Options
+//!
+//! Options shared by the parsers in this crate (firmware volumes, firmware files, variable stores and HOB lists).
+//!
+//! Real-world firmware images do not always follow the PI specification to the letter, and the EDK2 reference
+//! implementation tolerates a number of such deviations. [`ParseOptions`] selects a [`Strictness`] profile that
+//! controls whether those deviations are rejected, accepted and recorded, or silently accepted. It also selects the
+//! [`AlignmentPolicy`] of file and section offsets, where EDK2 departs from the specification.
+//!
+//! Parsers that accept a diagnostics sink (a `&mut dyn FnMut(Diagnostic)`) additionally report non-fatal oddities
+//! through it as [`Diagnostic`]s, so tooling can present a health report rather than a pass/fail result.
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+use core::fmt;
+use r_efi::efi;
+
+use crate::address_helper::align_up;
+
+/// Controls how parsers treat spec deviations that EDK2 tolerates.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Strictness {
+    /// Spec deviations are errors. This is the behavior of the parsers' plain constructors.
+    #[default]
+    Strict,
+    /// Spec deviations that EDK2 tolerates are accepted and recorded as [`Deviation`]s on the parse result.
+    Compatible,
+    /// Spec deviations that EDK2 tolerates are accepted without being recorded.
+    Permissive,
+}
+
+/// Options consumed by the parsers in this crate.
+///
+/// ## Example
+/// ```
+/// # use std::{env, fs, path::Path, error::Error};
+/// use mu_pi::{fw_fs::FirmwareVolume, parse::{ParseOptions, Strictness}};
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// # let root = Path::new(&env::var("CARGO_MANIFEST_DIR")?).join("test_resources");
+/// # let fv_bytes = fs::read(root.join("DXEFV.Fv"))?;
+/// let options = ParseOptions::new(Strictness::Compatible);
+/// let fv = FirmwareVolume::new_with_options(&fv_bytes, &options).expect("Firmware Volume Corrupt");
+/// for file in fv.file_iter() {
+///   for deviation in file.expect("File Corrupt").deviations() {
+///     println!("{deviation}");
+///   }
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ParseOptions {
+    pub strictness: Strictness,
+    /// How the offsets of firmware volume files and sections are aligned. Defaults to
+    /// [`AlignmentPolicy::RelativeToBase`] under [`Strictness::Strict`], and to [`AlignmentPolicy::Absolute`] (the EDK2
+    /// behavior) otherwise.
+    pub alignment: AlignmentPolicy,
+    /// Whether FFS file header and data checksums are verified when a file is parsed. Defaults to `true`.
+    pub verify_checksums: bool,
+    /// Whether firmware volume file iterators skip files that are not valid (being constructed, deleted or with an
+    /// invalid header) as the EDK2 FFS driver does, rather than returning an error. Defaults to `false`.
+    pub skip_invalid_files: bool,
+    /// How much of a firmware volume header is validated when the firmware volume is instantiated. Defaults to
+    /// [`ValidationLevel::Full`].
+    pub validation: ValidationLevel,
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        Self::STRICT
+    }
+}
+
+impl ParseOptions {
+    pub const STRICT: Self = Self::new(Strictness::Strict);
+    pub const COMPATIBLE: Self = Self::new(Strictness::Compatible);
+    pub const PERMISSIVE: Self = Self::new(Strictness::Permissive);
+
+    /// Creates a new set of parse options with the given strictness.
+    pub const fn new(strictness: Strictness) -> Self {
+        let alignment = match strictness {
+            Strictness::Strict => AlignmentPolicy::RelativeToBase,
+            Strictness::Compatible | Strictness::Permissive => AlignmentPolicy::Absolute,
+        };
+        Self {
+            strictness,
+            alignment,
+            verify_checksums: true,
+            skip_invalid_files: false,
+            validation: ValidationLevel::Full,
+        }
+    }
+
+    /// Applies the strictness profile to a spec deviation found by a parser.
+    ///
+    /// Returns the deviation as an error under [`Strictness::Strict`]. Otherwise the deviation is tolerated, and under
+    /// [`Strictness::Compatible`] it is passed to `report`.
+    pub(crate) fn tolerate(&self, deviation: Deviation, report: &mut dyn FnMut(Deviation)) -> Result<(), Deviation> {
+        match self.strictness {
+            Strictness::Strict => Err(deviation),
+            Strictness::Compatible => {
+                report(deviation);
+                Ok(())
+            }
+            Strictness::Permissive => Ok(()),
+        }
+    }
+}
+
+/// How parsers align the offsets of the files in a firmware volume and of the sections in a file.
+///
+/// The PI specification places each file at the next 8-byte aligned offset from the start of the firmware volume, and
+/// each section at the next 4-byte aligned offset from the start of the file data (or of the data extracted from an
+/// encapsulation section). EDK2 instead aligns the address of the file or section in memory, which only gives the same
+/// result when the firmware volume (or extracted data) is itself loaded at an aligned address.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AlignmentPolicy {
+    /// Offsets are aligned relative to the start of the firmware volume or file data, as the PI specification requires.
+    /// Images produced by tools other than EDK2's may rely on it.
+    #[default]
+    RelativeToBase,
+    /// Offsets are aligned on the address of the data in memory, as EDK2 does.
+    Absolute,
+}
+
+impl AlignmentPolicy {
+    /// Returns `offset` in `buffer`, rounded up to the next `alignment` boundary under this policy.
+    #[cfg_attr(not(feature = "fw_fs"), allow(dead_code))]
+    pub(crate) fn align(&self, buffer: &[u8], offset: usize, alignment: u64) -> usize {
+        match self {
+            AlignmentPolicy::RelativeToBase => align_up(offset as u64, alignment) as usize,
+            AlignmentPolicy::Absolute => {
+                let base = buffer.as_ptr() as usize;
+                align_up((base + offset) as u64, alignment) as usize - base
+            }
+        }
+    }
+}
+
+/// How much of a firmware volume header is validated when the firmware volume is instantiated.
+///
+/// Checks that guard the accesses of the parser to the buffer (the header, extended header and block map bounds, and
+/// the volume length) are always made. The others can be skipped by callers that instantiate trusted firmware volumes
+/// repeatedly, such as a dispatcher re-opening the volumes it already validated, and deferred to
+/// [`FirmwareVolume::verify_header`](crate::fw_fs::FirmwareVolume::verify_header).
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ValidationLevel {
+    /// The whole header is validated, including its checksum and each block map entry.
+    #[default]
+    Full,
+    /// Only the structure of the header is validated: the header checksum is not verified, and the block map entries
+    /// before the terminator are not checked to be non-zero.
+    Structural,
+}
+
+/// A spec deviation that EDK2 tolerates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Deviation {
+    /// An FFS file without the CHECKSUM attribute whose file checksum is not the fixed value (0xAA, or 0x5A for FFS1).
+    FileFixedChecksum { name: efi::Guid, checksum: u8 },
+    /// An FFS file that is in the EFI_FILE_MARKED_FOR_UPDATE state. EDK2 still treats the file as valid.
+    FileMarkedForUpdate { name: efi::Guid },
+    /// An FFS file with the LARGE_FILE attribute in an FFS2 volume. Large files are only valid in FFS3 volumes; EDK2
+    /// skips them with an error, while this implementation parses them using the extended header.
+    LargeFileInFfs2 { name: efi::Guid },
+    /// A fixed size HOB whose length is larger than the structure for its type. EDK2 only uses the length to find the
+    /// next HOB.
+    HobOversized { offset: usize, hob_type: u16, length: u16 },
+    /// An EFI_SYSTEM_NV_DATA_FV_GUID firmware volume whose header checksum does not sum to zero. The EDK2 variable
+    /// driver does not verify it.
+    NvDataFvHeaderChecksum,
+}
+
+impl fmt::Display for Deviation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Deviation::FileFixedChecksum { name, checksum } => {
+                write!(f, "file {name:?} has unexpected fixed checksum {checksum:#x}")
+            }
+            Deviation::FileMarkedForUpdate { name } => write!(f, "file {name:?} is marked for update"),
+            Deviation::LargeFileInFfs2 { name } => write!(f, "large file {name:?} is in an FFS2 volume"),
+            Deviation::HobOversized { offset, hob_type, length } => {
+                write!(f, "HOB of type {hob_type:#x} at offset {offset:#x} is oversized ({length:#x} bytes)")
+            }
+            Deviation::NvDataFvHeaderChecksum => write!(f, "NV data firmware volume header has bad checksum"),
+        }
+    }
+}
+
+/// The severity of a [`Diagnostic`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    /// Unusual, but permitted by the spec.
+    Info,
+    /// Not permitted by the spec, but tolerated by the parser.
+    Warning,
+}
+
+/// A non-fatal oddity reported by a parser to a diagnostics sink.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Diagnostic {
+    /// A spec deviation that was tolerated under [`Strictness::Compatible`].
+    Deviation(Deviation),
+    /// The firmware volume header revision is newer than EFI_FVH_REVISION (0x02).
+    FvRevision { revision: u8 },
+    /// The firmware volume data did not start 8-byte aligned after the header (or extended header) and was aligned
+    /// up to the next 8-byte boundary.
+    UnalignedFvData { offset: usize },
+    /// An FFS file with no content after its header.
+    EmptyFile { name: efi::Guid },
+    /// A variable store whose header is not marked formatted and healthy.
+    UnhealthyVariableStore { format: u8, state: u8 },
+}
+
+impl Diagnostic {
+    /// Returns the severity of the diagnostic.
+    pub fn severity(&self) -> Severity {
+        match self {
+            Diagnostic::Deviation(_) | Diagnostic::FvRevision { .. } | Diagnostic::UnhealthyVariableStore { .. } => {
+                Severity::Warning
+            }
+            Diagnostic::UnalignedFvData { .. } | Diagnostic::EmptyFile { .. } => Severity::Info,
+        }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Diagnostic::Deviation(deviation) => deviation.fmt(f),
+            Diagnostic::FvRevision { revision } => write!(f, "firmware volume has unknown revision {revision:#x}"),
+            Diagnostic::UnalignedFvData { offset } => {
+                write!(f, "firmware volume data at offset {offset:#x} is not 8-byte aligned")
+            }
+            Diagnostic::EmptyFile { name } => write!(f, "file {name:?} is empty"),
+            Diagnostic::UnhealthyVariableStore { format, state } => {
+                write!(f, "variable store is not formatted and healthy (format {format:#x}, state {state:#x})")
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tolerate_should_follow_strictness() {
+        let deviation = Deviation::HobOversized { offset: 0, hob_type: 3, length: 0x40 };
+
+        let mut report = Vec::new();
+        assert_eq!(
+            ParseOptions::default().tolerate(deviation, &mut |deviation| report.push(deviation)),
+            Err(deviation)
+        );
+        assert_eq!(ParseOptions::PERMISSIVE.tolerate(deviation, &mut |deviation| report.push(deviation)), Ok(()));
+        assert!(report.is_empty());
+        assert_eq!(ParseOptions::COMPATIBLE.tolerate(deviation, &mut |deviation| report.push(deviation)), Ok(()));
+        assert_eq!(report, [deviation]);
+    }
+
+    #[test]
+    fn diagnostic_severity() {
+        let deviation = Deviation::HobOversized { offset: 0, hob_type: 3, length: 0x40 };
+        assert_eq!(Diagnostic::Deviation(deviation).severity(), Severity::Warning);
+        assert_eq!(Diagnostic::FvRevision { revision: 3 }.severity(), Severity::Warning);
+        assert_eq!(Diagnostic::UnalignedFvData { offset: 0x4c }.severity(), Severity::Info);
+        assert!(Severity::Info < Severity::Warning);
+    }
+}
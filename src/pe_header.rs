@@ -0,0 +1,221 @@
+//! PE/COFF Header Reader
+//!
+//! A minimal reader for the PE/COFF header fields that firmware loaders need (machine type, subsystem, entry point and
+//! section alignment), without a full PE parser. It is typically used on the data of a PE32 section, see
+//! [`Section::pe_header_info`](crate::fw_fs::Section::pe_header_info).
+//!
+//! See <https://learn.microsoft.com/en-us/windows/win32/debug/pe-format>.
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+use core::fmt;
+
+use r_efi::efi;
+
+/// Machine types (`IMAGE_FILE_MACHINE_*` in EDK II code).
+pub mod machine {
+    pub const IA32: u16 = 0x014c;
+    pub const ARMTHUMB_MIXED: u16 = 0x01c2;
+    pub const EBC: u16 = 0x0ebc;
+    pub const RISCV32: u16 = 0x5032;
+    pub const RISCV64: u16 = 0x5064;
+    pub const RISCV128: u16 = 0x5128;
+    pub const LOONGARCH64: u16 = 0x6264;
+    pub const X64: u16 = 0x8664;
+    pub const AARCH64: u16 = 0xaa64;
+
+    /// Returns whether `machine` is one of the RISC-V machine types.
+    pub const fn is_riscv(machine: u16) -> bool {
+        matches!(machine, RISCV32 | RISCV64 | RISCV128)
+    }
+}
+
+/// Subsystems of UEFI images (`EFI_IMAGE_SUBSYSTEM_*` in EDK II code).
+pub mod subsystem {
+    pub const EFI_APPLICATION: u16 = 10;
+    pub const EFI_BOOT_SERVICE_DRIVER: u16 = 11;
+    pub const EFI_RUNTIME_DRIVER: u16 = 12;
+    pub const EFI_ROM: u16 = 13;
+}
+
+const DOS_SIGNATURE: u16 = u16::from_le_bytes(*b"MZ");
+const DOS_LFANEW_OFFSET: usize = 0x3c;
+const PE_SIGNATURE: u32 = u32::from_le_bytes(*b"PE\0\0");
+const COFF_HEADER_SIZE: usize = 20;
+const PE32_MAGIC: u16 = 0x10b;
+const PE32_PLUS_MAGIC: u16 = 0x20b;
+// Size of the optional header up to and including the Subsystem field, which has the same offset in PE32 and PE32+.
+const OPTIONAL_HEADER_MIN_SIZE: usize = 70;
+
+/// Errors detected while reading the PE/COFF header of an image.
+///
+/// Truncated headers convert to `LOAD_ERROR`, and images that are not PE32 or PE32+ images to `UNSUPPORTED`, for
+/// callers that return an [`efi::Status`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PeHeaderError {
+    /// The image ends before the header field at the given offset.
+    Truncated { offset: usize },
+    /// The PE signature is not `PE\0\0`.
+    BadSignature { signature: u32 },
+    /// The optional header magic is neither the PE32 nor the PE32+ magic.
+    UnsupportedMagic { magic: u16 },
+    /// The optional header is too small to hold the subsystem.
+    BadOptionalHeaderSize { size_of_optional_header: u16 },
+}
+
+impl fmt::Display for PeHeaderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PeHeaderError::Truncated { offset } => write!(f, "image ends before the header field at {offset:#x}"),
+            PeHeaderError::BadSignature { signature } => write!(f, "image has bad PE signature {signature:#x}"),
+            PeHeaderError::UnsupportedMagic { magic } => {
+                write!(f, "image has unsupported optional header magic {magic:#x}")
+            }
+            PeHeaderError::BadOptionalHeaderSize { size_of_optional_header } => {
+                write!(f, "image has bad optional header size {size_of_optional_header:#x}")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for PeHeaderError {}
+
+impl From<PeHeaderError> for efi::Status {
+    fn from(error: PeHeaderError) -> Self {
+        match error {
+            PeHeaderError::BadSignature { .. } | PeHeaderError::UnsupportedMagic { .. } => efi::Status::UNSUPPORTED,
+            PeHeaderError::Truncated { .. } | PeHeaderError::BadOptionalHeaderSize { .. } => efi::Status::LOAD_ERROR,
+        }
+    }
+}
+
+/// The PE/COFF header fields of an image.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PeHeaderInfo {
+    /// The machine type, one of [`machine`].
+    pub machine: u16,
+    /// The subsystem, e.g. one of [`subsystem`].
+    pub subsystem: u16,
+    /// Whether the image is PE32+ (64-bit) rather than PE32.
+    pub pe32_plus: bool,
+    /// The relative virtual address of the entry point.
+    pub entry_point_rva: u32,
+    /// The preferred load address of the image.
+    pub image_base: u64,
+    /// The alignment of the image sections when loaded in memory.
+    pub section_alignment: u32,
+    /// The size of the image when loaded in memory.
+    pub size_of_image: u32,
+}
+
+impl PeHeaderInfo {
+    /// Reads the PE/COFF header of the given image. The image may start with a DOS header or directly with the PE
+    /// signature.
+    ///
+    /// Returns an error if the image is not a PE32 or PE32+ image, or if the headers are truncated.
+    pub fn parse(image: &[u8]) -> Result<Self, PeHeaderError> {
+        let pe_offset =
+            if read_u16(image, 0)? == DOS_SIGNATURE { read_u32(image, DOS_LFANEW_OFFSET)? as usize } else { 0 };
+        let signature = read_u32(image, pe_offset)?;
+        if signature != PE_SIGNATURE {
+            Err(PeHeaderError::BadSignature { signature })?;
+        }
+
+        let coff_offset = pe_offset + 4;
+        let machine = read_u16(image, coff_offset)?;
+        let size_of_optional_header = read_u16(image, coff_offset + 16)?;
+
+        let optional_offset = coff_offset + COFF_HEADER_SIZE;
+        let pe32_plus = match read_u16(image, optional_offset)? {
+            PE32_MAGIC => false,
+            PE32_PLUS_MAGIC => true,
+            magic => Err(PeHeaderError::UnsupportedMagic { magic })?,
+        };
+        if (size_of_optional_header as usize) < OPTIONAL_HEADER_MIN_SIZE {
+            Err(PeHeaderError::BadOptionalHeaderSize { size_of_optional_header })?;
+        }
+
+        let image_base = if pe32_plus {
+            read_u64(image, optional_offset + 24)?
+        } else {
+            read_u32(image, optional_offset + 28)? as u64
+        };
+
+        Ok(Self {
+            machine,
+            subsystem: read_u16(image, optional_offset + 68)?,
+            pe32_plus,
+            entry_point_rva: read_u32(image, optional_offset + 16)?,
+            image_base,
+            section_alignment: read_u32(image, optional_offset + 32)?,
+            size_of_image: read_u32(image, optional_offset + 56)?,
+        })
+    }
+}
+
+fn read_bytes<const N: usize>(image: &[u8], offset: usize) -> Result<[u8; N], PeHeaderError> {
+    let truncated = PeHeaderError::Truncated { offset };
+    let bytes = image.get(offset..offset.checked_add(N).ok_or(truncated)?).ok_or(truncated)?;
+    Ok(bytes.try_into().unwrap())
+}
+
+fn read_u16(image: &[u8], offset: usize) -> Result<u16, PeHeaderError> {
+    read_bytes(image, offset).map(u16::from_le_bytes)
+}
+
+fn read_u32(image: &[u8], offset: usize) -> Result<u32, PeHeaderError> {
+    read_bytes(image, offset).map(u32::from_le_bytes)
+}
+
+fn read_u64(image: &[u8], offset: usize) -> Result<u64, PeHeaderError> {
+    read_bytes(image, offset).map(u64::from_le_bytes)
+}
+
+#[cfg(all(test, feature = "fw_fs"))]
+mod tests {
+    use super::*;
+    use crate::fw_fs::{FfsFileType, FfsSectionType, FirmwareVolume};
+    use std::{env, fs, path::Path};
+
+    #[test]
+    fn pe32_sections_should_have_headers() {
+        let root = Path::new(&env::var("CARGO_MANIFEST_DIR").unwrap()).join("test_resources");
+        let fv_bytes = fs::read(root.join("DXEFV.Fv")).unwrap();
+        let fv = FirmwareVolume::new(&fv_bytes).unwrap();
+
+        let mut drivers = 0;
+        for file in fv.files_of_type(FfsFileType::Driver) {
+            let file = file.unwrap();
+            let section = file.first_section_of_type(FfsSectionType::Pe32).unwrap().unwrap();
+            let info = section.pe_header_info().unwrap();
+            assert!(info.pe32_plus);
+            assert_eq!(info.machine, machine::X64);
+            assert!((subsystem::EFI_APPLICATION..=subsystem::EFI_RUNTIME_DRIVER).contains(&info.subsystem));
+            assert!(info.section_alignment.is_power_of_two());
+            assert!(info.entry_point_rva < info.size_of_image);
+            drivers += 1;
+        }
+        assert!(drivers > 0);
+
+        let file = fv.files_of_type(FfsFileType::Driver).next().unwrap().unwrap();
+        let section = file.first_section_of_type(FfsSectionType::Pe32).unwrap().unwrap();
+        let truncated = PeHeaderInfo::parse(&section.section_data()[..0x80]).unwrap_err();
+        assert!(matches!(truncated, PeHeaderError::Truncated { .. }));
+        assert_eq!(efi::Status::from(truncated), efi::Status::LOAD_ERROR);
+        assert_eq!(PeHeaderInfo::parse(&[0u8; 0x100]), Err(PeHeaderError::BadSignature { signature: 0 }));
+        assert_eq!(efi::Status::from(PeHeaderError::BadSignature { signature: 0 }), efi::Status::UNSUPPORTED);
+    }
+
+    #[test]
+    fn riscv_machine_types_should_be_recognized() {
+        assert!([machine::RISCV32, machine::RISCV64, machine::RISCV128].into_iter().all(machine::is_riscv));
+        assert!(!machine::is_riscv(machine::X64));
+        assert!(!machine::is_riscv(machine::LOONGARCH64));
+    }
+}
@@ -0,0 +1,136 @@
+//! PEI Core Entry
+//!
+//! Definitions used by the Security (SEC) phase to hand control to the PEI Foundation:
+//! - **EFI_SEC_PEI_HAND_OFF** - Describes the boot firmware volume and the temporary RAM layout set up by SEC.
+//! - **EFI_PEI_PPI_DESCRIPTOR** - Describes a PPI passed from SEC to the PEI Foundation.
+//! - **EFI_PEI_CORE_ENTRY_POINT** - The entry point of the PEI Foundation.
+//!
+//! See <https://uefi.org/specs/PI/1.8A/V1_PEI_Foundation.html>
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+use core::{ffi::c_void, mem, ptr};
+
+use r_efi::efi;
+
+/// Flags of a [`PpiDescriptor`] (`EFI_PEI_PPI_DESCRIPTOR_*` in EDK II code).
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Volume 1, EFI_PEI_PPI_DESCRIPTOR
+pub mod ppi_descriptor_flags {
+    pub const PIC: u32 = 0x00000001;
+    pub const PPI: u32 = 0x00000010;
+    pub const NOTIFY_CALLBACK: u32 = 0x00000020;
+    pub const NOTIFY_DISPATCH: u32 = 0x00000040;
+    pub const NOTIFY_TYPES: u32 = 0x00000060;
+    /// Marks the last descriptor in a list.
+    pub const TERMINATE_LIST: u32 = 0x80000000;
+}
+
+/// Describes the state of the system and the temporary RAM handed from SEC to the PEI Foundation.
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Volume 1, EFI_SEC_PEI_HAND_OFF
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct SecPeiHandOff {
+    /// Size of this structure in bytes.
+    pub data_size: u16,
+    /// Base address of the boot firmware volume, which contains the PEI Foundation.
+    pub boot_firmware_volume_base: *mut c_void,
+    /// Size of the boot firmware volume in bytes.
+    pub boot_firmware_volume_size: usize,
+    /// Base address of the temporary RAM.
+    pub temporary_ram_base: *mut c_void,
+    /// Size of the temporary RAM in bytes.
+    pub temporary_ram_size: usize,
+    /// Base address of the part of the temporary RAM available to the PEI Foundation for heap and PPI storage.
+    pub pei_temporary_ram_base: *mut c_void,
+    /// Size of the part of the temporary RAM available to the PEI Foundation.
+    pub pei_temporary_ram_size: usize,
+    /// Base address of the stack, which is in temporary RAM.
+    pub stack_base: *mut c_void,
+    /// Size of the stack in bytes.
+    pub stack_size: usize,
+}
+
+impl Default for SecPeiHandOff {
+    fn default() -> Self {
+        Self {
+            data_size: mem::size_of::<Self>() as u16,
+            boot_firmware_volume_base: ptr::null_mut(),
+            boot_firmware_volume_size: 0,
+            temporary_ram_base: ptr::null_mut(),
+            temporary_ram_size: 0,
+            pei_temporary_ram_base: ptr::null_mut(),
+            pei_temporary_ram_size: 0,
+            stack_base: ptr::null_mut(),
+            stack_size: 0,
+        }
+    }
+}
+
+/// Describes a PPI, either installed for the PEI Foundation or passed to it by SEC.
+///
+/// A list of descriptors is terminated by a descriptor with [`ppi_descriptor_flags::TERMINATE_LIST`] set.
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Volume 1, EFI_PEI_PPI_DESCRIPTOR
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct PpiDescriptor {
+    /// Combination of [`ppi_descriptor_flags`].
+    pub flags: u32,
+    /// GUID of the PPI.
+    pub guid: *const efi::Guid,
+    /// Pointer to the PPI interface.
+    pub ppi: *mut c_void,
+}
+
+impl PpiDescriptor {
+    /// Creates a descriptor for the given PPI. If `last` is true, the descriptor terminates the list.
+    pub const fn new(guid: *const efi::Guid, ppi: *mut c_void, last: bool) -> Self {
+        let flags = if last {
+            ppi_descriptor_flags::PPI | ppi_descriptor_flags::TERMINATE_LIST
+        } else {
+            ppi_descriptor_flags::PPI
+        };
+        Self { flags, guid, ppi }
+    }
+
+    /// Indicates whether this descriptor terminates the list.
+    pub const fn is_last(&self) -> bool {
+        self.flags & ppi_descriptor_flags::TERMINATE_LIST != 0
+    }
+}
+
+/// Entry point of the PEI Foundation, called by SEC. Does not return.
+///
+/// `sec_core_data` describes the temporary RAM and boot firmware volume, and `ppi_list` is a list of PPIs that SEC
+/// passes to the PEI Foundation, terminated by a descriptor with [`ppi_descriptor_flags::TERMINATE_LIST`] set.
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Volume 1, EFI_PEI_CORE_ENTRY_POINT
+pub type PeiCoreEntryPoint = extern "efiapi" fn(sec_core_data: *const SecPeiHandOff, ppi_list: *const PpiDescriptor);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pei_core_structures_layout() {
+        let hand_off = SecPeiHandOff::default();
+        assert_eq!(hand_off.data_size as usize, mem::size_of::<SecPeiHandOff>());
+        assert_eq!(mem::size_of::<SecPeiHandOff>(), 9 * mem::size_of::<usize>());
+        assert_eq!(mem::size_of::<PpiDescriptor>(), 3 * mem::size_of::<usize>());
+
+        let descriptor = PpiDescriptor::new(ptr::null(), ptr::null_mut(), true);
+        assert!(descriptor.is_last());
+        assert!(!PpiDescriptor::new(ptr::null(), ptr::null_mut(), false).is_last());
+    }
+}
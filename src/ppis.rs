@@ -0,0 +1,18 @@
+//! Platform Initialization PEIM-to-PEIM Interfaces (PPIs)
+//!
+//! Each PPI in the PI Specification is maintained as a separate module.
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+pub mod i2c_master;
+pub mod load_file;
+pub mod permanent_memory_installed;
+#[cfg(feature = "alloc")]
+pub mod ppi_database;
+pub mod smbus2;
+pub mod vector_handoff_info;
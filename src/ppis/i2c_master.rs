@@ -0,0 +1,62 @@
+//! I2C Master PPI
+//!
+//! Provides the services to perform I2C transactions on an I2C bus in the PEI phase. Each I2C host controller in the
+//! system is described by a separate instance of this PPI.
+//!
+//! See <https://uefi.org/specs/PI/1.8A/V5_I2C_Protocol_Stack.html>
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+use r_efi::efi;
+
+use crate::i2c::{ControllerCapabilities, RequestPacket};
+
+/// I2C Master PPI GUID
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Volume 5, EFI_PEI_I2C_MASTER_PPI
+pub const PPI_GUID: efi::Guid =
+    efi::Guid::from_fields(0xb3bfab9b, 0x9f9c, 0x4e8b, 0xad, 0x37, &[0x7f, 0x8c, 0x51, 0xfc, 0x62, 0x80]);
+
+/// Describes `EFI_PEI_I2C_MASTER_PPI` for the protocol registry.
+pub const INFO: crate::protocols::ProtocolInfo =
+    crate::protocols::ProtocolInfo { guid: PPI_GUID, name: "EFI_PEI_I2C_MASTER_PPI", module: module_path!() };
+
+/// Sets the frequency of the I2C clock. On input, `bus_clock_hertz` is the requested frequency in Hertz; on output
+/// it holds the frequency actually selected, which is the closest supported frequency that is not greater.
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Volume 5, EFI_PEI_I2C_MASTER_PPI.SetBusFrequency()
+pub type SetBusFrequency = extern "efiapi" fn(this: *const Ppi, bus_clock_hertz: *mut usize) -> efi::Status;
+
+/// Resets the I2C controller and configures it for use.
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Volume 5, EFI_PEI_I2C_MASTER_PPI.Reset()
+pub type Reset = extern "efiapi" fn(this: *const Ppi) -> efi::Status;
+
+/// Starts an I2C transaction on the host controller and waits for it to complete.
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Volume 5, EFI_PEI_I2C_MASTER_PPI.StartRequest()
+pub type StartRequest =
+    extern "efiapi" fn(this: *const Ppi, slave_address: usize, request_packet: *mut RequestPacket) -> efi::Status;
+
+/// Provides the I2C host controller services in the PEI phase.
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Volume 5, EFI_PEI_I2C_MASTER_PPI
+#[repr(C)]
+pub struct Ppi {
+    pub set_bus_frequency: SetBusFrequency,
+    pub reset: Reset,
+    pub start_request: StartRequest,
+    pub i2c_controller_capabilities: *const ControllerCapabilities,
+    /// Identifies the I2C host controller (and so the I2C bus) managed by this PPI instance.
+    pub identifier: efi::Guid,
+}
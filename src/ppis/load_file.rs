@@ -0,0 +1,45 @@
+//! Load File PPI
+//!
+//! Loads a PEIM or the DXE Foundation from a firmware file. The PEI Foundation locates the instances of this PPI and
+//! uses them to load each file it dispatches.
+//!
+//! See <https://uefi.org/specs/PI/1.8A/V1_PEI_Foundation.html>
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+use core::ffi::c_void;
+
+use r_efi::efi;
+
+crate::define_pi_protocol! {
+    /// Load File PPI GUID
+    ///
+    /// # Documentation
+    /// UEFI Platform Initialization Specification, Release 1.8, Volume 1, EFI_PEI_LOAD_FILE_PPI
+    guid: PPI_GUID = (0xb9e0abfe, 0x5979, 0x4914, 0x97, 0x7f, [0x6d, 0xee, 0x78, 0xc2, 0x78, 0xa6]);
+
+    /// Installed by a PEIM that supports the Load File PPI services for a particular image format.
+    ///
+    /// # Documentation
+    /// UEFI Platform Initialization Specification, Release 1.8, Volume 1, EFI_PEI_LOAD_FILE_PPI
+    struct Ppi = "EFI_PEI_LOAD_FILE_PPI" {
+        /// Loads a PEIM into memory for subsequent execution. The loaded image is described by `image_address`,
+        /// `image_size` and `entry_point` on return (see [`crate::image::LoadedImage`]).
+        ///
+        /// # Documentation
+        /// UEFI Platform Initialization Specification, Release 1.8, Volume 1, EFI_PEI_LOAD_FILE_PPI.LoadFile()
+        load_file: LoadFile = fn(
+            this: *const Ppi,
+            file_handle: *mut c_void,
+            image_address: *mut efi::PhysicalAddress,
+            image_size: *mut u64,
+            entry_point: *mut efi::PhysicalAddress,
+            authentication_state: *mut u32,
+        ) -> efi::Status,
+    }
+}
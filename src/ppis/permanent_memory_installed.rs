@@ -0,0 +1,31 @@
+//! Permanent Memory Installed PPI
+//!
+//! Installed by the PEI Foundation once it has switched from temporary RAM to the permanent memory registered with
+//! InstallPeiMemory(). The PPI has no interface: PEIMs register a notification on its GUID to learn that permanent
+//! memory is available (e.g. to migrate data they kept in temporary RAM). See [`crate::hob::migration`] for the
+//! corresponding migration of the HOB list.
+//!
+//! See <https://uefi.org/specs/PI/1.8A/V1_PEI_Foundation.html>
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+use r_efi::efi;
+
+/// Permanent Memory Installed PPI GUID
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Volume 1, EFI_PEI_PERMANENT_MEMORY_INSTALLED_PPI
+pub const PPI_GUID: efi::Guid =
+    efi::Guid::from_fields(0xf894643d, 0xc449, 0x42d1, 0x8e, 0xa8, &[0x85, 0xbd, 0xd8, 0xc6, 0x5b, 0xde]);
+
+/// Describes `EFI_PEI_PERMANENT_MEMORY_INSTALLED_PPI` for the protocol registry.
+pub const INFO: crate::protocols::ProtocolInfo = crate::protocols::ProtocolInfo {
+    guid: PPI_GUID,
+    name: "EFI_PEI_PERMANENT_MEMORY_INSTALLED_PPI",
+    module: module_path!(),
+};
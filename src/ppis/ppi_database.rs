@@ -0,0 +1,429 @@
+//! In-Memory PPI Database
+//!
+//! Host-side implementation of the PPI services of the PEI Foundation (InstallPpi, ReInstallPpi, LocatePpi and
+//! NotifyPpi), for unit testing PEIM logic without real firmware.
+//!
+//! PEIM logic is written against the [`PpiServices`] trait, and a [`PpiDatabase`] plays the role of the PEI
+//! Foundation in tests:
+//! - PPIs are located by GUID and zero-based instance, in installation order.
+//! - Callback notifications are invoked as soon as a matching PPI is installed or reinstalled, and for the matching
+//!   PPIs already installed when the notification is registered.
+//! - Dispatch notifications are invoked by [`PpiDatabase::dispatch_notifications`], which simulates the PEI Dispatcher
+//!   returning from a PEIM. Each dispatch notification is invoked once for each matching PPI instance.
+//!
+//! When a single event triggers several notifications, they are invoked in registration order, and for each
+//! notification, in the installation order of the PPIs.
+//!
+//! ## Example
+//! ```
+//! use core::{cell::Cell, ptr};
+//! use std::rc::Rc;
+//! use mu_pi::ppis::ppi_database::{NotifyType, Ppi, PpiDatabase, PpiNotify, PpiServices};
+//! use r_efi::efi;
+//!
+//! const MEMORY_DISCOVERED_GUID: efi::Guid =
+//!   efi::Guid::from_fields(0xf894643d, 0xc449, 0x42d1, 0x8e, 0xa8, &[0x85, 0xbd, 0xd8, 0xc6, 0x5b, 0xde]);
+//!
+//! // The PEIM logic under test.
+//! fn register_for_memory(services: &dyn PpiServices, discovered: Rc<Cell<bool>>) -> Result<(), efi::Status> {
+//!   let notify = PpiNotify::new(NotifyType::Callback, MEMORY_DISCOVERED_GUID, move |_, _| {
+//!     discovered.set(true);
+//!     efi::Status::SUCCESS
+//!   });
+//!   services.notify_ppi(&[notify])
+//! }
+//!
+//! let database = PpiDatabase::new();
+//! let discovered = Rc::new(Cell::new(false));
+//! register_for_memory(&database, discovered.clone()).unwrap();
+//! assert!(!discovered.get());
+//!
+//! database.install_ppi(&[Ppi::new(MEMORY_DISCOVERED_GUID, ptr::null_mut())]).unwrap();
+//! assert!(discovered.get());
+//! ```
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+use alloc::{rc::Rc, vec::Vec};
+use core::{cell::RefCell, ffi::c_void, fmt, ops::Range};
+
+use r_efi::efi;
+
+use crate::pei_core::{ppi_descriptor_flags, PpiDescriptor};
+
+/// A PPI: its GUID and a pointer to its interface, which may be NULL for PPIs that only signal an event.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Ppi {
+    /// GUID of the PPI.
+    pub guid: efi::Guid,
+    /// Pointer to the PPI interface.
+    pub interface: *mut c_void,
+}
+
+impl Ppi {
+    /// Creates a PPI with the given GUID and interface.
+    pub const fn new(guid: efi::Guid, interface: *mut c_void) -> Self {
+        Self { guid, interface }
+    }
+
+    /// Creates a PPI from an `EFI_PEI_PPI_DESCRIPTOR`.
+    ///
+    /// Returns `INVALID_PARAMETER` if the descriptor does not have [`ppi_descriptor_flags::PPI`] set or its GUID pointer
+    /// is NULL.
+    ///
+    /// # Safety
+    /// The GUID pointer of the descriptor must be NULL or valid for reads.
+    pub unsafe fn from_descriptor(descriptor: &PpiDescriptor) -> Result<Self, efi::Status> {
+        if descriptor.flags & ppi_descriptor_flags::PPI == 0 || descriptor.guid.is_null() {
+            return Err(efi::Status::INVALID_PARAMETER);
+        }
+        Ok(Self::new(*descriptor.guid, descriptor.ppi))
+    }
+}
+
+/// When a [`PpiNotify`] is invoked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NotifyType {
+    /// Invoked as soon as a matching PPI is installed (`EFI_PEI_PPI_DESCRIPTOR_NOTIFY_CALLBACK`).
+    Callback,
+    /// Invoked when the PEI Dispatcher returns from the PEIM that installed a matching PPI
+    /// (`EFI_PEI_PPI_DESCRIPTOR_NOTIFY_DISPATCH`).
+    Dispatch,
+}
+
+/// A notification function. It receives the PPI services and the PPI that triggered the notification. Its return
+/// value is ignored, as in the PEI Foundation.
+pub type NotifyFunction = dyn Fn(&dyn PpiServices, &Ppi) -> efi::Status;
+
+/// A notification registered for the installation of a PPI, the counterpart of an `EFI_PEI_NOTIFY_DESCRIPTOR`.
+#[derive(Clone)]
+pub struct PpiNotify {
+    notify_type: NotifyType,
+    guid: efi::Guid,
+    function: Rc<NotifyFunction>,
+}
+
+impl PpiNotify {
+    /// Creates a notification of the given type for the PPI with the given GUID.
+    pub fn new(
+        notify_type: NotifyType,
+        guid: efi::Guid,
+        function: impl Fn(&dyn PpiServices, &Ppi) -> efi::Status + 'static,
+    ) -> Self {
+        Self { notify_type, guid, function: Rc::new(function) }
+    }
+
+    /// Returns the type of the notification.
+    pub fn notify_type(&self) -> NotifyType {
+        self.notify_type
+    }
+
+    /// Returns the GUID of the PPI the notification is for.
+    pub fn guid(&self) -> efi::Guid {
+        self.guid
+    }
+}
+
+impl fmt::Debug for PpiNotify {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PpiNotify").field("notify_type", &self.notify_type).field("guid", &self.guid).finish()
+    }
+}
+
+/// The PPI services of the PEI Foundation.
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Volume 1, Section 4.4 PPI Services
+pub trait PpiServices {
+    /// Installs the PPIs in `ppi_list`, then invokes the callback notifications registered for them.
+    ///
+    /// Returns `INVALID_PARAMETER` if `ppi_list` is empty.
+    fn install_ppi(&self, ppi_list: &[Ppi]) -> Result<(), efi::Status>;
+
+    /// Replaces the installed PPI matching `old_ppi` (same GUID and interface) with `new_ppi`, then invokes the callback
+    /// notifications registered for `new_ppi`.
+    ///
+    /// Returns `NOT_FOUND` if `old_ppi` is not installed.
+    fn reinstall_ppi(&self, old_ppi: &Ppi, new_ppi: &Ppi) -> Result<(), efi::Status>;
+
+    /// Returns the interface of the zero-based `instance` of the installed PPIs with the given GUID.
+    ///
+    /// Returns `NOT_FOUND` if there are fewer such PPIs.
+    fn locate_ppi(&self, guid: &efi::Guid, instance: usize) -> Result<*mut c_void, efi::Status>;
+
+    /// Registers the notifications in `notify_list`. Callback notifications are invoked right away for the matching
+    /// PPIs already installed.
+    ///
+    /// Returns `INVALID_PARAMETER` if `notify_list` is empty.
+    fn notify_ppi(&self, notify_list: &[PpiNotify]) -> Result<(), efi::Status>;
+}
+
+#[derive(Default)]
+struct State {
+    ppis: Vec<Ppi>,
+    callbacks: Vec<PpiNotify>,
+    // Dispatch notifications, with the number of installed PPIs already processed for each.
+    dispatches: Vec<(PpiNotify, usize)>,
+}
+
+/// An in-memory PPI database, see the [module documentation](self).
+#[derive(Default)]
+pub struct PpiDatabase {
+    state: RefCell<State>,
+}
+
+impl PpiDatabase {
+    /// Creates an empty PPI database.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the installed PPIs, in installation order.
+    pub fn ppis(&self) -> Vec<Ppi> {
+        self.state.borrow().ppis.clone()
+    }
+
+    /// Invokes the dispatch notifications for the matching PPIs installed since the last call, as the PEI Dispatcher
+    /// does when a PEIM returns. PPIs installed by the notification functions are processed as well.
+    pub fn dispatch_notifications(&self) {
+        loop {
+            let pending = {
+                let mut state = self.state.borrow_mut();
+                let State { ppis, dispatches, .. } = &mut *state;
+                let mut pending = Vec::new();
+                for (notify, processed) in dispatches.iter_mut() {
+                    pending.extend(Self::matches(notify, ppis, *processed..ppis.len()));
+                    *processed = ppis.len();
+                }
+                pending
+            };
+            if pending.is_empty() {
+                break;
+            }
+            self.invoke(pending);
+        }
+    }
+
+    // Invokes the callback notifications in `notify_range` for the installed PPIs in `ppi_range`.
+    fn process_callbacks(&self, notify_range: Range<usize>, ppi_range: Range<usize>) {
+        let pending = {
+            let state = self.state.borrow();
+            state.callbacks[notify_range]
+                .iter()
+                .flat_map(|notify| Self::matches(notify, &state.ppis, ppi_range.clone()))
+                .collect::<Vec<_>>()
+        };
+        self.invoke(pending);
+    }
+
+    // Returns the (notification, PPI) pairs for the PPIs in `ppi_range` that match `notify`.
+    fn matches(notify: &PpiNotify, ppis: &[Ppi], ppi_range: Range<usize>) -> Vec<(Rc<NotifyFunction>, Ppi)> {
+        ppis[ppi_range]
+            .iter()
+            .filter(|ppi| ppi.guid == notify.guid)
+            .map(|ppi| (notify.function.clone(), *ppi))
+            .collect()
+    }
+
+    // Invokes the notification functions. No borrow of the state is held, so they can use the PPI services.
+    fn invoke(&self, pending: Vec<(Rc<NotifyFunction>, Ppi)>) {
+        for (function, ppi) in pending {
+            function(self, &ppi);
+        }
+    }
+}
+
+impl PpiServices for PpiDatabase {
+    fn install_ppi(&self, ppi_list: &[Ppi]) -> Result<(), efi::Status> {
+        if ppi_list.is_empty() {
+            return Err(efi::Status::INVALID_PARAMETER);
+        }
+        let (ppi_range, callback_count) = {
+            let mut state = self.state.borrow_mut();
+            let start = state.ppis.len();
+            state.ppis.extend_from_slice(ppi_list);
+            (start..state.ppis.len(), state.callbacks.len())
+        };
+        self.process_callbacks(0..callback_count, ppi_range);
+        Ok(())
+    }
+
+    fn reinstall_ppi(&self, old_ppi: &Ppi, new_ppi: &Ppi) -> Result<(), efi::Status> {
+        let (index, callback_count) = {
+            let mut state = self.state.borrow_mut();
+            let index = state.ppis.iter().position(|ppi| ppi == old_ppi).ok_or(efi::Status::NOT_FOUND)?;
+            state.ppis[index] = *new_ppi;
+            (index, state.callbacks.len())
+        };
+        self.process_callbacks(0..callback_count, index..index + 1);
+        Ok(())
+    }
+
+    fn locate_ppi(&self, guid: &efi::Guid, instance: usize) -> Result<*mut c_void, efi::Status> {
+        let state = self.state.borrow();
+        let ppi = state.ppis.iter().filter(|ppi| ppi.guid == *guid).nth(instance).ok_or(efi::Status::NOT_FOUND)?;
+        Ok(ppi.interface)
+    }
+
+    fn notify_ppi(&self, notify_list: &[PpiNotify]) -> Result<(), efi::Status> {
+        if notify_list.is_empty() {
+            return Err(efi::Status::INVALID_PARAMETER);
+        }
+        let (notify_range, ppi_count) = {
+            let mut state = self.state.borrow_mut();
+            let start = state.callbacks.len();
+            for notify in notify_list {
+                match notify.notify_type {
+                    NotifyType::Callback => state.callbacks.push(notify.clone()),
+                    NotifyType::Dispatch => state.dispatches.push((notify.clone(), 0)),
+                }
+            }
+            (start..state.callbacks.len(), state.ppis.len())
+        };
+        self.process_callbacks(notify_range, 0..ppi_count);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::{format, string::String};
+    use core::ptr;
+
+    const PPI_A: efi::Guid =
+        efi::Guid::from_fields(0x3b8a6c21, 0x5d0e, 0x4f7a, 0x9e, 0x12, &[0x6c, 0x44, 0x0a, 0xb7, 0x39, 0xe1]);
+    const PPI_B: efi::Guid =
+        efi::Guid::from_fields(0x3b8a6c22, 0x5d0e, 0x4f7a, 0x9e, 0x12, &[0x6c, 0x44, 0x0a, 0xb7, 0x39, 0xe1]);
+
+    fn interface(value: usize) -> *mut c_void {
+        value as *mut c_void
+    }
+
+    // Returns a notification that records "<name>:<interface>" in `log` when invoked.
+    fn logging_notify(
+        notify_type: NotifyType,
+        guid: efi::Guid,
+        name: &'static str,
+        log: &Rc<RefCell<Vec<String>>>,
+    ) -> PpiNotify {
+        let log = log.clone();
+        PpiNotify::new(notify_type, guid, move |_, ppi| {
+            log.borrow_mut().push(format!("{}:{}", name, ppi.interface as usize));
+            efi::Status::SUCCESS
+        })
+    }
+
+    #[test]
+    fn ppis_should_be_located_by_instance() {
+        let database = PpiDatabase::new();
+        assert_eq!(database.install_ppi(&[]), Err(efi::Status::INVALID_PARAMETER));
+        database.install_ppi(&[Ppi::new(PPI_A, interface(1)), Ppi::new(PPI_B, interface(2))]).unwrap();
+        database.install_ppi(&[Ppi::new(PPI_A, interface(3))]).unwrap();
+
+        assert_eq!(database.locate_ppi(&PPI_A, 0), Ok(interface(1)));
+        assert_eq!(database.locate_ppi(&PPI_A, 1), Ok(interface(3)));
+        assert_eq!(database.locate_ppi(&PPI_A, 2), Err(efi::Status::NOT_FOUND));
+        assert_eq!(database.locate_ppi(&PPI_B, 0), Ok(interface(2)));
+
+        // reinstalling keeps the instance in place.
+        database.reinstall_ppi(&Ppi::new(PPI_A, interface(1)), &Ppi::new(PPI_A, interface(4))).unwrap();
+        assert_eq!(database.locate_ppi(&PPI_A, 0), Ok(interface(4)));
+        assert_eq!(
+            database.reinstall_ppi(&Ppi::new(PPI_A, interface(1)), &Ppi::new(PPI_A, interface(5))),
+            Err(efi::Status::NOT_FOUND)
+        );
+        assert_eq!(database.ppis().len(), 3);
+    }
+
+    #[test]
+    fn callbacks_should_be_invoked_in_order() {
+        let database = PpiDatabase::new();
+        let log = Rc::new(RefCell::new(Vec::new()));
+        database.install_ppi(&[Ppi::new(PPI_A, interface(1))]).unwrap();
+
+        // callbacks are invoked for PPIs already installed.
+        database
+            .notify_ppi(&[
+                logging_notify(NotifyType::Callback, PPI_A, "first", &log),
+                logging_notify(NotifyType::Callback, PPI_A, "second", &log),
+            ])
+            .unwrap();
+        assert_eq!(*log.borrow(), ["first:1", "second:1"]);
+        log.borrow_mut().clear();
+
+        database
+            .install_ppi(&[Ppi::new(PPI_A, interface(2)), Ppi::new(PPI_B, interface(3)), Ppi::new(PPI_A, interface(4))])
+            .unwrap();
+        assert_eq!(*log.borrow(), ["first:2", "first:4", "second:2", "second:4"]);
+        log.borrow_mut().clear();
+
+        database.reinstall_ppi(&Ppi::new(PPI_A, interface(4)), &Ppi::new(PPI_A, interface(5))).unwrap();
+        assert_eq!(*log.borrow(), ["first:5", "second:5"]);
+        assert_eq!(database.notify_ppi(&[]), Err(efi::Status::INVALID_PARAMETER));
+    }
+
+    #[test]
+    fn dispatch_notifications_should_be_deferred() {
+        let database = PpiDatabase::new();
+        let log = Rc::new(RefCell::new(Vec::new()));
+        database.install_ppi(&[Ppi::new(PPI_A, interface(1))]).unwrap();
+        database.notify_ppi(&[logging_notify(NotifyType::Dispatch, PPI_A, "dispatch", &log)]).unwrap();
+        database.install_ppi(&[Ppi::new(PPI_A, interface(2))]).unwrap();
+        assert!(log.borrow().is_empty());
+
+        database.dispatch_notifications();
+        assert_eq!(*log.borrow(), ["dispatch:1", "dispatch:2"]);
+
+        // each PPI instance is only notified once.
+        database.dispatch_notifications();
+        assert_eq!(log.borrow().len(), 2);
+    }
+
+    #[test]
+    fn notifications_should_be_able_to_use_ppi_services() {
+        let database = PpiDatabase::new();
+        let log = Rc::new(RefCell::new(Vec::new()));
+        database
+            .notify_ppi(&[
+                PpiNotify::new(NotifyType::Callback, PPI_A, |services, ppi| {
+                    services.install_ppi(&[Ppi::new(PPI_B, ppi.interface)]).unwrap();
+                    efi::Status::SUCCESS
+                }),
+                logging_notify(NotifyType::Callback, PPI_B, "callback", &log),
+                PpiNotify::new(NotifyType::Dispatch, PPI_B, |services, ppi| {
+                    if (ppi.interface as usize) < 10 {
+                        services.install_ppi(&[Ppi::new(PPI_A, interface(ppi.interface as usize + 10))]).unwrap();
+                    }
+                    efi::Status::SUCCESS
+                }),
+            ])
+            .unwrap();
+
+        database.install_ppi(&[Ppi::new(PPI_A, interface(1))]).unwrap();
+        assert_eq!(*log.borrow(), ["callback:1"]);
+        assert_eq!(database.locate_ppi(&PPI_B, 0), Ok(interface(1)));
+
+        // the PPIs installed by dispatch notifications are processed in the same dispatch.
+        database.dispatch_notifications();
+        assert_eq!(*log.borrow(), ["callback:1", "callback:11"]);
+        assert_eq!(database.locate_ppi(&PPI_A, 1), Ok(interface(11)));
+        assert_eq!(database.locate_ppi(&PPI_B, 1), Ok(interface(11)));
+    }
+
+    #[test]
+    fn descriptors_should_be_validated() {
+        let descriptor = PpiDescriptor::new(&PPI_A, interface(1), true);
+        assert_eq!(unsafe { Ppi::from_descriptor(&descriptor) }, Ok(Ppi::new(PPI_A, interface(1))));
+
+        let descriptor = PpiDescriptor { flags: ppi_descriptor_flags::NOTIFY_CALLBACK, ..descriptor };
+        assert_eq!(unsafe { Ppi::from_descriptor(&descriptor) }, Err(efi::Status::INVALID_PARAMETER));
+        let descriptor = PpiDescriptor::new(ptr::null(), interface(1), true);
+        assert_eq!(unsafe { Ppi::from_descriptor(&descriptor) }, Err(efi::Status::INVALID_PARAMETER));
+    }
+}
@@ -0,0 +1,95 @@
+//! SMBus2 PPI
+//!
+//! Provides the basic I/O interfaces that a PEIM uses to access its SMBus controller and the slave devices attached to
+//! it, e.g. to read the SPD of the memory modules during memory initialization.
+//!
+//! See <https://uefi.org/specs/PI/1.8A/V5_SMBus_PPI_Code_Definitions.html>
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+use core::ffi::c_void;
+
+use r_efi::efi;
+
+use crate::smbus::{DeviceAddress, DeviceCommand, DeviceMap, Operation, Udid};
+
+/// SMBus2 PPI GUID
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Volume 5, EFI_PEI_SMBUS2_PPI
+pub const PPI_GUID: efi::Guid =
+    efi::Guid::from_fields(0x9ca93627, 0xb65b, 0x4324, 0xa2, 0x02, &[0xc0, 0xb4, 0x61, 0x76, 0x45, 0x43]);
+
+/// Describes `EFI_PEI_SMBUS2_PPI` for the protocol registry.
+pub const INFO: crate::protocols::ProtocolInfo =
+    crate::protocols::ProtocolInfo { guid: PPI_GUID, name: "EFI_PEI_SMBUS2_PPI", module: module_path!() };
+
+/// Executes an SMBus operation to an SMBus controller.
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Volume 5, EFI_PEI_SMBUS2_PPI.Execute()
+pub type Execute = extern "efiapi" fn(
+    this: *const Ppi,
+    slave_address: DeviceAddress,
+    command: DeviceCommand,
+    operation: Operation,
+    pec_check: efi::Boolean,
+    length: *mut usize,
+    buffer: *mut c_void,
+) -> efi::Status;
+
+/// Sets the SMBus slave device addresses for the device with a given unique ID, or enumerates the entire bus.
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Volume 5, EFI_PEI_SMBUS2_PPI.ArpDevice()
+pub type ArpDevice = extern "efiapi" fn(
+    this: *const Ppi,
+    arp_all: efi::Boolean,
+    smbus_udid: *mut Udid,
+    slave_address: *mut DeviceAddress,
+) -> efi::Status;
+
+/// Returns a pointer to the address resolution protocol (ARP) device map.
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Volume 5, EFI_PEI_SMBUS2_PPI.GetArpMap()
+pub type GetArpMap =
+    extern "efiapi" fn(this: *const Ppi, length: *mut usize, smbus_device_map: *mut *mut DeviceMap) -> efi::Status;
+
+/// Called when the SMBus controller receives a notification from the given slave device with the given data.
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Volume 5, EFI_PEI_SMBUS_NOTIFY2_FUNCTION
+pub type NotifyFunction =
+    extern "efiapi" fn(smbus_ppi: *const Ppi, slave_address: DeviceAddress, data: usize) -> efi::Status;
+
+/// Registers a callback function to be invoked when the given slave device sends the given data.
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Volume 5, EFI_PEI_SMBUS2_PPI.Notify()
+pub type Notify = extern "efiapi" fn(
+    this: *const Ppi,
+    slave_address: DeviceAddress,
+    data: usize,
+    notify_function: NotifyFunction,
+) -> efi::Status;
+
+/// Provides the basic I/O interfaces that a PEIM uses to access its SMBus controller and the slave devices attached
+/// to it.
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Volume 5, EFI_PEI_SMBUS2_PPI
+#[repr(C)]
+pub struct Ppi {
+    pub execute: Execute,
+    pub arp_device: ArpDevice,
+    pub get_arp_map: GetArpMap,
+    pub notify: Notify,
+    /// Identifies the SMBus controller managed by this PPI instance.
+    pub identifier: efi::Guid,
+}
@@ -0,0 +1,105 @@
+//! Vector Handoff Info PPI
+//!
+//! Describes the interrupt and exception vectors that SEC hooked and that the PEI and DXE phases must preserve when
+//! they install their own vector tables. The same table is passed to DXE as a configuration table.
+//!
+//! See <https://uefi.org/specs/PI/1.8A/V1_PEI_Services.html>
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+use r_efi::efi;
+
+/// Vector Handoff Info PPI GUID
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Volume 1, EFI_PEI_VECTOR_HANDOFF_INFO_PPI
+pub const PPI_GUID: efi::Guid =
+    efi::Guid::from_fields(0x3cd652b4, 0x6d33, 0x4dce, 0x89, 0xdb, &[0x83, 0xdf, 0x97, 0x66, 0xfc, 0xca]);
+
+/// Describes `EFI_PEI_VECTOR_HANDOFF_INFO_PPI` for the protocol registry.
+pub const INFO: crate::protocols::ProtocolInfo =
+    crate::protocols::ProtocolInfo { guid: PPI_GUID, name: "EFI_PEI_VECTOR_HANDOFF_INFO_PPI", module: module_path!() };
+
+/// GUID of the configuration table holding the vector handoff information in DXE.
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Volume 2, EFI_VECTOR_HANDOF_TABLE_GUID
+pub const VECTOR_HANDOFF_TABLE_GUID: efi::Guid =
+    efi::Guid::from_fields(0x996ec11c, 0x5397, 0x4e73, 0xb5, 0x8f, &[0x82, 0x7e, 0x52, 0x90, 0x6d, 0xef]);
+
+/// Values of [`VectorHandoffInfo::attribute`].
+pub mod attribute {
+    /// The vector was not hooked by SEC; it may be replaced.
+    pub const DO_NOT_HOOK: u32 = 0xffffffff;
+    /// The vector was hooked by SEC; the new handler must call the SEC handler before its own.
+    pub const HOOK_BEFORE: u32 = 0xfffffffe;
+    /// The vector was hooked by SEC; the new handler must call the SEC handler after its own.
+    pub const HOOK_AFTER: u32 = 0xfffffffd;
+    /// Marks the end of the vector handoff table.
+    pub const LAST_ENTRY: u32 = 0x80000000;
+}
+
+/// Describes how one vector was hooked by SEC.
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Volume 1, EFI_VECTOR_HANDOFF_INFO
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VectorHandoffInfo {
+    /// The interrupt or exception vector number.
+    pub vector_number: u32,
+    /// How the vector was hooked, one of [`attribute`].
+    pub attribute: u32,
+    /// The GUID of the module that hooked the vector.
+    pub owner: efi::Guid,
+}
+
+/// Points to the vector handoff table, an array of [`VectorHandoffInfo`] terminated by an entry with the
+/// [`attribute::LAST_ENTRY`] attribute.
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Volume 1, EFI_PEI_VECTOR_HANDOFF_INFO_PPI
+#[repr(C)]
+pub struct Ppi {
+    pub info: *mut VectorHandoffInfo,
+}
+
+/// Returns an iterator of the entries of the vector handoff table at `info`, not including the terminating entry.
+///
+/// ## Safety
+/// `info` must point to a vector handoff table terminated by an entry with the [`attribute::LAST_ENTRY`] attribute,
+/// which is not modified for the lifetime `'a`.
+pub unsafe fn entries<'a>(info: *const VectorHandoffInfo) -> impl Iterator<Item = &'a VectorHandoffInfo> {
+    let mut next = info;
+    core::iter::from_fn(move || {
+        let entry = &*next;
+        if entry.attribute == attribute::LAST_ENTRY {
+            return None;
+        }
+        next = next.add(1);
+        Some(entry)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn entries_should_stop_at_last_entry() {
+        let owner = efi::Guid::from_bytes(&[0x5a; 16]);
+        let table = [
+            VectorHandoffInfo { vector_number: 3, attribute: attribute::HOOK_BEFORE, owner },
+            VectorHandoffInfo { vector_number: 14, attribute: attribute::DO_NOT_HOOK, owner },
+            VectorHandoffInfo { vector_number: 0, attribute: attribute::LAST_ENTRY, owner },
+            VectorHandoffInfo { vector_number: 8, attribute: attribute::HOOK_AFTER, owner },
+        ];
+        let vectors = unsafe { entries(table.as_ptr()) }.map(|entry| entry.vector_number).collect::<Vec<_>>();
+        assert_eq!(vectors, [3, 14]);
+    }
+}
@@ -2,6 +2,12 @@
 //!
 //! Each protocol in the PI Specification is maintained as a separate module.
 //!
+//! [`PROTOCOLS`] maps the GUID of every protocol and PPI defined in this crate to its name and module, and [`lookup`]
+//! can be used to resolve a GUID to a human-readable protocol name (e.g. when dumping the handle database).
+//!
+//! Protocols (and PPIs) that consist only of function pointers should be defined with `define_pi_protocol!`, which
+//! keeps their modules structurally consistent and checks their layout at compile time.
+//!
 //! ## License
 //!
 //! Copyright (C) Microsoft Corporation. All rights reserved.
@@ -9,14 +15,266 @@
 //! SPDX-License-Identifier: BSD-2-Clause-Patent
 //!
 
+use r_efi::efi;
+
+/// Defines the GUID, function typedefs and `#[repr(C)]` interface struct of a protocol or PPI.
+///
+/// The macro expands to:
+/// - the GUID constant, with the given name and documentation;
+/// - an `extern "efiapi"` typedef for every function of the interface;
+/// - the `#[repr(C)]` interface struct, with one field per function;
+/// - compile-time assertions that the struct is laid out as a plain table of function pointers;
+/// - an `INFO` constant describing the interface, which is the entry expected in [`PROTOCOLS`].
+///
+/// Interfaces with data members (e.g. the Metronome Architectural Protocol) must be defined by hand.
+///
+/// ## Example
+/// ```
+/// use mu_pi::define_pi_protocol;
+/// use r_efi::efi;
+///
+/// define_pi_protocol! {
+///     /// Watchdog Architectural Protocol GUID
+///     guid: PROTOCOL_GUID = (0x665E3FF5, 0x46CC, 0x11d4, 0x9A, 0x38, [0x00, 0x90, 0x27, 0x3F, 0xC1, 0x4D]);
+///
+///     /// Used to program the watchdog timer and optionally register a handler when the watchdog timer fires.
+///     struct Protocol = "EFI_WATCHDOG_TIMER_ARCH_PROTOCOL" {
+///         /// Sets the amount of time in the future to fire the watchdog timer.
+///         set_timer_period: SetTimerPeriod = fn(*const Protocol, u64) -> efi::Status,
+///     }
+/// }
+///
+/// extern "efiapi" fn set_timer_period(_this: *const Protocol, _period: u64) -> efi::Status {
+///     efi::Status::SUCCESS
+/// }
+///
+/// let protocol = Protocol { set_timer_period };
+/// assert_eq!((protocol.set_timer_period)(&protocol, 0), efi::Status::SUCCESS);
+/// assert_eq!(INFO.guid, PROTOCOL_GUID);
+/// assert_eq!(INFO.name, "EFI_WATCHDOG_TIMER_ARCH_PROTOCOL");
+/// ```
+#[macro_export]
+macro_rules! define_pi_protocol {
+    (
+        $(#[$guid_doc:meta])*
+        guid: $guid:ident = (
+            $time_low:expr, $time_mid:expr, $time_hi:expr, $clk_seq_hi:expr, $clk_seq_low:expr,
+            [$($node:expr),* $(,)?] $(,)?
+        );
+
+        $(#[$struct_doc:meta])*
+        struct $interface:ident = $name:literal {
+            $(
+                $(#[$fn_doc:meta])*
+                $field:ident: $fn_type:ident = fn($($arg:tt)*) $(-> $ret:ty)?
+            ),* $(,)?
+        }
+    ) => {
+        $(#[$guid_doc])*
+        pub const $guid: $crate::r_efi::efi::Guid = $crate::r_efi::efi::Guid::from_fields(
+            $time_low, $time_mid, $time_hi, $clk_seq_hi, $clk_seq_low, &[$($node),*]
+        );
+
+        $(
+            $(#[$fn_doc])*
+            pub type $fn_type = extern "efiapi" fn($($arg)*) $(-> $ret)?;
+        )*
+
+        $(#[$struct_doc])*
+        #[repr(C)]
+        pub struct $interface {
+            $(pub $field: $fn_type,)*
+        }
+
+        #[doc = concat!("Describes `", $name, "` for the protocol registry.")]
+        pub const INFO: $crate::protocols::ProtocolInfo =
+            $crate::protocols::ProtocolInfo { guid: $guid, name: $name, module: module_path!() };
+
+        // The interface must be a table of pointer-sized function pointers without padding.
+        const _: () = {
+            let value = core::mem::MaybeUninit::<$interface>::uninit();
+            let base = value.as_ptr();
+            let offsets: &[isize] = &[$(
+                // SAFETY: no reference to the (uninitialized) field is created.
+                unsafe { core::ptr::addr_of!((*base).$field).cast::<u8>().offset_from(base.cast::<u8>()) }
+            ),*];
+            let mut index = 0;
+            while index < offsets.len() {
+                assert!(offsets[index] as usize == index * core::mem::size_of::<usize>(), "unexpected field offset");
+                index += 1;
+            }
+            assert!(core::mem::size_of::<$interface>() == offsets.len() * core::mem::size_of::<usize>());
+        };
+    };
+}
+
+#[cfg(feature = "dxe")]
 pub mod bds;
+#[cfg(feature = "dxe")]
 pub mod cpu_arch;
+#[cfg(feature = "dxe")]
+pub mod cpu_io2;
+#[cfg(all(feature = "dxe", feature = "fw_fs"))]
 pub mod firmware_volume;
+#[cfg(all(feature = "dxe", feature = "fw_fs"))]
 pub mod firmware_volume_block;
+#[cfg(feature = "dxe")]
+pub mod i2c_master;
+#[cfg(feature = "dxe")]
 pub mod metronome;
+#[cfg(feature = "mm")]
+pub mod mm_communication;
+#[cfg(feature = "mm")]
+pub mod mm_communication2;
+#[cfg(feature = "mm")]
+pub mod mm_communication3;
+#[cfg(all(feature = "alloc", feature = "mm"))]
+pub mod mm_communication_mock;
+#[cfg(feature = "mm")]
+pub mod mm_exit_boot_services;
+#[cfg(feature = "mm")]
+pub mod mm_ready_to_boot;
+#[cfg(all(feature = "alloc", feature = "dxe"))]
+pub mod protocol_database;
+#[cfg(feature = "dxe")]
 pub mod runtime;
+#[cfg(feature = "dxe")]
 pub mod security;
+#[cfg(feature = "dxe")]
 pub mod security2;
+#[cfg(feature = "dxe")]
+pub mod smbus_hc;
 pub mod status_code;
+#[cfg(feature = "dxe")]
 pub mod timer;
+#[cfg(feature = "dxe")]
 pub mod watchdog;
+
+/// Describes a protocol (or PPI) defined in this crate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProtocolInfo {
+    /// The protocol GUID.
+    pub guid: efi::Guid,
+    /// The protocol name as used in the PI Specification (e.g. `EFI_BDS_ARCH_PROTOCOL`).
+    pub name: &'static str,
+    /// The path of the module defining the protocol (e.g. `mu_pi::protocols::bds`).
+    pub module: &'static str,
+}
+
+/// All protocols and PPIs defined in this crate, for the enabled phase features.
+///
+/// Every entry is the `INFO` constant of the defining module.
+pub const PROTOCOLS: &[ProtocolInfo] = &[
+    #[cfg(feature = "dxe")]
+    bds::INFO,
+    #[cfg(feature = "dxe")]
+    cpu_arch::INFO,
+    #[cfg(feature = "dxe")]
+    cpu_io2::INFO,
+    #[cfg(all(feature = "dxe", feature = "fw_fs"))]
+    firmware_volume::INFO,
+    #[cfg(all(feature = "dxe", feature = "fw_fs"))]
+    firmware_volume_block::INFO,
+    #[cfg(feature = "dxe")]
+    i2c_master::INFO,
+    #[cfg(feature = "dxe")]
+    metronome::INFO,
+    #[cfg(feature = "mm")]
+    mm_communication::INFO,
+    #[cfg(feature = "mm")]
+    mm_communication2::INFO,
+    #[cfg(feature = "mm")]
+    mm_communication3::INFO,
+    #[cfg(feature = "mm")]
+    mm_exit_boot_services::INFO,
+    #[cfg(feature = "mm")]
+    mm_ready_to_boot::INFO,
+    #[cfg(feature = "dxe")]
+    runtime::INFO,
+    #[cfg(feature = "dxe")]
+    security::INFO,
+    #[cfg(feature = "dxe")]
+    security2::INFO,
+    #[cfg(feature = "dxe")]
+    smbus_hc::INFO,
+    status_code::INFO,
+    #[cfg(feature = "dxe")]
+    timer::INFO,
+    #[cfg(feature = "dxe")]
+    watchdog::INFO,
+    #[cfg(feature = "pei")]
+    crate::ppis::i2c_master::INFO,
+    #[cfg(feature = "pei")]
+    crate::ppis::load_file::INFO,
+    #[cfg(feature = "pei")]
+    crate::ppis::permanent_memory_installed::INFO,
+    #[cfg(feature = "pei")]
+    crate::ppis::smbus2::INFO,
+    #[cfg(feature = "pei")]
+    crate::ppis::vector_handoff_info::INFO,
+];
+
+/// Returns the protocol with the given GUID, if it is defined in this crate.
+///
+/// ## Example
+/// ```
+/// use mu_pi::protocols::{bds, lookup};
+///
+/// let info = lookup(&bds::PROTOCOL_GUID).unwrap();
+/// assert_eq!(info.name, "EFI_BDS_ARCH_PROTOCOL");
+/// assert_eq!(info.module, "mu_pi::protocols::bds");
+/// ```
+pub fn lookup(guid: &efi::Guid) -> Option<&'static ProtocolInfo> {
+    PROTOCOLS.iter().find(|info| &info.guid == guid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn protocol_guids_should_be_unique() {
+        for (index, info) in PROTOCOLS.iter().enumerate() {
+            assert_eq!(lookup(&info.guid), Some(info), "duplicate GUID for {}", info.name);
+            assert!(PROTOCOLS[index + 1..].iter().all(|other| other.name != info.name));
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "pei")]
+    fn lookup_should_find_ppis() {
+        let info = lookup(&crate::ppis::i2c_master::PPI_GUID).unwrap();
+        assert_eq!(info.name, "EFI_PEI_I2C_MASTER_PPI");
+        assert_eq!(info.module, "mu_pi::ppis::i2c_master");
+    }
+
+    #[test]
+    #[cfg(feature = "dxe")]
+    fn defined_protocols_should_be_registered() {
+        assert_eq!(lookup(&watchdog::PROTOCOL_GUID), Some(&watchdog::INFO));
+    }
+
+    #[test]
+    #[cfg(all(feature = "dxe", feature = "fw_fs", feature = "mm", feature = "pei"))]
+    fn every_public_guid_should_be_registered() {
+        use std::{env, fs, path::Path};
+
+        let root = Path::new(&env::var("CARGO_MANIFEST_DIR").unwrap()).join("src");
+        for (directory, guid) in [("protocols", "PROTOCOL_GUID"), ("ppis", "PPI_GUID")] {
+            for entry in fs::read_dir(root.join(directory)).unwrap() {
+                let path = entry.unwrap().path();
+                let source = fs::read_to_string(&path).unwrap();
+                if !source.contains(&format!("pub const {guid}:")) && !source.contains(&format!("guid: {guid} =")) {
+                    continue;
+                }
+                let module = format!("mu_pi::{directory}::{}", path.file_stem().unwrap().to_str().unwrap());
+                assert!(PROTOCOLS.iter().any(|info| info.module == module), "{module} is missing from PROTOCOLS");
+            }
+        }
+    }
+
+    #[test]
+    fn lookup_should_not_find_unknown_guid() {
+        assert_eq!(lookup(&efi::Guid::from_bytes(&[0; 16])), None);
+    }
+}
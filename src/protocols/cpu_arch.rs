@@ -20,6 +20,10 @@ use r_efi::efi;
 pub const PROTOCOL_GUID: efi::Guid =
     efi::Guid::from_fields(0x26baccb1, 0x6f42, 0x11d4, 0xbc, 0xe7, &[0x00, 0x80, 0xc7, 0x3c, 0x88, 0x81]);
 
+/// Describes `EFI_CPU_ARCH_PROTOCOL` for the protocol registry.
+pub const INFO: crate::protocols::ProtocolInfo =
+    crate::protocols::ProtocolInfo { guid: PROTOCOL_GUID, name: "EFI_CPU_ARCH_PROTOCOL", module: module_path!() };
+
 #[repr(C)]
 pub enum CpuFlushType {
     EfiCpuFlushTypeWriteBackInvalidate,
@@ -70,6 +74,8 @@ pub type EfiExceptionType = isize;
 
 /// Pointer to system context structure.
 ///
+/// The per-architecture context structures it points to are re-exported by [`crate::status_code::exception_data`].
+///
 /// # Documentation
 /// UEFI Specification version 2.10, Section 18.2.4
 pub type EfiSystemContext = efi::protocols::debug_support::SystemContext;
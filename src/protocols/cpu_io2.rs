@@ -0,0 +1,61 @@
+//! CPU I/O 2 Protocol
+//!
+//! Provides the basic memory and I/O interfaces that are used to abstract accesses to devices in a system.
+//!
+//! See <https://uefi.org/specs/PI/1.8A/V5_CPU_IO_Protocol.html>
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+use core::ffi::c_void;
+
+use r_efi::efi;
+
+pub use crate::io_access::Width;
+
+/// CPU I/O 2 Protocol GUID
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Volume 5, EFI_CPU_IO2_PROTOCOL
+pub const PROTOCOL_GUID: efi::Guid =
+    efi::Guid::from_fields(0xad61f191, 0xae5f, 0x4c0e, 0xb9, 0xfa, &[0xe8, 0x69, 0xd2, 0x88, 0xc6, 0x4f]);
+
+/// Describes `EFI_CPU_IO2_PROTOCOL` for the protocol registry.
+pub const INFO: crate::protocols::ProtocolInfo =
+    crate::protocols::ProtocolInfo { guid: PROTOCOL_GUID, name: "EFI_CPU_IO2_PROTOCOL", module: module_path!() };
+
+/// Enables a driver to access registers in the memory or I/O space.
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Volume 5, EFI_CPU_IO_PROTOCOL_IO_MEM
+pub type IoMem = extern "efiapi" fn(
+    this: *const Protocol,
+    width: Width,
+    address: u64,
+    count: usize,
+    buffer: *mut c_void,
+) -> efi::Status;
+
+/// Service for read and write accesses.
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Volume 5, EFI_CPU_IO_PROTOCOL_ACCESS
+#[repr(C)]
+pub struct Access {
+    pub read: IoMem,
+    pub write: IoMem,
+}
+
+/// Provides the basic memory and I/O interfaces used to abstract accesses to devices.
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Volume 5, EFI_CPU_IO2_PROTOCOL
+#[repr(C)]
+pub struct Protocol {
+    pub mem: Access,
+    pub io: Access,
+}
@@ -27,6 +27,13 @@ use r_efi::efi::{Guid, Handle, Status};
 pub const PROTOCOL_GUID: Guid =
     Guid::from_fields(0x220e73b6, 0x6bdb, 0x4413, 0x84, 0x5, &[0xb9, 0x74, 0xb1, 0x8, 0x61, 0x9a]);
 
+/// Describes `EFI_FIRMWARE_VOLUME2_PROTOCOL` for the protocol registry.
+pub const INFO: crate::protocols::ProtocolInfo = crate::protocols::ProtocolInfo {
+    guid: PROTOCOL_GUID,
+    name: "EFI_FIRMWARE_VOLUME2_PROTOCOL",
+    module: module_path!(),
+};
+
 pub type EfiFvWritePolicy = u32;
 
 #[repr(C)]
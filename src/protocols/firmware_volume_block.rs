@@ -21,6 +21,13 @@ use crate::{fw_fs::EfiFvbAttributes2, hob::EfiPhysicalAddress};
 pub const PROTOCOL_GUID: Guid =
     Guid::from_fields(0x8f644fa9, 0xe850, 0x4db1, 0x9c, 0xe2, &[0xb, 0x44, 0x69, 0x8e, 0x8d, 0xa4]);
 
+/// Describes `EFI_FIRMWARE_VOLUME_BLOCK2_PROTOCOL` for the protocol registry.
+pub const INFO: crate::protocols::ProtocolInfo = crate::protocols::ProtocolInfo {
+    guid: PROTOCOL_GUID,
+    name: "EFI_FIRMWARE_VOLUME_BLOCK2_PROTOCOL",
+    module: module_path!(),
+};
+
 pub type GetAttributes = extern "efiapi" fn(*mut Protocol, *mut EfiFvbAttributes2) -> Status;
 
 pub type SetAttributes = extern "efiapi" fn(*mut Protocol, *mut EfiFvbAttributes2) -> Status;
@@ -0,0 +1,69 @@
+//! I2C Master Protocol
+//!
+//! Provides the services to perform I2C transactions on an I2C bus in the DXE phase. This protocol is the lowest layer
+//! of the I2C protocol stack and is produced by the driver for an I2C host controller.
+//!
+//! See <https://uefi.org/specs/PI/1.8A/V5_I2C_Protocol_Stack.html>
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+use r_efi::efi;
+
+use crate::i2c::{ControllerCapabilities, RequestPacket};
+
+/// I2C Master Protocol GUID
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Volume 5, EFI_I2C_MASTER_PROTOCOL
+pub const PROTOCOL_GUID: efi::Guid =
+    efi::Guid::from_fields(0xcd72881f, 0x45b5, 0x4feb, 0x98, 0xc8, &[0x31, 0x3d, 0xa8, 0x11, 0x74, 0x62]);
+
+/// Describes `EFI_I2C_MASTER_PROTOCOL` for the protocol registry.
+pub const INFO: crate::protocols::ProtocolInfo =
+    crate::protocols::ProtocolInfo { guid: PROTOCOL_GUID, name: "EFI_I2C_MASTER_PROTOCOL", module: module_path!() };
+
+/// Sets the frequency of the I2C clock. On input, `bus_clock_hertz` is the requested frequency in Hertz; on output
+/// it holds the frequency actually selected, which is the closest supported frequency that is not greater.
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Volume 5, EFI_I2C_MASTER_PROTOCOL.SetBusFrequency()
+pub type SetBusFrequency = extern "efiapi" fn(this: *const Protocol, bus_clock_hertz: *mut usize) -> efi::Status;
+
+/// Resets the I2C controller and configures it for use.
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Volume 5, EFI_I2C_MASTER_PROTOCOL.Reset()
+pub type Reset = extern "efiapi" fn(this: *const Protocol) -> efi::Status;
+
+/// Starts an I2C transaction on the host controller.
+///
+/// The transaction is performed synchronously when `event` is null. Otherwise the call returns once the transaction
+/// is queued, and `event` is signaled when the transaction completes, at which point `i2c_status` (if not null) holds
+/// the completion status.
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Volume 5, EFI_I2C_MASTER_PROTOCOL.StartRequest()
+pub type StartRequest = extern "efiapi" fn(
+    this: *const Protocol,
+    slave_address: usize,
+    request_packet: *mut RequestPacket,
+    event: efi::Event,
+    i2c_status: *mut efi::Status,
+) -> efi::Status;
+
+/// Provides the I2C host controller services in the DXE phase.
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Volume 5, EFI_I2C_MASTER_PROTOCOL
+#[repr(C)]
+pub struct Protocol {
+    pub set_bus_frequency: SetBusFrequency,
+    pub reset: Reset,
+    pub start_request: StartRequest,
+    pub i2c_controller_capabilities: *const ControllerCapabilities,
+}
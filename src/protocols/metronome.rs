@@ -21,6 +21,10 @@ use r_efi::efi;
 pub const PROTOCOL_GUID: efi::Guid =
     efi::Guid::from_fields(0x26baccb2, 0x6f42, 0x11d4, 0xbc, 0xe7, &[0x00, 0x80, 0xc7, 0x3c, 0x88, 0x81]);
 
+/// Describes `EFI_METRONOME_ARCH_PROTOCOL` for the protocol registry.
+pub const INFO: crate::protocols::ProtocolInfo =
+    crate::protocols::ProtocolInfo { guid: PROTOCOL_GUID, name: "EFI_METRONOME_ARCH_PROTOCOL", module: module_path!() };
+
 /// Waits for a specified number of ticks from a known time source in a platform.
 ///
 /// # Documentation
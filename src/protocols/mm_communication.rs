@@ -0,0 +1,135 @@
+//! MM Communication Protocol
+//!
+//! Provides a means of communicating between drivers outside of MM and MMI handlers inside of MM. The buffer passed to
+//! [`Communicate`] starts with a [`CommunicateHeader`] that selects the MMI handler, followed by the message.
+//!
+//! See <https://uefi.org/specs/PI/1.8A/V4_UEFI_Protocols.html#efi-mm-communication-protocol>
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+use core::ffi::c_void;
+
+use r_efi::efi;
+
+/// The header of a communication buffer. The message follows the header and is `message_length` bytes long.
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Volume 4, EFI_MM_COMMUNICATE_HEADER
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CommunicateHeader {
+    /// The GUID of the MMI handler the message is for.
+    pub header_guid: efi::Guid,
+    /// The size of the message, not including the header.
+    pub message_length: usize,
+}
+
+define_pi_protocol! {
+    /// MM Communication Protocol GUID
+    ///
+    /// # Documentation
+    /// UEFI Platform Initialization Specification, Release 1.8, Volume 4, EFI_MM_COMMUNICATION_PROTOCOL
+    guid: PROTOCOL_GUID = (0xc68ed8e2, 0x9dc6, 0x4cbd, 0x9d, 0x94, [0xdb, 0x65, 0xac, 0xc5, 0xc3, 0x32]);
+
+    /// Provides the means to send messages to MMI handlers.
+    ///
+    /// # Documentation
+    /// UEFI Platform Initialization Specification, Release 1.8, Volume 4, EFI_MM_COMMUNICATION_PROTOCOL
+    struct Protocol = "EFI_MM_COMMUNICATION_PROTOCOL" {
+        /// Sends the communication buffer to the MMI handler registered for its header GUID.
+        ///
+        /// `comm_size` is optional. If given, it holds the size of the whole buffer on input and the size of the
+        /// returned data on output. On `BAD_BUFFER_SIZE`, `comm_size` and the message length in the header are updated
+        /// to the largest size the MM implementation can accommodate.
+        ///
+        /// # Documentation
+        /// UEFI Platform Initialization Specification, Release 1.8, Volume 4, EFI_MM_COMMUNICATION_PROTOCOL.Communicate()
+        communicate: Communicate = fn(
+            this: *const Protocol,
+            comm_buffer: *mut c_void,
+            comm_size: *mut usize,
+        ) -> efi::Status,
+    }
+}
+
+/// EFI_SMM_COMMUNICATION_PROTOCOL_GUID, the name of [`PROTOCOL_GUID`] before the PI Specification renamed SMM to MM.
+/// The GUID is unchanged, so cores that still publish the SMM-named protocol interoperate with this definition.
+pub const SMM_PROTOCOL_GUID: efi::Guid = PROTOCOL_GUID;
+
+/// EFI_SMM_COMMUNICATE_HEADER, the legacy name of [`CommunicateHeader`].
+pub type SmmCommunicateHeader = CommunicateHeader;
+
+/// EFI_SMM_COMMUNICATION_PROTOCOL, the legacy name of [`Protocol`].
+pub type SmmProtocol = Protocol;
+
+/// Arm MM conventions used by implementations of [`Communicate`] on Arm platforms.
+///
+/// On Arm, the MM environment runs in the secure world. The communication buffer is passed to it with an SMC (or
+/// FF-A) call: `x0` holds [`SMC_ID_MM_COMMUNICATE_AARCH64`](arm::SMC_ID_MM_COMMUNICATE_AARCH64), `x1` is reserved
+/// (zero), `x2` holds the physical address of the communication buffer and `x3` is reserved (zero). On return, `x0`
+/// holds one of the `SMC_MM_RET_*` values.
+///
+/// # Documentation
+/// Arm Management Mode Interface Specification (DEN0060A), Section 3
+pub mod arm {
+    use r_efi::efi;
+
+    /// Returns the MM interface version, `major << 16 | minor`.
+    pub const SMC_ID_MM_VERSION_AARCH32: u32 = 0x8400_0040;
+    /// Sends a communication buffer to MM, AArch32 calling convention.
+    pub const SMC_ID_MM_COMMUNICATE_AARCH32: u32 = 0x8400_0041;
+    /// Sends a communication buffer to MM, AArch64 calling convention.
+    pub const SMC_ID_MM_COMMUNICATE_AARCH64: u32 = 0xC400_0041;
+
+    /// The major version of the MM interface defined by DEN0060A.
+    pub const MM_MAJOR_VERSION: u16 = 1;
+    /// The minor version of the MM interface defined by DEN0060A.
+    pub const MM_MINOR_VERSION: u16 = 0;
+
+    /// The call completed successfully.
+    pub const SMC_MM_RET_SUCCESS: i64 = 0;
+    /// The function is not implemented by the MM environment.
+    pub const SMC_MM_RET_NOT_SUPPORTED: i64 = -1;
+    /// The buffer address or size is invalid.
+    pub const SMC_MM_RET_INVALID_PARAMS: i64 = -2;
+    /// The caller is not permitted to communicate with MM.
+    pub const SMC_MM_RET_DENIED: i64 = -3;
+    /// The MM environment has no memory to handle the message.
+    pub const SMC_MM_RET_NO_MEMORY: i64 = -5;
+
+    /// Converts the value returned in `x0` by an MM communicate call to the status [`Communicate`](super::Communicate)
+    /// returns, following the EDK II Arm MM communication driver.
+    pub fn status_from_smc_return(ret: i64) -> efi::Status {
+        match ret {
+            SMC_MM_RET_SUCCESS => efi::Status::SUCCESS,
+            SMC_MM_RET_NOT_SUPPORTED | SMC_MM_RET_INVALID_PARAMS => efi::Status::INVALID_PARAMETER,
+            SMC_MM_RET_NO_MEMORY => efi::Status::OUT_OF_RESOURCES,
+            _ => efi::Status::ACCESS_DENIED,
+        }
+    }
+
+    /// Packs an MM interface version as returned in `x0` by [`SMC_ID_MM_VERSION_AARCH32`].
+    pub const fn mm_version(major: u16, minor: u16) -> u32 {
+        (major as u32) << 16 | minor as u32
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn smc_returns_should_map_to_status() {
+            assert_eq!(status_from_smc_return(SMC_MM_RET_SUCCESS), efi::Status::SUCCESS);
+            assert_eq!(status_from_smc_return(SMC_MM_RET_INVALID_PARAMS), efi::Status::INVALID_PARAMETER);
+            assert_eq!(status_from_smc_return(SMC_MM_RET_DENIED), efi::Status::ACCESS_DENIED);
+            assert_eq!(status_from_smc_return(SMC_MM_RET_NO_MEMORY), efi::Status::OUT_OF_RESOURCES);
+            assert_eq!(status_from_smc_return(-42), efi::Status::ACCESS_DENIED);
+            assert_eq!(mm_version(MM_MAJOR_VERSION, MM_MINOR_VERSION), 0x1_0000);
+        }
+    }
+}
@@ -0,0 +1,45 @@
+//! MM Communication2 Protocol
+//!
+//! Same as the [MM Communication Protocol](super::mm_communication), except that both the physical and the virtual
+//! address of the communication buffer are passed, so it can be used after SetVirtualAddressMap().
+//!
+//! See <https://uefi.org/specs/PI/1.8A/V4_UEFI_Protocols.html#efi-mm-communication2-protocol>
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+use core::ffi::c_void;
+
+use r_efi::efi;
+
+define_pi_protocol! {
+    /// MM Communication2 Protocol GUID
+    ///
+    /// # Documentation
+    /// UEFI Platform Initialization Specification, Release 1.8, Volume 4, EFI_MM_COMMUNICATION2_PROTOCOL
+    guid: PROTOCOL_GUID = (0x378daedc, 0xf06b, 0x4446, 0x83, 0x14, [0x40, 0xab, 0x93, 0x3c, 0x87, 0xa3]);
+
+    /// Provides the means to send messages to MMI handlers.
+    ///
+    /// # Documentation
+    /// UEFI Platform Initialization Specification, Release 1.8, Volume 4, EFI_MM_COMMUNICATION2_PROTOCOL
+    struct Protocol = "EFI_MM_COMMUNICATION2_PROTOCOL" {
+        /// Sends the communication buffer to the MMI handler registered for its header GUID.
+        ///
+        /// The buffer starts with a [`CommunicateHeader`](super::mm_communication::CommunicateHeader), and `comm_size`
+        /// behaves as in [`mm_communication::Communicate`](super::mm_communication::Communicate).
+        ///
+        /// # Documentation
+        /// UEFI Platform Initialization Specification, Release 1.8, Volume 4, EFI_MM_COMMUNICATION2_PROTOCOL.Communicate()
+        communicate: Communicate = fn(
+            this: *const Protocol,
+            comm_buffer_physical: *mut c_void,
+            comm_buffer_virtual: *mut c_void,
+            comm_size: *mut usize,
+        ) -> efi::Status,
+    }
+}
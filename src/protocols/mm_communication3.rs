@@ -0,0 +1,55 @@
+//! MM Communication3 Protocol
+//!
+//! Like the [MM Communication2 Protocol](super::mm_communication2), but the communication buffer starts with a
+//! [`CommunicateHeader`] that carries the buffer size, so there is no separate size argument, and the header layout is
+//! the same on every architecture.
+//!
+//! This protocol is defined by EDK II (MdePkg/Include/Protocol/MmCommunication3.h), not by the PI Specification.
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+use core::ffi::c_void;
+
+use r_efi::efi;
+
+/// The value of [`CommunicateHeader::header_guid`].
+pub const COMMUNICATE_HEADER_GUID: efi::Guid =
+    efi::Guid::from_fields(0x68e8c853, 0x2ba9, 0x4dd7, 0x9a, 0xc0, &[0x91, 0xe1, 0x61, 0x55, 0xc9, 0x35]);
+
+/// The header of a communication buffer. The message follows the header and is `message_size` bytes long.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CommunicateHeader {
+    /// [`COMMUNICATE_HEADER_GUID`].
+    pub header_guid: efi::Guid,
+    /// The size of the whole communication buffer, including the header.
+    pub buffer_size: u64,
+    pub reserved: u64,
+    /// The GUID of the MMI handler the message is for.
+    pub message_guid: efi::Guid,
+    /// The size of the message, not including the header. Updated with the size of the returned data.
+    pub message_size: u64,
+}
+
+define_pi_protocol! {
+    /// MM Communication3 Protocol GUID
+    guid: PROTOCOL_GUID = (0xf7234a14, 0x0df2, 0x46c0, 0xad, 0x28, [0x90, 0xe6, 0xb8, 0x83, 0xa7, 0x2f]);
+
+    /// Provides the means to send messages to MMI handlers.
+    struct Protocol = "EFI_MM_COMMUNICATION3_PROTOCOL" {
+        /// Sends the communication buffer to the MMI handler registered for its message GUID.
+        ///
+        /// On `BAD_BUFFER_SIZE`, the message size in the header is updated to the largest size the MM implementation
+        /// can accommodate.
+        communicate: Communicate = fn(
+            this: *const Protocol,
+            comm_buffer_physical: *mut c_void,
+            comm_buffer_virtual: *mut c_void,
+        ) -> efi::Status,
+    }
+}
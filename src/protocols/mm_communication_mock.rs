@@ -0,0 +1,492 @@
+//! MM Communication Test Doubles
+//!
+//! Host-side implementations of the [MM Communication](super::mm_communication),
+//! [MM Communication2](super::mm_communication2) and [MM Communication3](super::mm_communication3) protocols, for unit
+//! testing DXE code that talks to MM without an MM environment.
+//!
+//! A [`MockMm`] plays the role of the MM environment: it dispatches messages to handlers registered by GUID, records
+//! every call, and can be configured to fail calls with `BAD_BUFFER_SIZE` or `ACCESS_DENIED`. The protocol instances
+//! ([`MockMmCommunication`], [`MockMmCommunication2`] and [`MockMmCommunication3`]) forward their calls to a shared
+//! [`MockMm`], and can be handed to the code under test as regular protocol pointers.
+//!
+//! ## Example
+//! ```
+//! use core::ffi::c_void;
+//! use mu_pi::protocols::{mm_communication, mm_communication_mock::{MockMm, MockMmCommunication}};
+//! use r_efi::efi;
+//!
+//! const HANDLER_GUID: efi::Guid = efi::Guid::from_fields(0, 0, 0, 0, 0, &[0, 0, 0, 0, 0, 1]);
+//!
+//! let mm = MockMm::new();
+//! mm.register_handler(HANDLER_GUID, |message| {
+//!   message[0] += 1;
+//!   1
+//! });
+//! let mock = MockMmCommunication::new(&mm);
+//! let protocol = mock.protocol();
+//!
+//! // The code under test builds a communication buffer and calls the protocol.
+//! let header = mm_communication::CommunicateHeader { header_guid: HANDLER_GUID, message_length: 1 };
+//! let mut buffer = [0u64; 4];
+//! unsafe { (buffer.as_mut_ptr() as *mut mm_communication::CommunicateHeader).write(header) };
+//! let status = (protocol.communicate)(protocol, buffer.as_mut_ptr() as *mut c_void, core::ptr::null_mut());
+//!
+//! assert_eq!(status, efi::Status::SUCCESS);
+//! assert_eq!(mm.calls()[0].response, [1]);
+//! ```
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+use alloc::{boxed::Box, collections::BTreeMap, vec::Vec};
+use core::{cell::RefCell, ffi::c_void, fmt, mem, ptr, slice};
+
+use r_efi::efi;
+
+use super::{mm_communication, mm_communication2, mm_communication3};
+
+/// An MMI handler. It receives the message, may overwrite it with its response, and returns the size of the response.
+type Handler = Box<dyn FnMut(&mut [u8]) -> usize>;
+
+/// The protocol through which a [`MockCall`] was made.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CommunicationProtocol {
+    Communication,
+    Communication2,
+    Communication3,
+}
+
+/// A call recorded by a [`MockMm`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MockCall {
+    /// The protocol the call was made through.
+    pub protocol: CommunicationProtocol,
+    /// The GUID of the MMI handler the message was for.
+    pub handler_guid: efi::Guid,
+    /// The message, as passed by the caller. Empty if the message did not fit in the communication buffer.
+    pub request: Vec<u8>,
+    /// The message on return. Empty unless the call was dispatched to a handler.
+    pub response: Vec<u8>,
+    /// The status returned to the caller.
+    pub status: efi::Status,
+}
+
+#[derive(Default)]
+struct State {
+    handlers: BTreeMap<efi::Guid, Handler>,
+    max_buffer_size: Option<usize>,
+    access_denied: bool,
+    calls: Vec<MockCall>,
+}
+
+/// A simulated MM environment, see the [module documentation](self).
+///
+/// Calls with a NULL communication buffer or a malformed header fail with `INVALID_PARAMETER`, and messages for a GUID
+/// with no registered handler fail with `NOT_FOUND`.
+#[derive(Default)]
+pub struct MockMm {
+    state: RefCell<State>,
+}
+
+impl MockMm {
+    /// Creates an MM environment with no handlers.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers the MMI handler for `guid`, replacing any previous one.
+    ///
+    /// The handler receives the message, may overwrite it with its response, and returns the size of the response,
+    /// which must not exceed the size of the message.
+    pub fn register_handler(&self, guid: efi::Guid, handler: impl FnMut(&mut [u8]) -> usize + 'static) {
+        self.state.borrow_mut().handlers.insert(guid, Box::new(handler));
+    }
+
+    /// Sets the largest communication buffer (header included) the MM environment accepts. Calls with a larger buffer
+    /// fail with `BAD_BUFFER_SIZE` and report this size back to the caller. `None` (the default) accepts any size.
+    pub fn set_max_buffer_size(&self, max_buffer_size: Option<usize>) {
+        self.state.borrow_mut().max_buffer_size = max_buffer_size;
+    }
+
+    /// Makes the communication buffer inaccessible to the MM environment, so that calls fail with `ACCESS_DENIED`.
+    pub fn set_access_denied(&self, access_denied: bool) {
+        self.state.borrow_mut().access_denied = access_denied;
+    }
+
+    /// Returns the calls made so far, in order.
+    pub fn calls(&self) -> Vec<MockCall> {
+        self.state.borrow().calls.clone()
+    }
+
+    /// Forgets the calls made so far.
+    pub fn clear_calls(&self) {
+        self.state.borrow_mut().calls.clear();
+    }
+
+    // Implements Communicate() of the MM Communication and MM Communication2 protocols.
+    fn communicate(&self, protocol: CommunicationProtocol, buffer: *mut u8, comm_size: *mut usize) -> efi::Status {
+        const HEADER_SIZE: usize = mem::size_of::<mm_communication::CommunicateHeader>();
+        if buffer.is_null() || (!comm_size.is_null() && unsafe { *comm_size } < HEADER_SIZE) {
+            return efi::Status::INVALID_PARAMETER;
+        }
+        let header = unsafe { ptr::read_unaligned(buffer as *const mm_communication::CommunicateHeader) };
+        let buffer_size =
+            if comm_size.is_null() { HEADER_SIZE.saturating_add(header.message_length) } else { unsafe { *comm_size } };
+
+        let mut message_length = header.message_length;
+        let status = self.dispatch(protocol, header.header_guid, buffer, HEADER_SIZE, buffer_size, &mut message_length);
+        if status == efi::Status::SUCCESS || status == efi::Status::BAD_BUFFER_SIZE {
+            let header = mm_communication::CommunicateHeader { message_length, ..header };
+            unsafe { ptr::write_unaligned(buffer as *mut mm_communication::CommunicateHeader, header) };
+            if !comm_size.is_null() {
+                unsafe { *comm_size = HEADER_SIZE + message_length };
+            }
+        }
+        status
+    }
+
+    // Implements Communicate() of the MM Communication3 protocol.
+    fn communicate3(&self, buffer: *mut u8) -> efi::Status {
+        const HEADER_SIZE: usize = mem::size_of::<mm_communication3::CommunicateHeader>();
+        if buffer.is_null() {
+            return efi::Status::INVALID_PARAMETER;
+        }
+        let header = unsafe { ptr::read_unaligned(buffer as *const mm_communication3::CommunicateHeader) };
+        let (Ok(buffer_size), Ok(mut message_size)) =
+            (usize::try_from(header.buffer_size), usize::try_from(header.message_size))
+        else {
+            return efi::Status::INVALID_PARAMETER;
+        };
+        if header.header_guid != mm_communication3::COMMUNICATE_HEADER_GUID || buffer_size < HEADER_SIZE {
+            return efi::Status::INVALID_PARAMETER;
+        }
+
+        let status = self.dispatch(
+            CommunicationProtocol::Communication3,
+            header.message_guid,
+            buffer,
+            HEADER_SIZE,
+            buffer_size,
+            &mut message_size,
+        );
+        if status == efi::Status::SUCCESS || status == efi::Status::BAD_BUFFER_SIZE {
+            let header = mm_communication3::CommunicateHeader { message_size: message_size as u64, ..header };
+            unsafe { ptr::write_unaligned(buffer as *mut mm_communication3::CommunicateHeader, header) };
+        }
+        status
+    }
+
+    // Validates the buffer, dispatches the message to its handler and records the call. On return, `message_size`
+    // holds the size of the response, or the largest accepted message size on BAD_BUFFER_SIZE.
+    fn dispatch(
+        &self,
+        protocol: CommunicationProtocol,
+        handler_guid: efi::Guid,
+        buffer: *mut u8,
+        header_size: usize,
+        buffer_size: usize,
+        message_size: &mut usize,
+    ) -> efi::Status {
+        let mut state = self.state.borrow_mut();
+        let fits = header_size.checked_add(*message_size).map_or(false, |end| end <= buffer_size);
+        let message =
+            if fits { unsafe { slice::from_raw_parts_mut(buffer.add(header_size), *message_size) } } else { &mut [] };
+        let mut call = MockCall {
+            protocol,
+            handler_guid,
+            request: message.to_vec(),
+            response: Vec::new(),
+            status: efi::Status::SUCCESS,
+        };
+
+        call.status = match state.max_buffer_size {
+            _ if !fits => efi::Status::INVALID_PARAMETER,
+            _ if state.access_denied => efi::Status::ACCESS_DENIED,
+            Some(max_buffer_size) if buffer_size > max_buffer_size => {
+                *message_size = max_buffer_size.saturating_sub(header_size);
+                efi::Status::BAD_BUFFER_SIZE
+            }
+            _ => match state.handlers.get_mut(&handler_guid) {
+                Some(handler) => {
+                    let response_size = handler(message);
+                    assert!(response_size <= message.len(), "MMI handler response exceeds the message size");
+                    *message_size = response_size;
+                    call.response = message[..response_size].to_vec();
+                    efi::Status::SUCCESS
+                }
+                None => efi::Status::NOT_FOUND,
+            },
+        };
+
+        let status = call.status;
+        state.calls.push(call);
+        status
+    }
+}
+
+impl fmt::Debug for MockMm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let state = self.state.borrow();
+        f.debug_struct("MockMm")
+            .field("handlers", &state.handlers.keys().collect::<Vec<_>>())
+            .field("max_buffer_size", &state.max_buffer_size)
+            .field("access_denied", &state.access_denied)
+            .field("calls", &state.calls)
+            .finish()
+    }
+}
+
+/// An MM Communication Protocol instance backed by a [`MockMm`].
+#[repr(C)]
+pub struct MockMmCommunication<'a> {
+    // Must be the first field, so that the protocol pointer can be converted back to the mock.
+    protocol: mm_communication::Protocol,
+    mm: &'a MockMm,
+}
+
+impl<'a> MockMmCommunication<'a> {
+    /// Creates a protocol instance that forwards calls to `mm`.
+    pub fn new(mm: &'a MockMm) -> Self {
+        Self { protocol: mm_communication::Protocol { communicate: Self::communicate }, mm }
+    }
+
+    /// Returns the protocol, to be passed to the code under test. It remains valid as long as the mock is not moved.
+    pub fn protocol(&self) -> &mm_communication::Protocol {
+        &self.protocol
+    }
+
+    extern "efiapi" fn communicate(
+        this: *const mm_communication::Protocol,
+        comm_buffer: *mut c_void,
+        comm_size: *mut usize,
+    ) -> efi::Status {
+        let this = unsafe { &*(this as *const MockMmCommunication) };
+        this.mm.communicate(CommunicationProtocol::Communication, comm_buffer as *mut u8, comm_size)
+    }
+}
+
+/// An MM Communication2 Protocol instance backed by a [`MockMm`].
+///
+/// The virtual address of the communication buffer is used, and the physical address is ignored.
+#[repr(C)]
+pub struct MockMmCommunication2<'a> {
+    // Must be the first field, so that the protocol pointer can be converted back to the mock.
+    protocol: mm_communication2::Protocol,
+    mm: &'a MockMm,
+}
+
+impl<'a> MockMmCommunication2<'a> {
+    /// Creates a protocol instance that forwards calls to `mm`.
+    pub fn new(mm: &'a MockMm) -> Self {
+        Self { protocol: mm_communication2::Protocol { communicate: Self::communicate }, mm }
+    }
+
+    /// Returns the protocol, to be passed to the code under test. It remains valid as long as the mock is not moved.
+    pub fn protocol(&self) -> &mm_communication2::Protocol {
+        &self.protocol
+    }
+
+    extern "efiapi" fn communicate(
+        this: *const mm_communication2::Protocol,
+        _comm_buffer_physical: *mut c_void,
+        comm_buffer_virtual: *mut c_void,
+        comm_size: *mut usize,
+    ) -> efi::Status {
+        let this = unsafe { &*(this as *const MockMmCommunication2) };
+        this.mm.communicate(CommunicationProtocol::Communication2, comm_buffer_virtual as *mut u8, comm_size)
+    }
+}
+
+/// An MM Communication3 Protocol instance backed by a [`MockMm`].
+///
+/// The virtual address of the communication buffer is used, and the physical address is ignored.
+#[repr(C)]
+pub struct MockMmCommunication3<'a> {
+    // Must be the first field, so that the protocol pointer can be converted back to the mock.
+    protocol: mm_communication3::Protocol,
+    mm: &'a MockMm,
+}
+
+impl<'a> MockMmCommunication3<'a> {
+    /// Creates a protocol instance that forwards calls to `mm`.
+    pub fn new(mm: &'a MockMm) -> Self {
+        Self { protocol: mm_communication3::Protocol { communicate: Self::communicate }, mm }
+    }
+
+    /// Returns the protocol, to be passed to the code under test. It remains valid as long as the mock is not moved.
+    pub fn protocol(&self) -> &mm_communication3::Protocol {
+        &self.protocol
+    }
+
+    extern "efiapi" fn communicate(
+        this: *const mm_communication3::Protocol,
+        _comm_buffer_physical: *mut c_void,
+        comm_buffer_virtual: *mut c_void,
+    ) -> efi::Status {
+        let this = unsafe { &*(this as *const MockMmCommunication3) };
+        this.mm.communicate3(comm_buffer_virtual as *mut u8)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ECHO_GUID: efi::Guid =
+        efi::Guid::from_fields(0x8a1f3c27, 0x41d2, 0x4e0b, 0x9c, 0x55, &[0x3e, 0x6a, 0x17, 0xb0, 0xd4, 0x92]);
+    const UNKNOWN_GUID: efi::Guid =
+        efi::Guid::from_fields(0x2b6e90d4, 0x7c13, 0x4a58, 0x81, 0xef, &[0x05, 0xc9, 0x6d, 0x3a, 0xb2, 0x48]);
+
+    // Builds an 8-byte aligned MM Communication buffer holding `message` for `guid`.
+    fn comm_buffer(guid: efi::Guid, message: &[u8]) -> Vec<u64> {
+        let header_size = mem::size_of::<mm_communication::CommunicateHeader>();
+        let mut buffer = vec![0u64; (header_size + message.len() + 7) / 8];
+        let header = mm_communication::CommunicateHeader { header_guid: guid, message_length: message.len() };
+        unsafe {
+            ptr::write_unaligned(buffer.as_mut_ptr() as *mut mm_communication::CommunicateHeader, header);
+            ptr::copy_nonoverlapping(
+                message.as_ptr(),
+                (buffer.as_mut_ptr() as *mut u8).add(header_size),
+                message.len(),
+            );
+        }
+        buffer
+    }
+
+    fn message_length(buffer: &[u64]) -> usize {
+        unsafe { ptr::read_unaligned(buffer.as_ptr() as *const mm_communication::CommunicateHeader) }.message_length
+    }
+
+    fn echo_mm() -> MockMm {
+        let mm = MockMm::new();
+        mm.register_handler(ECHO_GUID, |message| {
+            message.reverse();
+            message.len() - 1
+        });
+        mm
+    }
+
+    #[test]
+    fn communicate_should_dispatch_and_record() {
+        let mm = echo_mm();
+        let mock = MockMmCommunication::new(&mm);
+        let protocol = mock.protocol();
+
+        let mut buffer = comm_buffer(ECHO_GUID, &[1, 2, 3]);
+        let mut comm_size = mem::size_of::<u64>() * buffer.len();
+        let status = (protocol.communicate)(protocol, buffer.as_mut_ptr() as *mut c_void, &mut comm_size);
+        assert_eq!(status, efi::Status::SUCCESS);
+        assert_eq!(message_length(&buffer), 2);
+        assert_eq!(comm_size, mem::size_of::<mm_communication::CommunicateHeader>() + 2);
+
+        let mut buffer = comm_buffer(UNKNOWN_GUID, &[4]);
+        let status = (protocol.communicate)(protocol, buffer.as_mut_ptr() as *mut c_void, ptr::null_mut());
+        assert_eq!(status, efi::Status::NOT_FOUND);
+
+        let status = (protocol.communicate)(protocol, ptr::null_mut(), ptr::null_mut());
+        assert_eq!(status, efi::Status::INVALID_PARAMETER);
+
+        assert_eq!(
+            mm.calls(),
+            [
+                MockCall {
+                    protocol: CommunicationProtocol::Communication,
+                    handler_guid: ECHO_GUID,
+                    request: vec![1, 2, 3],
+                    response: vec![3, 2],
+                    status: efi::Status::SUCCESS,
+                },
+                MockCall {
+                    protocol: CommunicationProtocol::Communication,
+                    handler_guid: UNKNOWN_GUID,
+                    request: vec![4],
+                    response: Vec::new(),
+                    status: efi::Status::NOT_FOUND,
+                },
+            ]
+        );
+        mm.clear_calls();
+        assert!(mm.calls().is_empty());
+    }
+
+    #[test]
+    fn communicate_should_simulate_failures() {
+        let mm = echo_mm();
+        let mock = MockMmCommunication2::new(&mm);
+        let protocol = mock.protocol();
+        let header_size = mem::size_of::<mm_communication::CommunicateHeader>();
+
+        // A buffer larger than the MM environment accepts reports the largest message size.
+        mm.set_max_buffer_size(Some(header_size + 4));
+        let mut buffer = comm_buffer(ECHO_GUID, &[0; 16]);
+        let mut comm_size = header_size + 16;
+        let address = buffer.as_mut_ptr() as *mut c_void;
+        assert_eq!((protocol.communicate)(protocol, address, address, &mut comm_size), efi::Status::BAD_BUFFER_SIZE);
+        assert_eq!(comm_size, header_size + 4);
+        assert_eq!(message_length(&buffer), 4);
+
+        // The message now fits.
+        assert_eq!((protocol.communicate)(protocol, address, address, &mut comm_size), efi::Status::SUCCESS);
+
+        mm.set_access_denied(true);
+        assert_eq!((protocol.communicate)(protocol, address, address, &mut comm_size), efi::Status::ACCESS_DENIED);
+
+        // A message length beyond the buffer size is rejected.
+        mm.set_access_denied(false);
+        let mut comm_size = header_size + 1;
+        assert_eq!((protocol.communicate)(protocol, address, address, &mut comm_size), efi::Status::INVALID_PARAMETER);
+
+        let statuses = mm.calls().iter().map(|call| call.status).collect::<Vec<_>>();
+        assert_eq!(
+            statuses,
+            [
+                efi::Status::BAD_BUFFER_SIZE,
+                efi::Status::SUCCESS,
+                efi::Status::ACCESS_DENIED,
+                efi::Status::INVALID_PARAMETER
+            ]
+        );
+        assert!(mm.calls().iter().all(|call| call.protocol == CommunicationProtocol::Communication2));
+    }
+
+    #[test]
+    fn communicate3_should_use_message_guid() {
+        let mm = echo_mm();
+        let mock = MockMmCommunication3::new(&mm);
+        let protocol = mock.protocol();
+        let header_size = mem::size_of::<mm_communication3::CommunicateHeader>();
+        assert_eq!(header_size, 56);
+
+        let mut buffer = vec![0u64; 8];
+        let mut header = mm_communication3::CommunicateHeader {
+            header_guid: mm_communication3::COMMUNICATE_HEADER_GUID,
+            buffer_size: 64,
+            reserved: 0,
+            message_guid: ECHO_GUID,
+            message_size: 2,
+        };
+        let address = buffer.as_mut_ptr() as *mut c_void;
+        unsafe {
+            ptr::write_unaligned(address as *mut mm_communication3::CommunicateHeader, header);
+            *(address as *mut u8).add(header_size) = 7;
+        }
+        assert_eq!((protocol.communicate)(protocol, address, address), efi::Status::SUCCESS);
+        let returned = unsafe { ptr::read_unaligned(address as *const mm_communication3::CommunicateHeader) };
+        assert_eq!(returned.message_size, 1);
+        assert_eq!(mm.calls()[0].request, [7, 0]);
+        assert_eq!(mm.calls()[0].response, [0]);
+
+        mm.set_max_buffer_size(Some(header_size));
+        assert_eq!((protocol.communicate)(protocol, address, address), efi::Status::BAD_BUFFER_SIZE);
+        let returned = unsafe { ptr::read_unaligned(address as *const mm_communication3::CommunicateHeader) };
+        assert_eq!(returned.message_size, 0);
+
+        header.header_guid = ECHO_GUID;
+        unsafe { ptr::write_unaligned(address as *mut mm_communication3::CommunicateHeader, header) };
+        assert_eq!((protocol.communicate)(protocol, address, address), efi::Status::INVALID_PARAMETER);
+    }
+}
@@ -0,0 +1,35 @@
+//! MM Exit Boot Services Protocol
+//!
+//! A protocol with no interface that the MM core installs in the MM protocol database when the DXE phase signals the
+//! exit-boot-services event group. MM drivers that must stop using boot services resources, or that act when the OS
+//! takes over, register a protocol notification for [`PROTOCOL_GUID`].
+//!
+//! The DXE side signals the event by sending an MMI whose header GUID is [`MMI_HANDLER_GUID`], the exit-boot-services
+//! event group GUID, for which the MM core registers an MMI handler.
+//!
+//! This protocol is defined by EDK II (MdeModulePkg/Include/Protocol/SmmExitBootServices.h), not by the PI
+//! Specification.
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+use r_efi::efi;
+
+/// MM Exit Boot Services Protocol GUID (EDKII_SMM_EXIT_BOOT_SERVICES_PROTOCOL_GUID)
+pub const PROTOCOL_GUID: efi::Guid =
+    efi::Guid::from_fields(0x296eb418, 0xc4c8, 0x4e05, 0xab, 0x59, &[0x39, 0xe8, 0xaf, 0x56, 0xf0, 0x0a]);
+
+/// Describes `EDKII_SMM_EXIT_BOOT_SERVICES_PROTOCOL` for the protocol registry.
+pub const INFO: crate::protocols::ProtocolInfo = crate::protocols::ProtocolInfo {
+    guid: PROTOCOL_GUID,
+    name: "EDKII_SMM_EXIT_BOOT_SERVICES_PROTOCOL",
+    module: module_path!(),
+};
+
+/// The GUID of the MMI the DXE side sends to signal exit-boot-services to the MM core: the exit-boot-services event
+/// group GUID.
+pub const MMI_HANDLER_GUID: efi::Guid = efi::EVENT_GROUP_EXIT_BOOT_SERVICES;
@@ -0,0 +1,33 @@
+//! MM Ready To Boot Protocol
+//!
+//! A protocol with no interface that the MM core installs in the MM protocol database when the DXE phase signals the
+//! ready-to-boot event group. MM drivers that act at ready-to-boot (e.g. to lock down resources) register a protocol
+//! notification for [`PROTOCOL_GUID`].
+//!
+//! The DXE side signals the event by sending an MMI whose header GUID is [`MMI_HANDLER_GUID`], the ready-to-boot
+//! event group GUID, for which the MM core registers an MMI handler.
+//!
+//! This protocol is defined by EDK II (MdeModulePkg/Include/Protocol/SmmReadyToBoot.h), not by the PI Specification.
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+use r_efi::efi;
+
+/// MM Ready To Boot Protocol GUID (EDKII_SMM_READY_TO_BOOT_PROTOCOL_GUID)
+pub const PROTOCOL_GUID: efi::Guid =
+    efi::Guid::from_fields(0x6e057ecf, 0xfa99, 0x4f39, 0x95, 0x39, &[0x05, 0x4e, 0x9e, 0x86, 0xc4, 0x9a]);
+
+/// Describes `EDKII_SMM_READY_TO_BOOT_PROTOCOL` for the protocol registry.
+pub const INFO: crate::protocols::ProtocolInfo = crate::protocols::ProtocolInfo {
+    guid: PROTOCOL_GUID,
+    name: "EDKII_SMM_READY_TO_BOOT_PROTOCOL",
+    module: module_path!(),
+};
+
+/// The GUID of the MMI the DXE side sends to signal ready-to-boot to the MM core: the ready-to-boot event group GUID.
+pub const MMI_HANDLER_GUID: efi::Guid = efi::EVENT_GROUP_READY_TO_BOOT;
@@ -0,0 +1,533 @@
+//! In-Memory Protocol Handle Database
+//!
+//! Host-side implementation of the protocol handler services of the UEFI Boot Services (InstallProtocolInterface,
+//! UninstallProtocolInterface, HandleProtocol, OpenProtocol, CloseProtocol, OpenProtocolInformation, LocateHandleBuffer,
+//! LocateProtocol and ProtocolsPerHandle), for unit testing DXE driver logic without real firmware.
+//!
+//! DXE driver logic is written against the [`ProtocolServices`] trait, and a [`ProtocolDatabase`] plays the role of the
+//! DXE Foundation in tests. OpenProtocol follows the rules of the UEFI Specification for `BY_DRIVER`, `EXCLUSIVE` and
+//! `BY_CHILD_CONTROLLER` opens, with one difference: there is no driver model to disconnect drivers, so requests that
+//! would require disconnecting a driver (opening a protocol `EXCLUSIVE` while it is open `BY_DRIVER`, or uninstalling a
+//! protocol that is still open by a driver) fail with `ACCESS_DENIED`.
+//!
+//! Handles are allocated by the database and are never dereferenced.
+//!
+//! ## Example
+//! ```
+//! use core::ptr;
+//! use mu_pi::protocols::protocol_database::{ProtocolDatabase, ProtocolServices};
+//! use r_efi::efi;
+//!
+//! const IMAGE_GUID: efi::Guid = efi::Guid::from_fields(0, 0, 0, 0, 0, &[0, 0, 0, 0, 0, 1]);
+//! const DEVICE_GUID: efi::Guid = efi::Guid::from_fields(0, 0, 0, 0, 0, &[0, 0, 0, 0, 0, 2]);
+//!
+//! let database = ProtocolDatabase::new();
+//! let driver = database.install_protocol_interface(None, &IMAGE_GUID, ptr::null_mut()).unwrap();
+//! let controller = database.install_protocol_interface(None, &DEVICE_GUID, ptr::null_mut()).unwrap();
+//!
+//! // The driver logic under test opens the device protocol of the controller.
+//! database.open_protocol(controller, &DEVICE_GUID, driver, controller, efi::OPEN_PROTOCOL_BY_DRIVER).unwrap();
+//!
+//! // The controller is now managed by the driver.
+//! assert_eq!(
+//!   database.open_protocol(controller, &DEVICE_GUID, driver, controller, efi::OPEN_PROTOCOL_BY_DRIVER),
+//!   Err(efi::Status::ALREADY_STARTED)
+//! );
+//! assert_eq!(
+//!   database.uninstall_protocol_interface(controller, &DEVICE_GUID, ptr::null_mut()),
+//!   Err(efi::Status::ACCESS_DENIED)
+//! );
+//! ```
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+use alloc::vec::Vec;
+use core::{cell::RefCell, ffi::c_void, ptr};
+
+use r_efi::efi;
+
+/// The protocol handler services of the UEFI Boot Services.
+///
+/// # Documentation
+/// UEFI Specification, Release 2.10, Section 7.3 Protocol Handler Services
+pub trait ProtocolServices {
+    /// Installs the protocol on `handle`, or on a new handle if `handle` is `None`, and returns the handle.
+    ///
+    /// Returns `INVALID_PARAMETER` if `handle` is not a valid handle or already has the protocol installed.
+    fn install_protocol_interface(
+        &self,
+        handle: Option<efi::Handle>,
+        protocol: &efi::Guid,
+        interface: *mut c_void,
+    ) -> Result<efi::Handle, efi::Status>;
+
+    /// Removes the protocol from `handle`. The handle is removed from the database with its last protocol.
+    ///
+    /// Returns `NOT_FOUND` if the protocol is not installed on `handle` with the given interface, and `ACCESS_DENIED`
+    /// if it is still open `BY_DRIVER`, `BY_CHILD_CONTROLLER` or `EXCLUSIVE`.
+    fn uninstall_protocol_interface(
+        &self,
+        handle: efi::Handle,
+        protocol: &efi::Guid,
+        interface: *mut c_void,
+    ) -> Result<(), efi::Status>;
+
+    /// Returns the interface of the protocol on `handle`, opening it with `BY_HANDLE_PROTOCOL` and no agent.
+    fn handle_protocol(&self, handle: efi::Handle, protocol: &efi::Guid) -> Result<*mut c_void, efi::Status> {
+        self.open_protocol(handle, protocol, ptr::null_mut(), ptr::null_mut(), efi::OPEN_PROTOCOL_BY_HANDLE_PROTOCOL)
+    }
+
+    /// Opens the protocol on `handle` on behalf of `agent_handle` and `controller_handle`, and returns its interface
+    /// (NULL for `TEST_PROTOCOL`).
+    ///
+    /// Returns `INVALID_PARAMETER` for an invalid handle or attributes, `UNSUPPORTED` if the protocol is not installed
+    /// on `handle`, `ALREADY_STARTED` if the agent already has it open `BY_DRIVER` for the controller, and
+    /// `ACCESS_DENIED` if the open conflicts with a `BY_DRIVER` or `EXCLUSIVE` open.
+    fn open_protocol(
+        &self,
+        handle: efi::Handle,
+        protocol: &efi::Guid,
+        agent_handle: efi::Handle,
+        controller_handle: efi::Handle,
+        attributes: u32,
+    ) -> Result<*mut c_void, efi::Status>;
+
+    /// Closes the opens of the protocol on `handle` by `agent_handle` for `controller_handle`.
+    ///
+    /// Returns `INVALID_PARAMETER` for an invalid handle, and `NOT_FOUND` if the protocol is not installed on `handle`
+    /// or not open by the agent for the controller.
+    fn close_protocol(
+        &self,
+        handle: efi::Handle,
+        protocol: &efi::Guid,
+        agent_handle: efi::Handle,
+        controller_handle: efi::Handle,
+    ) -> Result<(), efi::Status>;
+
+    /// Returns the opens of the protocol on `handle`, in the order they were made.
+    ///
+    /// Returns `NOT_FOUND` if the protocol is not installed on `handle`.
+    fn open_protocol_information(
+        &self,
+        handle: efi::Handle,
+        protocol: &efi::Guid,
+    ) -> Result<Vec<efi::OpenProtocolInformationEntry>, efi::Status>;
+
+    /// Returns the handles that have the protocol installed, in the order they were created.
+    ///
+    /// Returns `NOT_FOUND` if there are no such handles.
+    fn locate_handle_buffer(&self, protocol: &efi::Guid) -> Result<Vec<efi::Handle>, efi::Status>;
+
+    /// Returns the interface of the protocol on the first handle that has it installed.
+    ///
+    /// Returns `NOT_FOUND` if there is no such handle.
+    fn locate_protocol(&self, protocol: &efi::Guid) -> Result<*mut c_void, efi::Status>;
+
+    /// Returns the protocols installed on `handle`, in the order they were installed.
+    ///
+    /// Returns `INVALID_PARAMETER` if `handle` is not a valid handle.
+    fn protocols_per_handle(&self, handle: efi::Handle) -> Result<Vec<efi::Guid>, efi::Status>;
+}
+
+struct ProtocolEntry {
+    guid: efi::Guid,
+    interface: *mut c_void,
+    opens: Vec<efi::OpenProtocolInformationEntry>,
+}
+
+struct HandleEntry {
+    handle: efi::Handle,
+    protocols: Vec<ProtocolEntry>,
+}
+
+#[derive(Default)]
+struct State {
+    handles: Vec<HandleEntry>,
+    handle_count: usize,
+}
+
+impl State {
+    fn is_valid(&self, handle: efi::Handle) -> bool {
+        !handle.is_null() && self.handles.iter().any(|entry| entry.handle == handle)
+    }
+
+    fn handle_mut(&mut self, handle: efi::Handle) -> Result<&mut HandleEntry, efi::Status> {
+        if handle.is_null() {
+            return Err(efi::Status::INVALID_PARAMETER);
+        }
+        self.handles.iter_mut().find(|entry| entry.handle == handle).ok_or(efi::Status::INVALID_PARAMETER)
+    }
+}
+
+/// An in-memory protocol handle database, see the [module documentation](self).
+#[derive(Default)]
+pub struct ProtocolDatabase {
+    state: RefCell<State>,
+}
+
+impl ProtocolDatabase {
+    /// Creates an empty protocol database.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns all the handles in the database, in the order they were created.
+    pub fn handles(&self) -> Vec<efi::Handle> {
+        self.state.borrow().handles.iter().map(|entry| entry.handle).collect()
+    }
+}
+
+// Attributes of an exclusive open by a driver.
+const OPEN_PROTOCOL_BY_DRIVER_EXCLUSIVE: u32 = efi::OPEN_PROTOCOL_BY_DRIVER | efi::OPEN_PROTOCOL_EXCLUSIVE;
+
+// Attributes that are removed by UninstallProtocolInterface without involving the driver that opened the protocol.
+const NON_DRIVER_ATTRIBUTES: u32 =
+    efi::OPEN_PROTOCOL_BY_HANDLE_PROTOCOL | efi::OPEN_PROTOCOL_GET_PROTOCOL | efi::OPEN_PROTOCOL_TEST_PROTOCOL;
+
+impl ProtocolServices for ProtocolDatabase {
+    fn install_protocol_interface(
+        &self,
+        handle: Option<efi::Handle>,
+        protocol: &efi::Guid,
+        interface: *mut c_void,
+    ) -> Result<efi::Handle, efi::Status> {
+        let mut state = self.state.borrow_mut();
+        let entry = match handle {
+            Some(handle) => {
+                let entry = state.handle_mut(handle)?;
+                if entry.protocols.iter().any(|protocol_entry| protocol_entry.guid == *protocol) {
+                    return Err(efi::Status::INVALID_PARAMETER);
+                }
+                entry
+            }
+            None => {
+                state.handle_count += 1;
+                let handle = state.handle_count as efi::Handle;
+                state.handles.push(HandleEntry { handle, protocols: Vec::new() });
+                state.handles.last_mut().unwrap()
+            }
+        };
+        entry.protocols.push(ProtocolEntry { guid: *protocol, interface, opens: Vec::new() });
+        Ok(entry.handle)
+    }
+
+    fn uninstall_protocol_interface(
+        &self,
+        handle: efi::Handle,
+        protocol: &efi::Guid,
+        interface: *mut c_void,
+    ) -> Result<(), efi::Status> {
+        let mut state = self.state.borrow_mut();
+        let entry = state.handle_mut(handle)?;
+        let index = entry
+            .protocols
+            .iter()
+            .position(|protocol_entry| protocol_entry.guid == *protocol && protocol_entry.interface == interface)
+            .ok_or(efi::Status::NOT_FOUND)?;
+        if entry.protocols[index].opens.iter().any(|open| open.attributes & !NON_DRIVER_ATTRIBUTES != 0) {
+            return Err(efi::Status::ACCESS_DENIED);
+        }
+        entry.protocols.remove(index);
+        if entry.protocols.is_empty() {
+            state.handles.retain(|entry| entry.handle != handle);
+        }
+        Ok(())
+    }
+
+    fn open_protocol(
+        &self,
+        handle: efi::Handle,
+        protocol: &efi::Guid,
+        agent_handle: efi::Handle,
+        controller_handle: efi::Handle,
+        attributes: u32,
+    ) -> Result<*mut c_void, efi::Status> {
+        let mut state = self.state.borrow_mut();
+        if !state.is_valid(handle) {
+            return Err(efi::Status::INVALID_PARAMETER);
+        }
+        let valid_parameters = match attributes {
+            efi::OPEN_PROTOCOL_BY_CHILD_CONTROLLER => {
+                state.is_valid(agent_handle) && state.is_valid(controller_handle) && handle != controller_handle
+            }
+            efi::OPEN_PROTOCOL_BY_DRIVER | OPEN_PROTOCOL_BY_DRIVER_EXCLUSIVE => {
+                state.is_valid(agent_handle) && state.is_valid(controller_handle)
+            }
+            efi::OPEN_PROTOCOL_EXCLUSIVE => state.is_valid(agent_handle),
+            efi::OPEN_PROTOCOL_BY_HANDLE_PROTOCOL
+            | efi::OPEN_PROTOCOL_GET_PROTOCOL
+            | efi::OPEN_PROTOCOL_TEST_PROTOCOL => true,
+            _ => false,
+        };
+        if !valid_parameters {
+            return Err(efi::Status::INVALID_PARAMETER);
+        }
+
+        let entry = state.handle_mut(handle)?;
+        let protocol_entry = entry
+            .protocols
+            .iter_mut()
+            .find(|protocol_entry| protocol_entry.guid == *protocol)
+            .ok_or(efi::Status::UNSUPPORTED)?;
+
+        let mut by_driver = false;
+        let mut exclusive = false;
+        for open in &protocol_entry.opens {
+            if open.attributes & efi::OPEN_PROTOCOL_BY_DRIVER != 0 {
+                by_driver = true;
+                if open.agent_handle == agent_handle
+                    && open.controller_handle == controller_handle
+                    && open.attributes == attributes
+                {
+                    return Err(efi::Status::ALREADY_STARTED);
+                }
+            }
+            if open.attributes & efi::OPEN_PROTOCOL_EXCLUSIVE != 0 {
+                exclusive = true;
+            }
+        }
+        // Driver and exclusive opens conflict with existing ones. For exclusive opens, the driver model would disconnect
+        // the drivers that have the protocol open BY_DRIVER instead.
+        let restricted_attributes =
+            [efi::OPEN_PROTOCOL_BY_DRIVER, efi::OPEN_PROTOCOL_EXCLUSIVE, OPEN_PROTOCOL_BY_DRIVER_EXCLUSIVE];
+        if restricted_attributes.contains(&attributes) && (by_driver || exclusive) {
+            return Err(efi::Status::ACCESS_DENIED);
+        }
+
+        if attributes == efi::OPEN_PROTOCOL_TEST_PROTOCOL {
+            return Ok(ptr::null_mut());
+        }
+        match protocol_entry.opens.iter_mut().find(|open| {
+            open.agent_handle == agent_handle
+                && open.controller_handle == controller_handle
+                && open.attributes == attributes
+        }) {
+            Some(open) => open.open_count += 1,
+            None => protocol_entry.opens.push(efi::OpenProtocolInformationEntry {
+                agent_handle,
+                controller_handle,
+                attributes,
+                open_count: 1,
+            }),
+        }
+        Ok(protocol_entry.interface)
+    }
+
+    fn close_protocol(
+        &self,
+        handle: efi::Handle,
+        protocol: &efi::Guid,
+        agent_handle: efi::Handle,
+        controller_handle: efi::Handle,
+    ) -> Result<(), efi::Status> {
+        let mut state = self.state.borrow_mut();
+        if !state.is_valid(agent_handle) || (!controller_handle.is_null() && !state.is_valid(controller_handle)) {
+            return Err(efi::Status::INVALID_PARAMETER);
+        }
+        let entry = state.handle_mut(handle)?;
+        let protocol_entry = entry
+            .protocols
+            .iter_mut()
+            .find(|protocol_entry| protocol_entry.guid == *protocol)
+            .ok_or(efi::Status::NOT_FOUND)?;
+        let open_count = protocol_entry.opens.len();
+        protocol_entry
+            .opens
+            .retain(|open| open.agent_handle != agent_handle || open.controller_handle != controller_handle);
+        if protocol_entry.opens.len() == open_count {
+            return Err(efi::Status::NOT_FOUND);
+        }
+        Ok(())
+    }
+
+    fn open_protocol_information(
+        &self,
+        handle: efi::Handle,
+        protocol: &efi::Guid,
+    ) -> Result<Vec<efi::OpenProtocolInformationEntry>, efi::Status> {
+        let mut state = self.state.borrow_mut();
+        let entry = state.handle_mut(handle).map_err(|_| efi::Status::NOT_FOUND)?;
+        let protocol_entry = entry
+            .protocols
+            .iter()
+            .find(|protocol_entry| protocol_entry.guid == *protocol)
+            .ok_or(efi::Status::NOT_FOUND)?;
+        Ok(protocol_entry.opens.clone())
+    }
+
+    fn locate_handle_buffer(&self, protocol: &efi::Guid) -> Result<Vec<efi::Handle>, efi::Status> {
+        let handles = self
+            .state
+            .borrow()
+            .handles
+            .iter()
+            .filter(|entry| entry.protocols.iter().any(|protocol_entry| protocol_entry.guid == *protocol))
+            .map(|entry| entry.handle)
+            .collect::<Vec<_>>();
+        if handles.is_empty() {
+            return Err(efi::Status::NOT_FOUND);
+        }
+        Ok(handles)
+    }
+
+    fn locate_protocol(&self, protocol: &efi::Guid) -> Result<*mut c_void, efi::Status> {
+        self.state
+            .borrow()
+            .handles
+            .iter()
+            .flat_map(|entry| entry.protocols.iter())
+            .find(|protocol_entry| protocol_entry.guid == *protocol)
+            .map(|protocol_entry| protocol_entry.interface)
+            .ok_or(efi::Status::NOT_FOUND)
+    }
+
+    fn protocols_per_handle(&self, handle: efi::Handle) -> Result<Vec<efi::Guid>, efi::Status> {
+        let mut state = self.state.borrow_mut();
+        let entry = state.handle_mut(handle)?;
+        Ok(entry.protocols.iter().map(|protocol_entry| protocol_entry.guid).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const IMAGE_GUID: efi::Guid =
+        efi::Guid::from_fields(0x5c1e4a90, 0x2f7d, 0x4b3e, 0xa6, 0x18, &[0x9d, 0x02, 0x7e, 0x4b, 0xc1, 0x35]);
+    const DEVICE_GUID: efi::Guid =
+        efi::Guid::from_fields(0x5c1e4a91, 0x2f7d, 0x4b3e, 0xa6, 0x18, &[0x9d, 0x02, 0x7e, 0x4b, 0xc1, 0x35]);
+    const OTHER_GUID: efi::Guid =
+        efi::Guid::from_fields(0x5c1e4a92, 0x2f7d, 0x4b3e, 0xa6, 0x18, &[0x9d, 0x02, 0x7e, 0x4b, 0xc1, 0x35]);
+
+    fn interface(value: usize) -> *mut c_void {
+        value as *mut c_void
+    }
+
+    // Returns a database with two driver image handles and a controller handle.
+    fn database() -> (ProtocolDatabase, efi::Handle, efi::Handle, efi::Handle) {
+        let database = ProtocolDatabase::new();
+        let driver1 = database.install_protocol_interface(None, &IMAGE_GUID, interface(1)).unwrap();
+        let driver2 = database.install_protocol_interface(None, &IMAGE_GUID, interface(2)).unwrap();
+        let controller = database.install_protocol_interface(None, &DEVICE_GUID, interface(3)).unwrap();
+        (database, driver1, driver2, controller)
+    }
+
+    #[test]
+    fn protocols_should_be_installed_and_located() {
+        let (database, driver1, driver2, controller) = database();
+        assert_eq!(database.handles(), [driver1, driver2, controller]);
+        assert_eq!(database.locate_handle_buffer(&IMAGE_GUID), Ok(vec![driver1, driver2]));
+        assert_eq!(database.locate_handle_buffer(&OTHER_GUID), Err(efi::Status::NOT_FOUND));
+        assert_eq!(database.locate_protocol(&IMAGE_GUID), Ok(interface(1)));
+        assert_eq!(database.handle_protocol(controller, &DEVICE_GUID), Ok(interface(3)));
+        assert_eq!(database.handle_protocol(controller, &IMAGE_GUID), Err(efi::Status::UNSUPPORTED));
+
+        assert_eq!(
+            database.install_protocol_interface(Some(controller), &DEVICE_GUID, interface(4)),
+            Err(efi::Status::INVALID_PARAMETER)
+        );
+        assert_eq!(
+            database.install_protocol_interface(Some(interface(100)), &OTHER_GUID, interface(4)),
+            Err(efi::Status::INVALID_PARAMETER)
+        );
+        assert_eq!(database.install_protocol_interface(Some(controller), &OTHER_GUID, interface(4)), Ok(controller));
+        assert_eq!(database.protocols_per_handle(controller), Ok(vec![DEVICE_GUID, OTHER_GUID]));
+
+        // the handle goes away with its last protocol.
+        assert_eq!(
+            database.uninstall_protocol_interface(controller, &OTHER_GUID, interface(5)),
+            Err(efi::Status::NOT_FOUND)
+        );
+        database.uninstall_protocol_interface(controller, &OTHER_GUID, interface(4)).unwrap();
+        database.uninstall_protocol_interface(controller, &DEVICE_GUID, interface(3)).unwrap();
+        assert_eq!(database.handles(), [driver1, driver2]);
+        assert_eq!(database.protocols_per_handle(controller), Err(efi::Status::INVALID_PARAMETER));
+    }
+
+    #[test]
+    fn by_driver_opens_should_follow_uefi_rules() {
+        let (database, driver1, driver2, controller) = database();
+        let open = |agent, attributes| database.open_protocol(controller, &DEVICE_GUID, agent, controller, attributes);
+
+        assert_eq!(open(driver1, efi::OPEN_PROTOCOL_BY_DRIVER), Ok(interface(3)));
+        assert_eq!(open(driver1, efi::OPEN_PROTOCOL_BY_DRIVER), Err(efi::Status::ALREADY_STARTED));
+        assert_eq!(open(driver2, efi::OPEN_PROTOCOL_BY_DRIVER), Err(efi::Status::ACCESS_DENIED));
+        assert_eq!(open(driver2, OPEN_PROTOCOL_BY_DRIVER_EXCLUSIVE), Err(efi::Status::ACCESS_DENIED));
+        assert_eq!(open(driver2, efi::OPEN_PROTOCOL_GET_PROTOCOL), Ok(interface(3)));
+        assert_eq!(open(driver2, efi::OPEN_PROTOCOL_GET_PROTOCOL), Ok(interface(3)));
+        assert_eq!(open(driver2, efi::OPEN_PROTOCOL_TEST_PROTOCOL), Ok(ptr::null_mut()));
+
+        let opens = database.open_protocol_information(controller, &DEVICE_GUID).unwrap();
+        assert_eq!(opens.len(), 2);
+        assert_eq!((opens[0].agent_handle, opens[0].attributes, opens[0].open_count), (driver1, 0x10, 1));
+        assert_eq!((opens[1].agent_handle, opens[1].attributes, opens[1].open_count), (driver2, 0x02, 2));
+
+        // the protocol cannot be uninstalled while the driver has it open.
+        assert_eq!(
+            database.uninstall_protocol_interface(controller, &DEVICE_GUID, interface(3)),
+            Err(efi::Status::ACCESS_DENIED)
+        );
+        database.close_protocol(controller, &DEVICE_GUID, driver1, controller).unwrap();
+        assert_eq!(database.close_protocol(controller, &DEVICE_GUID, driver1, controller), Err(efi::Status::NOT_FOUND));
+        assert_eq!(open(driver2, OPEN_PROTOCOL_BY_DRIVER_EXCLUSIVE), Ok(interface(3)));
+        assert_eq!(open(driver1, efi::OPEN_PROTOCOL_EXCLUSIVE), Err(efi::Status::ACCESS_DENIED));
+        assert_eq!(open(driver1, efi::OPEN_PROTOCOL_BY_DRIVER), Err(efi::Status::ACCESS_DENIED));
+
+        database.close_protocol(controller, &DEVICE_GUID, driver2, controller).unwrap();
+        database.uninstall_protocol_interface(controller, &DEVICE_GUID, interface(3)).unwrap();
+    }
+
+    #[test]
+    fn open_parameters_should_be_validated() {
+        let (database, driver1, _, controller) = database();
+        let child = database.install_protocol_interface(None, &OTHER_GUID, interface(4)).unwrap();
+
+        // BY_CHILD_CONTROLLER requires a controller other than the handle.
+        assert_eq!(
+            database.open_protocol(
+                controller,
+                &DEVICE_GUID,
+                driver1,
+                controller,
+                efi::OPEN_PROTOCOL_BY_CHILD_CONTROLLER
+            ),
+            Err(efi::Status::INVALID_PARAMETER)
+        );
+        assert_eq!(
+            database.open_protocol(controller, &DEVICE_GUID, driver1, child, efi::OPEN_PROTOCOL_BY_CHILD_CONTROLLER),
+            Ok(interface(3))
+        );
+        assert_eq!(
+            database.uninstall_protocol_interface(controller, &DEVICE_GUID, interface(3)),
+            Err(efi::Status::ACCESS_DENIED)
+        );
+
+        assert_eq!(
+            database.open_protocol(controller, &DEVICE_GUID, ptr::null_mut(), controller, efi::OPEN_PROTOCOL_BY_DRIVER),
+            Err(efi::Status::INVALID_PARAMETER)
+        );
+        assert_eq!(
+            database.open_protocol(controller, &DEVICE_GUID, driver1, controller, 0x40),
+            Err(efi::Status::INVALID_PARAMETER)
+        );
+        assert_eq!(
+            database.open_protocol(interface(100), &DEVICE_GUID, driver1, controller, efi::OPEN_PROTOCOL_BY_DRIVER),
+            Err(efi::Status::INVALID_PARAMETER)
+        );
+        assert_eq!(database.close_protocol(controller, &OTHER_GUID, driver1, controller), Err(efi::Status::NOT_FOUND));
+        assert_eq!(
+            database.close_protocol(controller, &DEVICE_GUID, interface(100), controller),
+            Err(efi::Status::INVALID_PARAMETER)
+        );
+
+        // opens by handle_protocol() are removed by uninstall.
+        database.close_protocol(controller, &DEVICE_GUID, driver1, child).unwrap();
+        database.handle_protocol(controller, &DEVICE_GUID).unwrap();
+        database.uninstall_protocol_interface(controller, &DEVICE_GUID, interface(3)).unwrap();
+    }
+}
@@ -19,6 +19,10 @@ use r_efi::efi;
 pub const PROTOCOL_GUID: efi::Guid =
     efi::Guid::from_fields(0xb7dfb4e1, 0x052f, 0x449f, 0x87, 0xbe, &[0x98, 0x18, 0xfc, 0x91, 0xb7, 0x33]);
 
+/// Describes `EFI_RUNTIME_ARCH_PROTOCOL` for the protocol registry.
+pub const INFO: crate::protocols::ProtocolInfo =
+    crate::protocols::ProtocolInfo { guid: PROTOCOL_GUID, name: "EFI_RUNTIME_ARCH_PROTOCOL", module: module_path!() };
+
 /// Allows the runtime functionality of the DXE Foundation to be contained
 /// in a separate driver. It also provides hooks for the DXE Foundation to
 /// export information that is needed at runtime. As such, this protocol allows
@@ -0,0 +1,85 @@
+//! SMBus Host Controller Protocol
+//!
+//! Provides SMBus host controller management and basic data transactions over SMBus. There is one instance of this
+//! protocol for each SMBus host controller.
+//!
+//! See <https://uefi.org/specs/PI/1.8A/V5_SMBus_Host_Controller_Code_Definitions.html>
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+use core::ffi::c_void;
+
+use r_efi::efi;
+
+use crate::smbus::{DeviceAddress, DeviceCommand, DeviceMap, Operation, Udid};
+
+/// Called when the SMBus host controller receives a notification from the given slave device with the given data.
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Volume 5, EFI_SMBUS_NOTIFY_FUNCTION
+pub type NotifyFunction = extern "efiapi" fn(slave_address: DeviceAddress, data: usize) -> efi::Status;
+
+define_pi_protocol! {
+    /// SMBus Host Controller Protocol GUID
+    ///
+    /// # Documentation
+    /// UEFI Platform Initialization Specification, Release 1.8, Volume 5, EFI_SMBUS_HC_PROTOCOL
+    guid: PROTOCOL_GUID = (0xe49d33ed, 0x513d, 0x4634, 0xb6, 0x98, [0x6f, 0x55, 0xaa, 0x75, 0x1c, 0x1b]);
+
+    /// Provides the basic I/O interfaces that are used to abstract an SMBus host controller.
+    ///
+    /// # Documentation
+    /// UEFI Platform Initialization Specification, Release 1.8, Volume 5, EFI_SMBUS_HC_PROTOCOL
+    struct Protocol = "EFI_SMBUS_HC_PROTOCOL" {
+        /// Executes an SMBus operation to an SMBus controller.
+        ///
+        /// # Documentation
+        /// UEFI Platform Initialization Specification, Release 1.8, Volume 5, EFI_SMBUS_HC_PROTOCOL.Execute()
+        execute: Execute = fn(
+            this: *const Protocol,
+            slave_address: DeviceAddress,
+            command: DeviceCommand,
+            operation: Operation,
+            pec_check: efi::Boolean,
+            length: *mut usize,
+            buffer: *mut c_void,
+        ) -> efi::Status,
+
+        /// Sets the SMBus slave device addresses for the device with a given unique ID, or enumerates the entire bus.
+        ///
+        /// # Documentation
+        /// UEFI Platform Initialization Specification, Release 1.8, Volume 5, EFI_SMBUS_HC_PROTOCOL.ArpDevice()
+        arp_device: ArpDevice = fn(
+            this: *const Protocol,
+            arp_all: efi::Boolean,
+            smbus_udid: *mut Udid,
+            slave_address: *mut DeviceAddress,
+        ) -> efi::Status,
+
+        /// Returns a pointer to the address resolution protocol (ARP) device map.
+        ///
+        /// # Documentation
+        /// UEFI Platform Initialization Specification, Release 1.8, Volume 5, EFI_SMBUS_HC_PROTOCOL.GetArpMap()
+        get_arp_map: GetArpMap = fn(
+            this: *const Protocol,
+            length: *mut usize,
+            smbus_device_map: *mut *mut DeviceMap,
+        ) -> efi::Status,
+
+        /// Registers a callback function to be invoked when the given slave device sends the given data.
+        ///
+        /// # Documentation
+        /// UEFI Platform Initialization Specification, Release 1.8, Volume 5, EFI_SMBUS_HC_PROTOCOL.Notify()
+        notify: Notify = fn(
+            this: *const Protocol,
+            slave_address: DeviceAddress,
+            data: usize,
+            notify_function: NotifyFunction,
+        ) -> efi::Status,
+    }
+}
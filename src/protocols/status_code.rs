@@ -13,9 +13,6 @@
 
 use r_efi::efi;
 
-pub const PROTOCOL_GUID: efi::Guid =
-    efi::Guid::from_fields(0xD2B2B828, 0x0826, 0x48A7, 0xB3, 0xDF, &[0x98, 0x3C, 0x00, 0x60, 0x24, 0xF0]);
-
 /// Status Code Type Definition.
 ///
 pub type EfiStatusCodeType = u32;
@@ -36,19 +33,25 @@ pub struct EfiStatusCodeData {
     pub r#type: efi::Guid,
 }
 
-/// Provides an interface that a software module can call to report a status code.
-///
-/// # Documentation
-/// UEFI Platform Initialization Specification, Release 1.8, Section II-14.2.1
-pub type ReportStatusCode =
-    extern "efiapi" fn(u32, u32, u32, *const efi::Guid, *const EfiStatusCodeData) -> efi::Status;
+define_pi_protocol! {
+    guid: PROTOCOL_GUID = (0xD2B2B828, 0x0826, 0x48A7, 0xB3, 0xDF, [0x98, 0x3C, 0x00, 0x60, 0x24, 0xF0]);
 
-/// Provides the service required to report a status code to the platform firmware.
-/// This protocol must be produced by a runtime DXE driver.
-///
-/// # Documentation
-/// UEFI Platform Initialization Specification, Release 1.8, Section II-14.2.1
-#[repr(C)]
-pub struct Protocol {
-    pub report_status_code: ReportStatusCode,
+    /// Provides the service required to report a status code to the platform firmware.
+    /// This protocol must be produced by a runtime DXE driver.
+    ///
+    /// # Documentation
+    /// UEFI Platform Initialization Specification, Release 1.8, Section II-14.2.1
+    struct Protocol = "EFI_STATUS_CODE_PROTOCOL" {
+        /// Provides an interface that a software module can call to report a status code.
+        ///
+        /// # Documentation
+        /// UEFI Platform Initialization Specification, Release 1.8, Section II-14.2.1
+        report_status_code: ReportStatusCode = fn(
+            u32,
+            u32,
+            u32,
+            *const efi::Guid,
+            *const EfiStatusCodeData,
+        ) -> efi::Status,
+    }
 }
@@ -0,0 +1,526 @@
+//! Boot Reports
+//!
+//! A versioned document that describes a boot for triage and regression comparison: the firmware volumes of the
+//! platform with their files, the HOB list handed off to DXE, and the status codes reported during the boot, in the
+//! order they were reported.
+//!
+//! A report is built from the parsed structures of this crate ([`BootReport::add_firmware_volume`],
+//! [`BootReport::add_hob_list`]) or from a [`capture`] of the boot ([`BootReport::add_capture`]). With the `serde`
+//! feature, it can be serialized into any format supported by serde.
+//!
+//! [`BootReport::add_firmware_volume_with_sections`] also records the sections of the files, the firmware volumes
+//! nested in them and optionally CRC32 hashes of their data, so that the layout of two images can be compared from
+//! their reports alone.
+//!
+//! ## Example
+//! ```
+//! use mu_pi::{fw_fs::FirmwareVolume, report::BootReport};
+//!
+//! fn report(flash: &[u8], capture: &[u8]) -> Result<BootReport, String> {
+//!   let mut report = BootReport::new();
+//!   for (offset, fv) in FirmwareVolume::find_all(flash) {
+//!     report.add_firmware_volume(offset as u64, &fv).map_err(|status| format!("{status:?}"))?;
+//!   }
+//!   report.add_capture(capture).map_err(|err| err.to_string())?;
+//!   Ok(report)
+//! }
+//! ```
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+use alloc::{boxed::Box, string::String, vec::Vec};
+use core::mem;
+use r_efi::efi;
+
+use crate::{
+    address_helper::align_up,
+    capture::{self, CaptureError, CapturedHobList, HandleDatabaseRecord, StatusCodeRecord},
+    crc32::crc32,
+    fw_fs::{
+        ffs, ExtractionLimits, FfsFileRawType, FfsSectionType, FirmwareVolume, Section, SectionExtractor,
+        SectionMetaData,
+    },
+    hob::{
+        decoders::{DecodedValue, GuidHobDecoders},
+        Hob, HobList,
+    },
+};
+
+/// The version of the report format, incremented on incompatible changes to it.
+pub const REPORT_VERSION: u32 = 1;
+
+/// A report describing a boot.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BootReport {
+    /// The [`REPORT_VERSION`] the report was produced with.
+    pub version: u32,
+    pub firmware_volumes: Vec<FirmwareVolumeEntry>,
+    pub hobs: Vec<HobEntry>,
+    /// The status codes, in the order they were reported.
+    pub status_codes: Vec<StatusCodeRecord>,
+    /// The last snapshot of the handle database, if one was captured.
+    pub handle_database: Option<HandleDatabaseRecord>,
+}
+
+/// A firmware volume and the files in it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FirmwareVolumeEntry {
+    /// The address (or offset in a flash image) of the firmware volume.
+    pub base_address: u64,
+    /// The name of the firmware volume, from its extended header.
+    #[cfg_attr(feature = "serde", serde(with = "capture::guid_serde"))]
+    pub name: Option<efi::Guid>,
+    pub size: u64,
+    pub used_size: u64,
+    pub files: Vec<FileEntry>,
+}
+
+/// A file in a firmware volume.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FileEntry {
+    #[cfg_attr(feature = "serde", serde(with = "capture::guid_serde::required"))]
+    pub name: efi::Guid,
+    /// The raw EFI_FV_FILETYPE of the file.
+    pub file_type: u8,
+    pub size: u64,
+    /// The name from the user interface section of the file, if it has one outside of encapsulation sections.
+    pub ui_name: Option<String>,
+    /// The offset of the file in its firmware volume.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub offset: u64,
+    /// The name of the file GUID, if it is well-known (with the `guid_names` feature).
+    pub known_name: Option<String>,
+    /// The CRC32 of the content of the file, if hashes were requested.
+    pub crc32: Option<u32>,
+    /// The sections of the file, if they were requested.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub sections: Vec<SectionEntry>,
+}
+
+/// A section of a file, and what it encapsulates.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SectionEntry {
+    /// The raw EFI_SECTION_TYPE of the section.
+    pub section_type: u8,
+    /// The offset of the section in the content of its file, or in the data extracted from its encapsulation section.
+    pub offset: u64,
+    pub size: u64,
+    /// The section definition GUID of a GUID_DEFINED section.
+    #[cfg_attr(feature = "serde", serde(with = "capture::guid_serde"))]
+    pub definition: Option<efi::Guid>,
+    /// The name of the section definition GUID, if it is well-known (with the `guid_names` feature).
+    pub definition_name: Option<String>,
+    /// The CRC32 of the section data as stored in the section, if hashes were requested.
+    pub crc32: Option<u32>,
+    /// The sections extracted from an encapsulation section.
+    pub sections: Vec<SectionEntry>,
+    /// The firmware volume of a FIRMWARE_VOLUME_IMAGE section, with a base address of 0.
+    pub firmware_volume: Option<Box<FirmwareVolumeEntry>>,
+}
+
+/// A HOB in the HOB list.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HobEntry {
+    pub hob_type: u16,
+    pub length: u16,
+    /// The GUID that names the HOB or its contents: the name of a GUID HOB or of a memory allocation, the owner of a
+    /// resource, or the name of a firmware volume.
+    #[cfg_attr(feature = "serde", serde(with = "capture::guid_serde"))]
+    pub name: Option<efi::Guid>,
+    /// A readable label for the name: the kind of a memory allocation (e.g. `Stack`), or the name of a well-known GUID
+    /// (e.g. `DxeCore` for the module of a memory allocation module HOB, with the `guid_names` feature).
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub label: Option<String>,
+    /// The base address of the range of memory the HOB describes.
+    pub base_address: Option<u64>,
+    /// The length of the range of memory the HOB describes.
+    pub range_length: Option<u64>,
+    /// The payload of a GUID HOB, decoded by the decoder registered for its name.
+    pub decoded: Option<DecodedValue>,
+}
+
+impl BootReport {
+    /// Creates an empty report.
+    pub fn new() -> Self {
+        Self {
+            version: REPORT_VERSION,
+            firmware_volumes: Vec::new(),
+            hobs: Vec::new(),
+            status_codes: Vec::new(),
+            handle_database: None,
+        }
+    }
+
+    /// Adds a firmware volume located at `base_address` to the report.
+    ///
+    /// Returns the error of the first file of the firmware volume that fails to parse, leaving the report unchanged.
+    pub fn add_firmware_volume(&mut self, base_address: u64, fv: &FirmwareVolume) -> Result<(), efi::Status> {
+        self.firmware_volumes.push(FirmwareVolumeEntry::new(base_address, fv, None, 0)?);
+        Ok(())
+    }
+
+    /// Adds a firmware volume located at `base_address` to the report, with the sections of its files.
+    ///
+    /// Encapsulation sections are expanded with `extractor`; sections it returns no data for have no nested sections.
+    /// With `hashes`, the entries hold the CRC32 of the content of files and of the data of sections.
+    ///
+    /// Returns the first error encountered while parsing or extracting, leaving the report unchanged.
+    pub fn add_firmware_volume_with_sections(
+        &mut self,
+        base_address: u64,
+        fv: &FirmwareVolume,
+        extractor: &dyn SectionExtractor,
+        hashes: bool,
+    ) -> Result<(), efi::Status> {
+        let options = SectionOptions { extractor, hashes };
+        self.firmware_volumes.push(FirmwareVolumeEntry::new(base_address, fv, Some(&options), 0)?);
+        Ok(())
+    }
+
+    /// Adds the HOBs of a HOB list to the report.
+    pub fn add_hob_list(&mut self, hob_list: &HobList) {
+        self.add_hob_list_with_decoders(hob_list, &GuidHobDecoders::new());
+    }
+
+    /// Adds the HOBs of a HOB list to the report, decoding the payloads of GUID HOBs with `decoders`.
+    pub fn add_hob_list_with_decoders(&mut self, hob_list: &HobList, decoders: &GuidHobDecoders) {
+        self.hobs.extend(hob_list.iter().map(|hob| HobEntry::with_decoders(hob, decoders)));
+    }
+
+    /// Adds the HOB lists, status codes and handle database snapshots of a capture to the report.
+    ///
+    /// Returns the error of the first frame or record that fails to decode; the records that precede it are added.
+    pub fn add_capture(&mut self, capture: &[u8]) -> Result<(), CaptureError> {
+        self.add_capture_with_decoders(capture, &GuidHobDecoders::new())
+    }
+
+    /// Behaves like [`BootReport::add_capture`], decoding the payloads of GUID HOBs with `decoders`.
+    pub fn add_capture_with_decoders(
+        &mut self,
+        capture: &[u8],
+        decoders: &GuidHobDecoders,
+    ) -> Result<(), CaptureError> {
+        for frame in capture::frames(capture) {
+            let frame = frame?;
+            match frame.record_type {
+                capture::record_type::HOB_LIST => {
+                    let hob_list = CapturedHobList::new(frame.payload);
+                    let hob_list = hob_list.hob_list().map_err(|_| CaptureError::MalformedRecord {
+                        record_type: frame.record_type,
+                        length: frame.payload.len(),
+                    })?;
+                    self.add_hob_list_with_decoders(&hob_list, decoders);
+                }
+                capture::record_type::STATUS_CODE => self.status_codes.push(StatusCodeRecord::decode(frame.payload)?),
+                capture::record_type::HANDLE_DATABASE => {
+                    self.handle_database = Some(HandleDatabaseRecord::decode(frame.payload)?)
+                }
+                _ => (),
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Default for BootReport {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// How the sections of files are added to a report.
+struct SectionOptions<'a> {
+    extractor: &'a dyn SectionExtractor,
+    hashes: bool,
+}
+
+#[cfg(feature = "guid_names")]
+fn known_name(guid: &efi::Guid) -> Option<String> {
+    crate::fw_fs::guid_names::lookup(guid).map(Into::into)
+}
+
+#[cfg(not(feature = "guid_names"))]
+fn known_name(_guid: &efi::Guid) -> Option<String> {
+    None
+}
+
+impl FirmwareVolumeEntry {
+    fn new(
+        base_address: u64,
+        fv: &FirmwareVolume,
+        options: Option<&SectionOptions>,
+        depth: usize,
+    ) -> Result<Self, efi::Status> {
+        let base = fv.data().as_ptr() as usize;
+        let files = fv
+            .file_iter()
+            .map(|file| {
+                let file = file?;
+                let mut entry = FileEntry {
+                    name: file.name(),
+                    file_type: file.file_type_raw(),
+                    size: file.size(),
+                    ui_name: file.ui_name().ok().flatten(),
+                    offset: (file.data().as_ptr() as usize - base) as u64,
+                    known_name: known_name(&file.name()),
+                    crc32: None,
+                    sections: Vec::new(),
+                };
+                if let Some(options) = options {
+                    entry.crc32 = options.hashes.then(|| crc32(file.content()));
+                    // only these file types hold sections.
+                    if (FfsFileRawType::FREEFORM..=FfsFileRawType::MM_CORE_STANDALONE).contains(&file.file_type_raw()) {
+                        entry.sections = SectionEntry::all(file.content(), options, depth + 1)?;
+                    }
+                }
+                Ok(entry)
+            })
+            .collect::<Result<Vec<_>, efi::Status>>()?;
+        Ok(Self { base_address, name: fv.fv_name(), size: fv.size(), used_size: fv.used_size()?, files })
+    }
+}
+
+impl SectionEntry {
+    // Creates the entries of the sections in `buffer`.
+    fn all(buffer: &[u8], options: &SectionOptions, depth: usize) -> Result<Vec<Self>, efi::Status> {
+        // extracted FVs and sections can nest without bound in a malicious image.
+        if depth > ExtractionLimits::default().max_depth * 2 {
+            Err(efi::Status::VOLUME_CORRUPTED)?;
+        }
+        let mut entries = Vec::new();
+        let mut offset = 0;
+        while buffer.len().saturating_sub(offset) >= mem::size_of::<ffs::section::Header>() {
+            let section = Section::new(&buffer[offset..])?;
+            let definition = match section.meta_data() {
+                SectionMetaData::GuidDefined(header, _) => Some(header.section_definition_guid),
+                _ => None,
+            };
+            let mut entry = Self {
+                section_type: section.section_type_raw(),
+                offset: offset as u64,
+                size: section.section_size() as u64,
+                definition,
+                definition_name: definition.as_ref().and_then(known_name),
+                crc32: options.hashes.then(|| crc32(section.raw_section_data())),
+                sections: Vec::new(),
+                firmware_volume: None,
+            };
+            if section.section_type() == Some(FfsSectionType::FirmwareVolumeImage) {
+                let fv = section.firmware_volume()?;
+                entry.firmware_volume = Some(Box::new(FirmwareVolumeEntry::new(0, &fv, Some(options), depth + 1)?));
+            } else if section.is_encapsulation() {
+                entry.sections = Self::all(&options.extractor.extract(&section)?, options, depth + 1)?;
+            }
+            // sections are 4-byte aligned.
+            offset += align_up(entry.size, 4) as usize;
+            entries.push(entry);
+        }
+        Ok(entries)
+    }
+}
+
+impl HobEntry {
+    /// Creates the entry of a HOB.
+    pub fn new(hob: &Hob) -> Self {
+        Self::with_decoders(hob, &GuidHobDecoders::new())
+    }
+
+    /// Creates the entry of a HOB, decoding the payload of a GUID HOB with `decoders`.
+    pub fn with_decoders(hob: &Hob, decoders: &GuidHobDecoders) -> Self {
+        let header = hob.header();
+        let (name, range) = match hob {
+            Hob::MemoryAllocation(hob) => (
+                Some(hob.alloc_descriptor.name),
+                Some((hob.alloc_descriptor.memory_base_address, hob.alloc_descriptor.memory_length)),
+            ),
+            Hob::MemoryAllocationModule(hob) => (
+                Some(hob.module_name),
+                Some((hob.alloc_descriptor.memory_base_address, hob.alloc_descriptor.memory_length)),
+            ),
+            Hob::ResourceDescriptor(hob) => (Some(hob.owner), Some((hob.physical_start, hob.resource_length))),
+            Hob::GuidHob(hob, _) => (Some(hob.name), None),
+            Hob::FirmwareVolume(hob) => (None, Some((hob.base_address, hob.length))),
+            Hob::FirmwareVolume2(hob) => (Some(hob.fv_name), Some((hob.base_address, hob.length))),
+            Hob::FirmwareVolume3(hob) => (Some(hob.fv_name), Some((hob.base_address, hob.length))),
+            Hob::Capsule(hob) => (None, Some((hob.base_address, hob.length))),
+            Hob::Handoff(_) | Hob::Cpu(_) | Hob::Vendor(..) => (None, None),
+        };
+        let label = match hob {
+            Hob::MemoryAllocation(hob) => hob.kind().label().map(Into::into),
+            Hob::MemoryAllocationModule(hob) => hob.module_label().map(Into::into),
+            _ => name.as_ref().and_then(known_name),
+        };
+        Self {
+            hob_type: header.r#type,
+            length: header.length,
+            name,
+            label,
+            base_address: range.map(|(base, _)| base),
+            range_length: range.map(|(_, length)| length),
+            decoded: decoders.decode(hob),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+    use std::{env, fs, path::Path};
+
+    use super::*;
+    use crate::{hob, status_code};
+
+    const HOB_NAME: efi::Guid =
+        efi::Guid::from_fields(0x1234abcd, 0x5678, 0x9abc, 0xde, 0xf0, &[0x01, 0x23, 0x45, 0x67, 0x89, 0xab]);
+
+    fn test_report() -> BootReport {
+        let root = Path::new(&env::var("CARGO_MANIFEST_DIR").unwrap()).join("test_resources");
+        let fv_bytes = fs::read(root.join("DXEFV.Fv")).unwrap();
+        let fv = FirmwareVolume::new(&fv_bytes).unwrap();
+
+        // a GUID HOB holding a u64, then the END_OF_HOB_LIST HOB.
+        let mut hob_list = Vec::new();
+        hob_list.extend_from_slice(&hob::GUID_EXTENSION.to_le_bytes());
+        hob_list.extend_from_slice(&[32, 0, 0, 0, 0, 0]);
+        hob_list.extend_from_slice(HOB_NAME.as_bytes());
+        hob_list.extend_from_slice(&0x1234u64.to_le_bytes());
+        hob_list.extend_from_slice(&[0xff, 0xff, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00]);
+
+        let mut capture = Vec::new();
+        capture::encode_hob_list(&hob_list, &mut |bytes| capture.extend_from_slice(bytes));
+        for value in [status_code::EFI_SW_DXE_CORE_PC_ENTRY_POINT, status_code::EFI_SW_DXE_CORE_PC_HANDOFF_TO_NEXT] {
+            capture::encode_status_code(
+                status_code::EFI_PROGRESS_CODE,
+                status_code::EFI_SOFTWARE_DXE_CORE | value,
+                0,
+                None,
+                &[],
+                &mut |bytes| capture.extend_from_slice(bytes),
+            );
+        }
+
+        let mut decoders = GuidHobDecoders::new();
+        decoders
+            .register(HOB_NAME, |payload| Some(DecodedValue::Integer(u64::from_le_bytes(payload.try_into().ok()?))));
+
+        let mut report = BootReport::new();
+        report.add_firmware_volume_with_sections(0x1000, &fv, &NoExtraction, true).unwrap();
+        report.add_capture_with_decoders(&capture, &decoders).unwrap();
+        report
+    }
+
+    struct NoExtraction;
+
+    impl SectionExtractor for NoExtraction {
+        fn extract(&self, _section: &Section) -> Result<Box<[u8]>, efi::Status> {
+            Ok(Box::new([]))
+        }
+    }
+
+    #[test]
+    fn sections_should_be_reported_with_offsets_and_hashes() {
+        let root = Path::new(&env::var("CARGO_MANIFEST_DIR").unwrap()).join("test_resources");
+        let fv_bytes = fs::read(root.join("DXEFV.Fv")).unwrap();
+        let fv = FirmwareVolume::new(&fv_bytes).unwrap();
+        let mut report = BootReport::new();
+        report.add_firmware_volume(0, &fv).unwrap();
+        report.add_firmware_volume_with_sections(0, &fv, &NoExtraction, true).unwrap();
+        let (files, detailed_files) = (&report.firmware_volumes[0].files, &report.firmware_volumes[1].files);
+
+        assert_eq!(files.len(), fv.file_iter().count());
+        for ((file, detailed), fv_file) in files.iter().zip(detailed_files).zip(fv.file_iter()) {
+            assert_eq!((file.name, file.offset, file.size), (detailed.name, detailed.offset, detailed.size));
+            assert!(file.sections.is_empty() && file.crc32.is_none());
+            let fv_file = fv_file.unwrap();
+            assert_eq!(fv_file.data().as_ptr(), fv_bytes[file.offset as usize..].as_ptr());
+            assert_eq!(detailed.crc32, Some(crc32(fv_file.content())));
+
+            // sections follow each other at 4-byte aligned offsets in the content of the file.
+            let mut offset = 0;
+            for (section, fv_section) in detailed.sections.iter().zip(fv_file.section_iter()) {
+                let fv_section = fv_section.unwrap();
+                assert_eq!(section.offset, offset);
+                assert_eq!(section.section_type, fv_section.section_type_raw());
+                assert_eq!(section.crc32, Some(crc32(fv_section.raw_section_data())));
+                offset = align_up(section.offset + section.size, 4);
+            }
+        }
+
+        let dxe_core = detailed_files.iter().find(|file| file.ui_name.as_deref() == Some("DxeRust")).unwrap();
+        assert!(dxe_core.sections.iter().any(|section| section.section_type == ffs::section::raw_type::PE32));
+    }
+
+    #[cfg(feature = "brotli")]
+    #[test]
+    fn nested_volumes_should_be_reported_in_their_sections() {
+        use crate::fw_fs::extractors::BrotliSectionExtractor;
+
+        let root = Path::new(&env::var("CARGO_MANIFEST_DIR").unwrap()).join("test_resources");
+        let fv_bytes = fs::read(root.join("FVMAIN_COMPACT.Fv")).unwrap();
+        let fv = FirmwareVolume::new(&fv_bytes).unwrap();
+        let mut report = BootReport::new();
+        report.add_firmware_volume_with_sections(0, &fv, &BrotliSectionExtractor {}, false).unwrap();
+
+        // the FV image file holds a brotli GUID_DEFINED section, holding the FV image sections.
+        let guided = &report.firmware_volumes[0].files[0].sections[0];
+        assert_eq!(guided.definition, Some(ffs::guid::BROTLI_CUSTOM_DECOMPRESS_GUID));
+        assert!(guided.crc32.is_none());
+        let volumes = guided.sections.iter().filter_map(|section| section.firmware_volume.as_deref());
+        assert!(volumes
+            .flat_map(|volume| &volume.files)
+            .any(|file| file.ui_name.as_deref() == Some("DxeCore") && !file.sections.is_empty()));
+    }
+
+    #[test]
+    fn report_should_aggregate_volumes_hobs_and_status_codes() {
+        let report = test_report();
+        assert_eq!(report.version, REPORT_VERSION);
+
+        assert_eq!(report.firmware_volumes.len(), 1);
+        let fv = &report.firmware_volumes[0];
+        assert_eq!(fv.base_address, 0x1000);
+        assert!(!fv.files.is_empty());
+        assert!(fv.files.iter().any(|file| file.ui_name.as_deref() == Some("DxeRust")));
+
+        assert_eq!(
+            report.hobs,
+            [HobEntry {
+                hob_type: hob::GUID_EXTENSION,
+                length: 32,
+                name: Some(HOB_NAME),
+                label: None,
+                base_address: None,
+                range_length: None,
+                decoded: Some(DecodedValue::Integer(0x1234)),
+            }]
+        );
+
+        assert_eq!(
+            report.status_codes.iter().map(|record| record.value).collect::<Vec<_>>(),
+            [
+                status_code::EFI_SOFTWARE_DXE_CORE | status_code::EFI_SW_DXE_CORE_PC_ENTRY_POINT,
+                status_code::EFI_SOFTWARE_DXE_CORE | status_code::EFI_SW_DXE_CORE_PC_HANDOFF_TO_NEXT
+            ]
+        );
+        assert!(report.handle_database.is_none());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn report_should_round_trip_through_serde() {
+        let report = test_report();
+        let yaml = serde_yaml::to_string(&report).unwrap();
+        assert!(yaml.contains("1234ABCD-5678-9ABC-DEF0-0123456789AB"));
+        assert_eq!(serde_yaml::from_str::<BootReport>(&yaml).unwrap(), report);
+    }
+}
@@ -0,0 +1,107 @@
+//! SMBus Definitions
+//!
+//! Device address, operation and ARP (Address Resolution Protocol) device map structures shared by the SMBus host
+//! controller protocol ([`crate::protocols::smbus_hc`]) and the PEI SMBus2 PPI ([`crate::ppis::smbus2`]).
+//!
+//! See <https://uefi.org/specs/PI/1.8A/V5_SMBus_Host_Controller_Code_Definitions.html>.
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+/// The 7-bit address of an SMBus slave device.
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Volume 5, EFI_SMBUS_DEVICE_ADDRESS
+#[repr(transparent)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct DeviceAddress(usize);
+
+impl DeviceAddress {
+    /// Creates a device address from a 7-bit slave address. Bits above bit 6 are ignored.
+    pub const fn new(address: u8) -> Self {
+        Self((address & 0x7f) as usize)
+    }
+
+    /// Returns the 7-bit slave address.
+    pub const fn address(&self) -> u8 {
+        (self.0 & 0x7f) as u8
+    }
+}
+
+/// The SMBus command (the register or offset within the device) sent to a slave device.
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Volume 5, EFI_SMBUS_DEVICE_COMMAND
+pub type DeviceCommand = usize;
+
+/// The SMBus protocol operations.
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Volume 5, EFI_SMBUS_OPERATION
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operation {
+    QuickRead,
+    QuickWrite,
+    ReceiveByte,
+    SendByte,
+    ReadByte,
+    WriteByte,
+    ReadWord,
+    WriteWord,
+    ReadBlock,
+    WriteBlock,
+    ProcessCall,
+    BWBRProcessCall,
+}
+
+/// The unique device identifier (UDID) used to assign a slave address to a device during ARP.
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Volume 5, EFI_SMBUS_UDID
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Udid {
+    pub vendor_specific_id: u32,
+    pub subsystem_device_id: u16,
+    pub subsystem_vendor_id: u16,
+    pub interface: u16,
+    pub device_id: u16,
+    pub vendor_id: u16,
+    pub vendor_revision: u8,
+    pub device_capabilities: u8,
+}
+
+/// An entry of the SMBus ARP device map: the address assigned to the device with the given UDID.
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Volume 5, EFI_SMBUS_DEVICE_MAP
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct DeviceMap {
+    pub smbus_device_address: DeviceAddress,
+    pub smbus_device_udid: Udid,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::mem::size_of;
+
+    #[test]
+    fn device_address_should_be_7_bits() {
+        assert_eq!(DeviceAddress::new(0x50).address(), 0x50);
+        assert_eq!(DeviceAddress::new(0xd0).address(), 0x50);
+        assert_eq!(size_of::<DeviceAddress>(), size_of::<usize>());
+    }
+
+    #[test]
+    fn device_map_layout_should_match_c() {
+        assert_eq!(size_of::<Udid>(), 16);
+        assert_eq!(size_of::<DeviceMap>(), size_of::<usize>() + 16);
+    }
+}
@@ -15,9 +15,16 @@
 //!
 
 use crate::protocols::status_code::{EfiStatusCodeType, EfiStatusCodeValue};
-// Required for IA32, X64, IPF, ARM and EBC defines for CPU exception types
+use r_efi::efi;
+// Required for IA32, X64, IPF, ARM, AArch64, RISC-V and EBC defines for CPU exception types
 use r_efi::efi::protocols::debug_support;
 
+#[cfg(feature = "alloc")]
+pub mod exception_data;
+pub mod phase;
+pub mod report;
+pub mod severity;
+
 /// A Status Code Type is made up of the code type and severity.
 /// All values masked by EFI_STATUS_CODE_RESERVED_MASK are
 /// reserved for use by this specification.
@@ -929,3 +936,211 @@ pub const EFI_SW_EC_ARM_RESERVED:               EfiStatusCodeValue = debug_suppo
 pub const EFI_SW_EC_ARM_IRQ:                    EfiStatusCodeValue = debug_support::EXCEPT_ARM_IRQ as u32;
 pub const EFI_SW_EC_ARM_FIQ:                    EfiStatusCodeValue = debug_support::EXCEPT_ARM_FIQ as u32;
 
+/// Software Class ARM Exception Subclass Error Code definitions for AArch64.
+/// There is no separate AArch64 exception subclass; AArch64 exceptions are
+/// reported with the EFI_SOFTWARE_ARM_EXCEPTION subclass using these operations,
+/// which are derived from the debug protocol definitions in the EFI specification.
+///
+pub const EFI_SW_EC_AARCH64_SYNCHRONOUS_EXCEPTIONS:  EfiStatusCodeValue = debug_support::EXCEPT_AARCH64_SYNCHRONOUS_EXCEPTIONS as u32;
+pub const EFI_SW_EC_AARCH64_IRQ:                     EfiStatusCodeValue = debug_support::EXCEPT_AARCH64_IRQ as u32;
+pub const EFI_SW_EC_AARCH64_FIQ:                     EfiStatusCodeValue = debug_support::EXCEPT_AARCH64_FIQ as u32;
+pub const EFI_SW_EC_AARCH64_SERROR:                  EfiStatusCodeValue = debug_support::EXCEPT_AARCH64_SERROR as u32;
+
+/// RISC-V Exception Error Code definitions.
+/// The PI specification does not assign a RISC-V exception subclass yet; these
+/// operations are derived from the RISC-V exception types in the debug protocol
+/// definitions in the EFI specification.
+///
+pub const EFI_SW_EC_RISCV_INST_MISALIGNED:              EfiStatusCodeValue = debug_support::EXCEPT_RISCV_INST_MISALIGNED as u32;
+pub const EFI_SW_EC_RISCV_INST_ACCESS_FAULT:            EfiStatusCodeValue = debug_support::EXCEPT_RISCV_INST_ACCESS_FAULT as u32;
+pub const EFI_SW_EC_RISCV_ILLEGAL_INST:                 EfiStatusCodeValue = debug_support::EXCEPT_RISCV_ILLEGAL_INST as u32;
+pub const EFI_SW_EC_RISCV_BREAKPOINT:                   EfiStatusCodeValue = debug_support::EXCEPT_RISCV_BREAKPOINT as u32;
+pub const EFI_SW_EC_RISCV_LOAD_ADDRESS_MISALIGNED:      EfiStatusCodeValue = debug_support::EXCEPT_RISCV_LOAD_ADDRESS_MISALIGNED as u32;
+pub const EFI_SW_EC_RISCV_LOAD_ACCESS_FAULT:            EfiStatusCodeValue = debug_support::EXCEPT_RISCV_LOAD_ACCESS_FAULT as u32;
+pub const EFI_SW_EC_RISCV_STORE_AMO_ADDRESS_MISALIGNED: EfiStatusCodeValue = debug_support::EXCEPT_RISCV_STORE_AMO_ADDRESS_MISALIGNED as u32;
+pub const EFI_SW_EC_RISCV_STORE_AMO_ACCESS_FAULT:       EfiStatusCodeValue = debug_support::EXCEPT_RISCV_STORE_AMO_ACCESS_FAULT as u32;
+pub const EFI_SW_EC_RISCV_ENV_CALL_FROM_UMODE:          EfiStatusCodeValue = debug_support::EXCEPT_RISCV_ENV_CALL_FROM_UMODE as u32;
+pub const EFI_SW_EC_RISCV_ENV_CALL_FROM_SMODE:          EfiStatusCodeValue = debug_support::EXCEPT_RISCV_ENV_CALL_FROM_SMODE as u32;
+pub const EFI_SW_EC_RISCV_ENV_CALL_FROM_MMODE:          EfiStatusCodeValue = debug_support::EXCEPT_RISCV_ENV_CALL_FROM_MMODE as u32;
+pub const EFI_SW_EC_RISCV_INST_PAGE_FAULT:              EfiStatusCodeValue = debug_support::EXCEPT_RISCV_INST_PAGE_FAULT as u32;
+pub const EFI_SW_EC_RISCV_LOAD_PAGE_FAULT:              EfiStatusCodeValue = debug_support::EXCEPT_RISCV_LOAD_PAGE_FAULT as u32;
+pub const EFI_SW_EC_RISCV_STORE_AMO_PAGE_FAULT:         EfiStatusCodeValue = debug_support::EXCEPT_RISCV_STORE_AMO_PAGE_FAULT as u32;
+
+
+// The exception subclass and exception error codes of each instruction set architecture that has one. ARM and AArch64
+// share the ARM exception subclass.
+const EXCEPTION_ERROR_CODES: &[(debug_support::InstructionSetArchitecture, EfiStatusCodeValue, &[EfiStatusCodeValue])] = &[
+    (debug_support::ISA_EBC, EFI_SOFTWARE_EBC_EXCEPTION, &[
+        EFI_SW_EC_EBC_UNDEFINED, EFI_SW_EC_EBC_DIVIDE_ERROR, EFI_SW_EC_EBC_DEBUG, EFI_SW_EC_EBC_BREAKPOINT,
+        EFI_SW_EC_EBC_OVERFLOW, EFI_SW_EC_EBC_INVALID_OPCODE, EFI_SW_EC_EBC_STACK_FAULT, EFI_SW_EC_EBC_ALIGNMENT_CHECK,
+        EFI_SW_EC_EBC_INSTRUCTION_ENCODING, EFI_SW_EC_EBC_BAD_BREAK, EFI_SW_EC_EBC_STEP,
+    ]),
+    (debug_support::ISA_IA32, EFI_SOFTWARE_IA32_EXCEPTION, &[
+        EFI_SW_EC_IA32_DIVIDE_ERROR, EFI_SW_EC_IA32_DEBUG, EFI_SW_EC_IA32_NMI, EFI_SW_EC_IA32_BREAKPOINT,
+        EFI_SW_EC_IA32_OVERFLOW, EFI_SW_EC_IA32_BOUND, EFI_SW_EC_IA32_INVALID_OPCODE, EFI_SW_EC_IA32_DOUBLE_FAULT,
+        EFI_SW_EC_IA32_INVALID_TSS, EFI_SW_EC_IA32_SEG_NOT_PRESENT, EFI_SW_EC_IA32_STACK_FAULT, EFI_SW_EC_IA32_GP_FAULT,
+        EFI_SW_EC_IA32_PAGE_FAULT, EFI_SW_EC_IA32_FP_ERROR, EFI_SW_EC_IA32_ALIGNMENT_CHECK, EFI_SW_EC_IA32_MACHINE_CHECK,
+        EFI_SW_EC_IA32_SIMD,
+    ]),
+    (debug_support::ISA_IPF, EFI_SOFTWARE_IPF_EXCEPTION, &[
+        EFI_SW_EC_IPF_ALT_DTLB, EFI_SW_EC_IPF_DNESTED_TLB, EFI_SW_EC_IPF_BREAKPOINT, EFI_SW_EC_IPF_EXTERNAL_INTERRUPT,
+        EFI_SW_EC_IPF_GEN_EXCEPT, EFI_SW_EC_IPF_NAT_CONSUMPTION, EFI_SW_EC_IPF_DEBUG_EXCEPT,
+        EFI_SW_EC_IPF_UNALIGNED_ACCESS, EFI_SW_EC_IPF_FP_FAULT, EFI_SW_EC_IPF_FP_TRAP, EFI_SW_EC_IPF_TAKEN_BRANCH,
+        EFI_SW_EC_IPF_SINGLE_STEP,
+    ]),
+    (debug_support::ISA_X64, EFI_SOFTWARE_X64_EXCEPTION, &[
+        EFI_SW_EC_X64_DIVIDE_ERROR, EFI_SW_EC_X64_DEBUG, EFI_SW_EC_X64_NMI, EFI_SW_EC_X64_BREAKPOINT,
+        EFI_SW_EC_X64_OVERFLOW, EFI_SW_EC_X64_BOUND, EFI_SW_EC_X64_INVALID_OPCODE, EFI_SW_EC_X64_DOUBLE_FAULT,
+        EFI_SW_EC_X64_INVALID_TSS, EFI_SW_EC_X64_SEG_NOT_PRESENT, EFI_SW_EC_X64_STACK_FAULT, EFI_SW_EC_X64_GP_FAULT,
+        EFI_SW_EC_X64_PAGE_FAULT, EFI_SW_EC_X64_FP_ERROR, EFI_SW_EC_X64_ALIGNMENT_CHECK, EFI_SW_EC_X64_MACHINE_CHECK,
+        EFI_SW_EC_X64_SIMD,
+    ]),
+    (debug_support::ISA_ARM, EFI_SOFTWARE_ARM_EXCEPTION, &[
+        EFI_SW_EC_ARM_RESET, EFI_SW_EC_ARM_UNDEFINED_INSTRUCTION, EFI_SW_EC_ARM_SOFTWARE_INTERRUPT,
+        EFI_SW_EC_ARM_PREFETCH_ABORT, EFI_SW_EC_ARM_DATA_ABORT, EFI_SW_EC_ARM_RESERVED, EFI_SW_EC_ARM_IRQ,
+        EFI_SW_EC_ARM_FIQ,
+    ]),
+    (debug_support::ISA_AARCH64, EFI_SOFTWARE_ARM_EXCEPTION, &[
+        EFI_SW_EC_AARCH64_SYNCHRONOUS_EXCEPTIONS, EFI_SW_EC_AARCH64_IRQ, EFI_SW_EC_AARCH64_FIQ,
+        EFI_SW_EC_AARCH64_SERROR,
+    ]),
+];
+
+/// Returns the status code value to report for the CPU exception `vector` caught on the instruction set architecture
+/// `arch` (one of the `debug_support::ISA_*` values), made of the exception subclass of the architecture and the
+/// exception error code of the vector.
+///
+/// Returns `None` for architectures without an exception subclass (such as RISC-V) and for vectors without an
+/// exception error code.
+pub fn status_value_for_vector(
+    arch: debug_support::InstructionSetArchitecture,
+    vector: debug_support::ExceptionType,
+) -> Option<EfiStatusCodeValue> {
+    let (_, subclass, error_codes) = EXCEPTION_ERROR_CODES.iter().find(|(isa, _, _)| *isa == arch)?;
+    let error_code = EfiStatusCodeValue::try_from(vector).ok()?;
+    error_codes.contains(&error_code).then_some(subclass | error_code)
+}
+
+/// Returns the CPU exception vector reported by the status code value `value`.
+///
+/// Returns `None` if `value` is not an exception error code of one of the exception subclasses. ARM and AArch64
+/// exceptions share a subclass, so the caller must know which of the two reported the exception to name the vector.
+pub fn exception_vector_for(value: EfiStatusCodeValue) -> Option<debug_support::ExceptionType> {
+    let subclass = value & (EFI_STATUS_CODE_CLASS_MASK | EFI_STATUS_CODE_SUBCLASS_MASK);
+    let error_code = value & EFI_STATUS_CODE_OPERATION_MASK;
+    EXCEPTION_ERROR_CODES
+        .iter()
+        .any(|(_, exception_subclass, error_codes)| *exception_subclass == subclass && error_codes.contains(&error_code))
+        .then_some(error_code as debug_support::ExceptionType)
+}
+
+// EFI_STATUS values and the software class error codes that describe them. When several statuses share an error code,
+// the first one is the status the error code maps back to.
+const STATUS_ERROR_CODES: &[(efi::Status, EfiStatusCodeValue)] = &[
+    (efi::Status::LOAD_ERROR, EFI_SW_EC_LOAD_ERROR),
+    (efi::Status::INVALID_PARAMETER, EFI_SW_EC_INVALID_PARAMETER),
+    (efi::Status::UNSUPPORTED, EFI_SW_EC_UNSUPPORTED),
+    (efi::Status::BAD_BUFFER_SIZE, EFI_SW_EC_INVALID_BUFFER),
+    (efi::Status::BUFFER_TOO_SMALL, EFI_SW_EC_INVALID_BUFFER),
+    (efi::Status::OUT_OF_RESOURCES, EFI_SW_EC_OUT_OF_RESOURCES),
+    (efi::Status::ABORTED, EFI_SW_EC_ABORTED),
+    (efi::Status::DEVICE_ERROR, EFI_SW_EC_ILLEGAL_HARDWARE_STATE),
+    (efi::Status::WRITE_PROTECTED, EFI_SW_EC_WRITE_PROTECTED),
+    (efi::Status::VOLUME_CORRUPTED, EFI_SW_EC_FV_CORRUPTED),
+];
+
+/// Returns the software class error code (one of the `EFI_SW_EC_*` operations) describing the error `status`.
+///
+/// Errors without a dedicated error code map to [`EFI_SW_EC_NON_SPECIFIC`]. Returns `None` if `status` is not an
+/// error.
+pub fn error_code_for_status(status: efi::Status) -> Option<EfiStatusCodeValue> {
+    if !status.is_error() {
+        return None;
+    }
+    let error_code = STATUS_ERROR_CODES.iter().find(|(error, _)| *error == status).map(|(_, error_code)| *error_code);
+    Some(error_code.unwrap_or(EFI_SW_EC_NON_SPECIFIC))
+}
+
+/// Returns the status code value to report for the error `status` in the software `subclass` (one of the
+/// `EFI_SOFTWARE_*` subclasses), made of the subclass and the error code of [`error_code_for_status`].
+///
+/// ## Example
+/// ```
+/// use mu_pi::status_code::{error_value_for_status, EFI_SOFTWARE_DXE_CORE, EFI_SW_EC_OUT_OF_RESOURCES};
+/// use r_efi::efi;
+///
+/// let result: Result<(), efi::Status> = Err(efi::Status::OUT_OF_RESOURCES);
+/// let value = result.err().and_then(|status| error_value_for_status(EFI_SOFTWARE_DXE_CORE, status));
+/// assert_eq!(value, Some(EFI_SOFTWARE_DXE_CORE | EFI_SW_EC_OUT_OF_RESOURCES));
+/// ```
+pub fn error_value_for_status(subclass: EfiStatusCodeValue, status: efi::Status) -> Option<EfiStatusCodeValue> {
+    error_code_for_status(status).map(|error_code| subclass | error_code)
+}
+
+/// Returns the EFI_STATUS described by the error code of the software class status code value `value`.
+///
+/// Returns `None` if `value` is not in the software class or its error code does not describe a specific status.
+pub fn status_for_error_code(value: EfiStatusCodeValue) -> Option<efi::Status> {
+    if value & EFI_STATUS_CODE_CLASS_MASK != EFI_SOFTWARE {
+        return None;
+    }
+    let error_code = value & EFI_STATUS_CODE_OPERATION_MASK;
+    STATUS_ERROR_CODES.iter().find(|(_, code)| *code == error_code).map(|(status, _)| *status)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exception_vectors_should_round_trip() {
+        for &(arch, subclass, error_codes) in EXCEPTION_ERROR_CODES {
+            for &error_code in error_codes {
+                let value = status_value_for_vector(arch, error_code as debug_support::ExceptionType).unwrap();
+                assert_eq!(value, subclass | error_code);
+                assert_eq!(exception_vector_for(value), Some(error_code as debug_support::ExceptionType));
+            }
+        }
+
+        assert_eq!(
+            status_value_for_vector(debug_support::ISA_X64, debug_support::EXCEPT_X64_PAGE_FAULT),
+            Some(EFI_SOFTWARE_X64_EXCEPTION | EFI_SW_EC_X64_PAGE_FAULT)
+        );
+        assert_eq!(
+            status_value_for_vector(debug_support::ISA_AARCH64, debug_support::EXCEPT_AARCH64_SERROR),
+            Some(EFI_SOFTWARE_ARM_EXCEPTION | EFI_SW_EC_AARCH64_SERROR)
+        );
+        // Reserved vectors, negative vectors and architectures without an exception subclass have no status code.
+        assert_eq!(status_value_for_vector(debug_support::ISA_X64, 15), None);
+        assert_eq!(status_value_for_vector(debug_support::ISA_X64, -1), None);
+        assert_eq!(status_value_for_vector(debug_support::ISA_AARCH64, debug_support::EXCEPT_ARM_DATA_ABORT), None);
+        assert_eq!(status_value_for_vector(debug_support::ISA_RISCV64, debug_support::EXCEPT_RISCV_BREAKPOINT), None);
+
+        assert_eq!(exception_vector_for(EFI_SOFTWARE_IA32_EXCEPTION | 9), None);
+        assert_eq!(exception_vector_for(EFI_SOFTWARE_DXE_BS_DRIVER | EFI_SW_EC_X64_PAGE_FAULT), None);
+        assert_eq!(exception_vector_for(EFI_SW_EC_X64_PAGE_FAULT), None);
+    }
+
+    #[test]
+    fn error_statuses_should_map_to_error_codes() {
+        for &(status, error_code) in STATUS_ERROR_CODES {
+            let value = error_value_for_status(EFI_SOFTWARE_PEI_MODULE, status).unwrap();
+            assert_eq!(value, EFI_SOFTWARE_PEI_MODULE | error_code);
+            let mapped = status_for_error_code(value).unwrap();
+            assert_eq!(error_code_for_status(mapped), Some(error_code));
+        }
+        assert_eq!(
+            status_for_error_code(EFI_SOFTWARE_DXE_CORE | EFI_SW_EC_INVALID_BUFFER),
+            Some(efi::Status::BAD_BUFFER_SIZE)
+        );
+
+        // Errors without a dedicated error code are non-specific, and successes and warnings have no error code.
+        assert_eq!(error_code_for_status(efi::Status::NOT_FOUND), Some(EFI_SW_EC_NON_SPECIFIC));
+        assert_eq!(error_code_for_status(efi::Status::SUCCESS), None);
+        assert_eq!(error_code_for_status(efi::Status::WARN_BUFFER_TOO_SMALL), None);
+
+        assert_eq!(status_for_error_code(EFI_SOFTWARE_DXE_CORE | EFI_SW_EC_NON_SPECIFIC), None);
+        assert_eq!(status_for_error_code(EFI_SOFTWARE_DXE_CORE | EFI_SW_EC_PWD_INVALID), None);
+        assert_eq!(status_for_error_code(EFI_PERIPHERAL | EFI_SW_EC_LOAD_ERROR), None);
+    }
+}
@@ -0,0 +1,301 @@
+//! Exception Status Code Extended Data
+//!
+//! The processor context that accompanies the exception-class status codes
+//! ([`EFI_SOFTWARE_IA32_EXCEPTION`](super::EFI_SOFTWARE_IA32_EXCEPTION),
+//! [`EFI_SOFTWARE_X64_EXCEPTION`](super::EFI_SOFTWARE_X64_EXCEPTION),
+//! [`EFI_SOFTWARE_ARM_EXCEPTION`](super::EFI_SOFTWARE_ARM_EXCEPTION), ...). The per-architecture contexts are the
+//! EFI_SYSTEM_CONTEXT structures of the UEFI debug support protocol, which are also passed to the handlers registered
+//! with the CPU Architectural Protocol (see [`crate::protocols::cpu_arch::InterruptHandler`]).
+//!
+//! [`CrashRecord`] puts an exception vector and its processor context together into a complete exception status code,
+//! which can be reported through a [`StatusCodeReporter`] or converted to a serializable [`StatusCodeRecord`] for
+//! post-mortem analysis.
+//!
+//! See <https://uefi.org/specs/PI/1.8A/V3_Status_Codes.html>
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+use alloc::vec::Vec;
+use core::{
+    mem::{self, MaybeUninit},
+    slice,
+};
+
+use r_efi::efi::{self, protocols::debug_support};
+
+use crate::{
+    capture::StatusCodeRecord,
+    protocols::status_code::{EfiStatusCodeData, EfiStatusCodeType, EfiStatusCodeValue},
+    status_code::{report::StatusCodeReporter, status_value_for_vector, EFI_ERROR_CODE, EFI_ERROR_UNRECOVERED},
+};
+
+pub use r_efi::efi::protocols::debug_support::{
+    SystemContextAArch64, SystemContextArm, SystemContextEbc, SystemContextIa32, SystemContextIpf,
+    SystemContextRiscV64, SystemContextX64,
+};
+
+/// The data type GUID of status code extended data whose format is given by the status code value, such as
+/// [`ExceptionExtendedData`].
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Volume 3, EFI_STATUS_CODE_SPECIFIC_DATA_GUID
+pub const STATUS_CODE_SPECIFIC_DATA_GUID: efi::Guid =
+    efi::Guid::from_fields(0x335984bd, 0xe805, 0x409a, 0xb8, 0xf8, &[0xd2, 0x7e, 0xce, 0x5f, 0xf7, 0xa6]);
+
+/// The processor context at the time of an exception, as defined by the PI specification.
+///
+/// The PI specification only lists the EBC, IA32, IPF, X64 and ARM contexts. Use [`ExceptionExtendedData`] with
+/// [`SystemContextAArch64`] or [`SystemContextRiscV64`] to report the context of other architectures.
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Volume 3, EFI_STATUS_CODE_EXCEP_SYSTEM_CONTEXT
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub union ExceptionSystemContext {
+    pub system_context_ebc: SystemContextEbc,
+    pub system_context_ia32: SystemContextIa32,
+    pub system_context_ipf: SystemContextIpf,
+    pub system_context_x64: SystemContextX64,
+    pub system_context_arm: SystemContextArm,
+}
+
+/// The extended data of an exception status code: a data header followed by the processor context.
+///
+/// `C` is [`ExceptionSystemContext`] for the layout defined by the PI specification, or one of the per-architecture
+/// contexts.
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Volume 3, EFI_STATUS_CODE_EXCEP_EXTENDED_DATA
+#[repr(C)]
+pub struct ExceptionExtendedData<C = ExceptionSystemContext> {
+    pub data_header: EfiStatusCodeData,
+    pub context: C,
+}
+
+impl<C: Copy> ExceptionExtendedData<C> {
+    /// Creates the extended data for the given context.
+    ///
+    /// The header size is the offset of the context, which includes any padding needed to align the context.
+    pub fn new(context: C) -> Self {
+        Self {
+            data_header: EfiStatusCodeData {
+                header_size: Self::context_offset() as u16,
+                size: mem::size_of::<C>() as u16,
+                r#type: STATUS_CODE_SPECIFIC_DATA_GUID,
+            },
+            context,
+        }
+    }
+
+    /// Returns the context held in the extended data of an exception status code, or `None` if the data does not
+    /// hold a context of type `C`.
+    pub fn context_from_data(data: &EfiStatusCodeData) -> Option<C> {
+        if data.r#type != STATUS_CODE_SPECIFIC_DATA_GUID || (data.size as usize) < mem::size_of::<C>() {
+            return None;
+        }
+        let context = (data as *const EfiStatusCodeData as *const u8).wrapping_add(data.header_size as usize);
+        // Safety: the header describes the extended data that follows it, which holds a context of type C.
+        Some(unsafe { (context as *const C).read_unaligned() })
+    }
+
+    fn context_offset() -> usize {
+        let data = MaybeUninit::<Self>::uninit();
+        let base = data.as_ptr();
+        // Safety: only the address of the field is taken; the uninitialized data is not read.
+        unsafe { core::ptr::addr_of!((*base).context) as usize - base as usize }
+    }
+}
+
+/// The processor context of one instruction set architecture.
+///
+/// # Safety
+/// Implementors must be plain register images without padding bytes, so that they can be viewed as bytes.
+pub unsafe trait ExceptionContext: Copy {
+    /// The instruction set architecture of the context, one of the `debug_support::ISA_*` values.
+    const ARCH: debug_support::InstructionSetArchitecture;
+}
+
+// Safety: the debug support system contexts are made of register fields only, laid out without padding.
+unsafe impl ExceptionContext for SystemContextEbc {
+    const ARCH: debug_support::InstructionSetArchitecture = debug_support::ISA_EBC;
+}
+unsafe impl ExceptionContext for SystemContextIa32 {
+    const ARCH: debug_support::InstructionSetArchitecture = debug_support::ISA_IA32;
+}
+unsafe impl ExceptionContext for SystemContextIpf {
+    const ARCH: debug_support::InstructionSetArchitecture = debug_support::ISA_IPF;
+}
+unsafe impl ExceptionContext for SystemContextX64 {
+    const ARCH: debug_support::InstructionSetArchitecture = debug_support::ISA_X64;
+}
+unsafe impl ExceptionContext for SystemContextArm {
+    const ARCH: debug_support::InstructionSetArchitecture = debug_support::ISA_ARM;
+}
+unsafe impl ExceptionContext for SystemContextAArch64 {
+    const ARCH: debug_support::InstructionSetArchitecture = debug_support::ISA_AARCH64;
+}
+unsafe impl ExceptionContext for SystemContextRiscV64 {
+    const ARCH: debug_support::InstructionSetArchitecture = debug_support::ISA_RISCV64;
+}
+
+/// A CPU exception as a complete exception status code: an unrecovered error code, the status code value made of the
+/// exception subclass of the architecture and the exception error code of the vector, and the extended data holding
+/// the processor context.
+///
+/// ## Example
+/// ```
+/// use mu_pi::status_code::{self, exception_data::{CrashRecord, SystemContextX64}};
+/// use r_efi::efi::protocols::debug_support;
+///
+/// // Safety: the context is plain register values.
+/// let mut context: SystemContextX64 = unsafe { core::mem::zeroed() };
+/// context.rip = 0xfffff000;
+///
+/// let record = CrashRecord::new(debug_support::EXCEPT_X64_PAGE_FAULT, context).unwrap();
+/// assert_eq!(record.value, status_code::EFI_SOFTWARE_X64_EXCEPTION | status_code::EFI_SW_EC_X64_PAGE_FAULT);
+/// let _ = record.report(status_code::report::reporter(), 0, None);
+///
+/// // Post-mortem: the captured record holds the extended data, from which the context can be recovered.
+/// let captured = record.to_status_code_record(0, None);
+/// let decoded = CrashRecord::<SystemContextX64>::from_status_code_record(&captured).unwrap();
+/// assert_eq!(decoded.data.context.rip, 0xfffff000);
+/// ```
+pub struct CrashRecord<C> {
+    pub code_type: EfiStatusCodeType,
+    pub value: EfiStatusCodeValue,
+    pub data: ExceptionExtendedData<C>,
+}
+
+impl<C: ExceptionContext> CrashRecord<C> {
+    /// Creates the record of the exception `vector` caught with the processor context `context`.
+    ///
+    /// Returns `None` if the PI specification defines no exception subclass for the architecture of the context (such
+    /// as RISC-V), or if the vector has no exception error code.
+    pub fn new(vector: debug_support::ExceptionType, context: C) -> Option<Self> {
+        Some(Self {
+            code_type: EFI_ERROR_CODE | EFI_ERROR_UNRECOVERED,
+            value: status_value_for_vector(C::ARCH, vector)?,
+            data: ExceptionExtendedData::new(context),
+        })
+    }
+
+    /// Reports the exception through `reporter`.
+    pub fn report(
+        &self,
+        reporter: &dyn StatusCodeReporter,
+        instance: u32,
+        caller_id: Option<&efi::Guid>,
+    ) -> efi::Status {
+        reporter.report_status_code(self.code_type, self.value, instance, caller_id, Some(&self.data.data_header))
+    }
+
+    /// Returns the extended data as bytes: the data header, zeroes up to the context, then the context.
+    pub fn data_bytes(&self) -> Vec<u8> {
+        let header = &self.data.data_header;
+        let mut bytes = Vec::with_capacity(header.header_size as usize + header.size as usize);
+        bytes.extend_from_slice(&header.header_size.to_le_bytes());
+        bytes.extend_from_slice(&header.size.to_le_bytes());
+        bytes.extend_from_slice(header.r#type.as_bytes());
+        bytes.resize(header.header_size as usize, 0);
+        // Safety: ExceptionContext guarantees that the context has no padding bytes.
+        let context =
+            unsafe { slice::from_raw_parts(&self.data.context as *const C as *const u8, mem::size_of::<C>()) };
+        bytes.extend_from_slice(context);
+        bytes
+    }
+
+    /// Returns the record as a [`StatusCodeRecord`], the serializable form of status codes used by the capture
+    /// format.
+    pub fn to_status_code_record(&self, instance: u32, caller_id: Option<efi::Guid>) -> StatusCodeRecord {
+        StatusCodeRecord { code_type: self.code_type, value: self.value, instance, caller_id, data: self.data_bytes() }
+    }
+
+    /// Recovers a record from a [`StatusCodeRecord`].
+    ///
+    /// Returns `None` if the record is not an exception of the architecture of `C`, or its extended data does not hold
+    /// a context of type `C`.
+    pub fn from_status_code_record(record: &StatusCodeRecord) -> Option<Self> {
+        let vector = super::exception_vector_for(record.value)?;
+        if status_value_for_vector(C::ARCH, vector) != Some(record.value)
+            || record.data.len() < mem::size_of::<EfiStatusCodeData>()
+        {
+            return None;
+        }
+        // Safety: the data is large enough to hold the header, which is read unaligned.
+        let header = unsafe { (record.data.as_ptr() as *const EfiStatusCodeData).read_unaligned() };
+        let context_end = header.header_size as usize + mem::size_of::<C>();
+        if header.r#type != STATUS_CODE_SPECIFIC_DATA_GUID
+            || (header.size as usize) < mem::size_of::<C>()
+            || record.data.len() < context_end
+        {
+            return None;
+        }
+        // Safety: the data holds a context of type C at the header size, which is read unaligned.
+        let context = unsafe { (record.data[header.header_size as usize..].as_ptr() as *const C).read_unaligned() };
+        Some(Self { code_type: record.code_type, value: record.value, data: ExceptionExtendedData::new(context) })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn context_should_round_trip() {
+        // Safety: the context is plain register values.
+        let mut context: SystemContextX64 = unsafe { mem::zeroed() };
+        context.rip = 0xfffff000;
+        context.exception_data = 0x12;
+
+        let data = ExceptionExtendedData::new(context);
+        let align = mem::align_of::<SystemContextX64>();
+        let header_size = (mem::size_of::<EfiStatusCodeData>() + align - 1) / align * align;
+        assert_eq!(data.data_header.header_size as usize, header_size);
+        assert_eq!(data.data_header.size as usize, mem::size_of::<SystemContextX64>());
+
+        let decoded = ExceptionExtendedData::<SystemContextX64>::context_from_data(&data.data_header).unwrap();
+        assert_eq!((decoded.rip, decoded.exception_data), (0xfffff000, 0x12));
+        assert!(ExceptionExtendedData::<ExceptionSystemContext>::context_from_data(&data.data_header).is_none());
+    }
+
+    #[test]
+    fn crash_record_should_round_trip_through_status_code_record() {
+        // Safety: the context is plain register values.
+        let mut context: SystemContextAArch64 = unsafe { mem::zeroed() };
+        context.elr = 0x8000_1000;
+        context.esr = 0x9600_0045;
+
+        let record = CrashRecord::new(debug_support::EXCEPT_AARCH64_SYNCHRONOUS_EXCEPTIONS, context).unwrap();
+        assert_eq!(record.code_type, EFI_ERROR_CODE | EFI_ERROR_UNRECOVERED);
+        assert_eq!(
+            record.value,
+            crate::status_code::EFI_SOFTWARE_ARM_EXCEPTION | crate::status_code::EFI_SW_EC_AARCH64_SYNCHRONOUS_EXCEPTIONS
+        );
+
+        let data = record.data_bytes();
+        assert_eq!(data.len(), record.data.data_header.header_size as usize + mem::size_of::<SystemContextAArch64>());
+        assert_eq!(
+            ExceptionExtendedData::<SystemContextAArch64>::context_from_data(&record.data.data_header).unwrap().elr,
+            0x8000_1000
+        );
+
+        let captured = record.to_status_code_record(1, None);
+        let decoded = CrashRecord::<SystemContextAArch64>::from_status_code_record(&captured).unwrap();
+        assert_eq!((decoded.value, decoded.data.context.elr, decoded.data.context.esr), (record.value, 0x8000_1000, 0x9600_0045));
+        assert!(CrashRecord::<SystemContextX64>::from_status_code_record(&captured).is_none());
+
+        let mut truncated = captured.clone();
+        truncated.data.pop();
+        assert!(CrashRecord::<SystemContextAArch64>::from_status_code_record(&truncated).is_none());
+
+        // RISC-V exceptions have no exception subclass to report them with.
+        // Safety: the context is plain register values.
+        let context: SystemContextRiscV64 = unsafe { mem::zeroed() };
+        assert!(CrashRecord::new(debug_support::EXCEPT_RISCV_BREAKPOINT, context).is_none());
+    }
+}
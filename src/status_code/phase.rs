@@ -0,0 +1,135 @@
+//! Boot Phase Status Codes
+//!
+//! Helpers that report the status codes the PI specification defines for the transitions between boot phases (entry
+//! into SEC, PEI and DXE, handoff to the next phase, the DXE boot service events, ...). Each helper pairs the class,
+//! subclass and operation constants of [`crate::status_code`] with the code type the code is defined for, so that
+//! every phase reports the same values.
+//!
+//! Like the reporting macros, the helpers honor the compile-time filters of [`super::report::enabled`].
+//!
+//! ## Example
+//! ```
+//! use mu_pi::status_code::{phase, report};
+//!
+//! let _ = phase::report_dxe_core_entry(report::reporter());
+//! ```
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+use r_efi::efi;
+
+use super::{
+    report::{enabled, StatusCodeReporter},
+    *,
+};
+use crate::protocols::status_code::{EfiStatusCodeType, EfiStatusCodeValue};
+
+#[inline]
+fn report(reporter: &dyn StatusCodeReporter, code_type: EfiStatusCodeType, value: EfiStatusCodeValue) -> efi::Status {
+    if enabled(code_type) {
+        reporter.report_status_code(code_type, value, 0, None, None)
+    } else {
+        efi::Status::SUCCESS
+    }
+}
+
+macro_rules! phase_status_codes {
+    ($($(#[$doc:meta])* $name:ident => ($code_type:expr, $value:expr);)*) => {
+        $(
+            $(#[$doc])*
+            #[inline]
+            pub fn $name(reporter: &dyn StatusCodeReporter) -> efi::Status {
+                report(reporter, $code_type, $value)
+            }
+        )*
+    };
+}
+
+phase_status_codes! {
+    /// Reports entry into SEC (`EFI_SOFTWARE_SEC | EFI_SW_SEC_PC_ENTRY_POINT`).
+    report_sec_entry => (EFI_PROGRESS_CODE, EFI_SOFTWARE_SEC | EFI_SW_SEC_PC_ENTRY_POINT);
+    /// Reports the handoff from SEC to PEI (`EFI_SOFTWARE_SEC | EFI_SW_SEC_PC_HANDOFF_TO_NEXT`).
+    report_sec_handoff => (EFI_PROGRESS_CODE, EFI_SOFTWARE_SEC | EFI_SW_SEC_PC_HANDOFF_TO_NEXT);
+    /// Reports entry into the PEI Foundation (`EFI_SOFTWARE_PEI_CORE | EFI_SW_PEI_CORE_PC_ENTRY_POINT`).
+    report_pei_core_entry => (EFI_PROGRESS_CODE, EFI_SOFTWARE_PEI_CORE | EFI_SW_PEI_CORE_PC_ENTRY_POINT);
+    /// Reports the handoff from PEI to DXE (`EFI_SOFTWARE_PEI_CORE | EFI_SW_PEI_CORE_PC_HANDOFF_TO_NEXT`).
+    report_pei_handoff => (EFI_PROGRESS_CODE, EFI_SOFTWARE_PEI_CORE | EFI_SW_PEI_CORE_PC_HANDOFF_TO_NEXT);
+    /// Reports entry into the DXE Foundation (`EFI_SOFTWARE_DXE_CORE | EFI_SW_DXE_CORE_PC_ENTRY_POINT`).
+    report_dxe_core_entry => (EFI_PROGRESS_CODE, EFI_SOFTWARE_DXE_CORE | EFI_SW_DXE_CORE_PC_ENTRY_POINT);
+    /// Reports that all the architectural protocols are available (`EFI_SOFTWARE_DXE_CORE |
+    /// EFI_SW_DXE_CORE_PC_ARCH_READY`).
+    report_dxe_arch_ready => (EFI_PROGRESS_CODE, EFI_SOFTWARE_DXE_CORE | EFI_SW_DXE_CORE_PC_ARCH_READY);
+    /// Reports the handoff from the DXE Foundation to BDS (`EFI_SOFTWARE_DXE_CORE | EFI_SW_DXE_CORE_PC_HANDOFF_TO_NEXT`).
+    report_dxe_handoff => (EFI_PROGRESS_CODE, EFI_SOFTWARE_DXE_CORE | EFI_SW_DXE_CORE_PC_HANDOFF_TO_NEXT);
+    /// Reports the signaling of the Ready To Boot event (`EFI_SOFTWARE_DXE_BS_DRIVER |
+    /// EFI_SW_DXE_BS_PC_READY_TO_BOOT_EVENT`).
+    report_ready_to_boot => (EFI_PROGRESS_CODE, EFI_SOFTWARE_DXE_BS_DRIVER | EFI_SW_DXE_BS_PC_READY_TO_BOOT_EVENT);
+    /// Reports the signaling of the Exit Boot Services event (`EFI_SOFTWARE_DXE_BS_DRIVER |
+    /// EFI_SW_DXE_BS_PC_EXIT_BOOT_SERVICES_EVENT`).
+    report_exit_boot_services =>
+        (EFI_PROGRESS_CODE, EFI_SOFTWARE_DXE_BS_DRIVER | EFI_SW_DXE_BS_PC_EXIT_BOOT_SERVICES_EVENT);
+    /// Reports the signaling of the Virtual Address Change event (`EFI_SOFTWARE_DXE_BS_DRIVER |
+    /// EFI_SW_DXE_BS_PC_VIRTUAL_ADDRESS_CHANGE_EVENT`).
+    report_virtual_address_change =>
+        (EFI_PROGRESS_CODE, EFI_SOFTWARE_DXE_BS_DRIVER | EFI_SW_DXE_BS_PC_VIRTUAL_ADDRESS_CHANGE_EVENT);
+    /// Reports that the PEI Foundation found no permanent memory installed (`EFI_SOFTWARE_PEI_CORE |
+    /// EFI_SW_PEI_CORE_EC_MEMORY_NOT_INSTALLED`), as a major error.
+    report_pei_memory_not_installed =>
+        (EFI_ERROR_CODE | EFI_ERROR_MAJOR, EFI_SOFTWARE_PEI_CORE | EFI_SW_PEI_CORE_EC_MEMORY_NOT_INSTALLED);
+    /// Reports that the PEI Foundation found no DXE IPL PPI to hand off to DXE with (`EFI_SOFTWARE_PEI_CORE |
+    /// EFI_SW_PEI_CORE_EC_DXEIPL_NOT_FOUND`), as a major error.
+    report_pei_dxeipl_not_found =>
+        (EFI_ERROR_CODE | EFI_ERROR_MAJOR, EFI_SOFTWARE_PEI_CORE | EFI_SW_PEI_CORE_EC_DXEIPL_NOT_FOUND);
+    /// Reports that the DXE Foundation is missing architectural protocols (`EFI_SOFTWARE_DXE_CORE |
+    /// EFI_SW_DXE_CORE_EC_NO_ARCH`), as a major error.
+    report_dxe_no_arch => (EFI_ERROR_CODE | EFI_ERROR_MAJOR, EFI_SOFTWARE_DXE_CORE | EFI_SW_DXE_CORE_EC_NO_ARCH);
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+    use std::{sync::Mutex, vec::Vec};
+
+    use super::*;
+    use crate::protocols::status_code::EfiStatusCodeData;
+
+    struct RecordingReporter(Mutex<Vec<(EfiStatusCodeType, EfiStatusCodeValue)>>);
+
+    impl StatusCodeReporter for RecordingReporter {
+        fn report_status_code(
+            &self,
+            code_type: EfiStatusCodeType,
+            value: EfiStatusCodeValue,
+            _instance: u32,
+            _caller_id: Option<&efi::Guid>,
+            _data: Option<&EfiStatusCodeData>,
+        ) -> efi::Status {
+            self.0.lock().unwrap().push((code_type, value));
+            efi::Status::SUCCESS
+        }
+    }
+
+    #[test]
+    fn phase_helpers_should_report_spec_codes() {
+        let reporter = RecordingReporter(Mutex::new(Vec::new()));
+        assert_eq!(report_pei_core_entry(&reporter), efi::Status::SUCCESS);
+        assert_eq!(report_dxe_handoff(&reporter), efi::Status::SUCCESS);
+        assert_eq!(report_pei_dxeipl_not_found(&reporter), efi::Status::SUCCESS);
+
+        // codes filtered out by crate features are not reported.
+        let expected = [
+            (EFI_PROGRESS_CODE, 0x03021000),
+            (EFI_PROGRESS_CODE, 0x03041001),
+            (EFI_ERROR_CODE | EFI_ERROR_MAJOR, 0x03021001),
+        ]
+        .into_iter()
+        .filter(|(code_type, _)| enabled(*code_type))
+        .collect::<Vec<_>>();
+        assert_eq!(*reporter.0.lock().unwrap(), expected);
+    }
+}
@@ -0,0 +1,342 @@
+//! Status Code Reporting Facade
+//!
+//! Provides a lightweight facade for reporting status codes, similar in spirit to the `log` crate. Firmware registers a
+//! single [`StatusCodeReporter`] backend with [`set_reporter`], and code throughout the firmware reports status codes
+//! with the [`report_progress!`](crate::report_progress), [`report_error!`](crate::report_error),
+//! [`report_debug!`](crate::report_debug) and [`report_status_code!`](crate::report_status_code) macros.
+//!
+//! Reports can be filtered at compile time with crate features. When a code type is filtered out, the macros compile
+//! to nothing, so status reporting is zero-cost in builds that do not want it:
+//!
+//! | Feature                             | Effect                                                          |
+//! |-------------------------------------|-----------------------------------------------------------------|
+//! | `report_progress_off`               | Drops all progress codes.                                       |
+//! | `report_error_off`                  | Drops all error codes.                                          |
+//! | `report_debug_off`                  | Drops all debug codes.                                          |
+//! | `release_report_progress_off`       | Drops all progress codes when `debug_assertions` are disabled.  |
+//! | `release_report_error_off`          | Drops all error codes when `debug_assertions` are disabled.     |
+//! | `release_report_debug_off`          | Drops all debug codes when `debug_assertions` are disabled.     |
+//! | `report_error_min_major`            | Drops error codes less severe than `EFI_ERROR_MAJOR`.           |
+//! | `report_error_min_unrecovered`      | Drops error codes less severe than `EFI_ERROR_UNRECOVERED`.     |
+//! | `report_error_min_uncontained`      | Drops error codes less severe than `EFI_ERROR_UNCONTAINED`.     |
+//!
+//! ## Example
+//! ```
+//! use mu_pi::{report_error, report_progress, status_code, status_code::report::StatusCodeReporter};
+//! use mu_pi::protocols::status_code::{EfiStatusCodeData, EfiStatusCodeType, EfiStatusCodeValue};
+//! use r_efi::efi;
+//!
+//! struct SerialReporter;
+//!
+//! impl StatusCodeReporter for SerialReporter {
+//!     fn report_status_code(
+//!         &self,
+//!         code_type: EfiStatusCodeType,
+//!         value: EfiStatusCodeValue,
+//!         instance: u32,
+//!         _caller_id: Option<&efi::Guid>,
+//!         _data: Option<&EfiStatusCodeData>,
+//!     ) -> efi::Status {
+//!         println!("type: {:#x} value: {:#x} instance: {}", code_type, value, instance);
+//!         efi::Status::SUCCESS
+//!     }
+//! }
+//!
+//! static REPORTER: SerialReporter = SerialReporter;
+//!
+//! status_code::report::set_reporter(&REPORTER).unwrap();
+//!
+//! report_progress!(status_code::EFI_SOFTWARE_DXE_CORE | status_code::EFI_SW_DXE_CORE_PC_ENTRY_POINT);
+//! report_error!(status_code::EFI_ERROR_MAJOR, status_code::EFI_SOFTWARE_DXE_CORE | status_code::EFI_SW_EC_ABORTED);
+//! ```
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use r_efi::efi;
+
+use crate::{
+    protocols::status_code::{self, EfiStatusCodeData, EfiStatusCodeType, EfiStatusCodeValue},
+    status_code::{
+        EFI_DEBUG_CODE, EFI_ERROR_CODE, EFI_ERROR_MAJOR, EFI_ERROR_UNCONTAINED, EFI_ERROR_UNRECOVERED,
+        EFI_PROGRESS_CODE, EFI_STATUS_CODE_SEVERITY_MASK, EFI_STATUS_CODE_TYPE_MASK,
+    },
+};
+
+/// A backend that status codes reported through this facade are routed to.
+///
+/// The signature mirrors `ReportStatusCode()` from the Status Code Runtime Protocol and the PEI Services table so that
+/// a backend can forward directly to whichever service is available in the current boot phase.
+pub trait StatusCodeReporter: Sync {
+    /// Reports a status code.
+    fn report_status_code(
+        &self,
+        code_type: EfiStatusCodeType,
+        value: EfiStatusCodeValue,
+        instance: u32,
+        caller_id: Option<&efi::Guid>,
+        data: Option<&EfiStatusCodeData>,
+    ) -> efi::Status;
+}
+
+/// Forwards status codes to an installed Status Code Runtime Protocol instance.
+impl StatusCodeReporter for status_code::Protocol {
+    fn report_status_code(
+        &self,
+        code_type: EfiStatusCodeType,
+        value: EfiStatusCodeValue,
+        instance: u32,
+        caller_id: Option<&efi::Guid>,
+        data: Option<&EfiStatusCodeData>,
+    ) -> efi::Status {
+        let caller_id = caller_id.map_or(core::ptr::null(), |guid| guid as *const efi::Guid);
+        let data = data.map_or(core::ptr::null(), |data| data as *const EfiStatusCodeData);
+        (self.report_status_code)(code_type, value, instance, caller_id, data)
+    }
+}
+
+// Reporter used until a real backend is registered with [`set_reporter`].
+struct NopReporter;
+
+impl StatusCodeReporter for NopReporter {
+    fn report_status_code(
+        &self,
+        _code_type: EfiStatusCodeType,
+        _value: EfiStatusCodeValue,
+        _instance: u32,
+        _caller_id: Option<&efi::Guid>,
+        _data: Option<&EfiStatusCodeData>,
+    ) -> efi::Status {
+        efi::Status::SUCCESS
+    }
+}
+
+const UNINITIALIZED: usize = 0;
+const INITIALIZING: usize = 1;
+const INITIALIZED: usize = 2;
+
+static STATE: AtomicUsize = AtomicUsize::new(UNINITIALIZED);
+static mut REPORTER: &dyn StatusCodeReporter = &NopReporter;
+
+/// Registers the global status code reporter.
+///
+/// The reporter can only be set once. Returns `ALREADY_STARTED` if a reporter has already been registered.
+pub fn set_reporter(reporter: &'static dyn StatusCodeReporter) -> Result<(), efi::Status> {
+    match STATE.compare_exchange(UNINITIALIZED, INITIALIZING, Ordering::Acquire, Ordering::Relaxed) {
+        Ok(_) => {
+            // Safety: the state transition above guarantees exclusive access to REPORTER.
+            unsafe { REPORTER = reporter };
+            STATE.store(INITIALIZED, Ordering::Release);
+            Ok(())
+        }
+        Err(_) => Err(efi::Status::ALREADY_STARTED),
+    }
+}
+
+/// Returns the global status code reporter, or a no-op reporter if none has been registered.
+pub fn reporter() -> &'static dyn StatusCodeReporter {
+    if STATE.load(Ordering::Acquire) == INITIALIZED {
+        // Safety: REPORTER is never written once STATE is INITIALIZED.
+        unsafe { REPORTER }
+    } else {
+        &NopReporter
+    }
+}
+
+/// Returns whether status codes of the given type pass the compile-time filters selected by crate features.
+///
+/// This is a `const fn` so that filtered reports are eliminated entirely when the code type is a constant.
+pub const fn enabled(code_type: EfiStatusCodeType) -> bool {
+    let release = !cfg!(debug_assertions);
+    match code_type & EFI_STATUS_CODE_TYPE_MASK {
+        EFI_PROGRESS_CODE => {
+            !(cfg!(feature = "report_progress_off") || (release && cfg!(feature = "release_report_progress_off")))
+        }
+        EFI_ERROR_CODE => {
+            if cfg!(feature = "report_error_off") || (release && cfg!(feature = "release_report_error_off")) {
+                return false;
+            }
+            code_type & EFI_STATUS_CODE_SEVERITY_MASK >= min_error_severity()
+        }
+        EFI_DEBUG_CODE => {
+            !(cfg!(feature = "report_debug_off") || (release && cfg!(feature = "release_report_debug_off")))
+        }
+        _ => true,
+    }
+}
+
+const fn min_error_severity() -> EfiStatusCodeType {
+    if cfg!(feature = "report_error_min_uncontained") {
+        EFI_ERROR_UNCONTAINED
+    } else if cfg!(feature = "report_error_min_unrecovered") {
+        EFI_ERROR_UNRECOVERED
+    } else if cfg!(feature = "report_error_min_major") {
+        EFI_ERROR_MAJOR
+    } else {
+        0
+    }
+}
+
+// Not public API; called by the reporting macros.
+#[doc(hidden)]
+pub fn __private_report(
+    code_type: EfiStatusCodeType,
+    value: EfiStatusCodeValue,
+    instance: u32,
+    caller_id: Option<&efi::Guid>,
+) {
+    let _ = reporter().report_status_code(code_type, value, instance, caller_id, None);
+}
+
+/// Reports a status code of the given type through the global reporter.
+///
+/// An optional instance number and caller ID may be supplied.
+#[macro_export]
+macro_rules! report_status_code {
+    ($code_type:expr, $value:expr) => {
+        $crate::report_status_code!($code_type, $value, 0)
+    };
+    ($code_type:expr, $value:expr, $instance:expr) => {
+        $crate::report_status_code!($code_type, $value, $instance, None)
+    };
+    ($code_type:expr, $value:expr, $instance:expr, $caller_id:expr) => {{
+        let code_type: $crate::protocols::status_code::EfiStatusCodeType = $code_type;
+        if $crate::status_code::report::enabled(code_type) {
+            $crate::status_code::report::__private_report(code_type, $value, $instance, $caller_id);
+        }
+    }};
+}
+
+/// Reports a progress code through the global reporter.
+#[macro_export]
+macro_rules! report_progress {
+    ($value:expr $(, $rest:expr)*) => {
+        $crate::report_status_code!($crate::status_code::EFI_PROGRESS_CODE, $value $(, $rest)*)
+    };
+}
+
+/// Reports an error code with the given severity through the global reporter.
+#[macro_export]
+macro_rules! report_error {
+    ($severity:expr, $value:expr $(, $rest:expr)*) => {
+        $crate::report_status_code!($crate::status_code::EFI_ERROR_CODE | $severity, $value $(, $rest)*)
+    };
+}
+
+/// Reports a debug code through the global reporter.
+#[macro_export]
+macro_rules! report_debug {
+    ($value:expr $(, $rest:expr)*) => {
+        $crate::report_status_code!($crate::status_code::EFI_DEBUG_CODE, $value $(, $rest)*)
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+    use std::{sync::Mutex, vec::Vec};
+
+    use r_efi::efi;
+
+    use super::{enabled, set_reporter, StatusCodeReporter};
+    use crate::{
+        protocols::status_code::{EfiStatusCodeData, EfiStatusCodeType, EfiStatusCodeValue},
+        status_code::{
+            EFI_DEBUG_CODE, EFI_ERROR_CODE, EFI_ERROR_MAJOR, EFI_ERROR_MINOR, EFI_ERROR_UNCONTAINED, EFI_ERROR_UNRECOVERED,
+            EFI_PROGRESS_CODE, EFI_SOFTWARE_DXE_CORE, EFI_SW_DXE_CORE_PC_ENTRY_POINT, EFI_SW_EC_ABORTED,
+        },
+    };
+
+    struct RecordingReporter(Mutex<Vec<(EfiStatusCodeType, EfiStatusCodeValue, u32)>>);
+
+    impl StatusCodeReporter for RecordingReporter {
+        fn report_status_code(
+            &self,
+            code_type: EfiStatusCodeType,
+            value: EfiStatusCodeValue,
+            instance: u32,
+            _caller_id: Option<&efi::Guid>,
+            _data: Option<&EfiStatusCodeData>,
+        ) -> efi::Status {
+            self.0.lock().unwrap().push((code_type, value, instance));
+            efi::Status::SUCCESS
+        }
+    }
+
+    static REPORTER: RecordingReporter = RecordingReporter(Mutex::new(Vec::new()));
+
+    #[test]
+    fn macros_should_route_to_reporter() {
+        assert_eq!(set_reporter(&REPORTER), Ok(()));
+        assert_eq!(set_reporter(&REPORTER), Err(efi::Status::ALREADY_STARTED));
+
+        crate::report_progress!(EFI_SOFTWARE_DXE_CORE | EFI_SW_DXE_CORE_PC_ENTRY_POINT);
+        crate::report_error!(EFI_ERROR_MAJOR, EFI_SOFTWARE_DXE_CORE | EFI_SW_EC_ABORTED, 3);
+        crate::report_error!(EFI_ERROR_MINOR, EFI_SOFTWARE_DXE_CORE | EFI_SW_EC_ABORTED, 4);
+        crate::report_debug!(0, 1, Some(&efi::Guid::from_bytes(&[0; 16])));
+
+        // reports of filtered out types must never reach the reporter.
+        let expected = [
+            (EFI_PROGRESS_CODE, EFI_SOFTWARE_DXE_CORE | EFI_SW_DXE_CORE_PC_ENTRY_POINT, 0),
+            (EFI_ERROR_CODE | EFI_ERROR_MAJOR, EFI_SOFTWARE_DXE_CORE | EFI_SW_EC_ABORTED, 3),
+            (EFI_ERROR_CODE | EFI_ERROR_MINOR, EFI_SOFTWARE_DXE_CORE | EFI_SW_EC_ABORTED, 4),
+            (EFI_DEBUG_CODE, 0, 1),
+        ]
+        .into_iter()
+        .filter(|(code_type, _, _)| enabled(*code_type))
+        .collect::<Vec<_>>();
+        assert_eq!(*REPORTER.0.lock().unwrap(), expected);
+    }
+
+    #[test]
+    #[cfg(not(any(
+        feature = "report_progress_off",
+        feature = "report_error_off",
+        feature = "report_debug_off",
+        feature = "release_report_progress_off",
+        feature = "release_report_error_off",
+        feature = "release_report_debug_off",
+        feature = "report_error_min_major",
+        feature = "report_error_min_unrecovered",
+        feature = "report_error_min_uncontained"
+    )))]
+    fn default_features_should_enable_all_types() {
+        assert!(enabled(EFI_PROGRESS_CODE));
+        assert!(enabled(EFI_ERROR_CODE | EFI_ERROR_MINOR));
+        assert!(enabled(EFI_ERROR_CODE | EFI_ERROR_MAJOR));
+        assert!(enabled(EFI_DEBUG_CODE));
+    }
+
+    #[test]
+    fn filter_features_should_disable_filtered_types() {
+        let release = !cfg!(debug_assertions);
+        if cfg!(feature = "report_progress_off") || (release && cfg!(feature = "release_report_progress_off")) {
+            assert!(!enabled(EFI_PROGRESS_CODE));
+        }
+        if cfg!(feature = "report_error_off") || (release && cfg!(feature = "release_report_error_off")) {
+            assert!(!enabled(EFI_ERROR_CODE | EFI_ERROR_UNCONTAINED));
+        }
+        if cfg!(feature = "report_debug_off") || (release && cfg!(feature = "release_report_debug_off")) {
+            assert!(!enabled(EFI_DEBUG_CODE));
+        }
+        if cfg!(feature = "report_error_min_major") {
+            assert!(!enabled(EFI_ERROR_CODE | EFI_ERROR_MINOR));
+        }
+        if cfg!(feature = "report_error_min_unrecovered") {
+            assert!(!enabled(EFI_ERROR_CODE | EFI_ERROR_MAJOR));
+        }
+        if cfg!(feature = "report_error_min_uncontained") {
+            assert!(!enabled(EFI_ERROR_CODE | EFI_ERROR_UNRECOVERED));
+            assert_eq!(
+                enabled(EFI_ERROR_CODE | EFI_ERROR_UNCONTAINED),
+                !(cfg!(feature = "report_error_off") || (release && cfg!(feature = "release_report_error_off")))
+            );
+        }
+    }
+}
@@ -0,0 +1,302 @@
+//! Status Code Severity and Display
+//!
+//! [`StatusCodeSeverity`] decodes the code type and error severity of a status code type, and orders them from the
+//! least to the most severe. [`StatusCodeDisplay`] formats a status code for a console, optionally highlighting its
+//! severity with ANSI colors.
+//!
+//! With the `log` feature, severities map onto `log::Level`, and `LogReporter` is a
+//! [`StatusCodeReporter`] that forwards ReportStatusCode traffic to the `log` crate,
+//! so firmware that already has `log` sinks can route status codes into them.
+//!
+//! ## Example
+//! ```
+//! use mu_pi::status_code::{
+//!     self,
+//!     severity::{DisplayStyle, StatusCodeDisplay, StatusCodeSeverity},
+//! };
+//!
+//! let code_type = status_code::EFI_ERROR_CODE | status_code::EFI_ERROR_MAJOR;
+//! assert_eq!(StatusCodeSeverity::from_code_type(code_type), StatusCodeSeverity::ErrorMajor);
+//!
+//! let value = status_code::EFI_SOFTWARE_DXE_CORE | status_code::EFI_SW_EC_ABORTED;
+//! let display = StatusCodeDisplay::new(code_type, value, 0, DisplayStyle::Plain);
+//! assert_eq!(display.to_string(), "ERROR(MAJOR) 0x03040006");
+//! ```
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+use core::fmt;
+
+use crate::{
+    protocols::status_code::{EfiStatusCodeType, EfiStatusCodeValue},
+    status_code::{
+        EFI_DEBUG_CODE, EFI_ERROR_CODE, EFI_ERROR_MAJOR, EFI_ERROR_UNCONTAINED, EFI_ERROR_UNRECOVERED,
+        EFI_PROGRESS_CODE, EFI_STATUS_CODE_SEVERITY_MASK, EFI_STATUS_CODE_TYPE_MASK,
+    },
+};
+
+#[cfg(feature = "log")]
+use crate::protocols::status_code::EfiStatusCodeData;
+#[cfg(any(feature = "log", doc))]
+use crate::status_code::report::StatusCodeReporter;
+#[cfg(feature = "log")]
+use r_efi::efi;
+
+/// The code type of a status code, with the severity of error codes.
+///
+/// Severities are ordered from the least to the most severe. Error severities that fall between the ones defined by the
+/// specification are rounded down to the next defined severity, as the severity field is ordered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum StatusCodeSeverity {
+    /// A code type that is not defined by the specification.
+    Unknown,
+    /// `EFI_DEBUG_CODE`.
+    Debug,
+    /// `EFI_PROGRESS_CODE`.
+    Progress,
+    /// `EFI_ERROR_CODE` with the `EFI_ERROR_MINOR` severity (or a lesser one).
+    ErrorMinor,
+    /// `EFI_ERROR_CODE` with the `EFI_ERROR_MAJOR` severity.
+    ErrorMajor,
+    /// `EFI_ERROR_CODE` with the `EFI_ERROR_UNRECOVERED` severity.
+    ErrorUnrecovered,
+    /// `EFI_ERROR_CODE` with the `EFI_ERROR_UNCONTAINED` severity (or a greater one).
+    ErrorUncontained,
+}
+
+impl StatusCodeSeverity {
+    /// Decodes the severity of a status code type.
+    pub const fn from_code_type(code_type: EfiStatusCodeType) -> Self {
+        match code_type & EFI_STATUS_CODE_TYPE_MASK {
+            EFI_PROGRESS_CODE => Self::Progress,
+            EFI_DEBUG_CODE => Self::Debug,
+            EFI_ERROR_CODE => {
+                let severity = code_type & EFI_STATUS_CODE_SEVERITY_MASK;
+                if severity >= EFI_ERROR_UNCONTAINED {
+                    Self::ErrorUncontained
+                } else if severity >= EFI_ERROR_UNRECOVERED {
+                    Self::ErrorUnrecovered
+                } else if severity >= EFI_ERROR_MAJOR {
+                    Self::ErrorMajor
+                } else {
+                    Self::ErrorMinor
+                }
+            }
+            _ => Self::Unknown,
+        }
+    }
+
+    /// Returns whether the severity is that of an error code.
+    pub const fn is_error(&self) -> bool {
+        matches!(self, Self::ErrorMinor | Self::ErrorMajor | Self::ErrorUnrecovered | Self::ErrorUncontained)
+    }
+
+    /// Returns the label of the severity, as printed by [`StatusCodeDisplay`] (e.g. `ERROR(MAJOR)`).
+    pub const fn label(&self) -> &'static str {
+        match self {
+            Self::Unknown => "UNKNOWN",
+            Self::Debug => "DEBUG",
+            Self::Progress => "PROGRESS",
+            Self::ErrorMinor => "ERROR(MINOR)",
+            Self::ErrorMajor => "ERROR(MAJOR)",
+            Self::ErrorUnrecovered => "ERROR(UNRECOVERED)",
+            Self::ErrorUncontained => "ERROR(UNCONTAINED)",
+        }
+    }
+
+    // The ANSI SGR parameters used to highlight the severity.
+    const fn color(&self) -> &'static str {
+        match self {
+            Self::Unknown | Self::Debug => "2",
+            Self::Progress => "32",
+            Self::ErrorMinor => "33",
+            Self::ErrorMajor => "31",
+            Self::ErrorUnrecovered | Self::ErrorUncontained => "1;31",
+        }
+    }
+
+    /// Returns the `log` level that status codes of this severity are logged at.
+    ///
+    /// Minor errors are warnings and all other errors are errors. Progress codes are informational, debug codes are
+    /// debug messages, and codes of unknown types are traces.
+    #[cfg(feature = "log")]
+    pub const fn log_level(&self) -> log::Level {
+        match self {
+            Self::Unknown => log::Level::Trace,
+            Self::Debug => log::Level::Debug,
+            Self::Progress => log::Level::Info,
+            Self::ErrorMinor => log::Level::Warn,
+            Self::ErrorMajor | Self::ErrorUnrecovered | Self::ErrorUncontained => log::Level::Error,
+        }
+    }
+}
+
+impl fmt::Display for StatusCodeSeverity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.label())
+    }
+}
+
+#[cfg(feature = "log")]
+impl From<StatusCodeSeverity> for log::Level {
+    fn from(severity: StatusCodeSeverity) -> Self {
+        severity.log_level()
+    }
+}
+
+/// How [`StatusCodeDisplay`] formats a status code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DisplayStyle {
+    /// Plain text.
+    #[default]
+    Plain,
+    /// The severity is highlighted with ANSI colors, for terminals and serial consoles that support them.
+    Colored,
+}
+
+/// Formats a status code as its severity and value, followed by its instance when it is not 0 (e.g.
+/// `ERROR(MAJOR) 0x03040006 instance 3`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StatusCodeDisplay {
+    code_type: EfiStatusCodeType,
+    value: EfiStatusCodeValue,
+    instance: u32,
+    style: DisplayStyle,
+}
+
+impl StatusCodeDisplay {
+    /// Creates a display of the given status code.
+    pub const fn new(
+        code_type: EfiStatusCodeType,
+        value: EfiStatusCodeValue,
+        instance: u32,
+        style: DisplayStyle,
+    ) -> Self {
+        Self { code_type, value, instance, style }
+    }
+
+    /// Returns the severity of the status code.
+    pub const fn severity(&self) -> StatusCodeSeverity {
+        StatusCodeSeverity::from_code_type(self.code_type)
+    }
+}
+
+impl fmt::Display for StatusCodeDisplay {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let severity = self.severity();
+        match self.style {
+            DisplayStyle::Plain => write!(f, "{severity}")?,
+            DisplayStyle::Colored => write!(f, "\x1b[{}m{severity}\x1b[0m", severity.color())?,
+        }
+        write!(f, " {:#010x}", self.value)?;
+        if self.instance != 0 {
+            write!(f, " instance {}", self.instance)?;
+        }
+        Ok(())
+    }
+}
+
+/// A [`StatusCodeReporter`] that logs status codes with the `log` crate, at the level of their severity.
+///
+/// Status codes are logged with the `status_code` target, formatted by [`StatusCodeDisplay`] in plain style. The caller
+/// ID, when given, is appended to the message.
+#[cfg(feature = "log")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LogReporter;
+
+#[cfg(feature = "log")]
+impl StatusCodeReporter for LogReporter {
+    fn report_status_code(
+        &self,
+        code_type: EfiStatusCodeType,
+        value: EfiStatusCodeValue,
+        instance: u32,
+        caller_id: Option<&efi::Guid>,
+        _data: Option<&EfiStatusCodeData>,
+    ) -> efi::Status {
+        let display = StatusCodeDisplay::new(code_type, value, instance, DisplayStyle::Plain);
+        let level = display.severity().log_level();
+        match caller_id {
+            Some(caller_id) => log::log!(target: "status_code", level, "{display} caller {caller_id:?}"),
+            None => log::log!(target: "status_code", level, "{display}"),
+        }
+        efi::Status::SUCCESS
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+    use std::string::ToString;
+
+    use super::*;
+    use crate::status_code::{EFI_ERROR_MINOR, EFI_SOFTWARE_DXE_CORE, EFI_SW_DXE_CORE_PC_ENTRY_POINT};
+
+    #[test]
+    fn severities_should_decode_and_order() {
+        assert_eq!(StatusCodeSeverity::from_code_type(EFI_PROGRESS_CODE), StatusCodeSeverity::Progress);
+        assert_eq!(StatusCodeSeverity::from_code_type(EFI_DEBUG_CODE), StatusCodeSeverity::Debug);
+        assert_eq!(StatusCodeSeverity::from_code_type(EFI_ERROR_CODE), StatusCodeSeverity::ErrorMinor);
+        assert_eq!(
+            StatusCodeSeverity::from_code_type(EFI_ERROR_CODE | EFI_ERROR_MINOR),
+            StatusCodeSeverity::ErrorMinor
+        );
+        assert_eq!(
+            StatusCodeSeverity::from_code_type(EFI_ERROR_CODE | EFI_ERROR_MAJOR),
+            StatusCodeSeverity::ErrorMajor
+        );
+        assert_eq!(
+            StatusCodeSeverity::from_code_type(EFI_ERROR_CODE | EFI_ERROR_UNRECOVERED),
+            StatusCodeSeverity::ErrorUnrecovered
+        );
+        assert_eq!(
+            StatusCodeSeverity::from_code_type(EFI_ERROR_CODE | EFI_ERROR_UNCONTAINED),
+            StatusCodeSeverity::ErrorUncontained
+        );
+        assert_eq!(StatusCodeSeverity::from_code_type(EFI_ERROR_CODE | 0x88000000), StatusCodeSeverity::ErrorMajor);
+        assert_eq!(
+            StatusCodeSeverity::from_code_type(EFI_ERROR_CODE | 0xff000000),
+            StatusCodeSeverity::ErrorUncontained
+        );
+        assert_eq!(StatusCodeSeverity::from_code_type(0x7), StatusCodeSeverity::Unknown);
+        // the severity of progress and debug codes is reserved, and ignored.
+        assert_eq!(
+            StatusCodeSeverity::from_code_type(EFI_PROGRESS_CODE | EFI_ERROR_MAJOR),
+            StatusCodeSeverity::Progress
+        );
+
+        assert!(StatusCodeSeverity::ErrorMinor < StatusCodeSeverity::ErrorMajor);
+        assert!(StatusCodeSeverity::Progress < StatusCodeSeverity::ErrorMinor);
+        assert!(StatusCodeSeverity::ErrorMinor.is_error() && !StatusCodeSeverity::Progress.is_error());
+    }
+
+    #[test]
+    fn status_codes_should_display_with_their_severity() {
+        let value = EFI_SOFTWARE_DXE_CORE | EFI_SW_DXE_CORE_PC_ENTRY_POINT;
+        let display = StatusCodeDisplay::new(EFI_PROGRESS_CODE, value, 0, DisplayStyle::Plain);
+        assert_eq!(display.to_string(), "PROGRESS 0x03041000");
+
+        let display = StatusCodeDisplay::new(EFI_ERROR_CODE | EFI_ERROR_UNRECOVERED, value, 2, DisplayStyle::Plain);
+        assert_eq!(display.to_string(), "ERROR(UNRECOVERED) 0x03041000 instance 2");
+
+        let display = StatusCodeDisplay::new(EFI_ERROR_CODE | EFI_ERROR_MINOR, value, 0, DisplayStyle::Colored);
+        assert_eq!(display.to_string(), "\x1b[33mERROR(MINOR)\x1b[0m 0x03041000");
+    }
+
+    #[test]
+    #[cfg(feature = "log")]
+    fn severities_should_map_to_log_levels() {
+        assert_eq!(log::Level::from(StatusCodeSeverity::Progress), log::Level::Info);
+        assert_eq!(StatusCodeSeverity::Debug.log_level(), log::Level::Debug);
+        assert_eq!(StatusCodeSeverity::ErrorMinor.log_level(), log::Level::Warn);
+        assert_eq!(StatusCodeSeverity::ErrorMajor.log_level(), log::Level::Error);
+        assert_eq!(StatusCodeSeverity::ErrorUncontained.log_level(), log::Level::Error);
+        assert_eq!(StatusCodeSeverity::Unknown.log_level(), log::Level::Trace);
+        assert_eq!(LogReporter.report_status_code(EFI_PROGRESS_CODE, 0, 0, None, None), efi::Status::SUCCESS);
+    }
+}
@@ -0,0 +1,741 @@
+//! NVRAM Variable Store
+//!
+//! Exports services used to inspect the UEFI variable stores held in EFI_SYSTEM_NV_DATA_FV_GUID firmware volumes,
+//! such as the NVRAM region of a flash image.
+//!
+//! The variable store layout is not defined by the PI specification; the definitions here follow the EDK2 reference
+//! implementation (MdeModulePkg/Include/Guid/VariableFormat.h), which is the de facto standard for NVRAM regions.
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+use alloc::string::String;
+use core::{char, fmt, mem, ptr};
+
+use r_efi::efi;
+
+use crate::{
+    address_helper::align_up,
+    checksum,
+    fw_fs::fv,
+    parse::{Deviation, Diagnostic, ParseOptions},
+};
+
+// {FFF12B8D-7696-4C8B-A985-2747075B4F50}
+pub const EFI_SYSTEM_NV_DATA_FV_GUID: efi::Guid =
+    efi::Guid::from_fields(0xfff12b8d, 0x7696, 0x4c8b, 0xa9, 0x85, &[0x27, 0x47, 0x07, 0x5b, 0x4f, 0x50]);
+
+// {DDCF3616-3275-4164-98B6-FE85707FFE7D}
+pub const EFI_VARIABLE_GUID: efi::Guid =
+    efi::Guid::from_fields(0xddcf3616, 0x3275, 0x4164, 0x98, 0xb6, &[0xfe, 0x85, 0x70, 0x7f, 0xfe, 0x7d]);
+
+// {AAF32C78-947B-439A-A180-2E144EC37792}
+pub const EFI_AUTHENTICATED_VARIABLE_GUID: efi::Guid =
+    efi::Guid::from_fields(0xaaf32c78, 0x947b, 0x439a, 0xa1, 0x80, &[0x2e, 0x14, 0x4e, 0xc3, 0x77, 0x92]);
+
+/// Value of [`StoreHeader::format`] for a formatted variable store.
+pub const VARIABLE_STORE_FORMATTED: u8 = 0x5a;
+/// Value of [`StoreHeader::state`] for a healthy variable store.
+pub const VARIABLE_STORE_HEALTHY: u8 = 0xfe;
+
+/// Value of [`Header::start_id`] that marks the start of a variable.
+pub const VARIABLE_DATA: u16 = 0x55aa;
+
+/// Variable states. Bits are cleared as the state advances.
+pub mod state {
+    pub const VAR_IN_DELETED_TRANSITION: u8 = 0xfe;
+    pub const VAR_DELETED: u8 = 0xfd;
+    pub const VAR_HEADER_VALID_ONLY: u8 = 0x7f;
+    pub const VAR_ADDED: u8 = 0x3f;
+}
+
+/// Variable store header. Located at the start of the FV data and followed by the variables.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StoreHeader {
+    /// EFI_VARIABLE_GUID or EFI_AUTHENTICATED_VARIABLE_GUID.
+    pub signature: efi::Guid,
+    /// Size of the variable store, including this header.
+    pub size: u32,
+    pub format: u8,
+    pub state: u8,
+    pub reserved: u16,
+    pub reserved1: u32,
+}
+
+/// Header of a variable in a store with the EFI_VARIABLE_GUID signature.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Header {
+    pub start_id: u16,
+    pub state: u8,
+    pub reserved: u8,
+    pub attributes: u32,
+    pub name_size: u32,
+    pub data_size: u32,
+    pub vendor_guid: efi::Guid,
+}
+
+/// Header of a variable in a store with the EFI_AUTHENTICATED_VARIABLE_GUID signature.
+///
+/// EDK2 packs this header, so it occupies [`AUTHENTICATED_HEADER_SIZE`] bytes in the store rather than
+/// `size_of::<AuthenticatedHeader>()`; the field offsets are the same.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct AuthenticatedHeader {
+    pub start_id: u16,
+    pub state: u8,
+    pub reserved: u8,
+    pub attributes: u32,
+    pub monotonic_count: u64,
+    pub time_stamp: efi::Time,
+    pub pub_key_index: u32,
+    pub name_size: u32,
+    pub data_size: u32,
+    pub vendor_guid: efi::Guid,
+}
+
+/// Size of an [`AuthenticatedHeader`] in the variable store.
+pub const AUTHENTICATED_HEADER_SIZE: usize = 60;
+
+/// Authentication fields of a variable in an authenticated variable store.
+#[derive(Debug, Clone, Copy)]
+pub struct AuthenticationInfo {
+    pub monotonic_count: u64,
+    pub time_stamp: efi::Time,
+    pub pub_key_index: u32,
+}
+
+/// Errors detected while parsing a variable store.
+///
+/// The `efi::Status` based APIs report these as `INVALID_PARAMETER` (for buffers too small to hold the header, and
+/// stores or firmware volumes of an unknown type) or `VOLUME_CORRUPTED` (for everything else); see
+/// [`VariableStore::try_new`], [`VariableStore::try_new_from_fv`] and [`VariableStore::try_iter`] for the APIs that
+/// return them directly.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum VariableStoreError {
+    /// The buffer is too small to contain the variable store or firmware volume header.
+    BufferTooSmall { needed: usize, available: usize },
+    /// The variable store signature is neither EFI_VARIABLE_GUID nor EFI_AUTHENTICATED_VARIABLE_GUID.
+    UnknownSignature { signature: efi::Guid },
+    /// The variable store size is smaller than the store header or larger than the buffer.
+    BadStoreSize { size: u32 },
+    /// The FV header signature is not `_FVH`.
+    BadFvSignature { signature: u32 },
+    /// The FV file system GUID is not EFI_SYSTEM_NV_DATA_FV_GUID.
+    NotNvDataFv { guid: efi::Guid },
+    /// The FV header length is too small, odd or larger than the buffer.
+    BadFvHeaderLength { header_length: u16 },
+    /// The FV header checksum does not sum to zero.
+    BadFvHeaderChecksum,
+    /// The FV length is smaller than the header or larger than the buffer.
+    BadFvLength { fv_length: u64 },
+    /// The name and data of the variable at the given offset in the store run past the end of the store.
+    BadVariableSize { offset: usize, name_size: u32, data_size: u32 },
+    /// A spec deviation that is rejected under [`Strictness::Strict`](crate::parse::Strictness::Strict).
+    Deviation(Deviation),
+}
+
+impl fmt::Display for VariableStoreError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VariableStoreError::BufferTooSmall { needed, available } => {
+                write!(f, "buffer of {available:#x} bytes is too small for a {needed:#x} byte header")
+            }
+            VariableStoreError::UnknownSignature { signature } => {
+                write!(f, "variable store has unknown signature {signature:?}")
+            }
+            VariableStoreError::BadStoreSize { size } => write!(f, "variable store has bad size {size:#x}"),
+            VariableStoreError::BadFvSignature { signature } => write!(f, "FV header has bad signature {signature:#x}"),
+            VariableStoreError::NotNvDataFv { guid } => write!(f, "FV has file system {guid:?}, not NV data"),
+            VariableStoreError::BadFvHeaderLength { header_length } => {
+                write!(f, "FV header has bad header length {header_length:#x}")
+            }
+            VariableStoreError::BadFvHeaderChecksum => write!(f, "FV header has bad checksum"),
+            VariableStoreError::BadFvLength { fv_length } => write!(f, "FV has bad length {fv_length:#x}"),
+            VariableStoreError::BadVariableSize { offset, name_size, data_size } => write!(
+                f,
+                "variable at offset {offset:#x} with name size {name_size:#x} and data size {data_size:#x} runs past \
+                 the end of the store"
+            ),
+            VariableStoreError::Deviation(deviation) => write!(f, "{deviation}"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for VariableStoreError {}
+
+impl From<VariableStoreError> for efi::Status {
+    fn from(error: VariableStoreError) -> Self {
+        match error {
+            VariableStoreError::BufferTooSmall { .. }
+            | VariableStoreError::UnknownSignature { .. }
+            | VariableStoreError::NotNvDataFv { .. } => efi::Status::INVALID_PARAMETER,
+            _ => efi::Status::VOLUME_CORRUPTED,
+        }
+    }
+}
+
+/// Variable store access support
+///
+/// Provides access to the variables in an NVRAM variable store.
+///
+/// ## Example
+///```
+/// use mu_pi::variable_store::VariableStore;
+///
+/// fn dump_nvram(nv_fv_bytes: &[u8]) {
+///   let store = VariableStore::new_from_fv(nv_fv_bytes).expect("Variable Store Corrupt");
+///   for variable in store.iter() {
+///     let variable = variable.expect("Variable Corrupt");
+///     if variable.is_valid() {
+///       println!("{:?} {}: {:x?}", variable.vendor_guid(), variable.name(), variable.data());
+///     }
+///   }
+/// }
+///```
+#[derive(Debug, Clone, Copy)]
+pub struct VariableStore<'a> {
+    data: &'a [u8],
+    authenticated: bool,
+    format: u8,
+    state: u8,
+}
+
+impl<'a> VariableStore<'a> {
+    /// Instantiate a new VariableStore from a buffer that starts with a variable store header.
+    pub fn new(buffer: &'a [u8]) -> Result<Self, efi::Status> {
+        Ok(Self::try_new(buffer)?)
+    }
+
+    /// Instantiate a new VariableStore from a buffer that starts with a variable store header, reporting non-fatal
+    /// oddities in the store to `diagnostics`.
+    pub fn new_with_diagnostics(
+        buffer: &'a [u8],
+        diagnostics: &mut dyn FnMut(Diagnostic),
+    ) -> Result<Self, efi::Status> {
+        Ok(Self::parse(buffer, diagnostics)?)
+    }
+
+    /// Instantiate a new VariableStore from a buffer that starts with a variable store header.
+    ///
+    /// Behaves like [`VariableStore::new`], but returns an error that describes why the variable store is malformed.
+    ///
+    /// ## Example
+    ///```
+    /// use mu_pi::variable_store::VariableStore;
+    ///
+    /// if let Err(err) = VariableStore::try_new(&[0u8; 0x40]) {
+    ///   println!("malformed variable store: {err}");
+    /// }
+    ///```
+    pub fn try_new(buffer: &'a [u8]) -> Result<Self, VariableStoreError> {
+        Self::parse(buffer, &mut |_| ())
+    }
+
+    fn parse(buffer: &'a [u8], diagnostics: &mut dyn FnMut(Diagnostic)) -> Result<Self, VariableStoreError> {
+        //buffer must be large enough to hold the header structure.
+        if buffer.len() < mem::size_of::<StoreHeader>() {
+            Err(VariableStoreError::BufferTooSmall { needed: mem::size_of::<StoreHeader>(), available: buffer.len() })?;
+        }
+
+        //Safety: buffer is large enough to contain the header; the read does not require alignment.
+        let store_header = unsafe { ptr::read_unaligned(buffer.as_ptr() as *const StoreHeader) };
+
+        let authenticated = match store_header.signature {
+            EFI_VARIABLE_GUID => false,
+            EFI_AUTHENTICATED_VARIABLE_GUID => true,
+            signature => Err(VariableStoreError::UnknownSignature { signature })?,
+        };
+
+        // size: must hold the header and fit in the buffer.
+        let size = store_header.size as usize;
+        if size < mem::size_of::<StoreHeader>() || size > buffer.len() {
+            Err(VariableStoreError::BadStoreSize { size: store_header.size })?;
+        }
+
+        // a store that is not formatted and healthy is still parsed; EDK2 reclaims or reinitializes it.
+        let store =
+            Self { data: &buffer[..size], authenticated, format: store_header.format, state: store_header.state };
+        if !store.is_healthy() {
+            diagnostics(Diagnostic::UnhealthyVariableStore { format: store.format, state: store.state });
+        }
+        Ok(store)
+    }
+
+    /// Instantiate a new VariableStore from an EFI_SYSTEM_NV_DATA_FV_GUID firmware volume.
+    pub fn new_from_fv(buffer: &'a [u8]) -> Result<Self, efi::Status> {
+        Self::new_from_fv_with_options(buffer, &ParseOptions::default())
+    }
+
+    /// Instantiate a new VariableStore from an EFI_SYSTEM_NV_DATA_FV_GUID firmware volume, using the given parse
+    /// options.
+    ///
+    /// Under [`Strictness::Strict`](crate::parse::Strictness::Strict), spec deviations that EDK2 tolerates in the
+    /// firmware volume header are rejected as `VOLUME_CORRUPTED`; use
+    /// [`VariableStore::new_from_fv_with_diagnostics`] to receive the deviations tolerated under
+    /// [`Strictness::Compatible`](crate::parse::Strictness::Compatible).
+    ///
+    /// ## Example
+    ///```
+    /// use mu_pi::{parse::ParseOptions, variable_store::VariableStore};
+    ///
+    /// fn open_nvram(nv_fv_bytes: &[u8]) -> Option<VariableStore> {
+    ///   // EDK2 does not verify the NV data firmware volume header checksum.
+    ///   VariableStore::new_from_fv_with_options(nv_fv_bytes, &ParseOptions::PERMISSIVE).ok()
+    /// }
+    ///```
+    pub fn new_from_fv_with_options(buffer: &'a [u8], options: &ParseOptions) -> Result<Self, efi::Status> {
+        Self::new_from_fv_with_diagnostics(buffer, options, &mut |_| ())
+    }
+
+    /// Instantiate a new VariableStore from an EFI_SYSTEM_NV_DATA_FV_GUID firmware volume, using the given parse
+    /// options and reporting non-fatal oddities in the firmware volume header and store to `diagnostics`.
+    ///
+    /// Spec deviations tolerated under [`Strictness::Compatible`](crate::parse::Strictness::Compatible) are reported
+    /// as [`Diagnostic::Deviation`].
+    pub fn new_from_fv_with_diagnostics(
+        buffer: &'a [u8],
+        options: &ParseOptions,
+        diagnostics: &mut dyn FnMut(Diagnostic),
+    ) -> Result<Self, efi::Status> {
+        Ok(Self::parse_fv(buffer, options, diagnostics)?)
+    }
+
+    /// Instantiate a new VariableStore from an EFI_SYSTEM_NV_DATA_FV_GUID firmware volume.
+    ///
+    /// Behaves like [`VariableStore::new_from_fv`], but returns an error that describes why the firmware volume or
+    /// variable store is malformed.
+    pub fn try_new_from_fv(buffer: &'a [u8]) -> Result<Self, VariableStoreError> {
+        Self::parse_fv(buffer, &ParseOptions::default(), &mut |_| ())
+    }
+
+    fn parse_fv(
+        buffer: &'a [u8],
+        options: &ParseOptions,
+        diagnostics: &mut dyn FnMut(Diagnostic),
+    ) -> Result<Self, VariableStoreError> {
+        //buffer must be large enough to hold the header structure.
+        if buffer.len() < mem::size_of::<fv::Header>() {
+            Err(VariableStoreError::BufferTooSmall { needed: mem::size_of::<fv::Header>(), available: buffer.len() })?;
+        }
+
+        //Safety: buffer is large enough to contain the header; the read does not require alignment.
+        let fv_header = unsafe { ptr::read_unaligned(buffer.as_ptr() as *const fv::Header) };
+
+        // signature: must be ASCII '_FVH'
+        if fv_header.signature != u32::from_le_bytes(*b"_FVH") {
+            Err(VariableStoreError::BadFvSignature { signature: fv_header.signature })?;
+        }
+
+        // file_system_guid: must be EFI_SYSTEM_NV_DATA_FV_GUID.
+        if fv_header.file_system_guid != EFI_SYSTEM_NV_DATA_FV_GUID {
+            Err(VariableStoreError::NotNvDataFv { guid: fv_header.file_system_guid })?;
+        }
+
+        // header_length: must hold the header, fit in the buffer and be a multiple of 2 bytes.
+        let header_length = fv_header.header_length as usize;
+        if header_length < mem::size_of::<fv::Header>() || header_length > buffer.len() || header_length & 0x01 != 0 {
+            Err(VariableStoreError::BadFvHeaderLength { header_length: fv_header.header_length })?;
+        }
+
+        // checksum: fv header must sum to zero. The EDK2 variable driver does not verify it.
+        if !checksum::verify16(&buffer[..header_length]) {
+            options
+                .tolerate(Deviation::NvDataFvHeaderChecksum, &mut |deviation| {
+                    diagnostics(Diagnostic::Deviation(deviation))
+                })
+                .map_err(|_| VariableStoreError::BadFvHeaderChecksum)?;
+        }
+
+        // the variable store follows the fv header (variable FVs do not have an ext header).
+        let bad_fv_length = VariableStoreError::BadFvLength { fv_length: fv_header.fv_length };
+        let fv_length = usize::try_from(fv_header.fv_length).map_err(|_| bad_fv_length)?;
+        if fv_length < header_length || fv_length > buffer.len() {
+            Err(bad_fv_length)?;
+        }
+        Self::parse(&buffer[header_length..fv_length], diagnostics)
+    }
+
+    /// Returns whether the store uses authenticated variable headers (EFI_AUTHENTICATED_VARIABLE_GUID signature).
+    pub fn is_authenticated(&self) -> bool {
+        self.authenticated
+    }
+
+    /// Returns whether the store header is marked formatted and healthy.
+    pub fn is_healthy(&self) -> bool {
+        self.format == VARIABLE_STORE_FORMATTED && self.state == VARIABLE_STORE_HEALTHY
+    }
+
+    /// Returns the size in bytes of the variable store, including the header.
+    pub fn size(&self) -> usize {
+        self.data.len()
+    }
+
+    /// Returns the variable store data, including the header.
+    pub fn data(&self) -> &'a [u8] {
+        self.data
+    }
+
+    /// Returns an iterator of every variable header in the store, including deleted variables.
+    ///
+    /// Use [`Variable::is_valid()`] to select the variables that are in effect.
+    pub fn iter(&self) -> impl Iterator<Item = Result<Variable<'a>, efi::Status>> {
+        self.try_iter().map(|variable| variable.map_err(efi::Status::from))
+    }
+
+    /// Returns an iterator of every variable header in the store, like [`VariableStore::iter()`], whose errors describe
+    /// why a variable is malformed.
+    pub fn try_iter(&self) -> impl Iterator<Item = Result<Variable<'a>, VariableStoreError>> {
+        VariableIterator {
+            buffer: self.data,
+            authenticated: self.authenticated,
+            next_offset: mem::size_of::<StoreHeader>(),
+            error: false,
+        }
+    }
+}
+
+/// Variable access support
+///
+/// Provides access to a variable in a [`VariableStore`].
+#[derive(Clone, Copy)]
+pub struct Variable<'a> {
+    name: &'a [u8],
+    data: &'a [u8],
+    vendor_guid: efi::Guid,
+    attributes: u32,
+    state: u8,
+    authentication_info: Option<AuthenticationInfo>,
+}
+
+impl<'a> Variable<'a> {
+    /// Returns the variable name, decoded from UTF-16 up to the null terminator.
+    ///
+    /// Invalid UTF-16 code units are replaced with U+FFFD.
+    pub fn name(&self) -> String {
+        char::decode_utf16(self.name.chunks_exact(2).map(|x| u16::from_le_bytes([x[0], x[1]])).take_while(|&x| x != 0))
+            .map(|x| x.unwrap_or(char::REPLACEMENT_CHARACTER))
+            .collect()
+    }
+
+    /// Returns the variable name as raw UTF-16LE bytes, including the null terminator.
+    pub fn name_raw(&self) -> &'a [u8] {
+        self.name
+    }
+
+    /// Returns the variable vendor GUID.
+    pub fn vendor_guid(&self) -> efi::Guid {
+        self.vendor_guid
+    }
+
+    /// Returns the variable attributes (EFI_VARIABLE_*).
+    pub fn attributes(&self) -> u32 {
+        self.attributes
+    }
+
+    /// Returns the variable data.
+    pub fn data(&self) -> &'a [u8] {
+        self.data
+    }
+
+    /// Returns the variable state as a raw u8, as stored in the variable header.
+    pub fn state_raw(&self) -> u8 {
+        self.state
+    }
+
+    /// Returns whether the variable is in effect: it has been added, and not deleted. Like EDK2, a variable in
+    /// transition to being deleted is still considered valid.
+    pub fn is_valid(&self) -> bool {
+        self.state == state::VAR_ADDED || self.state == state::VAR_ADDED & state::VAR_IN_DELETED_TRANSITION
+    }
+
+    /// Returns the authentication fields of the variable, if it is in an authenticated variable store.
+    pub fn authentication_info(&self) -> Option<&AuthenticationInfo> {
+        self.authentication_info.as_ref()
+    }
+}
+
+impl<'a> fmt::Debug for Variable<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Variable")
+            .field("name", &self.name())
+            .field("vendor_guid", &self.vendor_guid)
+            .field("attributes", &self.attributes)
+            .field("state", &self.state)
+            .field("authentication_info", &self.authentication_info)
+            .field("data.len()", &self.data.len())
+            .finish()
+    }
+}
+
+struct VariableIterator<'a> {
+    buffer: &'a [u8],
+    authenticated: bool,
+    next_offset: usize,
+    error: bool,
+}
+
+impl<'a> VariableIterator<'a> {
+    // Parses the variable at next_offset, returning it with the offset following its data.
+    fn parse(&self) -> Result<Option<(Variable<'a>, usize)>, VariableStoreError> {
+        let buffer = &self.buffer[self.next_offset..];
+        let header_size = if self.authenticated { AUTHENTICATED_HEADER_SIZE } else { mem::size_of::<Header>() };
+
+        // the variables end where the remaining space cannot hold a variable header, or at the first header without
+        // the start id.
+        if buffer.len() < header_size || u16::from_le_bytes([buffer[0], buffer[1]]) != VARIABLE_DATA {
+            return Ok(None);
+        }
+
+        let (header, authentication_info) = if self.authenticated {
+            //Safety: buffer is large enough to contain the packed header, which is copied into an (unpacked) zeroed
+            //header with the same field offsets, leaving only the trailing padding zeroed.
+            let header = unsafe {
+                let mut header = mem::MaybeUninit::<AuthenticatedHeader>::zeroed();
+                ptr::copy_nonoverlapping(buffer.as_ptr(), header.as_mut_ptr() as *mut u8, AUTHENTICATED_HEADER_SIZE);
+                header.assume_init()
+            };
+            let authentication_info = AuthenticationInfo {
+                monotonic_count: header.monotonic_count,
+                time_stamp: header.time_stamp,
+                pub_key_index: header.pub_key_index,
+            };
+            let header = Header {
+                start_id: header.start_id,
+                state: header.state,
+                reserved: header.reserved,
+                attributes: header.attributes,
+                name_size: header.name_size,
+                data_size: header.data_size,
+                vendor_guid: header.vendor_guid,
+            };
+            (header, Some(authentication_info))
+        } else {
+            //Safety: buffer is large enough to contain the header; the read does not require alignment.
+            (unsafe { ptr::read_unaligned(buffer.as_ptr() as *const Header) }, None)
+        };
+
+        // the name and data follow the header and must fit in the store.
+        let bad_size = VariableStoreError::BadVariableSize {
+            offset: self.next_offset,
+            name_size: header.name_size,
+            data_size: header.data_size,
+        };
+        let name_end = header_size.checked_add(header.name_size as usize).ok_or(bad_size)?;
+        let data_end = name_end.checked_add(header.data_size as usize).ok_or(bad_size)?;
+        if data_end > buffer.len() {
+            Err(bad_size)?;
+        }
+
+        let variable = Variable {
+            name: &buffer[header_size..name_end],
+            data: &buffer[name_end..data_end],
+            vendor_guid: header.vendor_guid,
+            attributes: header.attributes,
+            state: header.state,
+            authentication_info,
+        };
+        // variable headers are 4-byte aligned relative to the start of the store.
+        let next_offset = align_up((self.next_offset + data_end) as u64, 4) as usize;
+        Ok(Some((variable, next_offset)))
+    }
+}
+
+impl<'a> Iterator for VariableIterator<'a> {
+    type Item = Result<Variable<'a>, VariableStoreError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.error || self.next_offset >= self.buffer.len() {
+            return None;
+        }
+        match self.parse() {
+            Ok(Some((variable, next_offset))) => {
+                self.next_offset = next_offset;
+                Some(Ok(variable))
+            }
+            Ok(None) => None,
+            Err(err) => {
+                self.error = true;
+                Some(Err(err))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fw_fs::{builder::blank_volume, fv::header_offset, FfsFileSystem, FirmwareVolume, FvbAttributes2};
+    use alloc::vec::Vec;
+
+    fn push_variable(store: &mut Vec<u8>, name: &str, data: &[u8], state: u8, authenticated: bool) {
+        let name = name.encode_utf16().chain([0]).flat_map(u16::to_le_bytes).collect::<Vec<_>>();
+        store.extend_from_slice(&VARIABLE_DATA.to_le_bytes());
+        store.extend_from_slice(&[state, 0]);
+        store.extend_from_slice(&efi::VARIABLE_BOOTSERVICE_ACCESS.to_le_bytes());
+        if authenticated {
+            store.extend_from_slice(&7u64.to_le_bytes());
+            store.extend_from_slice(&[0; mem::size_of::<efi::Time>()]);
+            store.extend_from_slice(&3u32.to_le_bytes());
+        }
+        store.extend_from_slice(&(name.len() as u32).to_le_bytes());
+        store.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        store.extend_from_slice(efi::Guid::from_bytes(&[0x5a; 16]).as_bytes());
+        store.extend_from_slice(&name);
+        store.extend_from_slice(data);
+        store.resize(align_up(store.len() as u64, 4) as usize, 0xff);
+    }
+
+    fn build_store(authenticated: bool) -> Vec<u8> {
+        let signature = if authenticated { EFI_AUTHENTICATED_VARIABLE_GUID } else { EFI_VARIABLE_GUID };
+        let mut store = Vec::new();
+        store.extend_from_slice(signature.as_bytes());
+        store.extend_from_slice(&0x100u32.to_le_bytes());
+        store.extend_from_slice(&[VARIABLE_STORE_FORMATTED, VARIABLE_STORE_HEALTHY, 0, 0, 0, 0, 0, 0]);
+        push_variable(&mut store, "Boot0000", &[1, 2, 3], state::VAR_ADDED & state::VAR_DELETED, authenticated);
+        push_variable(&mut store, "Boot0000", &[4, 5, 6, 7], state::VAR_ADDED, authenticated);
+        store.resize(0x100, 0xff);
+        store
+    }
+
+    // The header length of the FVs built by build_nv_data_fv, with a single block map entry.
+    const NV_DATA_FV_HEADER_LENGTH: usize = mem::size_of::<fv::Header>() + 2 * mem::size_of::<fv::BlockMapEntry>();
+
+    // Builds an NV data FV holding the given variable store.
+    fn build_nv_data_fv(store: &[u8]) -> Vec<u8> {
+        let fv_length = NV_DATA_FV_HEADER_LENGTH + store.len();
+        let block_map = [fv::BlockMapEntry { num_blocks: 1, length: fv_length as u32 }];
+        let mut fv_bytes = blank_volume(FfsFileSystem::Ffs2, &block_map, FvbAttributes2::ERASE_POLARITY).unwrap();
+        let file_system_guid = header_offset::FILE_SYSTEM_GUID;
+        fv_bytes[file_system_guid..file_system_guid + 16].copy_from_slice(EFI_SYSTEM_NV_DATA_FV_GUID.as_bytes());
+        fv_bytes[NV_DATA_FV_HEADER_LENGTH..].copy_from_slice(store);
+        FirmwareVolume::recompute_checksum(&mut fv_bytes).unwrap();
+        fv_bytes
+    }
+
+    #[test]
+    fn header_layout() {
+        assert_eq!(mem::size_of::<StoreHeader>(), 28);
+        assert_eq!(mem::size_of::<Header>(), 32);
+        assert_eq!(mem::size_of::<AuthenticatedHeader>(), align_up(AUTHENTICATED_HEADER_SIZE as u64, 8) as usize);
+    }
+
+    #[test]
+    fn variable_store_should_yield_variables() {
+        for authenticated in [false, true] {
+            let store_bytes = build_store(authenticated);
+            let store = VariableStore::new(&store_bytes).unwrap();
+            assert_eq!(store.is_authenticated(), authenticated);
+            assert!(store.is_healthy());
+
+            let variables = store.iter().collect::<Result<Vec<_>, efi::Status>>().unwrap();
+            assert_eq!(variables.len(), 2);
+            assert!(!variables[0].is_valid());
+            assert!(variables[1].is_valid());
+            assert_eq!(variables[1].name(), "Boot0000");
+            assert_eq!(variables[1].data(), &[4, 5, 6, 7]);
+            assert_eq!(variables[1].vendor_guid(), efi::Guid::from_bytes(&[0x5a; 16]));
+            assert_eq!(variables[1].attributes(), efi::VARIABLE_BOOTSERVICE_ACCESS);
+            assert_eq!(variables[1].authentication_info().map(|x| x.pub_key_index), authenticated.then_some(3));
+        }
+    }
+
+    #[test]
+    fn variable_store_should_parse_from_fv() {
+        let mut fv_bytes = build_nv_data_fv(&build_store(true));
+
+        let store = VariableStore::new_from_fv(&fv_bytes).unwrap();
+        assert_eq!(store.size(), 0x100);
+        assert_eq!(store.iter().filter(|x| matches!(x, Ok(variable) if variable.is_valid())).count(), 1);
+
+        // bad fv header checksum.
+        fv_bytes[header_offset::CHECKSUM] ^= 0xff;
+        assert_eq!(VariableStore::new_from_fv(&fv_bytes).unwrap_err(), efi::Status::VOLUME_CORRUPTED);
+    }
+
+    #[test]
+    fn variable_store_from_fv_should_follow_strictness() {
+        let mut fv_bytes = build_nv_data_fv(&build_store(false));
+        // a bad fv header checksum, and a store marked as not healthy.
+        fv_bytes[header_offset::CHECKSUM] ^= 0xff;
+        fv_bytes[NV_DATA_FV_HEADER_LENGTH + 0x15] = 0xff;
+
+        assert_eq!(
+            VariableStore::new_from_fv_with_options(&fv_bytes, &ParseOptions::STRICT).unwrap_err(),
+            efi::Status::VOLUME_CORRUPTED
+        );
+        assert_eq!(VariableStore::try_new_from_fv(&fv_bytes).unwrap_err(), VariableStoreError::BadFvHeaderChecksum);
+
+        let mut diagnostics = Vec::new();
+        let store =
+            VariableStore::new_from_fv_with_diagnostics(&fv_bytes, &ParseOptions::COMPATIBLE, &mut |diagnostic| {
+                diagnostics.push(diagnostic)
+            })
+            .unwrap();
+        assert_eq!(store.iter().count(), 2);
+        assert_eq!(
+            diagnostics,
+            [
+                Diagnostic::Deviation(Deviation::NvDataFvHeaderChecksum),
+                Diagnostic::UnhealthyVariableStore { format: VARIABLE_STORE_FORMATTED, state: 0xff },
+            ]
+        );
+
+        diagnostics.clear();
+        VariableStore::new_from_fv_with_diagnostics(&fv_bytes, &ParseOptions::PERMISSIVE, &mut |diagnostic| {
+            diagnostics.push(diagnostic)
+        })
+        .unwrap();
+        assert_eq!(diagnostics, [Diagnostic::UnhealthyVariableStore { format: VARIABLE_STORE_FORMATTED, state: 0xff }]);
+    }
+
+    #[test]
+    fn variable_store_should_reject_corrupt_variables() {
+        let mut store_bytes = build_store(false);
+        // data size of the first variable runs past the end of the store.
+        store_bytes[0x1c + 12..0x1c + 16].copy_from_slice(&0x1000u32.to_le_bytes());
+        let store = VariableStore::new(&store_bytes).unwrap();
+        let mut variables = store.iter();
+        assert_eq!(variables.next().unwrap().unwrap_err(), efi::Status::VOLUME_CORRUPTED);
+        assert!(variables.next().is_none());
+
+        // store size larger than the buffer.
+        assert_eq!(VariableStore::new(&store_bytes[..0x80]).unwrap_err(), efi::Status::VOLUME_CORRUPTED);
+        // unknown signature.
+        assert_eq!(VariableStore::new(&[0; 0x40]).unwrap_err(), efi::Status::INVALID_PARAMETER);
+    }
+
+    #[test]
+    fn try_apis_should_describe_errors() {
+        let mut store_bytes = build_store(false);
+        store_bytes[0x1c + 12..0x1c + 16].copy_from_slice(&0x1000u32.to_le_bytes());
+        let store = VariableStore::try_new(&store_bytes).unwrap();
+        let err = store.try_iter().next().unwrap().unwrap_err();
+        assert_eq!(err, VariableStoreError::BadVariableSize { offset: 0x1c, name_size: 18, data_size: 0x1000 });
+        assert_eq!(efi::Status::from(err), efi::Status::VOLUME_CORRUPTED);
+        assert!(err.to_string().contains("runs past the end of the store"));
+
+        assert_eq!(
+            VariableStore::try_new(&store_bytes[..0x80]).unwrap_err(),
+            VariableStoreError::BadStoreSize { size: 0x100 }
+        );
+        assert_eq!(
+            VariableStore::try_new(&[0; 0x10]).unwrap_err(),
+            VariableStoreError::BufferTooSmall { needed: 28, available: 0x10 }
+        );
+        assert_eq!(
+            VariableStore::try_new_from_fv(&[0; 0x10]).unwrap_err(),
+            VariableStoreError::BufferTooSmall { needed: mem::size_of::<fv::Header>(), available: 0x10 }
+        );
+        assert_eq!(
+            VariableStore::try_new_from_fv(&[0; 0x48]).unwrap_err(),
+            VariableStoreError::BadFvSignature { signature: 0 }
+        );
+    }
+}
@@ -0,0 +1,240 @@
+//! WIN_CERTIFICATE
+//!
+//! Certificate framing shared by signed UEFI objects:
+//! - **WIN_CERTIFICATE** - The common header of a certificate, as used in the PE/COFF attribute certificate table.
+//! - **WIN_CERTIFICATE_UEFI_GUID** - A certificate whose format is identified by a GUID.
+//! - **WIN_CERTIFICATE_EFI_PKCS1_15** - A PKCS#1 v1.5 signature.
+//! - **EFI_FIRMWARE_IMAGE_AUTHENTICATION** - The authentication header that precedes a signed FMP image.
+//!
+//! See <https://uefi.org/specs/UEFI/2.10/32_Secure_Boot_and_Driver_Signing.html#data-structures>.
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+use core::{fmt, mem, ptr};
+
+use r_efi::efi;
+
+/// WIN_CERTIFICATE revision 1.0.
+pub const WIN_CERT_REVISION_1_0: u16 = 0x0100;
+/// WIN_CERTIFICATE revision 2.0, the revision used by UEFI.
+pub const WIN_CERT_REVISION_2_0: u16 = 0x0200;
+
+/// Certificate types (`WIN_CERT_TYPE_*` in EDK II code).
+pub mod cert_type {
+    /// The certificate is a PKCS#7 SignedData structure.
+    pub const PKCS_SIGNED_DATA: u16 = 0x0002;
+    /// The certificate is a [`WinCertificateEfiPkcs1_15`](super::WinCertificateEfiPkcs1_15).
+    pub const EFI_PKCS115: u16 = 0x0ef0;
+    /// The certificate is a [`WinCertificateUefiGuid`](super::WinCertificateUefiGuid).
+    pub const EFI_GUID: u16 = 0x0ef1;
+}
+
+// {A7717414-C616-4977-9420-844712A735BF}
+pub const EFI_CERT_TYPE_RSA2048_SHA256_GUID: efi::Guid =
+    efi::Guid::from_fields(0xa7717414, 0xc616, 0x4977, 0x94, 0x20, &[0x84, 0x47, 0x12, 0xa7, 0x35, 0xbf]);
+
+// {4AAFD29D-68DF-49EE-8AA9-347D375665A7}
+pub const EFI_CERT_TYPE_PKCS7_GUID: efi::Guid =
+    efi::Guid::from_fields(0x4aafd29d, 0x68df, 0x49ee, 0x8a, 0xa9, &[0x34, 0x7d, 0x37, 0x56, 0x65, 0xa7]);
+
+/// The header of a certificate. The certificate data follows the header.
+///
+/// # Documentation
+/// UEFI Specification, Release 2.10, Section 32.2.4
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WinCertificate {
+    /// The length of the certificate, including this header, in bytes.
+    pub length: u32,
+    /// The revision of the certificate structure ([`WIN_CERT_REVISION_2_0`] for UEFI).
+    pub revision: u16,
+    /// The certificate type, one of [`cert_type`].
+    pub certificate_type: u16,
+}
+
+/// A certificate whose format is identified by `cert_type`. The certificate data follows the structure.
+///
+/// # Documentation
+/// UEFI Specification, Release 2.10, Section 32.2.4
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WinCertificateUefiGuid {
+    /// The certificate header, with `certificate_type` set to [`cert_type::EFI_GUID`].
+    pub hdr: WinCertificate,
+    /// The format of the certificate data, e.g. [`EFI_CERT_TYPE_PKCS7_GUID`].
+    pub cert_type: efi::Guid,
+}
+
+/// A PKCS#1 v1.5 signature. The signature follows the structure.
+///
+/// # Documentation
+/// UEFI Specification, Release 2.10, Section 32.2.4
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WinCertificateEfiPkcs1_15 {
+    /// The certificate header, with `certificate_type` set to [`cert_type::EFI_PKCS115`].
+    pub hdr: WinCertificate,
+    /// The hash algorithm used to create the signature.
+    pub hash_algorithm: efi::Guid,
+}
+
+/// The authentication header that precedes the payload of a signed firmware image.
+///
+/// The signature in `auth_info` covers the payload followed by `monotonic_count`.
+///
+/// # Documentation
+/// UEFI Specification, Release 2.10, EFI_FIRMWARE_MANAGEMENT_PROTOCOL.SetImage()
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FirmwareImageAuthentication {
+    /// Monotonic count that must increase with each signed image, preventing rollback to older images.
+    pub monotonic_count: u64,
+    /// The certificate, with `hdr.length` covering the certificate data that follows it.
+    pub auth_info: WinCertificateUefiGuid,
+}
+
+/// Errors detected while parsing a certificate or a signed firmware image.
+///
+/// They convert to `INVALID_PARAMETER` for callers that return an [`efi::Status`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum WinCertificateError {
+    /// The buffer is too small to contain the monotonic count or the certificate header.
+    BufferTooSmall { needed: usize, available: usize },
+    /// The certificate is not a [`cert_type::EFI_GUID`] certificate.
+    UnsupportedCertificateType { certificate_type: u16 },
+    /// The certificate length is smaller than the certificate header or larger than the buffer.
+    BadLength { length: u32 },
+}
+
+impl fmt::Display for WinCertificateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WinCertificateError::BufferTooSmall { needed, available } => {
+                write!(f, "buffer of {available:#x} bytes is too small for a {needed:#x} byte header")
+            }
+            WinCertificateError::UnsupportedCertificateType { certificate_type } => {
+                write!(f, "certificate has unsupported type {certificate_type:#x}")
+            }
+            WinCertificateError::BadLength { length } => write!(f, "certificate has bad length {length:#x}"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for WinCertificateError {}
+
+impl From<WinCertificateError> for efi::Status {
+    fn from(_error: WinCertificateError) -> Self {
+        efi::Status::INVALID_PARAMETER
+    }
+}
+
+/// A parsed GUID-typed certificate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UefiGuidCertificate<'a> {
+    /// The certificate header.
+    pub header: WinCertificateUefiGuid,
+    /// The certificate data that follows the header.
+    pub cert_data: &'a [u8],
+}
+
+impl<'a> UefiGuidCertificate<'a> {
+    /// Parses the WIN_CERTIFICATE_UEFI_GUID certificate at the start of buffer.
+    ///
+    /// Returns an error if the buffer is too small to hold the certificate described by its header, or if the
+    /// certificate is not a [`cert_type::EFI_GUID`] certificate.
+    pub fn new(buffer: &'a [u8]) -> Result<Self, WinCertificateError> {
+        let header_size = mem::size_of::<WinCertificateUefiGuid>();
+        if buffer.len() < header_size {
+            Err(WinCertificateError::BufferTooSmall { needed: header_size, available: buffer.len() })?;
+        }
+        //Safety: buffer is large enough to hold the header. The buffer may not be aligned, so read it unaligned.
+        let header = unsafe { ptr::read_unaligned(buffer.as_ptr() as *const WinCertificateUefiGuid) };
+
+        if header.hdr.certificate_type != cert_type::EFI_GUID {
+            Err(WinCertificateError::UnsupportedCertificateType { certificate_type: header.hdr.certificate_type })?;
+        }
+        let length = header.hdr.length as usize;
+        if length < header_size || length > buffer.len() {
+            Err(WinCertificateError::BadLength { length: header.hdr.length })?;
+        }
+        Ok(Self { header, cert_data: &buffer[header_size..length] })
+    }
+
+    /// Returns the length of the certificate, including the header, in bytes.
+    pub fn length(&self) -> usize {
+        self.header.hdr.length as usize
+    }
+}
+
+/// Splits a signed firmware image into its authentication header and payload.
+///
+/// Returns the monotonic count, the certificate, and the payload that follows the certificate.
+///
+/// ## Example
+///```
+/// use mu_pi::win_certificate::split_firmware_image;
+///
+/// fn print_image(image: &[u8]) {
+///   match split_firmware_image(image) {
+///     Ok((count, certificate, payload)) => {
+///       println!("count {count}, {} bytes of signature, {} bytes of payload", certificate.cert_data.len(), payload.len())
+///     }
+///     Err(error) => println!("image is not signed: {error}"),
+///   }
+/// }
+///```
+pub fn split_firmware_image(image: &[u8]) -> Result<(u64, UefiGuidCertificate<'_>, &[u8]), WinCertificateError> {
+    let count_size = mem::size_of::<u64>();
+    if image.len() < count_size {
+        Err(WinCertificateError::BufferTooSmall { needed: count_size, available: image.len() })?;
+    }
+    let monotonic_count = u64::from_le_bytes(image[..count_size].try_into().unwrap());
+    let certificate = UefiGuidCertificate::new(&image[count_size..])?;
+    let payload = &image[count_size + certificate.length()..];
+    Ok((monotonic_count, certificate, payload))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn firmware_image_should_split() {
+        assert_eq!(mem::size_of::<WinCertificate>(), 8);
+        assert_eq!(mem::size_of::<WinCertificateUefiGuid>(), 24);
+        assert_eq!(mem::size_of::<FirmwareImageAuthentication>(), 32);
+
+        let cert_data = [0xc5u8; 5];
+        let mut image = Vec::new();
+        image.extend_from_slice(&7u64.to_le_bytes());
+        image.extend_from_slice(&(24 + cert_data.len() as u32).to_le_bytes());
+        image.extend_from_slice(&WIN_CERT_REVISION_2_0.to_le_bytes());
+        image.extend_from_slice(&cert_type::EFI_GUID.to_le_bytes());
+        image.extend_from_slice(EFI_CERT_TYPE_PKCS7_GUID.as_bytes());
+        image.extend_from_slice(&cert_data);
+        image.extend_from_slice(b"payload");
+
+        let (count, certificate, payload) = split_firmware_image(&image).unwrap();
+        assert_eq!(count, 7);
+        assert_eq!(certificate.header.cert_type, EFI_CERT_TYPE_PKCS7_GUID);
+        assert_eq!(certificate.header.hdr.revision, WIN_CERT_REVISION_2_0);
+        assert_eq!(certificate.cert_data, cert_data);
+        assert_eq!(payload, b"payload");
+
+        // a certificate length that overruns the image is rejected.
+        image[8] = 0xff;
+        assert_eq!(split_firmware_image(&image).unwrap_err(), WinCertificateError::BadLength { length: 0xff });
+        assert_eq!(efi::Status::from(split_firmware_image(&image).unwrap_err()), efi::Status::INVALID_PARAMETER);
+        image[14] = 0;
+        assert_eq!(
+            split_firmware_image(&image).unwrap_err(),
+            WinCertificateError::UnsupportedCertificateType { certificate_type: 0x0e00 }
+        );
+    }
+}